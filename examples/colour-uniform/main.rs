@@ -42,6 +42,7 @@ use std::mem::{size_of, ManuallyDrop};
 use std::rc::Rc;
 use std::{fs, iter, ptr};
 
+use gfx_hal_derive::Vertex;
 use hal::{
     adapter::{Adapter, MemoryType},
     buffer,
@@ -52,7 +53,7 @@ use hal::{
     pass,
     pool,
     prelude::*,
-    pso,
+    pso::{self, Vertex as _},
     queue::{QueueGroup, Submission},
     window as w,
     Backend,
@@ -66,7 +67,9 @@ const DIMS: w::Extent2D = w::Extent2D {
     height: 768,
 };
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Vertex)]
+#[repr(C)]
+#[vertex(crate = "hal")]
 struct Vertex {
     a_pos: [f32; 2],
     a_uv: [f32; 2],
@@ -1279,28 +1282,8 @@ impl<B: Backend> PipelineState<B> {
                     mask: pso::ColorMask::ALL,
                     blend: Some(pso::BlendState::ALPHA),
                 });
-                pipeline_desc.vertex_buffers.push(pso::VertexBufferDesc {
-                    binding: 0,
-                    stride: size_of::<Vertex>() as u32,
-                    rate: pso::VertexInputRate::Vertex,
-                });
-
-                pipeline_desc.attributes.push(pso::AttributeDesc {
-                    location: 0,
-                    binding: 0,
-                    element: pso::Element {
-                        format: f::Format::Rg32Sfloat,
-                        offset: 0,
-                    },
-                });
-                pipeline_desc.attributes.push(pso::AttributeDesc {
-                    location: 1,
-                    binding: 0,
-                    element: pso::Element {
-                        format: f::Format::Rg32Sfloat,
-                        offset: 8,
-                    },
-                });
+                pipeline_desc.vertex_buffers.push(Vertex::buffer_desc(0));
+                pipeline_desc.attributes.extend(Vertex::attributes(0));
 
                 device.create_graphics_pipeline(&pipeline_desc, None)
             };
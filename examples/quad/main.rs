@@ -30,6 +30,7 @@ pub fn wasm_main() {
     main();
 }
 
+use gfx_hal_derive::Vertex;
 use hal::{
     buffer,
     command,
@@ -42,7 +43,7 @@ use hal::{
     pool,
     prelude::*,
     pso,
-    pso::{PipelineStage, ShaderStageFlags, VertexInputRate},
+    pso::{PipelineStage, ShaderStageFlags, Vertex as _},
     queue::{QueueGroup, Submission},
     window,
 };
@@ -60,7 +61,9 @@ const DIMS: window::Extent2D = window::Extent2D { width: 1024, height: 768 };
 
 const ENTRY_NAME: &str = "main";
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Vertex)]
+#[repr(C)]
+#[vertex(crate = "hal")]
 #[allow(non_snake_case)]
 struct Vertex {
     a_Pos: [f32; 2],
@@ -695,28 +698,8 @@ where
                     mask: pso::ColorMask::ALL,
                     blend: Some(pso::BlendState::ALPHA),
                 });
-                pipeline_desc.vertex_buffers.push(pso::VertexBufferDesc {
-                    binding: 0,
-                    stride: mem::size_of::<Vertex>() as u32,
-                    rate: VertexInputRate::Vertex,
-                });
-
-                pipeline_desc.attributes.push(pso::AttributeDesc {
-                    location: 0,
-                    binding: 0,
-                    element: pso::Element {
-                        format: f::Format::Rg32Sfloat,
-                        offset: 0,
-                    },
-                });
-                pipeline_desc.attributes.push(pso::AttributeDesc {
-                    location: 1,
-                    binding: 0,
-                    element: pso::Element {
-                        format: f::Format::Rg32Sfloat,
-                        offset: 8,
-                    },
-                });
+                pipeline_desc.vertex_buffers.push(Vertex::buffer_desc(0));
+                pipeline_desc.attributes.extend(Vertex::attributes(0));
 
                 unsafe { device.create_graphics_pipeline(&pipeline_desc, None) }
             };
@@ -0,0 +1,88 @@
+//! Mapping to `DXGI_FORMAT` values.
+//!
+//! Packed formats are mapped according to the little-endian layout,
+//! which is the only one DXGI is available on.
+
+use hal::format::Format;
+
+/// Table of formats and their `DXGI_FORMAT` values.
+pub const TABLE: &[(Format, u32)] = &[
+    (Format::Rgba32Sfloat, 2),
+    (Format::Rgba32Uint, 3),
+    (Format::Rgba32Sint, 4),
+    (Format::Rgb32Sfloat, 6),
+    (Format::Rgb32Uint, 7),
+    (Format::Rgb32Sint, 8),
+    (Format::Rgba16Sfloat, 10),
+    (Format::Rgba16Unorm, 11),
+    (Format::Rgba16Uint, 12),
+    (Format::Rgba16Snorm, 13),
+    (Format::Rgba16Sint, 14),
+    (Format::Rg32Sfloat, 16),
+    (Format::Rg32Uint, 17),
+    (Format::Rg32Sint, 18),
+    (Format::D32SfloatS8Uint, 20),
+    (Format::A2b10g10r10Unorm, 24),
+    (Format::A2b10g10r10Uint, 25),
+    (Format::B10g11r11Ufloat, 26),
+    (Format::Rgba8Unorm, 28),
+    (Format::Rgba8Srgb, 29),
+    (Format::Rgba8Uint, 30),
+    (Format::Rgba8Snorm, 31),
+    (Format::Rgba8Sint, 32),
+    (Format::Rg16Sfloat, 34),
+    (Format::Rg16Unorm, 35),
+    (Format::Rg16Uint, 36),
+    (Format::Rg16Snorm, 37),
+    (Format::Rg16Sint, 38),
+    (Format::D32Sfloat, 40),
+    (Format::R32Sfloat, 41),
+    (Format::R32Uint, 42),
+    (Format::R32Sint, 43),
+    (Format::D24UnormS8Uint, 45),
+    (Format::Rg8Unorm, 49),
+    (Format::Rg8Uint, 50),
+    (Format::Rg8Snorm, 51),
+    (Format::Rg8Sint, 52),
+    (Format::R16Sfloat, 54),
+    (Format::D16Unorm, 55),
+    (Format::R16Unorm, 56),
+    (Format::R16Uint, 57),
+    (Format::R16Snorm, 58),
+    (Format::R16Sint, 59),
+    (Format::R8Unorm, 61),
+    (Format::R8Uint, 62),
+    (Format::R8Snorm, 63),
+    (Format::R8Sint, 64),
+    (Format::E5b9g9r9Ufloat, 67),
+    (Format::Bc1RgbUnorm, 71),
+    (Format::Bc1RgbSrgb, 72),
+    (Format::Bc2Unorm, 74),
+    (Format::Bc2Srgb, 75),
+    (Format::Bc3Unorm, 77),
+    (Format::Bc3Srgb, 78),
+    (Format::Bc4Unorm, 80),
+    (Format::Bc4Snorm, 81),
+    (Format::Bc5Unorm, 83),
+    (Format::Bc5Snorm, 84),
+    (Format::R5g6b5Unorm, 85),
+    (Format::A1r5g5b5Unorm, 86),
+    (Format::Bgra8Unorm, 87),
+    (Format::Bgra8Srgb, 91),
+    (Format::Bc6hUfloat, 95),
+    (Format::Bc6hSfloat, 96),
+    (Format::Bc7Unorm, 98),
+    (Format::Bc7Srgb, 99),
+];
+
+/// Get the `DXGI_FORMAT` value of a format.
+pub fn to_dxgi_format(format: Format) -> Option<u32> {
+    super::lookup(TABLE, format)
+}
+
+/// Get the format matching a `DXGI_FORMAT` value.
+///
+/// Returns `None` for typeless formats and formats with no equivalent.
+pub fn from_dxgi_format(raw: u32) -> Option<Format> {
+    super::reverse_lookup(TABLE, raw)
+}
@@ -0,0 +1,206 @@
+//! Mapping to OpenGL internal formats, pixel formats and pixel types.
+
+use hal::format::Format;
+
+/// OpenGL description of a format, as passed to `glTexImage*`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct GlFormat {
+    /// Sized internal format.
+    pub internal: u32,
+    /// Pixel data format. Zero for compressed formats.
+    pub format: u32,
+    /// Pixel data type. Zero for compressed formats.
+    pub ty: u32,
+}
+
+// Pixel data types.
+const BYTE: u32 = 0x1400;
+const UNSIGNED_BYTE: u32 = 0x1401;
+const SHORT: u32 = 0x1402;
+const UNSIGNED_SHORT: u32 = 0x1403;
+const INT: u32 = 0x1404;
+const UNSIGNED_INT: u32 = 0x1405;
+const FLOAT: u32 = 0x1406;
+const HALF_FLOAT: u32 = 0x140B;
+const UNSIGNED_SHORT_4_4_4_4: u32 = 0x8033;
+const UNSIGNED_SHORT_5_5_5_1: u32 = 0x8034;
+const UNSIGNED_SHORT_5_6_5: u32 = 0x8363;
+const UNSIGNED_INT_2_10_10_10_REV: u32 = 0x8368;
+const UNSIGNED_INT_24_8: u32 = 0x84FA;
+const UNSIGNED_INT_10F_11F_11F_REV: u32 = 0x8C3B;
+const UNSIGNED_INT_5_9_9_9_REV: u32 = 0x8C3E;
+const FLOAT_32_UNSIGNED_INT_24_8_REV: u32 = 0x8DAD;
+
+// Pixel data formats.
+const STENCIL_INDEX: u32 = 0x1901;
+const DEPTH_COMPONENT: u32 = 0x1902;
+const RED: u32 = 0x1903;
+const RGB: u32 = 0x1907;
+const RGBA: u32 = 0x1908;
+const BGRA: u32 = 0x80E1;
+const RG: u32 = 0x8227;
+const RG_INTEGER: u32 = 0x8228;
+const DEPTH_STENCIL: u32 = 0x84F9;
+const RED_INTEGER: u32 = 0x8D94;
+const RGB_INTEGER: u32 = 0x8D98;
+const RGBA_INTEGER: u32 = 0x8D99;
+
+const fn plain(internal: u32, format: u32, ty: u32) -> GlFormat {
+    GlFormat {
+        internal,
+        format,
+        ty,
+    }
+}
+
+const fn compressed(internal: u32) -> GlFormat {
+    GlFormat {
+        internal,
+        format: 0,
+        ty: 0,
+    }
+}
+
+/// Table of formats and their OpenGL descriptions.
+pub const TABLE: &[(Format, GlFormat)] = &[
+    (Format::Rgba4Unorm, plain(0x8056, RGBA, UNSIGNED_SHORT_4_4_4_4)),
+    (Format::R5g6b5Unorm, plain(0x8D62, RGB, UNSIGNED_SHORT_5_6_5)),
+    (Format::R5g5b5a1Unorm, plain(0x8057, RGBA, UNSIGNED_SHORT_5_5_5_1)),
+    (Format::R8Unorm, plain(0x8229, RED, UNSIGNED_BYTE)),
+    (Format::R8Snorm, plain(0x8F94, RED, BYTE)),
+    (Format::R8Uint, plain(0x8232, RED_INTEGER, UNSIGNED_BYTE)),
+    (Format::R8Sint, plain(0x8231, RED_INTEGER, BYTE)),
+    (Format::Rg8Unorm, plain(0x822B, RG, UNSIGNED_BYTE)),
+    (Format::Rg8Snorm, plain(0x8F95, RG, BYTE)),
+    (Format::Rg8Uint, plain(0x8238, RG_INTEGER, UNSIGNED_BYTE)),
+    (Format::Rg8Sint, plain(0x8237, RG_INTEGER, BYTE)),
+    (Format::Rgb8Unorm, plain(0x8051, RGB, UNSIGNED_BYTE)),
+    (Format::Rgb8Snorm, plain(0x8F96, RGB, BYTE)),
+    (Format::Rgb8Uint, plain(0x8D7D, RGB_INTEGER, UNSIGNED_BYTE)),
+    (Format::Rgb8Sint, plain(0x8D8F, RGB_INTEGER, BYTE)),
+    (Format::Rgb8Srgb, plain(0x8C41, RGB, UNSIGNED_BYTE)),
+    (Format::Rgba8Unorm, plain(0x8058, RGBA, UNSIGNED_BYTE)),
+    (Format::Rgba8Snorm, plain(0x8F97, RGBA, BYTE)),
+    (Format::Rgba8Uint, plain(0x8D7C, RGBA_INTEGER, UNSIGNED_BYTE)),
+    (Format::Rgba8Sint, plain(0x8D8E, RGBA_INTEGER, BYTE)),
+    (Format::Rgba8Srgb, plain(0x8C43, RGBA, UNSIGNED_BYTE)),
+    (Format::Bgra8Unorm, plain(0x8058, BGRA, UNSIGNED_BYTE)),
+    (Format::Bgra8Srgb, plain(0x8C43, BGRA, UNSIGNED_BYTE)),
+    (Format::A2b10g10r10Unorm, plain(0x8059, RGBA, UNSIGNED_INT_2_10_10_10_REV)),
+    (Format::A2b10g10r10Uint, plain(0x906F, RGBA_INTEGER, UNSIGNED_INT_2_10_10_10_REV)),
+    (Format::R16Unorm, plain(0x822A, RED, UNSIGNED_SHORT)),
+    (Format::R16Snorm, plain(0x8F98, RED, SHORT)),
+    (Format::R16Uint, plain(0x8234, RED_INTEGER, UNSIGNED_SHORT)),
+    (Format::R16Sint, plain(0x8233, RED_INTEGER, SHORT)),
+    (Format::R16Sfloat, plain(0x822D, RED, HALF_FLOAT)),
+    (Format::Rg16Unorm, plain(0x822C, RG, UNSIGNED_SHORT)),
+    (Format::Rg16Snorm, plain(0x8F99, RG, SHORT)),
+    (Format::Rg16Uint, plain(0x823A, RG_INTEGER, UNSIGNED_SHORT)),
+    (Format::Rg16Sint, plain(0x8239, RG_INTEGER, SHORT)),
+    (Format::Rg16Sfloat, plain(0x822F, RG, HALF_FLOAT)),
+    (Format::Rgb16Unorm, plain(0x8054, RGB, UNSIGNED_SHORT)),
+    (Format::Rgb16Snorm, plain(0x8F9A, RGB, SHORT)),
+    (Format::Rgb16Uint, plain(0x8D77, RGB_INTEGER, UNSIGNED_SHORT)),
+    (Format::Rgb16Sint, plain(0x8D89, RGB_INTEGER, SHORT)),
+    (Format::Rgb16Sfloat, plain(0x881B, RGB, HALF_FLOAT)),
+    (Format::Rgba16Unorm, plain(0x805B, RGBA, UNSIGNED_SHORT)),
+    (Format::Rgba16Snorm, plain(0x8F9B, RGBA, SHORT)),
+    (Format::Rgba16Uint, plain(0x8D76, RGBA_INTEGER, UNSIGNED_SHORT)),
+    (Format::Rgba16Sint, plain(0x8D88, RGBA_INTEGER, SHORT)),
+    (Format::Rgba16Sfloat, plain(0x881A, RGBA, HALF_FLOAT)),
+    (Format::R32Uint, plain(0x8236, RED_INTEGER, UNSIGNED_INT)),
+    (Format::R32Sint, plain(0x8235, RED_INTEGER, INT)),
+    (Format::R32Sfloat, plain(0x822E, RED, FLOAT)),
+    (Format::Rg32Uint, plain(0x823C, RG_INTEGER, UNSIGNED_INT)),
+    (Format::Rg32Sint, plain(0x823B, RG_INTEGER, INT)),
+    (Format::Rg32Sfloat, plain(0x8230, RG, FLOAT)),
+    (Format::Rgb32Uint, plain(0x8D71, RGB_INTEGER, UNSIGNED_INT)),
+    (Format::Rgb32Sint, plain(0x8D83, RGB_INTEGER, INT)),
+    (Format::Rgb32Sfloat, plain(0x8815, RGB, FLOAT)),
+    (Format::Rgba32Uint, plain(0x8D70, RGBA_INTEGER, UNSIGNED_INT)),
+    (Format::Rgba32Sint, plain(0x8D82, RGBA_INTEGER, INT)),
+    (Format::Rgba32Sfloat, plain(0x8814, RGBA, FLOAT)),
+    (Format::B10g11r11Ufloat, plain(0x8C3A, RGB, UNSIGNED_INT_10F_11F_11F_REV)),
+    (Format::E5b9g9r9Ufloat, plain(0x8C3D, RGB, UNSIGNED_INT_5_9_9_9_REV)),
+    (Format::D16Unorm, plain(0x81A5, DEPTH_COMPONENT, UNSIGNED_SHORT)),
+    (Format::X8D24Unorm, plain(0x81A6, DEPTH_COMPONENT, UNSIGNED_INT)),
+    (Format::D32Sfloat, plain(0x8CAC, DEPTH_COMPONENT, FLOAT)),
+    (Format::S8Uint, plain(0x8D48, STENCIL_INDEX, UNSIGNED_BYTE)),
+    (Format::D24UnormS8Uint, plain(0x88F0, DEPTH_STENCIL, UNSIGNED_INT_24_8)),
+    (Format::D32SfloatS8Uint, plain(0x8CAD, DEPTH_STENCIL, FLOAT_32_UNSIGNED_INT_24_8_REV)),
+    (Format::Bc1RgbUnorm, compressed(0x83F0)),
+    (Format::Bc1RgbaUnorm, compressed(0x83F1)),
+    (Format::Bc2Unorm, compressed(0x83F2)),
+    (Format::Bc3Unorm, compressed(0x83F3)),
+    (Format::Bc1RgbSrgb, compressed(0x8C4C)),
+    (Format::Bc1RgbaSrgb, compressed(0x8C4D)),
+    (Format::Bc2Srgb, compressed(0x8C4E)),
+    (Format::Bc3Srgb, compressed(0x8C4F)),
+    (Format::Bc4Unorm, compressed(0x8DBB)),
+    (Format::Bc4Snorm, compressed(0x8DBC)),
+    (Format::Bc5Unorm, compressed(0x8DBD)),
+    (Format::Bc5Snorm, compressed(0x8DBE)),
+    (Format::Bc7Unorm, compressed(0x8E8C)),
+    (Format::Bc7Srgb, compressed(0x8E8D)),
+    (Format::Bc6hSfloat, compressed(0x8E8E)),
+    (Format::Bc6hUfloat, compressed(0x8E8F)),
+    (Format::EacR11Unorm, compressed(0x9270)),
+    (Format::EacR11Snorm, compressed(0x9271)),
+    (Format::EacR11g11Unorm, compressed(0x9272)),
+    (Format::EacR11g11Snorm, compressed(0x9273)),
+    (Format::Etc2R8g8b8Unorm, compressed(0x9274)),
+    (Format::Etc2R8g8b8Srgb, compressed(0x9275)),
+    (Format::Etc2R8g8b8a1Unorm, compressed(0x9276)),
+    (Format::Etc2R8g8b8a1Srgb, compressed(0x9277)),
+    (Format::Etc2R8g8b8a8Unorm, compressed(0x9278)),
+    (Format::Etc2R8g8b8a8Srgb, compressed(0x9279)),
+    (Format::Astc4x4Unorm, compressed(0x93B0)),
+    (Format::Astc5x4Unorm, compressed(0x93B1)),
+    (Format::Astc5x5Unorm, compressed(0x93B2)),
+    (Format::Astc6x5Unorm, compressed(0x93B3)),
+    (Format::Astc6x6Unorm, compressed(0x93B4)),
+    (Format::Astc8x5Unorm, compressed(0x93B5)),
+    (Format::Astc8x6Unorm, compressed(0x93B6)),
+    (Format::Astc8x8Unorm, compressed(0x93B7)),
+    (Format::Astc10x5Unorm, compressed(0x93B8)),
+    (Format::Astc10x6Unorm, compressed(0x93B9)),
+    (Format::Astc10x8Unorm, compressed(0x93BA)),
+    (Format::Astc10x10Unorm, compressed(0x93BB)),
+    (Format::Astc12x10Unorm, compressed(0x93BC)),
+    (Format::Astc12x12Unorm, compressed(0x93BD)),
+    (Format::Astc4x4Srgb, compressed(0x93D0)),
+    (Format::Astc5x4Srgb, compressed(0x93D1)),
+    (Format::Astc5x5Srgb, compressed(0x93D2)),
+    (Format::Astc6x5Srgb, compressed(0x93D3)),
+    (Format::Astc6x6Srgb, compressed(0x93D4)),
+    (Format::Astc8x5Srgb, compressed(0x93D5)),
+    (Format::Astc8x6Srgb, compressed(0x93D6)),
+    (Format::Astc8x8Srgb, compressed(0x93D7)),
+    (Format::Astc10x5Srgb, compressed(0x93D8)),
+    (Format::Astc10x6Srgb, compressed(0x93D9)),
+    (Format::Astc10x8Srgb, compressed(0x93DA)),
+    (Format::Astc10x10Srgb, compressed(0x93DB)),
+    (Format::Astc12x10Srgb, compressed(0x93DC)),
+    (Format::Astc12x12Srgb, compressed(0x93DD)),
+];
+
+/// Get the OpenGL description of a format.
+pub fn to_gl_format(format: Format) -> Option<GlFormat> {
+    super::lookup(TABLE, format)
+}
+
+/// Get the format matching an OpenGL description.
+pub fn from_gl_format(gl: GlFormat) -> Option<Format> {
+    super::reverse_lookup(TABLE, gl)
+}
+
+/// Get the format matching a sized internal format alone.
+///
+/// When several formats share the internal format, the one
+/// with the matching channel order (`RGBA` rather than `BGRA`) is returned.
+pub fn from_gl_internal_format(internal: u32) -> Option<Format> {
+    TABLE
+        .iter()
+        .find(|&&(_, gl)| gl.internal == internal)
+        .map(|&(format, _)| format)
+}
@@ -0,0 +1,124 @@
+//! Mapping to `MTLPixelFormat` values.
+
+use hal::format::Format;
+
+/// Table of formats and their `MTLPixelFormat` values.
+pub const TABLE: &[(Format, u32)] = &[
+    (Format::R8Unorm, 10),
+    (Format::R8Srgb, 11),
+    (Format::R8Snorm, 12),
+    (Format::R8Uint, 13),
+    (Format::R8Sint, 14),
+    (Format::R16Unorm, 20),
+    (Format::R16Snorm, 22),
+    (Format::R16Uint, 23),
+    (Format::R16Sint, 24),
+    (Format::R16Sfloat, 25),
+    (Format::Rg8Unorm, 30),
+    (Format::Rg8Srgb, 31),
+    (Format::Rg8Snorm, 32),
+    (Format::Rg8Uint, 33),
+    (Format::Rg8Sint, 34),
+    (Format::R5g6b5Unorm, 40),
+    (Format::R5g5b5a1Unorm, 41),
+    (Format::Rgba4Unorm, 42),
+    (Format::A1r5g5b5Unorm, 43),
+    (Format::R32Uint, 53),
+    (Format::R32Sint, 54),
+    (Format::R32Sfloat, 55),
+    (Format::Rg16Unorm, 60),
+    (Format::Rg16Snorm, 62),
+    (Format::Rg16Uint, 63),
+    (Format::Rg16Sint, 64),
+    (Format::Rg16Sfloat, 65),
+    (Format::Rgba8Unorm, 70),
+    (Format::Rgba8Srgb, 71),
+    (Format::Rgba8Snorm, 72),
+    (Format::Rgba8Uint, 73),
+    (Format::Rgba8Sint, 74),
+    (Format::Bgra8Unorm, 80),
+    (Format::Bgra8Srgb, 81),
+    (Format::A2b10g10r10Unorm, 90),
+    (Format::A2b10g10r10Uint, 91),
+    (Format::B10g11r11Ufloat, 92),
+    (Format::E5b9g9r9Ufloat, 93),
+    (Format::A2r10g10b10Unorm, 94),
+    (Format::Rg32Uint, 103),
+    (Format::Rg32Sint, 104),
+    (Format::Rg32Sfloat, 105),
+    (Format::Rgba16Unorm, 110),
+    (Format::Rgba16Snorm, 112),
+    (Format::Rgba16Uint, 113),
+    (Format::Rgba16Sint, 114),
+    (Format::Rgba16Sfloat, 115),
+    (Format::Rgba32Uint, 123),
+    (Format::Rgba32Sint, 124),
+    (Format::Rgba32Sfloat, 125),
+    (Format::Bc1RgbaUnorm, 130),
+    (Format::Bc1RgbaSrgb, 131),
+    (Format::Bc2Unorm, 132),
+    (Format::Bc2Srgb, 133),
+    (Format::Bc3Unorm, 134),
+    (Format::Bc3Srgb, 135),
+    (Format::Bc4Unorm, 140),
+    (Format::Bc4Snorm, 141),
+    (Format::Bc5Unorm, 142),
+    (Format::Bc5Snorm, 143),
+    (Format::Bc6hSfloat, 150),
+    (Format::Bc6hUfloat, 151),
+    (Format::Bc7Unorm, 152),
+    (Format::Bc7Srgb, 153),
+    (Format::EacR11Unorm, 170),
+    (Format::EacR11Snorm, 172),
+    (Format::EacR11g11Unorm, 174),
+    (Format::EacR11g11Snorm, 176),
+    (Format::Etc2R8g8b8a8Unorm, 178),
+    (Format::Etc2R8g8b8a8Srgb, 179),
+    (Format::Etc2R8g8b8Unorm, 180),
+    (Format::Etc2R8g8b8Srgb, 181),
+    (Format::Etc2R8g8b8a1Unorm, 182),
+    (Format::Etc2R8g8b8a1Srgb, 183),
+    (Format::Astc4x4Srgb, 186),
+    (Format::Astc5x4Srgb, 187),
+    (Format::Astc5x5Srgb, 188),
+    (Format::Astc6x5Srgb, 189),
+    (Format::Astc6x6Srgb, 190),
+    (Format::Astc8x5Srgb, 192),
+    (Format::Astc8x6Srgb, 193),
+    (Format::Astc8x8Srgb, 194),
+    (Format::Astc10x5Srgb, 195),
+    (Format::Astc10x6Srgb, 196),
+    (Format::Astc10x8Srgb, 197),
+    (Format::Astc10x10Srgb, 198),
+    (Format::Astc12x10Srgb, 199),
+    (Format::Astc12x12Srgb, 200),
+    (Format::Astc4x4Unorm, 204),
+    (Format::Astc5x4Unorm, 205),
+    (Format::Astc5x5Unorm, 206),
+    (Format::Astc6x5Unorm, 207),
+    (Format::Astc6x6Unorm, 208),
+    (Format::Astc8x5Unorm, 210),
+    (Format::Astc8x6Unorm, 211),
+    (Format::Astc8x8Unorm, 212),
+    (Format::Astc10x5Unorm, 213),
+    (Format::Astc10x6Unorm, 214),
+    (Format::Astc10x8Unorm, 215),
+    (Format::Astc10x10Unorm, 216),
+    (Format::Astc12x10Unorm, 217),
+    (Format::Astc12x12Unorm, 218),
+    (Format::D16Unorm, 250),
+    (Format::D32Sfloat, 252),
+    (Format::S8Uint, 253),
+    (Format::D24UnormS8Uint, 255),
+    (Format::D32SfloatS8Uint, 260),
+];
+
+/// Get the `MTLPixelFormat` value of a format.
+pub fn to_mtl_pixel_format(format: Format) -> Option<u32> {
+    super::lookup(TABLE, format)
+}
+
+/// Get the format matching a `MTLPixelFormat` value.
+pub fn from_mtl_pixel_format(raw: u32) -> Option<Format> {
+    super::reverse_lookup(TABLE, raw)
+}
@@ -0,0 +1,74 @@
+//! Bidirectional mappings between `hal::format::Format` and the format
+//! enumerations of the native APIs.
+//!
+//! The tables only contain raw numbers, so they are available on every platform,
+//! regardless of which backends can be built there. Every table is one-to-one:
+//! aliases that depend on the platform endianness or on the device capabilities
+//! are left to the backends.
+
+use hal::format::Format;
+
+pub mod dxgi;
+pub mod gl;
+pub mod metal;
+pub mod vulkan;
+
+fn lookup<T: Copy>(table: &[(Format, T)], format: Format) -> Option<T> {
+    table
+        .iter()
+        .find(|&&(f, _)| f == format)
+        .map(|&(_, native)| native)
+}
+
+fn reverse_lookup<T: Copy + PartialEq>(table: &[(Format, T)], native: T) -> Option<Format> {
+    table
+        .iter()
+        .find(|&&(_, n)| n == native)
+        .map(|&(format, _)| format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Debug;
+
+    fn check_table<T: Copy + PartialEq + Debug>(table: &[(Format, T)]) {
+        for (i, &(format, native)) in table.iter().enumerate() {
+            for &(other_format, other_native) in &table[i + 1 ..] {
+                assert_ne!(format, other_format);
+                assert_ne!(native, other_native, "{:?} and {:?}", format, other_format);
+            }
+            assert_eq!(lookup(table, format), Some(native));
+            assert_eq!(reverse_lookup(table, native), Some(format));
+        }
+    }
+
+    #[test]
+    fn test_round_trip_vulkan() {
+        for format in Format::all() {
+            assert_eq!(vulkan::from_vk_format(vulkan::to_vk_format(format)), Some(format));
+        }
+        assert_eq!(vulkan::from_vk_format(0), None);
+        assert_eq!(vulkan::from_vk_format(1_000_054_000), None);
+    }
+
+    #[test]
+    fn test_round_trip_dxgi() {
+        check_table(dxgi::TABLE);
+        assert_eq!(dxgi::from_dxgi_format(0), None);
+    }
+
+    #[test]
+    fn test_round_trip_gl() {
+        check_table(gl::TABLE);
+        assert_eq!(
+            gl::from_gl_internal_format(gl::to_gl_format(Format::Bgra8Unorm).unwrap().internal),
+            Some(Format::Rgba8Unorm),
+        );
+    }
+
+    #[test]
+    fn test_round_trip_metal() {
+        check_table(metal::TABLE);
+    }
+}
@@ -0,0 +1,18 @@
+//! Mapping to `VkFormat` values.
+
+use hal::format::Format;
+
+/// Get the `VkFormat` value of a format.
+///
+/// The values of `Format` match the Vulkan core formats exactly.
+pub fn to_vk_format(format: Format) -> u32 {
+    format as u32
+}
+
+/// Get the format matching a `VkFormat` value.
+///
+/// Returns `None` for `VK_FORMAT_UNDEFINED` and for formats
+/// introduced by extensions.
+pub fn from_vk_format(raw: u32) -> Option<Format> {
+    Format::all().find(|&format| format as u32 == raw)
+}
@@ -4,6 +4,12 @@ use {
     spirv_cross::spirv,
 };
 
+pub mod format_map;
+pub mod offscreen;
+pub mod profiler;
+pub mod reflect;
+pub mod sampler;
+
 /// Fast hash map used internally.
 pub type FastHashMap<K, V> =
     std::collections::HashMap<K, V, std::hash::BuildHasherDefault<fxhash::FxHasher>>;
@@ -0,0 +1,490 @@
+//! Presentation engine for offscreen surfaces.
+//!
+//! Backends keep a [`Presenter`] in their offscreen surfaces, forward the swapchain
+//! configuration, acquisition and presentation to it, and provide the texel data
+//! of each presented image. The presenter emulates a display refreshing once per
+//! acquisition, and delivers the frames to the sink according to the present mode.
+
+use hal::{
+    format::{Format, SurfaceType},
+    window::{
+        AcquireError,
+        CreationError,
+        Frame,
+        FrameSink,
+        OffscreenConfig,
+        PresentError,
+        PresentMode,
+        SurfaceCapabilities,
+        SwapImageIndex,
+        SwapchainConfig,
+    },
+};
+
+use std::{
+    collections::VecDeque,
+    fmt,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ImageState {
+    Free,
+    Acquired,
+    Presented,
+}
+
+#[derive(Debug)]
+struct Swapchain {
+    config: SwapchainConfig,
+    images: Vec<ImageState>,
+    next_image: usize,
+    /// Presented frames waiting for the display, with their image and number.
+    queue: VecDeque<(SwapImageIndex, u64, Vec<u8>)>,
+}
+
+/// Swapchain emulation of an offscreen surface.
+pub struct Presenter {
+    formats: Vec<Format>,
+    capabilities: SurfaceCapabilities,
+    sink: Option<Mutex<Box<dyn FrameSink>>>,
+    swapchain: Option<Swapchain>,
+    frame_count: u64,
+}
+
+impl fmt::Debug for Presenter {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Presenter")
+            .field("formats", &self.formats)
+            .field("capabilities", &self.capabilities)
+            .field("swapchain", &self.swapchain)
+            .field("frame_count", &self.frame_count)
+            .finish()
+    }
+}
+
+impl Presenter {
+    /// Create a presenter for the given surface configuration.
+    pub fn new(config: OffscreenConfig) -> Self {
+        Presenter {
+            formats: config.formats,
+            capabilities: config.capabilities,
+            sink: config.sink.map(Mutex::new),
+            swapchain: None,
+            frame_count: 0,
+        }
+    }
+
+    /// Capabilities of the surface.
+    pub fn capabilities(&self) -> SurfaceCapabilities {
+        self.capabilities.clone()
+    }
+
+    /// Formats supported by the surface.
+    pub fn formats(&self) -> Vec<Format> {
+        self.formats.clone()
+    }
+
+    /// Configuration of the current swapchain, if any.
+    pub fn swapchain_config(&self) -> Option<&SwapchainConfig> {
+        self.swapchain.as_ref().map(|sc| &sc.config)
+    }
+
+    /// Size in bytes of the texel data of one presented image.
+    pub fn image_size(&self) -> usize {
+        self.swapchain.as_ref().map_or(0, |sc| {
+            let bytes = sc.config.format.surface_desc().bits as usize / 8;
+            sc.config.extent.width as usize * sc.config.extent.height as usize * bytes
+        })
+    }
+
+    /// (Re)create the swapchain, delivering the frames still queued.
+    ///
+    /// Fails with `CreationError::Unsupported` if the configuration doesn't
+    /// match the surface capabilities or formats, keeping the current swapchain.
+    pub fn configure(&mut self, config: &SwapchainConfig) -> Result<(), CreationError> {
+        let caps = &self.capabilities;
+        let supported = self.formats.contains(&config.format)
+            && caps.image_count.contains(&config.image_count)
+            && caps.extents.start().width <= config.extent.width
+            && caps.extents.start().height <= config.extent.height
+            && config.extent.width <= caps.extents.end().width
+            && config.extent.height <= caps.extents.end().height
+            && config.image_layers <= caps.max_image_layers
+            && caps.usage.contains(config.image_usage)
+            && caps.present_modes.contains(config.present_mode)
+            && caps
+                .composite_alpha_modes
+                .contains(config.composite_alpha_mode);
+        if !supported {
+            return Err(CreationError::Unsupported);
+        }
+
+        self.unconfigure();
+        self.swapchain = Some(Swapchain {
+            config: config.clone(),
+            images: vec![ImageState::Free; config.image_count as usize],
+            next_image: 0,
+            queue: VecDeque::new(),
+        });
+        Ok(())
+    }
+
+    /// Destroy the swapchain, delivering the frames still queued.
+    pub fn unconfigure(&mut self) {
+        if let Some(mut swapchain) = self.swapchain.take() {
+            while let Some((_, index, data)) = swapchain.queue.pop_front() {
+                Self::deliver(&mut self.sink, &swapchain.config, index, &data);
+            }
+        }
+    }
+
+    /// Acquire a free image, refreshing the display before.
+    pub fn acquire(&mut self) -> Result<SwapImageIndex, AcquireError> {
+        let swapchain = self.swapchain.as_mut().ok_or(AcquireError::OutOfDate)?;
+
+        // The display shows at most one frame per refresh.
+        if let Some((image, index, data)) = swapchain.queue.pop_front() {
+            Self::deliver(&mut self.sink, &swapchain.config, index, &data);
+            swapchain.images[image as usize] = ImageState::Free;
+        }
+
+        let count = swapchain.images.len();
+        let image = (0 .. count)
+            .map(|i| (swapchain.next_image + i) % count)
+            .find(|&i| swapchain.images[i] == ImageState::Free)
+            .ok_or(AcquireError::NotReady)?;
+        swapchain.images[image] = ImageState::Acquired;
+        swapchain.next_image = (image + 1) % count;
+        Ok(image as SwapImageIndex)
+    }
+
+    /// Present an acquired image with the given texel data,
+    /// in rows from top to bottom without padding.
+    ///
+    /// Fails with `PresentError::OutOfDate` if there is no swapchain.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `image` wasn't returned by `acquire` since the swapchain was
+    /// configured, or was already presented, since presenting an image that
+    /// isn't acquired is invalid usage of a `hal` swapchain.
+    pub fn present(&mut self, image: SwapImageIndex, data: Vec<u8>) -> Result<(), PresentError> {
+        let swapchain = self.swapchain.as_mut().ok_or(PresentError::OutOfDate)?;
+        assert_eq!(
+            swapchain.images.get(image as usize),
+            Some(&ImageState::Acquired),
+            "Image {} is presented without being acquired",
+            image
+        );
+        let index = self.frame_count;
+        self.frame_count += 1;
+
+        let mode = swapchain.config.present_mode;
+        if mode.contains(PresentMode::IMMEDIATE) {
+            Self::deliver(&mut self.sink, &swapchain.config, index, &data);
+            swapchain.images[image as usize] = ImageState::Free;
+            return Ok(());
+        }
+        if mode.contains(PresentMode::MAILBOX) {
+            if let Some((replaced, _, _)) = swapchain.queue.pop_back() {
+                swapchain.images[replaced as usize] = ImageState::Free;
+            }
+        }
+        swapchain.images[image as usize] = ImageState::Presented;
+        swapchain.queue.push_back((image, index, data));
+        Ok(())
+    }
+
+    fn deliver(
+        sink: &mut Option<Mutex<Box<dyn FrameSink>>>,
+        config: &SwapchainConfig,
+        index: u64,
+        data: &[u8],
+    ) {
+        if let Some(ref mut sink) = *sink {
+            let sink = sink.get_mut().unwrap();
+            sink.consume(Frame {
+                index,
+                extent: config.extent,
+                format: config.format,
+                data,
+            });
+        }
+    }
+}
+
+impl Drop for Presenter {
+    fn drop(&mut self) {
+        self.unconfigure();
+    }
+}
+
+/// Image file format for [`FileSink`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileFormat {
+    /// Binary PPM, dropping the alpha channel.
+    Ppm,
+    /// PNG, with the alpha channel.
+    #[cfg(feature = "png")]
+    Png,
+}
+
+/// Frame sink writing the frames as numbered image files into a directory.
+///
+/// Only formats with 8-bit color channels can be written. Since errors can't be
+/// reported through the presentation, the frames that can't be written are
+/// logged and dropped.
+#[derive(Debug)]
+pub struct FileSink {
+    directory: PathBuf,
+    prefix: String,
+    format: FileFormat,
+}
+
+impl FileSink {
+    /// Create a sink writing the files `{prefix}{index:05}.{ext}` into `directory`.
+    pub fn new(directory: impl Into<PathBuf>, prefix: &str, format: FileFormat) -> Self {
+        FileSink {
+            directory: directory.into(),
+            prefix: prefix.to_string(),
+            format,
+        }
+    }
+
+    /// Return the path of the file for a frame.
+    pub fn path(&self, index: u64) -> PathBuf {
+        let extension = match self.format {
+            FileFormat::Ppm => "ppm",
+            #[cfg(feature = "png")]
+            FileFormat::Png => "png",
+        };
+        self.directory
+            .join(format!("{}{:05}.{}", self.prefix, index, extension))
+    }
+
+    fn write(&self, frame: &Frame<'_>) -> io::Result<()> {
+        let rgba = to_rgba8(frame).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("format {:?} can't be written to a file", frame.format),
+            )
+        })?;
+        let mut file = BufWriter::new(File::create(self.path(frame.index))?);
+        match self.format {
+            FileFormat::Ppm => {
+                write!(
+                    file,
+                    "P6\n{} {}\n255\n",
+                    frame.extent.width, frame.extent.height
+                )?;
+                for texel in rgba.chunks(4) {
+                    file.write_all(&texel[.. 3])?;
+                }
+            }
+            #[cfg(feature = "png")]
+            FileFormat::Png => {
+                let mut encoder = png::Encoder::new(file, frame.extent.width, frame.extent.height);
+                encoder.set_color(png::ColorType::RGBA);
+                encoder.set_depth(png::BitDepth::Eight);
+                encoder.write_header()?.write_image_data(&rgba)?;
+                return Ok(());
+            }
+        }
+        file.flush()
+    }
+}
+
+impl FrameSink for FileSink {
+    fn consume(&mut self, frame: Frame<'_>) {
+        if let Err(e) = self.write(&frame) {
+            log::error!(
+                "Unable to write frame {} to {:?}: {}",
+                frame.index,
+                self.path(frame.index),
+                e
+            );
+        }
+    }
+}
+
+/// Convert the texels of a frame to 8-bit RGBA, if the format has 8-bit channels.
+pub fn to_rgba8(frame: &Frame<'_>) -> Option<Vec<u8>> {
+    // Positions of the R, G, B and A channels in a texel, if present.
+    let (size, channels): (usize, [Option<usize>; 4]) = match frame.format.base_format().0 {
+        SurfaceType::R8 => (1, [Some(0), None, None, None]),
+        SurfaceType::R8_G8 => (2, [Some(0), Some(1), None, None]),
+        SurfaceType::R8_G8_B8 => (3, [Some(0), Some(1), Some(2), None]),
+        SurfaceType::B8_G8_R8 => (3, [Some(2), Some(1), Some(0), None]),
+        SurfaceType::R8_G8_B8_A8 | SurfaceType::A8_B8_G8_R8 => {
+            (4, [Some(0), Some(1), Some(2), Some(3)])
+        }
+        SurfaceType::B8_G8_R8_A8 => (4, [Some(2), Some(1), Some(0), Some(3)]),
+        _ => return None,
+    };
+    Some(
+        frame
+            .data
+            .chunks(size)
+            .flat_map(|texel| {
+                let mut rgba = [0, 0, 0, 0xFF];
+                for (out, channel) in rgba.iter_mut().zip(&channels) {
+                    if let Some(i) = *channel {
+                        *out = texel[i];
+                    }
+                }
+                rgba.to_vec()
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hal::window::Extent2D;
+    use std::sync::{Arc, Mutex};
+
+    fn create(mode: PresentMode) -> (Presenter, Arc<Mutex<Vec<u64>>>) {
+        let frames = Arc::new(Mutex::new(Vec::new()));
+        let frames_sink = Arc::clone(&frames);
+        let extent = Extent2D {
+            width: 2,
+            height: 2,
+        };
+        let config = OffscreenConfig::new(extent, vec![Format::Rgba8Unorm])
+            .with_sink(move |frame: Frame<'_>| frames_sink.lock().unwrap().push(frame.index));
+        let mut presenter = Presenter::new(config);
+        presenter
+            .configure(&SwapchainConfig::new(2, 2, Format::Rgba8Unorm, 3).with_present_mode(mode))
+            .unwrap();
+        (presenter, frames)
+    }
+
+    fn present_frame(presenter: &mut Presenter) {
+        let image = presenter.acquire().unwrap();
+        let data = vec![0; presenter.image_size()];
+        presenter.present(image, data).unwrap();
+    }
+
+    #[test]
+    fn test_present_modes() {
+        let (mut presenter, frames) = create(PresentMode::IMMEDIATE);
+        present_frame(&mut presenter);
+        present_frame(&mut presenter);
+        assert_eq!(*frames.lock().unwrap(), vec![0, 1]);
+
+        let (mut presenter, frames) = create(PresentMode::FIFO);
+        for _ in 0 .. 3 {
+            present_frame(&mut presenter);
+        }
+        assert_eq!(*frames.lock().unwrap(), vec![0, 1]);
+        presenter.unconfigure();
+        assert_eq!(*frames.lock().unwrap(), vec![0, 1, 2]);
+
+        let (mut presenter, frames) = create(PresentMode::MAILBOX);
+        let first = presenter.acquire().unwrap();
+        let second = presenter.acquire().unwrap();
+        presenter.present(first, vec![0; 16]).unwrap();
+        presenter.present(second, vec![0; 16]).unwrap();
+        present_frame(&mut presenter);
+        drop(presenter);
+        assert_eq!(*frames.lock().unwrap(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_configure_unsupported() {
+        let (mut presenter, _) = create(PresentMode::FIFO);
+        let config = SwapchainConfig::new(2, 2, Format::Rgba8Unorm, 3).with_image_count(4);
+        assert_eq!(
+            presenter.configure(&config),
+            Err(CreationError::Unsupported)
+        );
+        assert_eq!(
+            presenter.configure(&SwapchainConfig::new(4, 2, Format::Rgba8Unorm, 3)),
+            Err(CreationError::Unsupported)
+        );
+        assert_eq!(
+            presenter.configure(&SwapchainConfig::new(2, 2, Format::R8Unorm, 3)),
+            Err(CreationError::Unsupported)
+        );
+        // The swapchain is kept on failure.
+        assert!(presenter.acquire().is_ok());
+    }
+
+    #[test]
+    fn test_acquire_exhausted() {
+        let (mut presenter, _) = create(PresentMode::FIFO);
+        for _ in 0 .. 3 {
+            presenter.acquire().unwrap();
+        }
+        assert_eq!(presenter.acquire(), Err(AcquireError::NotReady));
+    }
+
+    #[test]
+    #[should_panic(expected = "Image 3 is presented without being acquired")]
+    fn test_present_out_of_range() {
+        let (mut presenter, _) = create(PresentMode::FIFO);
+        presenter.acquire().unwrap();
+        let _ = presenter.present(3, vec![0; 16]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Image 0 is presented without being acquired")]
+    fn test_present_twice() {
+        let (mut presenter, _) = create(PresentMode::FIFO);
+        let image = presenter.acquire().unwrap();
+        presenter.present(image, vec![0; 16]).unwrap();
+        let _ = presenter.present(image, vec![0; 16]);
+    }
+
+    #[test]
+    fn test_to_rgba8() {
+        let frame = Frame {
+            index: 0,
+            extent: Extent2D {
+                width: 1,
+                height: 1,
+            },
+            format: Format::Bgra8Srgb,
+            data: &[1, 2, 3, 4],
+        };
+        assert_eq!(to_rgba8(&frame), Some(vec![3, 2, 1, 4]));
+    }
+
+    #[test]
+    fn test_file_sink() {
+        let directory = std::env::temp_dir();
+        let mut sink = FileSink::new(&directory, "gfx-offscreen-test-", FileFormat::Ppm);
+        sink.consume(Frame {
+            index: 7,
+            extent: Extent2D {
+                width: 2,
+                height: 1,
+            },
+            format: Format::Rgba8Unorm,
+            data: &[1, 2, 3, 4, 5, 6, 7, 8],
+        });
+        let path = sink.path(7);
+        assert!(path.ends_with("gfx-offscreen-test-00007.ppm"));
+        let contents = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(contents, b"P6\n2 1\n255\n\x01\x02\x03\x05\x06\x07");
+
+        // Frames that can't be written are dropped.
+        let mut sink = FileSink::new(directory.join("gfx-offscreen-missing"), "", FileFormat::Ppm);
+        sink.consume(Frame {
+            index: 0,
+            extent: Extent2D {
+                width: 1,
+                height: 1,
+            },
+            format: Format::Rgba8Unorm,
+            data: &[1, 2, 3, 4],
+        });
+        assert!(!sink.path(0).exists());
+    }
+}
@@ -0,0 +1,706 @@
+//! Scoped GPU timing on top of timestamp queries and debug markers.
+//!
+//! Each scope is a debug marker surrounded by two timestamps. Every frame in
+//! flight has its own timestamp query pool, which is read back when the frame
+//! slot is reused, at which point the fence of its previous submission must
+//! have signaled. The results form a tree of nested scopes per frame, which
+//! can be exported in the Chrome tracing format.
+//!
+//! Devices without timestamp queries still get the debug markers, but no timings.
+
+use hal::{
+    command::CommandBuffer,
+    device::{Device, OomOrDeviceLost, OutOfMemory},
+    pso::PipelineStage,
+    query,
+    Backend,
+};
+
+use std::{
+    fmt,
+    io::{self, Write},
+    ops::Range,
+    slice,
+};
+
+/// Operations the profiler needs from a backend.
+///
+/// Implemented for every `hal` backend, and for mock backends in tests.
+pub trait ProfilerBackend {
+    /// Logical device.
+    type Device;
+    /// Command buffer the scopes are recorded into.
+    type CommandBuffer;
+    /// Timestamp query pool.
+    type QueryPool;
+
+    /// Create a pool of `count` timestamp queries.
+    ///
+    /// # Safety
+    ///
+    /// Same as `Device::create_query_pool`.
+    unsafe fn create_timestamp_pool(
+        device: &Self::Device,
+        count: query::Id,
+    ) -> Result<Self::QueryPool, query::CreationError>;
+    /// Destroy a timestamp query pool.
+    ///
+    /// # Safety
+    ///
+    /// The pool must come from `device`, and no pending command may use it.
+    unsafe fn destroy_timestamp_pool(device: &Self::Device, pool: Self::QueryPool);
+    /// Read the raw timestamps of `queries`, returning `Ok(false)` if they aren't available.
+    ///
+    /// # Safety
+    ///
+    /// The pool must come from `device`, and `data` must hold one value per query.
+    unsafe fn read_timestamps(
+        device: &Self::Device,
+        pool: &Self::QueryPool,
+        queries: Range<query::Id>,
+        data: &mut [u64],
+    ) -> Result<bool, OomOrDeviceLost>;
+    /// Record resetting `queries`.
+    ///
+    /// # Safety
+    ///
+    /// `cmd` must be in the recording state, and `pool` must have been created
+    /// from the device of `cmd`.
+    unsafe fn reset_timestamps(
+        cmd: &mut Self::CommandBuffer,
+        pool: &Self::QueryPool,
+        queries: Range<query::Id>,
+    );
+    /// Record writing a timestamp once all previous commands are complete.
+    ///
+    /// # Safety
+    ///
+    /// `cmd` must be in the recording state, and `pool` must have been created
+    /// from the device of `cmd`.
+    unsafe fn write_timestamp(cmd: &mut Self::CommandBuffer, pool: &Self::QueryPool, id: query::Id);
+    /// Record opening a debug marker.
+    ///
+    /// # Safety
+    ///
+    /// `cmd` must be in the recording state.
+    unsafe fn begin_marker(cmd: &mut Self::CommandBuffer, name: &str, color: u32);
+    /// Record closing the last opened debug marker.
+    ///
+    /// # Safety
+    ///
+    /// `cmd` must be in the recording state, with a debug marker opened.
+    unsafe fn end_marker(cmd: &mut Self::CommandBuffer);
+}
+
+impl<B: Backend> ProfilerBackend for B {
+    type Device = B::Device;
+    type CommandBuffer = B::CommandBuffer;
+    type QueryPool = B::QueryPool;
+
+    unsafe fn create_timestamp_pool(
+        device: &B::Device,
+        count: query::Id,
+    ) -> Result<B::QueryPool, query::CreationError> {
+        device.create_query_pool(query::Type::Timestamp, count)
+    }
+
+    unsafe fn destroy_timestamp_pool(device: &B::Device, pool: B::QueryPool) {
+        device.destroy_query_pool(pool)
+    }
+
+    unsafe fn read_timestamps(
+        device: &B::Device,
+        pool: &B::QueryPool,
+        queries: Range<query::Id>,
+        data: &mut [u64],
+    ) -> Result<bool, OomOrDeviceLost> {
+        let bytes = slice::from_raw_parts_mut(data.as_mut_ptr() as *mut u8, data.len() * 8);
+        device.get_query_pool_results(pool, queries, bytes, 8, query::ResultFlags::BITS_64)
+    }
+
+    unsafe fn reset_timestamps(
+        cmd: &mut B::CommandBuffer,
+        pool: &B::QueryPool,
+        queries: Range<query::Id>,
+    ) {
+        cmd.reset_query_pool(pool, queries)
+    }
+
+    unsafe fn write_timestamp(cmd: &mut B::CommandBuffer, pool: &B::QueryPool, id: query::Id) {
+        cmd.write_timestamp(PipelineStage::BOTTOM_OF_PIPE, query::Query { pool, id })
+    }
+
+    unsafe fn begin_marker(cmd: &mut B::CommandBuffer, name: &str, color: u32) {
+        cmd.begin_debug_marker(name, color)
+    }
+
+    unsafe fn end_marker(cmd: &mut B::CommandBuffer) {
+        cmd.end_debug_marker()
+    }
+}
+
+/// Timing of a profiled scope, in nanoseconds of the GPU clock.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scope {
+    /// Name given when the scope was opened.
+    pub name: String,
+    /// Start and end time.
+    pub time: Range<u64>,
+    /// Scopes nested in this one, in recording order.
+    pub children: Vec<Scope>,
+}
+
+impl Scope {
+    /// Duration of the scope in nanoseconds.
+    pub fn duration(&self) -> u64 {
+        self.time.end.saturating_sub(self.time.start)
+    }
+}
+
+/// Timings of a profiled frame.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameTimings {
+    /// Number of the frame, counting from 0.
+    pub frame: u64,
+    /// Start and end time, in nanoseconds of the GPU clock.
+    pub time: Range<u64>,
+    /// Top level scopes, in recording order.
+    pub scopes: Vec<Scope>,
+    /// Number of scopes left untimed because the query pool was full.
+    pub untimed: usize,
+}
+
+impl FrameTimings {
+    /// Duration of the frame in nanoseconds.
+    pub fn duration(&self) -> u64 {
+        self.time.end.saturating_sub(self.time.start)
+    }
+}
+
+#[derive(Debug)]
+struct Record {
+    name: String,
+    parent: Option<usize>,
+    /// First of the two queries of the scope.
+    query: query::Id,
+}
+
+#[derive(Debug)]
+struct Slot<P> {
+    pool: P,
+    /// Frame recorded with this slot, waiting to be read back.
+    frame: Option<u64>,
+    records: Vec<Record>,
+    used: query::Id,
+    untimed: usize,
+}
+
+/// Per-frame GPU profiler.
+pub struct Profiler<B: ProfilerBackend> {
+    /// Empty if the device doesn't support timestamps.
+    slots: Vec<Slot<B::QueryPool>>,
+    current: usize,
+    queries_per_frame: query::Id,
+    timestamp_period: f32,
+    /// Indices of the records of the opened scopes, `None` for untimed ones.
+    stack: Vec<Option<usize>>,
+    frame_count: u64,
+    /// Frames whose timings weren't available when read back.
+    missed_frames: u64,
+}
+
+impl<B: ProfilerBackend> fmt::Debug for Profiler<B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Profiler")
+            .field("frames_in_flight", &self.slots.len())
+            .field("current", &self.current)
+            .field("queries_per_frame", &self.queries_per_frame)
+            .field("timestamp_period", &self.timestamp_period)
+            .field("depth", &self.stack.len())
+            .field("frame_count", &self.frame_count)
+            .field("missed_frames", &self.missed_frames)
+            .finish()
+    }
+}
+
+impl<B: ProfilerBackend> Profiler<B> {
+    /// Create a profiler for `frames_in_flight` frames with up to `max_scopes` timed
+    /// scopes each. `timestamp_period` is the number of nanoseconds per timestamp tick.
+    ///
+    /// If the device doesn't support timestamp queries, the profiler only records
+    /// debug markers.
+    ///
+    /// # Safety
+    ///
+    /// The profiler must be used with command buffers of `device` only, and
+    /// destroyed with it.
+    pub unsafe fn new(
+        device: &B::Device,
+        frames_in_flight: usize,
+        max_scopes: u32,
+        timestamp_period: f32,
+    ) -> Result<Self, OutOfMemory> {
+        assert_ne!(frames_in_flight, 0);
+        let queries_per_frame = 2 + 2 * max_scopes;
+        let mut slots = Vec::with_capacity(frames_in_flight);
+        for _ in 0 .. frames_in_flight {
+            match B::create_timestamp_pool(device, queries_per_frame) {
+                Ok(pool) => slots.push(Slot {
+                    pool,
+                    frame: None,
+                    records: Vec::new(),
+                    used: 0,
+                    untimed: 0,
+                }),
+                Err(err) => {
+                    for slot in slots.drain(..) {
+                        B::destroy_timestamp_pool(device, slot.pool);
+                    }
+                    match err {
+                        query::CreationError::OutOfMemory(oom) => return Err(oom),
+                        query::CreationError::Unsupported(_) => break,
+                    }
+                }
+            }
+        }
+        Ok(Profiler {
+            slots,
+            current: 0,
+            queries_per_frame,
+            timestamp_period,
+            stack: Vec::new(),
+            frame_count: 0,
+            missed_frames: 0,
+        })
+    }
+
+    /// Whether the device supports timestamps, and frames get timings.
+    pub fn has_timestamps(&self) -> bool {
+        !self.slots.is_empty()
+    }
+
+    /// Start profiling a new frame, recording into `cmd` outside of any render pass.
+    ///
+    /// Returns the timings of the frame previously recorded in the same slot, the
+    /// fence of its submission must have signaled. The results are not waited for:
+    /// if they aren't available yet, `None` is returned, the timings of that frame
+    /// are lost, and the frame is counted by `missed_frames`.
+    ///
+    /// # Safety
+    ///
+    /// `cmd` must be in the recording state, outside of a render pass.
+    pub unsafe fn begin_frame(
+        &mut self,
+        device: &B::Device,
+        cmd: &mut B::CommandBuffer,
+    ) -> Result<Option<FrameTimings>, OomOrDeviceLost> {
+        debug_assert!(self.stack.is_empty(), "Frame started with opened scopes");
+        let period = self.timestamp_period;
+        let slot = match self.slots.get_mut(self.current) {
+            Some(slot) => slot,
+            None => return Ok(None),
+        };
+        let timings = match slot.frame.take() {
+            Some(frame) => {
+                let mut data = vec![0; slot.used as usize];
+                if B::read_timestamps(device, &slot.pool, 0 .. slot.used, &mut data)? {
+                    Some(build_timings(frame, slot, &data, period))
+                } else {
+                    self.missed_frames += 1;
+                    None
+                }
+            }
+            None => None,
+        };
+
+        B::reset_timestamps(cmd, &slot.pool, 0 .. self.queries_per_frame);
+        B::write_timestamp(cmd, &slot.pool, 0);
+        slot.frame = Some(self.frame_count);
+        slot.records.clear();
+        slot.used = 2;
+        slot.untimed = 0;
+        Ok(timings)
+    }
+
+    /// Number of frames whose timings were lost, because they weren't available
+    /// yet when `begin_frame` read them back.
+    pub fn missed_frames(&self) -> u64 {
+        self.missed_frames
+    }
+
+    /// Finish profiling the current frame, recording into `cmd`.
+    ///
+    /// # Safety
+    ///
+    /// `cmd` must be in the recording state, outside of a render pass.
+    pub unsafe fn end_frame(&mut self, cmd: &mut B::CommandBuffer) {
+        debug_assert!(self.stack.is_empty(), "Frame ended with opened scopes");
+        if let Some(slot) = self.slots.get(self.current) {
+            B::write_timestamp(cmd, &slot.pool, 1);
+            self.current = (self.current + 1) % self.slots.len();
+        }
+        self.frame_count += 1;
+    }
+
+    /// Open a scope nested in the currently opened one.
+    ///
+    /// # Safety
+    ///
+    /// `cmd` must be in the recording state, between `begin_frame` and `end_frame`.
+    pub unsafe fn begin_scope(&mut self, cmd: &mut B::CommandBuffer, name: &str, color: u32) {
+        B::begin_marker(cmd, name, color);
+        let parent = self.stack.iter().rev().find_map(|&record| record);
+        let queries_per_frame = self.queries_per_frame;
+        let record = self.slots.get_mut(self.current).and_then(|slot| {
+            if slot.used + 2 > queries_per_frame {
+                slot.untimed += 1;
+                return None;
+            }
+            B::write_timestamp(cmd, &slot.pool, slot.used);
+            slot.records.push(Record {
+                name: name.to_string(),
+                parent,
+                query: slot.used,
+            });
+            slot.used += 2;
+            Some(slot.records.len() - 1)
+        });
+        self.stack.push(record);
+    }
+
+    /// Close the last opened scope.
+    ///
+    /// # Safety
+    ///
+    /// `cmd` must be in the recording state, and in the same render pass as when
+    /// the scope was opened, if any.
+    pub unsafe fn end_scope(&mut self, cmd: &mut B::CommandBuffer) {
+        let record = self.stack.pop().expect("No scope is opened");
+        if let Some(index) = record {
+            let slot = &self.slots[self.current];
+            B::write_timestamp(cmd, &slot.pool, slot.records[index].query + 1);
+        }
+        B::end_marker(cmd);
+    }
+
+    /// Destroy the query pools.
+    ///
+    /// # Safety
+    ///
+    /// All the profiled frames must have completed.
+    pub unsafe fn destroy(self, device: &B::Device) {
+        for slot in self.slots {
+            B::destroy_timestamp_pool(device, slot.pool);
+        }
+    }
+}
+
+fn build_timings<P>(frame: u64, slot: &Slot<P>, data: &[u64], period: f32) -> FrameTimings {
+    let ns = |query: query::Id| (data[query as usize] as f64 * period as f64) as u64;
+    let mut nodes = slot
+        .records
+        .iter()
+        .map(|record| {
+            Some(Scope {
+                name: record.name.clone(),
+                time: ns(record.query) .. ns(record.query + 1),
+                children: Vec::new(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // Parents are always recorded before their children.
+    let mut scopes = Vec::new();
+    for (index, record) in slot.records.iter().enumerate().rev() {
+        let node = nodes[index].take().unwrap();
+        match record.parent {
+            Some(parent) => nodes[parent].as_mut().unwrap().children.insert(0, node),
+            None => scopes.insert(0, node),
+        }
+    }
+
+    FrameTimings {
+        frame,
+        time: ns(0) .. ns(1),
+        scopes,
+        untimed: slot.untimed,
+    }
+}
+
+fn write_json_string<W: Write>(writer: &mut W, value: &str) -> io::Result<()> {
+    write!(writer, "\"")?;
+    for c in value.chars() {
+        match c {
+            '"' => write!(writer, "\\\"")?,
+            '\\' => write!(writer, "\\\\")?,
+            '\n' => write!(writer, "\\n")?,
+            c if (c as u32) < 0x20 => write!(writer, "\\u{:04x}", c as u32)?,
+            c => write!(writer, "{}", c)?,
+        }
+    }
+    write!(writer, "\"")
+}
+
+fn write_event<W: Write>(
+    writer: &mut W,
+    first: &mut bool,
+    name: &str,
+    time: &Range<u64>,
+    origin: u64,
+) -> io::Result<()> {
+    write!(writer, "{}\n{{\"name\":", if *first { "" } else { "," })?;
+    *first = false;
+    write_json_string(writer, name)?;
+    write!(
+        writer,
+        ",\"cat\":\"gpu\",\"ph\":\"X\",\"pid\":0,\"tid\":0,\"ts\":{:.3},\"dur\":{:.3}}}",
+        time.start.saturating_sub(origin) as f64 / 1000.0,
+        time.end.saturating_sub(time.start) as f64 / 1000.0,
+    )
+}
+
+fn write_scope<W: Write>(
+    writer: &mut W,
+    first: &mut bool,
+    scope: &Scope,
+    origin: u64,
+) -> io::Result<()> {
+    write_event(writer, first, &scope.name, &scope.time, origin)?;
+    for child in scope.children.iter() {
+        write_scope(writer, first, child, origin)?;
+    }
+    Ok(())
+}
+
+/// Write frame timings as a Chrome tracing JSON document, as loaded by
+/// `chrome://tracing` or Perfetto.
+///
+/// Times are relative to the start of the earliest frame.
+pub fn write_chrome_trace<W: Write>(frames: &[FrameTimings], mut writer: W) -> io::Result<()> {
+    let origin = frames
+        .iter()
+        .map(|frame| frame.time.start)
+        .min()
+        .unwrap_or(0);
+    let mut first = true;
+    write!(writer, "{{\"traceEvents\":[")?;
+    for frame in frames {
+        let name = format!("Frame {}", frame.frame);
+        write_event(&mut writer, &mut first, &name, &frame.time, origin)?;
+        for child in frame.scopes.iter() {
+            write_scope(&mut writer, &mut first, child, origin)?;
+        }
+    }
+    writeln!(writer, "\n],\"displayTimeUnit\":\"ns\"}}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::RefCell, rc::Rc};
+
+    // Pools are shared with the command buffers, which write timestamps from a
+    // synthetic clock when recording instead of when executing.
+    type Pool = Rc<RefCell<Vec<Option<u64>>>>;
+
+    struct MockDevice {
+        timestamps: bool,
+    }
+
+    #[derive(Default)]
+    struct MockCommandBuffer {
+        clock: u64,
+        markers: Vec<String>,
+    }
+
+    enum Mock {}
+
+    impl ProfilerBackend for Mock {
+        type Device = MockDevice;
+        type CommandBuffer = MockCommandBuffer;
+        type QueryPool = Pool;
+
+        unsafe fn create_timestamp_pool(
+            device: &MockDevice,
+            count: query::Id,
+        ) -> Result<Pool, query::CreationError> {
+            if device.timestamps {
+                Ok(Rc::new(RefCell::new(vec![None; count as usize])))
+            } else {
+                Err(query::CreationError::Unsupported(query::Type::Timestamp))
+            }
+        }
+
+        unsafe fn destroy_timestamp_pool(_: &MockDevice, _: Pool) {}
+
+        unsafe fn read_timestamps(
+            _: &MockDevice,
+            pool: &Pool,
+            queries: Range<query::Id>,
+            data: &mut [u64],
+        ) -> Result<bool, OomOrDeviceLost> {
+            let pool = pool.borrow();
+            let results = &pool[queries.start as usize .. queries.end as usize];
+            if results.iter().any(Option::is_none) {
+                return Ok(false);
+            }
+            for (value, result) in data.iter_mut().zip(results) {
+                *value = result.unwrap();
+            }
+            Ok(true)
+        }
+
+        unsafe fn reset_timestamps(
+            _: &mut MockCommandBuffer,
+            pool: &Pool,
+            queries: Range<query::Id>,
+        ) {
+            for value in &mut pool.borrow_mut()[queries.start as usize .. queries.end as usize] {
+                *value = None;
+            }
+        }
+
+        unsafe fn write_timestamp(cmd: &mut MockCommandBuffer, pool: &Pool, id: query::Id) {
+            cmd.clock += 10;
+            pool.borrow_mut()[id as usize] = Some(cmd.clock);
+        }
+
+        unsafe fn begin_marker(cmd: &mut MockCommandBuffer, name: &str, _: u32) {
+            cmd.markers.push(format!("+{}", name));
+        }
+
+        unsafe fn end_marker(cmd: &mut MockCommandBuffer) {
+            cmd.markers.push("-".to_string());
+        }
+    }
+
+    unsafe fn record(profiler: &mut Profiler<Mock>, cmd: &mut MockCommandBuffer) {
+        profiler.begin_scope(cmd, "shadows", 0);
+        profiler.end_scope(cmd);
+        profiler.begin_scope(cmd, "main", 0);
+        profiler.begin_scope(cmd, "opaque", 0);
+        profiler.end_scope(cmd);
+        profiler.begin_scope(cmd, "transparent", 0);
+        profiler.end_scope(cmd);
+        profiler.end_scope(cmd);
+    }
+
+    fn scope(name: &str, time: Range<u64>, children: Vec<Scope>) -> Scope {
+        Scope {
+            name: name.to_string(),
+            time,
+            children,
+        }
+    }
+
+    #[test]
+    fn test_frames() {
+        let device = MockDevice { timestamps: true };
+        let mut cmd = MockCommandBuffer::default();
+        unsafe {
+            let mut profiler = Profiler::<Mock>::new(&device, 2, 3, 2.0).unwrap();
+            assert!(profiler.has_timestamps());
+            for frame in 0 .. 2 {
+                assert_eq!(
+                    profiler.begin_frame(&device, &mut cmd),
+                    Ok(None),
+                    "{}",
+                    frame
+                );
+                record(&mut profiler, &mut cmd);
+                profiler.end_frame(&mut cmd);
+            }
+
+            let timings = profiler.begin_frame(&device, &mut cmd).unwrap().unwrap();
+            assert_eq!(
+                timings,
+                FrameTimings {
+                    frame: 0,
+                    time: 20 .. 160,
+                    scopes: vec![
+                        scope("shadows", 40 .. 60, vec![]),
+                        scope("main", 80 .. 140, vec![scope("opaque", 100 .. 120, vec![])],),
+                    ],
+                    untimed: 1,
+                }
+            );
+            assert_eq!(timings.scopes[1].duration(), 60);
+            assert_eq!(
+                cmd.markers[.. 8],
+                [
+                    "+shadows",
+                    "-",
+                    "+main",
+                    "+opaque",
+                    "-",
+                    "+transparent",
+                    "-",
+                    "-"
+                ]
+            );
+            profiler.destroy(&device);
+        }
+    }
+
+    #[test]
+    fn test_missed_frame() {
+        let device = MockDevice { timestamps: true };
+        let mut cmd = MockCommandBuffer::default();
+        unsafe {
+            let mut profiler = Profiler::<Mock>::new(&device, 1, 2, 1.0).unwrap();
+            assert_eq!(profiler.begin_frame(&device, &mut cmd), Ok(None));
+            record(&mut profiler, &mut cmd);
+            profiler.end_frame(&mut cmd);
+            // The end of the frame hasn't been reached by the device yet.
+            profiler.slots[0].pool.borrow_mut()[1] = None;
+            assert_eq!(profiler.begin_frame(&device, &mut cmd), Ok(None));
+            assert_eq!(profiler.missed_frames(), 1);
+
+            profiler.end_frame(&mut cmd);
+            let timings = profiler.begin_frame(&device, &mut cmd).unwrap().unwrap();
+            assert_eq!(timings.frame, 1);
+            assert_eq!(profiler.missed_frames(), 1);
+            profiler.destroy(&device);
+        }
+    }
+
+    #[test]
+    fn test_no_timestamps() {
+        let device = MockDevice { timestamps: false };
+        let mut cmd = MockCommandBuffer::default();
+        unsafe {
+            let mut profiler = Profiler::<Mock>::new(&device, 2, 4, 1.0).unwrap();
+            assert!(!profiler.has_timestamps());
+            for _ in 0 .. 3 {
+                assert_eq!(profiler.begin_frame(&device, &mut cmd), Ok(None));
+                record(&mut profiler, &mut cmd);
+                profiler.end_frame(&mut cmd);
+            }
+            assert_eq!(cmd.markers.len(), 24);
+            assert_eq!(cmd.clock, 0);
+        }
+    }
+
+    #[test]
+    fn test_chrome_trace() {
+        let frames = [FrameTimings {
+            frame: 3,
+            time: 1000 .. 5000,
+            scopes: vec![scope("\"main\"", 1500 .. 4500, vec![])],
+            untimed: 0,
+        }];
+        let mut output = Vec::new();
+        write_chrome_trace(&frames, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            concat!(
+                "{\"traceEvents\":[\n",
+                "{\"name\":\"Frame 3\",\"cat\":\"gpu\",\"ph\":\"X\",\"pid\":0,\"tid\":0,",
+                "\"ts\":0.000,\"dur\":4.000},\n",
+                "{\"name\":\"\\\"main\\\"\",\"cat\":\"gpu\",\"ph\":\"X\",\"pid\":0,\"tid\":0,",
+                "\"ts\":0.500,\"dur\":3.000}\n",
+                "],\"displayTimeUnit\":\"ns\"}\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,625 @@
+//! Reflection of the interface blocks and specialization constants of a SPIR-V module.
+//!
+//! The explicit layout decorations of uniform blocks, storage blocks and push constant
+//! blocks are flattened into [`Entry`] lists, which can be compared with the layout of
+//! Rust types with `hal::pso::layout::check`. The types of the specialization constants
+//! can be checked with `hal::pso::SpecConstants::check`.
+
+use crate::FastHashMap;
+use hal::pso::{
+    layout::{Entry, Scalar},
+    ShaderSpecConstant,
+    SpecConstantType,
+};
+
+use std::fmt;
+
+const MAGIC: u32 = 0x0723_0203;
+
+mod op {
+    pub const NAME: u32 = 5;
+    pub const MEMBER_NAME: u32 = 6;
+    pub const TYPE_BOOL: u32 = 20;
+    pub const TYPE_INT: u32 = 21;
+    pub const TYPE_FLOAT: u32 = 22;
+    pub const TYPE_VECTOR: u32 = 23;
+    pub const TYPE_MATRIX: u32 = 24;
+    pub const TYPE_ARRAY: u32 = 28;
+    pub const TYPE_RUNTIME_ARRAY: u32 = 29;
+    pub const TYPE_STRUCT: u32 = 30;
+    pub const TYPE_POINTER: u32 = 32;
+    pub const CONSTANT: u32 = 43;
+    pub const SPEC_CONSTANT_TRUE: u32 = 48;
+    pub const SPEC_CONSTANT_FALSE: u32 = 49;
+    pub const SPEC_CONSTANT: u32 = 50;
+    pub const VARIABLE: u32 = 59;
+    pub const DECORATE: u32 = 71;
+    pub const MEMBER_DECORATE: u32 = 72;
+}
+
+mod decoration {
+    pub const SPEC_ID: u32 = 1;
+    pub const BLOCK: u32 = 2;
+    pub const BUFFER_BLOCK: u32 = 3;
+    pub const ROW_MAJOR: u32 = 4;
+    pub const ARRAY_STRIDE: u32 = 6;
+    pub const MATRIX_STRIDE: u32 = 7;
+    pub const BINDING: u32 = 33;
+    pub const DESCRIPTOR_SET: u32 = 34;
+    pub const OFFSET: u32 = 35;
+}
+
+mod storage_class {
+    pub const UNIFORM: u32 = 2;
+    pub const PUSH_CONSTANT: u32 = 9;
+    pub const STORAGE_BUFFER: u32 = 12;
+}
+
+/// Error reflecting a SPIR-V module.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReflectError {
+    /// The module doesn't start with the SPIR-V magic number.
+    BadMagic,
+    /// An instruction runs past the end of the module.
+    Truncated,
+    /// A block refers to an undefined type or constant.
+    UnknownId(u32),
+    /// A block contains a type that can't be described by an entry.
+    UnsupportedType(u32),
+    /// A member of a block structure lacks a layout decoration.
+    MissingDecoration {
+        /// Type or structure missing the decoration.
+        id: u32,
+        /// Name of the decoration.
+        decoration: &'static str,
+    },
+}
+
+impl fmt::Display for ReflectError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ReflectError::BadMagic => write!(fmt, "Not a SPIR-V module"),
+            ReflectError::Truncated => write!(fmt, "Truncated SPIR-V module"),
+            ReflectError::UnknownId(id) => write!(fmt, "Unknown SPIR-V id %{}", id),
+            ReflectError::UnsupportedType(id) => {
+                write!(fmt, "Unsupported type %{} in a block", id)
+            }
+            ReflectError::MissingDecoration { id, decoration } => {
+                write!(fmt, "Type %{} has no {} decoration", id, decoration)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReflectError {}
+
+/// Kind of interface block.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BlockKind {
+    /// Uniform buffer.
+    Uniform,
+    /// Storage buffer.
+    Storage,
+    /// Push constants.
+    PushConstant,
+}
+
+/// Interface block of a shader module.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Block {
+    /// Name of the block variable, or of its type if the variable is anonymous.
+    pub name: String,
+    /// Kind of block.
+    pub kind: BlockKind,
+    /// Descriptor set of a buffer block.
+    pub set: Option<u32>,
+    /// Binding of a buffer block.
+    pub binding: Option<u32>,
+    /// Scalars and vectors of the block, in order.
+    ///
+    /// A trailing runtime array has no entries.
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Type {
+    Bool,
+    Int { width: u32, signed: bool },
+    Float { width: u32 },
+    Vector { component: u32, count: u32 },
+    Matrix { column: u32, count: u32 },
+    Array { element: u32, length: u32 },
+    RuntimeArray,
+    Struct,
+    Pointer { pointee: u32 },
+}
+
+#[derive(Debug, Default)]
+struct Module {
+    names: FastHashMap<u32, String>,
+    member_names: FastHashMap<(u32, u32), String>,
+    decorations: FastHashMap<u32, Vec<(u32, u32)>>,
+    member_decorations: FastHashMap<(u32, u32), Vec<(u32, u32)>>,
+    types: FastHashMap<u32, Type>,
+    members: FastHashMap<u32, Vec<u32>>,
+    constants: FastHashMap<u32, u32>,
+    variables: Vec<(u32, u32, u32)>,
+    spec_constants: Vec<(u32, u32)>,
+}
+
+/// Decode a nul-terminated literal string.
+fn string(words: &[u32]) -> String {
+    let bytes: Vec<u8> = words
+        .iter()
+        .flat_map(|word| word.to_le_bytes().to_vec())
+        .take_while(|&byte| byte != 0)
+        .collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+impl Module {
+    fn parse(words: &[u32]) -> Result<Self, ReflectError> {
+        if words.first() != Some(&MAGIC) {
+            return Err(ReflectError::BadMagic);
+        }
+        let mut module = Module::default();
+        let mut rest = words.get(5 ..).ok_or(ReflectError::Truncated)?;
+        while let Some(&first) = rest.first() {
+            let count = (first >> 16) as usize;
+            if count == 0 || count > rest.len() {
+                return Err(ReflectError::Truncated);
+            }
+            let operands = &rest[1 .. count];
+            module.instruction(first & 0xFFFF, operands);
+            rest = &rest[count ..];
+        }
+        Ok(module)
+    }
+
+    fn instruction(&mut self, opcode: u32, operands: &[u32]) {
+        let operand = |i: usize| operands.get(i).cloned().unwrap_or(0);
+        let ty = match opcode {
+            op::NAME if !operands.is_empty() => {
+                self.names.insert(operands[0], string(&operands[1 ..]));
+                return;
+            }
+            op::MEMBER_NAME if operands.len() > 1 => {
+                let name = string(&operands[2 ..]);
+                self.member_names.insert((operands[0], operands[1]), name);
+                return;
+            }
+            op::DECORATE if operands.len() > 1 => {
+                self.decorations
+                    .entry(operands[0])
+                    .or_default()
+                    .push((operands[1], operand(2)));
+                return;
+            }
+            op::MEMBER_DECORATE if operands.len() > 2 => {
+                self.member_decorations
+                    .entry((operands[0], operands[1]))
+                    .or_default()
+                    .push((operands[2], operand(3)));
+                return;
+            }
+            op::CONSTANT | op::SPEC_CONSTANT if operands.len() > 2 => {
+                self.constants.insert(operands[1], operands[2]);
+                if opcode == op::SPEC_CONSTANT {
+                    self.spec_constants.push((operands[0], operands[1]));
+                }
+                return;
+            }
+            op::SPEC_CONSTANT_TRUE | op::SPEC_CONSTANT_FALSE if operands.len() > 1 => {
+                self.spec_constants.push((operands[0], operands[1]));
+                return;
+            }
+            op::VARIABLE if operands.len() > 2 => {
+                self.variables.push((operands[0], operands[1], operands[2]));
+                return;
+            }
+            op::TYPE_STRUCT if !operands.is_empty() => {
+                self.members.insert(operands[0], operands[1 ..].to_vec());
+                Type::Struct
+            }
+            op::TYPE_BOOL => Type::Bool,
+            op::TYPE_INT => Type::Int {
+                width: operand(1),
+                signed: operand(2) != 0,
+            },
+            op::TYPE_FLOAT => Type::Float { width: operand(1) },
+            op::TYPE_VECTOR => Type::Vector {
+                component: operand(1),
+                count: operand(2),
+            },
+            op::TYPE_MATRIX => Type::Matrix {
+                column: operand(1),
+                count: operand(2),
+            },
+            op::TYPE_ARRAY => Type::Array {
+                element: operand(1),
+                length: operand(2),
+            },
+            op::TYPE_RUNTIME_ARRAY => Type::RuntimeArray,
+            op::TYPE_POINTER => Type::Pointer {
+                pointee: operand(2),
+            },
+            _ => return,
+        };
+        if let Some(&id) = operands.first() {
+            self.types.insert(id, ty);
+        }
+    }
+
+    fn ty(&self, id: u32) -> Result<Type, ReflectError> {
+        self.types
+            .get(&id)
+            .cloned()
+            .ok_or(ReflectError::UnknownId(id))
+    }
+
+    fn decoration(&self, id: u32, decoration: u32) -> Option<u32> {
+        self.decorations.get(&id).and_then(|decorations| {
+            decorations
+                .iter()
+                .find(|&&(d, _)| d == decoration)
+                .map(|&(_, value)| value)
+        })
+    }
+
+    fn member_decoration(&self, id: u32, member: u32, decoration: u32) -> Option<u32> {
+        self.member_decorations
+            .get(&(id, member))
+            .and_then(|decorations| {
+                decorations
+                    .iter()
+                    .find(|&&(d, _)| d == decoration)
+                    .map(|&(_, value)| value)
+            })
+    }
+
+    fn scalar(&self, id: u32) -> Result<Scalar, ReflectError> {
+        match self.ty(id)? {
+            Type::Bool => Ok(Scalar::Uint),
+            Type::Int {
+                width: 32,
+                signed: true,
+            } => Ok(Scalar::Int),
+            Type::Int {
+                width: 32,
+                signed: false,
+            } => Ok(Scalar::Uint),
+            Type::Float { width: 32 } => Ok(Scalar::Float),
+            Type::Float { width: 64 } => Ok(Scalar::Double),
+            _ => Err(ReflectError::UnsupportedType(id)),
+        }
+    }
+
+    /// Flatten the type `id` at `offset`. `matrix` holds the stride and majorness
+    /// decorated on the enclosing structure member.
+    fn collect(
+        &self,
+        id: u32,
+        path: String,
+        offset: u32,
+        matrix: Option<(u32, bool)>,
+        entries: &mut Vec<Entry>,
+    ) -> Result<(), ReflectError> {
+        match self.ty(id)? {
+            Type::Bool | Type::Int { .. } | Type::Float { .. } => entries.push(Entry {
+                path,
+                offset,
+                scalar: self.scalar(id)?,
+                components: 1,
+            }),
+            Type::Vector { component, count } => entries.push(Entry {
+                path,
+                offset,
+                scalar: self.scalar(component)?,
+                components: count,
+            }),
+            Type::Matrix { column, count } => {
+                let (stride, row_major) = matrix.ok_or(ReflectError::MissingDecoration {
+                    id,
+                    decoration: "MatrixStride",
+                })?;
+                let (component, rows) = match self.ty(column)? {
+                    Type::Vector { component, count } => (component, count),
+                    _ => return Err(ReflectError::UnsupportedType(column)),
+                };
+                let scalar = self.scalar(component)?;
+                let (vectors, components) = if row_major {
+                    (rows, count)
+                } else {
+                    (count, rows)
+                };
+                for i in 0 .. vectors {
+                    entries.push(Entry {
+                        path: format!("{}[{}]", path, i),
+                        offset: offset + i * stride,
+                        scalar,
+                        components,
+                    });
+                }
+            }
+            Type::Array { element, length } => {
+                let count = *self
+                    .constants
+                    .get(&length)
+                    .ok_or(ReflectError::UnknownId(length))?;
+                let stride = self.decoration(id, decoration::ARRAY_STRIDE).ok_or(
+                    ReflectError::MissingDecoration {
+                        id,
+                        decoration: "ArrayStride",
+                    },
+                )?;
+                for i in 0 .. count {
+                    self.collect(
+                        element,
+                        format!("{}[{}]", path, i),
+                        offset + i * stride,
+                        matrix,
+                        entries,
+                    )?;
+                }
+            }
+            Type::RuntimeArray => {}
+            Type::Struct => {
+                for (i, &member) in self.members[&id].iter().enumerate() {
+                    let index = i as u32;
+                    let member_offset = self
+                        .member_decoration(id, index, decoration::OFFSET)
+                        .ok_or(ReflectError::MissingDecoration {
+                            id,
+                            decoration: "Offset",
+                        })?;
+                    let matrix = self
+                        .member_decoration(id, index, decoration::MATRIX_STRIDE)
+                        .map(|stride| {
+                            let row_major = self
+                                .member_decoration(id, index, decoration::ROW_MAJOR)
+                                .is_some();
+                            (stride, row_major)
+                        });
+                    let name = self
+                        .member_names
+                        .get(&(id, index))
+                        .filter(|name| !name.is_empty())
+                        .cloned()
+                        .unwrap_or_else(|| i.to_string());
+                    let path = if path.is_empty() {
+                        name
+                    } else {
+                        format!("{}.{}", path, name)
+                    };
+                    self.collect(member, path, offset + member_offset, matrix, entries)?;
+                }
+            }
+            Type::Pointer { .. } => return Err(ReflectError::UnsupportedType(id)),
+        }
+        Ok(())
+    }
+}
+
+/// Specialization constant of a shader module.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpecConstant {
+    /// Constant identifier, from the `constant_id` layout qualifier.
+    pub id: u32,
+    /// Name of the constant, if any.
+    pub name: String,
+    /// Type of the constant.
+    pub ty: SpecConstantType,
+}
+
+impl ShaderSpecConstant for SpecConstant {
+    fn id(&self) -> u32 {
+        self.id
+    }
+    fn ty(&self) -> SpecConstantType {
+        self.ty
+    }
+}
+
+/// Reflect the specialization constants of a SPIR-V module.
+pub fn spec_constants(words: &[u32]) -> Result<Vec<SpecConstant>, ReflectError> {
+    let module = Module::parse(words)?;
+    let mut constants = Vec::new();
+    for &(ty, id) in &module.spec_constants {
+        // Constants without an identifier are derived from other constants.
+        let constant_id = match module.decoration(id, decoration::SPEC_ID) {
+            Some(constant_id) => constant_id,
+            None => continue,
+        };
+        let ty = match module.ty(ty)? {
+            Type::Bool => SpecConstantType::Bool,
+            _ => match module.scalar(ty)? {
+                Scalar::Int => SpecConstantType::Int,
+                Scalar::Uint => SpecConstantType::Uint,
+                Scalar::Float => SpecConstantType::Float,
+                Scalar::Double => SpecConstantType::Double,
+            },
+        };
+        constants.push(SpecConstant {
+            id: constant_id,
+            name: module.names.get(&id).cloned().unwrap_or_default(),
+            ty,
+        });
+    }
+    Ok(constants)
+}
+
+/// Reflect the uniform, storage and push constant blocks of a SPIR-V module.
+pub fn blocks(words: &[u32]) -> Result<Vec<Block>, ReflectError> {
+    let module = Module::parse(words)?;
+    let mut blocks = Vec::new();
+    for &(pointer, id, storage) in &module.variables {
+        let mut ty = match module.ty(pointer)? {
+            Type::Pointer { pointee } => pointee,
+            _ => continue,
+        };
+        // Arrays of buffers share the layout of their elements.
+        while let Type::Array { element, .. } = module.ty(ty)? {
+            ty = element;
+        }
+        match module.ty(ty)? {
+            Type::Struct => {}
+            _ => continue,
+        }
+        let buffer_block = module.decoration(ty, decoration::BUFFER_BLOCK).is_some();
+        let kind = match storage {
+            storage_class::PUSH_CONSTANT => BlockKind::PushConstant,
+            storage_class::UNIFORM if buffer_block => BlockKind::Storage,
+            storage_class::UNIFORM => BlockKind::Uniform,
+            storage_class::STORAGE_BUFFER => BlockKind::Storage,
+            _ => continue,
+        };
+        if !buffer_block && module.decoration(ty, decoration::BLOCK).is_none() {
+            continue;
+        }
+
+        let mut entries = Vec::new();
+        module.collect(ty, String::new(), 0, None, &mut entries)?;
+        let name = match module.names.get(&id) {
+            Some(name) if !name.is_empty() => name.clone(),
+            _ => module.names.get(&ty).cloned().unwrap_or_default(),
+        };
+        blocks.push(Block {
+            name,
+            kind,
+            set: module.decoration(id, decoration::DESCRIPTOR_SET),
+            binding: module.decoration(id, decoration::BINDING),
+            entries,
+        });
+    }
+    Ok(blocks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hal::pso::layout::{BlockLayout, Member, Shape};
+
+    fn string(s: &str) -> Vec<u32> {
+        let mut bytes = s.as_bytes().to_vec();
+        bytes.resize(s.len() / 4 * 4 + 4, 0);
+        bytes
+            .chunks(4)
+            .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .collect()
+    }
+
+    fn module(instructions: Vec<(u32, Vec<u32>)>) -> Vec<u32> {
+        let mut words = vec![MAGIC, 0x0001_0000, 0, 100, 0];
+        for (opcode, operands) in instructions {
+            words.push((operands.len() as u32 + 1) << 16 | opcode);
+            words.extend(operands);
+        }
+        words
+    }
+
+    #[test]
+    fn test_uniform_block() {
+        let mut name = vec![10];
+        name.extend(string("globals"));
+        let mut member = vec![8, 3];
+        member.extend(string("weights"));
+        let words = module(vec![
+            (op::NAME, name),
+            (op::MEMBER_NAME, member),
+            (op::DECORATE, vec![7, decoration::ARRAY_STRIDE, 16]),
+            (op::DECORATE, vec![8, decoration::BLOCK]),
+            (op::MEMBER_DECORATE, vec![8, 0, decoration::OFFSET, 0]),
+            (op::MEMBER_DECORATE, vec![8, 0, 5]),
+            (
+                op::MEMBER_DECORATE,
+                vec![8, 0, decoration::MATRIX_STRIDE, 16],
+            ),
+            (op::MEMBER_DECORATE, vec![8, 1, decoration::OFFSET, 64]),
+            (op::MEMBER_DECORATE, vec![8, 2, decoration::OFFSET, 76]),
+            (op::MEMBER_DECORATE, vec![8, 3, decoration::OFFSET, 80]),
+            (op::DECORATE, vec![10, decoration::DESCRIPTOR_SET, 0]),
+            (op::DECORATE, vec![10, decoration::BINDING, 1]),
+            (op::TYPE_FLOAT, vec![1, 32]),
+            (op::TYPE_VECTOR, vec![2, 1, 3]),
+            (op::TYPE_VECTOR, vec![3, 1, 4]),
+            (op::TYPE_MATRIX, vec![4, 3, 4]),
+            (op::TYPE_INT, vec![5, 32, 0]),
+            (op::CONSTANT, vec![5, 6, 2]),
+            (op::TYPE_ARRAY, vec![7, 1, 6]),
+            (op::TYPE_STRUCT, vec![8, 4, 2, 1, 7]),
+            (op::TYPE_POINTER, vec![9, storage_class::UNIFORM, 8]),
+            (op::VARIABLE, vec![9, 10, storage_class::UNIFORM]),
+        ]);
+
+        let blocks = blocks(&words).unwrap();
+        assert_eq!(blocks.len(), 1);
+        let block = &blocks[0];
+        assert_eq!(block.name, "globals");
+        assert_eq!(block.kind, BlockKind::Uniform);
+        assert_eq!((block.set, block.binding), (Some(0), Some(1)));
+        assert_eq!(block.entries[7].path, "weights[1]");
+
+        let shape = Shape::Struct(vec![
+            Member {
+                name: "0".into(),
+                shape: Shape::Matrix {
+                    scalar: Scalar::Float,
+                    columns: 4,
+                    rows: 4,
+                },
+            },
+            Member {
+                name: "1".into(),
+                shape: Shape::Vector(Scalar::Float, 3),
+            },
+            Member {
+                name: "2".into(),
+                shape: Shape::Scalar(Scalar::Float),
+            },
+            Member {
+                name: "weights".into(),
+                shape: Shape::Array(Box::new(Shape::Scalar(Scalar::Float)), 2),
+            },
+        ]);
+        assert_eq!(block.entries, shape.entries(BlockLayout::Std140));
+    }
+
+    #[test]
+    fn test_spec_constants() {
+        let mut name = vec![3];
+        name.extend(string("specular"));
+        let words = module(vec![
+            (op::NAME, name),
+            (op::DECORATE, vec![3, decoration::SPEC_ID, 7]),
+            (op::DECORATE, vec![4, decoration::SPEC_ID, 2]),
+            (op::TYPE_BOOL, vec![1]),
+            (op::TYPE_FLOAT, vec![2, 32]),
+            (op::SPEC_CONSTANT_TRUE, vec![1, 3]),
+            (op::SPEC_CONSTANT, vec![2, 4, 0]),
+            (op::SPEC_CONSTANT, vec![2, 5, 0]),
+        ]);
+        assert_eq!(
+            spec_constants(&words),
+            Ok(vec![
+                SpecConstant {
+                    id: 7,
+                    name: "specular".into(),
+                    ty: SpecConstantType::Bool,
+                },
+                SpecConstant {
+                    id: 2,
+                    name: String::new(),
+                    ty: SpecConstantType::Float,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(blocks(&[0; 5]), Err(ReflectError::BadMagic));
+        let mut words = module(vec![(op::TYPE_FLOAT, vec![1, 32])]);
+        words.pop();
+        assert_eq!(blocks(&words), Err(ReflectError::Truncated));
+    }
+}
@@ -0,0 +1,366 @@
+//! Reference CPU implementation of `hal::image::SamplerDesc`.
+//!
+//! The sampler evaluates texel fetches following the rules of the Vulkan
+//! specification ("Texel Filtering" and "Wrapping Operation"), so it can be
+//! used as a ground truth when checking the sampler conversions of the
+//! backends. Level of detail is passed in explicitly, since there are no
+//! derivatives available on the CPU. Anisotropic filtering is not emulated.
+
+use hal::{
+    image::{Extent, Filter, Level, SamplerDesc, WrapMode},
+    pso::Comparison,
+};
+
+/// Texel color, with channels in RGBA order.
+pub type Texel = [f32; 4];
+
+/// A source of already decoded texels that can be sampled from.
+pub trait TexelSource {
+    /// Number of dimensions of the image, from 1 to 3.
+    ///
+    /// Filtering is only applied along the existing dimensions.
+    fn dimensions(&self) -> usize;
+    /// Number of mipmap levels.
+    fn num_levels(&self) -> Level;
+    /// Extent of a given mipmap level.
+    fn level_extent(&self, level: Level) -> Extent;
+    /// Fetch a single texel. The coordinates are guaranteed to be
+    /// within the extent of the level.
+    fn fetch(&self, level: Level, coord: [u32; 3]) -> Texel;
+}
+
+/// A simple owned mipmap chain of decoded texels.
+#[derive(Clone, Debug)]
+pub struct MipChain {
+    dimensions: usize,
+    levels: Vec<(Extent, Vec<Texel>)>,
+}
+
+impl MipChain {
+    /// Create a chain with a single level.
+    ///
+    /// Texels are stored row by row, then slice by slice.
+    pub fn new(dimensions: usize, extent: Extent, texels: Vec<Texel>) -> Self {
+        assert!((1 ..= 3).contains(&dimensions));
+        let mut chain = MipChain {
+            dimensions,
+            levels: Vec::new(),
+        };
+        chain.push_level(extent, texels);
+        chain
+    }
+
+    /// Append the next mipmap level.
+    pub fn push_level(&mut self, extent: Extent, texels: Vec<Texel>) {
+        assert_eq!(
+            (extent.width * extent.height * extent.depth) as usize,
+            texels.len()
+        );
+        self.levels.push((extent, texels));
+    }
+}
+
+impl TexelSource for MipChain {
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn num_levels(&self) -> Level {
+        self.levels.len() as Level
+    }
+
+    fn level_extent(&self, level: Level) -> Extent {
+        self.levels[level as usize].0
+    }
+
+    fn fetch(&self, level: Level, coord: [u32; 3]) -> Texel {
+        let (extent, ref texels) = self.levels[level as usize];
+        let index = (coord[2] * extent.height + coord[1]) * extent.width + coord[0];
+        texels[index as usize]
+    }
+}
+
+/// Software sampler evaluating a `SamplerDesc`.
+#[derive(Clone, Debug)]
+pub struct Sampler {
+    desc: SamplerDesc,
+    border: Texel,
+}
+
+impl Sampler {
+    /// Create a new sampler from the description.
+    pub fn new(desc: SamplerDesc) -> Self {
+        Sampler {
+            border: desc.border.into(),
+            desc,
+        }
+    }
+
+    /// Return the description of this sampler.
+    pub fn desc(&self) -> &SamplerDesc {
+        &self.desc
+    }
+
+    /// Sample the source at the given coordinates.
+    ///
+    /// `lod` is the level of detail that would be computed from the derivatives,
+    /// before `lod_bias` and `lod_range` are applied. It's ignored for
+    /// unnormalized samplers, which always read from the first level.
+    pub fn sample<S: TexelSource>(&self, source: &S, coord: [f32; 3], lod: f32) -> Texel {
+        self.sample_impl(source, coord, lod, &|texel| texel)
+    }
+
+    /// Sample the source with a depth comparison against `reference`.
+    ///
+    /// Each fetched texel is compared first, then the results are filtered,
+    /// producing a value between `0.0` and `1.0`. Samplers without
+    /// a `comparison` return the filtered depth instead.
+    pub fn sample_compare<S: TexelSource>(
+        &self,
+        source: &S,
+        coord: [f32; 3],
+        lod: f32,
+        reference: f32,
+    ) -> f32 {
+        match self.desc.comparison {
+            Some(comparison) => {
+                let compare = |texel: Texel| {
+                    let pass = compare(comparison, reference, texel[0]);
+                    [if pass { 1.0 } else { 0.0 }; 4]
+                };
+                self.sample_impl(source, coord, lod, &compare)[0]
+            }
+            None => self.sample(source, coord, lod)[0],
+        }
+    }
+
+    fn sample_impl<S: TexelSource>(
+        &self,
+        source: &S,
+        coord: [f32; 3],
+        lod: f32,
+        map: &dyn Fn(Texel) -> Texel,
+    ) -> Texel {
+        if !self.desc.normalized {
+            return self.sample_level(source, 0, self.desc.mag_filter, coord, map);
+        }
+
+        let lod = (lod + self.desc.lod_bias.0)
+            .max(self.desc.lod_range.start.0)
+            .min(self.desc.lod_range.end.0);
+        let filter = if lod <= 0.0 {
+            self.desc.mag_filter
+        } else {
+            self.desc.min_filter
+        };
+        let max_level = source.num_levels().saturating_sub(1) as f32;
+        let level = lod.max(0.0).min(max_level);
+
+        match self.desc.mip_filter {
+            Filter::Nearest => {
+                let level = ((level + 0.5).ceil() - 1.0).max(0.0) as Level;
+                self.sample_level(source, level, filter, coord, map)
+            }
+            Filter::Linear => {
+                let hi = level.floor();
+                let alpha = level - hi;
+                let a = self.sample_level(source, hi as Level, filter, coord, map);
+                if alpha == 0.0 {
+                    return a;
+                }
+                let b = self.sample_level(source, hi as Level + 1, filter, coord, map);
+                lerp(a, b, alpha)
+            }
+        }
+    }
+
+    fn sample_level<S: TexelSource>(
+        &self,
+        source: &S,
+        level: Level,
+        filter: Filter,
+        coord: [f32; 3],
+        map: &dyn Fn(Texel) -> Texel,
+    ) -> Texel {
+        let extent = source.level_extent(level);
+        let sizes = [extent.width, extent.height, extent.depth];
+        let wraps = [
+            self.desc.wrap_mode.0,
+            self.desc.wrap_mode.1,
+            self.desc.wrap_mode.2,
+        ];
+        let dimensions = source.dimensions();
+
+        // For every axis, collect the (texel index, weight) pairs to combine.
+        let mut taps = [[(Some(0u32), 1.0f32); 2]; 3];
+        let mut counts = [1usize; 3];
+        for axis in 0 .. dimensions {
+            let size = sizes[axis];
+            let texel = if self.desc.normalized {
+                coord[axis] * size as f32
+            } else {
+                coord[axis]
+            };
+            match filter {
+                Filter::Nearest => {
+                    let i = texel.floor() as i32;
+                    taps[axis][0] = (wrap(wraps[axis], i, size), 1.0);
+                }
+                Filter::Linear => {
+                    let shifted = texel - 0.5;
+                    let i = shifted.floor();
+                    let alpha = shifted - i;
+                    let i = i as i32;
+                    taps[axis][0] = (wrap(wraps[axis], i, size), 1.0 - alpha);
+                    taps[axis][1] = (wrap(wraps[axis], i + 1, size), alpha);
+                    counts[axis] = 2;
+                }
+            }
+        }
+
+        let mut result = [0.0; 4];
+        for &(z, wz) in &taps[2][.. counts[2]] {
+            for &(y, wy) in &taps[1][.. counts[1]] {
+                for &(x, wx) in &taps[0][.. counts[0]] {
+                    let weight = wx * wy * wz;
+                    if weight == 0.0 {
+                        continue;
+                    }
+                    let texel = match (x, y, z) {
+                        (Some(x), Some(y), Some(z)) => source.fetch(level, [x, y, z]),
+                        _ => self.border,
+                    };
+                    let texel = map(texel);
+                    for (r, t) in result.iter_mut().zip(texel.iter()) {
+                        *r += t * weight;
+                    }
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Apply the wrap mode to an integer texel coordinate.
+/// Returns `None` if the border color needs to be used.
+fn wrap(mode: WrapMode, i: i32, size: u32) -> Option<u32> {
+    let size = size as i32;
+    let mirror = |n: i32| if n >= 0 { n } else { -(1 + n) };
+    let i = match mode {
+        WrapMode::Tile => i.rem_euclid(size),
+        WrapMode::Mirror => (size - 1) - mirror(i.rem_euclid(2 * size) - size),
+        WrapMode::Clamp => i.max(0).min(size - 1),
+        WrapMode::Border => {
+            if i < 0 || i >= size {
+                return None;
+            }
+            i
+        }
+        WrapMode::MirrorClamp => mirror(i).max(0).min(size - 1),
+    };
+    Some(i as u32)
+}
+
+fn compare(comparison: Comparison, reference: f32, value: f32) -> bool {
+    match comparison {
+        Comparison::Never => false,
+        Comparison::Less => reference < value,
+        Comparison::Equal => reference == value,
+        Comparison::LessEqual => reference <= value,
+        Comparison::Greater => reference > value,
+        Comparison::NotEqual => reference != value,
+        Comparison::GreaterEqual => reference >= value,
+        Comparison::Always => true,
+    }
+}
+
+fn lerp(a: Texel, b: Texel, alpha: f32) -> Texel {
+    let mut out = a;
+    for (o, b) in out.iter_mut().zip(b.iter()) {
+        *o += (b - *o) * alpha;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hal::image::{Lod, PackedColor};
+
+    fn gradient() -> MipChain {
+        let extent = Extent {
+            width: 4,
+            height: 1,
+            depth: 1,
+        };
+        let texels = (0 .. 4).map(|i| [i as f32; 4]).collect();
+        let mut chain = MipChain::new(1, extent, texels);
+        chain.push_level(
+            Extent {
+                width: 2,
+                ..extent
+            },
+            vec![[10.0; 4], [20.0; 4]],
+        );
+        chain
+    }
+
+    #[test]
+    fn test_nearest_wrap_modes() {
+        let chain = gradient();
+        let sample = |wrap, u| {
+            let sampler = Sampler::new(SamplerDesc::new(Filter::Nearest, wrap));
+            sampler.sample(&chain, [u, 0.5, 0.5], 0.0)[0]
+        };
+        assert_eq!(sample(WrapMode::Tile, 1.125), 0.0);
+        assert_eq!(sample(WrapMode::Mirror, 1.125), 3.0);
+        assert_eq!(sample(WrapMode::Clamp, 1.125), 3.0);
+        assert_eq!(sample(WrapMode::Clamp, -0.5), 0.0);
+        assert_eq!(sample(WrapMode::MirrorClamp, -0.125), 0.0);
+        assert_eq!(sample(WrapMode::MirrorClamp, -0.375), 1.0);
+        assert_eq!(sample(WrapMode::MirrorClamp, -2.0), 3.0);
+    }
+
+    #[test]
+    fn test_linear_border() {
+        let chain = gradient();
+        let mut desc = SamplerDesc::new(Filter::Linear, WrapMode::Border);
+        desc.border = PackedColor::from([1.0, 1.0, 1.0, 1.0]);
+        let sampler = Sampler::new(desc);
+        assert_eq!(sampler.sample(&chain, [0.25, 0.0, 0.0], 0.0)[0], 0.5);
+        assert_eq!(sampler.sample(&chain, [0.0, 0.0, 0.0], 0.0)[0], 0.5);
+        assert_eq!(sampler.sample(&chain, [1.0, 0.0, 0.0], 0.0)[0], 2.0);
+    }
+
+    #[test]
+    fn test_mip_selection() {
+        let chain = gradient();
+        let mut desc = SamplerDesc::new(Filter::Nearest, WrapMode::Clamp);
+        assert_eq!(Sampler::new(desc.clone()).sample(&chain, [0.0; 3], 1.0)[0], 10.0);
+        desc.lod_range = Lod(0.0) .. Lod(0.0);
+        assert_eq!(Sampler::new(desc.clone()).sample(&chain, [0.0; 3], 1.0)[0], 0.0);
+        desc.lod_range = Lod::RANGE;
+        desc.lod_bias = Lod(0.75);
+        desc.mip_filter = Filter::Linear;
+        assert_eq!(Sampler::new(desc).sample(&chain, [0.0; 3], 0.0)[0], 7.5);
+    }
+
+    #[test]
+    fn test_unnormalized() {
+        let chain = gradient();
+        let mut desc = SamplerDesc::new(Filter::Nearest, WrapMode::Clamp);
+        desc.normalized = false;
+        let sampler = Sampler::new(desc);
+        assert_eq!(sampler.sample(&chain, [2.5, 0.0, 0.0], 5.0)[0], 2.0);
+    }
+
+    #[test]
+    fn test_compare() {
+        let chain = gradient();
+        let mut desc = SamplerDesc::new(Filter::Linear, WrapMode::Clamp);
+        desc.comparison = Some(Comparison::Less);
+        let sampler = Sampler::new(desc);
+        assert_eq!(sampler.sample_compare(&chain, [0.5, 0.0, 0.0], 0.0, 1.5), 0.5);
+        assert_eq!(sampler.sample_compare(&chain, [0.5, 0.0, 0.0], 0.0, 0.5), 1.0);
+    }
+}
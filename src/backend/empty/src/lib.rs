@@ -65,47 +65,250 @@ impl hal::Backend for Backend {
     type QueryPool = ();
 }
 
-/// Dummy physical device.
+/// Physical device emulated from a [profile][adapter::DeviceProfile].
+///
+/// All the queries are answered from the profile, and opening a device
+/// checks the requested families and features against it.
+/// The resulting logical device can't be used for anything else.
 #[derive(Debug)]
-pub struct PhysicalDevice;
+pub struct PhysicalDevice {
+    profile: adapter::DeviceProfile,
+}
+
+impl PhysicalDevice {
+    /// Create a physical device emulating the given profile.
+    pub fn from_profile(profile: adapter::DeviceProfile) -> Self {
+        PhysicalDevice { profile }
+    }
+
+    /// Return the profile being emulated.
+    pub fn profile(&self) -> &adapter::DeviceProfile {
+        &self.profile
+    }
+}
+
+impl Default for PhysicalDevice {
+    fn default() -> Self {
+        PhysicalDevice::from_profile(builtin_profile())
+    }
+}
+
 impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
     unsafe fn open(
         &self,
-        _: &[(&QueueFamily, &[queue::QueuePriority])],
-        _: hal::Features,
+        families: &[(&QueueFamily, &[queue::QueuePriority])],
+        requested_features: hal::Features,
     ) -> Result<adapter::Gpu<Backend>, device::CreationError> {
-        panic!(DO_NOT_USE_MESSAGE)
-    }
-
-    fn format_properties(&self, _: Option<format::Format>) -> format::Properties {
-        panic!(DO_NOT_USE_MESSAGE)
+        if !self.profile.features.contains(requested_features) {
+            return Err(device::CreationError::MissingFeature);
+        }
+        let queue_groups = families
+            .iter()
+            .map(|&(family, priorities)| {
+                match self.profile.queue_families.get(family.id.0) {
+                    Some(profile) if priorities.len() <= profile.max_queues => {}
+                    _ => return Err(device::CreationError::InitializationFailed),
+                }
+                let mut group = queue::QueueGroup::new(family.id);
+                for _ in priorities {
+                    group.add_queue(CommandQueue);
+                }
+                Ok(group)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(adapter::Gpu {
+            device: Device,
+            queue_groups,
+        })
+    }
+
+    fn format_properties(&self, format: Option<format::Format>) -> format::Properties {
+        self.profile.format_properties(format)
     }
 
     fn image_format_properties(
         &self,
-        _: format::Format,
-        _dim: u8,
-        _: image::Tiling,
-        _: image::Usage,
-        _: image::ViewCapabilities,
+        format: format::Format,
+        dimensions: u8,
+        tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
     ) -> Option<image::FormatProperties> {
-        panic!(DO_NOT_USE_MESSAGE)
+        self.profile
+            .image_format_properties(format, dimensions, tiling, usage, view_caps)
     }
 
     fn memory_properties(&self) -> adapter::MemoryProperties {
-        panic!(DO_NOT_USE_MESSAGE)
+        self.profile.memory_properties.clone()
     }
 
     fn features(&self) -> hal::Features {
-        panic!(DO_NOT_USE_MESSAGE)
+        self.profile.features
     }
 
     fn hints(&self) -> hal::Hints {
-        panic!(DO_NOT_USE_MESSAGE)
+        self.profile.hints
     }
 
     fn limits(&self) -> hal::Limits {
-        panic!(DO_NOT_USE_MESSAGE)
+        self.profile.limits
+    }
+}
+
+/// The profile of the default [`PhysicalDevice`]: a device with a single general queue,
+/// no optional features, and the minimal limits required by Vulkan.
+pub fn builtin_profile() -> adapter::DeviceProfile {
+    let color = format::Properties {
+        linear_tiling: format::ImageFeature::empty(),
+        optimal_tiling: format::ImageFeature::SAMPLED
+            | format::ImageFeature::SAMPLED_LINEAR
+            | format::ImageFeature::COLOR_ATTACHMENT
+            | format::ImageFeature::COLOR_ATTACHMENT_BLEND
+            | format::ImageFeature::BLIT_SRC
+            | format::ImageFeature::BLIT_DST,
+        buffer_features: format::BufferFeature::UNIFORM_TEXEL | format::BufferFeature::VERTEX,
+    };
+    let depth = format::Properties {
+        linear_tiling: format::ImageFeature::empty(),
+        optimal_tiling: format::ImageFeature::SAMPLED
+            | format::ImageFeature::DEPTH_STENCIL_ATTACHMENT
+            | format::ImageFeature::BLIT_SRC,
+        buffer_features: format::BufferFeature::empty(),
+    };
+    let formats = [
+        (format::Format::Rgba8Unorm, color),
+        (format::Format::Rgba8Srgb, color),
+        (format::Format::Bgra8Unorm, color),
+        (format::Format::Bgra8Srgb, color),
+        (format::Format::D32Sfloat, depth),
+    ]
+    .iter()
+    .map(|&(format, properties)| adapter::FormatProfile {
+        format,
+        properties,
+        images: (1 ..= 2)
+            .map(|dimensions| adapter::ImageFormatProfile {
+                dimensions,
+                tiling: image::Tiling::Optimal,
+                usage: image::Usage::all() - image::Usage::STORAGE,
+                view_caps: image::ViewCapabilities::empty(),
+                properties: image::FormatProperties {
+                    max_extent: image::Extent {
+                        width: 4096,
+                        height: if dimensions == 1 { 1 } else { 4096 },
+                        depth: 1,
+                    },
+                    max_levels: 13,
+                    max_layers: 256,
+                    sample_count_mask: 0x1,
+                    max_resource_size: 1 << 30,
+                },
+            })
+            .collect(),
+    })
+    .collect();
+
+    adapter::DeviceProfile {
+        info: adapter::AdapterInfo {
+            name: "Empty".to_string(),
+            vendor: 0,
+            device: 0,
+            device_type: adapter::DeviceType::Other,
+        },
+        features: hal::Features::empty(),
+        hints: hal::Hints::empty(),
+        limits: hal::Limits {
+            max_image_1d_size: 4096,
+            max_image_2d_size: 4096,
+            max_image_3d_size: 256,
+            max_image_cube_size: 4096,
+            max_image_array_layers: 256,
+            max_texel_elements: 65536,
+            max_uniform_buffer_range: 16384,
+            max_storage_buffer_range: 1 << 27,
+            max_push_constants_size: 128,
+            max_memory_allocation_count: 4096,
+            max_sampler_allocation_count: 4000,
+            max_bound_descriptor_sets: 4,
+            max_framebuffer_layers: 256,
+            max_per_stage_descriptor_samplers: 16,
+            max_per_stage_descriptor_uniform_buffers: 12,
+            max_per_stage_descriptor_storage_buffers: 4,
+            max_per_stage_descriptor_sampled_images: 16,
+            max_per_stage_descriptor_storage_images: 4,
+            max_per_stage_descriptor_input_attachments: 4,
+            max_per_stage_resources: 128,
+            max_descriptor_set_samplers: 96,
+            max_descriptor_set_uniform_buffers: 72,
+            max_descriptor_set_uniform_buffers_dynamic: 8,
+            max_descriptor_set_storage_buffers: 24,
+            max_descriptor_set_storage_buffers_dynamic: 4,
+            max_descriptor_set_sampled_images: 96,
+            max_descriptor_set_storage_images: 24,
+            max_descriptor_set_input_attachments: 4,
+            max_vertex_input_attributes: 16,
+            max_vertex_input_bindings: 16,
+            max_vertex_input_attribute_offset: 2047,
+            max_vertex_input_binding_stride: 2048,
+            max_vertex_output_components: 64,
+            max_patch_size: 0,
+            max_geometry_shader_invocations: 0,
+            max_geometry_input_components: 0,
+            max_geometry_output_components: 0,
+            max_geometry_output_vertices: 0,
+            max_geometry_total_output_components: 0,
+            max_fragment_input_components: 64,
+            max_fragment_output_attachments: 4,
+            max_fragment_dual_source_attachments: 0,
+            max_fragment_combined_output_resources: 4,
+            max_compute_shared_memory_size: 16384,
+            max_compute_work_group_count: [65535; 3],
+            max_compute_work_group_invocations: 128,
+            max_compute_work_group_size: [128, 128, 64],
+            max_draw_indexed_index_value: (1 << 24) - 1,
+            max_draw_indirect_count: 1,
+            max_sampler_lod_bias: 2.0,
+            max_sampler_anisotropy: 1.0,
+            max_viewports: 1,
+            max_viewport_dimensions: [4096; 2],
+            max_framebuffer_extent: image::Extent {
+                width: 4096,
+                height: 4096,
+                depth: 256,
+            },
+            min_memory_map_alignment: 64,
+            buffer_image_granularity: 131072,
+            min_texel_buffer_offset_alignment: 256,
+            min_uniform_buffer_offset_alignment: 256,
+            min_storage_buffer_offset_alignment: 256,
+            framebuffer_color_sample_counts: 0x1,
+            framebuffer_depth_sample_counts: 0x1,
+            framebuffer_stencil_sample_counts: 0x1,
+            max_color_attachments: 4,
+            standard_sample_locations: false,
+            optimal_buffer_copy_offset_alignment: 1,
+            optimal_buffer_copy_pitch_alignment: 1,
+            non_coherent_atom_size: 256,
+            min_vertex_input_binding_stride_alignment: 1,
+        },
+        memory_properties: adapter::MemoryProperties {
+            memory_types: vec![
+                adapter::MemoryType {
+                    properties: memory::Properties::DEVICE_LOCAL,
+                    heap_index: 0,
+                },
+                adapter::MemoryType {
+                    properties: memory::Properties::CPU_VISIBLE | memory::Properties::COHERENT,
+                    heap_index: 1,
+                },
+            ],
+            memory_heaps: vec![1 << 28, 1 << 28],
+        },
+        queue_families: vec![adapter::QueueFamilyProfile {
+            queue_type: queue::QueueType::General,
+            max_queues: 1,
+        }],
+        formats,
     }
 }
 
@@ -143,11 +346,13 @@ impl queue::CommandQueue<Backend> for CommandQueue {
 
     unsafe fn present_surface(
         &mut self,
-        _surface: &mut Surface,
-        _image: (),
+        surface: &mut Surface,
+        image: SwapchainImage,
         _wait_semaphore: Option<&()>,
     ) -> Result<Option<window::Suboptimal>, window::PresentError> {
-        panic!(DO_NOT_USE_MESSAGE)
+        // Nothing is ever rendered, so the presented images are blank.
+        let data = vec![0; surface.presenter.image_size()];
+        surface.presenter.present(image.index, data).map(|()| None)
     }
 
     fn wait_idle(&self) -> Result<(), device::OutOfMemory> {
@@ -565,17 +770,22 @@ impl device::Device<Backend> for Device {
     }
 }
 
+/// Queue family of an emulated [`PhysicalDevice`].
 #[derive(Debug)]
-pub struct QueueFamily;
+pub struct QueueFamily {
+    id: queue::QueueFamilyId,
+    queue_type: queue::QueueType,
+    max_queues: usize,
+}
 impl queue::QueueFamily for QueueFamily {
     fn queue_type(&self) -> queue::QueueType {
-        panic!(DO_NOT_USE_MESSAGE)
+        self.queue_type
     }
     fn max_queues(&self) -> usize {
-        panic!(DO_NOT_USE_MESSAGE)
+        self.max_queues
     }
     fn id(&self) -> queue::QueueFamilyId {
-        panic!(DO_NOT_USE_MESSAGE)
+        self.id
     }
 }
 
@@ -944,42 +1154,57 @@ impl pso::DescriptorPool<Backend> for DescriptorPool {
     }
 }
 
-/// Dummy surface.
+/// Offscreen surface, delivering blank images.
 #[derive(Debug)]
-pub struct Surface;
+pub struct Surface {
+    presenter: auxil::offscreen::Presenter,
+}
 impl window::Surface<Backend> for Surface {
     fn supports_queue_family(&self, _: &QueueFamily) -> bool {
-        panic!(DO_NOT_USE_MESSAGE)
+        true
     }
 
     fn capabilities(&self, _: &PhysicalDevice) -> window::SurfaceCapabilities {
-        panic!(DO_NOT_USE_MESSAGE)
+        self.presenter.capabilities()
     }
 
     fn supported_formats(&self, _: &PhysicalDevice) -> Option<Vec<format::Format>> {
-        panic!(DO_NOT_USE_MESSAGE)
+        Some(self.presenter.formats())
     }
 }
+
+/// Image acquired from an offscreen [`Surface`].
+#[derive(Debug)]
+pub struct SwapchainImage {
+    index: window::SwapImageIndex,
+}
+impl Borrow<()> for SwapchainImage {
+    fn borrow(&self) -> &() {
+        &()
+    }
+}
+
 impl window::PresentationSurface<Backend> for Surface {
-    type SwapchainImage = ();
+    type SwapchainImage = SwapchainImage;
 
     unsafe fn configure_swapchain(
         &mut self,
         _: &Device,
-        _: window::SwapchainConfig,
+        config: window::SwapchainConfig,
     ) -> Result<(), window::CreationError> {
-        panic!(DO_NOT_USE_MESSAGE)
+        self.presenter.configure(&config)
     }
 
     unsafe fn unconfigure_swapchain(&mut self, _: &Device) {
-        panic!(DO_NOT_USE_MESSAGE)
+        self.presenter.unconfigure();
     }
 
     unsafe fn acquire_image(
         &mut self,
         _: u64,
-    ) -> Result<((), Option<window::Suboptimal>), window::AcquireError> {
-        panic!(DO_NOT_USE_MESSAGE)
+    ) -> Result<(SwapchainImage, Option<window::Suboptimal>), window::AcquireError> {
+        let index = self.presenter.acquire()?;
+        Ok((SwapchainImage { index }, None))
     }
 }
 
@@ -997,16 +1222,42 @@ impl window::Swapchain<Backend> for Swapchain {
     }
 }
 
-#[derive(Debug)]
-pub struct Instance;
+/// Dummy instance, enumerating one emulated adapter per profile.
+#[derive(Debug, Default)]
+pub struct Instance {
+    profiles: Vec<adapter::DeviceProfile>,
+}
+
+impl Instance {
+    /// Create an instance exposing adapters emulated from the given profiles.
+    pub fn with_profiles(profiles: Vec<adapter::DeviceProfile>) -> Self {
+        Instance { profiles }
+    }
+}
 
 impl hal::Instance<Backend> for Instance {
     fn create(_name: &str, _version: u32) -> Result<Self, hal::UnsupportedBackend> {
-        Ok(Instance)
+        Ok(Instance::default())
     }
 
     fn enumerate_adapters(&self) -> Vec<adapter::Adapter<Backend>> {
-        vec![]
+        self.profiles
+            .iter()
+            .map(|profile| adapter::Adapter {
+                info: profile.info.clone(),
+                queue_families: profile
+                    .queue_families
+                    .iter()
+                    .enumerate()
+                    .map(|(index, family)| QueueFamily {
+                        id: queue::QueueFamilyId(index),
+                        queue_type: family.queue_type,
+                        max_queues: family.max_queues,
+                    })
+                    .collect(),
+                physical_device: PhysicalDevice::from_profile(profile.clone()),
+            })
+            .collect()
     }
 
     unsafe fn create_surface(
@@ -1016,7 +1267,166 @@ impl hal::Instance<Backend> for Instance {
         panic!(DO_NOT_USE_MESSAGE)
     }
 
-    unsafe fn destroy_surface(&self, _surface: Surface) {
-        panic!(DO_NOT_USE_MESSAGE)
+    unsafe fn destroy_surface(&self, _surface: Surface) {}
+
+    fn create_offscreen_surface(
+        &self,
+        config: window::OffscreenConfig,
+    ) -> Result<Surface, window::InitError> {
+        Ok(Surface {
+            presenter: auxil::offscreen::Presenter::new(config),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hal::{adapter::PhysicalDevice as _, Instance as _};
+
+    #[test]
+    fn test_profile_emulation() {
+        let instance = Instance::with_profiles(vec![builtin_profile()]);
+        let adapter = instance.enumerate_adapters().pop().unwrap();
+        let family = &adapter.queue_families[0];
+        let device = &adapter.physical_device;
+
+        assert_eq!(device.limits(), builtin_profile().limits);
+        assert!(unsafe { device.open(&[(family, &[1.0])], hal::Features::empty()) }.is_ok());
+        assert_eq!(
+            unsafe { device.open(&[(family, &[1.0])], hal::Features::GEOMETRY_SHADER) }.err(),
+            Some(device::CreationError::MissingFeature)
+        );
+        assert!(unsafe { device.open(&[(family, &[1.0, 1.0])], hal::Features::empty()) }.is_err());
+
+        let query = |usage| {
+            device.image_format_properties(
+                format::Format::Rgba8Srgb,
+                2,
+                image::Tiling::Optimal,
+                usage,
+                image::ViewCapabilities::empty(),
+            )
+        };
+        assert!(query(image::Usage::SAMPLED).is_some());
+        assert!(query(image::Usage::STORAGE).is_none());
+
+        // Capturing an emulated adapter reproduces everything the device reports.
+        let profile = adapter.profile();
+        assert_eq!(profile.limits, device.limits());
+        assert_eq!(
+            profile.format_properties(Some(format::Format::D32Sfloat)),
+            device.format_properties(Some(format::Format::D32Sfloat))
+        );
+        let adapter = Instance::with_profiles(vec![profile.clone()])
+            .enumerate_adapters()
+            .pop()
+            .unwrap();
+        assert_eq!(adapter.profile(), profile);
+    }
+
+    #[test]
+    fn test_profile_narrower_usage() {
+        // A device supporting sampling and color attachments of a format,
+        // but not both at once, with smaller color attachments.
+        let query = |usage, max_width| adapter::ImageFormatProfile {
+            dimensions: 2,
+            tiling: image::Tiling::Optimal,
+            usage,
+            view_caps: image::ViewCapabilities::empty(),
+            properties: image::FormatProperties {
+                max_extent: image::Extent {
+                    width: max_width,
+                    height: max_width,
+                    depth: 1,
+                },
+                max_levels: 13,
+                max_layers: 256,
+                sample_count_mask: 0x1,
+                max_resource_size: 1 << 30,
+            },
+        };
+        let mut profile = builtin_profile();
+        for format in &mut profile.formats {
+            format.images = vec![
+                query(
+                    image::Usage::TRANSFER_SRC
+                        | image::Usage::TRANSFER_DST
+                        | image::Usage::SAMPLED
+                        | image::Usage::INPUT_ATTACHMENT
+                        | image::Usage::DEPTH_STENCIL_ATTACHMENT,
+                    4096,
+                ),
+                query(image::Usage::COLOR_ATTACHMENT, 2048),
+            ];
+        }
+        let adapter = Instance::with_profiles(vec![profile])
+            .enumerate_adapters()
+            .pop()
+            .unwrap();
+
+        let captured = adapter.profile();
+        let width = |usage| {
+            captured
+                .image_format_properties(
+                    format::Format::Rgba8Srgb,
+                    2,
+                    image::Tiling::Optimal,
+                    usage,
+                    image::ViewCapabilities::empty(),
+                )
+                .map(|properties| properties.max_extent.width)
+        };
+        assert_eq!(width(image::Usage::SAMPLED), Some(4096));
+        assert_eq!(width(image::Usage::COLOR_ATTACHMENT), Some(2048));
+        assert_eq!(
+            width(image::Usage::SAMPLED | image::Usage::COLOR_ATTACHMENT),
+            Some(2048)
+        );
+        assert_eq!(width(image::Usage::STORAGE), None);
+    }
+
+    #[test]
+    fn test_offscreen_surface() {
+        use hal::{
+            queue::CommandQueue as _,
+            window::{PresentationSurface as _, Surface as _},
+        };
+        use std::sync::{Arc, Mutex};
+
+        let frames = Arc::new(Mutex::new(Vec::new()));
+        let frames_sink = Arc::clone(&frames);
+        let extent = window::Extent2D {
+            width: 4,
+            height: 2,
+        };
+        let config = window::OffscreenConfig::new(extent, vec![format::Format::Rgba8Srgb])
+            .with_sink(move |frame: window::Frame<'_>| {
+                frames_sink
+                    .lock()
+                    .unwrap()
+                    .push((frame.index, frame.data.len()));
+            });
+        let instance = Instance::default();
+        let mut surface = instance.create_offscreen_surface(config).unwrap();
+        let device = PhysicalDevice::default();
+        let caps = surface.capabilities(&device);
+        assert_eq!(caps.current_extent, Some(extent));
+
+        let swapchain_config =
+            window::SwapchainConfig::from_caps(&caps, format::Format::Rgba8Srgb, extent);
+        let mut queue = CommandQueue;
+        unsafe {
+            surface
+                .configure_swapchain(&Device, swapchain_config)
+                .unwrap();
+            for _ in 0 .. 2 {
+                let (image, _) = surface.acquire_image(!0).unwrap();
+                queue.present_surface(&mut surface, image, None).unwrap();
+            }
+            surface.unconfigure_swapchain(&Device);
+        }
+        // Each acquisition shows the pending frame, the last one is shown on unconfiguration.
+        assert_eq!(*frames.lock().unwrap(), vec![(0, 32), (1, 32)]);
     }
 }
@@ -5,7 +5,9 @@ use crate::GlContext;
 use hal::format::ChannelType;
 use hal::{self, buffer, command, image, memory, pass, pso, query};
 
+use crate::dump;
 use crate::info;
+use crate::optimize::{self, OptimizerStats};
 use crate::pool::{self, BufferMemory};
 use crate::{native as n, Backend, ColorSlot};
 
@@ -305,6 +307,35 @@ impl CommandStorage {
         slice
     }
 
+    /// Remove redundant state changes from the recorded commands.
+    fn optimize(&mut self) -> OptimizerStats {
+        let mut memory = self
+            .memory
+            .try_lock()
+            .expect("Trying to finish a command buffer, while memory is in-use.");
+
+        let commands = &mut match *memory {
+            BufferMemory::Linear(ref mut buffer) => buffer,
+            BufferMemory::Individual {
+                ref mut storage, ..
+            } => storage.get_mut(&self.id).unwrap(),
+        }
+        .commands;
+
+        // Only one command buffer of a pool is recorded at a time,
+        // so its commands are at the end of the storage.
+        let start = self.buf.offset as usize;
+        if self.buf.size == 0 || start + self.buf.size as usize != commands.len() {
+            return OptimizerStats::default();
+        }
+
+        let mut recorded = commands.split_off(start);
+        let stats = optimize::optimize(&mut recorded);
+        self.buf.size = recorded.len() as u32;
+        commands.extend(recorded);
+        stats
+    }
+
     fn reset(&mut self) {
         let mut memory = self
             .memory
@@ -355,6 +386,7 @@ pub struct CommandBuffer {
     limits: Limits,
     legacy_featues: info::LegacyFeatures,
     active_attribs: usize,
+    optimizer_stats: OptimizerStats,
 }
 
 impl CommandBuffer {
@@ -399,9 +431,34 @@ impl CommandBuffer {
             limits,
             active_attribs: 0,
             legacy_featues,
+            optimizer_stats: OptimizerStats::default(),
         }
     }
 
+    /// Print the recorded commands as the GL calls they are lowered to.
+    ///
+    /// This only reads the recorded data, so it doesn't need a GL context.
+    pub fn dump(&self) -> String {
+        let memory = self
+            .data
+            .memory
+            .try_lock()
+            .expect("Trying to dump a command buffer, while memory is in-use.");
+
+        let buffer = match *memory {
+            BufferMemory::Linear(ref buffer) => buffer,
+            BufferMemory::Individual { ref storage, .. } => storage.get(&self.data.id).unwrap(),
+        };
+        let start = self.data.buf.offset as usize;
+        let end = start + self.data.buf.size as usize;
+        dump::dump(&buffer.commands[start .. end], &buffer.data)
+    }
+
+    /// Number of commands removed when the command buffer was last finished.
+    pub fn optimizer_stats(&self) -> OptimizerStats {
+        self.optimizer_stats
+    }
+
     // Soft reset only the buffers, but doesn't free any memory or clears memory
     // of the owning pool.
     pub(crate) fn soft_reset(&mut self) {
@@ -409,6 +466,7 @@ impl CommandBuffer {
         self.cache = Cache::new();
         self.pass_cache = None;
         self.cur_subpass = !0;
+        self.optimizer_stats = OptimizerStats::default();
     }
 
     fn update_blend_targets(&mut self, blend_targets: &[pso::ColorBlendDesc]) {
@@ -663,7 +721,13 @@ impl command::CommandBuffer<Backend> for CommandBuffer {
     }
 
     unsafe fn finish(&mut self) {
-        // no-op
+        self.optimizer_stats = self.data.optimize();
+        if self.optimizer_stats.removed() != 0 {
+            debug!(
+                "Removed {} redundant and {} overwritten commands",
+                self.optimizer_stats.redundant, self.optimizer_stats.overwritten
+            );
+        }
     }
 
     unsafe fn reset(&mut self, _release_resources: bool) {
@@ -0,0 +1,1118 @@
+//! Human-readable dump of recorded command lists.
+//!
+//! Each recorded command is printed as the GL calls it lowers to, with the
+//! viewports, scissors, draw buffers and uniform values read back from the
+//! command data. Calls depending on the capabilities of the context are
+//! printed in their most general form.
+
+use crate::command::{BufferSlice, Command};
+use crate::native as n;
+use crate::state;
+
+use hal::pso;
+
+use std::convert::TryInto;
+use std::fmt::{self, Write};
+
+fn bytes(data: &[u8], ptr: BufferSlice) -> &[u8] {
+    data.get(ptr.offset as usize .. (ptr.offset + ptr.size) as usize)
+        .unwrap_or(&[])
+}
+
+fn f32s(data: &[u8], ptr: BufferSlice) -> Vec<f32> {
+    bytes(data, ptr)
+        .chunks_exact(4)
+        .map(|c| f32::from_ne_bytes(c.try_into().unwrap()))
+        .collect()
+}
+
+fn f64s(data: &[u8], ptr: BufferSlice) -> Vec<f64> {
+    bytes(data, ptr)
+        .chunks_exact(8)
+        .map(|c| f64::from_ne_bytes(c.try_into().unwrap()))
+        .collect()
+}
+
+fn i32s(data: &[u8], ptr: BufferSlice) -> Vec<i32> {
+    bytes(data, ptr)
+        .chunks_exact(4)
+        .map(|c| i32::from_ne_bytes(c.try_into().unwrap()))
+        .collect()
+}
+
+fn u32s(data: &[u8], ptr: BufferSlice) -> Vec<u32> {
+    bytes(data, ptr)
+        .chunks_exact(4)
+        .map(|c| u32::from_ne_bytes(c.try_into().unwrap()))
+        .collect()
+}
+
+// Name of a GL enum value, given the names it may have in this context.
+fn name(value: u32, names: &[(u32, &str)]) -> String {
+    names
+        .iter()
+        .find(|&&(v, _)| v == value)
+        .map(|&(_, name)| name.to_string())
+        .unwrap_or_else(|| format!("0x{:04X}", value))
+}
+
+fn primitive(value: u32) -> String {
+    name(
+        value,
+        &[
+            (glow::POINTS, "GL_POINTS"),
+            (glow::LINES, "GL_LINES"),
+            (glow::LINE_STRIP, "GL_LINE_STRIP"),
+            (glow::TRIANGLES, "GL_TRIANGLES"),
+            (glow::TRIANGLE_STRIP, "GL_TRIANGLE_STRIP"),
+            (glow::LINES_ADJACENCY, "GL_LINES_ADJACENCY"),
+            (glow::LINE_STRIP_ADJACENCY, "GL_LINE_STRIP_ADJACENCY"),
+            (glow::TRIANGLES_ADJACENCY, "GL_TRIANGLES_ADJACENCY"),
+            (
+                glow::TRIANGLE_STRIP_ADJACENCY,
+                "GL_TRIANGLE_STRIP_ADJACENCY",
+            ),
+            (glow::PATCHES, "GL_PATCHES"),
+        ],
+    )
+}
+
+fn data_type(value: u32) -> String {
+    name(
+        value,
+        &[
+            (glow::BYTE, "GL_BYTE"),
+            (glow::UNSIGNED_BYTE, "GL_UNSIGNED_BYTE"),
+            (glow::SHORT, "GL_SHORT"),
+            (glow::UNSIGNED_SHORT, "GL_UNSIGNED_SHORT"),
+            (glow::INT, "GL_INT"),
+            (glow::UNSIGNED_INT, "GL_UNSIGNED_INT"),
+            (glow::HALF_FLOAT, "GL_HALF_FLOAT"),
+            (glow::FLOAT, "GL_FLOAT"),
+            (glow::DOUBLE, "GL_DOUBLE"),
+        ],
+    )
+}
+
+fn texture_target(value: u32) -> String {
+    name(
+        value,
+        &[
+            (glow::TEXTURE_1D, "GL_TEXTURE_1D"),
+            (glow::TEXTURE_1D_ARRAY, "GL_TEXTURE_1D_ARRAY"),
+            (glow::TEXTURE_2D, "GL_TEXTURE_2D"),
+            (glow::TEXTURE_2D_ARRAY, "GL_TEXTURE_2D_ARRAY"),
+            (glow::TEXTURE_2D_MULTISAMPLE, "GL_TEXTURE_2D_MULTISAMPLE"),
+            (glow::TEXTURE_3D, "GL_TEXTURE_3D"),
+            (glow::TEXTURE_CUBE_MAP, "GL_TEXTURE_CUBE_MAP"),
+            (glow::TEXTURE_CUBE_MAP_ARRAY, "GL_TEXTURE_CUBE_MAP_ARRAY"),
+        ],
+    )
+}
+
+fn framebuffer_target(value: u32) -> String {
+    name(
+        value,
+        &[
+            (glow::FRAMEBUFFER, "GL_FRAMEBUFFER"),
+            (glow::DRAW_FRAMEBUFFER, "GL_DRAW_FRAMEBUFFER"),
+            (glow::READ_FRAMEBUFFER, "GL_READ_FRAMEBUFFER"),
+        ],
+    )
+}
+
+fn attachment(value: u32) -> String {
+    match value {
+        glow::NONE => "GL_NONE".to_string(),
+        glow::DEPTH_ATTACHMENT => "GL_DEPTH_ATTACHMENT".to_string(),
+        glow::STENCIL_ATTACHMENT => "GL_STENCIL_ATTACHMENT".to_string(),
+        glow::DEPTH_STENCIL_ATTACHMENT => "GL_DEPTH_STENCIL_ATTACHMENT".to_string(),
+        _ if (glow::COLOR_ATTACHMENT0 .. glow::COLOR_ATTACHMENT0 + 32).contains(&value) => {
+            format!("GL_COLOR_ATTACHMENT{}", value - glow::COLOR_ATTACHMENT0)
+        }
+        _ => format!("0x{:04X}", value),
+    }
+}
+
+fn buffer_target(value: u32) -> String {
+    name(
+        value,
+        &[
+            (glow::UNIFORM_BUFFER, "GL_UNIFORM_BUFFER"),
+            (glow::SHADER_STORAGE_BUFFER, "GL_SHADER_STORAGE_BUFFER"),
+            (glow::ATOMIC_COUNTER_BUFFER, "GL_ATOMIC_COUNTER_BUFFER"),
+            (
+                glow::TRANSFORM_FEEDBACK_BUFFER,
+                "GL_TRANSFORM_FEEDBACK_BUFFER",
+            ),
+        ],
+    )
+}
+
+fn blend_equation(value: u32) -> String {
+    name(
+        value,
+        &[
+            (glow::FUNC_ADD, "GL_FUNC_ADD"),
+            (glow::FUNC_SUBTRACT, "GL_FUNC_SUBTRACT"),
+            (glow::FUNC_REVERSE_SUBTRACT, "GL_FUNC_REVERSE_SUBTRACT"),
+            (glow::MIN, "GL_MIN"),
+            (glow::MAX, "GL_MAX"),
+        ],
+    )
+}
+
+fn blend_factor(value: u32) -> String {
+    name(
+        value,
+        &[
+            (glow::ZERO, "GL_ZERO"),
+            (glow::ONE, "GL_ONE"),
+            (glow::SRC_COLOR, "GL_SRC_COLOR"),
+            (glow::ONE_MINUS_SRC_COLOR, "GL_ONE_MINUS_SRC_COLOR"),
+            (glow::DST_COLOR, "GL_DST_COLOR"),
+            (glow::ONE_MINUS_DST_COLOR, "GL_ONE_MINUS_DST_COLOR"),
+            (glow::SRC_ALPHA, "GL_SRC_ALPHA"),
+            (glow::ONE_MINUS_SRC_ALPHA, "GL_ONE_MINUS_SRC_ALPHA"),
+            (glow::DST_ALPHA, "GL_DST_ALPHA"),
+            (glow::ONE_MINUS_DST_ALPHA, "GL_ONE_MINUS_DST_ALPHA"),
+            (glow::CONSTANT_COLOR, "GL_CONSTANT_COLOR"),
+            (
+                glow::ONE_MINUS_CONSTANT_COLOR,
+                "GL_ONE_MINUS_CONSTANT_COLOR",
+            ),
+            (glow::CONSTANT_ALPHA, "GL_CONSTANT_ALPHA"),
+            (
+                glow::ONE_MINUS_CONSTANT_ALPHA,
+                "GL_ONE_MINUS_CONSTANT_ALPHA",
+            ),
+            (glow::SRC_ALPHA_SATURATE, "GL_SRC_ALPHA_SATURATE"),
+            (glow::SRC1_COLOR, "GL_SRC1_COLOR"),
+            (glow::ONE_MINUS_SRC1_COLOR, "GL_ONE_MINUS_SRC1_COLOR"),
+            (glow::SRC1_ALPHA, "GL_SRC1_ALPHA"),
+            (glow::ONE_MINUS_SRC1_ALPHA, "GL_ONE_MINUS_SRC1_ALPHA"),
+        ],
+    )
+}
+
+fn comparison(cmp: pso::Comparison) -> &'static str {
+    use hal::pso::Comparison::*;
+    match cmp {
+        Never => "GL_NEVER",
+        Less => "GL_LESS",
+        LessEqual => "GL_LEQUAL",
+        Equal => "GL_EQUAL",
+        GreaterEqual => "GL_GEQUAL",
+        Greater => "GL_GREATER",
+        NotEqual => "GL_NOTEQUAL",
+        Always => "GL_ALWAYS",
+    }
+}
+
+fn blend(out: &mut String, slot: Option<u32>, blend: &Option<pso::BlendState>) -> fmt::Result {
+    let (suffix, slot) = match slot {
+        Some(slot) => ("i", format!("{}, ", slot)),
+        None => ("", String::new()),
+    };
+    match *blend {
+        Some(ref blend) => {
+            let (color_eq, color_src, color_dst) = state::map_blend_op(blend.color);
+            let (alpha_eq, alpha_src, alpha_dst) = state::map_blend_op(blend.alpha);
+            write!(
+                out,
+                "glEnable{}({}GL_BLEND); glBlendEquationSeparate{}({}{}, {}); \
+                 glBlendFuncSeparate{}({}{}, {}, {}, {})",
+                suffix,
+                slot,
+                suffix,
+                slot,
+                blend_equation(color_eq),
+                blend_equation(alpha_eq),
+                suffix,
+                slot,
+                blend_factor(color_src),
+                blend_factor(color_dst),
+                blend_factor(alpha_src),
+                blend_factor(alpha_dst)
+            )
+        }
+        None => write!(out, "glDisable{}({}GL_BLEND)", suffix, slot),
+    }
+}
+
+fn color_mask(mask: pso::ColorMask) -> String {
+    use hal::pso::ColorMask as Cm;
+    format!(
+        "{}, {}, {}, {}",
+        mask.contains(Cm::RED),
+        mask.contains(Cm::GREEN),
+        mask.contains(Cm::BLUE),
+        mask.contains(Cm::ALPHA)
+    )
+}
+
+fn uniform(out: &mut String, desc: &n::UniformDesc, data: &[u8], ptr: BufferSlice) -> fmt::Result {
+    let (function, floats) = match desc.utype {
+        glow::FLOAT => ("glUniform1fv", true),
+        glow::FLOAT_VEC2 => ("glUniform2fv", true),
+        glow::FLOAT_VEC3 => ("glUniform3fv", true),
+        glow::FLOAT_VEC4 => ("glUniform4fv", true),
+        glow::INT => ("glUniform1iv", false),
+        glow::INT_VEC2 => ("glUniform2iv", false),
+        glow::INT_VEC3 => ("glUniform3iv", false),
+        glow::INT_VEC4 => ("glUniform4iv", false),
+        glow::FLOAT_MAT2 => ("glUniformMatrix2fv", true),
+        glow::FLOAT_MAT3 => ("glUniformMatrix3fv", true),
+        glow::FLOAT_MAT4 => ("glUniformMatrix4fv", true),
+        other => {
+            return write!(
+                out,
+                "glUniform({:?}, <unsupported type 0x{:04X}>)",
+                desc.location, other
+            )
+        }
+    };
+    if floats {
+        write!(
+            out,
+            "{}({:?}, {:?})",
+            function,
+            desc.location,
+            f32s(data, ptr)
+        )
+    } else {
+        write!(
+            out,
+            "{}({:?}, {:?})",
+            function,
+            desc.location,
+            i32s(data, ptr)
+        )
+    }
+}
+
+fn rasterizer(out: &mut String, rasterizer: &pso::Rasterizer) -> fmt::Result {
+    use hal::pso::{FrontFace, PolygonMode};
+
+    let front_face = match rasterizer.front_face {
+        FrontFace::Clockwise => "GL_CW",
+        FrontFace::CounterClockwise => "GL_CCW",
+    };
+    write!(out, "glFrontFace({}); ", front_face)?;
+    if rasterizer.cull_face.is_empty() {
+        write!(out, "glDisable(GL_CULL_FACE); ")?;
+    } else {
+        let face = match rasterizer.cull_face {
+            pso::Face::FRONT => "GL_FRONT",
+            pso::Face::BACK => "GL_BACK",
+            _ => "GL_FRONT_AND_BACK",
+        };
+        write!(out, "glEnable(GL_CULL_FACE); glCullFace({}); ", face)?;
+    }
+    if let pso::State::Static(width) = rasterizer.line_width {
+        write!(out, "glLineWidth({}); ", width)?;
+    }
+    let (mode, offset) = match rasterizer.polygon_mode {
+        PolygonMode::Point => ("GL_POINT", "GL_POLYGON_OFFSET_POINT"),
+        PolygonMode::Line => ("GL_LINE", "GL_POLYGON_OFFSET_LINE"),
+        PolygonMode::Fill => ("GL_FILL", "GL_POLYGON_OFFSET_FILL"),
+    };
+    write!(out, "glPolygonMode(GL_FRONT_AND_BACK, {}); ", mode)?;
+    match rasterizer.depth_bias {
+        Some(pso::State::Static(bias)) => write!(
+            out,
+            "glEnable({}); glPolygonOffset({}, {})",
+            offset, bias.slope_factor, bias.const_factor
+        ),
+        _ => write!(out, "glDisable({})", offset),
+    }
+}
+
+fn command(out: &mut String, cmd: &Command, data: &[u8]) -> fmt::Result {
+    match *cmd {
+        Command::Dispatch(count) => write!(
+            out,
+            "glDispatchCompute({}, {}, {})",
+            count[0], count[1], count[2]
+        ),
+        Command::DispatchIndirect(buffer, offset) => write!(
+            out,
+            "glBindBuffer(GL_DISPATCH_INDIRECT_BUFFER, {:?}); glDispatchComputeIndirect({})",
+            buffer, offset
+        ),
+        Command::Draw {
+            primitive: prim,
+            ref vertices,
+            ref instances,
+        } => {
+            let (first, count) = (vertices.start, vertices.end - vertices.start);
+            if *instances == (0 .. 1) {
+                write!(
+                    out,
+                    "glDrawArrays({}, {}, {})",
+                    primitive(prim),
+                    first,
+                    count
+                )
+            } else if instances.start == 0 {
+                write!(
+                    out,
+                    "glDrawArraysInstanced({}, {}, {}, {})",
+                    primitive(prim),
+                    first,
+                    count,
+                    instances.end
+                )
+            } else {
+                write!(
+                    out,
+                    "glDrawArraysInstancedBaseInstance({}, {}, {}, {}, {})",
+                    primitive(prim),
+                    first,
+                    count,
+                    instances.end - instances.start,
+                    instances.start
+                )
+            }
+        }
+        Command::DrawIndexed {
+            primitive: prim,
+            index_type,
+            index_count,
+            index_buffer_offset,
+            base_vertex,
+            ref instances,
+        } => {
+            if *instances == (0 .. 1) && base_vertex == 0 {
+                write!(
+                    out,
+                    "glDrawElements({}, {}, {}, {})",
+                    primitive(prim),
+                    index_count,
+                    data_type(index_type),
+                    index_buffer_offset
+                )
+            } else if *instances == (0 .. 1) {
+                write!(
+                    out,
+                    "glDrawElementsBaseVertex({}, {}, {}, {}, {})",
+                    primitive(prim),
+                    index_count,
+                    data_type(index_type),
+                    index_buffer_offset,
+                    base_vertex
+                )
+            } else if instances.start == 0 && base_vertex == 0 {
+                write!(
+                    out,
+                    "glDrawElementsInstanced({}, {}, {}, {}, {})",
+                    primitive(prim),
+                    index_count,
+                    data_type(index_type),
+                    index_buffer_offset,
+                    instances.end
+                )
+            } else if instances.start == 0 {
+                write!(
+                    out,
+                    "glDrawElementsInstancedBaseVertex({}, {}, {}, {}, {}, {})",
+                    primitive(prim),
+                    index_count,
+                    data_type(index_type),
+                    index_buffer_offset,
+                    instances.end,
+                    base_vertex
+                )
+            } else {
+                write!(
+                    out,
+                    "glDrawElementsInstancedBaseVertexBaseInstance({}, {}, {}, {}, {}, {}, {})",
+                    primitive(prim),
+                    index_count,
+                    data_type(index_type),
+                    index_buffer_offset,
+                    instances.end - instances.start,
+                    base_vertex,
+                    instances.start
+                )
+            }
+        }
+        Command::BindIndexBuffer(buffer) => {
+            write!(out, "glBindBuffer(GL_ELEMENT_ARRAY_BUFFER, {:?})", buffer)
+        }
+        Command::BindUniform {
+            ref uniform,
+            buffer,
+        } => self::uniform(out, uniform, data, buffer),
+        Command::BindRasterizer {
+            rasterizer: ref state,
+        } => rasterizer(out, state),
+        Command::BindDepth(Some(cmp)) => write!(
+            out,
+            "glEnable(GL_DEPTH_TEST); glDepthFunc({})",
+            comparison(cmp)
+        ),
+        Command::BindDepth(None) => write!(out, "glDisable(GL_DEPTH_TEST)"),
+        Command::SetViewports {
+            first_viewport,
+            viewport_ptr,
+            depth_range_ptr,
+        } => {
+            let viewports = f32s(data, viewport_ptr);
+            let depth_ranges = f64s(data, depth_range_ptr);
+            if viewports.len() == 4 && depth_ranges.len() == 2 {
+                write!(
+                    out,
+                    "glViewport({}, {}, {}, {}); glDepthRange({}, {})",
+                    viewports[0],
+                    viewports[1],
+                    viewports[2],
+                    viewports[3],
+                    depth_ranges[0],
+                    depth_ranges[1]
+                )
+            } else {
+                write!(
+                    out,
+                    "glViewportArrayv({}, {}, {:?}); glDepthRangeArrayv({}, {}, {:?})",
+                    first_viewport,
+                    viewports.len() / 4,
+                    viewports,
+                    first_viewport,
+                    depth_ranges.len() / 2,
+                    depth_ranges
+                )
+            }
+        }
+        Command::SetScissors(first_scissor, ptr) => {
+            let scissors = i32s(data, ptr);
+            if scissors.len() == 4 {
+                write!(
+                    out,
+                    "glScissor({}, {}, {}, {})",
+                    scissors[0], scissors[1], scissors[2], scissors[3]
+                )
+            } else {
+                write!(
+                    out,
+                    "glScissorArrayv({}, {}, {:?})",
+                    first_scissor,
+                    scissors.len() / 4,
+                    scissors
+                )
+            }
+        }
+        Command::SetBlendColor(color) => write!(
+            out,
+            "glBlendColor({}, {}, {}, {})",
+            color[0], color[1], color[2], color[3]
+        ),
+        Command::ClearBufferColorF(draw_buffer, cv) => {
+            write!(out, "glClearBufferfv(GL_COLOR, {}, {:?})", draw_buffer, cv)
+        }
+        Command::ClearBufferColorU(draw_buffer, cv) => {
+            write!(out, "glClearBufferuiv(GL_COLOR, {}, {:?})", draw_buffer, cv)
+        }
+        Command::ClearBufferColorI(draw_buffer, cv) => {
+            write!(out, "glClearBufferiv(GL_COLOR, {}, {:?})", draw_buffer, cv)
+        }
+        Command::ClearBufferDepthStencil(depth, stencil) => match (depth, stencil) {
+            (Some(depth), Some(stencil)) => write!(
+                out,
+                "glClearBufferfi(GL_DEPTH_STENCIL, 0, {}, {})",
+                depth, stencil
+            ),
+            (Some(depth), None) => write!(out, "glClearBufferfv(GL_DEPTH, 0, [{}])", depth),
+            (None, Some(stencil)) => write!(out, "glClearBufferiv(GL_STENCIL, 0, [{}])", stencil),
+            (None, None) => write!(out, "<empty depth-stencil clear>"),
+        },
+        Command::ClearTexture(color) => write!(out, "glClearTexImage(<bound>, {:?})", color),
+        Command::DrawBuffers(ptr) => {
+            let buffers = u32s(data, ptr)
+                .into_iter()
+                .map(attachment)
+                .collect::<Vec<_>>();
+            write!(
+                out,
+                "glDrawBuffers({}, [{}])",
+                buffers.len(),
+                buffers.join(", ")
+            )
+        }
+        Command::BindFrameBuffer(target, framebuffer) => write!(
+            out,
+            "glBindFramebuffer({}, {:?})",
+            framebuffer_target(target),
+            framebuffer
+        ),
+        Command::BindTargetView(target, point, view) => match view {
+            n::ImageView::Renderbuffer(renderbuffer) => write!(
+                out,
+                "glFramebufferRenderbuffer({}, {}, GL_RENDERBUFFER, {:?})",
+                framebuffer_target(target),
+                attachment(point),
+                renderbuffer
+            ),
+            n::ImageView::Texture(texture, _, level) => write!(
+                out,
+                "glFramebufferTexture({}, {}, {:?}, {})",
+                framebuffer_target(target),
+                attachment(point),
+                texture,
+                level
+            ),
+            n::ImageView::TextureLayer(texture, _, level, layer) => write!(
+                out,
+                "glFramebufferTextureLayer({}, {}, {:?}, {}, {})",
+                framebuffer_target(target),
+                attachment(point),
+                texture,
+                level,
+                layer
+            ),
+        },
+        Command::SetDrawColorBuffers(num) => {
+            let buffers = (0 .. num as u32)
+                .map(|i| attachment(glow::COLOR_ATTACHMENT0 + i))
+                .collect::<Vec<_>>();
+            write!(out, "glDrawBuffers({}, [{}])", num, buffers.join(", "))
+        }
+        Command::SetPatchSize(num) => write!(out, "glPatchParameteri(GL_PATCH_VERTICES, {})", num),
+        Command::BindProgram(program) => write!(out, "glUseProgram({:?})", program),
+        Command::SetBlend(ref state) => blend(out, None, state),
+        Command::SetBlendSlot(slot, ref state) => blend(out, Some(slot as u32), state),
+        Command::BindAttribute(ref attribute, buffer, stride, rate) => {
+            use crate::native::VertexAttribFunction::*;
+
+            let function = match attribute.vertex_attrib_fn {
+                Float => "glVertexAttribPointer",
+                Integer => "glVertexAttribIPointer",
+                Double => "glVertexAttribLPointer",
+            };
+            write!(
+                out,
+                "glBindBuffer(GL_ARRAY_BUFFER, {:?}); {}({}, {}, {}, {}, {}); \
+                 glVertexAttribDivisor({}, {}); glEnableVertexAttribArray({})",
+                buffer,
+                function,
+                attribute.location,
+                attribute.size,
+                data_type(attribute.format),
+                stride,
+                attribute.offset,
+                attribute.location,
+                rate,
+                attribute.location
+            )
+        }
+        Command::CopyBufferToBuffer(src, dst, ref region) => write!(
+            out,
+            "glCopyBufferSubData({:?}, {:?}, {}, {}, {})",
+            src, dst, region.src, region.dst, region.size
+        ),
+        Command::CopyBufferToTexture {
+            src_buffer,
+            dst_texture,
+            texture_target: target,
+            texture_format,
+            pixel_type,
+            ref data,
+        } => write!(
+            out,
+            "glBindBuffer(GL_PIXEL_UNPACK_BUFFER, {:?}); glBindTexture({}, {:?}); \
+             glTexSubImage({}, {}, {:?}, {:?}, {:?}, 0x{:04X}, {}, {})",
+            src_buffer,
+            texture_target(target),
+            dst_texture,
+            texture_target(target),
+            data.image_layers.level,
+            data.image_offset,
+            data.image_extent,
+            data.image_layers.layers,
+            texture_format,
+            data_type(pixel_type),
+            data.buffer_offset
+        ),
+        Command::CopyBufferToRenderbuffer(buffer, renderbuffer, ref data) => write!(
+            out,
+            "<copy buffer {:?} at {} to renderbuffer {:?}>",
+            buffer, data.buffer_offset, renderbuffer
+        ),
+        Command::CopyTextureToBuffer {
+            src_texture,
+            texture_target: target,
+            texture_format,
+            pixel_type,
+            dst_buffer,
+            ref data,
+        } => write!(
+            out,
+            "glBindBuffer(GL_PIXEL_PACK_BUFFER, {:?}); glBindTexture({}, {:?}); \
+             glGetTexImage({}, {}, 0x{:04X}, {}, {})",
+            dst_buffer,
+            texture_target(target),
+            src_texture,
+            texture_target(target),
+            data.image_layers.level,
+            texture_format,
+            data_type(pixel_type),
+            data.buffer_offset
+        ),
+        Command::CopyRenderbufferToBuffer(renderbuffer, buffer, ref data) => write!(
+            out,
+            "<copy renderbuffer {:?} to buffer {:?} at {}>",
+            renderbuffer, buffer, data.buffer_offset
+        ),
+        Command::CopyImageToTexture(ref src, texture, _, ref data) => write!(
+            out,
+            "<copy image {:?} to texture {:?}: {:?}>",
+            src, texture, data
+        ),
+        Command::CopyImageToRenderbuffer {
+            ref src_image,
+            dst_renderbuffer,
+            ref data,
+            ..
+        } => write!(
+            out,
+            "glBlitFramebuffer(<{:?}> -> <renderbuffer {:?}>, {:?}, {:?}, {:?})",
+            src_image, dst_renderbuffer, data.src_offset, data.dst_offset, data.extent
+        ),
+        Command::BindBufferRange(target, index, buffer, offset, size) => write!(
+            out,
+            "glBindBufferRange({}, {}, {:?}, {}, {})",
+            buffer_target(target),
+            index,
+            buffer,
+            offset,
+            size
+        ),
+        Command::BindTexture(unit, texture, target) => write!(
+            out,
+            "glActiveTexture(GL_TEXTURE{}); glBindTexture({}, {:?})",
+            unit,
+            texture_target(target),
+            texture
+        ),
+        Command::BindSampler(unit, sampler) => {
+            write!(out, "glBindSampler({}, {:?})", unit, sampler)
+        }
+        Command::SetTextureSamplerSettings(unit, texture, target, ref info) => write!(
+            out,
+            "glActiveTexture(GL_TEXTURE{}); glBindTexture({}, {:?}); glTexParameter({:?})",
+            unit,
+            texture_target(target),
+            texture,
+            info
+        ),
+        Command::SetColorMask(Some(slot), mask) => {
+            write!(out, "glColorMaski({}, {})", slot, color_mask(mask))
+        }
+        Command::SetColorMask(None, mask) => write!(out, "glColorMask({})", color_mask(mask)),
+        Command::SetDepthMask(write) => write!(out, "glDepthMask({})", write),
+        Command::SetStencilMask(value) => write!(out, "glStencilMask(0x{:X})", value),
+        Command::SetStencilMaskSeparate(values) => write!(
+            out,
+            "glStencilMaskSeparate(GL_FRONT, 0x{:X}); glStencilMaskSeparate(GL_BACK, 0x{:X})",
+            values.front, values.back
+        ),
+    }
+}
+
+/// Print the commands as GL calls, one line per command.
+pub(crate) fn dump(commands: &[Command], data: &[u8]) -> String {
+    let mut out = String::new();
+    for (i, cmd) in commands.iter().enumerate() {
+        write!(out, "{:4}: ", i).unwrap();
+        command(&mut out, cmd, data).unwrap();
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Starc;
+    use hal::{command as com, format::Aspects, image};
+
+    fn add<I: IntoIterator<Item = [u8; N]>, const N: usize>(
+        data: &mut Vec<u8>,
+        values: I,
+    ) -> BufferSlice {
+        let offset = data.len() as u32;
+        for bytes in values {
+            data.extend_from_slice(&bytes);
+        }
+        BufferSlice {
+            offset,
+            size: data.len() as u32 - offset,
+        }
+    }
+
+    #[test]
+    fn test_dump_commands() {
+        let mut data = Vec::new();
+        let viewport_ptr = add(
+            &mut data,
+            [0.0f32, 0.0, 640.0, 480.0].iter().map(|v| v.to_ne_bytes()),
+        );
+        let depth_range_ptr = add(&mut data, [0.0f64, 1.0].iter().map(|v| v.to_ne_bytes()));
+        let scissors = add(
+            &mut data,
+            [0i32, 0, 320, 240, 320, 240, 320, 240]
+                .iter()
+                .map(|v| v.to_ne_bytes()),
+        );
+        let draw_buffers = add(
+            &mut data,
+            [glow::COLOR_ATTACHMENT0, glow::NONE, glow::COLOR_ATTACHMENT2]
+                .iter()
+                .map(|v| v.to_ne_bytes()),
+        );
+
+        let commands = [
+            Command::SetViewports {
+                first_viewport: 0,
+                viewport_ptr,
+                depth_range_ptr,
+            },
+            Command::SetScissors(0, scissors),
+            Command::DrawBuffers(draw_buffers),
+            Command::SetColorMask(Some(1), pso::ColorMask::RED | pso::ColorMask::ALPHA),
+            Command::SetBlend(Some(pso::BlendState::ALPHA)),
+            Command::DrawIndexed {
+                primitive: glow::TRIANGLES,
+                index_type: glow::UNSIGNED_SHORT,
+                index_count: 6,
+                index_buffer_offset: 0,
+                base_vertex: 0,
+                instances: 0 .. 4,
+            },
+        ];
+
+        let dump = dump(&commands, &data);
+        assert_eq!(
+            dump.lines().collect::<Vec<_>>(),
+            [
+                "   0: glViewport(0, 0, 640, 480); glDepthRange(0, 1)",
+                "   1: glScissorArrayv(0, 2, [0, 0, 320, 240, 320, 240, 320, 240])",
+                "   2: glDrawBuffers(3, [GL_COLOR_ATTACHMENT0, GL_NONE, GL_COLOR_ATTACHMENT2])",
+                "   3: glColorMaski(1, true, false, false, true)",
+                "   4: glEnable(GL_BLEND); glBlendEquationSeparate(GL_FUNC_ADD, GL_FUNC_ADD); \
+                 glBlendFuncSeparate(GL_SRC_ALPHA, GL_ONE_MINUS_SRC_ALPHA, GL_ONE, GL_ONE_MINUS_SRC_ALPHA)",
+                "   5: glDrawElementsInstanced(GL_TRIANGLES, 6, GL_UNSIGNED_SHORT, 0, 4)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dump_draws() {
+        let commands = [
+            Command::Draw {
+                primitive: glow::TRIANGLES,
+                vertices: 0 .. 3,
+                instances: 0 .. 1,
+            },
+            Command::Draw {
+                primitive: glow::TRIANGLE_STRIP,
+                vertices: 2 .. 6,
+                instances: 0 .. 2,
+            },
+            Command::Draw {
+                primitive: glow::POINTS,
+                vertices: 0 .. 1,
+                instances: 1 .. 3,
+            },
+            Command::DrawIndexed {
+                primitive: glow::LINES,
+                index_type: glow::UNSIGNED_INT,
+                index_count: 12,
+                index_buffer_offset: 8,
+                base_vertex: 0,
+                instances: 0 .. 1,
+            },
+            Command::DrawIndexed {
+                primitive: glow::LINES,
+                index_type: glow::UNSIGNED_INT,
+                index_count: 12,
+                index_buffer_offset: 8,
+                base_vertex: 5,
+                instances: 0 .. 1,
+            },
+            Command::DrawIndexed {
+                primitive: glow::TRIANGLES,
+                index_type: glow::UNSIGNED_SHORT,
+                index_count: 3,
+                index_buffer_offset: 0,
+                base_vertex: -2,
+                instances: 0 .. 3,
+            },
+            Command::DrawIndexed {
+                primitive: glow::PATCHES,
+                index_type: glow::UNSIGNED_BYTE,
+                index_count: 3,
+                index_buffer_offset: 4,
+                base_vertex: 1,
+                instances: 2 .. 5,
+            },
+            Command::Dispatch([8, 4, 1]),
+            Command::DispatchIndirect(7, 16),
+        ];
+
+        let dump = dump(&commands, &[]);
+        assert_eq!(
+            dump.lines().collect::<Vec<_>>(),
+            [
+                "   0: glDrawArrays(GL_TRIANGLES, 0, 3)",
+                "   1: glDrawArraysInstanced(GL_TRIANGLE_STRIP, 2, 4, 2)",
+                "   2: glDrawArraysInstancedBaseInstance(GL_POINTS, 0, 1, 2, 1)",
+                "   3: glDrawElements(GL_LINES, 12, GL_UNSIGNED_INT, 8)",
+                "   4: glDrawElementsBaseVertex(GL_LINES, 12, GL_UNSIGNED_INT, 8, 5)",
+                "   5: glDrawElementsInstancedBaseVertex(GL_TRIANGLES, 3, GL_UNSIGNED_SHORT, 0, 3, -2)",
+                "   6: glDrawElementsInstancedBaseVertexBaseInstance(\
+                 GL_PATCHES, 3, GL_UNSIGNED_BYTE, 4, 3, 1, 2)",
+                "   7: glDispatchCompute(8, 4, 1)",
+                "   8: glBindBuffer(GL_DISPATCH_INDIRECT_BUFFER, 7); glDispatchComputeIndirect(16)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dump_copies() {
+        let region = com::BufferImageCopy {
+            buffer_offset: 256,
+            buffer_width: 0,
+            buffer_height: 0,
+            image_layers: image::SubresourceLayers {
+                aspects: Aspects::COLOR,
+                level: 1,
+                layers: 0 .. 1,
+            },
+            image_offset: image::Offset { x: 0, y: 0, z: 0 },
+            image_extent: image::Extent {
+                width: 4,
+                height: 4,
+                depth: 1,
+            },
+        };
+        let commands = [
+            Command::CopyBufferToBuffer(
+                1,
+                2,
+                com::BufferCopy {
+                    src: 0,
+                    dst: 16,
+                    size: 64,
+                },
+            ),
+            Command::CopyBufferToTexture {
+                src_buffer: 1,
+                dst_texture: 3,
+                texture_target: glow::TEXTURE_2D,
+                texture_format: glow::RGBA,
+                pixel_type: glow::UNSIGNED_BYTE,
+                data: region.clone(),
+            },
+            Command::CopyTextureToBuffer {
+                src_texture: 3,
+                texture_target: glow::TEXTURE_2D,
+                texture_format: glow::RGBA,
+                pixel_type: glow::UNSIGNED_BYTE,
+                dst_buffer: 2,
+                data: region.clone(),
+            },
+            Command::CopyBufferToRenderbuffer(1, 4, region.clone()),
+            Command::CopyRenderbufferToBuffer(4, 2, region),
+            Command::CopyImageToRenderbuffer {
+                src_image: n::ImageKind::Renderbuffer {
+                    renderbuffer: 5,
+                    format: glow::RGBA8,
+                },
+                dst_renderbuffer: 4,
+                dst_format: glow::RGBA8,
+                data: com::ImageCopy {
+                    src_subresource: image::SubresourceLayers {
+                        aspects: Aspects::COLOR,
+                        level: 0,
+                        layers: 0 .. 1,
+                    },
+                    src_offset: image::Offset { x: 1, y: 2, z: 0 },
+                    dst_subresource: image::SubresourceLayers {
+                        aspects: Aspects::COLOR,
+                        level: 0,
+                        layers: 0 .. 1,
+                    },
+                    dst_offset: image::Offset { x: 0, y: 0, z: 0 },
+                    extent: image::Extent {
+                        width: 2,
+                        height: 2,
+                        depth: 1,
+                    },
+                },
+            },
+        ];
+
+        let dump = dump(&commands, &[]);
+        assert_eq!(
+            dump.lines().collect::<Vec<_>>(),
+            [
+                "   0: glCopyBufferSubData(1, 2, 0, 16, 64)",
+                "   1: glBindBuffer(GL_PIXEL_UNPACK_BUFFER, 1); glBindTexture(GL_TEXTURE_2D, 3); \
+                 glTexSubImage(GL_TEXTURE_2D, 1, Offset { x: 0, y: 0, z: 0 }, \
+                 Extent { width: 4, height: 4, depth: 1 }, 0..1, 0x1908, GL_UNSIGNED_BYTE, 256)",
+                "   2: glBindBuffer(GL_PIXEL_PACK_BUFFER, 2); glBindTexture(GL_TEXTURE_2D, 3); \
+                 glGetTexImage(GL_TEXTURE_2D, 1, 0x1908, GL_UNSIGNED_BYTE, 256)",
+                "   3: <copy buffer 1 at 256 to renderbuffer 4>",
+                "   4: <copy renderbuffer 4 to buffer 2 at 256>",
+                "   5: glBlitFramebuffer(<Renderbuffer { renderbuffer: 5, format: 32856 }> -> \
+                 <renderbuffer 4>, Offset { x: 1, y: 2, z: 0 }, Offset { x: 0, y: 0, z: 0 }, \
+                 Extent { width: 2, height: 2, depth: 1 })",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dump_binds() {
+        let mut rasterizer = pso::Rasterizer::FILL;
+        rasterizer.cull_face = pso::Face::BACK;
+        rasterizer.depth_bias = Some(pso::State::Static(pso::DepthBias {
+            const_factor: 1.0,
+            clamp: 0.0,
+            slope_factor: 2.0,
+        }));
+        let commands = [
+            Command::BindIndexBuffer(3),
+            Command::BindFrameBuffer(glow::DRAW_FRAMEBUFFER, Some(2)),
+            Command::BindFrameBuffer(glow::FRAMEBUFFER, None),
+            Command::BindTargetView(
+                glow::DRAW_FRAMEBUFFER,
+                glow::COLOR_ATTACHMENT1,
+                n::ImageView::Texture(3, glow::TEXTURE_2D, 0),
+            ),
+            Command::BindTargetView(
+                glow::FRAMEBUFFER,
+                glow::DEPTH_STENCIL_ATTACHMENT,
+                n::ImageView::Renderbuffer(4),
+            ),
+            Command::BindTargetView(
+                glow::READ_FRAMEBUFFER,
+                glow::COLOR_ATTACHMENT0,
+                n::ImageView::TextureLayer(3, glow::TEXTURE_2D_ARRAY, 1, 2),
+            ),
+            Command::BindProgram(6),
+            Command::BindBufferRange(glow::UNIFORM_BUFFER, 1, 2, 0, 256),
+            Command::BindTexture(2, 3, glow::TEXTURE_CUBE_MAP),
+            Command::BindSampler(2, 9),
+            Command::BindDepth(Some(pso::Comparison::LessEqual)),
+            Command::BindDepth(None),
+            Command::BindRasterizer {
+                rasterizer: pso::Rasterizer::FILL,
+            },
+            Command::BindRasterizer { rasterizer },
+        ];
+
+        let dump = dump(&commands, &[]);
+        assert_eq!(
+            dump.lines().collect::<Vec<_>>(),
+            [
+                "   0: glBindBuffer(GL_ELEMENT_ARRAY_BUFFER, 3)",
+                "   1: glBindFramebuffer(GL_DRAW_FRAMEBUFFER, Some(2))",
+                "   2: glBindFramebuffer(GL_FRAMEBUFFER, None)",
+                "   3: glFramebufferTexture(GL_DRAW_FRAMEBUFFER, GL_COLOR_ATTACHMENT1, 3, 0)",
+                "   4: glFramebufferRenderbuffer(\
+                 GL_FRAMEBUFFER, GL_DEPTH_STENCIL_ATTACHMENT, GL_RENDERBUFFER, 4)",
+                "   5: glFramebufferTextureLayer(GL_READ_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, 3, 1, 2)",
+                "   6: glUseProgram(6)",
+                "   7: glBindBufferRange(GL_UNIFORM_BUFFER, 1, 2, 0, 256)",
+                "   8: glActiveTexture(GL_TEXTURE2); glBindTexture(GL_TEXTURE_CUBE_MAP, 3)",
+                "   9: glBindSampler(2, 9)",
+                "  10: glEnable(GL_DEPTH_TEST); glDepthFunc(GL_LEQUAL)",
+                "  11: glDisable(GL_DEPTH_TEST)",
+                "  12: glFrontFace(GL_CCW); glDisable(GL_CULL_FACE); glLineWidth(1); \
+                 glPolygonMode(GL_FRONT_AND_BACK, GL_FILL); glDisable(GL_POLYGON_OFFSET_FILL)",
+                "  13: glFrontFace(GL_CCW); glEnable(GL_CULL_FACE); glCullFace(GL_BACK); \
+                 glLineWidth(1); glPolygonMode(GL_FRONT_AND_BACK, GL_FILL); \
+                 glEnable(GL_POLYGON_OFFSET_FILL); glPolygonOffset(2, 1)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dump_uniforms_and_attributes() {
+        let mut data = Vec::new();
+        let floats = add(&mut data, [1.0f32, 2.0].iter().map(|v| v.to_ne_bytes()));
+        let ints = add(&mut data, [3i32, -4].iter().map(|v| v.to_ne_bytes()));
+        let uniform = |utype, buffer| Command::BindUniform {
+            uniform: n::UniformDesc {
+                location: Starc::new(0),
+                offset: 0,
+                utype,
+            },
+            buffer,
+        };
+        let attribute = |location, format, vertex_attrib_fn| n::AttributeDesc {
+            location,
+            offset: 8,
+            binding: 0,
+            size: 3,
+            format,
+            vertex_attrib_fn,
+        };
+        let commands = [
+            uniform(glow::FLOAT_VEC2, floats),
+            uniform(glow::INT_VEC2, ints),
+            uniform(glow::SAMPLER_2D, ints),
+            Command::BindAttribute(
+                attribute(1, glow::FLOAT, n::VertexAttribFunction::Float),
+                2,
+                20,
+                0,
+            ),
+            Command::BindAttribute(
+                attribute(2, glow::INT, n::VertexAttribFunction::Integer),
+                2,
+                20,
+                1,
+            ),
+            Command::SetBlendColor([0.5, 0.0, 0.0, 1.0]),
+            Command::SetBlendSlot(1, None),
+            Command::SetColorMask(None, pso::ColorMask::ALL),
+            Command::SetDrawColorBuffers(2),
+            Command::SetPatchSize(3),
+            Command::SetDepthMask(false),
+            Command::SetStencilMask(0xFF),
+            Command::SetStencilMaskSeparate(pso::Sided {
+                front: 0xF,
+                back: 0xF0,
+            }),
+        ];
+
+        let dump = dump(&commands, &data);
+        let lines = dump.lines().collect::<Vec<_>>();
+        // Uniform locations are printed with their address.
+        assert!(lines[0].starts_with("   0: glUniform2fv("));
+        assert!(lines[0].ends_with(", [1.0, 2.0])"));
+        assert!(lines[1].starts_with("   1: glUniform2iv("));
+        assert!(lines[1].ends_with(", [3, -4])"));
+        assert!(lines[2].starts_with("   2: glUniform("));
+        assert!(lines[2].ends_with(", <unsupported type 0x8B5E>)"));
+        assert_eq!(
+            lines[3 ..],
+            [
+                "   3: glBindBuffer(GL_ARRAY_BUFFER, 2); glVertexAttribPointer(1, 3, GL_FLOAT, 20, 8); \
+                 glVertexAttribDivisor(1, 0); glEnableVertexAttribArray(1)",
+                "   4: glBindBuffer(GL_ARRAY_BUFFER, 2); glVertexAttribIPointer(2, 3, GL_INT, 20, 8); \
+                 glVertexAttribDivisor(2, 1); glEnableVertexAttribArray(2)",
+                "   5: glBlendColor(0.5, 0, 0, 1)",
+                "   6: glDisablei(1, GL_BLEND)",
+                "   7: glColorMask(true, true, true, true)",
+                "   8: glDrawBuffers(2, [GL_COLOR_ATTACHMENT0, GL_COLOR_ATTACHMENT1])",
+                "   9: glPatchParameteri(GL_PATCH_VERTICES, 3)",
+                "  10: glDepthMask(false)",
+                "  11: glStencilMask(0xFF)",
+                "  12: glStencilMaskSeparate(GL_FRONT, 0xF); glStencilMaskSeparate(GL_BACK, 0xF0)",
+            ]
+        );
+    }
+}
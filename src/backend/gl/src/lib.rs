@@ -22,12 +22,15 @@ use hal::{adapter, buffer, image, memory, pso, queue as q};
 
 pub use self::device::Device;
 pub use self::info::{Info, PlatformName, Version};
+pub use self::optimize::OptimizerStats;
 
 mod command;
 mod conv;
 mod device;
+mod dump;
 mod info;
 mod native;
+mod optimize;
 mod pool;
 mod queue;
 mod state;
@@ -304,7 +304,7 @@ pub struct PipelineLayout {
 // No inter-queue synchronization required for GL.
 pub struct Semaphore;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct AttributeDesc {
     pub(crate) location: u32,
     pub(crate) offset: u32,
@@ -321,7 +321,7 @@ pub struct UniformDesc {
     pub(crate) utype: u32,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum VertexAttribFunction {
     Float,   // glVertexAttribPointer
     Integer, // glVertexAttribIPointer
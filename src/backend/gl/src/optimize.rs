@@ -0,0 +1,388 @@
+//! Redundant state elimination for recorded command lists.
+//!
+//! Command buffers are recorded without knowing the state left behind by
+//! previously recorded commands, so the same program, textures or blend
+//! state are often set again and again. The optimizer runs when a command
+//! buffer is finished and removes:
+//!  - commands setting a state to the value it already has,
+//!  - commands whose state is overwritten before any command could observe it.
+//!
+//! The state at the start of a command buffer is unknown, so the first
+//! command setting a given state is always kept.
+
+use crate::command::{Command, DrawBuffer};
+use crate::{native as n, ColorSlot};
+
+use auxil::FastHashMap;
+use hal::pso;
+
+use std::hash::Hash;
+use std::ops::AddAssign;
+
+/// Number of commands removed by the optimizer.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct OptimizerStats {
+    /// Commands setting a state to the value it already had.
+    pub redundant: usize,
+    /// Commands whose state was overwritten before being used.
+    pub overwritten: usize,
+}
+
+impl OptimizerStats {
+    /// Total number of removed commands.
+    pub fn removed(&self) -> usize {
+        self.redundant + self.overwritten
+    }
+}
+
+impl AddAssign for OptimizerStats {
+    fn add_assign(&mut self, other: Self) {
+        self.redundant += other.redundant;
+        self.overwritten += other.overwritten;
+    }
+}
+
+// State which can be set for all color slots at once, or for a single one.
+#[derive(Debug)]
+struct Slotted<K, T> {
+    all: Option<T>,
+    slots: FastHashMap<K, T>,
+}
+
+impl<K: Eq + Hash, T: Copy + PartialEq> Slotted<K, T> {
+    fn new() -> Self {
+        Slotted {
+            all: None,
+            slots: FastHashMap::default(),
+        }
+    }
+
+    // Returns `false` if the state already had this value.
+    fn set(&mut self, slot: Option<K>, value: T) -> bool {
+        match slot {
+            None => {
+                let same =
+                    self.all == Some(value) && self.slots.values().all(|&other| other == value);
+                self.all = Some(value);
+                self.slots.clear();
+                !same
+            }
+            Some(slot) => {
+                let current = self.slots.get(&slot).cloned().or(self.all);
+                self.slots.insert(slot, value);
+                current != Some(value)
+            }
+        }
+    }
+}
+
+// Tracked GL state, `None` or missing entries are unknown.
+#[derive(Debug)]
+struct State {
+    program: Option<n::Program>,
+    textures: FastHashMap<(u32, n::TextureTarget), n::Texture>,
+    samplers: FastHashMap<u32, n::Sampler>,
+    blend: Slotted<ColorSlot, Option<pso::BlendState>>,
+    color_mask: Slotted<DrawBuffer, pso::ColorMask>,
+    attributes: FastHashMap<u32, (n::AttributeDesc, n::RawBuffer, i32, u32)>,
+}
+
+impl State {
+    fn new() -> Self {
+        State {
+            program: None,
+            textures: FastHashMap::default(),
+            samplers: FastHashMap::default(),
+            blend: Slotted::new(),
+            color_mask: Slotted::new(),
+            attributes: FastHashMap::default(),
+        }
+    }
+
+    // Apply the command, returning `false` if it doesn't change the state.
+    fn apply(&mut self, cmd: &Command) -> bool {
+        match *cmd {
+            Command::BindProgram(program) => self.program.replace(program) != Some(program),
+            Command::BindTexture(unit, texture, target) => {
+                self.textures.insert((unit, target), texture) != Some(texture)
+            }
+            Command::BindSampler(unit, sampler) => {
+                self.samplers.insert(unit, sampler) != Some(sampler)
+            }
+            Command::SetBlend(blend) => self.blend.set(None, blend),
+            Command::SetBlendSlot(slot, blend) => self.blend.set(Some(slot), blend),
+            Command::SetColorMask(slot, mask) => self.color_mask.set(slot, mask),
+            Command::BindAttribute(ref desc, buffer, stride, rate) => {
+                let value = (desc.clone(), buffer, stride, rate);
+                self.attributes.insert(desc.location, value.clone()) != Some(value)
+            }
+            Command::SetTextureSamplerSettings(unit, texture, target, _) => {
+                self.textures.insert((unit, target), texture);
+                true
+            }
+            // Copies bind the texture to the first unit.
+            Command::CopyBufferToTexture { .. } | Command::CopyTextureToBuffer { .. } => {
+                self.textures.retain(|&(unit, _), _| unit != 0);
+                true
+            }
+            _ => true,
+        }
+    }
+}
+
+// State set by a command which can be removed.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum Key {
+    Program,
+    Texture(u32, n::TextureTarget),
+    Sampler(u32),
+    Blend(Option<ColorSlot>),
+    ColorMask(Option<DrawBuffer>),
+    Attribute(u32),
+}
+
+impl Key {
+    fn of(cmd: &Command) -> Option<Self> {
+        Some(match *cmd {
+            Command::BindProgram(_) => Key::Program,
+            Command::BindTexture(unit, _, target) => Key::Texture(unit, target),
+            Command::BindSampler(unit, _) => Key::Sampler(unit),
+            Command::SetBlend(_) => Key::Blend(None),
+            Command::SetBlendSlot(slot, _) => Key::Blend(Some(slot)),
+            Command::SetColorMask(slot, _) => Key::ColorMask(slot),
+            Command::BindAttribute(ref desc, ..) => Key::Attribute(desc.location),
+            _ => return None,
+        })
+    }
+
+    // Whether setting this state overwrites the state set with `other`.
+    fn overwrites(&self, other: &Key) -> bool {
+        match (*self, *other) {
+            (Key::Blend(None), Key::Blend(_)) | (Key::ColorMask(None), Key::ColorMask(_)) => true,
+            (a, b) => a == b,
+        }
+    }
+}
+
+/// Remove redundant state changes from a list of recorded commands.
+pub(crate) fn optimize(commands: &mut Vec<Command>) -> OptimizerStats {
+    let mut stats = OptimizerStats::default();
+
+    // Commands setting state that is overwritten before any other command
+    // could observe it.
+    let mut keep = vec![true; commands.len()];
+    let mut pending = Vec::<(Key, usize)>::new();
+    for (i, cmd) in commands.iter().enumerate() {
+        match Key::of(cmd) {
+            Some(key) => {
+                pending.retain(|&(other, index)| {
+                    let overwritten = key.overwrites(&other);
+                    if overwritten {
+                        keep[index] = false;
+                        stats.overwritten += 1;
+                    }
+                    !overwritten
+                });
+                pending.push((key, i));
+            }
+            None => pending.clear(),
+        }
+    }
+    let mut keep = keep.into_iter();
+    commands.retain(|_| keep.next().unwrap());
+
+    // Commands setting state to the value it already has.
+    let mut state = State::new();
+    commands.retain(|cmd| {
+        let changed = state.apply(cmd);
+        if !changed {
+            stats.redundant += 1;
+        }
+        changed
+    });
+
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::BufferSlice;
+    use hal::pso::{BlendState, ColorMask};
+
+    fn attribute(location: u32) -> n::AttributeDesc {
+        n::AttributeDesc {
+            location,
+            offset: 0,
+            binding: 0,
+            size: 4,
+            format: glow::FLOAT,
+            vertex_attrib_fn: n::VertexAttribFunction::Float,
+        }
+    }
+
+    fn draw() -> Command {
+        Command::Draw {
+            primitive: glow::TRIANGLES,
+            vertices: 0 .. 3,
+            instances: 0 .. 1,
+        }
+    }
+
+    fn names(commands: &[Command]) -> Vec<&'static str> {
+        commands
+            .iter()
+            .map(|cmd| match *cmd {
+                Command::BindProgram(_) => "program",
+                Command::BindTexture(..) => "texture",
+                Command::BindSampler(..) => "sampler",
+                Command::SetBlend(_) => "blend",
+                Command::SetBlendSlot(..) => "blend_slot",
+                Command::SetColorMask(..) => "color_mask",
+                Command::BindAttribute(..) => "attribute",
+                Command::Draw { .. } => "draw",
+                _ => "other",
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_redundant() {
+        let mut commands = vec![
+            Command::BindProgram(1),
+            Command::BindTexture(0, 2, glow::TEXTURE_2D),
+            Command::BindSampler(0, 3),
+            Command::BindAttribute(attribute(0), 4, 16, 0),
+            draw(),
+            Command::BindProgram(1),
+            Command::BindTexture(0, 2, glow::TEXTURE_2D),
+            Command::BindTexture(0, 2, glow::TEXTURE_2D_ARRAY),
+            Command::BindSampler(0, 3),
+            Command::BindAttribute(attribute(0), 4, 16, 0),
+            draw(),
+        ];
+        let stats = optimize(&mut commands);
+        assert_eq!(
+            stats,
+            OptimizerStats {
+                redundant: 4,
+                overwritten: 0,
+            }
+        );
+        assert_eq!(
+            names(&commands),
+            [
+                "program",
+                "texture",
+                "sampler",
+                "attribute",
+                "draw",
+                "texture",
+                "draw",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_overwritten() {
+        let mut commands = vec![
+            Command::BindProgram(1),
+            Command::SetBlendSlot(1, None),
+            Command::SetColorMask(Some(1), ColorMask::empty()),
+            Command::BindProgram(2),
+            Command::SetBlend(Some(BlendState::ADD)),
+            Command::SetColorMask(None, ColorMask::ALL),
+            Command::SetBlendSlot(0, None),
+            draw(),
+        ];
+        let stats = optimize(&mut commands);
+        assert_eq!(
+            stats,
+            OptimizerStats {
+                redundant: 0,
+                overwritten: 3,
+            }
+        );
+        assert_eq!(
+            names(&commands),
+            ["program", "blend", "color_mask", "blend_slot", "draw"]
+        );
+    }
+
+    #[test]
+    fn test_slots() {
+        let mut commands = vec![
+            Command::SetColorMask(None, ColorMask::ALL),
+            draw(),
+            // Same as the mask of all the slots.
+            Command::SetColorMask(Some(0), ColorMask::ALL),
+            draw(),
+            Command::SetColorMask(Some(1), ColorMask::RED),
+            draw(),
+            // Slot 1 differs, so resetting all slots is needed.
+            Command::SetColorMask(None, ColorMask::ALL),
+            draw(),
+            Command::SetBlend(None),
+            draw(),
+            Command::SetBlendSlot(2, None),
+            draw(),
+        ];
+        let stats = optimize(&mut commands);
+        assert_eq!(stats.removed(), 2);
+        assert_eq!(
+            names(&commands),
+            [
+                "color_mask",
+                "draw",
+                "draw",
+                "color_mask",
+                "draw",
+                "color_mask",
+                "draw",
+                "blend",
+                "draw",
+                "draw",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_barriers() {
+        let mut commands = vec![
+            Command::BindTexture(0, 2, glow::TEXTURE_2D),
+            // Other commands may observe the state, so the binding is kept.
+            Command::SetScissors(0, BufferSlice { offset: 0, size: 0 }),
+            Command::BindTexture(0, 3, glow::TEXTURE_2D),
+            draw(),
+            Command::CopyTextureToBuffer {
+                src_texture: 4,
+                texture_target: glow::TEXTURE_2D,
+                texture_format: glow::RGBA,
+                pixel_type: glow::UNSIGNED_BYTE,
+                dst_buffer: 5,
+                data: hal::command::BufferImageCopy {
+                    buffer_offset: 0,
+                    buffer_width: 0,
+                    buffer_height: 0,
+                    image_layers: hal::image::SubresourceLayers {
+                        aspects: hal::format::Aspects::COLOR,
+                        level: 0,
+                        layers: 0 .. 1,
+                    },
+                    image_offset: hal::image::Offset::ZERO,
+                    image_extent: hal::image::Extent {
+                        width: 1,
+                        height: 1,
+                        depth: 1,
+                    },
+                },
+            },
+            // The copy changed the binding of the first unit.
+            Command::BindTexture(0, 3, glow::TEXTURE_2D),
+            draw(),
+        ];
+        let stats = optimize(&mut commands);
+        assert_eq!(stats.removed(), 0);
+        assert_eq!(commands.len(), 7);
+    }
+}
@@ -5,8 +5,8 @@ use glow::HasContext;
 use smallvec::SmallVec;
 
 use crate::{
-    command as com, device, info::LegacyFeatures, native, state, Backend, GlContext, Share, Starc,
-    Surface, Swapchain,
+    command as com, device, dump, info::LegacyFeatures, native, state, Backend, GlContext, Share,
+    Starc, Surface, Swapchain,
 };
 
 // State caching system for command queue.
@@ -278,6 +278,46 @@ impl CommandQueue {
         swapchain.swap_buffers();
     }
 
+    /// Read the contents of an offscreen swapchain, with the rows from top to bottom.
+    #[cfg(surfman)]
+    fn read_back(
+        &self,
+        swapchain: &Swapchain,
+        presenter: &auxil::offscreen::Presenter,
+        index: hal::window::SwapImageIndex,
+    ) -> Vec<u8> {
+        let gl = &self.share.context;
+        let extent = swapchain.extent;
+        let format = presenter.swapchain_config().unwrap().format;
+        let desc = crate::conv::describe_format(format).unwrap();
+        let mut data = vec![0; presenter.image_size()];
+
+        gl.surfman_device
+            .write()
+            .make_context_current(&gl.surfman_context.read())
+            .unwrap();
+        unsafe {
+            gl.bind_framebuffer(glow::READ_FRAMEBUFFER, Some(swapchain.fbos[index as usize]));
+            gl.pixel_store_i32(glow::PACK_ALIGNMENT, 1);
+            gl.read_pixels(
+                0,
+                0,
+                extent.width as _,
+                extent.height as _,
+                desc.tex_external,
+                desc.data_type,
+                &mut data,
+            );
+        }
+
+        // GL rows go from bottom to top.
+        let row_size = data.len() / extent.height.max(1) as usize;
+        data.chunks(row_size.max(1))
+            .rev()
+            .flat_map(|row| row.iter().cloned())
+            .collect()
+    }
+
     // Reset the state to match our _expected_ state before executing
     // a command buffer.
     fn reset_state(&mut self) {
@@ -1174,6 +1214,12 @@ impl hal::queue::CommandQueue<Backend> for CommandQueue {
                 assert!(buffer.commands.len() >= (cb.buf.offset + cb.buf.size) as usize);
                 let commands = &buffer.commands
                     [cb.buf.offset as usize..(cb.buf.offset + cb.buf.size) as usize];
+                if log_enabled!(log::Level::Trace) {
+                    trace!(
+                        "Submitting command buffer:\n{}",
+                        dump::dump(commands, &buffer.data)
+                    );
+                }
                 self.reset_state();
                 for com in commands {
                     self.process(com, &buffer.data);
@@ -1220,13 +1266,24 @@ impl hal::queue::CommandQueue<Backend> for CommandQueue {
     unsafe fn present_surface(
         &mut self,
         surface: &mut Surface,
-        _image: native::ImageView,
+        #[cfg_attr(not(surfman), allow(unused_variables))] image: native::ImageView,
         _wait_semaphore: Option<&native::Semaphore>,
     ) -> Result<Option<hal::window::Suboptimal>, hal::window::PresentError> {
         let swapchain = surface
             .swapchain
             .as_ref()
             .expect("No swapchain is configured!");
+
+        #[cfg(surfman)]
+        {
+            let index = surface.image_index(image);
+            if let Some(ref mut offscreen) = surface.offscreen {
+                let index = index.expect("Presented image doesn't belong to the swapchain");
+                let data = self.read_back(swapchain, &offscreen.presenter, index);
+                return offscreen.presenter.present(index, data).map(|()| None);
+            }
+        }
+
         self.present_by_copy(swapchain, 0);
 
         #[cfg(wgl)]
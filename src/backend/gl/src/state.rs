@@ -123,7 +123,7 @@ fn map_factor(factor: pso::Factor) -> u32 {
     }
 }
 
-fn map_blend_op(operation: pso::BlendOp) -> (u32, u32, u32) {
+pub(crate) fn map_blend_op(operation: pso::BlendOp) -> (u32, u32, u32) {
     match operation {
         pso::BlendOp::Add { src, dst } => (glow::FUNC_ADD, map_factor(src), map_factor(dst)),
         pso::BlendOp::Sub { src, dst } => (glow::FUNC_SUBTRACT, map_factor(src), map_factor(dst)),
@@ -3,14 +3,13 @@
 use crate::{conv, native, Backend as B, Device, GlContainer, PhysicalDevice, QueueFamily, Starc};
 use hal::{adapter::Adapter, format as f, image, window};
 
-use arrayvec::ArrayVec;
+use auxil::offscreen::Presenter;
 use glow::HasContext;
 use parking_lot::RwLock;
 use surfman as sm;
 
 use std::cell::RefCell;
 use std::fmt;
-use std::iter;
 
 #[derive(Debug)]
 pub struct Swapchain {
@@ -18,8 +17,8 @@ pub struct Swapchain {
     pub(crate) context: Starc<RwLock<sm::Context>>,
     // Extent because the window lies
     pub(crate) extent: window::Extent2D,
-    ///
-    pub(crate) fbos: ArrayVec<[native::RawFrameBuffer; 3]>,
+    /// Framebuffers of the swapchain images.
+    pub(crate) fbos: Vec<native::RawFrameBuffer>,
 }
 
 impl window::Swapchain<B> for Swapchain {
@@ -111,10 +110,11 @@ impl Instance {
 
         // Create a surface with the given context
         Surface {
-            renderbuffer: None,
+            renderbuffers: Vec::new(),
             swapchain: None,
             context: Starc::new(RwLock::new(context)),
             device: Starc::new(RwLock::new(device)),
+            offscreen: None,
         }
     }
 }
@@ -186,6 +186,45 @@ impl hal::Instance<B> for Instance {
     }
 
     unsafe fn destroy_surface(&self, _surface: Surface) {}
+
+    fn create_offscreen_surface(
+        &self,
+        config: window::OffscreenConfig,
+    ) -> Result<Surface, window::InitError> {
+        // Offscreen surfaces don't have any surfman surface bound,
+        // the swapchain renderbuffer is read back on presentation instead.
+        fn unsupported(e: sm::Error) -> window::InitError {
+            error!("Failed to create an offscreen surfman context: {:?}", e);
+            window::InitError::UnsupportedOffscreen
+        }
+
+        let context_attributes = Self::get_default_context_attributes();
+        let mut device = SM_CONN
+            .with(|c| c.borrow().create_device(&self.software_adapter))
+            .map_err(unsupported)?;
+        let context_descriptor = device
+            .create_context_descriptor(&context_attributes)
+            .map_err(unsupported)?;
+        let context = device
+            .create_context(&context_descriptor)
+            .map_err(unsupported)?;
+
+        Ok(Surface {
+            renderbuffers: Vec::new(),
+            swapchain: None,
+            context: Starc::new(RwLock::new(context)),
+            device: Starc::new(RwLock::new(device)),
+            offscreen: Some(Offscreen {
+                presenter: Presenter::new(config),
+            }),
+        })
+    }
+}
+
+/// State of an offscreen surface.
+#[derive(Debug)]
+pub(crate) struct Offscreen {
+    pub(crate) presenter: Presenter,
 }
 
 #[derive(Debug)]
@@ -193,7 +232,9 @@ pub struct Surface {
     pub(crate) swapchain: Option<Swapchain>,
     pub(crate) context: Starc<RwLock<sm::Context>>,
     device: Starc<RwLock<sm::Device>>,
-    renderbuffer: Option<native::Renderbuffer>,
+    /// Renderbuffers of the swapchain images, indexed by `SwapImageIndex`.
+    renderbuffers: Vec<native::Renderbuffer>,
+    pub(crate) offscreen: Option<Offscreen>,
 }
 
 impl Surface {
@@ -209,6 +250,29 @@ impl Surface {
         self.context.clone()
     }
 
+    /// Return the index of a swapchain image.
+    pub(crate) fn image_index(&self, image: native::ImageView) -> Option<window::SwapImageIndex> {
+        match image {
+            native::ImageView::Renderbuffer(rbo) => self
+                .renderbuffers
+                .iter()
+                .position(|&r| r == rbo)
+                .map(|index| index as window::SwapImageIndex),
+            _ => None,
+        }
+    }
+
+    unsafe fn destroy_swapchain(&mut self, gl: &GlContainer) {
+        if let Some(old) = self.swapchain.take() {
+            for fbo in old.fbos {
+                gl.delete_framebuffer(fbo);
+            }
+        }
+        for rbo in self.renderbuffers.drain(..) {
+            gl.delete_renderbuffer(rbo);
+        }
+    }
+
     fn swapchain_formats(&self) -> Vec<f::Format> {
         // TODO: Make sure this is correct. I believe it is. Reference:
         // https://github.com/pcwalton/surfman/blob/master/surfman/src/context.rs#L34-L37
@@ -251,38 +315,45 @@ impl window::PresentationSurface<B> for Surface {
     ) -> Result<(), window::CreationError> {
         let gl = &device.share.context;
 
-        if let Some(old) = self.swapchain.take() {
-            for fbo in old.fbos {
-                gl.delete_framebuffer(fbo);
-            }
-        }
+        // Offscreen images are read back on presentation, so each of them
+        // needs its own storage.
+        let image_count = if let Some(ref mut offscreen) = self.offscreen {
+            offscreen.presenter.configure(&config)?;
+            config.image_count
+        } else {
+            1
+        };
 
-        if self.renderbuffer.is_none() {
-            self.renderbuffer = Some(gl.create_renderbuffer().unwrap());
-        }
+        self.destroy_swapchain(gl);
 
         let desc = conv::describe_format(config.format).unwrap();
-        gl.bind_renderbuffer(glow::RENDERBUFFER, self.renderbuffer);
-        gl.renderbuffer_storage(
-            glow::RENDERBUFFER,
-            desc.tex_internal,
-            config.extent.width as i32,
-            config.extent.height as i32,
-        );
+        let mut fbos = Vec::with_capacity(image_count as usize);
+        for _ in 0 .. image_count {
+            let rbo = gl.create_renderbuffer().unwrap();
+            gl.bind_renderbuffer(glow::RENDERBUFFER, Some(rbo));
+            gl.renderbuffer_storage(
+                glow::RENDERBUFFER,
+                desc.tex_internal,
+                config.extent.width as i32,
+                config.extent.height as i32,
+            );
 
-        // let fbo = surface_info.framebuffer_object;
-        let fbo = gl.create_framebuffer().unwrap();
-        gl.bind_framebuffer(glow::READ_FRAMEBUFFER, Some(fbo));
-        gl.framebuffer_renderbuffer(
-            glow::READ_FRAMEBUFFER,
-            glow::COLOR_ATTACHMENT0,
-            glow::RENDERBUFFER,
-            self.renderbuffer,
-        );
+            // let fbo = surface_info.framebuffer_object;
+            let fbo = gl.create_framebuffer().unwrap();
+            gl.bind_framebuffer(glow::READ_FRAMEBUFFER, Some(fbo));
+            gl.framebuffer_renderbuffer(
+                glow::READ_FRAMEBUFFER,
+                glow::COLOR_ATTACHMENT0,
+                glow::RENDERBUFFER,
+                Some(rbo),
+            );
+            self.renderbuffers.push(rbo);
+            fbos.push(fbo);
+        }
         self.swapchain = Some(Swapchain {
             context: self.context.clone(),
             extent: config.extent,
-            fbos: iter::once(fbo).collect(),
+            fbos,
             // out_fbo: Some(surface_info.framebuffer_object),
         });
 
@@ -291,32 +362,37 @@ impl window::PresentationSurface<B> for Surface {
 
     unsafe fn unconfigure_swapchain(&mut self, device: &Device) {
         let gl = &device.share.context;
-        if let Some(old) = self.swapchain.take() {
-            for fbo in old.fbos {
-                gl.delete_framebuffer(fbo);
-            }
-        }
-        if let Some(rbo) = self.renderbuffer.take() {
-            gl.delete_renderbuffer(rbo);
+        if let Some(ref mut offscreen) = self.offscreen {
+            offscreen.presenter.unconfigure();
         }
+        self.destroy_swapchain(gl);
     }
 
     unsafe fn acquire_image(
         &mut self,
         _timeout_ns: u64,
     ) -> Result<(Self::SwapchainImage, Option<window::Suboptimal>), window::AcquireError> {
-        let image = native::ImageView::Renderbuffer(self.renderbuffer.unwrap());
+        let index = match self.offscreen {
+            Some(ref mut offscreen) => offscreen.presenter.acquire()?,
+            None => 0,
+        };
+        let image = native::ImageView::Renderbuffer(self.renderbuffers[index as usize]);
         Ok((image, None))
     }
 }
 
 impl window::Surface<B> for Surface {
     fn supports_queue_family(&self, _: &QueueFamily) -> bool {
-        self.make_context_current();
+        if self.offscreen.is_none() {
+            self.make_context_current();
+        }
         true
     }
 
     fn capabilities(&self, _physical_device: &PhysicalDevice) -> window::SurfaceCapabilities {
+        if let Some(ref offscreen) = self.offscreen {
+            return offscreen.presenter.capabilities();
+        }
         window::SurfaceCapabilities {
             present_modes: window::PresentMode::FIFO, //TODO
             composite_alpha_modes: window::CompositeAlphaMode::OPAQUE, //TODO
@@ -341,6 +417,88 @@ impl window::Surface<B> for Surface {
     }
 
     fn supported_formats(&self, _physical_device: &PhysicalDevice) -> Option<Vec<f::Format>> {
+        if let Some(ref offscreen) = self.offscreen {
+            return Some(offscreen.presenter.formats());
+        }
         Some(self.swapchain_formats())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hal::{
+        adapter::PhysicalDevice as _,
+        queue::CommandQueue as _,
+        window::{PresentationSurface as _, Surface as _},
+        Instance as _,
+    };
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_offscreen_present_out_of_order() {
+        let frames = Arc::new(Mutex::new(Vec::new()));
+        let frames_sink = Arc::clone(&frames);
+        let extent = window::Extent2D {
+            width: 4,
+            height: 4,
+        };
+        let config = window::OffscreenConfig::new(extent, vec![f::Format::Rgba8Unorm]).with_sink(
+            move |frame: window::Frame<'_>| {
+                frames_sink
+                    .lock()
+                    .unwrap()
+                    .push((frame.index, frame.data[.. 4].to_vec()))
+            },
+        );
+
+        let instance = Instance::create("gfx-backend-gl", 1).unwrap();
+        let mut surface = instance.create_offscreen_surface(config).unwrap();
+        let adapter = instance.enumerate_adapters().pop().unwrap();
+        let family = adapter
+            .queue_families
+            .iter()
+            .find(|family| surface.supports_queue_family(family))
+            .unwrap();
+        let mut gpu = unsafe {
+            adapter
+                .physical_device
+                .open(&[(family, &[1.0])], hal::Features::empty())
+                .unwrap()
+        };
+        let device = &gpu.device;
+        let queue = &mut gpu.queue_groups[0].queues[0];
+
+        let swap_config = window::SwapchainConfig::new(4, 4, f::Format::Rgba8Unorm, 2)
+            .with_present_mode(window::PresentMode::IMMEDIATE);
+        unsafe {
+            surface.configure_swapchain(device, swap_config).unwrap();
+            let (first, _) = surface.acquire_image(!0).unwrap();
+            let (second, _) = surface.acquire_image(!0).unwrap();
+            assert_ne!(first, second);
+
+            // Clear the first image to red and the second one to green.
+            let gl = &device.share.context;
+            gl.surfman_device
+                .write()
+                .make_context_current(&gl.surfman_context.read())
+                .unwrap();
+            for (image, color) in [(first, [1.0, 0.0]), (second, [0.0, 1.0])].iter() {
+                let index = surface.image_index(*image).unwrap() as usize;
+                let fbo = surface.swapchain.as_ref().unwrap().fbos[index];
+                gl.bind_framebuffer(glow::DRAW_FRAMEBUFFER, Some(fbo));
+                gl.clear_color(color[0], color[1], 0.0, 1.0);
+                gl.clear(glow::COLOR_BUFFER_BIT);
+            }
+
+            queue.present_surface(&mut surface, second, None).unwrap();
+            queue.present_surface(&mut surface, first, None).unwrap();
+            surface.unconfigure_swapchain(device);
+        }
+
+        assert_eq!(
+            *frames.lock().unwrap(),
+            vec![(0, vec![0, 0xFF, 0, 0xFF]), (1, vec![0xFF, 0, 0, 0xFF])]
+        );
+    }
+}
@@ -0,0 +1,697 @@
+//! Recording of commands, replayed by the queue on submission.
+
+use crate::{
+    resource::{
+        Bound,
+        DescriptorSet,
+        Event,
+        GraphicsPipelineDesc,
+        Image,
+        ImageView,
+        QueryPoolData,
+        RenderPassDesc,
+    },
+    spirv,
+    Backend,
+};
+use hal::{buffer, command as com, image, memory, pool, pso, query};
+
+use std::borrow::Borrow;
+use std::ops::Range;
+use std::sync::{atomic::AtomicBool, Arc};
+
+/// Bind point of pipelines and descriptor sets.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BindPoint {
+    Graphics,
+    Compute,
+}
+
+#[derive(Clone, Debug)]
+pub enum Command {
+    FillBuffer {
+        buffer: Bound,
+        data: u32,
+    },
+    UpdateBuffer {
+        buffer: Bound,
+        offset: buffer::Offset,
+        data: Vec<u8>,
+    },
+    CopyBuffer {
+        src: Bound,
+        dst: Bound,
+        regions: Vec<com::BufferCopy>,
+    },
+    CopyImage {
+        src: Image,
+        dst: Image,
+        regions: Vec<com::ImageCopy>,
+    },
+    CopyBufferToImage {
+        src: Bound,
+        dst: Image,
+        regions: Vec<com::BufferImageCopy>,
+    },
+    CopyImageToBuffer {
+        src: Image,
+        dst: Bound,
+        regions: Vec<com::BufferImageCopy>,
+    },
+    ClearImage {
+        image: Image,
+        value: com::ClearValue,
+        ranges: Vec<image::SubresourceRange>,
+    },
+    BlitImage {
+        src: Image,
+        dst: Image,
+        filter: image::Filter,
+        regions: Vec<com::ImageBlit>,
+    },
+    ResolveImage {
+        src: Image,
+        dst: Image,
+        regions: Vec<com::ImageResolve>,
+    },
+    BeginRenderPass {
+        render_pass: Arc<RenderPassDesc>,
+        attachments: Arc<[ImageView]>,
+        area: pso::Rect,
+        clear_values: Vec<com::ClearValue>,
+    },
+    NextSubpass,
+    EndRenderPass,
+    ClearAttachments {
+        clears: Vec<com::AttachmentClear>,
+        rects: Vec<pso::ClearRect>,
+    },
+    BindGraphicsPipeline(Arc<GraphicsPipelineDesc>),
+    BindComputePipeline(Arc<spirv::Program>),
+    BindDescriptorSets {
+        bind_point: BindPoint,
+        first_set: usize,
+        sets: Vec<DescriptorSet>,
+        offsets: Vec<com::DescriptorSetOffset>,
+    },
+    BindIndexBuffer {
+        buffer: Bound,
+        index_type: hal::IndexType,
+    },
+    BindVertexBuffers {
+        first_binding: pso::BufferIndex,
+        buffers: Vec<Bound>,
+    },
+    SetViewports {
+        first: u32,
+        viewports: Vec<pso::Viewport>,
+    },
+    SetScissors {
+        first: u32,
+        rects: Vec<pso::Rect>,
+    },
+    SetBlendConstants(pso::ColorValue),
+    PushConstants {
+        offset: u32,
+        constants: Vec<u32>,
+    },
+    Draw {
+        vertices: Range<hal::VertexCount>,
+        instances: Range<hal::InstanceCount>,
+    },
+    DrawIndexed {
+        indices: Range<hal::IndexCount>,
+        base_vertex: hal::VertexOffset,
+        instances: Range<hal::InstanceCount>,
+    },
+    DrawIndirect {
+        buffer: Bound,
+        offset: buffer::Offset,
+        draw_count: hal::DrawCount,
+        stride: u32,
+        indexed: bool,
+    },
+    Dispatch(hal::WorkGroupCount),
+    DispatchIndirect {
+        buffer: Bound,
+        offset: buffer::Offset,
+    },
+    SetEvent(Arc<AtomicBool>, bool),
+    BeginQuery(Arc<QueryPoolData>, query::Id),
+    EndQuery(Arc<QueryPoolData>, query::Id),
+    ResetQueries(Arc<QueryPoolData>, Range<query::Id>),
+    WriteTimestamp(Arc<QueryPoolData>, query::Id),
+    CopyQueryResults {
+        pool: Arc<QueryPoolData>,
+        queries: Range<query::Id>,
+        buffer: Bound,
+        offset: buffer::Offset,
+        stride: buffer::Offset,
+        flags: query::ResultFlags,
+    },
+}
+
+#[derive(Debug)]
+pub struct CommandPool;
+
+impl pool::CommandPool<Backend> for CommandPool {
+    unsafe fn reset(&mut self, _release_resources: bool) {}
+
+    unsafe fn allocate_one(&mut self, _level: com::Level) -> CommandBuffer {
+        CommandBuffer::default()
+    }
+
+    unsafe fn free<I>(&mut self, _buffers: I)
+    where
+        I: IntoIterator<Item = CommandBuffer>,
+    {
+    }
+}
+
+/// Command buffer, keeping a list of the recorded commands.
+#[derive(Debug, Default)]
+pub struct CommandBuffer {
+    pub(crate) commands: Vec<Command>,
+}
+
+fn event_flag(event: &Event) -> Arc<AtomicBool> {
+    Arc::clone(&event.0)
+}
+
+impl com::CommandBuffer<Backend> for CommandBuffer {
+    unsafe fn begin(
+        &mut self,
+        _flags: com::CommandBufferFlags,
+        _inheritance_info: com::CommandBufferInheritanceInfo<Backend>,
+    ) {
+        self.commands.clear();
+    }
+
+    unsafe fn finish(&mut self) {}
+
+    unsafe fn reset(&mut self, _release_resources: bool) {
+        self.commands.clear();
+    }
+
+    unsafe fn pipeline_barrier<'a, T>(
+        &mut self,
+        _stages: Range<pso::PipelineStage>,
+        _dependencies: memory::Dependencies,
+        _barriers: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<memory::Barrier<'a, Backend>>,
+    {
+        // Commands are executed in order, one after another.
+    }
+
+    unsafe fn fill_buffer(
+        &mut self,
+        buffer: &crate::resource::Buffer,
+        range: buffer::SubRange,
+        data: u32,
+    ) {
+        self.commands.push(Command::FillBuffer {
+            buffer: buffer.bound().slice(range),
+            data,
+        });
+    }
+
+    unsafe fn update_buffer(
+        &mut self,
+        buffer: &crate::resource::Buffer,
+        offset: buffer::Offset,
+        data: &[u8],
+    ) {
+        self.commands.push(Command::UpdateBuffer {
+            buffer: buffer.bound(),
+            offset,
+            data: data.to_vec(),
+        });
+    }
+
+    unsafe fn clear_image<T>(
+        &mut self,
+        image: &Image,
+        _layout: image::Layout,
+        value: com::ClearValue,
+        subresource_ranges: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<image::SubresourceRange>,
+    {
+        self.commands.push(Command::ClearImage {
+            image: image.clone(),
+            value,
+            ranges: subresource_ranges
+                .into_iter()
+                .map(|r| r.borrow().clone())
+                .collect(),
+        });
+    }
+
+    unsafe fn clear_attachments<T, U>(&mut self, clears: T, rects: U)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<com::AttachmentClear>,
+        U: IntoIterator,
+        U::Item: Borrow<pso::ClearRect>,
+    {
+        self.commands.push(Command::ClearAttachments {
+            clears: clears.into_iter().map(|c| *c.borrow()).collect(),
+            rects: rects.into_iter().map(|r| r.borrow().clone()).collect(),
+        });
+    }
+
+    unsafe fn resolve_image<T>(
+        &mut self,
+        src: &Image,
+        _src_layout: image::Layout,
+        dst: &Image,
+        _dst_layout: image::Layout,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::ImageResolve>,
+    {
+        self.commands.push(Command::ResolveImage {
+            src: src.clone(),
+            dst: dst.clone(),
+            regions: regions.into_iter().map(|r| r.borrow().clone()).collect(),
+        });
+    }
+
+    unsafe fn blit_image<T>(
+        &mut self,
+        src: &Image,
+        _src_layout: image::Layout,
+        dst: &Image,
+        _dst_layout: image::Layout,
+        filter: image::Filter,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::ImageBlit>,
+    {
+        self.commands.push(Command::BlitImage {
+            src: src.clone(),
+            dst: dst.clone(),
+            filter,
+            regions: regions.into_iter().map(|r| r.borrow().clone()).collect(),
+        });
+    }
+
+    unsafe fn bind_index_buffer(&mut self, view: buffer::IndexBufferView<Backend>) {
+        self.commands.push(Command::BindIndexBuffer {
+            buffer: view.buffer.bound().slice(view.range),
+            index_type: view.index_type,
+        });
+    }
+
+    unsafe fn bind_vertex_buffers<I, T>(&mut self, first_binding: pso::BufferIndex, buffers: I)
+    where
+        I: IntoIterator<Item = (T, buffer::SubRange)>,
+        T: Borrow<crate::resource::Buffer>,
+    {
+        self.commands.push(Command::BindVertexBuffers {
+            first_binding,
+            buffers: buffers
+                .into_iter()
+                .map(|(buffer, range)| buffer.borrow().bound().slice(range))
+                .collect(),
+        });
+    }
+
+    unsafe fn set_viewports<T>(&mut self, first_viewport: u32, viewports: T)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<pso::Viewport>,
+    {
+        self.commands.push(Command::SetViewports {
+            first: first_viewport,
+            viewports: viewports.into_iter().map(|v| v.borrow().clone()).collect(),
+        });
+    }
+
+    unsafe fn set_scissors<T>(&mut self, first_scissor: u32, rects: T)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<pso::Rect>,
+    {
+        self.commands.push(Command::SetScissors {
+            first: first_scissor,
+            rects: rects.into_iter().map(|r| *r.borrow()).collect(),
+        });
+    }
+
+    unsafe fn set_stencil_reference(&mut self, _faces: pso::Face, _value: pso::StencilValue) {
+        warn!("Stencil is not supported");
+    }
+
+    unsafe fn set_stencil_read_mask(&mut self, _faces: pso::Face, _value: pso::StencilValue) {
+        warn!("Stencil is not supported");
+    }
+
+    unsafe fn set_stencil_write_mask(&mut self, _faces: pso::Face, _value: pso::StencilValue) {
+        warn!("Stencil is not supported");
+    }
+
+    unsafe fn set_blend_constants(&mut self, color: pso::ColorValue) {
+        self.commands.push(Command::SetBlendConstants(color));
+    }
+
+    unsafe fn set_depth_bounds(&mut self, _bounds: Range<f32>) {
+        warn!("Depth bounds test is not supported");
+    }
+
+    unsafe fn set_line_width(&mut self, _width: f32) {}
+
+    unsafe fn set_depth_bias(&mut self, _depth_bias: pso::DepthBias) {
+        warn!("Depth bias is not supported");
+    }
+
+    unsafe fn begin_render_pass<T>(
+        &mut self,
+        render_pass: &crate::resource::RenderPass,
+        framebuffer: &crate::resource::Framebuffer,
+        render_area: pso::Rect,
+        clear_values: T,
+        _first_subpass: com::SubpassContents,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::ClearValue>,
+    {
+        self.commands.push(Command::BeginRenderPass {
+            render_pass: Arc::clone(&render_pass.0),
+            attachments: Arc::clone(&framebuffer.attachments),
+            area: render_area,
+            clear_values: clear_values.into_iter().map(|c| *c.borrow()).collect(),
+        });
+    }
+
+    unsafe fn next_subpass(&mut self, _contents: com::SubpassContents) {
+        self.commands.push(Command::NextSubpass);
+    }
+
+    unsafe fn end_render_pass(&mut self) {
+        self.commands.push(Command::EndRenderPass);
+    }
+
+    unsafe fn bind_graphics_pipeline(&mut self, pipeline: &crate::resource::GraphicsPipeline) {
+        self.commands
+            .push(Command::BindGraphicsPipeline(Arc::clone(&pipeline.0)));
+    }
+
+    unsafe fn bind_graphics_descriptor_sets<I, J>(
+        &mut self,
+        _layout: &crate::resource::PipelineLayout,
+        first_set: usize,
+        sets: I,
+        offsets: J,
+    ) where
+        I: IntoIterator,
+        I::Item: Borrow<DescriptorSet>,
+        J: IntoIterator,
+        J::Item: Borrow<com::DescriptorSetOffset>,
+    {
+        self.commands.push(Command::BindDescriptorSets {
+            bind_point: BindPoint::Graphics,
+            first_set,
+            sets: sets.into_iter().map(|s| s.borrow().clone()).collect(),
+            offsets: offsets.into_iter().map(|o| *o.borrow()).collect(),
+        });
+    }
+
+    unsafe fn bind_compute_pipeline(&mut self, pipeline: &crate::resource::ComputePipeline) {
+        self.commands
+            .push(Command::BindComputePipeline(Arc::clone(&pipeline.0)));
+    }
+
+    unsafe fn bind_compute_descriptor_sets<I, J>(
+        &mut self,
+        _layout: &crate::resource::PipelineLayout,
+        first_set: usize,
+        sets: I,
+        offsets: J,
+    ) where
+        I: IntoIterator,
+        I::Item: Borrow<DescriptorSet>,
+        J: IntoIterator,
+        J::Item: Borrow<com::DescriptorSetOffset>,
+    {
+        self.commands.push(Command::BindDescriptorSets {
+            bind_point: BindPoint::Compute,
+            first_set,
+            sets: sets.into_iter().map(|s| s.borrow().clone()).collect(),
+            offsets: offsets.into_iter().map(|o| *o.borrow()).collect(),
+        });
+    }
+
+    unsafe fn dispatch(&mut self, count: hal::WorkGroupCount) {
+        self.commands.push(Command::Dispatch(count));
+    }
+
+    unsafe fn dispatch_indirect(
+        &mut self,
+        buffer: &crate::resource::Buffer,
+        offset: buffer::Offset,
+    ) {
+        self.commands.push(Command::DispatchIndirect {
+            buffer: buffer.bound(),
+            offset,
+        });
+    }
+
+    unsafe fn copy_buffer<T>(
+        &mut self,
+        src: &crate::resource::Buffer,
+        dst: &crate::resource::Buffer,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::BufferCopy>,
+    {
+        self.commands.push(Command::CopyBuffer {
+            src: src.bound(),
+            dst: dst.bound(),
+            regions: regions.into_iter().map(|r| *r.borrow()).collect(),
+        });
+    }
+
+    unsafe fn copy_image<T>(
+        &mut self,
+        src: &Image,
+        _src_layout: image::Layout,
+        dst: &Image,
+        _dst_layout: image::Layout,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::ImageCopy>,
+    {
+        self.commands.push(Command::CopyImage {
+            src: src.clone(),
+            dst: dst.clone(),
+            regions: regions.into_iter().map(|r| r.borrow().clone()).collect(),
+        });
+    }
+
+    unsafe fn copy_buffer_to_image<T>(
+        &mut self,
+        src: &crate::resource::Buffer,
+        dst: &Image,
+        _dst_layout: image::Layout,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::BufferImageCopy>,
+    {
+        self.commands.push(Command::CopyBufferToImage {
+            src: src.bound(),
+            dst: dst.clone(),
+            regions: regions.into_iter().map(|r| r.borrow().clone()).collect(),
+        });
+    }
+
+    unsafe fn copy_image_to_buffer<T>(
+        &mut self,
+        src: &Image,
+        _src_layout: image::Layout,
+        dst: &crate::resource::Buffer,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<com::BufferImageCopy>,
+    {
+        self.commands.push(Command::CopyImageToBuffer {
+            src: src.clone(),
+            dst: dst.bound(),
+            regions: regions.into_iter().map(|r| r.borrow().clone()).collect(),
+        });
+    }
+
+    unsafe fn draw(
+        &mut self,
+        vertices: Range<hal::VertexCount>,
+        instances: Range<hal::InstanceCount>,
+    ) {
+        self.commands.push(Command::Draw {
+            vertices,
+            instances,
+        });
+    }
+
+    unsafe fn draw_indexed(
+        &mut self,
+        indices: Range<hal::IndexCount>,
+        base_vertex: hal::VertexOffset,
+        instances: Range<hal::InstanceCount>,
+    ) {
+        self.commands.push(Command::DrawIndexed {
+            indices,
+            base_vertex,
+            instances,
+        });
+    }
+
+    unsafe fn draw_indirect(
+        &mut self,
+        buffer: &crate::resource::Buffer,
+        offset: buffer::Offset,
+        draw_count: hal::DrawCount,
+        stride: u32,
+    ) {
+        self.commands.push(Command::DrawIndirect {
+            buffer: buffer.bound(),
+            offset,
+            draw_count,
+            stride,
+            indexed: false,
+        });
+    }
+
+    unsafe fn draw_indexed_indirect(
+        &mut self,
+        buffer: &crate::resource::Buffer,
+        offset: buffer::Offset,
+        draw_count: hal::DrawCount,
+        stride: u32,
+    ) {
+        self.commands.push(Command::DrawIndirect {
+            buffer: buffer.bound(),
+            offset,
+            draw_count,
+            stride,
+            indexed: true,
+        });
+    }
+
+    unsafe fn set_event(&mut self, event: &Event, _stages: pso::PipelineStage) {
+        self.commands
+            .push(Command::SetEvent(event_flag(event), true));
+    }
+
+    unsafe fn reset_event(&mut self, event: &Event, _stages: pso::PipelineStage) {
+        self.commands
+            .push(Command::SetEvent(event_flag(event), false));
+    }
+
+    unsafe fn wait_events<'a, I, J>(
+        &mut self,
+        _events: I,
+        _stages: Range<pso::PipelineStage>,
+        _barriers: J,
+    ) where
+        I: IntoIterator,
+        I::Item: Borrow<Event>,
+        J: IntoIterator,
+        J::Item: Borrow<memory::Barrier<'a, Backend>>,
+    {
+        // Previous commands are complete by the time the next ones run.
+    }
+
+    unsafe fn begin_query(&mut self, query: query::Query<Backend>, _flags: query::ControlFlags) {
+        self.commands
+            .push(Command::BeginQuery(Arc::clone(&query.pool.0), query.id));
+    }
+
+    unsafe fn end_query(&mut self, query: query::Query<Backend>) {
+        self.commands
+            .push(Command::EndQuery(Arc::clone(&query.pool.0), query.id));
+    }
+
+    unsafe fn reset_query_pool(
+        &mut self,
+        pool: &crate::resource::QueryPool,
+        queries: Range<query::Id>,
+    ) {
+        self.commands
+            .push(Command::ResetQueries(Arc::clone(&pool.0), queries));
+    }
+
+    unsafe fn copy_query_pool_results(
+        &mut self,
+        pool: &crate::resource::QueryPool,
+        queries: Range<query::Id>,
+        buffer: &crate::resource::Buffer,
+        offset: buffer::Offset,
+        stride: buffer::Offset,
+        flags: query::ResultFlags,
+    ) {
+        self.commands.push(Command::CopyQueryResults {
+            pool: Arc::clone(&pool.0),
+            queries,
+            buffer: buffer.bound(),
+            offset,
+            stride,
+            flags,
+        });
+    }
+
+    unsafe fn write_timestamp(&mut self, _stage: pso::PipelineStage, query: query::Query<Backend>) {
+        self.commands
+            .push(Command::WriteTimestamp(Arc::clone(&query.pool.0), query.id));
+    }
+
+    unsafe fn push_graphics_constants(
+        &mut self,
+        _layout: &crate::resource::PipelineLayout,
+        _stages: pso::ShaderStageFlags,
+        offset: u32,
+        constants: &[u32],
+    ) {
+        self.commands.push(Command::PushConstants {
+            offset,
+            constants: constants.to_vec(),
+        });
+    }
+
+    unsafe fn push_compute_constants(
+        &mut self,
+        _layout: &crate::resource::PipelineLayout,
+        offset: u32,
+        constants: &[u32],
+    ) {
+        self.commands.push(Command::PushConstants {
+            offset,
+            constants: constants.to_vec(),
+        });
+    }
+
+    unsafe fn execute_commands<'a, T, I>(&mut self, cmd_buffers: I)
+    where
+        T: 'a + Borrow<CommandBuffer>,
+        I: IntoIterator<Item = &'a T>,
+    {
+        for cmd_buffer in cmd_buffers {
+            self.commands
+                .extend(cmd_buffer.borrow().commands.iter().cloned());
+        }
+    }
+
+    unsafe fn insert_debug_marker(&mut self, _name: &str, _color: u32) {}
+
+    unsafe fn begin_debug_marker(&mut self, _name: &str, _color: u32) {}
+
+    unsafe fn end_debug_marker(&mut self) {}
+}
@@ -0,0 +1,662 @@
+use crate::{
+    command::{CommandBuffer, CommandPool},
+    format::TexelLayout,
+    resource::{
+        Buffer,
+        BufferView,
+        ComputePipeline,
+        Descriptor,
+        DescriptorPool,
+        DescriptorSet,
+        DescriptorSetLayout,
+        Event,
+        Fence,
+        Framebuffer,
+        GraphicsPipeline,
+        GraphicsPipelineDesc,
+        Image,
+        ImageView,
+        Memory,
+        PipelineLayout,
+        QueryPool,
+        QueryPoolData,
+        RenderPass,
+        RenderPassDesc,
+        Sampler,
+        ShaderModule,
+        SubpassDesc,
+    },
+    spirv,
+    Backend,
+    Surface,
+    Swapchain,
+};
+use hal::{buffer, device, format, image, memory, pass, pool, pso, query, queue, window};
+
+use std::borrow::Borrow;
+use std::ops::Range;
+use std::sync::{atomic::Ordering, Arc, Mutex};
+use std::{thread, time};
+
+/// Alignment of the resources in memory.
+const ALIGNMENT: u64 = 16;
+
+/// Logical device. Everything lives in host memory, so there is no state.
+#[derive(Debug)]
+pub struct Device;
+
+unsafe fn program(
+    entry: &pso::EntryPoint<Backend>,
+    stage: pso::Stage,
+) -> Result<spirv::Program, pso::CreationError> {
+    spirv::Program::new(&entry.module.0, entry.entry, stage, &entry.specialization)
+        .map_err(pso::CreationError::Shader)
+}
+
+impl device::Device<Backend> for Device {
+    unsafe fn allocate_memory(
+        &self,
+        _memory_type: hal::MemoryTypeId,
+        size: u64,
+    ) -> Result<Memory, device::AllocationError> {
+        Ok(Memory::new(size))
+    }
+
+    unsafe fn free_memory(&self, _memory: Memory) {}
+
+    unsafe fn create_command_pool(
+        &self,
+        _family: queue::QueueFamilyId,
+        _create_flags: pool::CommandPoolCreateFlags,
+    ) -> Result<CommandPool, device::OutOfMemory> {
+        Ok(CommandPool)
+    }
+
+    unsafe fn destroy_command_pool(&self, _pool: CommandPool) {}
+
+    unsafe fn create_render_pass<'a, IA, IS, ID>(
+        &self,
+        attachments: IA,
+        subpasses: IS,
+        _dependencies: ID,
+    ) -> Result<RenderPass, device::OutOfMemory>
+    where
+        IA: IntoIterator,
+        IA::Item: Borrow<pass::Attachment>,
+        IS: IntoIterator,
+        IS::Item: Borrow<pass::SubpassDesc<'a>>,
+        ID: IntoIterator,
+        ID::Item: Borrow<pass::SubpassDependency>,
+    {
+        let subpasses = subpasses
+            .into_iter()
+            .map(|subpass| {
+                let subpass = subpass.borrow();
+                SubpassDesc {
+                    colors: subpass.colors.iter().map(|&(id, _)| Some(id)).collect(),
+                    depth_stencil: subpass.depth_stencil.map(|&(id, _)| id),
+                    resolves: subpass.resolves.iter().map(|&(id, _)| Some(id)).collect(),
+                }
+            })
+            .collect();
+        Ok(RenderPass(Arc::new(RenderPassDesc {
+            attachments: attachments
+                .into_iter()
+                .map(|a| a.borrow().clone())
+                .collect(),
+            subpasses,
+        })))
+    }
+
+    unsafe fn destroy_render_pass(&self, _render_pass: RenderPass) {}
+
+    unsafe fn create_pipeline_layout<IS, IR>(
+        &self,
+        _set_layouts: IS,
+        _push_constants: IR,
+    ) -> Result<PipelineLayout, device::OutOfMemory>
+    where
+        IS: IntoIterator,
+        IS::Item: Borrow<DescriptorSetLayout>,
+        IR: IntoIterator,
+        IR::Item: Borrow<(pso::ShaderStageFlags, Range<u32>)>,
+    {
+        Ok(PipelineLayout)
+    }
+
+    unsafe fn destroy_pipeline_layout(&self, _layout: PipelineLayout) {}
+
+    unsafe fn create_pipeline_cache(
+        &self,
+        _data: Option<&[u8]>,
+    ) -> Result<(), device::OutOfMemory> {
+        Ok(())
+    }
+
+    unsafe fn get_pipeline_cache_data(&self, _cache: &()) -> Result<Vec<u8>, device::OutOfMemory> {
+        Ok(Vec::new())
+    }
+
+    unsafe fn merge_pipeline_caches<I>(
+        &self,
+        _target: &(),
+        _sources: I,
+    ) -> Result<(), device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<()>,
+    {
+        Ok(())
+    }
+
+    unsafe fn destroy_pipeline_cache(&self, _cache: ()) {}
+
+    unsafe fn create_graphics_pipeline<'a>(
+        &self,
+        desc: &pso::GraphicsPipelineDesc<'a, Backend>,
+        _cache: Option<&()>,
+    ) -> Result<GraphicsPipeline, pso::CreationError> {
+        let shaders = &desc.shaders;
+        for &(shader, stage) in &[
+            (&shaders.hull, pso::Stage::Hull),
+            (&shaders.domain, pso::Stage::Domain),
+            (&shaders.geometry, pso::Stage::Geometry),
+        ] {
+            if shader.is_some() {
+                return Err(pso::CreationError::Shader(
+                    device::ShaderError::UnsupportedStage(stage),
+                ));
+            }
+        }
+        let fragment = match shaders.fragment {
+            Some(ref entry) => Some(program(entry, pso::Stage::Fragment)?),
+            None => None,
+        };
+        Ok(GraphicsPipeline(Arc::new(GraphicsPipelineDesc {
+            vertex: program(&shaders.vertex, pso::Stage::Vertex)?,
+            fragment,
+            vertex_buffers: desc.vertex_buffers.clone(),
+            attributes: desc.attributes.clone(),
+            primitive: desc.input_assembler.primitive,
+            restart_index: desc.input_assembler.restart_index,
+            rasterizer: desc.rasterizer,
+            blender: desc.blender.clone(),
+            depth: desc.depth_stencil.depth,
+            baked_states: desc.baked_states.clone(),
+        })))
+    }
+
+    unsafe fn destroy_graphics_pipeline(&self, _pipeline: GraphicsPipeline) {}
+
+    unsafe fn create_compute_pipeline<'a>(
+        &self,
+        desc: &pso::ComputePipelineDesc<'a, Backend>,
+        _cache: Option<&()>,
+    ) -> Result<ComputePipeline, pso::CreationError> {
+        Ok(ComputePipeline(Arc::new(program(
+            &desc.shader,
+            pso::Stage::Compute,
+        )?)))
+    }
+
+    unsafe fn destroy_compute_pipeline(&self, _pipeline: ComputePipeline) {}
+
+    unsafe fn create_framebuffer<I>(
+        &self,
+        _render_pass: &RenderPass,
+        attachments: I,
+        _extent: image::Extent,
+    ) -> Result<Framebuffer, device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<ImageView>,
+    {
+        Ok(Framebuffer {
+            attachments: attachments
+                .into_iter()
+                .map(|a| a.borrow().clone())
+                .collect(),
+        })
+    }
+
+    unsafe fn destroy_framebuffer(&self, _framebuffer: Framebuffer) {}
+
+    unsafe fn create_shader_module(
+        &self,
+        spirv_data: &[u32],
+    ) -> Result<ShaderModule, device::ShaderError> {
+        spirv::Module::parse(spirv_data).map(|module| ShaderModule(Arc::new(module)))
+    }
+
+    unsafe fn destroy_shader_module(&self, _module: ShaderModule) {}
+
+    unsafe fn create_buffer(
+        &self,
+        size: u64,
+        _usage: buffer::Usage,
+    ) -> Result<Buffer, buffer::CreationError> {
+        Ok(Buffer { size, memory: None })
+    }
+
+    unsafe fn get_buffer_requirements(&self, buffer: &Buffer) -> memory::Requirements {
+        memory::Requirements {
+            size: buffer.size,
+            alignment: ALIGNMENT,
+            type_mask: 1,
+        }
+    }
+
+    unsafe fn bind_buffer_memory(
+        &self,
+        memory: &Memory,
+        offset: u64,
+        buffer: &mut Buffer,
+    ) -> Result<(), device::BindError> {
+        if offset + buffer.size > memory.block.len() as u64 {
+            return Err(device::BindError::OutOfBounds);
+        }
+        buffer.memory = Some(crate::resource::Bound::new(memory, offset, buffer.size));
+        Ok(())
+    }
+
+    unsafe fn destroy_buffer(&self, _buffer: Buffer) {}
+
+    unsafe fn create_buffer_view(
+        &self,
+        buffer: &Buffer,
+        format: Option<format::Format>,
+        range: buffer::SubRange,
+    ) -> Result<BufferView, buffer::ViewCreationError> {
+        let layout = format
+            .and_then(TexelLayout::of)
+            .ok_or(buffer::ViewCreationError::UnsupportedFormat(format))?;
+        Ok(BufferView {
+            memory: buffer.bound().slice(range),
+            layout,
+        })
+    }
+
+    unsafe fn destroy_buffer_view(&self, _view: BufferView) {}
+
+    unsafe fn create_image(
+        &self,
+        kind: image::Kind,
+        levels: image::Level,
+        format: format::Format,
+        _tiling: image::Tiling,
+        _usage: image::Usage,
+        _view_caps: image::ViewCapabilities,
+    ) -> Result<Image, image::CreationError> {
+        if kind.num_samples() != 1 {
+            return Err(image::CreationError::Samples(kind.num_samples()));
+        }
+        let layout = TexelLayout::of(format).ok_or(image::CreationError::Format(format))?;
+        Ok(Image {
+            kind,
+            levels,
+            layout,
+            memory: None,
+        })
+    }
+
+    unsafe fn get_image_requirements(&self, image: &Image) -> memory::Requirements {
+        memory::Requirements {
+            size: image.size(),
+            alignment: ALIGNMENT,
+            type_mask: 1,
+        }
+    }
+
+    unsafe fn get_image_subresource_footprint(
+        &self,
+        image: &Image,
+        subresource: image::Subresource,
+    ) -> image::SubresourceFootprint {
+        image.footprint(subresource.level, subresource.layer)
+    }
+
+    unsafe fn bind_image_memory(
+        &self,
+        memory: &Memory,
+        offset: u64,
+        image: &mut Image,
+    ) -> Result<(), device::BindError> {
+        let size = image.size();
+        if offset + size > memory.block.len() as u64 {
+            return Err(device::BindError::OutOfBounds);
+        }
+        image.memory = Some(crate::resource::Bound::new(memory, offset, size));
+        Ok(())
+    }
+
+    unsafe fn destroy_image(&self, _image: Image) {}
+
+    unsafe fn create_image_view(
+        &self,
+        image: &Image,
+        kind: image::ViewKind,
+        format: format::Format,
+        swizzle: format::Swizzle,
+        range: image::SubresourceRange,
+    ) -> Result<ImageView, image::ViewCreationError> {
+        let layout = TexelLayout::of(format).ok_or(image::ViewCreationError::BadFormat(format))?;
+        if layout.size() != image.layout.size() {
+            return Err(image::ViewCreationError::BadFormat(format));
+        }
+        if kind == image::ViewKind::Cube || kind == image::ViewKind::CubeArray {
+            return Err(image::ViewCreationError::BadKind(kind));
+        }
+        if range.levels.end > image.levels {
+            return Err(image::ViewCreationError::Level(range.levels.end));
+        }
+        Ok(ImageView {
+            image: image.clone(),
+            kind,
+            layout,
+            swizzle,
+            range,
+        })
+    }
+
+    unsafe fn destroy_image_view(&self, _view: ImageView) {}
+
+    unsafe fn create_sampler(
+        &self,
+        desc: &image::SamplerDesc,
+    ) -> Result<Sampler, device::AllocationError> {
+        Ok(Sampler(Arc::new(auxil::sampler::Sampler::new(
+            desc.clone(),
+        ))))
+    }
+
+    unsafe fn destroy_sampler(&self, _sampler: Sampler) {}
+
+    unsafe fn create_descriptor_pool<I>(
+        &self,
+        _max_sets: usize,
+        _descriptor_ranges: I,
+        _flags: pso::DescriptorPoolCreateFlags,
+    ) -> Result<DescriptorPool, device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::DescriptorRangeDesc>,
+    {
+        Ok(DescriptorPool)
+    }
+
+    unsafe fn destroy_descriptor_pool(&self, _pool: DescriptorPool) {}
+
+    unsafe fn create_descriptor_set_layout<I, J>(
+        &self,
+        bindings: I,
+        immutable_samplers: J,
+    ) -> Result<DescriptorSetLayout, device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::DescriptorSetLayoutBinding>,
+        J: IntoIterator,
+        J::Item: Borrow<Sampler>,
+    {
+        Ok(DescriptorSetLayout {
+            bindings: bindings.into_iter().map(|b| b.borrow().clone()).collect(),
+            immutable_samplers: immutable_samplers
+                .into_iter()
+                .map(|s| Arc::clone(&s.borrow().0))
+                .collect(),
+        })
+    }
+
+    unsafe fn destroy_descriptor_set_layout(&self, _layout: DescriptorSetLayout) {}
+
+    unsafe fn write_descriptor_sets<'a, I, J>(&self, write_iter: I)
+    where
+        I: IntoIterator<Item = pso::DescriptorSetWrite<'a, Backend, J>>,
+        J: IntoIterator,
+        J::Item: Borrow<pso::Descriptor<'a, Backend>>,
+    {
+        for write in write_iter {
+            let mut bindings = write.set.bindings.lock().unwrap();
+            let mut index = match bindings.iter().position(|b| b.binding == write.binding) {
+                Some(index) => index,
+                None => continue,
+            };
+            let mut offset = write.array_offset;
+            for descriptor in write.descriptors {
+                // Spill over to the next bindings when the array is full.
+                while index < bindings.len() && offset >= bindings[index].descriptors.len() {
+                    index += 1;
+                    offset = 0;
+                }
+                let binding = match bindings.get_mut(index) {
+                    Some(binding) => binding,
+                    None => break,
+                };
+                let descriptor = match *descriptor.borrow() {
+                    pso::Descriptor::Sampler(sampler) => {
+                        Descriptor::Sampler(Arc::clone(&sampler.0))
+                    }
+                    pso::Descriptor::Image(view, _) => Descriptor::Image(view.clone()),
+                    pso::Descriptor::CombinedImageSampler(view, _, sampler) => {
+                        Descriptor::CombinedImageSampler(view.clone(), Arc::clone(&sampler.0))
+                    }
+                    pso::Descriptor::Buffer(buffer, ref range) => {
+                        Descriptor::Buffer(buffer.bound().slice(range.clone()))
+                    }
+                    pso::Descriptor::TexelBuffer(view) => {
+                        Descriptor::TexelBuffer(view.memory.clone(), view.layout)
+                    }
+                };
+                binding.descriptors[offset] = Some(descriptor);
+                offset += 1;
+            }
+        }
+    }
+
+    unsafe fn copy_descriptor_sets<'a, I>(&self, copy_iter: I)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<pso::DescriptorSetCopy<'a, Backend>>,
+    {
+        for copy in copy_iter {
+            let copy = copy.borrow();
+            let descriptors: Vec<_> = {
+                let src = copy.src_set.bindings.lock().unwrap();
+                match src.iter().find(|b| b.binding == copy.src_binding) {
+                    Some(binding) => binding
+                        .descriptors
+                        .iter()
+                        .skip(copy.src_array_offset)
+                        .take(copy.count)
+                        .cloned()
+                        .collect(),
+                    None => continue,
+                }
+            };
+            let mut dst = copy.dst_set.bindings.lock().unwrap();
+            if let Some(binding) = dst.iter_mut().find(|b| b.binding == copy.dst_binding) {
+                let slots = binding.descriptors.iter_mut().skip(copy.dst_array_offset);
+                for (slot, descriptor) in slots.zip(descriptors) {
+                    *slot = descriptor;
+                }
+            }
+        }
+    }
+
+    unsafe fn map_memory(
+        &self,
+        memory: &Memory,
+        segment: memory::Segment,
+    ) -> Result<*mut u8, device::MapError> {
+        if segment.offset > memory.block.len() as u64 {
+            return Err(device::MapError::OutOfBounds);
+        }
+        Ok(memory.block.as_ptr().add(segment.offset as usize))
+    }
+
+    unsafe fn flush_mapped_memory_ranges<'a, I>(
+        &self,
+        _ranges: I,
+    ) -> Result<(), device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<(&'a Memory, memory::Segment)>,
+    {
+        Ok(())
+    }
+
+    unsafe fn invalidate_mapped_memory_ranges<'a, I>(
+        &self,
+        _ranges: I,
+    ) -> Result<(), device::OutOfMemory>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<(&'a Memory, memory::Segment)>,
+    {
+        Ok(())
+    }
+
+    unsafe fn unmap_memory(&self, _memory: &Memory) {}
+
+    fn create_semaphore(&self) -> Result<(), device::OutOfMemory> {
+        Ok(())
+    }
+
+    unsafe fn destroy_semaphore(&self, _semaphore: ()) {}
+
+    fn create_fence(&self, signaled: bool) -> Result<Fence, device::OutOfMemory> {
+        let fence = Fence::default();
+        fence.set(signaled);
+        Ok(fence)
+    }
+
+    unsafe fn reset_fence(&self, fence: &Fence) -> Result<(), device::OutOfMemory> {
+        fence.set(false);
+        Ok(())
+    }
+
+    unsafe fn wait_for_fence(
+        &self,
+        fence: &Fence,
+        timeout_ns: u64,
+    ) -> Result<bool, device::OomOrDeviceLost> {
+        // Submissions complete before returning, but another thread may still be submitting.
+        let start = time::Instant::now();
+        while !fence.is_signaled() {
+            if start.elapsed().as_nanos() >= timeout_ns as u128 {
+                return Ok(false);
+            }
+            thread::yield_now();
+        }
+        Ok(true)
+    }
+
+    unsafe fn get_fence_status(&self, fence: &Fence) -> Result<bool, device::DeviceLost> {
+        Ok(fence.is_signaled())
+    }
+
+    unsafe fn destroy_fence(&self, _fence: Fence) {}
+
+    fn create_event(&self) -> Result<Event, device::OutOfMemory> {
+        Ok(Event::default())
+    }
+
+    unsafe fn destroy_event(&self, _event: Event) {}
+
+    unsafe fn get_event_status(&self, event: &Event) -> Result<bool, device::OomOrDeviceLost> {
+        Ok(event.0.load(Ordering::Acquire))
+    }
+
+    unsafe fn set_event(&self, event: &Event) -> Result<(), device::OutOfMemory> {
+        event.0.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    unsafe fn reset_event(&self, event: &Event) -> Result<(), device::OutOfMemory> {
+        event.0.store(false, Ordering::Release);
+        Ok(())
+    }
+
+    unsafe fn create_query_pool(
+        &self,
+        ty: query::Type,
+        count: query::Id,
+    ) -> Result<QueryPool, query::CreationError> {
+        match ty {
+            query::Type::Occlusion | query::Type::Timestamp => {}
+            query::Type::PipelineStatistics(_) => {
+                return Err(query::CreationError::Unsupported(ty))
+            }
+        }
+        Ok(QueryPool(Arc::new(QueryPoolData {
+            ty,
+            results: Mutex::new(vec![None; count as usize]),
+        })))
+    }
+
+    unsafe fn destroy_query_pool(&self, _pool: QueryPool) {}
+
+    unsafe fn get_query_pool_results(
+        &self,
+        pool: &QueryPool,
+        queries: Range<query::Id>,
+        data: &mut [u8],
+        stride: buffer::Offset,
+        flags: query::ResultFlags,
+    ) -> Result<bool, device::OomOrDeviceLost> {
+        // Results are available as soon as the submission returns.
+        let available = pool.0.write_results(
+            queries,
+            |offset, value, wide| {
+                if wide {
+                    let bytes = value.to_le_bytes();
+                    if let Some(out) = data.get_mut(offset .. offset + 8) {
+                        out.copy_from_slice(&bytes);
+                    }
+                } else if let Some(out) = data.get_mut(offset .. offset + 4) {
+                    out.copy_from_slice(&(value as u32).to_le_bytes());
+                }
+            },
+            stride as usize,
+            flags,
+        );
+        Ok(available)
+    }
+
+    unsafe fn create_swapchain(
+        &self,
+        _surface: &mut Surface,
+        _config: window::SwapchainConfig,
+        _old_swapchain: Option<Swapchain>,
+    ) -> Result<(Swapchain, Vec<Image>), window::CreationError> {
+        unimplemented!("Use the `PresentationSurface` API")
+    }
+
+    unsafe fn destroy_swapchain(&self, _swapchain: Swapchain) {}
+
+    fn wait_idle(&self) -> Result<(), device::OutOfMemory> {
+        Ok(())
+    }
+
+    unsafe fn set_image_name(&self, _image: &mut Image, _name: &str) {}
+
+    unsafe fn set_buffer_name(&self, _buffer: &mut Buffer, _name: &str) {}
+
+    unsafe fn set_command_buffer_name(&self, _command_buffer: &mut CommandBuffer, _name: &str) {}
+
+    unsafe fn set_semaphore_name(&self, _semaphore: &mut (), _name: &str) {}
+
+    unsafe fn set_fence_name(&self, _fence: &mut Fence, _name: &str) {}
+
+    unsafe fn set_framebuffer_name(&self, _framebuffer: &mut Framebuffer, _name: &str) {}
+
+    unsafe fn set_render_pass_name(&self, _render_pass: &mut RenderPass, _name: &str) {}
+
+    unsafe fn set_descriptor_set_name(&self, _descriptor_set: &mut DescriptorSet, _name: &str) {}
+
+    unsafe fn set_descriptor_set_layout_name(
+        &self,
+        _descriptor_set_layout: &mut DescriptorSetLayout,
+        _name: &str,
+    ) {
+    }
+}
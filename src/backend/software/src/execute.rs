@@ -0,0 +1,898 @@
+//! Execution of recorded commands on the CPU.
+
+use crate::{
+    command::{BindPoint, Command},
+    format::Texel,
+    interpret::Invocation,
+    raster::DrawContext,
+    resource::{
+        Bound,
+        Descriptor,
+        DescriptorSet,
+        GraphicsPipelineDesc,
+        Image,
+        ImageView,
+        Memory,
+        QueryPoolData,
+        RenderPassDesc,
+    },
+    spirv::{builtin, Program, Resources, Value},
+};
+use hal::{command as com, image, pass, pso, query};
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::{atomic::Ordering, Arc};
+use std::time::Instant;
+
+/// Size of the push constant block.
+pub const PUSH_CONSTANTS_SIZE: usize = 256;
+
+fn signed_range(start: i32, end: i32) -> (i32, i32) {
+    (start.min(end), start.max(end))
+}
+
+#[derive(Debug)]
+struct RenderPassState {
+    desc: Arc<RenderPassDesc>,
+    attachments: Arc<[ImageView]>,
+    area: pso::Rect,
+    subpass: usize,
+}
+
+impl RenderPassState {
+    /// Resolve the color attachments of the current subpass.
+    unsafe fn resolve(&self) {
+        let subpass = match self.desc.subpasses.get(self.subpass) {
+            Some(subpass) => subpass,
+            None => return,
+        };
+        for (&color, &resolve) in subpass.colors.iter().zip(&subpass.resolves) {
+            let (src, dst) = match (color, resolve) {
+                (Some(src), Some(dst)) => (&self.attachments[src], &self.attachments[dst]),
+                _ => continue,
+            };
+            let extent = src.extent();
+            let x0 = self.area.x.max(0) as u32;
+            let y0 = self.area.y.max(0) as u32;
+            let x1 = (self.area.x as i32 + self.area.w as i32).max(0) as u32;
+            let y1 = (self.area.y as i32 + self.area.h as i32).max(0) as u32;
+            let layers = src.range.layers.end - src.range.layers.start;
+            for layer in 0 .. layers {
+                for y in y0 .. y1.min(extent.height) {
+                    for x in x0 .. x1.min(extent.width) {
+                        dst.write(0, layer, [x, y, 0], src.read(0, layer, [x, y, 0]));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Bound descriptor set, with its dynamic offsets.
+#[derive(Clone, Debug)]
+struct BoundSet {
+    set: DescriptorSet,
+    offsets: Vec<com::DescriptorSetOffset>,
+}
+
+/// State of a queue replaying command buffers.
+#[derive(Debug)]
+pub struct Executor {
+    epoch: Instant,
+    graphics_pipeline: Option<Arc<GraphicsPipelineDesc>>,
+    compute_pipeline: Option<Arc<Program>>,
+    graphics_sets: Vec<Option<BoundSet>>,
+    compute_sets: Vec<Option<BoundSet>>,
+    index_buffer: Option<(Bound, hal::IndexType)>,
+    vertex_buffers: Vec<Option<Bound>>,
+    viewports: Vec<pso::Viewport>,
+    scissors: Vec<pso::Rect>,
+    blend_constants: pso::ColorValue,
+    push_constants: Vec<u8>,
+    render_pass: Option<RenderPassState>,
+    occlusion_queries: Vec<(Arc<QueryPoolData>, query::Id)>,
+}
+
+impl Executor {
+    pub fn new(epoch: Instant) -> Self {
+        Executor {
+            epoch,
+            graphics_pipeline: None,
+            compute_pipeline: None,
+            graphics_sets: Vec::new(),
+            compute_sets: Vec::new(),
+            index_buffer: None,
+            vertex_buffers: Vec::new(),
+            viewports: Vec::new(),
+            scissors: Vec::new(),
+            blend_constants: [0.0; 4],
+            push_constants: vec![0; PUSH_CONSTANTS_SIZE],
+            render_pass: None,
+            occlusion_queries: Vec::new(),
+        }
+    }
+
+    /// Execute the commands of a command buffer.
+    ///
+    /// The binding state doesn't carry over between command buffers.
+    pub unsafe fn execute(&mut self, commands: &[Command]) {
+        *self = Executor::new(self.epoch);
+        for command in commands {
+            self.execute_one(command);
+        }
+    }
+
+    unsafe fn execute_one(&mut self, command: &Command) {
+        match *command {
+            Command::FillBuffer { ref buffer, data } => {
+                let count = buffer.size() / 4;
+                let bytes: Vec<u8> = (0 .. count)
+                    .flat_map(|_| data.to_le_bytes().to_vec())
+                    .collect();
+                buffer.write(0, &bytes);
+            }
+            Command::UpdateBuffer {
+                ref buffer,
+                offset,
+                ref data,
+            } => buffer.write(offset, data),
+            Command::CopyBuffer {
+                ref src,
+                ref dst,
+                ref regions,
+            } => {
+                for region in regions {
+                    let mut data = vec![0; region.size as usize];
+                    src.read(region.src, &mut data);
+                    dst.write(region.dst, &data);
+                }
+            }
+            Command::CopyImage {
+                ref src,
+                ref dst,
+                ref regions,
+            } => {
+                for region in regions {
+                    copy_image(
+                        src,
+                        &region.src_subresource,
+                        region.src_offset,
+                        dst,
+                        &region.dst_subresource,
+                        region.dst_offset,
+                        region.extent,
+                    );
+                }
+            }
+            Command::ResolveImage {
+                ref src,
+                ref dst,
+                ref regions,
+            } => {
+                // Only single-sampled images exist, so resolving is copying.
+                for region in regions {
+                    copy_image(
+                        src,
+                        &region.src_subresource,
+                        region.src_offset,
+                        dst,
+                        &region.dst_subresource,
+                        region.dst_offset,
+                        region.extent,
+                    );
+                }
+            }
+            Command::CopyBufferToImage {
+                ref src,
+                ref dst,
+                ref regions,
+            } => {
+                for region in regions {
+                    copy_buffer_image(src, dst, region, true);
+                }
+            }
+            Command::CopyImageToBuffer {
+                ref src,
+                ref dst,
+                ref regions,
+            } => {
+                for region in regions {
+                    copy_buffer_image(dst, src, region, false);
+                }
+            }
+            Command::ClearImage {
+                ref image,
+                value,
+                ref ranges,
+            } => {
+                let texel = clear_texel(image, value);
+                for range in ranges {
+                    for level in range.levels.clone() {
+                        let extent = image.level_extent(level);
+                        for layer in range.layers.clone() {
+                            fill_rect(image, level, layer, [0, 0], extent, texel);
+                        }
+                    }
+                }
+            }
+            Command::BlitImage {
+                ref src,
+                ref dst,
+                filter,
+                ref regions,
+            } => {
+                for region in regions {
+                    blit_image(src, dst, filter, region);
+                }
+            }
+            Command::BeginRenderPass {
+                ref render_pass,
+                ref attachments,
+                area,
+                ref clear_values,
+            } => {
+                for (i, attachment) in render_pass.attachments.iter().enumerate() {
+                    let view = match attachments.get(i) {
+                        Some(view) => view,
+                        None => continue,
+                    };
+                    let clear = attachment.ops.load == pass::AttachmentLoadOp::Clear
+                        || (view.layout.depth
+                            && attachment.stencil_ops.load == pass::AttachmentLoadOp::Clear);
+                    if let (true, Some(&value)) = (clear, clear_values.get(i)) {
+                        clear_view(view, area, 0..!0, clear_texel(&view.image, value));
+                    }
+                }
+                self.render_pass = Some(RenderPassState {
+                    desc: Arc::clone(render_pass),
+                    attachments: Arc::clone(attachments),
+                    area,
+                    subpass: 0,
+                });
+            }
+            Command::NextSubpass => {
+                if let Some(ref mut state) = self.render_pass {
+                    state.resolve();
+                    state.subpass += 1;
+                }
+            }
+            Command::EndRenderPass => {
+                if let Some(state) = self.render_pass.take() {
+                    state.resolve();
+                }
+            }
+            Command::ClearAttachments {
+                ref clears,
+                ref rects,
+            } => self.clear_attachments(clears, rects),
+            Command::BindGraphicsPipeline(ref pipeline) => {
+                let baked = &pipeline.baked_states;
+                if let Some(ref viewport) = baked.viewport {
+                    self.viewports = vec![viewport.clone()];
+                }
+                if let Some(scissor) = baked.scissor {
+                    self.scissors = vec![scissor];
+                }
+                if let Some(color) = baked.blend_color {
+                    self.blend_constants = color;
+                }
+                self.graphics_pipeline = Some(Arc::clone(pipeline));
+            }
+            Command::BindComputePipeline(ref pipeline) => {
+                self.compute_pipeline = Some(Arc::clone(pipeline));
+            }
+            Command::BindDescriptorSets {
+                bind_point,
+                first_set,
+                ref sets,
+                ref offsets,
+            } => {
+                let bound = match bind_point {
+                    BindPoint::Graphics => &mut self.graphics_sets,
+                    BindPoint::Compute => &mut self.compute_sets,
+                };
+                let mut offsets = offsets.iter().cloned();
+                for (i, set) in sets.iter().enumerate() {
+                    let dynamic_count: usize = set
+                        .bindings
+                        .lock()
+                        .unwrap()
+                        .iter()
+                        .filter(|b| b.is_dynamic())
+                        .map(|b| b.descriptors.len())
+                        .sum();
+                    let index = first_set + i;
+                    if bound.len() <= index {
+                        bound.resize(index + 1, None);
+                    }
+                    bound[index] = Some(BoundSet {
+                        set: set.clone(),
+                        offsets: offsets.by_ref().take(dynamic_count).collect(),
+                    });
+                }
+            }
+            Command::BindIndexBuffer {
+                ref buffer,
+                index_type,
+            } => self.index_buffer = Some((buffer.clone(), index_type)),
+            Command::BindVertexBuffers {
+                first_binding,
+                ref buffers,
+            } => {
+                for (i, buffer) in buffers.iter().enumerate() {
+                    let index = first_binding as usize + i;
+                    if self.vertex_buffers.len() <= index {
+                        self.vertex_buffers.resize(index + 1, None);
+                    }
+                    self.vertex_buffers[index] = Some(buffer.clone());
+                }
+            }
+            Command::SetViewports {
+                first,
+                ref viewports,
+            } => {
+                for (i, viewport) in viewports.iter().enumerate() {
+                    let index = first as usize + i;
+                    if self.viewports.len() <= index {
+                        self.viewports.resize(index + 1, viewport.clone());
+                    }
+                    self.viewports[index] = viewport.clone();
+                }
+            }
+            Command::SetScissors { first, ref rects } => {
+                for (i, &rect) in rects.iter().enumerate() {
+                    let index = first as usize + i;
+                    if self.scissors.len() <= index {
+                        self.scissors.resize(index + 1, rect);
+                    }
+                    self.scissors[index] = rect;
+                }
+            }
+            Command::SetBlendConstants(color) => self.blend_constants = color,
+            Command::PushConstants {
+                offset,
+                ref constants,
+            } => {
+                for (i, constant) in constants.iter().enumerate() {
+                    let start = offset as usize + i * 4;
+                    if let Some(bytes) = self.push_constants.get_mut(start .. start + 4) {
+                        bytes.copy_from_slice(&constant.to_le_bytes());
+                    }
+                }
+            }
+            Command::Draw {
+                ref vertices,
+                ref instances,
+            } => {
+                let list: Vec<_> = vertices.clone().map(Some).collect();
+                self.draw(&list, instances.clone());
+            }
+            Command::DrawIndexed {
+                ref indices,
+                base_vertex,
+                ref instances,
+            } => {
+                let list = self.indices(indices.clone(), base_vertex);
+                self.draw(&list, instances.clone());
+            }
+            Command::DrawIndirect {
+                ref buffer,
+                offset,
+                draw_count,
+                stride,
+                indexed,
+            } => {
+                let bytes = buffer.to_vec();
+                if indexed {
+                    let draws = <com::DrawIndexedIndirectCommand as com::IndirectCommand>::read(
+                        &bytes, offset, draw_count, stride,
+                    )
+                    .unwrap_or_default();
+                    for draw in draws {
+                        let first = draw.first_index;
+                        let list =
+                            self.indices(first .. first + draw.index_count, draw.vertex_offset);
+                        let first_instance = draw.first_instance;
+                        self.draw(&list, first_instance .. first_instance + draw.instance_count);
+                    }
+                } else {
+                    let draws = <com::DrawIndirectCommand as com::IndirectCommand>::read(
+                        &bytes, offset, draw_count, stride,
+                    )
+                    .unwrap_or_default();
+                    for draw in draws {
+                        let first = draw.first_vertex;
+                        let list: Vec<_> = (first .. first + draw.vertex_count).map(Some).collect();
+                        let first_instance = draw.first_instance;
+                        self.draw(&list, first_instance .. first_instance + draw.instance_count);
+                    }
+                }
+            }
+            Command::Dispatch(count) => self.dispatch(count),
+            Command::DispatchIndirect { ref buffer, offset } => {
+                let bytes = buffer.to_vec();
+                let stride = <com::DispatchIndirectCommand as com::IndirectCommand>::STRIDE;
+                if let Ok(commands) = <com::DispatchIndirectCommand as com::IndirectCommand>::read(
+                    &bytes, offset, 1, stride,
+                ) {
+                    self.dispatch(commands[0].count());
+                }
+            }
+            Command::SetEvent(ref event, value) => event.store(value, Ordering::Release),
+            Command::BeginQuery(ref pool, id) => match pool.ty {
+                query::Type::Occlusion => {
+                    pool.set(id, None);
+                    self.occlusion_queries.push((Arc::clone(pool), id));
+                }
+                _ => warn!("Unsupported query type {:?}", pool.ty),
+            },
+            Command::EndQuery(ref pool, id) => {
+                self.occlusion_queries
+                    .retain(|&(ref p, i)| !(Arc::ptr_eq(p, pool) && i == id));
+                if pool.ty == query::Type::Occlusion {
+                    pool.add(id, 0);
+                }
+            }
+            Command::ResetQueries(ref pool, ref queries) => {
+                for id in queries.clone() {
+                    pool.set(id, None);
+                }
+            }
+            Command::WriteTimestamp(ref pool, id) => {
+                let nanos = self.epoch.elapsed().as_nanos() as u64;
+                pool.set(id, Some(nanos));
+            }
+            Command::CopyQueryResults {
+                ref pool,
+                ref queries,
+                ref buffer,
+                offset,
+                stride,
+                flags,
+            } => {
+                pool.write_results(
+                    queries.clone(),
+                    |position, value, wide| {
+                        let at = offset + position as u64;
+                        if wide {
+                            buffer.write(at, &value.to_le_bytes());
+                        } else {
+                            buffer.write(at, &(value as u32).to_le_bytes());
+                        }
+                    },
+                    stride as usize,
+                    flags,
+                );
+            }
+        }
+    }
+
+    /// Read the bound index buffer, `None` marking primitive restarts.
+    unsafe fn indices(
+        &self,
+        range: Range<hal::IndexCount>,
+        base_vertex: hal::VertexOffset,
+    ) -> Vec<Option<u32>> {
+        let (buffer, index_type) = match self.index_buffer {
+            Some((ref buffer, index_type)) => (buffer, index_type),
+            None => return Vec::new(),
+        };
+        let restart = self
+            .graphics_pipeline
+            .as_ref()
+            .and_then(|p| p.restart_index)
+            .map(|_| match index_type {
+                hal::IndexType::U16 => 0xFFFF,
+                hal::IndexType::U32 => !0,
+            });
+        range
+            .map(|i| {
+                let index = match index_type {
+                    hal::IndexType::U16 => {
+                        let mut bytes = [0; 2];
+                        buffer.read(i as u64 * 2, &mut bytes);
+                        u16::from_le_bytes(bytes) as u32
+                    }
+                    hal::IndexType::U32 => buffer.read_u32(i as u64 * 4),
+                };
+                if Some(index) == restart {
+                    None
+                } else {
+                    Some((index as i32).wrapping_add(base_vertex) as u32)
+                }
+            })
+            .collect()
+    }
+
+    /// Gather the resources of the bound descriptor sets.
+    unsafe fn resources(&self, bind_point: BindPoint) -> Resources {
+        let sets = match bind_point {
+            BindPoint::Graphics => &self.graphics_sets,
+            BindPoint::Compute => &self.compute_sets,
+        };
+        let push_constants = Bound::new(
+            &Memory::new(PUSH_CONSTANTS_SIZE as u64),
+            0,
+            PUSH_CONSTANTS_SIZE as u64,
+        );
+        push_constants.write(0, &self.push_constants);
+        let mut descriptors = HashMap::new();
+        for (set_index, bound) in sets.iter().enumerate() {
+            let bound = match *bound {
+                Some(ref bound) => bound,
+                None => continue,
+            };
+            let mut offsets = bound.offsets.iter();
+            for binding in bound.set.bindings.lock().unwrap().iter() {
+                let dynamic = binding.is_dynamic();
+                for (i, descriptor) in binding.descriptors.iter().enumerate() {
+                    let offset = if dynamic {
+                        offsets.next().cloned().unwrap_or(0)
+                    } else {
+                        0
+                    };
+                    let descriptor = match *descriptor {
+                        Some(Descriptor::Buffer(ref bound)) if dynamic => {
+                            Descriptor::Buffer(bound.shift(offset as u64))
+                        }
+                        Some(ref descriptor) => descriptor.clone(),
+                        None => continue,
+                    };
+                    descriptors.insert((set_index as u32, binding.binding, i), descriptor);
+                }
+            }
+        }
+        Resources {
+            push_constants,
+            descriptors,
+        }
+    }
+
+    unsafe fn dispatch(&mut self, count: hal::WorkGroupCount) {
+        let program = match self.compute_pipeline {
+            Some(ref program) => Arc::clone(program),
+            None => return,
+        };
+        let resources = self.resources(BindPoint::Compute);
+        let size = program.local_size;
+        let set = |invocation: &mut Invocation, builtin: u32, value: Value| {
+            if let Some(interface) = program.builtin_inputs.get(&builtin) {
+                invocation.set(interface, value);
+            }
+        };
+        for gz in 0 .. count[2] {
+            for gy in 0 .. count[1] {
+                for gx in 0 .. count[0] {
+                    let mut shared = program.shared.clone();
+                    for lz in 0 .. size[2] {
+                        for ly in 0 .. size[1] {
+                            for lx in 0 .. size[0] {
+                                let mut invocation = Invocation::new(&program, &resources);
+                                let global =
+                                    [gx * size[0] + lx, gy * size[1] + ly, gz * size[2] + lz];
+                                let index = (lz * size[1] + ly) * size[0] + lx;
+                                set(
+                                    &mut invocation,
+                                    builtin::GLOBAL_INVOCATION_ID,
+                                    Value::vector(global.to_vec()),
+                                );
+                                set(
+                                    &mut invocation,
+                                    builtin::LOCAL_INVOCATION_ID,
+                                    Value::vector(vec![lx, ly, lz]),
+                                );
+                                set(
+                                    &mut invocation,
+                                    builtin::WORKGROUP_ID,
+                                    Value::vector(vec![gx, gy, gz]),
+                                );
+                                set(
+                                    &mut invocation,
+                                    builtin::NUM_WORKGROUPS,
+                                    Value::vector(count.to_vec()),
+                                );
+                                set(
+                                    &mut invocation,
+                                    builtin::LOCAL_INVOCATION_INDEX,
+                                    Value::Scalar(index),
+                                );
+                                let _ = invocation.run(&mut shared);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    unsafe fn draw(&mut self, vertices: &[Option<u32>], instances: Range<hal::InstanceCount>) {
+        let (pipeline, state) = match (&self.graphics_pipeline, &self.render_pass) {
+            (Some(pipeline), Some(state)) => (Arc::clone(pipeline), state),
+            _ => return,
+        };
+        let subpass = match state.desc.subpasses.get(state.subpass) {
+            Some(subpass) => subpass,
+            None => return,
+        };
+        let viewport = match self.viewports.first() {
+            Some(viewport) => viewport.clone(),
+            None => return,
+        };
+        let scissor = self.scissors.first().cloned().unwrap_or(state.area);
+        let bounds = intersect(intersect(scissor, state.area), attachments_rect(state));
+        let resources = self.resources(BindPoint::Graphics);
+        let attachment = |id: Option<usize>| id.and_then(|id| state.attachments.get(id));
+        let mut context = DrawContext {
+            pipeline: &pipeline,
+            resources: &resources,
+            vertex_buffers: &self.vertex_buffers,
+            colors: subpass.colors.iter().map(|&id| attachment(id)).collect(),
+            depth: attachment(subpass.depth_stencil),
+            viewport,
+            bounds,
+            blend_constants: self.blend_constants,
+            passed: 0,
+        };
+        context.draw(vertices, instances);
+        for &(ref pool, id) in &self.occlusion_queries {
+            pool.add(id, context.passed);
+        }
+    }
+
+    unsafe fn clear_attachments(&self, clears: &[com::AttachmentClear], rects: &[pso::ClearRect]) {
+        let state = match self.render_pass {
+            Some(ref state) => state,
+            None => return,
+        };
+        let subpass = match state.desc.subpasses.get(state.subpass) {
+            Some(subpass) => subpass,
+            None => return,
+        };
+        for clear in clears {
+            let (id, texel) = match *clear {
+                com::AttachmentClear::Color { index, value } => (
+                    subpass.colors.get(index).cloned().unwrap_or(None),
+                    value.uint32,
+                ),
+                com::AttachmentClear::DepthStencil {
+                    depth: Some(depth), ..
+                } => (
+                    subpass.depth_stencil,
+                    [depth.to_bits(), 0, 0, 1f32.to_bits()],
+                ),
+                com::AttachmentClear::DepthStencil { depth: None, .. } => continue,
+            };
+            let view = match id.and_then(|id| state.attachments.get(id)) {
+                Some(view) => view,
+                None => continue,
+            };
+            for rect in rects {
+                clear_view(view, rect.rect, rect.layers.clone(), texel);
+            }
+        }
+    }
+}
+
+fn intersect(a: pso::Rect, b: pso::Rect) -> pso::Rect {
+    let x = a.x.max(b.x);
+    let y = a.y.max(b.y);
+    let right = (a.x as i32 + a.w as i32).min(b.x as i32 + b.w as i32);
+    let bottom = (a.y as i32 + a.h as i32).min(b.y as i32 + b.h as i32);
+    pso::Rect {
+        x,
+        y,
+        w: (right - x as i32).max(0) as i16,
+        h: (bottom - y as i32).max(0) as i16,
+    }
+}
+
+/// Rectangle covered by all the attachments of a render pass.
+fn attachments_rect(state: &RenderPassState) -> pso::Rect {
+    let (mut w, mut h) = (i16::MAX as u32, i16::MAX as u32);
+    for view in state.attachments.iter() {
+        let extent = view.extent();
+        w = w.min(extent.width);
+        h = h.min(extent.height);
+    }
+    pso::Rect {
+        x: 0,
+        y: 0,
+        w: w as i16,
+        h: h as i16,
+    }
+}
+
+/// Texel a clear value writes to an image.
+unsafe fn clear_texel(image: &Image, value: com::ClearValue) -> Texel {
+    if image.layout.depth {
+        let depth = value.depth_stencil.depth;
+        [depth.to_bits(), 0, 0, 1f32.to_bits()]
+    } else {
+        value.color.uint32
+    }
+}
+
+unsafe fn fill_rect(
+    image: &Image,
+    level: image::Level,
+    layer: image::Layer,
+    origin: [u32; 2],
+    extent: image::Extent,
+    texel: Texel,
+) {
+    let full = image.level_extent(level);
+    let mut bytes = [0; 16];
+    let size = image.layout.size();
+    image.layout.write(texel, &mut bytes[.. size]);
+    let width = extent.width.min(full.width.saturating_sub(origin[0]));
+    let row: Vec<u8> = (0 .. width).flat_map(|_| bytes[.. size].to_vec()).collect();
+    for z in 0 .. extent.depth.min(full.depth) {
+        for y in origin[1] .. (origin[1] + extent.height).min(full.height) {
+            image.write_raw(level, layer, [origin[0], y, z], &row);
+        }
+    }
+}
+
+unsafe fn clear_view(view: &ImageView, rect: pso::Rect, layers: Range<image::Layer>, texel: Texel) {
+    let view_layers = view.range.layers.end - view.range.layers.start;
+    let x = rect.x.max(0) as u32;
+    let y = rect.y.max(0) as u32;
+    let extent = image::Extent {
+        width: (rect.w.max(0) as u32 + rect.x.min(0) as u32).min(rect.w.max(0) as u32),
+        height: rect.h.max(0) as u32,
+        depth: 1,
+    };
+    for layer in layers.start .. layers.end.min(view_layers) {
+        fill_rect(
+            &view.image,
+            view.range.levels.start,
+            view.range.layers.start + layer,
+            [x, y],
+            extent,
+            texel,
+        );
+    }
+}
+
+unsafe fn copy_image(
+    src: &Image,
+    src_sub: &image::SubresourceLayers,
+    src_offset: image::Offset,
+    dst: &Image,
+    dst_sub: &image::SubresourceLayers,
+    dst_offset: image::Offset,
+    extent: image::Extent,
+) {
+    let size = src.layout.size();
+    let mut row = vec![0; extent.width as usize * size];
+    let layers = src_sub.layers.end - src_sub.layers.start;
+    for layer in 0 .. layers {
+        for z in 0 .. extent.depth {
+            for y in 0 .. extent.height {
+                let src_coord = [
+                    src_offset.x as u32,
+                    src_offset.y as u32 + y,
+                    src_offset.z as u32 + z,
+                ];
+                let dst_coord = [
+                    dst_offset.x as u32,
+                    dst_offset.y as u32 + y,
+                    dst_offset.z as u32 + z,
+                ];
+                src.read_raw(
+                    src_sub.level,
+                    src_sub.layers.start + layer,
+                    src_coord,
+                    &mut row,
+                );
+                dst.write_raw(dst_sub.level, dst_sub.layers.start + layer, dst_coord, &row);
+            }
+        }
+    }
+}
+
+unsafe fn copy_buffer_image(
+    buffer: &Bound,
+    image: &Image,
+    region: &com::BufferImageCopy,
+    to_image: bool,
+) {
+    let size = image.layout.size() as u64;
+    let extent = region.image_extent;
+    let width = if region.buffer_width == 0 {
+        extent.width
+    } else {
+        region.buffer_width
+    };
+    let height = if region.buffer_height == 0 {
+        extent.height
+    } else {
+        region.buffer_height
+    };
+    let row_pitch = width as u64 * size;
+    let slice_pitch = height as u64 * row_pitch;
+    let layer_pitch = slice_pitch * extent.depth as u64;
+    let mut row = vec![0; (extent.width as u64 * size) as usize];
+    let sub = &region.image_layers;
+    for (i, layer) in sub.layers.clone().enumerate() {
+        for z in 0 .. extent.depth {
+            for y in 0 .. extent.height {
+                let offset = region.buffer_offset
+                    + i as u64 * layer_pitch
+                    + z as u64 * slice_pitch
+                    + y as u64 * row_pitch;
+                let coord = [
+                    region.image_offset.x as u32,
+                    region.image_offset.y as u32 + y,
+                    region.image_offset.z as u32 + z,
+                ];
+                if to_image {
+                    buffer.read(offset, &mut row);
+                    image.write_raw(sub.level, layer, coord, &row);
+                } else {
+                    image.read_raw(sub.level, layer, coord, &mut row);
+                    buffer.write(offset, &row);
+                }
+            }
+        }
+    }
+}
+
+unsafe fn blit_image(src: &Image, dst: &Image, filter: image::Filter, region: &com::ImageBlit) {
+    let (sb, db) = (&region.src_bounds, &region.dst_bounds);
+    let src_extent = src.level_extent(region.src_subresource.level);
+    let (dx0, dx1) = signed_range(db.start.x, db.end.x);
+    let (dy0, dy1) = signed_range(db.start.y, db.end.y);
+    let (dz0, dz1) = signed_range(db.start.z, db.end.z);
+    // Map a destination texel center to a source coordinate along an axis.
+    let map = |d: i32, d_start: i32, d_end: i32, s_start: i32, s_end: i32| -> f32 {
+        let t = (d as f32 + 0.5 - d_start as f32) / (d_end - d_start) as f32;
+        s_start as f32 + t * (s_end - s_start) as f32
+    };
+    let linear = filter == image::Filter::Linear && !src.layout.is_integer();
+    let clamp = |v: i32, max: u32| v.max(0).min(max as i32 - 1) as u32;
+    let layers = region.src_subresource.layers.end - region.src_subresource.layers.start;
+    for layer in 0 .. layers {
+        let src_layer = region.src_subresource.layers.start + layer;
+        let dst_layer = region.dst_subresource.layers.start + layer;
+        let read = |x: i32, y: i32, z: i32| {
+            let coord = [
+                clamp(x, src_extent.width),
+                clamp(y, src_extent.height),
+                clamp(z, src_extent.depth),
+            ];
+            src.read(region.src_subresource.level, src_layer, coord)
+        };
+        for z in dz0 .. dz1 {
+            for y in dy0 .. dy1 {
+                for x in dx0 .. dx1 {
+                    let sx = map(x, db.start.x, db.end.x, sb.start.x, sb.end.x);
+                    let sy = map(y, db.start.y, db.end.y, sb.start.y, sb.end.y);
+                    let sz = map(z, db.start.z, db.end.z, sb.start.z, sb.end.z);
+                    let texel = if linear {
+                        let (fx, fy) = (sx - 0.5, sy - 0.5);
+                        let (x0, y0) = (fx.floor() as i32, fy.floor() as i32);
+                        let (ax, ay) = (fx - fx.floor(), fy - fy.floor());
+                        let z0 = sz.floor() as i32;
+                        let sample = |x, y| src.layout.to_float(read(x, y, z0));
+                        let (t00, t10) = (sample(x0, y0), sample(x0 + 1, y0));
+                        let (t01, t11) = (sample(x0, y0 + 1), sample(x0 + 1, y0 + 1));
+                        let mut value = [0.0; 4];
+                        for c in 0 .. 4 {
+                            let top = t00[c] + (t10[c] - t00[c]) * ax;
+                            let bottom = t01[c] + (t11[c] - t01[c]) * ax;
+                            value[c] = top + (bottom - top) * ay;
+                        }
+                        dst.layout.to_texel(value)
+                    } else {
+                        let texel = read(sx.floor() as i32, sy.floor() as i32, sz.floor() as i32);
+                        dst.layout.to_texel(src.layout.to_float(texel))
+                    };
+                    let coord = [x as u32, y as u32, z as u32];
+                    dst.write(region.dst_subresource.level, dst_layer, coord, texel);
+                }
+            }
+        }
+    }
+}
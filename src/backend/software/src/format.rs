@@ -0,0 +1,300 @@
+//! Encoding and decoding of texels in memory.
+//!
+//! Texels are handled in the 32-bit representation used by the shaders:
+//! normalized, scaled and floating-point channels as `f32` bits, and integer
+//! channels as (sign-extended) integers.
+
+use hal::format::{ChannelType, Format, SurfaceType};
+
+/// Texel value in the shader representation, with channels in RGBA order.
+pub type Texel = [u32; 4];
+
+/// Memory layout of the texels of a format.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TexelLayout {
+    /// Size of every channel in bytes.
+    pub channel_size: u8,
+    /// Number of channels stored in memory.
+    pub channels: u8,
+    /// Whether the red and blue channels are swapped in memory.
+    pub bgr: bool,
+    pub ty: ChannelType,
+    /// Whether the only channel is depth.
+    pub depth: bool,
+}
+
+impl TexelLayout {
+    /// Return the layout of a format, if the format is supported.
+    ///
+    /// Packed, compressed and stencil formats aren't supported.
+    pub fn of(format: Format) -> Option<Self> {
+        use hal::format::ChannelType as Ct;
+
+        let base = format.base_format();
+        let (channel_size, channels, bgr, depth) = match base.0 {
+            SurfaceType::R8 => (1, 1, false, false),
+            SurfaceType::R8_G8 => (1, 2, false, false),
+            SurfaceType::R8_G8_B8 => (1, 3, false, false),
+            SurfaceType::B8_G8_R8 => (1, 3, true, false),
+            SurfaceType::R8_G8_B8_A8 => (1, 4, false, false),
+            SurfaceType::B8_G8_R8_A8 => (1, 4, true, false),
+            SurfaceType::R16 => (2, 1, false, false),
+            SurfaceType::R16_G16 => (2, 2, false, false),
+            SurfaceType::R16_G16_B16 => (2, 3, false, false),
+            SurfaceType::R16_G16_B16_A16 => (2, 4, false, false),
+            SurfaceType::R32 => (4, 1, false, false),
+            SurfaceType::R32_G32 => (4, 2, false, false),
+            SurfaceType::R32_G32_B32 => (4, 3, false, false),
+            SurfaceType::R32_G32_B32_A32 => (4, 4, false, false),
+            SurfaceType::D16 => (2, 1, false, true),
+            SurfaceType::D32 => (4, 1, false, true),
+            _ => return None,
+        };
+        let supported = match base.1 {
+            Ct::Uint | Ct::Sint => true,
+            Ct::Unorm | Ct::Snorm | Ct::Uscaled | Ct::Sscaled => channel_size != 4,
+            Ct::Srgb => channel_size == 1,
+            Ct::Sfloat => channel_size != 1,
+            Ct::Ufloat => false,
+        };
+        if !supported {
+            return None;
+        }
+        Some(TexelLayout {
+            channel_size,
+            channels,
+            bgr,
+            ty: base.1,
+            depth,
+        })
+    }
+
+    /// Size of a texel in bytes.
+    pub fn size(&self) -> usize {
+        self.channel_size as usize * self.channels as usize
+    }
+
+    /// Whether the channels hold integers rather than `f32` bits.
+    pub fn is_integer(&self) -> bool {
+        matches!(self.ty, ChannelType::Uint | ChannelType::Sint)
+    }
+
+    /// Decode a texel, filling the missing channels with `(0, 0, 0, 1)`.
+    pub fn read(&self, bytes: &[u8]) -> Texel {
+        let one = if self.is_integer() { 1 } else { 1f32.to_bits() };
+        let mut texel = [0, 0, 0, one];
+        for i in 0 .. self.channels as usize {
+            let size = self.channel_size as usize;
+            let raw = bytes[i * size .. (i + 1) * size]
+                .iter()
+                .rev()
+                .fold(0u32, |u, &b| (u << 8) | b as u32);
+            let value = self.decode(raw, i == 3);
+            texel[self.channel(i)] = value;
+        }
+        texel
+    }
+
+    /// Encode a texel, ignoring the channels that aren't stored.
+    pub fn write(&self, texel: Texel, bytes: &mut [u8]) {
+        for i in 0 .. self.channels as usize {
+            let size = self.channel_size as usize;
+            let raw = self.encode(texel[self.channel(i)], i == 3);
+            for (j, byte) in bytes[i * size .. (i + 1) * size].iter_mut().enumerate() {
+                *byte = (raw >> (j * 8)) as u8;
+            }
+        }
+    }
+
+    /// Convert a decoded texel to floating-point values.
+    pub fn to_float(self, texel: Texel) -> [f32; 4] {
+        let mut out = [0.0; 4];
+        for (o, &t) in out.iter_mut().zip(texel.iter()) {
+            *o = match self.ty {
+                ChannelType::Uint => t as f32,
+                ChannelType::Sint => t as i32 as f32,
+                _ => f32::from_bits(t),
+            };
+        }
+        out
+    }
+
+    /// Convert floating-point values to a texel of this layout.
+    pub fn to_texel(self, values: [f32; 4]) -> Texel {
+        let mut out = [0; 4];
+        for (o, &v) in out.iter_mut().zip(values.iter()) {
+            *o = match self.ty {
+                ChannelType::Uint => v as u32,
+                ChannelType::Sint => v as i32 as u32,
+                _ => v.to_bits(),
+            };
+        }
+        out
+    }
+
+    /// Index of the RGBA channel stored at position `i` in memory.
+    fn channel(&self, i: usize) -> usize {
+        match i {
+            0 | 2 if self.bgr => 2 - i,
+            _ => i,
+        }
+    }
+
+    fn max(&self) -> u32 {
+        match self.channel_size {
+            4 => !0,
+            size => (1 << (size * 8)) - 1,
+        }
+    }
+
+    fn decode(&self, raw: u32, alpha: bool) -> u32 {
+        let bits = self.channel_size as u32 * 8;
+        let sign_extend = |raw: u32| (raw << (32 - bits)) as i32 >> (32 - bits);
+        let value = match self.ty {
+            ChannelType::Uint => return raw,
+            ChannelType::Sint => return sign_extend(raw) as u32,
+            ChannelType::Unorm => raw as f32 / self.max() as f32,
+            ChannelType::Snorm => (sign_extend(raw) as f32 / (self.max() >> 1) as f32).max(-1.0),
+            ChannelType::Uscaled => raw as f32,
+            ChannelType::Sscaled => sign_extend(raw) as f32,
+            ChannelType::Srgb if alpha => raw as f32 / self.max() as f32,
+            ChannelType::Srgb => srgb_to_linear(raw as f32 / self.max() as f32),
+            ChannelType::Sfloat if self.channel_size == 2 => f16_to_f32(raw as u16),
+            ChannelType::Sfloat | ChannelType::Ufloat => return raw,
+        };
+        value.to_bits()
+    }
+
+    fn encode(&self, value: u32, alpha: bool) -> u32 {
+        let float = f32::from_bits(value);
+        let max = self.max() as f32;
+        let half = (self.max() >> 1) as f32;
+        let mask = self.max();
+        let raw = match self.ty {
+            ChannelType::Uint | ChannelType::Sint => value,
+            ChannelType::Unorm => (float.clamp(0.0, 1.0) * max).round() as u32,
+            ChannelType::Snorm => (float.clamp(-1.0, 1.0) * half).round() as i32 as u32,
+            ChannelType::Uscaled => float.max(0.0).min(max) as u32,
+            ChannelType::Sscaled => float.max(-half - 1.0).min(half) as i32 as u32,
+            ChannelType::Srgb if alpha => (float.clamp(0.0, 1.0) * max).round() as u32,
+            ChannelType::Srgb => (linear_to_srgb(float.clamp(0.0, 1.0)) * max).round() as u32,
+            ChannelType::Sfloat if self.channel_size == 2 => f32_to_f16(float) as u32,
+            ChannelType::Sfloat | ChannelType::Ufloat => value,
+        };
+        raw & mask
+    }
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Convert a half-precision float to single precision.
+pub fn f16_to_f32(half: u16) -> f32 {
+    let sign = (half as u32 & 0x8000) << 16;
+    let exponent = (half >> 10) & 0x1F;
+    let mantissa = half as u32 & 0x3FF;
+    let bits = match exponent {
+        0 if mantissa == 0 => sign,
+        0 => {
+            // Subnormal, normalized in single precision.
+            let shift = mantissa.leading_zeros() - 21;
+            let mantissa = (mantissa << shift) & 0x3FF;
+            sign | ((113 - shift) << 23) | (mantissa << 13)
+        }
+        0x1F => sign | 0x7F80_0000 | (mantissa << 13),
+        _ => sign | ((exponent as u32 + 112) << 23) | (mantissa << 13),
+    };
+    f32::from_bits(bits)
+}
+
+/// Convert a single precision float to half precision, rounding to nearest even.
+pub fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xFF) as i32;
+    let mantissa = bits & 0x7F_FFFF;
+    if exponent == 0xFF {
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7C00 | nan;
+    }
+    let exponent = exponent - 112;
+    if exponent >= 0x1F {
+        return sign | 0x7C00;
+    }
+    let (mantissa, shift) = if exponent <= 0 {
+        if exponent < -10 {
+            return sign;
+        }
+        (mantissa | 0x80_0000, (14 - exponent) as u32)
+    } else {
+        (mantissa, 13)
+    };
+    let half_mantissa = mantissa >> shift;
+    let remainder = mantissa & ((1 << shift) - 1);
+    let halfway = 1 << (shift - 1);
+    let round = remainder > halfway || (remainder == halfway && half_mantissa & 1 != 0);
+    let exponent = if exponent <= 0 { 0 } else { exponent as u32 };
+    // A carry out of the mantissa correctly bumps the exponent.
+    sign | ((exponent << 10) + half_mantissa + round as u32) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let layout = TexelLayout::of(Format::Bgra8Unorm).unwrap();
+        let texel = layout.read(&[0, 51, 255, 128]);
+        assert_eq!(layout.to_float(texel), [1.0, 0.2, 0.0, 128.0 / 255.0]);
+        let mut bytes = [0; 4];
+        layout.write(texel, &mut bytes);
+        assert_eq!(bytes, [0, 51, 255, 128]);
+
+        let layout = TexelLayout::of(Format::Rg16Sint).unwrap();
+        let texel = layout.read(&[0xFF, 0xFF, 2, 0]);
+        assert_eq!(texel, [!0, 2, 0, 1]);
+
+        let layout = TexelLayout::of(Format::R8Snorm).unwrap();
+        assert_eq!(layout.to_float(layout.read(&[0x80]))[0], -1.0);
+
+        let layout = TexelLayout::of(Format::Rgba8Srgb).unwrap();
+        let mut bytes = [0; 4];
+        layout.write(layout.to_texel([0.5, 0.0, 1.0, 0.5]), &mut bytes);
+        assert_eq!(bytes, [188, 0, 255, 128]);
+
+        assert_eq!(TexelLayout::of(Format::Bc1RgbUnorm), None);
+        assert_eq!(TexelLayout::of(Format::D24UnormS8Uint), None);
+    }
+
+    #[test]
+    fn test_half_float() {
+        for &(half, float) in &[
+            (0x0000u16, 0.0f32),
+            (0x3C00, 1.0),
+            (0xC000, -2.0),
+            (0x3555, 0.333_251_95),
+            (0x0001, 5.960_464_5e-8),
+            (0x7BFF, 65504.0),
+            (0x7C00, f32::INFINITY),
+        ] {
+            assert_eq!(f16_to_f32(half), float);
+            assert_eq!(f32_to_f16(float), half);
+        }
+        assert_eq!(f32_to_f16(1.0 / 3.0), 0x3555);
+        assert_eq!(f32_to_f16(1e6), 0x7C00);
+    }
+}
@@ -0,0 +1,1135 @@
+//! Execution of specialized SPIR-V entry points, one invocation at a time.
+//!
+//! Invocations run to completion one after another, so atomics are plain
+//! read-modify-write operations and barriers synchronizing invocations
+//! aren't available. Implicit level of detail is always `0`, since there
+//! are no derivatives.
+
+use crate::{
+    format::{f16_to_f32, f32_to_f16},
+    resource::{Bound, Descriptor, ImageView, ViewLevels},
+    spirv::{glsl, op, DescriptorKey, Id, Inst, Pointer, Program, Resources, Type, Value},
+};
+use hal::image;
+
+/// The invocation was discarded by the fragment shader.
+#[derive(Debug)]
+pub struct Discard;
+
+fn f(bits: u32) -> f32 {
+    f32::from_bits(bits)
+}
+
+fn u(value: f32) -> u32 {
+    value.to_bits()
+}
+
+fn map1(a: &Value, fun: &dyn Fn(u32) -> u32) -> Value {
+    match *a {
+        Value::Scalar(x) => Value::Scalar(fun(x)),
+        Value::Composite(ref xs) => Value::Composite(xs.iter().map(|x| map1(x, fun)).collect()),
+        ref other => other.clone(),
+    }
+}
+
+fn map2(a: &Value, b: &Value, fun: &dyn Fn(u32, u32) -> u32) -> Value {
+    match (a, b) {
+        (Value::Scalar(x), Value::Scalar(y)) => Value::Scalar(fun(*x, *y)),
+        (Value::Composite(xs), Value::Composite(ys)) => {
+            Value::Composite(xs.iter().zip(ys).map(|(x, y)| map2(x, y, fun)).collect())
+        }
+        (Value::Composite(xs), y) => Value::Composite(xs.iter().map(|x| map2(x, y, fun)).collect()),
+        (x, Value::Composite(ys)) => Value::Composite(ys.iter().map(|y| map2(x, y, fun)).collect()),
+        _ => a.clone(),
+    }
+}
+
+fn map3(a: &Value, b: &Value, c: &Value, fun: &dyn Fn(u32, u32, u32) -> u32) -> Value {
+    match *a {
+        Value::Scalar(x) => Value::Scalar(fun(x, b.scalar(), c.scalar())),
+        Value::Composite(ref xs) => {
+            let pick = |v: &Value, i: usize| match *v {
+                Value::Composite(ref vs) => vs.get(i).cloned().unwrap_or(Value::Scalar(0)),
+                ref scalar => scalar.clone(),
+            };
+            Value::Composite(
+                xs.iter()
+                    .enumerate()
+                    .map(|(i, x)| map3(x, &pick(b, i), &pick(c, i), fun))
+                    .collect(),
+            )
+        }
+        ref other => other.clone(),
+    }
+}
+
+fn floats(v: &Value) -> Vec<f32> {
+    v.components().into_iter().map(f).collect()
+}
+
+fn dot(a: &Value, b: &Value) -> f32 {
+    floats(a)
+        .iter()
+        .zip(floats(b).iter())
+        .map(|(x, y)| x * y)
+        .sum()
+}
+
+fn scale(a: &Value, factor: f32) -> Value {
+    map1(a, &|x| u(f(x) * factor))
+}
+
+fn columns(m: &Value) -> Vec<Value> {
+    match *m {
+        Value::Composite(ref columns) => columns.clone(),
+        _ => Vec::new(),
+    }
+}
+
+fn matrix_times_vector(m: &Value, v: &Value) -> Value {
+    let columns = columns(m);
+    let rows = columns.first().map_or(0, |c| c.components().len());
+    let v = floats(v);
+    Value::vector((0 .. rows).map(|r| {
+        u(columns
+            .iter()
+            .zip(&v)
+            .map(|(c, x)| f(c.components()[r]) * x)
+            .sum())
+    }))
+}
+
+fn transpose(m: &Value) -> Value {
+    let columns = columns(m);
+    let rows = columns.first().map_or(0, |c| c.components().len());
+    Value::Composite(
+        (0 .. rows)
+            .map(|r| Value::vector(columns.iter().map(|c| c.components()[r])))
+            .collect(),
+    )
+}
+
+fn all_true(v: &Value) -> bool {
+    v.components().iter().all(|&x| x != 0)
+}
+
+fn signed(x: u32) -> i32 {
+    x as i32
+}
+
+fn fclamp(x: f32, min: f32, max: f32) -> f32 {
+    x.max(min).min(max)
+}
+
+fn round_even(x: f32) -> f32 {
+    let r = x.round();
+    if (x - x.trunc()).abs() == 0.5 && r % 2.0 != 0.0 {
+        r - x.signum()
+    } else {
+        r
+    }
+}
+
+fn image_operand(inst: &Inst, mask_index: usize, bit: u32) -> Option<u32> {
+    let mask = *inst.words.get(mask_index)?;
+    if mask & bit == 0 {
+        return None;
+    }
+    let before = (mask & (bit - 1)).count_ones() as usize;
+    // `Grad` takes two operands, the others only one.
+    let extra = if mask & 0x4 != 0 && bit > 0x4 { 1 } else { 0 };
+    inst.words.get(mask_index + 1 + before + extra).cloned()
+}
+
+/// Split image coordinates into texel coordinates and array layer.
+fn split_layer(view: &ImageView, coords: &[u32]) -> ([u32; 3], u32) {
+    let get = |i: usize| coords.get(i).cloned().unwrap_or(0);
+    match view.kind {
+        image::ViewKind::D1 => ([get(0), 0, 0], 0),
+        image::ViewKind::D1Array => ([get(0), 0, 0], get(1)),
+        image::ViewKind::D2 => ([get(0), get(1), 0], 0),
+        image::ViewKind::D2Array => ([get(0), get(1), 0], get(2)),
+        image::ViewKind::D3 => ([get(0), get(1), get(2)], 0),
+        image::ViewKind::Cube | image::ViewKind::CubeArray => ([0; 3], !0),
+    }
+}
+
+/// State of a single invocation of an entry point.
+pub struct Invocation<'a> {
+    program: &'a Program,
+    resources: &'a Resources,
+    slots: Vec<Value>,
+    values: Vec<Option<Value>>,
+}
+
+impl<'a> Invocation<'a> {
+    pub fn new(program: &'a Program, resources: &'a Resources) -> Self {
+        Invocation {
+            program,
+            resources,
+            slots: program.slots.clone(),
+            values: vec![None; program.constants.len()],
+        }
+    }
+
+    /// Set an input or output variable.
+    pub fn set(&mut self, interface: &crate::spirv::Interface, value: Value) {
+        if let Some(slot) = self.slots.get_mut(interface.slot) {
+            if let Some(target) = slot.at_path_mut(&interface.path) {
+                *target = value;
+            }
+        }
+    }
+
+    /// Read an input or output variable.
+    pub fn get(&self, interface: &crate::spirv::Interface) -> Value {
+        self.slots
+            .get(interface.slot)
+            .and_then(|slot| slot.at_path(&interface.path))
+            .cloned()
+            .unwrap_or(Value::Scalar(0))
+    }
+
+    /// Run the entry point, with the workgroup variables in `shared`.
+    pub fn run(&mut self, shared: &mut [Value]) -> Result<(), Discard> {
+        let function = self.program.function;
+        self.call(function, Vec::new(), shared).map(drop)
+    }
+
+    fn value(&self, id: Id) -> Value {
+        self.values
+            .get(id as usize)
+            .and_then(|v| v.as_ref())
+            .or_else(|| {
+                self.program
+                    .constants
+                    .get(id as usize)
+                    .and_then(|v| v.as_ref())
+            })
+            .cloned()
+            .unwrap_or(Value::Scalar(0))
+    }
+
+    fn scalar(&self, id: Id) -> u32 {
+        self.value(id).scalar()
+    }
+
+    fn ty(&self, id: Id) -> &'a Type {
+        self.program.module.ty(id)
+    }
+
+    /// Whether the components of a type are floats.
+    fn is_float(&self, ty: Id) -> bool {
+        matches!(*self.program.module.scalar_type(ty), Type::Float)
+    }
+
+    fn is_signed(&self, ty: Id) -> bool {
+        match *self.program.module.scalar_type(ty) {
+            Type::Int { signed } => signed,
+            _ => false,
+        }
+    }
+
+    fn call(
+        &mut self,
+        function: Id,
+        args: Vec<Value>,
+        shared: &mut [Value],
+    ) -> Result<Option<Value>, Discard> {
+        let program = self.program;
+        let function = match program.module.functions.get(&function) {
+            Some(function) => function,
+            None => return Ok(None),
+        };
+        for (&param, arg) in function.params.iter().zip(args) {
+            self.values[param as usize] = Some(arg);
+        }
+        let mut current = 0;
+        let mut previous = None;
+        loop {
+            let block = &function.blocks[current];
+            if let Some(previous) = previous {
+                let incoming = block
+                    .phis
+                    .iter()
+                    .map(|phi| {
+                        let pairs = phi.words[2 ..].chunks(2);
+                        let value = pairs
+                            .filter(|pair| pair.len() == 2 && pair[1] == previous)
+                            .map(|pair| self.value(pair[0]))
+                            .next()
+                            .unwrap_or(Value::Scalar(0));
+                        (phi.words[1], value)
+                    })
+                    .collect::<Vec<_>>();
+                for (id, value) in incoming {
+                    self.values[id as usize] = Some(value);
+                }
+            }
+            let mut next = None;
+            for inst in &block.body {
+                let w = &inst.words;
+                match inst.opcode {
+                    op::BRANCH => next = Some(w[0]),
+                    op::BRANCH_CONDITIONAL => {
+                        next = Some(if self.scalar(w[0]) != 0 { w[1] } else { w[2] })
+                    }
+                    op::SWITCH => {
+                        let selector = self.scalar(w[0]);
+                        let target = w[2 ..]
+                            .chunks(2)
+                            .find(|case| case.len() == 2 && case[0] == selector)
+                            .map_or(w[1], |case| case[1]);
+                        next = Some(target);
+                    }
+                    op::RETURN | op::UNREACHABLE => return Ok(None),
+                    op::RETURN_VALUE => return Ok(Some(self.value(w[0]))),
+                    op::KILL => return Err(Discard),
+                    _ => self.execute(inst, shared)?,
+                }
+            }
+            match next.and_then(|label| function.labels.get(&label)) {
+                Some(&index) => {
+                    previous = Some(block.label);
+                    current = index;
+                }
+                None => return Ok(None),
+            }
+        }
+    }
+
+    fn access(&self, base: Pointer, indices: &[u32]) -> Pointer {
+        let program = self.program;
+        let mut pointer = base;
+        for &index in indices {
+            let index = self.scalar(index);
+            pointer = match pointer {
+                Pointer::Local { slot, mut path } => {
+                    path.push(index);
+                    Pointer::Local { slot, path }
+                }
+                Pointer::Shared { slot, mut path } => {
+                    path.push(index);
+                    Pointer::Shared { slot, path }
+                }
+                Pointer::Binding { key, ty } => match *self.ty(ty) {
+                    Type::Array { element, .. } => {
+                        // Arrays of descriptors are indexed first.
+                        let key = (key.0, key.1, key.2 + index as usize);
+                        self.resolve(Pointer::Binding { key, ty: element })
+                    }
+                    _ => self
+                        .resolve(Pointer::Binding { key, ty })
+                        .offset_into(program, index),
+                },
+                memory @ Pointer::Memory { .. } => memory.offset_into(program, index),
+            };
+        }
+        pointer
+    }
+
+    /// Turn a buffer binding into a pointer to its memory.
+    fn resolve(&self, pointer: Pointer) -> Pointer {
+        match pointer {
+            Pointer::Binding { key, ty } => match *self.ty(ty) {
+                Type::Struct { .. } => Pointer::Memory {
+                    buffer: Some(key),
+                    offset: 0,
+                    ty,
+                    matrix_stride: None,
+                },
+                _ => Pointer::Binding { key, ty },
+            },
+            other => other,
+        }
+    }
+
+    fn buffer(&self, buffer: Option<DescriptorKey>) -> Option<&'a Bound> {
+        match buffer {
+            None => Some(&self.resources.push_constants),
+            Some(key) => match self.resources.descriptors.get(&key) {
+                Some(Descriptor::Buffer(bound)) => Some(bound),
+                _ => None,
+            },
+        }
+    }
+
+    fn load(&self, pointer: &Pointer, shared: &[Value]) -> Value {
+        match self.resolve(pointer.clone()) {
+            Pointer::Local { slot, ref path } => self.slots.get(slot).and_then(|v| v.at_path(path)),
+            Pointer::Shared { slot, ref path } => shared.get(slot).and_then(|v| v.at_path(path)),
+            Pointer::Binding { key, ty } => {
+                return match *self.ty(ty) {
+                    Type::Image => Value::Handle {
+                        image: Some(key),
+                        sampler: None,
+                    },
+                    Type::Sampler => Value::Handle {
+                        image: None,
+                        sampler: Some(key),
+                    },
+                    _ => Value::Handle {
+                        image: Some(key),
+                        sampler: Some(key),
+                    },
+                }
+            }
+            Pointer::Memory {
+                buffer,
+                offset,
+                ty,
+                matrix_stride,
+            } => {
+                return match self.buffer(buffer) {
+                    Some(memory) => unsafe {
+                        self.program.read_memory(memory, offset, ty, matrix_stride)
+                    },
+                    None => self.program.zero(ty),
+                }
+            }
+        }
+        .cloned()
+        .unwrap_or(Value::Scalar(0))
+    }
+
+    fn store(&mut self, pointer: &Pointer, value: Value, shared: &mut [Value]) {
+        match self.resolve(pointer.clone()) {
+            Pointer::Local { slot, ref path } => {
+                if let Some(target) = self.slots.get_mut(slot).and_then(|v| v.at_path_mut(path)) {
+                    *target = value;
+                }
+            }
+            Pointer::Shared { slot, ref path } => {
+                if let Some(target) = shared.get_mut(slot).and_then(|v| v.at_path_mut(path)) {
+                    *target = value;
+                }
+            }
+            Pointer::Binding { .. } => {}
+            Pointer::Memory {
+                buffer,
+                offset,
+                ty,
+                matrix_stride,
+            } => {
+                if let Some(memory) = self.buffer(buffer) {
+                    unsafe {
+                        self.program
+                            .write_memory(memory, offset, ty, matrix_stride, &value)
+                    };
+                }
+            }
+        }
+    }
+
+    fn pointer(&self, id: Id) -> Pointer {
+        match self.value(id) {
+            Value::Pointer(pointer) => pointer,
+            _ => Pointer::Local {
+                slot: !0,
+                path: Vec::new(),
+            },
+        }
+    }
+
+    fn image_view(&self, handle: &Value) -> Option<&'a ImageView> {
+        match *handle {
+            Value::Handle {
+                image: Some(key), ..
+            } => match self.resources.descriptors.get(&key)? {
+                Descriptor::Image(view) | Descriptor::CombinedImageSampler(view, _) => Some(view),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn texel_buffer(&self, handle: &Value) -> Option<(&'a Bound, crate::format::TexelLayout)> {
+        match *handle {
+            Value::Handle {
+                image: Some(key), ..
+            } => match self.resources.descriptors.get(&key)? {
+                Descriptor::TexelBuffer(bound, layout) => Some((bound, *layout)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn sampler(&self, handle: &Value) -> Option<&'a auxil::sampler::Sampler> {
+        match *handle {
+            Value::Handle {
+                sampler: Some(key), ..
+            } => match self.resources.descriptors.get(&key)? {
+                Descriptor::Sampler(sampler) | Descriptor::CombinedImageSampler(_, sampler) => {
+                    Some(sampler)
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Convert sampled floats to the component type of `ty`.
+    fn texel_result(&self, ty: Id, texel: [f32; 4]) -> Value {
+        let float = self.is_float(ty);
+        let signed = self.is_signed(ty);
+        let components = texel.iter().map(|&x| {
+            if float {
+                u(x)
+            } else if signed {
+                x as i32 as u32
+            } else {
+                x as u32
+            }
+        });
+        match *self.ty(ty) {
+            Type::Vector { count, .. } => Value::vector(components.take(count as usize)),
+            _ => Value::Scalar(u(texel[0])),
+        }
+    }
+
+    fn sample(&self, inst: &Inst, dref: bool) -> Value {
+        let w = &inst.words;
+        let handle = self.value(w[2]);
+        let coords = floats(&self.value(w[3]));
+        let mask_index = if dref { 5 } else { 4 };
+        let lod = image_operand(inst, mask_index, 0x2)
+            .or_else(|| image_operand(inst, mask_index, 0x1))
+            .map_or(0.0, |id| f(self.scalar(id)));
+        let (view, sampler) = match (self.image_view(&handle), self.sampler(&handle)) {
+            (Some(view), Some(sampler)) => (view, sampler),
+            _ => return self.program.zero(w[0]),
+        };
+        let dimensions = {
+            use auxil::sampler::TexelSource;
+            ViewLevels { view, layer: 0 }.dimensions()
+        };
+        let get = |i: usize| coords.get(i).cloned().unwrap_or(0.0);
+        let layered = matches!(
+            view.kind,
+            image::ViewKind::D1Array | image::ViewKind::D2Array
+        );
+        let layer = if layered {
+            let layers = (view.range.layers.end - view.range.layers.start) as f32;
+            fclamp(get(dimensions).round(), 0.0, layers - 1.0) as image::Layer
+        } else {
+            0
+        };
+        let coord = [
+            get(0),
+            if dimensions > 1 { get(1) } else { 0.0 },
+            if dimensions > 2 { get(2) } else { 0.0 },
+        ];
+        let source = ViewLevels { view, layer };
+        if dref {
+            let reference = f(self.scalar(w[4]));
+            Value::Scalar(u(sampler.sample_compare(&source, coord, lod, reference)))
+        } else {
+            self.texel_result(w[0], sampler.sample(&source, coord, lod))
+        }
+    }
+
+    fn fetch(&self, inst: &Inst) -> Value {
+        let w = &inst.words;
+        let handle = self.value(w[2]);
+        let coords = self.value(w[3]).components();
+        if let Some((bound, layout)) = self.texel_buffer(&handle) {
+            let mut bytes = [0; 16];
+            let size = layout.size();
+            unsafe { bound.read(coords[0] as u64 * size as u64, &mut bytes[.. size]) };
+            return Value::vector(layout.read(&bytes[.. size]).iter().cloned());
+        }
+        let level = if inst.opcode == op::IMAGE_FETCH {
+            image_operand(inst, 4, 0x2).map_or(0, |id| self.scalar(id))
+        } else {
+            0
+        };
+        match self.image_view(&handle) {
+            Some(view) => {
+                let (coord, layer) = split_layer(view, &coords);
+                let levels = (view.range.levels.end - view.range.levels.start) as u32;
+                let layers = (view.range.layers.end - view.range.layers.start) as u32;
+                let extent = view
+                    .image
+                    .level_extent(view.range.levels.start + level as u8);
+                if level >= levels
+                    || layer >= layers
+                    || coord[0] >= extent.width
+                    || coord[1] >= extent.height
+                    || coord[2] >= extent.depth
+                {
+                    return self.program.zero(w[0]);
+                }
+                let texel = unsafe { view.read(level as _, layer as _, coord) };
+                Value::vector(texel.iter().cloned())
+            }
+            None => self.program.zero(w[0]),
+        }
+    }
+
+    fn image_write(&self, inst: &Inst) {
+        let w = &inst.words;
+        let handle = self.value(w[0]);
+        let coords = self.value(w[1]).components();
+        let mut texel = [0; 4];
+        for (t, c) in texel.iter_mut().zip(self.value(w[2]).components()) {
+            *t = c;
+        }
+        if let Some((bound, layout)) = self.texel_buffer(&handle) {
+            let mut bytes = [0; 16];
+            let size = layout.size();
+            layout.write(texel, &mut bytes[.. size]);
+            unsafe { bound.write(coords[0] as u64 * size as u64, &bytes[.. size]) };
+        } else if let Some(view) = self.image_view(&handle) {
+            let (coord, layer) = split_layer(view, &coords);
+            let layers = (view.range.layers.end - view.range.layers.start) as u32;
+            let extent = view.extent();
+            if layer < layers
+                && coord[0] < extent.width
+                && coord[1] < extent.height
+                && coord[2] < extent.depth
+            {
+                unsafe { view.write(0, layer as _, coord, texel) };
+            }
+        }
+    }
+
+    fn query_size(&self, inst: &Inst) -> Value {
+        let w = &inst.words;
+        let handle = self.value(w[2]);
+        if let Some((bound, layout)) = self.texel_buffer(&handle) {
+            return Value::Scalar((bound.size() / layout.size() as u64) as u32);
+        }
+        let view = match self.image_view(&handle) {
+            Some(view) => view,
+            None => return self.program.zero(w[0]),
+        };
+        let level = if inst.opcode == op::IMAGE_QUERY_SIZE_LOD {
+            self.scalar(w[3]) as image::Level
+        } else {
+            0
+        };
+        let extent = view.image.level_extent(view.range.levels.start + level);
+        let layers = (view.range.layers.end - view.range.layers.start) as u32;
+        let size = match view.kind {
+            image::ViewKind::D1 => vec![extent.width],
+            image::ViewKind::D1Array => vec![extent.width, layers],
+            image::ViewKind::D2 | image::ViewKind::Cube => vec![extent.width, extent.height],
+            image::ViewKind::D2Array | image::ViewKind::CubeArray => {
+                vec![extent.width, extent.height, layers]
+            }
+            image::ViewKind::D3 => vec![extent.width, extent.height, extent.depth],
+        };
+        match size.len() {
+            1 => Value::Scalar(size[0]),
+            _ => Value::vector(size),
+        }
+    }
+
+    fn atomic(&mut self, inst: &Inst, shared: &mut [Value]) -> Value {
+        let w = &inst.words;
+        let pointer = self.pointer(w[2]);
+        let old = self.load(&pointer, shared).scalar();
+        let operand = |i: usize| w.get(i).map_or(0, |&id| self.scalar(id));
+        let new = match inst.opcode {
+            op::ATOMIC_LOAD => return Value::Scalar(old),
+            op::ATOMIC_EXCHANGE => operand(5),
+            op::ATOMIC_COMPARE_EXCHANGE => {
+                if old == operand(7) {
+                    operand(6)
+                } else {
+                    old
+                }
+            }
+            op::ATOMIC_I_INCREMENT => old.wrapping_add(1),
+            op::ATOMIC_I_DECREMENT => old.wrapping_sub(1),
+            op::ATOMIC_I_ADD => old.wrapping_add(operand(5)),
+            op::ATOMIC_I_SUB => old.wrapping_sub(operand(5)),
+            op::ATOMIC_S_MIN => signed(old).min(signed(operand(5))) as u32,
+            op::ATOMIC_U_MIN => old.min(operand(5)),
+            op::ATOMIC_S_MAX => signed(old).max(signed(operand(5))) as u32,
+            op::ATOMIC_U_MAX => old.max(operand(5)),
+            op::ATOMIC_AND => old & operand(5),
+            op::ATOMIC_OR => old | operand(5),
+            op::ATOMIC_XOR => old ^ operand(5),
+            _ => old,
+        };
+        self.store(&pointer, Value::Scalar(new), shared);
+        Value::Scalar(old)
+    }
+
+    fn execute(&mut self, inst: &Inst, shared: &mut [Value]) -> Result<(), Discard> {
+        let w = &inst.words;
+        let a = |i: usize| self.value(w[i]);
+        let result = match inst.opcode {
+            op::NOP | op::LINE | op::NO_LINE | op::MEMORY_BARRIER => return Ok(()),
+            op::UNDEF => self.program.zero(w[0]),
+            op::VARIABLE => {
+                let pointee = match *self.ty(w[0]) {
+                    Type::Pointer { pointee, .. } => pointee,
+                    _ => w[0],
+                };
+                let init = match w.get(3) {
+                    Some(&init) => self.value(init),
+                    None => self.program.zero(pointee),
+                };
+                self.slots.push(init);
+                Value::Pointer(Pointer::Local {
+                    slot: self.slots.len() - 1,
+                    path: Vec::new(),
+                })
+            }
+            op::FUNCTION_CALL => {
+                let args = w[3 ..].iter().map(|&id| self.value(id)).collect();
+                self.call(w[2], args, shared)?.unwrap_or(Value::Scalar(0))
+            }
+            op::LOAD => {
+                let pointer = self.pointer(w[2]);
+                self.load(&pointer, shared)
+            }
+            op::STORE => {
+                let (pointer, value) = (self.pointer(w[0]), a(1));
+                self.store(&pointer, value, shared);
+                return Ok(());
+            }
+            op::COPY_MEMORY => {
+                let (target, source) = (self.pointer(w[0]), self.pointer(w[1]));
+                let value = self.load(&source, shared);
+                self.store(&target, value, shared);
+                return Ok(());
+            }
+            op::ACCESS_CHAIN | op::IN_BOUNDS_ACCESS_CHAIN => {
+                Value::Pointer(self.access(self.pointer(w[2]), &w[3 ..]))
+            }
+            op::ARRAY_LENGTH => match self.resolve(self.pointer(w[2])) {
+                Pointer::Memory {
+                    buffer, offset, ty, ..
+                } => {
+                    let size = self.buffer(buffer).map_or(0, |b| b.size());
+                    Value::Scalar(self.program.runtime_array_length(
+                        ty,
+                        w[3],
+                        size.saturating_sub(offset),
+                    ))
+                }
+                _ => Value::Scalar(0),
+            },
+            op::VECTOR_EXTRACT_DYNAMIC => {
+                let index = self.scalar(w[3]);
+                a(2).at_path(&[index]).cloned().unwrap_or(Value::Scalar(0))
+            }
+            op::VECTOR_INSERT_DYNAMIC => {
+                let mut vector = a(2);
+                let index = self.scalar(w[4]);
+                if let Some(target) = vector.at_path_mut(&[index]) {
+                    *target = a(3);
+                }
+                vector
+            }
+            op::VECTOR_SHUFFLE => {
+                let mut all = a(2).components();
+                all.extend(a(3).components());
+                Value::vector(
+                    w[4 ..]
+                        .iter()
+                        .map(|&i| all.get(i as usize).cloned().unwrap_or(0)),
+                )
+            }
+            op::COMPOSITE_CONSTRUCT => {
+                let parts = w[2 ..].iter().map(|&id| self.value(id));
+                match *self.ty(w[0]) {
+                    // Vectors can be built from smaller vectors.
+                    Type::Vector { .. } => Value::vector(parts.flat_map(|part| part.components())),
+                    _ => Value::Composite(parts.collect()),
+                }
+            }
+            op::COMPOSITE_EXTRACT => a(2).at_path(&w[3 ..]).cloned().unwrap_or(Value::Scalar(0)),
+            op::COMPOSITE_INSERT => {
+                let mut composite = a(3);
+                if let Some(target) = composite.at_path_mut(&w[4 ..]) {
+                    *target = a(2);
+                }
+                composite
+            }
+            op::COPY_OBJECT => a(2),
+            op::TRANSPOSE => transpose(&a(2)),
+            op::SAMPLED_IMAGE => match (a(2), a(3)) {
+                (
+                    Value::Handle { image, .. },
+                    Value::Handle {
+                        sampler: Some(sampler),
+                        ..
+                    },
+                ) => Value::Handle {
+                    image,
+                    sampler: Some(sampler),
+                },
+                (image, _) => image,
+            },
+            op::IMAGE => match a(2) {
+                Value::Handle { image, .. } => Value::Handle {
+                    image,
+                    sampler: None,
+                },
+                other => other,
+            },
+            op::IMAGE_SAMPLE_IMPLICIT_LOD | op::IMAGE_SAMPLE_EXPLICIT_LOD => {
+                self.sample(inst, false)
+            }
+            op::IMAGE_SAMPLE_DREF_IMPLICIT_LOD | op::IMAGE_SAMPLE_DREF_EXPLICIT_LOD => {
+                self.sample(inst, true)
+            }
+            op::IMAGE_FETCH | op::IMAGE_READ => self.fetch(inst),
+            op::IMAGE_WRITE => {
+                self.image_write(inst);
+                return Ok(());
+            }
+            op::IMAGE_QUERY_SIZE | op::IMAGE_QUERY_SIZE_LOD => self.query_size(inst),
+            op::IMAGE_QUERY_LEVELS => match self.image_view(&a(2)) {
+                Some(view) => {
+                    Value::Scalar((view.range.levels.end - view.range.levels.start) as u32)
+                }
+                None => Value::Scalar(0),
+            },
+            op::CONVERT_F_TO_U => map1(&a(2), &|x| f(x) as u32),
+            op::CONVERT_F_TO_S => map1(&a(2), &|x| f(x) as i32 as u32),
+            op::CONVERT_S_TO_F => map1(&a(2), &|x| u(signed(x) as f32)),
+            op::CONVERT_U_TO_F => map1(&a(2), &|x| u(x as f32)),
+            op::U_CONVERT | op::S_CONVERT | op::F_CONVERT | op::BITCAST => a(2),
+            op::S_NEGATE => map1(&a(2), &|x| signed(x).wrapping_neg() as u32),
+            op::F_NEGATE => map1(&a(2), &|x| u(-f(x))),
+            op::I_ADD => map2(&a(2), &a(3), &|x, y| x.wrapping_add(y)),
+            op::F_ADD => map2(&a(2), &a(3), &|x, y| u(f(x) + f(y))),
+            op::I_SUB => map2(&a(2), &a(3), &|x, y| x.wrapping_sub(y)),
+            op::F_SUB => map2(&a(2), &a(3), &|x, y| u(f(x) - f(y))),
+            op::I_MUL => map2(&a(2), &a(3), &|x, y| x.wrapping_mul(y)),
+            op::F_MUL => map2(&a(2), &a(3), &|x, y| u(f(x) * f(y))),
+            op::U_DIV => map2(&a(2), &a(3), &|x, y| x.checked_div(y).unwrap_or(0)),
+            op::S_DIV => map2(&a(2), &a(3), &|x, y| {
+                signed(x).checked_div(signed(y)).unwrap_or(0) as u32
+            }),
+            op::F_DIV => map2(&a(2), &a(3), &|x, y| u(f(x) / f(y))),
+            op::U_MOD => map2(&a(2), &a(3), &|x, y| x.checked_rem(y).unwrap_or(0)),
+            op::S_REM => map2(&a(2), &a(3), &|x, y| {
+                signed(x).checked_rem(signed(y)).unwrap_or(0) as u32
+            }),
+            op::S_MOD => map2(&a(2), &a(3), &|x, y| {
+                let r = signed(x).checked_rem(signed(y)).unwrap_or(0);
+                if r != 0 && (r < 0) != (signed(y) < 0) {
+                    (r + signed(y)) as u32
+                } else {
+                    r as u32
+                }
+            }),
+            op::F_REM => map2(&a(2), &a(3), &|x, y| u(f(x) % f(y))),
+            op::F_MOD => map2(&a(2), &a(3), &|x, y| u(f(x) - f(y) * (f(x) / f(y)).floor())),
+            op::VECTOR_TIMES_SCALAR | op::MATRIX_TIMES_SCALAR => scale(&a(2), f(self.scalar(w[3]))),
+            op::VECTOR_TIMES_MATRIX => {
+                let v = a(2);
+                Value::vector(columns(&a(3)).iter().map(|c| u(dot(&v, c))))
+            }
+            op::MATRIX_TIMES_VECTOR => matrix_times_vector(&a(2), &a(3)),
+            op::MATRIX_TIMES_MATRIX => {
+                let m = a(2);
+                Value::Composite(
+                    columns(&a(3))
+                        .iter()
+                        .map(|c| matrix_times_vector(&m, c))
+                        .collect(),
+                )
+            }
+            op::OUTER_PRODUCT => {
+                let v = a(2);
+                Value::Composite(
+                    floats(&a(3))
+                        .iter()
+                        .map(|&factor| scale(&v, factor))
+                        .collect(),
+                )
+            }
+            op::DOT => Value::Scalar(u(dot(&a(2), &a(3)))),
+            op::ANY => Value::Scalar(a(2).components().iter().any(|&x| x != 0) as u32),
+            op::ALL => Value::Scalar(all_true(&a(2)) as u32),
+            op::IS_NAN => map1(&a(2), &|x| f(x).is_nan() as u32),
+            op::IS_INF => map1(&a(2), &|x| f(x).is_infinite() as u32),
+            op::LOGICAL_EQUAL => map2(&a(2), &a(3), &|x, y| (x == y) as u32),
+            op::LOGICAL_NOT_EQUAL => map2(&a(2), &a(3), &|x, y| (x != y) as u32),
+            op::LOGICAL_OR => map2(&a(2), &a(3), &|x, y| (x != 0 || y != 0) as u32),
+            op::LOGICAL_AND => map2(&a(2), &a(3), &|x, y| (x != 0 && y != 0) as u32),
+            op::LOGICAL_NOT => map1(&a(2), &|x| (x == 0) as u32),
+            op::SELECT => {
+                let condition = a(2);
+                match condition {
+                    Value::Scalar(c) => {
+                        if c != 0 {
+                            a(3)
+                        } else {
+                            a(4)
+                        }
+                    }
+                    _ => map3(&condition, &a(3), &a(4), &|c, x, y| {
+                        if c != 0 {
+                            x
+                        } else {
+                            y
+                        }
+                    }),
+                }
+            }
+            op::I_EQUAL => map2(&a(2), &a(3), &|x, y| (x == y) as u32),
+            op::I_NOT_EQUAL => map2(&a(2), &a(3), &|x, y| (x != y) as u32),
+            op::U_GREATER_THAN => map2(&a(2), &a(3), &|x, y| (x > y) as u32),
+            op::S_GREATER_THAN => map2(&a(2), &a(3), &|x, y| (signed(x) > signed(y)) as u32),
+            op::U_GREATER_THAN_EQUAL => map2(&a(2), &a(3), &|x, y| (x >= y) as u32),
+            op::S_GREATER_THAN_EQUAL => map2(&a(2), &a(3), &|x, y| (signed(x) >= signed(y)) as u32),
+            op::U_LESS_THAN => map2(&a(2), &a(3), &|x, y| (x < y) as u32),
+            op::S_LESS_THAN => map2(&a(2), &a(3), &|x, y| (signed(x) < signed(y)) as u32),
+            op::U_LESS_THAN_EQUAL => map2(&a(2), &a(3), &|x, y| (x <= y) as u32),
+            op::S_LESS_THAN_EQUAL => map2(&a(2), &a(3), &|x, y| (signed(x) <= signed(y)) as u32),
+            op::F_ORD_EQUAL ..= op::F_UNORD_GREATER_THAN_EQUAL => {
+                let opcode = inst.opcode;
+                let ordered = (opcode - op::F_ORD_EQUAL) & 1 == 0;
+                map2(&a(2), &a(3), &|x, y| {
+                    let (x, y) = (f(x), f(y));
+                    if x.is_nan() || y.is_nan() {
+                        return !ordered as u32;
+                    }
+                    let result = match (opcode - op::F_ORD_EQUAL) / 2 {
+                        0 => x == y,
+                        1 => x != y,
+                        2 => x < y,
+                        3 => x > y,
+                        4 => x <= y,
+                        _ => x >= y,
+                    };
+                    result as u32
+                })
+            }
+            op::SHIFT_RIGHT_LOGICAL => map2(&a(2), &a(3), &|x, y| x.checked_shr(y).unwrap_or(0)),
+            op::SHIFT_RIGHT_ARITHMETIC => {
+                map2(&a(2), &a(3), &|x, y| (signed(x) >> y.min(31)) as u32)
+            }
+            op::SHIFT_LEFT_LOGICAL => map2(&a(2), &a(3), &|x, y| x.checked_shl(y).unwrap_or(0)),
+            op::BITWISE_OR => map2(&a(2), &a(3), &|x, y| x | y),
+            op::BITWISE_XOR => map2(&a(2), &a(3), &|x, y| x ^ y),
+            op::BITWISE_AND => map2(&a(2), &a(3), &|x, y| x & y),
+            op::NOT => map1(&a(2), &|x| !x),
+            op::BIT_FIELD_INSERT => {
+                let (offset, count) = (self.scalar(w[4]), self.scalar(w[5]));
+                let mask = (1u64 << count.min(32)).wrapping_sub(1) as u32;
+                let mask = mask.checked_shl(offset).unwrap_or(0);
+                map2(&a(2), &a(3), &|base, insert| {
+                    (base & !mask) | (insert.checked_shl(offset).unwrap_or(0) & mask)
+                })
+            }
+            op::BIT_FIELD_S_EXTRACT | op::BIT_FIELD_U_EXTRACT => {
+                let (offset, count) = (self.scalar(w[3]), self.scalar(w[4]));
+                let sign_extend = inst.opcode == op::BIT_FIELD_S_EXTRACT;
+                map1(&a(2), &|x| {
+                    if count == 0 {
+                        return 0;
+                    }
+                    let shifted = x.checked_shl(32 - offset - count).unwrap_or(0);
+                    if sign_extend {
+                        (signed(shifted) >> (32 - count)) as u32
+                    } else {
+                        shifted >> (32 - count)
+                    }
+                })
+            }
+            op::BIT_REVERSE => map1(&a(2), &|x| x.reverse_bits()),
+            op::BIT_COUNT => map1(&a(2), &|x| x.count_ones()),
+            op::ATOMIC_STORE => {
+                let (pointer, value) = (self.pointer(w[0]), a(3));
+                self.store(&pointer, value, shared);
+                return Ok(());
+            }
+            op::ATOMIC_LOAD ..= op::ATOMIC_XOR => self.atomic(inst, shared),
+            op::EXT_INST => self.extended(inst),
+            opcode => {
+                warn!("Unexpected SPIR-V instruction {}", opcode);
+                return Ok(());
+            }
+        };
+        self.values[w[1] as usize] = Some(result);
+        Ok(())
+    }
+
+    fn extended(&self, inst: &Inst) -> Value {
+        let w = &inst.words;
+        let a = |i: usize| self.value(w[4 + i]);
+        let float1 = |fun: &dyn Fn(f32) -> f32| map1(&a(0), &|x| u(fun(f(x))));
+        let float2 = |fun: &dyn Fn(f32, f32) -> f32| map2(&a(0), &a(1), &|x, y| u(fun(f(x), f(y))));
+        let float3 = |fun: &dyn Fn(f32, f32, f32) -> f32| {
+            map3(&a(0), &a(1), &a(2), &|x, y, z| u(fun(f(x), f(y), f(z))))
+        };
+        match w[3] {
+            glsl::ROUND => float1(&|x| x.round()),
+            glsl::ROUND_EVEN => float1(&round_even),
+            glsl::TRUNC => float1(&|x| x.trunc()),
+            glsl::F_ABS => float1(&|x| x.abs()),
+            glsl::S_ABS => map1(&a(0), &|x| signed(x).wrapping_abs() as u32),
+            glsl::F_SIGN => float1(&|x| if x == 0.0 { 0.0 } else { x.signum() }),
+            glsl::S_SIGN => map1(&a(0), &|x| signed(x).signum() as u32),
+            glsl::FLOOR => float1(&|x| x.floor()),
+            glsl::CEIL => float1(&|x| x.ceil()),
+            glsl::FRACT => float1(&|x| x - x.floor()),
+            glsl::RADIANS => float1(&|x| x.to_radians()),
+            glsl::DEGREES => float1(&|x| x.to_degrees()),
+            glsl::SIN => float1(&|x| x.sin()),
+            glsl::COS => float1(&|x| x.cos()),
+            glsl::TAN => float1(&|x| x.tan()),
+            glsl::ASIN => float1(&|x| x.asin()),
+            glsl::ACOS => float1(&|x| x.acos()),
+            glsl::ATAN => float1(&|x| x.atan()),
+            glsl::SINH => float1(&|x| x.sinh()),
+            glsl::COSH => float1(&|x| x.cosh()),
+            glsl::TANH => float1(&|x| x.tanh()),
+            glsl::ASINH => float1(&|x| x.asinh()),
+            glsl::ACOSH => float1(&|x| x.acosh()),
+            glsl::ATANH => float1(&|x| x.atanh()),
+            glsl::ATAN2 => float2(&|y, x| y.atan2(x)),
+            glsl::POW => float2(&|x, y| x.powf(y)),
+            glsl::EXP => float1(&|x| x.exp()),
+            glsl::LOG => float1(&|x| x.ln()),
+            glsl::EXP2 => float1(&|x| x.exp2()),
+            glsl::LOG2 => float1(&|x| x.log2()),
+            glsl::SQRT => float1(&|x| x.sqrt()),
+            glsl::INVERSE_SQRT => float1(&|x| 1.0 / x.sqrt()),
+            glsl::F_MIN | glsl::N_MIN => float2(&|x, y| x.min(y)),
+            glsl::F_MAX | glsl::N_MAX => float2(&|x, y| x.max(y)),
+            glsl::U_MIN => map2(&a(0), &a(1), &|x, y| x.min(y)),
+            glsl::U_MAX => map2(&a(0), &a(1), &|x, y| x.max(y)),
+            glsl::S_MIN => map2(&a(0), &a(1), &|x, y| signed(x).min(signed(y)) as u32),
+            glsl::S_MAX => map2(&a(0), &a(1), &|x, y| signed(x).max(signed(y)) as u32),
+            glsl::F_CLAMP | glsl::N_CLAMP => float3(&fclamp),
+            glsl::U_CLAMP => map3(&a(0), &a(1), &a(2), &|x, min, max| x.max(min).min(max)),
+            glsl::S_CLAMP => map3(&a(0), &a(1), &a(2), &|x, min, max| {
+                signed(x).max(signed(min)).min(signed(max)) as u32
+            }),
+            glsl::F_MIX => float3(&|x, y, t| x * (1.0 - t) + y * t),
+            glsl::STEP => float2(&|edge, x| if x < edge { 0.0 } else { 1.0 }),
+            glsl::SMOOTH_STEP => float3(&|e0, e1, x| {
+                let t = fclamp((x - e0) / (e1 - e0), 0.0, 1.0);
+                t * t * (3.0 - 2.0 * t)
+            }),
+            glsl::FMA => float3(&|x, y, z| x.mul_add(y, z)),
+            glsl::PACK_SNORM_4X8 | glsl::PACK_UNORM_4X8 => {
+                let snorm = w[3] == glsl::PACK_SNORM_4X8;
+                let packed = floats(&a(0)).iter().enumerate().fold(0u32, |acc, (i, &x)| {
+                    let byte = if snorm {
+                        (fclamp(x, -1.0, 1.0) * 127.0).round() as i8 as u8
+                    } else {
+                        (fclamp(x, 0.0, 1.0) * 255.0).round() as u8
+                    };
+                    acc | (byte as u32) << (8 * i)
+                });
+                Value::Scalar(packed)
+            }
+            glsl::UNPACK_SNORM_4X8 | glsl::UNPACK_UNORM_4X8 => {
+                let packed = a(0).scalar();
+                let snorm = w[3] == glsl::UNPACK_SNORM_4X8;
+                Value::vector((0 .. 4).map(|i| {
+                    let byte = (packed >> (8 * i)) as u8;
+                    u(if snorm {
+                        fclamp(byte as i8 as f32 / 127.0, -1.0, 1.0)
+                    } else {
+                        byte as f32 / 255.0
+                    })
+                }))
+            }
+            glsl::PACK_HALF_2X16 => {
+                let v = floats(&a(0));
+                Value::Scalar(f32_to_f16(v[0]) as u32 | (f32_to_f16(v[1]) as u32) << 16)
+            }
+            glsl::UNPACK_HALF_2X16 => {
+                let packed = a(0).scalar();
+                Value::vector(vec![
+                    u(f16_to_f32(packed as u16)),
+                    u(f16_to_f32((packed >> 16) as u16)),
+                ])
+            }
+            glsl::LENGTH => Value::Scalar(u(dot(&a(0), &a(0)).sqrt())),
+            glsl::DISTANCE => {
+                let d = map2(&a(0), &a(1), &|x, y| u(f(x) - f(y)));
+                Value::Scalar(u(dot(&d, &d).sqrt()))
+            }
+            glsl::CROSS => {
+                let (x, y) = (floats(&a(0)), floats(&a(1)));
+                Value::vector(vec![
+                    u(x[1] * y[2] - y[1] * x[2]),
+                    u(x[2] * y[0] - y[2] * x[0]),
+                    u(x[0] * y[1] - y[0] * x[1]),
+                ])
+            }
+            glsl::NORMALIZE => {
+                let v = a(0);
+                scale(&v, 1.0 / dot(&v, &v).sqrt())
+            }
+            glsl::FACE_FORWARD => {
+                let n = a(0);
+                if dot(&a(2), &a(1)) < 0.0 {
+                    n
+                } else {
+                    scale(&n, -1.0)
+                }
+            }
+            glsl::REFLECT => {
+                let (i, n) = (a(0), a(1));
+                let d = 2.0 * dot(&n, &i);
+                map2(&i, &n, &|x, y| u(f(x) - d * f(y)))
+            }
+            glsl::REFRACT => {
+                let (i, n, eta) = (a(0), a(1), f(a(2).scalar()));
+                let d = dot(&n, &i);
+                let k = 1.0 - eta * eta * (1.0 - d * d);
+                if k < 0.0 {
+                    map1(&i, &|_| 0)
+                } else {
+                    let factor = eta * d + k.sqrt();
+                    map2(&i, &n, &|x, y| u(eta * f(x) - factor * f(y)))
+                }
+            }
+            glsl::FIND_I_LSB => map1(&a(0), &|x| {
+                if x == 0 {
+                    !0
+                } else {
+                    x.trailing_zeros()
+                }
+            }),
+            glsl::FIND_S_MSB => map1(&a(0), &|x| {
+                let x = if signed(x) < 0 { !x } else { x };
+                31u32.wrapping_sub(x.leading_zeros())
+            }),
+            glsl::FIND_U_MSB => map1(&a(0), &|x| 31u32.wrapping_sub(x.leading_zeros())),
+            other => {
+                warn!("Unexpected GLSL.std.450 instruction {}", other);
+                Value::Scalar(0)
+            }
+        }
+    }
+}
+
+impl Pointer {
+    /// Pointer to an element of the value in memory.
+    fn offset_into(self, program: &Program, index: u32) -> Self {
+        match self {
+            Pointer::Memory {
+                buffer,
+                offset,
+                ty,
+                matrix_stride,
+            } => {
+                let (element_offset, element, stride) =
+                    program.element_layout(ty, matrix_stride, index);
+                Pointer::Memory {
+                    buffer,
+                    offset: offset + element_offset,
+                    ty: element,
+                    matrix_stride: stride,
+                }
+            }
+            other => other,
+        }
+    }
+}
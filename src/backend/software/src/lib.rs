@@ -0,0 +1,1051 @@
+//! Software backend, executing everything on the CPU.
+//!
+//! Commands are recorded into lists and replayed by the queue on submission,
+//! so submissions complete before returning. Shaders are interpreted from
+//! SPIR-V, one invocation at a time, and triangles are rasterized with
+//! single-sample coverage following the top-left rule.
+//!
+//! The point is to be deterministic and available anywhere, which makes it
+//! suitable to run reference tests in headless environments. It is slow,
+//! and supports a subset of SPIR-V that is checked when creating shader modules.
+
+#![allow(clippy::too_many_arguments)]
+
+extern crate gfx_hal as hal;
+#[macro_use]
+extern crate log;
+
+mod command;
+mod device;
+mod execute;
+mod format;
+mod interpret;
+mod raster;
+mod resource;
+mod spirv;
+
+pub use crate::command::{CommandBuffer, CommandPool};
+pub use crate::device::Device;
+pub use crate::resource::{
+    Buffer,
+    BufferView,
+    ComputePipeline,
+    DescriptorPool,
+    DescriptorSet,
+    DescriptorSetLayout,
+    Event,
+    Fence,
+    Framebuffer,
+    GraphicsPipeline,
+    Image,
+    ImageView,
+    Memory,
+    PipelineLayout,
+    QueryPool,
+    RenderPass,
+    Sampler,
+    ShaderModule,
+};
+
+use crate::{execute::Executor, format::TexelLayout};
+use hal::{adapter, device as d, format as f, image, memory, pso, queue, window};
+
+use std::borrow::Borrow;
+use std::time::Instant;
+
+/// Software backend.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Backend {}
+impl hal::Backend for Backend {
+    type Instance = Instance;
+    type PhysicalDevice = PhysicalDevice;
+    type Device = Device;
+
+    type Surface = Surface;
+    type Swapchain = Swapchain;
+
+    type QueueFamily = QueueFamily;
+    type CommandQueue = CommandQueue;
+    type CommandBuffer = CommandBuffer;
+
+    type Memory = Memory;
+    type CommandPool = CommandPool;
+
+    type ShaderModule = ShaderModule;
+    type RenderPass = RenderPass;
+    type Framebuffer = Framebuffer;
+
+    type Buffer = Buffer;
+    type BufferView = BufferView;
+    type Image = Image;
+    type ImageView = ImageView;
+    type Sampler = Sampler;
+
+    type ComputePipeline = ComputePipeline;
+    type GraphicsPipeline = GraphicsPipeline;
+    type PipelineCache = ();
+    type PipelineLayout = PipelineLayout;
+    type DescriptorSetLayout = DescriptorSetLayout;
+    type DescriptorPool = DescriptorPool;
+    type DescriptorSet = DescriptorSet;
+
+    type Fence = Fence;
+    type Semaphore = ();
+    type Event = Event;
+    type QueryPool = QueryPool;
+}
+
+const MAX_IMAGE_SIZE: u32 = 4096;
+const MAX_LAYERS: image::Layer = 256;
+
+/// The CPU, seen as a device with a single heap of host memory.
+#[derive(Debug)]
+pub struct PhysicalDevice {
+    epoch: Instant,
+}
+
+impl Default for PhysicalDevice {
+    fn default() -> Self {
+        PhysicalDevice {
+            epoch: Instant::now(),
+        }
+    }
+}
+
+impl adapter::PhysicalDevice<Backend> for PhysicalDevice {
+    unsafe fn open(
+        &self,
+        families: &[(&QueueFamily, &[queue::QueuePriority])],
+        requested_features: hal::Features,
+    ) -> Result<adapter::Gpu<Backend>, d::CreationError> {
+        if !self.features().contains(requested_features) {
+            return Err(d::CreationError::MissingFeature);
+        }
+        let queue_groups = families
+            .iter()
+            .map(|&(_, priorities)| {
+                let mut group = queue::QueueGroup::new(queue::QueueFamilyId(0));
+                for _ in priorities {
+                    group.add_queue(CommandQueue {
+                        executor: Executor::new(self.epoch),
+                    });
+                }
+                group
+            })
+            .collect();
+        Ok(adapter::Gpu {
+            device: Device,
+            queue_groups,
+        })
+    }
+
+    fn format_properties(&self, format: Option<f::Format>) -> f::Properties {
+        let layout = match format.and_then(TexelLayout::of) {
+            Some(layout) => layout,
+            None => return f::Properties::default(),
+        };
+        let mut image =
+            f::ImageFeature::SAMPLED | f::ImageFeature::BLIT_SRC | f::ImageFeature::BLIT_DST;
+        let mut buffer = f::BufferFeature::VERTEX;
+        if layout.depth {
+            image |= f::ImageFeature::DEPTH_STENCIL_ATTACHMENT;
+        } else {
+            image |= f::ImageFeature::COLOR_ATTACHMENT
+                | f::ImageFeature::STORAGE
+                | f::ImageFeature::STORAGE_ATOMIC;
+            buffer |= f::BufferFeature::UNIFORM_TEXEL
+                | f::BufferFeature::STORAGE_TEXEL
+                | f::BufferFeature::STORAGE_TEXEL_ATOMIC;
+            if !layout.is_integer() {
+                image |= f::ImageFeature::SAMPLED_LINEAR | f::ImageFeature::COLOR_ATTACHMENT_BLEND;
+            }
+        }
+        f::Properties {
+            linear_tiling: image,
+            optimal_tiling: image,
+            buffer_features: buffer,
+        }
+    }
+
+    fn image_format_properties(
+        &self,
+        format: f::Format,
+        dimensions: u8,
+        _tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+    ) -> Option<image::FormatProperties> {
+        let layout = TexelLayout::of(format)?;
+        if view_caps.contains(image::ViewCapabilities::KIND_CUBE) {
+            return None;
+        }
+        let unsupported = if layout.depth {
+            image::Usage::COLOR_ATTACHMENT | image::Usage::STORAGE
+        } else {
+            image::Usage::DEPTH_STENCIL_ATTACHMENT
+        };
+        if usage.intersects(unsupported) {
+            return None;
+        }
+        let max_extent = match dimensions {
+            1 => image::Extent {
+                width: MAX_IMAGE_SIZE,
+                height: 1,
+                depth: 1,
+            },
+            2 => image::Extent {
+                width: MAX_IMAGE_SIZE,
+                height: MAX_IMAGE_SIZE,
+                depth: 1,
+            },
+            3 => image::Extent {
+                width: 256,
+                height: 256,
+                depth: 256,
+            },
+            _ => return None,
+        };
+        Some(image::FormatProperties {
+            max_extent,
+            max_levels: 13,
+            max_layers: if dimensions == 3 { 1 } else { MAX_LAYERS },
+            sample_count_mask: 0x1,
+            max_resource_size: 1 << 30,
+        })
+    }
+
+    fn memory_properties(&self) -> adapter::MemoryProperties {
+        adapter::MemoryProperties {
+            memory_types: vec![adapter::MemoryType {
+                properties: memory::Properties::DEVICE_LOCAL
+                    | memory::Properties::CPU_VISIBLE
+                    | memory::Properties::COHERENT
+                    | memory::Properties::CPU_CACHED,
+                heap_index: 0,
+            }],
+            memory_heaps: vec![1 << 32],
+        }
+    }
+
+    fn features(&self) -> hal::Features {
+        hal::Features::ROBUST_BUFFER_ACCESS
+            | hal::Features::INDEPENDENT_BLENDING
+            | hal::Features::MULTI_DRAW_INDIRECT
+            | hal::Features::DRAW_INDIRECT_FIRST_INSTANCE
+            | hal::Features::DEPTH_CLAMP
+            | hal::Features::NON_FILL_POLYGON_MODE
+            | hal::Features::PRECISE_OCCLUSION_QUERY
+            | hal::Features::VERTEX_STORES_AND_ATOMICS
+            | hal::Features::FRAGMENT_STORES_AND_ATOMICS
+            | hal::Features::SHADER_STORAGE_IMAGE_EXTENDED_FORMATS
+            | hal::Features::SHADER_UNIFORM_BUFFER_ARRAY_DYNAMIC_INDEXING
+            | hal::Features::SHADER_SAMPLED_IMAGE_ARRAY_DYNAMIC_INDEXING
+            | hal::Features::SHADER_STORAGE_BUFFER_ARRAY_DYNAMIC_INDEXING
+            | hal::Features::SHADER_STORAGE_IMAGE_ARRAY_DYNAMIC_INDEXING
+            | hal::Features::SAMPLER_MIRROR_CLAMP_EDGE
+            | hal::Features::INSTANCE_RATE
+            | hal::Features::SAMPLER_MIP_LOD_BIAS
+    }
+
+    fn hints(&self) -> hal::Hints {
+        hal::Hints::BASE_VERTEX_INSTANCE_DRAWING
+    }
+
+    fn limits(&self) -> hal::Limits {
+        hal::Limits {
+            max_image_1d_size: MAX_IMAGE_SIZE,
+            max_image_2d_size: MAX_IMAGE_SIZE,
+            max_image_3d_size: 256,
+            max_image_cube_size: 0,
+            max_image_array_layers: MAX_LAYERS as _,
+            max_texel_elements: 1 << 27,
+            max_uniform_buffer_range: 1 << 16,
+            max_storage_buffer_range: 1 << 27,
+            max_push_constants_size: execute::PUSH_CONSTANTS_SIZE,
+            max_memory_allocation_count: 1 << 16,
+            max_sampler_allocation_count: 1 << 16,
+            max_bound_descriptor_sets: 8,
+            max_framebuffer_layers: MAX_LAYERS as _,
+            max_per_stage_descriptor_samplers: 16,
+            max_per_stage_descriptor_uniform_buffers: 16,
+            max_per_stage_descriptor_storage_buffers: 16,
+            max_per_stage_descriptor_sampled_images: 16,
+            max_per_stage_descriptor_storage_images: 8,
+            max_per_stage_descriptor_input_attachments: 0,
+            max_per_stage_resources: 128,
+            max_descriptor_set_samplers: 96,
+            max_descriptor_set_uniform_buffers: 72,
+            max_descriptor_set_uniform_buffers_dynamic: 8,
+            max_descriptor_set_storage_buffers: 24,
+            max_descriptor_set_storage_buffers_dynamic: 8,
+            max_descriptor_set_sampled_images: 96,
+            max_descriptor_set_storage_images: 24,
+            max_descriptor_set_input_attachments: 0,
+            max_vertex_input_attributes: 16,
+            max_vertex_input_bindings: 16,
+            max_vertex_input_attribute_offset: 2047,
+            max_vertex_input_binding_stride: 2048,
+            max_vertex_output_components: 64,
+            max_patch_size: 0,
+            max_geometry_shader_invocations: 0,
+            max_geometry_input_components: 0,
+            max_geometry_output_components: 0,
+            max_geometry_output_vertices: 0,
+            max_geometry_total_output_components: 0,
+            max_fragment_input_components: 64,
+            max_fragment_output_attachments: 8,
+            max_fragment_dual_source_attachments: 0,
+            max_fragment_combined_output_resources: 8,
+            max_compute_shared_memory_size: 1 << 15,
+            max_compute_work_group_count: [65535; 3],
+            max_compute_work_group_invocations: 1024,
+            max_compute_work_group_size: [1024, 1024, 64],
+            max_draw_indexed_index_value: !0,
+            max_draw_indirect_count: !0,
+            max_sampler_lod_bias: 16.0,
+            max_sampler_anisotropy: 1.0,
+            max_viewports: 1,
+            max_viewport_dimensions: [MAX_IMAGE_SIZE; 2],
+            max_framebuffer_extent: image::Extent {
+                width: MAX_IMAGE_SIZE,
+                height: MAX_IMAGE_SIZE,
+                depth: MAX_LAYERS as u32,
+            },
+            min_memory_map_alignment: 16,
+            buffer_image_granularity: 1,
+            min_texel_buffer_offset_alignment: 16,
+            min_uniform_buffer_offset_alignment: 16,
+            min_storage_buffer_offset_alignment: 16,
+            framebuffer_color_sample_counts: 0x1,
+            framebuffer_depth_sample_counts: 0x1,
+            // Stencil isn't supported.
+            framebuffer_stencil_sample_counts: 0,
+            max_color_attachments: 8,
+            standard_sample_locations: true,
+            optimal_buffer_copy_offset_alignment: 1,
+            optimal_buffer_copy_pitch_alignment: 1,
+            non_coherent_atom_size: 1,
+            min_vertex_input_binding_stride_alignment: 1,
+        }
+    }
+}
+
+/// The only queue family, supporting everything.
+#[derive(Debug)]
+pub struct QueueFamily;
+impl queue::QueueFamily for QueueFamily {
+    fn queue_type(&self) -> queue::QueueType {
+        queue::QueueType::General
+    }
+    fn max_queues(&self) -> usize {
+        16
+    }
+    fn id(&self) -> queue::QueueFamilyId {
+        queue::QueueFamilyId(0)
+    }
+}
+
+/// Queue executing the submitted command buffers before returning.
+#[derive(Debug)]
+pub struct CommandQueue {
+    executor: Executor,
+}
+
+impl queue::CommandQueue<Backend> for CommandQueue {
+    unsafe fn submit<'a, T, Ic, S, Iw, Is>(
+        &mut self,
+        submission: queue::Submission<Ic, Iw, Is>,
+        fence: Option<&Fence>,
+    ) where
+        T: 'a + Borrow<CommandBuffer>,
+        Ic: IntoIterator<Item = &'a T>,
+        S: 'a + Borrow<()>,
+        Iw: IntoIterator<Item = (&'a S, pso::PipelineStage)>,
+        Is: IntoIterator<Item = &'a S>,
+    {
+        for command_buffer in submission.command_buffers {
+            self.executor.execute(&command_buffer.borrow().commands);
+        }
+        if let Some(fence) = fence {
+            fence.set(true);
+        }
+    }
+
+    unsafe fn present<'a, W, Is, S, Iw>(
+        &mut self,
+        _swapchains: Is,
+        _wait_semaphores: Iw,
+    ) -> Result<Option<window::Suboptimal>, window::PresentError>
+    where
+        W: 'a + Borrow<Swapchain>,
+        Is: IntoIterator<Item = (&'a W, window::SwapImageIndex)>,
+        S: 'a + Borrow<()>,
+        Iw: IntoIterator<Item = &'a S>,
+    {
+        unimplemented!("Use the `PresentationSurface` API")
+    }
+
+    unsafe fn present_surface(
+        &mut self,
+        surface: &mut Surface,
+        image: SwapchainImage,
+        _wait_semaphore: Option<&()>,
+    ) -> Result<Option<window::Suboptimal>, window::PresentError> {
+        let data = image.view.image.footprint(0, 0);
+        let mut bytes = vec![0; (data.slice.end - data.slice.start) as usize];
+        image.view.image.read_raw(0, 0, [0, 0, 0], &mut bytes);
+        surface.presenter.present(image.index, bytes).map(|()| None)
+    }
+
+    fn wait_idle(&self) -> Result<(), d::OutOfMemory> {
+        Ok(())
+    }
+}
+
+/// Offscreen surface, whose images are rendered in host memory.
+#[derive(Debug)]
+pub struct Surface {
+    presenter: auxil::offscreen::Presenter,
+    images: Vec<ImageView>,
+}
+impl window::Surface<Backend> for Surface {
+    fn supports_queue_family(&self, _: &QueueFamily) -> bool {
+        true
+    }
+
+    fn capabilities(&self, _: &PhysicalDevice) -> window::SurfaceCapabilities {
+        self.presenter.capabilities()
+    }
+
+    fn supported_formats(&self, _: &PhysicalDevice) -> Option<Vec<f::Format>> {
+        Some(self.presenter.formats())
+    }
+}
+
+/// Image acquired from a [`Surface`].
+#[derive(Debug)]
+pub struct SwapchainImage {
+    index: window::SwapImageIndex,
+    view: ImageView,
+}
+impl Borrow<ImageView> for SwapchainImage {
+    fn borrow(&self) -> &ImageView {
+        &self.view
+    }
+}
+
+impl window::PresentationSurface<Backend> for Surface {
+    type SwapchainImage = SwapchainImage;
+
+    unsafe fn configure_swapchain(
+        &mut self,
+        device: &Device,
+        config: window::SwapchainConfig,
+    ) -> Result<(), window::CreationError> {
+        use hal::device::Device as _;
+
+        let kind = image::Kind::D2(
+            config.extent.width,
+            config.extent.height,
+            config.image_layers,
+            1,
+        );
+        let range = image::SubresourceRange {
+            aspects: f::Aspects::COLOR,
+            levels: 0 .. 1,
+            layers: 0 .. config.image_layers,
+        };
+        let mut images = Vec::new();
+        for _ in 0 .. config.image_count {
+            let mut image = device
+                .create_image(
+                    kind,
+                    1,
+                    config.format,
+                    image::Tiling::Optimal,
+                    config.image_usage,
+                    image::ViewCapabilities::empty(),
+                )
+                .map_err(|_| d::OutOfMemory::Host)?;
+            let memory = Memory::new(image.size());
+            device
+                .bind_image_memory(&memory, 0, &mut image)
+                .map_err(|_| d::OutOfMemory::Host)?;
+            let view = device
+                .create_image_view(
+                    &image,
+                    image::ViewKind::D2,
+                    config.format,
+                    f::Swizzle::NO,
+                    range.clone(),
+                )
+                .map_err(|_| d::OutOfMemory::Host)?;
+            images.push(view);
+        }
+        self.presenter.configure(&config)?;
+        self.images = images;
+        Ok(())
+    }
+
+    unsafe fn unconfigure_swapchain(&mut self, _: &Device) {
+        self.presenter.unconfigure();
+        self.images.clear();
+    }
+
+    unsafe fn acquire_image(
+        &mut self,
+        _timeout_ns: u64,
+    ) -> Result<(SwapchainImage, Option<window::Suboptimal>), window::AcquireError> {
+        let index = self.presenter.acquire()?;
+        let view = self.images[index as usize].clone();
+        Ok((SwapchainImage { index, view }, None))
+    }
+}
+
+/// Legacy swapchain, which isn't supported.
+#[derive(Debug)]
+pub struct Swapchain;
+impl window::Swapchain<Backend> for Swapchain {
+    unsafe fn acquire_image(
+        &mut self,
+        _timeout_ns: u64,
+        _semaphore: Option<&()>,
+        _fence: Option<&Fence>,
+    ) -> Result<(window::SwapImageIndex, Option<window::Suboptimal>), window::AcquireError> {
+        unimplemented!("Use the `PresentationSurface` API")
+    }
+}
+
+/// Instance exposing the software adapter.
+#[derive(Debug)]
+pub struct Instance;
+
+impl hal::Instance<Backend> for Instance {
+    fn create(_name: &str, _version: u32) -> Result<Self, hal::UnsupportedBackend> {
+        Ok(Instance)
+    }
+
+    fn enumerate_adapters(&self) -> Vec<adapter::Adapter<Backend>> {
+        vec![adapter::Adapter {
+            info: adapter::AdapterInfo {
+                name: "Software Renderer".to_string(),
+                vendor: 0,
+                device: 0,
+                device_type: adapter::DeviceType::Cpu,
+            },
+            physical_device: PhysicalDevice::default(),
+            queue_families: vec![QueueFamily],
+        }]
+    }
+
+    unsafe fn create_surface(
+        &self,
+        _: &impl raw_window_handle::HasRawWindowHandle,
+    ) -> Result<Surface, window::InitError> {
+        Err(window::InitError::UnsupportedWindowHandle)
+    }
+
+    unsafe fn destroy_surface(&self, _surface: Surface) {}
+
+    fn create_offscreen_surface(
+        &self,
+        config: window::OffscreenConfig,
+    ) -> Result<Surface, window::InitError> {
+        Ok(Surface {
+            presenter: auxil::offscreen::Presenter::new(config),
+            images: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hal::{
+        adapter::PhysicalDevice as _,
+        buffer,
+        command::{self as com, CommandBuffer as _},
+        device::Device as _,
+        pass,
+        pool::CommandPool as _,
+        pso::DescriptorPool as _,
+        queue::CommandQueue as _,
+        Instance as _,
+    };
+    use std::iter;
+
+    /// Minimal SPIR-V assembler, numbering the ids in order of creation.
+    #[derive(Default)]
+    struct Assembler {
+        words: Vec<u32>,
+        bound: u32,
+    }
+
+    impl Assembler {
+        fn id(&mut self) -> u32 {
+            self.bound += 1;
+            self.bound
+        }
+
+        fn op(&mut self, opcode: u32, operands: &[u32]) {
+            self.words.push((operands.len() as u32 + 1) << 16 | opcode);
+            self.words.extend_from_slice(operands);
+        }
+
+        fn string(text: &str) -> Vec<u32> {
+            let mut bytes = text.as_bytes().to_vec();
+            bytes.resize((bytes.len() / 4 + 1) * 4, 0);
+            bytes
+                .chunks(4)
+                .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+                .collect()
+        }
+
+        fn finish(self) -> Vec<u32> {
+            let mut words = vec![0x0723_0203, 0x0001_0000, 0, self.bound + 1, 0];
+            words.extend(self.words);
+            words
+        }
+    }
+
+    fn entry_point(asm: &mut Assembler, model: u32, main: u32, interface: &[u32]) {
+        asm.op(17, &[1]);
+        asm.op(14, &[0, 1]);
+        let mut operands = vec![model, main];
+        operands.extend(Assembler::string("main"));
+        operands.extend_from_slice(interface);
+        asm.op(15, &operands);
+    }
+
+    /// `data[gl_GlobalInvocationID.x] *= 2` over a storage buffer of `uint`.
+    fn double_shader() -> Vec<u32> {
+        let mut asm = Assembler::default();
+        let (main, gid, buf) = (asm.id(), asm.id(), asm.id());
+        let (void, func, uint, uvec3) = (asm.id(), asm.id(), asm.id(), asm.id());
+        let (ptr_input, runtime_array, block, ptr_block) = (asm.id(), asm.id(), asm.id(), asm.id());
+        let (zero, two, ptr_uint, ptr_input_uint) = (asm.id(), asm.id(), asm.id(), asm.id());
+        entry_point(&mut asm, 5, main, &[gid]);
+        asm.op(16, &[main, 17, 4, 1, 1]);
+        asm.op(71, &[gid, 11, 28]);
+        asm.op(71, &[runtime_array, 6, 4]);
+        asm.op(72, &[block, 0, 35, 0]);
+        asm.op(71, &[block, 3]);
+        asm.op(71, &[buf, 34, 0]);
+        asm.op(71, &[buf, 33, 0]);
+        asm.op(19, &[void]);
+        asm.op(33, &[func, void]);
+        asm.op(21, &[uint, 32, 0]);
+        asm.op(23, &[uvec3, uint, 3]);
+        asm.op(32, &[ptr_input, 1, uvec3]);
+        asm.op(59, &[ptr_input, gid, 1]);
+        asm.op(29, &[runtime_array, uint]);
+        asm.op(30, &[block, runtime_array]);
+        asm.op(32, &[ptr_block, 2, block]);
+        asm.op(59, &[ptr_block, buf, 2]);
+        asm.op(43, &[uint, zero, 0]);
+        asm.op(43, &[uint, two, 2]);
+        asm.op(32, &[ptr_uint, 2, uint]);
+        asm.op(32, &[ptr_input_uint, 1, uint]);
+        let (label, px, x, p, v, d) = (asm.id(), asm.id(), asm.id(), asm.id(), asm.id(), asm.id());
+        asm.op(54, &[void, main, 0, func]);
+        asm.op(248, &[label]);
+        asm.op(65, &[ptr_input_uint, px, gid, zero]);
+        asm.op(61, &[uint, x, px]);
+        asm.op(65, &[ptr_uint, p, buf, zero, x]);
+        asm.op(61, &[uint, v, p]);
+        asm.op(132, &[uint, d, v, two]);
+        asm.op(62, &[p, d]);
+        asm.op(253, &[]);
+        asm.op(56, &[]);
+        asm.finish()
+    }
+
+    /// Pass a 2D position through, and fill with red.
+    fn triangle_shaders() -> (Vec<u32>, Vec<u32>) {
+        let float_types = |asm: &mut Assembler| {
+            let ids = [
+                asm.id(),
+                asm.id(),
+                asm.id(),
+                asm.id(),
+                asm.id(),
+                asm.id(),
+                asm.id(),
+            ];
+            let [void, func, float, vec2, vec4, zero, one] = ids;
+            asm.op(19, &[void]);
+            asm.op(33, &[func, void]);
+            asm.op(22, &[float, 32]);
+            asm.op(23, &[vec2, float, 2]);
+            asm.op(23, &[vec4, float, 4]);
+            asm.op(43, &[float, zero, 0f32.to_bits()]);
+            asm.op(43, &[float, one, 1f32.to_bits()]);
+            ids
+        };
+
+        let mut asm = Assembler::default();
+        let (main, position, out_position) = (asm.id(), asm.id(), asm.id());
+        entry_point(&mut asm, 0, main, &[position, out_position]);
+        asm.op(71, &[position, 30, 0]);
+        asm.op(71, &[out_position, 11, 0]);
+        let [void, func, float, vec2, vec4, zero, one] = float_types(&mut asm);
+        let (ptr_input, ptr_output) = (asm.id(), asm.id());
+        asm.op(32, &[ptr_input, 1, vec2]);
+        asm.op(59, &[ptr_input, position, 1]);
+        asm.op(32, &[ptr_output, 3, vec4]);
+        asm.op(59, &[ptr_output, out_position, 3]);
+        let (label, p, x, y, v) = (asm.id(), asm.id(), asm.id(), asm.id(), asm.id());
+        asm.op(54, &[void, main, 0, func]);
+        asm.op(248, &[label]);
+        asm.op(61, &[vec2, p, position]);
+        asm.op(81, &[float, x, p, 0]);
+        asm.op(81, &[float, y, p, 1]);
+        asm.op(80, &[vec4, v, x, y, zero, one]);
+        asm.op(62, &[out_position, v]);
+        asm.op(253, &[]);
+        asm.op(56, &[]);
+        let vertex = asm.finish();
+
+        let mut asm = Assembler::default();
+        let (main, color) = (asm.id(), asm.id());
+        entry_point(&mut asm, 4, main, &[color]);
+        asm.op(16, &[main, 7]);
+        asm.op(71, &[color, 30, 0]);
+        let [void, func, _, _, vec4, zero, one] = float_types(&mut asm);
+        let (ptr_output, red) = (asm.id(), asm.id());
+        asm.op(32, &[ptr_output, 3, vec4]);
+        asm.op(59, &[ptr_output, color, 3]);
+        asm.op(44, &[vec4, red, one, zero, zero, one]);
+        let label = asm.id();
+        asm.op(54, &[void, main, 0, func]);
+        asm.op(248, &[label]);
+        asm.op(62, &[color, red]);
+        asm.op(253, &[]);
+        asm.op(56, &[]);
+        (vertex, asm.finish())
+    }
+
+    fn open() -> (Device, CommandQueue) {
+        let adapter = Instance.enumerate_adapters().pop().unwrap();
+        assert_eq!(adapter.info.device_type, adapter::DeviceType::Cpu);
+        let family = &adapter.queue_families[0];
+        let mut gpu = unsafe {
+            adapter
+                .physical_device
+                .open(&[(family, &[1.0])], hal::Features::empty())
+                .unwrap()
+        };
+        let queue = gpu.queue_groups[0].queues.pop().unwrap();
+        (gpu.device, queue)
+    }
+
+    unsafe fn host_buffer(device: &Device, data: &[u8]) -> (Buffer, Memory) {
+        let mut buffer = device
+            .create_buffer(data.len() as u64, buffer::Usage::all())
+            .unwrap();
+        let requirements = device.get_buffer_requirements(&buffer);
+        let memory = device
+            .allocate_memory(hal::MemoryTypeId(0), requirements.size)
+            .unwrap();
+        device.bind_buffer_memory(&memory, 0, &mut buffer).unwrap();
+        let ptr = device.map_memory(&memory, memory::Segment::ALL).unwrap();
+        std::ptr::copy_nonoverlapping(data.as_ptr(), ptr, data.len());
+        (buffer, memory)
+    }
+
+    unsafe fn submit(queue: &mut CommandQueue, device: &Device, cmd: &CommandBuffer) {
+        let fence = device.create_fence(false).unwrap();
+        queue.submit_without_semaphores(iter::once(cmd), Some(&fence));
+        assert!(device.wait_for_fence(&fence, 0).unwrap());
+    }
+
+    #[test]
+    fn test_compute() {
+        let (device, mut queue) = open();
+        let values: Vec<u32> = (0 .. 8).collect();
+        let bytes: Vec<u8> = values
+            .iter()
+            .flat_map(|v| v.to_le_bytes().to_vec())
+            .collect();
+        unsafe {
+            let (buffer, memory) = host_buffer(&device, &bytes);
+            let module = device.create_shader_module(&double_shader()).unwrap();
+            let set_layout = device
+                .create_descriptor_set_layout(
+                    iter::once(pso::DescriptorSetLayoutBinding {
+                        binding: 0,
+                        ty: pso::DescriptorType::Buffer {
+                            ty: pso::BufferDescriptorType::Storage { read_only: false },
+                            format: pso::BufferDescriptorFormat::Structured {
+                                dynamic_offset: false,
+                            },
+                        },
+                        count: 1,
+                        stage_flags: pso::ShaderStageFlags::COMPUTE,
+                        immutable_samplers: false,
+                    }),
+                    iter::empty::<Sampler>(),
+                )
+                .unwrap();
+            let mut pool = device
+                .create_descriptor_pool(
+                    1,
+                    iter::empty::<pso::DescriptorRangeDesc>(),
+                    pso::DescriptorPoolCreateFlags::empty(),
+                )
+                .unwrap();
+            let set = pool.allocate_set(&set_layout).unwrap();
+            device.write_descriptor_sets(iter::once(pso::DescriptorSetWrite {
+                set: &set,
+                binding: 0,
+                array_offset: 0,
+                descriptors: iter::once(pso::Descriptor::Buffer(&buffer, buffer::SubRange::WHOLE)),
+            }));
+            let layout = device
+                .create_pipeline_layout(
+                    iter::once(&set_layout),
+                    iter::empty::<(pso::ShaderStageFlags, std::ops::Range<u32>)>(),
+                )
+                .unwrap();
+            let entry = pso::EntryPoint {
+                entry: "main",
+                module: &module,
+                specialization: pso::Specialization::default(),
+            };
+            let pipeline = device
+                .create_compute_pipeline(&pso::ComputePipelineDesc::new(entry, &layout), None)
+                .unwrap();
+
+            let mut command_pool = device
+                .create_command_pool(
+                    queue::QueueFamilyId(0),
+                    hal::pool::CommandPoolCreateFlags::empty(),
+                )
+                .unwrap();
+            let mut cmd = command_pool.allocate_one(com::Level::Primary);
+            cmd.begin_primary(com::CommandBufferFlags::ONE_TIME_SUBMIT);
+            cmd.bind_compute_pipeline(&pipeline);
+            cmd.bind_compute_descriptor_sets(
+                &layout,
+                0,
+                iter::once(&set),
+                iter::empty::<com::DescriptorSetOffset>(),
+            );
+            cmd.dispatch([2, 1, 1]);
+            cmd.finish();
+            submit(&mut queue, &device, &cmd);
+
+            let ptr = device.map_memory(&memory, memory::Segment::ALL).unwrap();
+            let result = std::slice::from_raw_parts(ptr as *const u32, values.len());
+            let expected: Vec<u32> = values.iter().map(|v| v * 2).collect();
+            assert_eq!(result, &expected[..]);
+        }
+    }
+
+    #[test]
+    fn test_triangle() {
+        let (device, mut queue) = open();
+        let size = 4;
+        let format = f::Format::Rgba8Unorm;
+        // Covers the top-left half of the target, the diagonal excluded.
+        let positions: [f32; 6] = [-1.0, -1.0, 0.5, -1.0, -1.0, 0.5];
+        let bytes: Vec<u8> = positions
+            .iter()
+            .flat_map(|v| v.to_le_bytes().to_vec())
+            .collect();
+        unsafe {
+            let (vertices, _vertex_memory) = host_buffer(&device, &bytes);
+            let (readback, readback_memory) = host_buffer(&device, &[0; 64]);
+
+            let mut image = device
+                .create_image(
+                    image::Kind::D2(size, size, 1, 1),
+                    1,
+                    format,
+                    image::Tiling::Optimal,
+                    image::Usage::COLOR_ATTACHMENT | image::Usage::TRANSFER_SRC,
+                    image::ViewCapabilities::empty(),
+                )
+                .unwrap();
+            let requirements = device.get_image_requirements(&image);
+            let memory = device
+                .allocate_memory(hal::MemoryTypeId(0), requirements.size)
+                .unwrap();
+            device.bind_image_memory(&memory, 0, &mut image).unwrap();
+            let view = device
+                .create_image_view(
+                    &image,
+                    image::ViewKind::D2,
+                    format,
+                    f::Swizzle::NO,
+                    image::SubresourceRange {
+                        aspects: f::Aspects::COLOR,
+                        levels: 0 .. 1,
+                        layers: 0 .. 1,
+                    },
+                )
+                .unwrap();
+
+            let attachment = pass::Attachment {
+                format: Some(format),
+                samples: 1,
+                ops: pass::AttachmentOps::new(
+                    pass::AttachmentLoadOp::Clear,
+                    pass::AttachmentStoreOp::Store,
+                ),
+                stencil_ops: pass::AttachmentOps::DONT_CARE,
+                layouts: image::Layout::Undefined .. image::Layout::TransferSrcOptimal,
+            };
+            let subpass = pass::SubpassDesc {
+                colors: &[(0, image::Layout::ColorAttachmentOptimal)],
+                depth_stencil: None,
+                inputs: &[],
+                resolves: &[],
+                preserves: &[],
+            };
+            let render_pass = device
+                .create_render_pass(
+                    iter::once(attachment),
+                    iter::once(subpass),
+                    iter::empty::<pass::SubpassDependency>(),
+                )
+                .unwrap();
+            let extent = image::Extent {
+                width: size,
+                height: size,
+                depth: 1,
+            };
+            let framebuffer = device
+                .create_framebuffer(&render_pass, iter::once(&view), extent)
+                .unwrap();
+
+            let (vs, fs) = triangle_shaders();
+            let vs = device.create_shader_module(&vs).unwrap();
+            let fs = device.create_shader_module(&fs).unwrap();
+            let layout = device
+                .create_pipeline_layout(
+                    iter::empty::<&DescriptorSetLayout>(),
+                    iter::empty::<(pso::ShaderStageFlags, std::ops::Range<u32>)>(),
+                )
+                .unwrap();
+            let entry = |module| pso::EntryPoint {
+                entry: "main",
+                module,
+                specialization: pso::Specialization::default(),
+            };
+            let mut desc = pso::GraphicsPipelineDesc::new(
+                pso::GraphicsShaderSet {
+                    vertex: entry(&vs),
+                    hull: None,
+                    domain: None,
+                    geometry: None,
+                    fragment: Some(entry(&fs)),
+                },
+                pso::Primitive::TriangleList,
+                pso::Rasterizer::FILL,
+                &layout,
+                pass::Subpass {
+                    index: 0,
+                    main_pass: &render_pass,
+                },
+            );
+            desc.blender.targets.push(pso::ColorBlendDesc {
+                mask: pso::ColorMask::ALL,
+                blend: None,
+            });
+            desc.vertex_buffers.push(pso::VertexBufferDesc {
+                binding: 0,
+                stride: 8,
+                rate: pso::VertexInputRate::Vertex,
+            });
+            desc.attributes.push(pso::AttributeDesc {
+                location: 0,
+                binding: 0,
+                element: pso::Element {
+                    format: f::Format::Rg32Sfloat,
+                    offset: 0,
+                },
+            });
+            let pipeline = device.create_graphics_pipeline(&desc, None).unwrap();
+
+            let rect = pso::Rect {
+                x: 0,
+                y: 0,
+                w: size as i16,
+                h: size as i16,
+            };
+            let mut command_pool = device
+                .create_command_pool(
+                    queue::QueueFamilyId(0),
+                    hal::pool::CommandPoolCreateFlags::empty(),
+                )
+                .unwrap();
+            let mut cmd = command_pool.allocate_one(com::Level::Primary);
+            cmd.begin_primary(com::CommandBufferFlags::ONE_TIME_SUBMIT);
+            cmd.set_viewports(
+                0,
+                iter::once(pso::Viewport {
+                    rect,
+                    depth: 0.0 .. 1.0,
+                }),
+            );
+            cmd.set_scissors(0, iter::once(rect));
+            cmd.bind_graphics_pipeline(&pipeline);
+            cmd.bind_vertex_buffers(0, iter::once((&vertices, buffer::SubRange::WHOLE)));
+            cmd.begin_render_pass(
+                &render_pass,
+                &framebuffer,
+                rect,
+                iter::once(com::ClearValue {
+                    color: com::ClearColor {
+                        float32: [0.0, 0.0, 1.0, 1.0],
+                    },
+                }),
+                com::SubpassContents::Inline,
+            );
+            cmd.draw(0 .. 3, 0 .. 1);
+            cmd.end_render_pass();
+            cmd.copy_image_to_buffer(
+                &image,
+                image::Layout::TransferSrcOptimal,
+                &readback,
+                iter::once(com::BufferImageCopy {
+                    buffer_offset: 0,
+                    buffer_width: size,
+                    buffer_height: size,
+                    image_layers: image::SubresourceLayers {
+                        aspects: f::Aspects::COLOR,
+                        level: 0,
+                        layers: 0 .. 1,
+                    },
+                    image_offset: image::Offset::ZERO,
+                    image_extent: extent,
+                }),
+            );
+            cmd.finish();
+            submit(&mut queue, &device, &cmd);
+
+            let ptr = device
+                .map_memory(&readback_memory, memory::Segment::ALL)
+                .unwrap();
+            let texels = std::slice::from_raw_parts(ptr as *const [u8; 4], 16);
+            let red = [255, 0, 0, 255];
+            let blue = [0, 0, 255, 255];
+            for y in 0 .. size as usize {
+                for x in 0 .. size as usize {
+                    let expected = if x + y < 2 { red } else { blue };
+                    assert_eq!(texels[y * 4 + x], expected, "texel ({}, {})", x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_unsupported_shader() {
+        let device = Device;
+        // Dropping `OpFunctionEnd` leaves the function unterminated.
+        let mut words = double_shader();
+        words.truncate(words.len() - 1);
+        assert!(unsafe { device.create_shader_module(&words) }.is_err());
+    }
+}
@@ -0,0 +1,688 @@
+//! Vertex processing, rasterization and output merging of draws.
+//!
+//! Rasterization follows the Vulkan rules for single-sampled framebuffers:
+//! pixel centers at half-integer coordinates, the top-left fill rule, clipping
+//! against the near and far planes, and perspective-correct interpolation.
+//! Stencil tests, depth bias and logic operations aren't supported.
+
+use crate::{
+    format::TexelLayout,
+    interpret::Invocation,
+    resource::{Bound, GraphicsPipelineDesc, ImageView},
+    spirv::{builtin, Program, Resources, Type, Value},
+};
+use hal::pso;
+
+use std::collections::HashMap;
+
+/// Vertex after the vertex shader.
+#[derive(Clone, Debug)]
+struct Vertex {
+    position: [f32; 4],
+    point_size: f32,
+    /// Outputs of the vertex shader, by location.
+    varyings: HashMap<u32, Vec<u32>>,
+}
+
+/// Vertex in framebuffer coordinates.
+#[derive(Clone, Debug)]
+struct ScreenVertex {
+    x: f32,
+    y: f32,
+    z: f32,
+    /// Reciprocal of the clip-space `w`.
+    inv_w: f32,
+    varyings: HashMap<u32, Vec<u32>>,
+}
+
+/// Everything needed by a draw call.
+pub struct DrawContext<'a> {
+    pub pipeline: &'a GraphicsPipelineDesc,
+    pub resources: &'a Resources,
+    pub vertex_buffers: &'a [Option<Bound>],
+    /// Color attachments of the subpass.
+    pub colors: Vec<Option<&'a ImageView>>,
+    pub depth: Option<&'a ImageView>,
+    pub viewport: pso::Viewport,
+    /// Rectangle the fragments are limited to, from the scissor and render area.
+    pub bounds: pso::Rect,
+    pub blend_constants: pso::ColorValue,
+    /// Number of fragments passing the depth test, for occlusion queries.
+    pub passed: u64,
+}
+
+fn float_bits(values: &[f32]) -> Value {
+    Value::vector(values.iter().map(|x| x.to_bits()))
+}
+
+fn components(value: &Value) -> Vec<u32> {
+    match *value {
+        Value::Composite(ref values) => values.iter().flat_map(components).collect(),
+        Value::Scalar(x) => vec![x],
+        _ => Vec::new(),
+    }
+}
+
+/// Build a value of a scalar or vector type from components.
+fn typed_value(program: &Program, ty: u32, comps: &[u32]) -> Value {
+    let get = |i: usize| comps.get(i).cloned().unwrap_or(0);
+    match *program.module.ty(ty) {
+        Type::Vector { count, .. } => Value::vector((0 .. count as usize).map(get)),
+        Type::Array { element, .. } => match program.zero(ty) {
+            Value::Composite(elements) => {
+                let size = components(&program.zero(element)).len().max(1);
+                Value::Composite(
+                    (0 .. elements.len())
+                        .map(|i| {
+                            typed_value(program, element, &comps[(i * size).min(comps.len()) ..])
+                        })
+                        .collect(),
+                )
+            }
+            other => other,
+        },
+        _ => Value::Scalar(get(0)),
+    }
+}
+
+fn is_integer(program: &Program, ty: u32) -> bool {
+    matches!(
+        *program.module.scalar_type(ty),
+        Type::Int { .. } | Type::Bool
+    )
+}
+
+fn compare(fun: pso::Comparison, value: f32, reference: f32) -> bool {
+    use hal::pso::Comparison as C;
+    match fun {
+        C::Never => false,
+        C::Less => value < reference,
+        C::Equal => value == reference,
+        C::LessEqual => value <= reference,
+        C::Greater => value > reference,
+        C::NotEqual => value != reference,
+        C::GreaterEqual => value >= reference,
+        C::Always => true,
+    }
+}
+
+fn blend_factor(
+    factor: pso::Factor,
+    src: [f32; 4],
+    dst: [f32; 4],
+    constant: [f32; 4],
+    channel: usize,
+) -> f32 {
+    use hal::pso::Factor as F;
+    match factor {
+        F::Zero => 0.0,
+        F::One => 1.0,
+        F::SrcColor => src[channel],
+        F::OneMinusSrcColor => 1.0 - src[channel],
+        F::DstColor => dst[channel],
+        F::OneMinusDstColor => 1.0 - dst[channel],
+        F::SrcAlpha => src[3],
+        F::OneMinusSrcAlpha => 1.0 - src[3],
+        F::DstAlpha => dst[3],
+        F::OneMinusDstAlpha => 1.0 - dst[3],
+        F::ConstColor => constant[channel],
+        F::OneMinusConstColor => 1.0 - constant[channel],
+        F::ConstAlpha => constant[3],
+        F::OneMinusConstAlpha => 1.0 - constant[3],
+        F::SrcAlphaSaturate if channel == 3 => 1.0,
+        F::SrcAlphaSaturate => src[3].min(1.0 - dst[3]),
+        F::Src1Color | F::OneMinusSrc1Color | F::Src1Alpha | F::OneMinusSrc1Alpha => {
+            warn!("Dual-source blending is not supported");
+            0.0
+        }
+    }
+}
+
+fn blend_channel(
+    op: pso::BlendOp,
+    src: [f32; 4],
+    dst: [f32; 4],
+    constant: [f32; 4],
+    c: usize,
+) -> f32 {
+    use hal::pso::BlendOp as B;
+    let factors = |sf, df| {
+        (
+            src[c] * blend_factor(sf, src, dst, constant, c),
+            dst[c] * blend_factor(df, src, dst, constant, c),
+        )
+    };
+    match op {
+        B::Add { src: sf, dst: df } => {
+            let (s, d) = factors(sf, df);
+            s + d
+        }
+        B::Sub { src: sf, dst: df } => {
+            let (s, d) = factors(sf, df);
+            s - d
+        }
+        B::RevSub { src: sf, dst: df } => {
+            let (s, d) = factors(sf, df);
+            d - s
+        }
+        B::Min => src[c].min(dst[c]),
+        B::Max => src[c].max(dst[c]),
+    }
+}
+
+impl<'a> DrawContext<'a> {
+    /// Fetch the vertex attributes and run the vertex shader.
+    fn process_vertex(&self, vertex_index: u32, instance: u32, first_instance: u32) -> Vertex {
+        let program = &self.pipeline.vertex;
+        let mut invocation = Invocation::new(program, self.resources);
+        for attribute in &self.pipeline.attributes {
+            let interface = match program.inputs.get(&attribute.location) {
+                Some(interface) => interface,
+                None => continue,
+            };
+            let desc = self
+                .pipeline
+                .vertex_buffers
+                .iter()
+                .find(|vb| vb.binding == attribute.binding);
+            let buffer = self
+                .vertex_buffers
+                .get(attribute.binding as usize)
+                .and_then(|b| b.as_ref());
+            let layout = TexelLayout::of(attribute.element.format);
+            let (desc, buffer, layout) = match (desc, buffer, layout) {
+                (Some(desc), Some(buffer), Some(layout)) => (desc, buffer, layout),
+                _ => continue,
+            };
+            let index = match desc.rate {
+                pso::VertexInputRate::Vertex => vertex_index,
+                pso::VertexInputRate::Instance(0) => first_instance,
+                pso::VertexInputRate::Instance(divisor) => {
+                    first_instance + (instance - first_instance) / divisor as u32
+                }
+            };
+            let offset = index as u64 * desc.stride as u64 + attribute.element.offset as u64;
+            let mut bytes = [0; 16];
+            let size = layout.size();
+            unsafe { buffer.read(offset, &mut bytes[.. size]) };
+            let texel = layout.read(&bytes[.. size]);
+            invocation.set(interface, typed_value(program, interface.ty, &texel));
+        }
+        if let Some(interface) = program
+            .builtin_inputs
+            .get(&builtin::VERTEX_INDEX)
+            .or_else(|| program.builtin_inputs.get(&builtin::VERTEX_ID))
+        {
+            invocation.set(interface, Value::Scalar(vertex_index));
+        }
+        if let Some(interface) = program
+            .builtin_inputs
+            .get(&builtin::INSTANCE_INDEX)
+            .or_else(|| program.builtin_inputs.get(&builtin::INSTANCE_ID))
+        {
+            invocation.set(interface, Value::Scalar(instance));
+        }
+
+        let mut shared = program.shared.clone();
+        let _ = invocation.run(&mut shared);
+
+        let position = program
+            .builtin_outputs
+            .get(&builtin::POSITION)
+            .map(|interface| components(&invocation.get(interface)))
+            .unwrap_or_default();
+        let mut clip = [0.0, 0.0, 0.0, 1.0];
+        for (c, &p) in clip.iter_mut().zip(&position) {
+            *c = f32::from_bits(p);
+        }
+        let point_size = program
+            .builtin_outputs
+            .get(&builtin::POINT_SIZE)
+            .map_or(1.0, |interface| {
+                f32::from_bits(invocation.get(interface).scalar())
+            });
+        Vertex {
+            position: clip,
+            point_size,
+            varyings: program
+                .outputs
+                .iter()
+                .map(|(&location, interface)| (location, components(&invocation.get(interface))))
+                .collect(),
+        }
+    }
+
+    fn to_screen(&self, vertex: &Vertex) -> ScreenVertex {
+        let [x, y, z, w] = vertex.position;
+        let vp = &self.viewport;
+        let (half_w, half_h) = (vp.rect.w as f32 * 0.5, vp.rect.h as f32 * 0.5);
+        let mut depth = vp.depth.start + z / w * (vp.depth.end - vp.depth.start);
+        if self.pipeline.rasterizer.depth_clamping {
+            let (min, max) = if vp.depth.start < vp.depth.end {
+                (vp.depth.start, vp.depth.end)
+            } else {
+                (vp.depth.end, vp.depth.start)
+            };
+            depth = depth.max(min).min(max);
+        }
+        ScreenVertex {
+            x: vp.rect.x as f32 + half_w + x / w * half_w,
+            y: vp.rect.y as f32 + half_h + y / w * half_h,
+            z: depth,
+            inv_w: 1.0 / w,
+            varyings: vertex.varyings.clone(),
+        }
+    }
+
+    /// Draw a list of vertices, `None` restarting the primitive strips.
+    pub fn draw(&mut self, vertices: &[Option<u32>], instances: std::ops::Range<u32>) {
+        for instance in instances.clone() {
+            let mut cache = HashMap::new();
+            let mut processed = Vec::with_capacity(vertices.len());
+            for &index in vertices {
+                processed.push(index.map(|index| {
+                    cache
+                        .entry(index)
+                        .or_insert_with(|| self.process_vertex(index, instance, instances.start))
+                        .clone()
+                }));
+            }
+            self.assemble(&processed);
+        }
+    }
+
+    fn assemble(&mut self, vertices: &[Option<Vertex>]) {
+        let strips = vertices.split(|v| v.is_none());
+        for strip in strips {
+            let strip: Vec<&Vertex> = strip.iter().filter_map(|v| v.as_ref()).collect();
+            match self.pipeline.primitive {
+                pso::Primitive::PointList => {
+                    for v in &strip {
+                        self.point(v);
+                    }
+                }
+                pso::Primitive::LineList => {
+                    for pair in strip.chunks_exact(2) {
+                        self.line(pair[0], pair[1]);
+                    }
+                }
+                pso::Primitive::LineStrip => {
+                    for pair in strip.windows(2) {
+                        self.line(pair[0], pair[1]);
+                    }
+                }
+                pso::Primitive::TriangleList => {
+                    for tri in strip.chunks_exact(3) {
+                        self.triangle([tri[0], tri[1], tri[2]]);
+                    }
+                }
+                pso::Primitive::TriangleStrip => {
+                    for (i, tri) in strip.windows(3).enumerate() {
+                        if i % 2 == 0 {
+                            self.triangle([tri[0], tri[1], tri[2]]);
+                        } else {
+                            self.triangle([tri[1], tri[0], tri[2]]);
+                        }
+                    }
+                }
+                pso::Primitive::PatchList(_) => {
+                    warn!("Tessellation is not supported");
+                }
+            }
+        }
+    }
+
+    /// Clip a polygon against the near and far planes.
+    fn clip(&self, polygon: Vec<Vertex>) -> Vec<Vertex> {
+        let depth_clamp = self.pipeline.rasterizer.depth_clamping;
+        let planes: &[fn(&[f32; 4]) -> f32] = if depth_clamp {
+            &[|p| p[3] - 1e-6]
+        } else {
+            &[|p| p[2], |p| p[3] - p[2], |p| p[3] - 1e-6]
+        };
+        let mut polygon = polygon;
+        for plane in planes {
+            if polygon.is_empty() {
+                break;
+            }
+            let mut output = Vec::new();
+            for i in 0 .. polygon.len() {
+                let (a, b) = (&polygon[i], &polygon[(i + 1) % polygon.len()]);
+                let (da, db) = (plane(&a.position), plane(&b.position));
+                if da >= 0.0 {
+                    output.push(a.clone());
+                }
+                if (da >= 0.0) != (db >= 0.0) {
+                    output.push(lerp_vertex(a, b, da / (da - db)));
+                }
+            }
+            polygon = output;
+        }
+        polygon
+    }
+
+    fn triangle(&mut self, vertices: [&Vertex; 3]) {
+        let provoking = vertices[0].varyings.clone();
+        let clipped = self.clip(vertices.iter().map(|&v| v.clone()).collect());
+        if clipped.len() < 3 {
+            return;
+        }
+        let screen: Vec<_> = clipped.iter().map(|v| self.to_screen(v)).collect();
+
+        // Facing is decided by the whole polygon.
+        let area: f32 = (0 .. screen.len())
+            .map(|i| {
+                let (a, b) = (&screen[i], &screen[(i + 1) % screen.len()]);
+                a.x * b.y - b.x * a.y
+            })
+            .sum::<f32>()
+            * -0.5;
+        let ccw_front = self.pipeline.rasterizer.front_face == pso::FrontFace::CounterClockwise;
+        let front = (area > 0.0) == ccw_front;
+        let cull = self.pipeline.rasterizer.cull_face;
+        if (front && cull.contains(pso::Face::FRONT)) || (!front && cull.contains(pso::Face::BACK))
+        {
+            return;
+        }
+        match self.pipeline.rasterizer.polygon_mode {
+            pso::PolygonMode::Fill => {
+                for i in 1 .. screen.len() - 1 {
+                    self.fill_triangle([&screen[0], &screen[i], &screen[i + 1]], front, &provoking);
+                }
+            }
+            pso::PolygonMode::Line => {
+                for i in 0 .. clipped.len() {
+                    self.line(&clipped[i], &clipped[(i + 1) % clipped.len()]);
+                }
+            }
+            pso::PolygonMode::Point => {
+                for v in &clipped {
+                    self.point(v);
+                }
+            }
+        }
+    }
+
+    fn fill_triangle(
+        &mut self,
+        vertices: [&ScreenVertex; 3],
+        front: bool,
+        provoking: &HashMap<u32, Vec<u32>>,
+    ) {
+        let edge = |a: &ScreenVertex, b: &ScreenVertex, x: f32, y: f32| {
+            (b.x - a.x) * (y - a.y) - (b.y - a.y) * (x - a.x)
+        };
+        let [v0, mut v1, mut v2] = vertices;
+        let mut area = edge(v0, v1, v2.x, v2.y);
+        if area == 0.0 {
+            return;
+        }
+        if area < 0.0 {
+            std::mem::swap(&mut v1, &mut v2);
+            area = -area;
+        }
+        let top_left = |a: &ScreenVertex, b: &ScreenVertex| {
+            let (dx, dy) = (b.x - a.x, b.y - a.y);
+            dy < 0.0 || (dy == 0.0 && dx > 0.0)
+        };
+        let edges = [(v1, v2), (v2, v0), (v0, v1)];
+        let rules = [top_left(v1, v2), top_left(v2, v0), top_left(v0, v1)];
+
+        let min_x = v0.x.min(v1.x).min(v2.x).floor() as i32;
+        let max_x = v0.x.max(v1.x).max(v2.x).ceil() as i32;
+        let min_y = v0.y.min(v1.y).min(v2.y).floor() as i32;
+        let max_y = v0.y.max(v1.y).max(v2.y).ceil() as i32;
+        let (x_range, y_range) = self.clamp_to_bounds(min_x, max_x, min_y, max_y);
+
+        for y in y_range {
+            for x in x_range.clone() {
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                let mut weights = [0.0; 3];
+                let mut inside = true;
+                for (i, &(a, b)) in edges.iter().enumerate() {
+                    let e = edge(a, b, px, py);
+                    inside &= e > 0.0 || (e == 0.0 && rules[i]);
+                    weights[i] = e / area;
+                }
+                if !inside {
+                    continue;
+                }
+                let verts = [v0, v1, v2];
+                let z = (0 .. 3).map(|i| weights[i] * verts[i].z).sum();
+                let inv_w: f32 = (0 .. 3).map(|i| weights[i] * verts[i].inv_w).sum();
+                let perspective = [
+                    weights[0] * v0.inv_w / inv_w,
+                    weights[1] * v1.inv_w / inv_w,
+                    weights[2] * v2.inv_w / inv_w,
+                ];
+                let varyings = |location: u32, flat: bool| -> Vec<u32> {
+                    if flat {
+                        return provoking.get(&location).cloned().unwrap_or_default();
+                    }
+                    interpolate(&verts, &perspective, location)
+                };
+                self.fragment(x as u32, y as u32, z, inv_w, front, &varyings);
+            }
+        }
+    }
+
+    fn line(&mut self, a: &Vertex, b: &Vertex) {
+        let clipped = self.clip(vec![a.clone(), b.clone()]);
+        if clipped.len() < 2 {
+            return;
+        }
+        let (a, b) = (self.to_screen(&clipped[0]), self.to_screen(&clipped[1]));
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let steps = dx.abs().max(dy.abs()).ceil().max(1.0) as i32;
+        let provoking = a.varyings.clone();
+        let verts = [&a, &b];
+        for step in 0 .. steps {
+            let t = (step as f32 + 0.5) / steps as f32;
+            let (x, y) = (a.x + dx * t, a.y + dy * t);
+            let (x, y) = (x.floor() as i32, y.floor() as i32);
+            if !self.in_bounds(x, y) {
+                continue;
+            }
+            let z = a.z + (b.z - a.z) * t;
+            let inv_w = a.inv_w + (b.inv_w - a.inv_w) * t;
+            let perspective = [(1.0 - t) * a.inv_w / inv_w, t * b.inv_w / inv_w];
+            let varyings = |location: u32, flat: bool| -> Vec<u32> {
+                if flat {
+                    return provoking.get(&location).cloned().unwrap_or_default();
+                }
+                interpolate(&verts, &perspective, location)
+            };
+            self.fragment(x as u32, y as u32, z, inv_w, true, &varyings);
+        }
+    }
+
+    fn point(&mut self, vertex: &Vertex) {
+        let [_, _, z, w] = vertex.position;
+        if w <= 0.0 || (!self.pipeline.rasterizer.depth_clamping && (z < 0.0 || z > w)) {
+            return;
+        }
+        let v = self.to_screen(vertex);
+        let half = vertex.point_size.max(1.0) * 0.5;
+        let (x_range, y_range) = self.clamp_to_bounds(
+            (v.x - half).floor() as i32,
+            (v.x + half).ceil() as i32,
+            (v.y - half).floor() as i32,
+            (v.y + half).ceil() as i32,
+        );
+        for y in y_range {
+            for x in x_range.clone() {
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                if (px - v.x).abs() > half || (py - v.y).abs() > half {
+                    continue;
+                }
+                let varyings = |location: u32, _flat: bool| -> Vec<u32> {
+                    v.varyings.get(&location).cloned().unwrap_or_default()
+                };
+                self.fragment(x as u32, y as u32, v.z, v.inv_w, true, &varyings);
+            }
+        }
+    }
+
+    fn in_bounds(&self, x: i32, y: i32) -> bool {
+        let b = &self.bounds;
+        x >= b.x as i32 && y >= b.y as i32 && x < (b.x + b.w) as i32 && y < (b.y + b.h) as i32
+    }
+
+    fn clamp_to_bounds(
+        &self,
+        min_x: i32,
+        max_x: i32,
+        min_y: i32,
+        max_y: i32,
+    ) -> (std::ops::Range<i32>, std::ops::Range<i32>) {
+        let b = &self.bounds;
+        (
+            min_x.max(b.x as i32) .. max_x.min((b.x + b.w) as i32),
+            min_y.max(b.y as i32) .. max_y.min((b.y + b.h) as i32),
+        )
+    }
+
+    /// Shade a fragment, then test and write it to the attachments.
+    fn fragment(
+        &mut self,
+        x: u32,
+        y: u32,
+        z: f32,
+        inv_w: f32,
+        front: bool,
+        varyings: &dyn Fn(u32, bool) -> Vec<u32>,
+    ) {
+        let mut depth = z;
+        let mut outputs = HashMap::new();
+        if let Some(ref program) = self.pipeline.fragment {
+            let mut invocation = Invocation::new(program, self.resources);
+            for (&location, interface) in &program.inputs {
+                let flat = interface.flat || is_integer(program, interface.ty);
+                let value = typed_value(program, interface.ty, &varyings(location, flat));
+                invocation.set(interface, value);
+            }
+            if let Some(interface) = program.builtin_inputs.get(&builtin::FRAG_COORD) {
+                let coord = [x as f32 + 0.5, y as f32 + 0.5, z, inv_w];
+                invocation.set(interface, float_bits(&coord));
+            }
+            if let Some(interface) = program.builtin_inputs.get(&builtin::FRONT_FACING) {
+                invocation.set(interface, Value::Scalar(front as u32));
+            }
+            let mut shared = program.shared.clone();
+            if invocation.run(&mut shared).is_err() {
+                return;
+            }
+            if let Some(interface) = program.builtin_outputs.get(&builtin::FRAG_DEPTH) {
+                depth = f32::from_bits(invocation.get(interface).scalar());
+            }
+            for (&location, interface) in &program.outputs {
+                outputs.insert(location, components(&invocation.get(interface)));
+            }
+        }
+
+        if let (Some(view), Some(test)) = (self.depth, self.pipeline.depth) {
+            let coord = [x, y, 0];
+            let stored = view.layout.to_float(unsafe { view.read(0, 0, coord) })[0];
+            let value = view.layout.to_float(view.layout.to_texel([depth; 4]))[0];
+            if !compare(test.fun, value, stored) {
+                return;
+            }
+            if test.write {
+                unsafe { view.write(0, 0, coord, view.layout.to_texel([depth, 0.0, 0.0, 1.0])) };
+            }
+        }
+        self.passed += 1;
+
+        for (i, color) in self.colors.iter().enumerate() {
+            let view = match *color {
+                Some(view) => view,
+                None => continue,
+            };
+            let output = match outputs.get(&(i as u32)) {
+                Some(output) => output,
+                None => continue,
+            };
+            let target =
+                self.pipeline
+                    .blender
+                    .targets
+                    .get(i)
+                    .cloned()
+                    .unwrap_or(pso::ColorBlendDesc {
+                        mask: pso::ColorMask::ALL,
+                        blend: None,
+                    });
+            let coord = [x, y, 0];
+            let dst = unsafe { view.read(0, 0, coord) };
+            let mut src = [0, 0, 0, 0];
+            for (s, &o) in src.iter_mut().zip(output) {
+                *s = o;
+            }
+            let blended = match target.blend {
+                Some(blend) if !view.layout.is_integer() => {
+                    let (s, d) = (view.layout.to_float(src), view.layout.to_float(dst));
+                    let k = self.blend_constants;
+                    let mut result = [0.0; 4];
+                    for (c, value) in result.iter_mut().enumerate() {
+                        let op = if c < 3 { blend.color } else { blend.alpha };
+                        *value = blend_channel(op, s, d, k, c);
+                    }
+                    view.layout.to_texel(result)
+                }
+                _ => src,
+            };
+            let mut texel = dst;
+            for c in 0 .. 4 {
+                if target.mask.bits() & (1 << c) != 0 {
+                    texel[c] = blended[c];
+                }
+            }
+            unsafe { view.write(0, 0, coord, texel) };
+        }
+    }
+}
+
+fn lerp_vertex(a: &Vertex, b: &Vertex, t: f32) -> Vertex {
+    let mut position = [0.0; 4];
+    for (i, p) in position.iter_mut().enumerate() {
+        *p = a.position[i] + (b.position[i] - a.position[i]) * t;
+    }
+    let varyings = a
+        .varyings
+        .iter()
+        .map(|(&location, va)| {
+            let vb = b.varyings.get(&location).cloned().unwrap_or_default();
+            let mixed = va
+                .iter()
+                .zip(vb.iter().chain(std::iter::repeat(&0)))
+                .map(|(&x, &y)| {
+                    let (x, y) = (f32::from_bits(x), f32::from_bits(y));
+                    (x + (y - x) * t).to_bits()
+                })
+                .collect();
+            (location, mixed)
+        })
+        .collect();
+    Vertex {
+        position,
+        point_size: a.point_size,
+        varyings,
+    }
+}
+
+fn interpolate(vertices: &[&ScreenVertex], weights: &[f32], location: u32) -> Vec<u32> {
+    let count = vertices[0].varyings.get(&location).map_or(0, Vec::len);
+    (0 .. count)
+        .map(|c| {
+            let value: f32 = vertices
+                .iter()
+                .zip(weights)
+                .map(|(v, w)| {
+                    let x = v.varyings.get(&location).and_then(|x| x.get(c)).cloned();
+                    w * f32::from_bits(x.unwrap_or(0))
+                })
+                .sum();
+            value.to_bits()
+        })
+        .collect()
+}
@@ -0,0 +1,597 @@
+//! Resources living in host memory.
+
+use crate::{
+    format::{Texel, TexelLayout},
+    spirv,
+};
+use hal::{buffer, format, image, pass, pso, query};
+
+use std::fmt;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Block of host memory, shared by the resources bound to it.
+pub struct Block {
+    ptr: *mut u8,
+    len: usize,
+}
+
+// The contents are only accessed through raw pointers, following the
+// synchronization rules of the API.
+unsafe impl Send for Block {}
+unsafe impl Sync for Block {}
+
+impl fmt::Debug for Block {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Block({} bytes)", self.len)
+    }
+}
+
+impl Block {
+    fn new(size: usize) -> Self {
+        let data = vec![0u8; size].into_boxed_slice();
+        Block {
+            ptr: Box::into_raw(data) as *mut u8,
+            len: size,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.ptr
+    }
+}
+
+impl Drop for Block {
+    fn drop(&mut self) {
+        let slice = std::ptr::slice_from_raw_parts_mut(self.ptr, self.len);
+        unsafe { drop(Box::from_raw(slice)) };
+    }
+}
+
+/// Allocated device memory.
+#[derive(Debug)]
+pub struct Memory {
+    pub(crate) block: Arc<Block>,
+}
+
+impl Memory {
+    pub(crate) fn new(size: u64) -> Self {
+        Memory {
+            block: Arc::new(Block::new(size as usize)),
+        }
+    }
+}
+
+/// Range of a memory block used by a resource.
+///
+/// Accesses outside of the range read zeros and discard writes,
+/// like with robust buffer access.
+#[derive(Clone, Debug)]
+pub struct Bound {
+    block: Arc<Block>,
+    offset: u64,
+    size: u64,
+}
+
+impl Bound {
+    pub(crate) fn new(memory: &Memory, offset: u64, size: u64) -> Self {
+        let len = memory.block.len() as u64;
+        Bound {
+            block: Arc::clone(&memory.block),
+            offset: offset.min(len),
+            size: size.min(len.saturating_sub(offset)),
+        }
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Narrow down to a sub-range.
+    pub fn slice(&self, range: buffer::SubRange) -> Self {
+        let offset = range.offset.min(self.size);
+        let size = range.size.unwrap_or(self.size - offset);
+        Bound {
+            block: Arc::clone(&self.block),
+            offset: self.offset + offset,
+            size: size.min(self.size - offset),
+        }
+    }
+
+    /// Move the start of the range by a dynamic offset, keeping its size.
+    pub fn shift(&self, offset: u64) -> Self {
+        let len = self.block.len() as u64;
+        let start = (self.offset + offset).min(len);
+        Bound {
+            block: Arc::clone(&self.block),
+            offset: start,
+            size: self.size.min(len - start),
+        }
+    }
+
+    fn clamp(&self, offset: u64, len: usize) -> usize {
+        if offset >= self.size {
+            0
+        } else {
+            (self.size - offset).min(len as u64) as usize
+        }
+    }
+
+    /// Read bytes starting at `offset`.
+    pub unsafe fn read(&self, offset: u64, out: &mut [u8]) {
+        let count = self.clamp(offset, out.len());
+        let src = self.block.as_ptr().add((self.offset + offset) as usize);
+        std::ptr::copy(src, out.as_mut_ptr(), count);
+        for byte in &mut out[count ..] {
+            *byte = 0;
+        }
+    }
+
+    /// Write bytes starting at `offset`.
+    pub unsafe fn write(&self, offset: u64, data: &[u8]) {
+        let count = self.clamp(offset, data.len());
+        let dst = self.block.as_ptr().add((self.offset + offset) as usize);
+        std::ptr::copy(data.as_ptr(), dst, count);
+    }
+
+    pub unsafe fn read_u32(&self, offset: u64) -> u32 {
+        let mut bytes = [0; 4];
+        self.read(offset, &mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    pub unsafe fn write_u32(&self, offset: u64, value: u32) {
+        self.write(offset, &value.to_le_bytes());
+    }
+
+    pub unsafe fn to_vec(&self) -> Vec<u8> {
+        let mut data = vec![0; self.size as usize];
+        self.read(0, &mut data);
+        data
+    }
+}
+
+#[derive(Debug)]
+pub struct Buffer {
+    pub(crate) size: u64,
+    pub(crate) memory: Option<Bound>,
+}
+
+impl Buffer {
+    /// Memory range of the whole buffer.
+    pub(crate) fn bound(&self) -> Bound {
+        self.memory.clone().expect("Buffer is not bound to memory")
+    }
+}
+
+#[derive(Debug)]
+pub struct BufferView {
+    pub(crate) memory: Bound,
+    pub(crate) layout: TexelLayout,
+}
+
+/// Image stored linearly, level by level, then layer by layer,
+/// then slice by slice and row by row.
+#[derive(Clone, Debug)]
+pub struct Image {
+    pub(crate) kind: image::Kind,
+    pub(crate) levels: image::Level,
+    pub(crate) layout: TexelLayout,
+    pub(crate) memory: Option<Bound>,
+}
+
+impl Image {
+    pub(crate) fn level_extent(&self, level: image::Level) -> image::Extent {
+        self.kind.level_extent(level)
+    }
+
+    /// Size of a single layer of a level.
+    fn layer_size(&self, level: image::Level) -> u64 {
+        let extent = self.level_extent(level);
+        extent.width as u64 * extent.height as u64 * extent.depth as u64 * self.layout.size() as u64
+    }
+
+    fn level_offset(&self, level: image::Level) -> u64 {
+        let layers = self.kind.num_layers() as u64;
+        (0 .. level).map(|l| self.layer_size(l) * layers).sum()
+    }
+
+    pub(crate) fn size(&self) -> u64 {
+        self.level_offset(self.levels)
+    }
+
+    pub(crate) fn footprint(
+        &self,
+        level: image::Level,
+        layer: image::Layer,
+    ) -> image::SubresourceFootprint {
+        let extent = self.level_extent(level);
+        let row_pitch = extent.width as u64 * self.layout.size() as u64;
+        let array_pitch = self.layer_size(level);
+        let start = self.level_offset(level) + layer as u64 * array_pitch;
+        image::SubresourceFootprint {
+            slice: start .. start + array_pitch,
+            row_pitch,
+            array_pitch,
+            depth_pitch: row_pitch * extent.height as u64,
+        }
+    }
+
+    fn texel_offset(&self, level: image::Level, layer: image::Layer, coord: [u32; 3]) -> u64 {
+        let footprint = self.footprint(level, layer);
+        footprint.slice.start
+            + coord[2] as u64 * footprint.depth_pitch
+            + coord[1] as u64 * footprint.row_pitch
+            + coord[0] as u64 * self.layout.size() as u64
+    }
+
+    fn memory(&self) -> &Bound {
+        self.memory.as_ref().expect("Image is not bound to memory")
+    }
+
+    pub(crate) unsafe fn read_raw(
+        &self,
+        level: image::Level,
+        layer: image::Layer,
+        coord: [u32; 3],
+        out: &mut [u8],
+    ) {
+        let offset = self.texel_offset(level, layer, coord);
+        self.memory().read(offset, out);
+    }
+
+    pub(crate) unsafe fn write_raw(
+        &self,
+        level: image::Level,
+        layer: image::Layer,
+        coord: [u32; 3],
+        data: &[u8],
+    ) {
+        let offset = self.texel_offset(level, layer, coord);
+        self.memory().write(offset, data);
+    }
+
+    pub(crate) unsafe fn read(
+        &self,
+        level: image::Level,
+        layer: image::Layer,
+        coord: [u32; 3],
+    ) -> Texel {
+        let mut bytes = [0; 16];
+        let size = self.layout.size();
+        self.read_raw(level, layer, coord, &mut bytes[.. size]);
+        self.layout.read(&bytes[.. size])
+    }
+
+    pub(crate) unsafe fn write(
+        &self,
+        level: image::Level,
+        layer: image::Layer,
+        coord: [u32; 3],
+        texel: Texel,
+    ) {
+        let mut bytes = [0; 16];
+        let size = self.layout.size();
+        self.layout.write(texel, &mut bytes[.. size]);
+        self.write_raw(level, layer, coord, &bytes[.. size]);
+    }
+}
+
+/// Mip chain of an image view, as seen by the shaders.
+pub(crate) struct ViewLevels<'a> {
+    pub view: &'a ImageView,
+    pub layer: image::Layer,
+}
+
+impl auxil::sampler::TexelSource for ViewLevels<'_> {
+    fn dimensions(&self) -> usize {
+        match self.view.kind {
+            image::ViewKind::D1 | image::ViewKind::D1Array => 1,
+            image::ViewKind::D3 => 3,
+            _ => 2,
+        }
+    }
+
+    fn num_levels(&self) -> image::Level {
+        self.view.range.levels.end - self.view.range.levels.start
+    }
+
+    fn level_extent(&self, level: image::Level) -> image::Extent {
+        self.view
+            .image
+            .level_extent(self.view.range.levels.start + level)
+    }
+
+    fn fetch(&self, level: image::Level, coord: [u32; 3]) -> auxil::sampler::Texel {
+        let texel = unsafe { self.view.read(level, self.layer, coord) };
+        self.view.layout.to_float(texel)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ImageView {
+    pub(crate) image: Image,
+    pub(crate) kind: image::ViewKind,
+    pub(crate) layout: TexelLayout,
+    pub(crate) swizzle: format::Swizzle,
+    pub(crate) range: image::SubresourceRange,
+}
+
+impl ImageView {
+    pub(crate) fn extent(&self) -> image::Extent {
+        self.image.level_extent(self.range.levels.start)
+    }
+
+    fn swizzle(&self, texel: Texel) -> Texel {
+        use hal::format::Component as C;
+        let one = self.layout.to_texel([1.0; 4])[0];
+        let pick = |component| match component {
+            C::Zero => 0,
+            C::One => one,
+            C::R => texel[0],
+            C::G => texel[1],
+            C::B => texel[2],
+            C::A => texel[3],
+        };
+        let format::Swizzle(r, g, b, a) = self.swizzle;
+        [pick(r), pick(g), pick(b), pick(a)]
+    }
+
+    /// Read a texel from a level and layer relative to the view.
+    pub(crate) unsafe fn read(
+        &self,
+        level: image::Level,
+        layer: image::Layer,
+        coord: [u32; 3],
+    ) -> Texel {
+        let mut bytes = [0; 16];
+        let size = self.layout.size();
+        self.image.read_raw(
+            self.range.levels.start + level,
+            self.range.layers.start + layer,
+            coord,
+            &mut bytes[.. size],
+        );
+        self.swizzle(self.layout.read(&bytes[.. size]))
+    }
+
+    /// Write a texel to a level and layer relative to the view.
+    pub(crate) unsafe fn write(
+        &self,
+        level: image::Level,
+        layer: image::Layer,
+        coord: [u32; 3],
+        texel: Texel,
+    ) {
+        let mut bytes = [0; 16];
+        let size = self.layout.size();
+        self.layout.write(texel, &mut bytes[.. size]);
+        self.image.write_raw(
+            self.range.levels.start + level,
+            self.range.layers.start + layer,
+            coord,
+            &bytes[.. size],
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct Sampler(pub(crate) Arc<auxil::sampler::Sampler>);
+
+#[derive(Debug)]
+pub struct ShaderModule(pub(crate) Arc<spirv::Module>);
+
+#[derive(Debug)]
+pub struct SubpassDesc {
+    pub(crate) colors: Vec<Option<usize>>,
+    pub(crate) depth_stencil: Option<usize>,
+    pub(crate) resolves: Vec<Option<usize>>,
+}
+
+#[derive(Debug)]
+pub struct RenderPassDesc {
+    pub(crate) attachments: Vec<pass::Attachment>,
+    pub(crate) subpasses: Vec<SubpassDesc>,
+}
+
+#[derive(Debug)]
+pub struct RenderPass(pub(crate) Arc<RenderPassDesc>);
+
+#[derive(Debug)]
+pub struct Framebuffer {
+    pub(crate) attachments: Arc<[ImageView]>,
+}
+
+#[derive(Debug)]
+pub struct PipelineLayout;
+
+#[derive(Debug)]
+pub struct GraphicsPipelineDesc {
+    pub(crate) vertex: spirv::Program,
+    pub(crate) fragment: Option<spirv::Program>,
+    pub(crate) vertex_buffers: Vec<pso::VertexBufferDesc>,
+    pub(crate) attributes: Vec<pso::AttributeDesc>,
+    pub(crate) primitive: pso::Primitive,
+    pub(crate) restart_index: Option<hal::IndexType>,
+    pub(crate) rasterizer: pso::Rasterizer,
+    pub(crate) blender: pso::BlendDesc,
+    pub(crate) depth: Option<pso::DepthTest>,
+    pub(crate) baked_states: pso::BakedStates,
+}
+
+#[derive(Debug)]
+pub struct GraphicsPipeline(pub(crate) Arc<GraphicsPipelineDesc>);
+
+#[derive(Debug)]
+pub struct ComputePipeline(pub(crate) Arc<spirv::Program>);
+
+#[derive(Debug)]
+pub struct DescriptorSetLayout {
+    pub(crate) bindings: Vec<pso::DescriptorSetLayoutBinding>,
+    pub(crate) immutable_samplers: Vec<Arc<auxil::sampler::Sampler>>,
+}
+
+/// Resource written to a descriptor set.
+#[derive(Clone, Debug)]
+pub enum Descriptor {
+    Sampler(Arc<auxil::sampler::Sampler>),
+    Image(ImageView),
+    CombinedImageSampler(ImageView, Arc<auxil::sampler::Sampler>),
+    Buffer(Bound),
+    TexelBuffer(Bound, TexelLayout),
+}
+
+/// Descriptors of a binding.
+#[derive(Clone, Debug)]
+pub struct DescriptorBinding {
+    pub(crate) binding: pso::DescriptorBinding,
+    pub(crate) ty: pso::DescriptorType,
+    pub(crate) descriptors: Vec<Option<Descriptor>>,
+}
+
+impl DescriptorBinding {
+    pub(crate) fn is_dynamic(&self) -> bool {
+        match self.ty {
+            pso::DescriptorType::Buffer {
+                format: pso::BufferDescriptorFormat::Structured { dynamic_offset },
+                ..
+            } => dynamic_offset,
+            _ => false,
+        }
+    }
+}
+
+/// Descriptor set, whose contents are read when the commands are executed.
+#[derive(Clone, Debug)]
+pub struct DescriptorSet {
+    pub(crate) bindings: Arc<Mutex<Vec<DescriptorBinding>>>,
+}
+
+impl DescriptorSet {
+    pub(crate) fn new(layout: &DescriptorSetLayout) -> Self {
+        let mut samplers = layout.immutable_samplers.iter();
+        let mut bindings = layout
+            .bindings
+            .iter()
+            .map(|binding| DescriptorBinding {
+                binding: binding.binding,
+                ty: binding.ty,
+                descriptors: (0 .. binding.count)
+                    .map(|_| {
+                        if binding.immutable_samplers {
+                            samplers.next().cloned().map(Descriptor::Sampler)
+                        } else {
+                            None
+                        }
+                    })
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+        bindings.sort_by_key(|b| b.binding);
+        DescriptorSet {
+            bindings: Arc::new(Mutex::new(bindings)),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct DescriptorPool;
+
+impl pso::DescriptorPool<crate::Backend> for DescriptorPool {
+    unsafe fn allocate_set(
+        &mut self,
+        layout: &DescriptorSetLayout,
+    ) -> Result<DescriptorSet, pso::AllocationError> {
+        Ok(DescriptorSet::new(layout))
+    }
+
+    unsafe fn free<I>(&mut self, _descriptor_sets: I)
+    where
+        I: IntoIterator<Item = DescriptorSet>,
+    {
+    }
+
+    unsafe fn reset(&mut self) {}
+}
+
+#[derive(Debug, Default)]
+pub struct Fence(pub(crate) AtomicBool);
+
+impl Fence {
+    pub(crate) fn is_signaled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn set(&self, signaled: bool) {
+        self.0.store(signaled, Ordering::Release)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Event(pub(crate) Arc<AtomicBool>);
+
+#[derive(Debug)]
+pub struct QueryPoolData {
+    pub(crate) ty: query::Type,
+    /// Results of the queries, `None` when not available.
+    pub(crate) results: Mutex<Vec<Option<u64>>>,
+}
+
+#[derive(Debug)]
+pub struct QueryPool(pub(crate) Arc<QueryPoolData>);
+
+impl QueryPoolData {
+    pub(crate) fn set(&self, id: query::Id, value: Option<u64>) {
+        if let Some(result) = self.results.lock().unwrap().get_mut(id as usize) {
+            *result = value;
+        }
+    }
+
+    pub(crate) fn add(&self, id: query::Id, count: u64) {
+        if let Some(result) = self.results.lock().unwrap().get_mut(id as usize) {
+            *result = Some(result.unwrap_or(0) + count);
+        }
+    }
+
+    /// Write the results of a range of queries, returning whether they were all available.
+    pub(crate) fn write_results(
+        &self,
+        queries: Range<query::Id>,
+        mut write: impl FnMut(usize, u64, bool),
+        stride: usize,
+        flags: query::ResultFlags,
+    ) -> bool {
+        let results = self.results.lock().unwrap();
+        let value_size = if flags.contains(query::ResultFlags::BITS_64) {
+            8
+        } else {
+            4
+        };
+        let mut all_available = true;
+        for (i, id) in queries.enumerate() {
+            let result = results.get(id as usize).cloned().unwrap_or(None);
+            let offset = i * stride;
+            match result {
+                Some(value) => write(offset, value, value_size == 8),
+                None if flags.contains(query::ResultFlags::PARTIAL) => {
+                    write(offset, 0, value_size == 8)
+                }
+                None => {}
+            }
+            if flags.contains(query::ResultFlags::WITH_AVAILABILITY) {
+                write(
+                    offset + value_size,
+                    result.is_some() as u64,
+                    value_size == 8,
+                );
+            }
+            all_available &= result.is_some();
+        }
+        all_available
+    }
+}
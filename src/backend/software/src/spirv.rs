@@ -0,0 +1,1244 @@
+//! Parsing and specialization of SPIR-V modules.
+//!
+//! Only the subset of SPIR-V produced for simple compute, vertex and fragment
+//! shaders is supported: 32-bit scalars, vectors, column-major matrices,
+//! arrays and structures, buffers, push constants, sampled and storage images,
+//! and most of the core and `GLSL.std.450` instructions. Modules using anything
+//! else fail to be created instead of producing wrong results.
+
+use crate::resource::{Bound, Descriptor};
+use hal::{device::ShaderError, pso};
+
+use std::collections::HashMap;
+
+pub type Id = u32;
+
+/// Opcodes of the supported instructions.
+#[allow(dead_code)]
+pub(crate) mod op {
+    pub const NOP: u16 = 0;
+    pub const UNDEF: u16 = 1;
+    pub const SOURCE_CONTINUED: u16 = 2;
+    pub const SOURCE: u16 = 3;
+    pub const SOURCE_EXTENSION: u16 = 4;
+    pub const NAME: u16 = 5;
+    pub const MEMBER_NAME: u16 = 6;
+    pub const STRING: u16 = 7;
+    pub const LINE: u16 = 8;
+    pub const EXTENSION: u16 = 10;
+    pub const EXT_INST_IMPORT: u16 = 11;
+    pub const EXT_INST: u16 = 12;
+    pub const MEMORY_MODEL: u16 = 14;
+    pub const ENTRY_POINT: u16 = 15;
+    pub const EXECUTION_MODE: u16 = 16;
+    pub const CAPABILITY: u16 = 17;
+    pub const TYPE_VOID: u16 = 19;
+    pub const TYPE_BOOL: u16 = 20;
+    pub const TYPE_INT: u16 = 21;
+    pub const TYPE_FLOAT: u16 = 22;
+    pub const TYPE_VECTOR: u16 = 23;
+    pub const TYPE_MATRIX: u16 = 24;
+    pub const TYPE_IMAGE: u16 = 25;
+    pub const TYPE_SAMPLER: u16 = 26;
+    pub const TYPE_SAMPLED_IMAGE: u16 = 27;
+    pub const TYPE_ARRAY: u16 = 28;
+    pub const TYPE_RUNTIME_ARRAY: u16 = 29;
+    pub const TYPE_STRUCT: u16 = 30;
+    pub const TYPE_POINTER: u16 = 32;
+    pub const TYPE_FUNCTION: u16 = 33;
+    pub const CONSTANT_TRUE: u16 = 41;
+    pub const CONSTANT_FALSE: u16 = 42;
+    pub const CONSTANT: u16 = 43;
+    pub const CONSTANT_COMPOSITE: u16 = 44;
+    pub const CONSTANT_NULL: u16 = 46;
+    pub const SPEC_CONSTANT_TRUE: u16 = 48;
+    pub const SPEC_CONSTANT_FALSE: u16 = 49;
+    pub const SPEC_CONSTANT: u16 = 50;
+    pub const SPEC_CONSTANT_COMPOSITE: u16 = 51;
+    pub const FUNCTION: u16 = 54;
+    pub const FUNCTION_PARAMETER: u16 = 55;
+    pub const FUNCTION_END: u16 = 56;
+    pub const FUNCTION_CALL: u16 = 57;
+    pub const VARIABLE: u16 = 59;
+    pub const LOAD: u16 = 61;
+    pub const STORE: u16 = 62;
+    pub const COPY_MEMORY: u16 = 63;
+    pub const ACCESS_CHAIN: u16 = 65;
+    pub const IN_BOUNDS_ACCESS_CHAIN: u16 = 66;
+    pub const ARRAY_LENGTH: u16 = 68;
+    pub const DECORATE: u16 = 71;
+    pub const MEMBER_DECORATE: u16 = 72;
+    pub const VECTOR_EXTRACT_DYNAMIC: u16 = 77;
+    pub const VECTOR_INSERT_DYNAMIC: u16 = 78;
+    pub const VECTOR_SHUFFLE: u16 = 79;
+    pub const COMPOSITE_CONSTRUCT: u16 = 80;
+    pub const COMPOSITE_EXTRACT: u16 = 81;
+    pub const COMPOSITE_INSERT: u16 = 82;
+    pub const COPY_OBJECT: u16 = 83;
+    pub const TRANSPOSE: u16 = 84;
+    pub const SAMPLED_IMAGE: u16 = 86;
+    pub const IMAGE_SAMPLE_IMPLICIT_LOD: u16 = 87;
+    pub const IMAGE_SAMPLE_EXPLICIT_LOD: u16 = 88;
+    pub const IMAGE_SAMPLE_DREF_IMPLICIT_LOD: u16 = 89;
+    pub const IMAGE_SAMPLE_DREF_EXPLICIT_LOD: u16 = 90;
+    pub const IMAGE_FETCH: u16 = 95;
+    pub const IMAGE_READ: u16 = 98;
+    pub const IMAGE_WRITE: u16 = 99;
+    pub const IMAGE: u16 = 100;
+    pub const IMAGE_QUERY_SIZE_LOD: u16 = 103;
+    pub const IMAGE_QUERY_SIZE: u16 = 104;
+    pub const IMAGE_QUERY_LEVELS: u16 = 106;
+    pub const CONVERT_F_TO_U: u16 = 109;
+    pub const CONVERT_F_TO_S: u16 = 110;
+    pub const CONVERT_S_TO_F: u16 = 111;
+    pub const CONVERT_U_TO_F: u16 = 112;
+    pub const U_CONVERT: u16 = 113;
+    pub const S_CONVERT: u16 = 114;
+    pub const F_CONVERT: u16 = 115;
+    pub const BITCAST: u16 = 124;
+    pub const S_NEGATE: u16 = 126;
+    pub const F_NEGATE: u16 = 127;
+    pub const I_ADD: u16 = 128;
+    pub const F_ADD: u16 = 129;
+    pub const I_SUB: u16 = 130;
+    pub const F_SUB: u16 = 131;
+    pub const I_MUL: u16 = 132;
+    pub const F_MUL: u16 = 133;
+    pub const U_DIV: u16 = 134;
+    pub const S_DIV: u16 = 135;
+    pub const F_DIV: u16 = 136;
+    pub const U_MOD: u16 = 137;
+    pub const S_REM: u16 = 138;
+    pub const S_MOD: u16 = 139;
+    pub const F_REM: u16 = 140;
+    pub const F_MOD: u16 = 141;
+    pub const VECTOR_TIMES_SCALAR: u16 = 142;
+    pub const MATRIX_TIMES_SCALAR: u16 = 143;
+    pub const VECTOR_TIMES_MATRIX: u16 = 144;
+    pub const MATRIX_TIMES_VECTOR: u16 = 145;
+    pub const MATRIX_TIMES_MATRIX: u16 = 146;
+    pub const OUTER_PRODUCT: u16 = 147;
+    pub const DOT: u16 = 148;
+    pub const ANY: u16 = 154;
+    pub const ALL: u16 = 155;
+    pub const IS_NAN: u16 = 156;
+    pub const IS_INF: u16 = 157;
+    pub const LOGICAL_EQUAL: u16 = 164;
+    pub const LOGICAL_NOT_EQUAL: u16 = 165;
+    pub const LOGICAL_OR: u16 = 166;
+    pub const LOGICAL_AND: u16 = 167;
+    pub const LOGICAL_NOT: u16 = 168;
+    pub const SELECT: u16 = 169;
+    pub const I_EQUAL: u16 = 170;
+    pub const I_NOT_EQUAL: u16 = 171;
+    pub const U_GREATER_THAN: u16 = 172;
+    pub const S_GREATER_THAN: u16 = 173;
+    pub const U_GREATER_THAN_EQUAL: u16 = 174;
+    pub const S_GREATER_THAN_EQUAL: u16 = 175;
+    pub const U_LESS_THAN: u16 = 176;
+    pub const S_LESS_THAN: u16 = 177;
+    pub const U_LESS_THAN_EQUAL: u16 = 178;
+    pub const S_LESS_THAN_EQUAL: u16 = 179;
+    pub const F_ORD_EQUAL: u16 = 180;
+    pub const F_UNORD_EQUAL: u16 = 181;
+    pub const F_ORD_NOT_EQUAL: u16 = 182;
+    pub const F_UNORD_NOT_EQUAL: u16 = 183;
+    pub const F_ORD_LESS_THAN: u16 = 184;
+    pub const F_UNORD_LESS_THAN: u16 = 185;
+    pub const F_ORD_GREATER_THAN: u16 = 186;
+    pub const F_UNORD_GREATER_THAN: u16 = 187;
+    pub const F_ORD_LESS_THAN_EQUAL: u16 = 188;
+    pub const F_UNORD_LESS_THAN_EQUAL: u16 = 189;
+    pub const F_ORD_GREATER_THAN_EQUAL: u16 = 190;
+    pub const F_UNORD_GREATER_THAN_EQUAL: u16 = 191;
+    pub const SHIFT_RIGHT_LOGICAL: u16 = 194;
+    pub const SHIFT_RIGHT_ARITHMETIC: u16 = 195;
+    pub const SHIFT_LEFT_LOGICAL: u16 = 196;
+    pub const BITWISE_OR: u16 = 197;
+    pub const BITWISE_XOR: u16 = 198;
+    pub const BITWISE_AND: u16 = 199;
+    pub const NOT: u16 = 200;
+    pub const BIT_FIELD_INSERT: u16 = 201;
+    pub const BIT_FIELD_S_EXTRACT: u16 = 202;
+    pub const BIT_FIELD_U_EXTRACT: u16 = 203;
+    pub const BIT_REVERSE: u16 = 204;
+    pub const BIT_COUNT: u16 = 205;
+    pub const MEMORY_BARRIER: u16 = 225;
+    pub const ATOMIC_LOAD: u16 = 227;
+    pub const ATOMIC_STORE: u16 = 228;
+    pub const ATOMIC_EXCHANGE: u16 = 229;
+    pub const ATOMIC_COMPARE_EXCHANGE: u16 = 230;
+    pub const ATOMIC_I_INCREMENT: u16 = 232;
+    pub const ATOMIC_I_DECREMENT: u16 = 233;
+    pub const ATOMIC_I_ADD: u16 = 234;
+    pub const ATOMIC_I_SUB: u16 = 235;
+    pub const ATOMIC_S_MIN: u16 = 236;
+    pub const ATOMIC_U_MIN: u16 = 237;
+    pub const ATOMIC_S_MAX: u16 = 238;
+    pub const ATOMIC_U_MAX: u16 = 239;
+    pub const ATOMIC_AND: u16 = 240;
+    pub const ATOMIC_OR: u16 = 241;
+    pub const ATOMIC_XOR: u16 = 242;
+    pub const PHI: u16 = 245;
+    pub const LOOP_MERGE: u16 = 246;
+    pub const SELECTION_MERGE: u16 = 247;
+    pub const LABEL: u16 = 248;
+    pub const BRANCH: u16 = 249;
+    pub const BRANCH_CONDITIONAL: u16 = 250;
+    pub const SWITCH: u16 = 251;
+    pub const KILL: u16 = 252;
+    pub const RETURN: u16 = 253;
+    pub const RETURN_VALUE: u16 = 254;
+    pub const UNREACHABLE: u16 = 255;
+    pub const NO_LINE: u16 = 317;
+    pub const MODULE_PROCESSED: u16 = 330;
+}
+
+/// Instructions of the `GLSL.std.450` extended instruction set.
+#[allow(dead_code)]
+pub(crate) mod glsl {
+    pub const ROUND: u32 = 1;
+    pub const ROUND_EVEN: u32 = 2;
+    pub const TRUNC: u32 = 3;
+    pub const F_ABS: u32 = 4;
+    pub const S_ABS: u32 = 5;
+    pub const F_SIGN: u32 = 6;
+    pub const S_SIGN: u32 = 7;
+    pub const FLOOR: u32 = 8;
+    pub const CEIL: u32 = 9;
+    pub const FRACT: u32 = 10;
+    pub const RADIANS: u32 = 11;
+    pub const DEGREES: u32 = 12;
+    pub const SIN: u32 = 13;
+    pub const COS: u32 = 14;
+    pub const TAN: u32 = 15;
+    pub const ASIN: u32 = 16;
+    pub const ACOS: u32 = 17;
+    pub const ATAN: u32 = 18;
+    pub const SINH: u32 = 19;
+    pub const COSH: u32 = 20;
+    pub const TANH: u32 = 21;
+    pub const ASINH: u32 = 22;
+    pub const ACOSH: u32 = 23;
+    pub const ATANH: u32 = 24;
+    pub const ATAN2: u32 = 25;
+    pub const POW: u32 = 26;
+    pub const EXP: u32 = 27;
+    pub const LOG: u32 = 28;
+    pub const EXP2: u32 = 29;
+    pub const LOG2: u32 = 30;
+    pub const SQRT: u32 = 31;
+    pub const INVERSE_SQRT: u32 = 32;
+    pub const F_MIN: u32 = 37;
+    pub const U_MIN: u32 = 38;
+    pub const S_MIN: u32 = 39;
+    pub const F_MAX: u32 = 40;
+    pub const U_MAX: u32 = 41;
+    pub const S_MAX: u32 = 42;
+    pub const F_CLAMP: u32 = 43;
+    pub const U_CLAMP: u32 = 44;
+    pub const S_CLAMP: u32 = 45;
+    pub const F_MIX: u32 = 46;
+    pub const STEP: u32 = 48;
+    pub const SMOOTH_STEP: u32 = 49;
+    pub const FMA: u32 = 50;
+    pub const PACK_SNORM_4X8: u32 = 54;
+    pub const PACK_UNORM_4X8: u32 = 55;
+    pub const PACK_HALF_2X16: u32 = 58;
+    pub const UNPACK_HALF_2X16: u32 = 62;
+    pub const UNPACK_SNORM_4X8: u32 = 63;
+    pub const UNPACK_UNORM_4X8: u32 = 64;
+    pub const LENGTH: u32 = 66;
+    pub const DISTANCE: u32 = 67;
+    pub const CROSS: u32 = 68;
+    pub const NORMALIZE: u32 = 69;
+    pub const FACE_FORWARD: u32 = 70;
+    pub const REFLECT: u32 = 71;
+    pub const REFRACT: u32 = 72;
+    pub const FIND_I_LSB: u32 = 73;
+    pub const FIND_S_MSB: u32 = 74;
+    pub const FIND_U_MSB: u32 = 75;
+    pub const N_MIN: u32 = 79;
+    pub const N_MAX: u32 = 80;
+    pub const N_CLAMP: u32 = 81;
+}
+
+mod decoration {
+    pub const SPEC_ID: u32 = 1;
+    pub const ROW_MAJOR: u32 = 4;
+    pub const ARRAY_STRIDE: u32 = 6;
+    pub const MATRIX_STRIDE: u32 = 7;
+    pub const BUILT_IN: u32 = 11;
+    pub const FLAT: u32 = 14;
+    pub const LOCATION: u32 = 30;
+    pub const BINDING: u32 = 33;
+    pub const DESCRIPTOR_SET: u32 = 34;
+    pub const OFFSET: u32 = 35;
+}
+
+/// Built-in variables handled by the pipelines.
+#[allow(dead_code)]
+pub mod builtin {
+    pub const POSITION: u32 = 0;
+    pub const POINT_SIZE: u32 = 1;
+    pub const VERTEX_ID: u32 = 5;
+    pub const INSTANCE_ID: u32 = 6;
+    pub const FRAG_COORD: u32 = 15;
+    pub const FRONT_FACING: u32 = 17;
+    pub const FRAG_DEPTH: u32 = 22;
+    pub const NUM_WORKGROUPS: u32 = 24;
+    pub const WORKGROUP_SIZE: u32 = 25;
+    pub const WORKGROUP_ID: u32 = 26;
+    pub const LOCAL_INVOCATION_ID: u32 = 27;
+    pub const GLOBAL_INVOCATION_ID: u32 = 28;
+    pub const LOCAL_INVOCATION_INDEX: u32 = 29;
+    pub const VERTEX_INDEX: u32 = 42;
+    pub const INSTANCE_INDEX: u32 = 43;
+}
+
+pub(crate) mod storage {
+    pub const UNIFORM_CONSTANT: u32 = 0;
+    pub const INPUT: u32 = 1;
+    pub const UNIFORM: u32 = 2;
+    pub const OUTPUT: u32 = 3;
+    pub const WORKGROUP: u32 = 4;
+    pub const PRIVATE: u32 = 6;
+    pub const FUNCTION: u32 = 7;
+    pub const PUSH_CONSTANT: u32 = 9;
+    pub const STORAGE_BUFFER: u32 = 12;
+}
+
+const MAGIC: u32 = 0x0723_0203;
+const EXECUTION_MODE_LOCAL_SIZE: u32 = 17;
+const EXECUTION_MODE_ORIGIN_LOWER_LEFT: u32 = 8;
+/// Image operands that are understood: `Bias`, `Lod` and `Grad`.
+pub(crate) const IMAGE_OPERANDS_SUPPORTED: u32 = 0x7;
+const DIM_CUBE: u32 = 3;
+const DIM_SUBPASS_DATA: u32 = 6;
+
+#[derive(Clone, Debug)]
+pub enum Type {
+    Void,
+    Bool,
+    Int {
+        signed: bool,
+    },
+    Float,
+    Vector {
+        component: Id,
+        count: u32,
+    },
+    Matrix {
+        column: Id,
+        count: u32,
+    },
+    Image,
+    Sampler,
+    SampledImage,
+    /// Array of a constant length, or runtime array.
+    Array {
+        element: Id,
+        length: Option<Id>,
+    },
+    Struct {
+        members: Vec<Id>,
+    },
+    Pointer {
+        pointee: Id,
+    },
+    Function,
+}
+
+/// Instruction, with the words following the opcode.
+#[derive(Clone, Debug)]
+pub struct Inst {
+    pub opcode: u16,
+    pub words: Vec<u32>,
+}
+
+#[derive(Debug)]
+pub struct Block {
+    pub label: Id,
+    pub phis: Vec<Inst>,
+    /// Instructions of the block, ending with the terminator.
+    pub body: Vec<Inst>,
+}
+
+#[derive(Debug)]
+pub struct Function {
+    pub params: Vec<Id>,
+    pub blocks: Vec<Block>,
+    pub labels: HashMap<Id, usize>,
+}
+
+#[derive(Debug)]
+struct EntryPoint {
+    model: u32,
+    function: Id,
+    name: String,
+}
+
+#[derive(Debug)]
+struct Variable {
+    id: Id,
+    class: u32,
+    pointee: Id,
+    initializer: Option<Id>,
+}
+
+type Decorations = HashMap<u32, Vec<u32>>;
+
+/// Parsed and validated SPIR-V module.
+#[derive(Debug)]
+pub struct Module {
+    bound: u32,
+    entry_points: Vec<EntryPoint>,
+    execution_modes: HashMap<Id, Vec<(u32, Vec<u32>)>>,
+    decorations: HashMap<Id, Decorations>,
+    member_decorations: HashMap<(Id, u32), Decorations>,
+    pub(crate) types: HashMap<Id, Type>,
+    constants: Vec<Inst>,
+    variables: Vec<Variable>,
+    pub(crate) functions: HashMap<Id, Function>,
+}
+
+fn error(message: String) -> ShaderError {
+    ShaderError::CompilationFailed(message)
+}
+
+fn string(words: &[u32]) -> (String, usize) {
+    let mut bytes = Vec::new();
+    for (i, word) in words.iter().enumerate() {
+        for byte in word.to_le_bytes().iter() {
+            if *byte == 0 {
+                return (String::from_utf8_lossy(&bytes).into_owned(), i + 1);
+            }
+            bytes.push(*byte);
+        }
+    }
+    (String::from_utf8_lossy(&bytes).into_owned(), words.len())
+}
+
+fn is_supported(opcode: u16) -> bool {
+    use self::op::*;
+    matches!(
+        opcode,
+        NOP | UNDEF | LINE | NO_LINE | FUNCTION_CALL | VARIABLE | LOAD | STORE | COPY_MEMORY
+        | ACCESS_CHAIN | IN_BOUNDS_ACCESS_CHAIN | ARRAY_LENGTH | EXT_INST
+        | VECTOR_EXTRACT_DYNAMIC ..= TRANSPOSE
+        | SAMPLED_IMAGE ..= IMAGE_SAMPLE_DREF_EXPLICIT_LOD
+        | IMAGE_FETCH | IMAGE_READ | IMAGE_WRITE | IMAGE
+        | IMAGE_QUERY_SIZE_LOD | IMAGE_QUERY_SIZE | IMAGE_QUERY_LEVELS
+        | CONVERT_F_TO_U ..= F_CONVERT | BITCAST
+        | S_NEGATE ..= DOT
+        | ANY ..= IS_INF
+        | LOGICAL_EQUAL ..= F_UNORD_GREATER_THAN_EQUAL
+        | SHIFT_RIGHT_LOGICAL ..= BIT_COUNT
+        | MEMORY_BARRIER
+        | ATOMIC_LOAD ..= ATOMIC_COMPARE_EXCHANGE
+        | ATOMIC_I_INCREMENT ..= ATOMIC_XOR
+        | LOOP_MERGE | SELECTION_MERGE
+        | BRANCH ..= UNREACHABLE
+    )
+}
+
+fn is_glsl_supported(instruction: u32) -> bool {
+    use self::glsl::*;
+    matches!(
+        instruction,
+        ROUND ..= INVERSE_SQRT
+        | F_MIN ..= F_MIX | STEP | SMOOTH_STEP | FMA
+        | PACK_SNORM_4X8 | PACK_UNORM_4X8 | PACK_HALF_2X16
+        | UNPACK_HALF_2X16 | UNPACK_SNORM_4X8 | UNPACK_UNORM_4X8
+        | LENGTH ..= FIND_U_MSB
+        | N_MIN ..= N_CLAMP
+    )
+}
+
+fn is_terminator(opcode: u16) -> bool {
+    (op::BRANCH ..= op::UNREACHABLE).contains(&opcode)
+}
+
+impl Module {
+    /// Parse a module, rejecting anything the interpreter doesn't support.
+    pub fn parse(words: &[u32]) -> Result<Self, ShaderError> {
+        if words.len() < 5 || words[0] != MAGIC {
+            return Err(error("invalid SPIR-V header".to_string()));
+        }
+        let mut module = Module {
+            bound: words[3],
+            entry_points: Vec::new(),
+            execution_modes: HashMap::new(),
+            decorations: HashMap::new(),
+            member_decorations: HashMap::new(),
+            types: HashMap::new(),
+            constants: Vec::new(),
+            variables: Vec::new(),
+            functions: HashMap::new(),
+        };
+        let mut glsl_set = None;
+        let mut function: Option<(Id, Function)> = None;
+        let mut block: Option<Block> = None;
+
+        let mut rest = &words[5 ..];
+        while !rest.is_empty() {
+            let count = (rest[0] >> 16) as usize;
+            let opcode = rest[0] as u16;
+            if count == 0 || count > rest.len() {
+                return Err(error("truncated instruction".to_string()));
+            }
+            let inst = Inst {
+                opcode,
+                words: rest[1 .. count].to_vec(),
+            };
+            rest = &rest[count ..];
+            let w = &inst.words;
+            let operand = |i: usize| {
+                w.get(i)
+                    .cloned()
+                    .ok_or_else(|| error(format!("missing operands of instruction {}", opcode)))
+            };
+
+            if let Some(ref mut current) = block {
+                match opcode {
+                    op::PHI => current.phis.push(inst),
+                    op::LOOP_MERGE | op::SELECTION_MERGE | op::LINE | op::NO_LINE => {}
+                    op::EXT_INST => {
+                        if Some(operand(2)?) != glsl_set {
+                            return Err(error("unknown extended instruction set".to_string()));
+                        }
+                        if !is_glsl_supported(operand(3)?) {
+                            return Err(error(format!(
+                                "unsupported GLSL.std.450 instruction {}",
+                                w[3]
+                            )));
+                        }
+                        current.body.push(inst);
+                    }
+                    op::IMAGE_SAMPLE_IMPLICIT_LOD ..= op::IMAGE_WRITE => {
+                        let mask_index = match opcode {
+                            op::IMAGE_SAMPLE_DREF_IMPLICIT_LOD
+                            | op::IMAGE_SAMPLE_DREF_EXPLICIT_LOD => 5,
+                            op::IMAGE_WRITE => 3,
+                            _ => 4,
+                        };
+                        let mask = w.get(mask_index).cloned().unwrap_or(0);
+                        if !is_supported(opcode) || mask & !IMAGE_OPERANDS_SUPPORTED != 0 {
+                            return Err(error(format!(
+                                "unsupported image instruction {} with operands {:#x}",
+                                opcode, mask
+                            )));
+                        }
+                        current.body.push(inst);
+                    }
+                    _ if is_supported(opcode) => {
+                        let terminator = is_terminator(opcode);
+                        current.body.push(inst);
+                        if terminator {
+                            let (_, ref mut fun) = function.as_mut().unwrap();
+                            let done = block.take().unwrap();
+                            fun.labels.insert(done.label, fun.blocks.len());
+                            fun.blocks.push(done);
+                        }
+                    }
+                    _ => return Err(error(format!("unsupported instruction {}", opcode))),
+                }
+                continue;
+            }
+
+            if let Some((_, ref mut fun)) = function {
+                match opcode {
+                    op::FUNCTION_PARAMETER => fun.params.push(operand(1)?),
+                    op::LABEL => {
+                        block = Some(Block {
+                            label: operand(0)?,
+                            phis: Vec::new(),
+                            body: Vec::new(),
+                        })
+                    }
+                    op::FUNCTION_END => {
+                        let (id, fun) = function.take().unwrap();
+                        module.functions.insert(id, fun);
+                    }
+                    op::LINE | op::NO_LINE => {}
+                    _ => return Err(error(format!("unexpected instruction {}", opcode))),
+                }
+                continue;
+            }
+
+            match opcode {
+                op::NOP
+                | op::SOURCE_CONTINUED
+                | op::SOURCE
+                | op::SOURCE_EXTENSION
+                | op::NAME
+                | op::MEMBER_NAME
+                | op::STRING
+                | op::LINE
+                | op::NO_LINE
+                | op::MODULE_PROCESSED
+                | op::EXTENSION
+                | op::MEMORY_MODEL
+                | op::CAPABILITY => {}
+                op::EXT_INST_IMPORT => {
+                    if string(&w[1 ..]).0 == "GLSL.std.450" {
+                        glsl_set = Some(operand(0)?);
+                    }
+                }
+                op::ENTRY_POINT => {
+                    let (name, _) = string(&w[2 ..]);
+                    module.entry_points.push(EntryPoint {
+                        model: operand(0)?,
+                        function: operand(1)?,
+                        name,
+                    });
+                }
+                op::EXECUTION_MODE => {
+                    module
+                        .execution_modes
+                        .entry(operand(0)?)
+                        .or_default()
+                        .push((operand(1)?, w[2 ..].to_vec()));
+                }
+                op::DECORATE => {
+                    let value = w[2 ..].to_vec();
+                    module
+                        .decorations
+                        .entry(operand(0)?)
+                        .or_default()
+                        .insert(operand(1)?, value);
+                }
+                op::MEMBER_DECORATE => {
+                    if operand(2)? == decoration::ROW_MAJOR {
+                        return Err(error("row-major matrices are not supported".to_string()));
+                    }
+                    let value = w[3 ..].to_vec();
+                    module
+                        .member_decorations
+                        .entry((operand(0)?, operand(1)?))
+                        .or_default()
+                        .insert(operand(2)?, value);
+                }
+                op::TYPE_VOID ..= op::TYPE_FUNCTION => {
+                    let ty = match opcode {
+                        op::TYPE_VOID => Type::Void,
+                        op::TYPE_BOOL => Type::Bool,
+                        op::TYPE_INT | op::TYPE_FLOAT if operand(1)? != 32 => {
+                            return Err(error(format!("unsupported {}-bit type", w[1])));
+                        }
+                        op::TYPE_INT => Type::Int {
+                            signed: operand(2)? != 0,
+                        },
+                        op::TYPE_FLOAT => Type::Float,
+                        op::TYPE_VECTOR => Type::Vector {
+                            component: operand(1)?,
+                            count: operand(2)?,
+                        },
+                        op::TYPE_MATRIX => Type::Matrix {
+                            column: operand(1)?,
+                            count: operand(2)?,
+                        },
+                        op::TYPE_IMAGE => {
+                            let dim = operand(2)?;
+                            if dim == DIM_CUBE || dim == DIM_SUBPASS_DATA {
+                                return Err(error(format!("unsupported image dimension {}", dim)));
+                            }
+                            Type::Image
+                        }
+                        op::TYPE_SAMPLER => Type::Sampler,
+                        op::TYPE_SAMPLED_IMAGE => Type::SampledImage,
+                        op::TYPE_ARRAY => Type::Array {
+                            element: operand(1)?,
+                            length: Some(operand(2)?),
+                        },
+                        op::TYPE_RUNTIME_ARRAY => Type::Array {
+                            element: operand(1)?,
+                            length: None,
+                        },
+                        op::TYPE_STRUCT => Type::Struct {
+                            members: w[1 ..].to_vec(),
+                        },
+                        op::TYPE_POINTER => Type::Pointer {
+                            pointee: operand(2)?,
+                        },
+                        op::TYPE_FUNCTION => Type::Function,
+                        _ => return Err(error(format!("unsupported type instruction {}", opcode))),
+                    };
+                    module.types.insert(operand(0)?, ty);
+                }
+                op::UNDEF
+                | op::CONSTANT_TRUE
+                | op::CONSTANT_FALSE
+                | op::CONSTANT
+                | op::CONSTANT_COMPOSITE
+                | op::CONSTANT_NULL
+                | op::SPEC_CONSTANT_TRUE
+                | op::SPEC_CONSTANT_FALSE
+                | op::SPEC_CONSTANT
+                | op::SPEC_CONSTANT_COMPOSITE => {
+                    operand(1)?;
+                    module.constants.push(inst);
+                }
+                op::VARIABLE => {
+                    let pointee = match module.types.get(&operand(0)?) {
+                        Some(&Type::Pointer { pointee, .. }) => pointee,
+                        _ => return Err(error("variable without a pointer type".to_string())),
+                    };
+                    module.variables.push(Variable {
+                        id: operand(1)?,
+                        class: operand(2)?,
+                        pointee,
+                        initializer: w.get(3).cloned(),
+                    });
+                }
+                op::FUNCTION => {
+                    function = Some((
+                        operand(1)?,
+                        Function {
+                            params: Vec::new(),
+                            blocks: Vec::new(),
+                            labels: HashMap::new(),
+                        },
+                    ));
+                }
+                _ => return Err(error(format!("unsupported instruction {}", opcode))),
+            }
+        }
+
+        if function.is_some() || block.is_some() {
+            return Err(error("unterminated function".to_string()));
+        }
+        Ok(module)
+    }
+
+    pub(crate) fn decoration(&self, id: Id, decoration: u32) -> Option<&[u32]> {
+        self.decorations
+            .get(&id)
+            .and_then(|d| d.get(&decoration))
+            .map(|v| v.as_slice())
+    }
+
+    pub(crate) fn member_decoration(&self, id: Id, member: u32, decoration: u32) -> Option<u32> {
+        self.member_decorations
+            .get(&(id, member))
+            .and_then(|d| d.get(&decoration))
+            .and_then(|v| v.first().cloned())
+    }
+
+    pub(crate) fn ty(&self, id: Id) -> &Type {
+        self.types.get(&id).unwrap_or(&Type::Void)
+    }
+
+    /// Type of the components of a scalar or vector type.
+    pub(crate) fn scalar_type(&self, id: Id) -> &Type {
+        match *self.ty(id) {
+            Type::Vector { component, .. } => self.ty(component),
+            ref other => other,
+        }
+    }
+}
+
+/// Key of a descriptor in the bound descriptor sets.
+pub type DescriptorKey = (u32, pso::DescriptorBinding, pso::DescriptorArrayIndex);
+
+/// Resources accessible to the shaders of a draw or dispatch.
+#[derive(Debug)]
+pub struct Resources {
+    pub push_constants: Bound,
+    pub descriptors: HashMap<DescriptorKey, Descriptor>,
+}
+
+/// Location of a value in memory.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Pointer {
+    /// Value of a variable owned by the invocation.
+    Local { slot: usize, path: Vec<u32> },
+    /// Value of a variable shared by the workgroup.
+    Shared { slot: usize, path: Vec<u32> },
+    /// Descriptor, or array of descriptors, of a bound set.
+    Binding { key: DescriptorKey, ty: Id },
+    /// Data in a buffer, or in the push constants when `buffer` is `None`.
+    Memory {
+        buffer: Option<DescriptorKey>,
+        offset: u64,
+        ty: Id,
+        matrix_stride: Option<u32>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// Boolean, integer or float bits.
+    Scalar(u32),
+    Composite(Vec<Value>),
+    Pointer(Pointer),
+    /// Image and sampler descriptors, either of them optional.
+    Handle {
+        image: Option<DescriptorKey>,
+        sampler: Option<DescriptorKey>,
+    },
+}
+
+impl Value {
+    pub fn scalar(&self) -> u32 {
+        match *self {
+            Value::Scalar(value) => value,
+            Value::Composite(ref values) => values.first().map_or(0, Value::scalar),
+            _ => 0,
+        }
+    }
+
+    /// Scalars of a scalar or vector value.
+    pub fn components(&self) -> Vec<u32> {
+        match *self {
+            Value::Scalar(value) => vec![value],
+            Value::Composite(ref values) => values.iter().map(Value::scalar).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn vector(components: impl IntoIterator<Item = u32>) -> Self {
+        Value::Composite(components.into_iter().map(Value::Scalar).collect())
+    }
+
+    pub(crate) fn at_path(&self, path: &[u32]) -> Option<&Value> {
+        match path.split_first() {
+            None => Some(self),
+            Some((&index, rest)) => match *self {
+                Value::Composite(ref values) => values.get(index as usize)?.at_path(rest),
+                _ => None,
+            },
+        }
+    }
+
+    pub(crate) fn at_path_mut(&mut self, path: &[u32]) -> Option<&mut Value> {
+        match path.split_first() {
+            None => Some(self),
+            Some((&index, rest)) => match *self {
+                Value::Composite(ref mut values) => {
+                    values.get_mut(index as usize)?.at_path_mut(rest)
+                }
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Input or output variable of an entry point, assigned a location or a built-in.
+#[derive(Clone, Debug)]
+pub struct Interface {
+    pub slot: usize,
+    pub path: Vec<u32>,
+    pub ty: Id,
+    /// Whether the value isn't interpolated.
+    pub flat: bool,
+}
+
+/// Entry point of a module, with the specialization constants applied.
+#[derive(Debug)]
+pub struct Program {
+    pub(crate) module: std::sync::Arc<Module>,
+    pub(crate) function: Id,
+    /// Values of the constants and global variables.
+    pub(crate) constants: Vec<Option<Value>>,
+    /// Initial values of the variables owned by each invocation.
+    pub(crate) slots: Vec<Value>,
+    /// Initial values of the workgroup variables.
+    pub(crate) shared: Vec<Value>,
+    pub inputs: HashMap<u32, Interface>,
+    pub outputs: HashMap<u32, Interface>,
+    pub builtin_inputs: HashMap<u32, Interface>,
+    pub builtin_outputs: HashMap<u32, Interface>,
+    pub local_size: [u32; 3],
+    pub origin_lower_left: bool,
+}
+
+fn model_of(stage: pso::Stage) -> u32 {
+    match stage {
+        pso::Stage::Vertex => 0,
+        pso::Stage::Hull => 1,
+        pso::Stage::Domain => 2,
+        pso::Stage::Geometry => 3,
+        pso::Stage::Fragment => 4,
+        pso::Stage::Compute => 5,
+    }
+}
+
+impl Program {
+    pub fn new(
+        module: &std::sync::Arc<Module>,
+        entry: &str,
+        stage: pso::Stage,
+        specialization: &pso::Specialization,
+    ) -> Result<Self, ShaderError> {
+        let entry_point = module
+            .entry_points
+            .iter()
+            .find(|ep| ep.name == entry && ep.model == model_of(stage))
+            .ok_or(ShaderError::MissingEntryPoint(entry.to_string()))?;
+        let mut program = Program {
+            module: std::sync::Arc::clone(module),
+            function: entry_point.function,
+            constants: vec![None; module.bound as usize],
+            slots: Vec::new(),
+            shared: Vec::new(),
+            inputs: HashMap::new(),
+            outputs: HashMap::new(),
+            builtin_inputs: HashMap::new(),
+            builtin_outputs: HashMap::new(),
+            local_size: [1; 3],
+            origin_lower_left: false,
+        };
+        for &(mode, ref args) in module
+            .execution_modes
+            .get(&entry_point.function)
+            .map_or(&[][..], |m| m.as_slice())
+        {
+            match mode {
+                EXECUTION_MODE_LOCAL_SIZE if args.len() == 3 => {
+                    program.local_size = [args[0], args[1], args[2]];
+                }
+                EXECUTION_MODE_ORIGIN_LOWER_LEFT => program.origin_lower_left = true,
+                _ => {}
+            }
+        }
+        program.specialize(specialization)?;
+        program.assign_variables()?;
+        Ok(program)
+    }
+
+    fn specialize(&mut self, specialization: &pso::Specialization) -> Result<(), ShaderError> {
+        let module = std::sync::Arc::clone(&self.module);
+        for inst in &module.constants {
+            let (ty, id) = (inst.words[0], inst.words[1]);
+            let spec_value = module
+                .decoration(id, decoration::SPEC_ID)
+                .and_then(|spec_id| {
+                    let spec_id = *spec_id.first()?;
+                    specialization
+                        .constants
+                        .iter()
+                        .find(|constant| constant.id == spec_id)
+                })
+                .map(|constant| {
+                    let range = constant.range.start as usize .. constant.range.end as usize;
+                    let mut bytes = [0u8; 4];
+                    let data = specialization.data.get(range).unwrap_or(&[]);
+                    let len = data.len().min(4);
+                    bytes[.. len].copy_from_slice(&data[.. len]);
+                    u32::from_le_bytes(bytes)
+                });
+            let value = match inst.opcode {
+                op::CONSTANT_TRUE => Value::Scalar(1),
+                op::CONSTANT_FALSE => Value::Scalar(0),
+                op::SPEC_CONSTANT_TRUE => Value::Scalar(spec_value.map_or(1, |v| (v != 0) as u32)),
+                op::SPEC_CONSTANT_FALSE => Value::Scalar(spec_value.map_or(0, |v| (v != 0) as u32)),
+                op::CONSTANT => Value::Scalar(inst.words.get(2).cloned().unwrap_or(0)),
+                op::SPEC_CONSTANT => Value::Scalar(
+                    spec_value.unwrap_or_else(|| inst.words.get(2).cloned().unwrap_or(0)),
+                ),
+                op::CONSTANT_COMPOSITE | op::SPEC_CONSTANT_COMPOSITE => Value::Composite(
+                    inst.words[2 ..]
+                        .iter()
+                        .map(|&c| self.constant(c))
+                        .collect::<Result<_, _>>()?,
+                ),
+                _ => self.zero(ty),
+            };
+            if let Some(&[builtin::WORKGROUP_SIZE]) = module.decoration(id, decoration::BUILT_IN) {
+                let size = value.components();
+                if size.len() == 3 {
+                    self.local_size = [size[0], size[1], size[2]];
+                }
+            }
+            self.constants[id as usize] = Some(value);
+        }
+        Ok(())
+    }
+
+    fn constant(&self, id: Id) -> Result<Value, ShaderError> {
+        self.constants
+            .get(id as usize)
+            .cloned()
+            .and_then(|c| c)
+            .ok_or_else(|| error(format!("unknown constant {}", id)))
+    }
+
+    fn assign_variables(&mut self) -> Result<(), ShaderError> {
+        let module = std::sync::Arc::clone(&self.module);
+        for var in &module.variables {
+            let binding = || -> DescriptorKey {
+                let get = |d| {
+                    module
+                        .decoration(var.id, d)
+                        .and_then(|v| v.first().cloned())
+                        .unwrap_or(0)
+                };
+                (get(decoration::DESCRIPTOR_SET), get(decoration::BINDING), 0)
+            };
+            let pointer = match var.class {
+                storage::UNIFORM_CONSTANT | storage::UNIFORM | storage::STORAGE_BUFFER => {
+                    Pointer::Binding {
+                        key: binding(),
+                        ty: var.pointee,
+                    }
+                }
+                storage::PUSH_CONSTANT => Pointer::Memory {
+                    buffer: None,
+                    offset: 0,
+                    ty: var.pointee,
+                    matrix_stride: None,
+                },
+                storage::WORKGROUP => {
+                    let init = self.initial_value(var)?;
+                    self.shared.push(init);
+                    Pointer::Shared {
+                        slot: self.shared.len() - 1,
+                        path: Vec::new(),
+                    }
+                }
+                storage::INPUT | storage::OUTPUT | storage::PRIVATE | storage::FUNCTION => {
+                    let init = self.initial_value(var)?;
+                    self.slots.push(init);
+                    let slot = self.slots.len() - 1;
+                    if var.class != storage::PRIVATE {
+                        self.add_interface(var, slot);
+                    }
+                    Pointer::Local {
+                        slot,
+                        path: Vec::new(),
+                    }
+                }
+                other => return Err(error(format!("unsupported storage class {}", other))),
+            };
+            self.constants[var.id as usize] = Some(Value::Pointer(pointer));
+        }
+        Ok(())
+    }
+
+    fn initial_value(&self, var: &Variable) -> Result<Value, ShaderError> {
+        match var.initializer {
+            Some(id) => self.constant(id),
+            None => Ok(self.zero(var.pointee)),
+        }
+    }
+
+    fn add_interface(&mut self, var: &Variable, slot: usize) {
+        let module = &self.module;
+        let flat = module.decoration(var.id, decoration::FLAT).is_some();
+        let (located, builtins) = if var.class == storage::INPUT {
+            (&mut self.inputs, &mut self.builtin_inputs)
+        } else {
+            (&mut self.outputs, &mut self.builtin_outputs)
+        };
+        let interface = |path, ty| Interface {
+            slot,
+            path,
+            ty,
+            flat,
+        };
+        if let Some(&[location]) = module.decoration(var.id, decoration::LOCATION) {
+            located.insert(location, interface(Vec::new(), var.pointee));
+        } else if let Some(&[builtin]) = module.decoration(var.id, decoration::BUILT_IN) {
+            builtins.insert(builtin, interface(Vec::new(), var.pointee));
+        } else if let Type::Struct { ref members } = *module.ty(var.pointee) {
+            for (i, &member) in members.iter().enumerate() {
+                let i = i as u32;
+                if let Some(location) =
+                    module.member_decoration(var.pointee, i, decoration::LOCATION)
+                {
+                    located.insert(location, interface(vec![i], member));
+                } else if let Some(builtin) =
+                    module.member_decoration(var.pointee, i, decoration::BUILT_IN)
+                {
+                    builtins.insert(builtin, interface(vec![i], member));
+                }
+            }
+        }
+    }
+
+    /// Length of an array type, `None` for runtime arrays.
+    pub(crate) fn array_length(&self, length: Option<Id>) -> Option<u32> {
+        length.map(|id| {
+            self.constants
+                .get(id as usize)
+                .and_then(|c| c.as_ref())
+                .map_or(0, Value::scalar)
+        })
+    }
+
+    /// Zero value of a type.
+    pub fn zero(&self, ty: Id) -> Value {
+        match *self.module.ty(ty) {
+            Type::Vector { component, count }
+            | Type::Matrix {
+                column: component,
+                count,
+            } => Value::Composite((0 .. count).map(|_| self.zero(component)).collect()),
+            Type::Array { element, length } => {
+                let count = self.array_length(length).unwrap_or(0);
+                Value::Composite((0 .. count).map(|_| self.zero(element)).collect())
+            }
+            Type::Struct { ref members } => {
+                Value::Composite(members.iter().map(|&m| self.zero(m)).collect())
+            }
+            Type::Image | Type::Sampler | Type::SampledImage => Value::Handle {
+                image: None,
+                sampler: None,
+            },
+            _ => Value::Scalar(0),
+        }
+    }
+
+    /// Size of a type laid out in memory.
+    fn size_of(&self, ty: Id, matrix_stride: Option<u32>) -> u64 {
+        match *self.module.ty(ty) {
+            Type::Vector { count, .. } => 4 * count as u64,
+            Type::Matrix { column, count } => {
+                matrix_stride.map_or(self.size_of(column, None), |s| s as u64) * count as u64
+            }
+            Type::Array { element, length } => {
+                self.array_stride(ty, element) * self.array_length(length).unwrap_or(0) as u64
+            }
+            Type::Struct { ref members } => members
+                .iter()
+                .enumerate()
+                .map(|(i, &member)| {
+                    let (offset, stride) = self.member_layout(ty, i as u32);
+                    offset + self.size_of(member, stride)
+                })
+                .max()
+                .unwrap_or(0),
+            _ => 4,
+        }
+    }
+
+    fn array_stride(&self, array: Id, element: Id) -> u64 {
+        match self.module.decoration(array, decoration::ARRAY_STRIDE) {
+            Some(&[stride]) => stride as u64,
+            _ => self.size_of(element, None),
+        }
+    }
+
+    /// Offset and matrix stride of a structure member.
+    fn member_layout(&self, ty: Id, member: u32) -> (u64, Option<u32>) {
+        let offset = self
+            .module
+            .member_decoration(ty, member, decoration::OFFSET)
+            .map_or(0, |o| o as u64);
+        let stride = self
+            .module
+            .member_decoration(ty, member, decoration::MATRIX_STRIDE);
+        (offset, stride)
+    }
+
+    /// Offset, type and matrix stride of an element of a type in memory.
+    pub(crate) fn element_layout(
+        &self,
+        ty: Id,
+        matrix_stride: Option<u32>,
+        index: u32,
+    ) -> (u64, Id, Option<u32>) {
+        match *self.module.ty(ty) {
+            Type::Vector { component, .. } => (4 * index as u64, component, None),
+            Type::Matrix { column, .. } => {
+                let stride = matrix_stride.map_or(self.size_of(column, None), |s| s as u64);
+                (stride * index as u64, column, None)
+            }
+            Type::Array { element, .. } => {
+                let stride = self.array_stride(ty, element);
+                (stride * index as u64, element, None)
+            }
+            Type::Struct { ref members } => {
+                let (offset, stride) = self.member_layout(ty, index);
+                (
+                    offset,
+                    members.get(index as usize).cloned().unwrap_or(0),
+                    stride,
+                )
+            }
+            _ => (0, ty, None),
+        }
+    }
+
+    /// Number of elements of a runtime array member at the end of a buffer.
+    pub(crate) fn runtime_array_length(&self, ty: Id, member: u32, buffer_size: u64) -> u32 {
+        let (offset, element, _) = self.element_layout(ty, None, member);
+        match *self.module.ty(element) {
+            Type::Array { element: inner, .. } => {
+                let stride = self.array_stride(element, inner).max(1);
+                (buffer_size.saturating_sub(offset) / stride) as u32
+            }
+            _ => 0,
+        }
+    }
+
+    /// Read a value of a type from memory.
+    pub(crate) unsafe fn read_memory(
+        &self,
+        memory: &Bound,
+        offset: u64,
+        ty: Id,
+        matrix_stride: Option<u32>,
+    ) -> Value {
+        match *self.module.ty(ty) {
+            Type::Bool => Value::Scalar((memory.read_u32(offset) != 0) as u32),
+            Type::Int { .. } | Type::Float => Value::Scalar(memory.read_u32(offset)),
+            Type::Vector { count, .. } | Type::Matrix { count, .. } => {
+                self.read_elements(memory, offset, ty, matrix_stride, count)
+            }
+            Type::Array { length, element } => {
+                let count = self.array_length(length).unwrap_or_else(|| {
+                    let stride = self.array_stride(ty, element).max(1);
+                    (memory.size().saturating_sub(offset) / stride) as u32
+                });
+                self.read_elements(memory, offset, ty, matrix_stride, count)
+            }
+            Type::Struct { ref members } => {
+                self.read_elements(memory, offset, ty, matrix_stride, members.len() as u32)
+            }
+            _ => Value::Scalar(0),
+        }
+    }
+
+    unsafe fn read_elements(
+        &self,
+        memory: &Bound,
+        offset: u64,
+        ty: Id,
+        matrix_stride: Option<u32>,
+        count: u32,
+    ) -> Value {
+        Value::Composite(
+            (0 .. count)
+                .map(|i| {
+                    let (element_offset, element, stride) =
+                        self.element_layout(ty, matrix_stride, i);
+                    self.read_memory(memory, offset + element_offset, element, stride)
+                })
+                .collect(),
+        )
+    }
+
+    /// Write a value of a type to memory.
+    pub(crate) unsafe fn write_memory(
+        &self,
+        memory: &Bound,
+        offset: u64,
+        ty: Id,
+        matrix_stride: Option<u32>,
+        value: &Value,
+    ) {
+        match *value {
+            Value::Scalar(scalar) => memory.write_u32(offset, scalar),
+            Value::Composite(ref elements) => {
+                for (i, element) in elements.iter().enumerate() {
+                    let (element_offset, element_ty, stride) =
+                        self.element_layout(ty, matrix_stride, i as u32);
+                    self.write_memory(memory, offset + element_offset, element_ty, stride, element);
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! Parsing of the helper attributes.
+
+use proc_macro2::Span;
+use syn::{Attribute, Error, Lit, Meta, NestedMeta, Path, Result};
+
+/// A single item of a helper attribute, like `location = 2` or `skip`.
+pub struct Item {
+    pub path: Path,
+    pub value: Option<Lit>,
+}
+
+impl Item {
+    pub fn is(&self, name: &str) -> bool {
+        self.path.is_ident(name)
+    }
+
+    pub fn error(&self, message: &str) -> Error {
+        Error::new_spanned(&self.path, message)
+    }
+
+    fn value(&self) -> Result<&Lit> {
+        self.value
+            .as_ref()
+            .ok_or_else(|| self.error("expected a value"))
+    }
+
+    pub fn flag(&self) -> Result<()> {
+        match self.value {
+            Some(ref lit) => Err(Error::new_spanned(lit, "unexpected value")),
+            None => Ok(()),
+        }
+    }
+
+    pub fn string(&self) -> Result<String> {
+        match *self.value()? {
+            Lit::Str(ref lit) => Ok(lit.value()),
+            ref lit => Err(Error::new_spanned(lit, "expected a string")),
+        }
+    }
+
+    pub fn int<T>(&self) -> Result<T>
+    where
+        T: std::str::FromStr,
+        T::Err: std::fmt::Display,
+    {
+        match *self.value()? {
+            Lit::Int(ref lit) => lit.base10_parse(),
+            ref lit => Err(Error::new_spanned(lit, "expected an integer")),
+        }
+    }
+
+    /// Parse a string value as a path, such as `crate = "hal"`.
+    pub fn path(&self) -> Result<Path> {
+        match *self.value()? {
+            Lit::Str(ref lit) => lit.parse(),
+            ref lit => Err(Error::new_spanned(lit, "expected a string")),
+        }
+    }
+
+    pub fn ident(&self) -> Result<syn::Ident> {
+        match *self.value()? {
+            Lit::Str(ref lit) => lit.parse(),
+            ref lit => Err(Error::new_spanned(lit, "expected a string")),
+        }
+    }
+}
+
+/// Collect the items of all the `#[name(..)]` attributes.
+pub fn items(attrs: &[Attribute], name: &str) -> Result<Vec<Item>> {
+    let mut items = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident(name)) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => {
+                return Err(Error::new_spanned(
+                    meta,
+                    format!("expected `#[{}(..)]`", name),
+                ))
+            }
+        };
+        for nested in list.nested {
+            items.push(match nested {
+                NestedMeta::Meta(Meta::Path(path)) => Item { path, value: None },
+                NestedMeta::Meta(Meta::NameValue(nv)) => Item {
+                    path: nv.path,
+                    value: Some(nv.lit),
+                },
+                other => return Err(Error::new_spanned(other, "unexpected attribute")),
+            });
+        }
+    }
+    Ok(items)
+}
+
+/// Path to the `gfx_hal` crate.
+pub fn default_crate() -> Path {
+    syn::Ident::new("gfx_hal", Span::call_site()).into()
+}
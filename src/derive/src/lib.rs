@@ -0,0 +1,103 @@
+//! Derive macros for `gfx-hal` traits.
+//!
+//! The generated code refers to the `gfx_hal` crate. If it's imported under
+//! another name, specify it with the `crate` attribute of the derive, e.g.
+//! `#[vertex(crate = "hal")]`.
+
+extern crate proc_macro;
+
+mod attr;
+mod shader_type;
+mod specialization;
+mod vertex;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+/// Derive `gfx_hal::pso::Vertex` for a struct, with one attribute per field.
+///
+/// The offsets are taken from the actual layout of the struct, which should
+/// usually be `#[repr(C)]`, and the formats from `gfx_hal::format::AsFormat`.
+///
+/// Container attributes, in `#[vertex(..)]`:
+///
+/// - `rate = "vertex"` or `rate = "instance"`: vertex input rate, `vertex` by default.
+/// - `divisor = N`: number of instances per advancement with the `instance` rate, 1 by default.
+/// - `location = N`: location of the first attribute, 0 by default.
+/// - `crate = "path"`: path to the `gfx_hal` crate.
+///
+/// Field attributes, in `#[vertex(..)]`:
+///
+/// - `format = "Name"`: name of the `gfx_hal::format::Format` to use instead of the default.
+/// - `skip`: don't generate an attribute for this field, such as padding.
+///
+/// ```ignore
+/// #[derive(Vertex)]
+/// #[repr(C)]
+/// #[vertex(rate = "instance", location = 2)]
+/// struct Instance {
+///     offset: [f32; 2],
+///     #[vertex(format = "Rgba8Srgb")]
+///     color: [u8; 4],
+/// }
+/// ```
+#[proc_macro_derive(Vertex, attributes(vertex))]
+pub fn derive_vertex(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    vertex::derive(&input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+/// Derive `gfx_hal::pso::layout::ShaderType` for a struct, as a block structure with
+/// one member per field.
+///
+/// The layout of the Rust struct itself doesn't matter: the members are written at the
+/// offsets given by the `std140` or `std430` rules, as computed by `gfx_hal::pso::layout`.
+///
+/// Container attributes, in `#[shader_type(..)]`:
+///
+/// - `crate = "path"`: path to the `gfx_hal` crate.
+///
+/// ```ignore
+/// #[derive(ShaderType)]
+/// struct Light {
+///     position: [f32; 3],
+///     radius: f32,
+///     color: [f32; 3],
+/// }
+/// ```
+#[proc_macro_derive(ShaderType, attributes(shader_type))]
+pub fn derive_shader_type(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    shader_type::derive(&input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+/// Derive `gfx_hal::pso::SpecConstants` for a struct, with one specialization
+/// constant per field.
+///
+/// Each field needs a `#[constant_id = N]` attribute, and a type among `bool`,
+/// `i32`, `u32`, `f32` and `f64`.
+///
+/// Container attributes, in `#[specialization(..)]`:
+///
+/// - `crate = "path"`: path to the `gfx_hal` crate.
+///
+/// ```ignore
+/// #[derive(Specialization)]
+/// struct Constants {
+///     #[constant_id = 0]
+///     specular: bool,
+///     #[constant_id = 1]
+///     light_count: u32,
+/// }
+/// ```
+#[proc_macro_derive(Specialization, attributes(constant_id, specialization))]
+pub fn derive_specialization(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    specialization::derive(&input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
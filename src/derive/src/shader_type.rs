@@ -0,0 +1,88 @@
+use crate::attr;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Index, Member, Result};
+
+pub fn derive(input: &DeriveInput) -> Result<TokenStream> {
+    let mut krate = attr::default_crate();
+    for item in attr::items(&input.attrs, "shader_type")? {
+        if item.is("crate") {
+            krate = item.path()?;
+        } else {
+            return Err(item.error("unknown shader_type attribute"));
+        }
+    }
+
+    let fields = match input.data {
+        Data::Struct(ref data) => &data.fields,
+        _ => {
+            return Err(Error::new_spanned(
+                input,
+                "ShaderType can only be derived for structs",
+            ))
+        }
+    };
+    if fields.is_empty() {
+        return Err(Error::new_spanned(
+            input,
+            "ShaderType requires at least one field",
+        ));
+    }
+
+    let layout = quote!(#krate::pso::layout);
+    let mut members = Vec::new();
+    let mut writes = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        if let Some(attr) = field
+            .attrs
+            .iter()
+            .find(|attr| attr.path.is_ident("shader_type"))
+        {
+            return Err(Error::new_spanned(attr, "unexpected field attribute"));
+        }
+        let (member, name) = match field.ident {
+            Some(ref ident) => (Member::Named(ident.clone()), ident.to_string()),
+            None => (Member::Unnamed(Index::from(index)), index.to_string()),
+        };
+        let ty = &field.ty;
+        members.push(quote! {
+            #layout::Member {
+                name: #name.into(),
+                shape: <#ty as #layout::ShaderType>::shape(),
+            }
+        });
+        writes.push(quote! {
+            #layout::ShaderType::write_shaped(
+                &self.#member,
+                &members[#index].shape,
+                layout,
+                &mut bytes[offsets[#index] as usize ..],
+            );
+        });
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #layout::ShaderType for #name #ty_generics #where_clause {
+            fn shape() -> #layout::Shape {
+                #layout::Shape::Struct(vec![#(#members),*])
+            }
+
+            fn write_shaped(
+                &self,
+                shape: &#layout::Shape,
+                layout: #layout::BlockLayout,
+                bytes: &mut [u8],
+            ) {
+                let members = match *shape {
+                    #layout::Shape::Struct(ref members) => members,
+                    _ => unreachable!(),
+                };
+                let offsets = shape.member_offsets(layout);
+                #(#writes)*
+            }
+        }
+    })
+}
@@ -0,0 +1,79 @@
+use crate::attr;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Index, Lit, Member, Meta, Result};
+
+pub fn derive(input: &DeriveInput) -> Result<TokenStream> {
+    let mut krate = attr::default_crate();
+    for item in attr::items(&input.attrs, "specialization")? {
+        if item.is("crate") {
+            krate = item.path()?;
+        } else {
+            return Err(item.error("unknown specialization attribute"));
+        }
+    }
+
+    let fields = match input.data {
+        Data::Struct(ref data) => &data.fields,
+        _ => {
+            return Err(Error::new_spanned(
+                input,
+                "Specialization can only be derived for structs",
+            ))
+        }
+    };
+
+    let pso = quote!(#krate::pso);
+    let mut ids = Vec::new();
+    let mut types = Vec::new();
+    let mut pushes = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let mut id = None;
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("constant_id"))
+        {
+            let value = match attr.parse_meta()? {
+                Meta::NameValue(ref nv) => match nv.lit {
+                    Lit::Int(ref lit) => lit.base10_parse::<u32>()?,
+                    ref lit => return Err(Error::new_spanned(lit, "expected an integer")),
+                },
+                meta => return Err(Error::new_spanned(meta, "expected `#[constant_id = N]`")),
+            };
+            if id.replace(value).is_some() {
+                return Err(Error::new_spanned(attr, "duplicate constant_id"));
+            }
+            if ids.contains(&value) {
+                return Err(Error::new_spanned(attr, "constant_id is already used"));
+            }
+            ids.push(value);
+        }
+        let id = id.ok_or_else(|| Error::new_spanned(field, "missing `#[constant_id = N]`"))?;
+
+        let member = match field.ident {
+            Some(ref ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index::from(index)),
+        };
+        let ty = &field.ty;
+        types.push(quote!((#id, <#ty as #pso::SpecConstant>::TYPE)));
+        pushes.push(quote!(specialization.push(#id, self.#member);));
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #pso::SpecConstants for #name #ty_generics #where_clause {
+            fn constant_types() -> Vec<(u32, #pso::SpecConstantType)> {
+                vec![#(#types),*]
+            }
+
+            fn specialization(&self) -> #pso::Specialization<'static> {
+                let mut specialization = #pso::Specialization::default();
+                #(#pushes)*
+                specialization
+            }
+        }
+    })
+}
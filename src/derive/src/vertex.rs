@@ -0,0 +1,118 @@
+use crate::attr;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Fields, Index, Member, Result};
+
+enum Rate {
+    Vertex,
+    Instance,
+}
+
+pub fn derive(input: &DeriveInput) -> Result<TokenStream> {
+    let mut krate = attr::default_crate();
+    let mut rate = Rate::Vertex;
+    let mut divisor = None::<u8>;
+    let mut location = 0u32;
+    for item in attr::items(&input.attrs, "vertex")? {
+        if item.is("crate") {
+            krate = item.path()?;
+        } else if item.is("rate") {
+            rate = match item.string()?.as_str() {
+                "vertex" => Rate::Vertex,
+                "instance" => Rate::Instance,
+                _ => return Err(item.error("expected `vertex` or `instance`")),
+            };
+        } else if item.is("divisor") {
+            divisor = Some(item.int()?);
+        } else if item.is("location") {
+            location = item.int()?;
+        } else {
+            return Err(item.error("unknown vertex attribute"));
+        }
+    }
+
+    let rate = match (rate, divisor) {
+        (Rate::Vertex, None) => quote!(#krate::pso::VertexInputRate::Vertex),
+        (Rate::Vertex, Some(_)) => {
+            return Err(Error::new_spanned(
+                input,
+                "`divisor` requires `rate = \"instance\"`",
+            ))
+        }
+        (Rate::Instance, divisor) => {
+            let divisor = divisor.unwrap_or(1);
+            quote!(#krate::pso::VertexInputRate::Instance(#divisor))
+        }
+    };
+
+    let fields = match input.data {
+        Data::Struct(ref data) => &data.fields,
+        _ => {
+            return Err(Error::new_spanned(
+                input,
+                "Vertex can only be derived for structs",
+            ))
+        }
+    };
+    if let Fields::Unit = *fields {
+        return Err(Error::new_spanned(
+            input,
+            "Vertex requires at least one field",
+        ));
+    }
+
+    let mut elements = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let mut format = None;
+        let mut skip = false;
+        for item in attr::items(&field.attrs, "vertex")? {
+            if item.is("format") {
+                format = Some(item.ident()?);
+            } else if item.is("skip") {
+                item.flag()?;
+                skip = true;
+            } else {
+                return Err(item.error("unknown vertex field attribute"));
+            }
+        }
+        if skip {
+            continue;
+        }
+
+        let member = match field.ident {
+            Some(ref ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index::from(index)),
+        };
+        let ty = &field.ty;
+        let format = match format {
+            Some(name) => quote!(#krate::format::Format::#name),
+            None => quote!(<#ty as #krate::format::AsFormat>::SELF),
+        };
+        elements.push(quote! {
+            #krate::pso::Element {
+                format: #format,
+                offset: unsafe {
+                    (::std::ptr::addr_of!((*base).#member) as *const u8)
+                        .offset_from(base as *const u8)
+                } as #krate::pso::ElemOffset,
+            }
+        });
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #krate::pso::Vertex for #name #ty_generics #where_clause {
+            const RATE: #krate::pso::VertexInputRate = #rate;
+            const BASE_LOCATION: #krate::pso::Location = #location;
+
+            fn elements() -> Vec<#krate::pso::Element<#krate::format::Format>> {
+                let uninit = ::std::mem::MaybeUninit::<Self>::uninit();
+                #[allow(unused_variables)]
+                let base = uninit.as_ptr();
+                vec![#(#elements),*]
+            }
+        }
+    })
+}
@@ -0,0 +1,92 @@
+use gfx_hal::{
+    pso::layout::{self, Array, BlockLayout, ShaderType},
+    Limits,
+};
+use gfx_hal_derive::ShaderType;
+
+#[derive(ShaderType)]
+struct Light {
+    position: [f32; 3],
+    radius: f32,
+    color: [f32; 3],
+}
+
+#[derive(ShaderType)]
+struct Globals {
+    view: [[f32; 4]; 4],
+    ambient: [f32; 3],
+    count: u32,
+    lights: Array<Light, 2>,
+}
+
+#[derive(ShaderType)]
+struct Push(u32, [f32; 2]);
+
+#[test]
+fn test_offsets() {
+    let shape = Globals::shape();
+    assert_eq!(
+        shape.member_offsets(BlockLayout::Std140),
+        vec![0, 64, 76, 80]
+    );
+    assert_eq!(shape.size(BlockLayout::Std140), 144);
+    assert_eq!(
+        Light::shape().member_offsets(BlockLayout::Std430),
+        vec![0, 12, 16]
+    );
+    assert_eq!(
+        Push::shape().member_offsets(BlockLayout::Std430),
+        vec![0, 8]
+    );
+}
+
+#[test]
+fn test_write() {
+    let light = Light {
+        position: [1.0, 2.0, 3.0],
+        radius: 4.0,
+        color: [5.0, 6.0, 7.0],
+    };
+    let globals = Globals {
+        view: [[0.0; 4]; 4],
+        ambient: [0.5; 3],
+        count: 1,
+        lights: Array([
+            light,
+            Light {
+                position: [0.0; 3],
+                radius: 0.0,
+                color: [0.0; 3],
+            },
+        ]),
+    };
+    let words = layout::to_words(&globals, BlockLayout::Std140);
+    assert_eq!(words.len(), 36);
+    assert_eq!(words[19], 1);
+    let light: Vec<_> = words[20 .. 28].iter().map(|&w| f32::from_bits(w)).collect();
+    assert_eq!(light, [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 0.0]);
+}
+
+#[test]
+fn test_check() {
+    let entries = Light::shape().entries(BlockLayout::Std430);
+    assert_eq!(
+        layout::check::<Light>(BlockLayout::Std430, &entries),
+        Ok(())
+    );
+    assert!(layout::check::<Light>(BlockLayout::Std140, &entries).is_ok());
+    assert!(layout::check::<Push>(BlockLayout::Std430, &entries).is_err());
+}
+
+#[test]
+fn test_push_constants() {
+    let limits = Limits {
+        max_push_constants_size: 128,
+        ..Limits::default()
+    };
+    assert_eq!(layout::push_constant_range::<Push>(16, &limits), Ok(16 .. 32));
+    assert_eq!(
+        layout::to_words(&Push(3, [1.0, 2.0]), BlockLayout::Std430),
+        vec![3, 0, 1.0f32.to_bits(), 2.0f32.to_bits()]
+    );
+}
@@ -0,0 +1,90 @@
+use gfx_auxil::reflect;
+use gfx_hal::pso::{self, SpecConstantType, SpecConstants, SpecializationConstant};
+use gfx_hal_derive::Specialization;
+
+#[derive(Clone, Copy, Specialization)]
+struct Constants {
+    #[constant_id = 2]
+    specular: bool,
+    #[constant_id = 0]
+    light_count: u32,
+    #[constant_id = 5]
+    exposure: f64,
+}
+
+#[derive(Specialization)]
+struct Tuple(#[constant_id = 1] f32, #[constant_id = 0] i32);
+
+/// Matches `layout(constant_id = 0) const float scale` of the quad example.
+#[derive(Specialization)]
+struct Quad {
+    #[constant_id = 0]
+    scale: f32,
+}
+
+#[derive(Specialization)]
+struct QuadMismatch {
+    #[constant_id = 0]
+    scale: u32,
+}
+
+#[test]
+fn test_specialization() {
+    let constants = Constants {
+        specular: true,
+        light_count: 4,
+        exposure: 1.5,
+    };
+    let specialization = constants.specialization();
+    assert_eq!(
+        *specialization.constants,
+        [
+            SpecializationConstant { id: 2, range: 0 .. 4 },
+            SpecializationConstant { id: 0, range: 4 .. 8 },
+            SpecializationConstant {
+                id: 5,
+                range: 8 .. 16
+            },
+        ]
+    );
+    assert_eq!(specialization.data[.. 4], 1u32.to_ne_bytes());
+    assert_eq!(specialization.data[4 .. 8], 4u32.to_ne_bytes());
+    assert_eq!(specialization.data[8 ..], 1.5f64.to_ne_bytes());
+
+    let specialization = Tuple(0.25, -1).specialization();
+    assert_eq!(specialization.data[.. 4], 0.25f32.to_ne_bytes());
+    assert_eq!(specialization.data[4 ..], (-1i32).to_ne_bytes());
+}
+
+#[test]
+fn test_check() {
+    assert_eq!(
+        Constants::constant_types(),
+        vec![
+            (2, SpecConstantType::Bool),
+            (0, SpecConstantType::Uint),
+            (5, SpecConstantType::Double),
+        ]
+    );
+    let shader = [(0, SpecConstantType::Uint), (2, SpecConstantType::Bool)];
+    assert_eq!(Constants::check(&shader), Ok(()));
+    let err = Tuple::check(&shader).unwrap_err();
+    assert_eq!(
+        (err.id, err.expected, err.found),
+        (0, SpecConstantType::Int, SpecConstantType::Uint)
+    );
+}
+
+#[test]
+fn test_check_reflected() {
+    let spirv = include_bytes!("../../../examples/quad/data/quad.vert.spv");
+    let words = pso::read_spirv(std::io::Cursor::new(&spirv[..])).unwrap();
+    let constants = reflect::spec_constants(&words).unwrap();
+
+    assert_eq!(Quad::check(&constants), Ok(()));
+    let err = QuadMismatch::check(&constants).unwrap_err();
+    assert_eq!(
+        (err.id, err.expected, err.found),
+        (0, SpecConstantType::Uint, SpecConstantType::Float)
+    );
+}
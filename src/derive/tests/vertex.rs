@@ -0,0 +1,137 @@
+use gfx_hal::{
+    format::{Format, Unorm},
+    pso::{AttributeDesc, Element, Vertex, VertexBufferDesc, VertexInputRate},
+};
+use gfx_hal_derive::Vertex;
+
+#[derive(Vertex)]
+#[repr(C)]
+#[allow(dead_code)]
+struct Basic {
+    pos: [f32; 3],
+    uv: [f32; 2],
+    color: Unorm<[u8; 4]>,
+}
+
+#[derive(Vertex)]
+#[repr(C)]
+#[vertex(rate = "instance", divisor = 2, location = 3)]
+#[allow(dead_code)]
+struct Instance {
+    #[vertex(format = "Rgba8Srgb")]
+    tint: [u8; 4],
+    #[vertex(skip)]
+    _padding: u32,
+    scale: f32,
+}
+
+#[derive(Vertex)]
+#[repr(C)]
+#[allow(dead_code)]
+struct Tuple(u16, [i32; 2]);
+
+#[derive(Vertex)]
+#[repr(C)]
+#[allow(dead_code)]
+struct Double {
+    pos: [f64; 4],
+    normal: [f64; 3],
+    weight: f64,
+}
+
+mod renamed {
+    pub use gfx_hal as hal;
+}
+
+#[derive(Vertex)]
+#[repr(C)]
+#[vertex(crate = "renamed::hal")]
+#[allow(dead_code)]
+struct Renamed {
+    pos: [f32; 2],
+}
+
+#[test]
+fn test_basic() {
+    assert_eq!(
+        Basic::buffer_desc(1),
+        VertexBufferDesc {
+            binding: 1,
+            stride: 24,
+            rate: VertexInputRate::Vertex,
+        }
+    );
+    assert_eq!(
+        Basic::attributes(1),
+        vec![
+            AttributeDesc {
+                location: 0,
+                binding: 1,
+                element: Element {
+                    format: Format::Rgb32Sfloat,
+                    offset: 0,
+                },
+            },
+            AttributeDesc {
+                location: 1,
+                binding: 1,
+                element: Element {
+                    format: Format::Rg32Sfloat,
+                    offset: 12,
+                },
+            },
+            AttributeDesc {
+                location: 2,
+                binding: 1,
+                element: Element {
+                    format: Format::Rgba8Unorm,
+                    offset: 20,
+                },
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_attributes() {
+    assert_eq!(Instance::RATE, VertexInputRate::Instance(2));
+    assert_eq!(Instance::buffer_desc(0).stride, 12);
+    let attributes = Instance::attributes(0);
+    assert_eq!(
+        attributes
+            .iter()
+            .map(|a| (a.location, a.element.format, a.element.offset))
+            .collect::<Vec<_>>(),
+        vec![(3, Format::Rgba8Srgb, 0), (4, Format::R32Sfloat, 8)]
+    );
+
+    assert_eq!(
+        Tuple::elements(),
+        vec![
+            Element {
+                format: Format::R16Uint,
+                offset: 0,
+            },
+            Element {
+                format: Format::Rg32Sint,
+                offset: 4,
+            },
+        ]
+    );
+    assert_eq!(Renamed::elements().len(), 1);
+}
+
+#[test]
+fn test_double_locations() {
+    assert_eq!(
+        Double::attributes(0)
+            .iter()
+            .map(|a| (a.location, a.element.format, a.element.offset))
+            .collect::<Vec<_>>(),
+        vec![
+            (0, Format::Rgba64Sfloat, 0),
+            (2, Format::Rgb64Sfloat, 32),
+            (4, Format::R64Sfloat, 56),
+        ]
+    );
+}
@@ -15,7 +15,7 @@ use crate::{
     format,
     image,
     memory,
-    queue::{QueueGroup, QueuePriority},
+    queue::{QueueFamily, QueueGroup, QueuePriority, QueueType},
     Backend,
     Features,
     Hints,
@@ -173,3 +173,231 @@ pub struct Adapter<B: Backend> {
     /// [Queue families][crate::queue::family::QueueFamily] supported by this adapter.
     pub queue_families: Vec<B::QueueFamily>,
 }
+
+impl<B: Backend> Adapter<B> {
+    /// Capture everything this adapter reports into a [profile][DeviceProfile],
+    /// which can be stored and later emulated without the actual device.
+    pub fn profile(&self) -> DeviceProfile {
+        let device = &self.physical_device;
+        let formats = format::Format::all()
+            .filter_map(|format| {
+                let properties = device.format_properties(Some(format));
+                if properties == format::Properties::default() {
+                    return None;
+                }
+                let mut images = Vec::new();
+                for &(tiling, features) in &[
+                    (image::Tiling::Optimal, properties.optimal_tiling),
+                    (image::Tiling::Linear, properties.linear_tiling),
+                ] {
+                    if features.is_empty() {
+                        continue;
+                    }
+                    // Usages are queried one at a time, so that a device rejecting
+                    // their combination still has the supported ones recorded.
+                    for usage in usage_bits(image_usage_for(features)) {
+                        for dimensions in 1 ..= 3 {
+                            let all_caps = view_caps_for(dimensions);
+                            for &view_caps in &[image::ViewCapabilities::empty(), all_caps] {
+                                if let Some(properties) = device.image_format_properties(
+                                    format, dimensions, tiling, usage, view_caps,
+                                ) {
+                                    images.push(ImageFormatProfile {
+                                        dimensions,
+                                        tiling,
+                                        usage,
+                                        view_caps,
+                                        properties,
+                                    });
+                                }
+                            }
+                        }
+                    }
+                }
+                Some(FormatProfile {
+                    format,
+                    properties,
+                    images,
+                })
+            })
+            .collect();
+
+        DeviceProfile {
+            info: self.info.clone(),
+            features: device.features(),
+            hints: device.hints(),
+            limits: device.limits(),
+            memory_properties: device.memory_properties(),
+            queue_families: self
+                .queue_families
+                .iter()
+                .map(|family| QueueFamilyProfile {
+                    queue_type: family.queue_type(),
+                    max_queues: family.max_queues(),
+                })
+                .collect(),
+            formats,
+        }
+    }
+}
+
+/// Image usage covered by the given format features.
+fn image_usage_for(features: format::ImageFeature) -> image::Usage {
+    let mut usage = image::Usage::TRANSFER_SRC | image::Usage::TRANSFER_DST;
+    if features.contains(format::ImageFeature::SAMPLED) {
+        usage |= image::Usage::SAMPLED;
+    }
+    if features.contains(format::ImageFeature::STORAGE) {
+        usage |= image::Usage::STORAGE;
+    }
+    if features.contains(format::ImageFeature::COLOR_ATTACHMENT) {
+        usage |= image::Usage::COLOR_ATTACHMENT | image::Usage::INPUT_ATTACHMENT;
+    }
+    if features.contains(format::ImageFeature::DEPTH_STENCIL_ATTACHMENT) {
+        usage |= image::Usage::DEPTH_STENCIL_ATTACHMENT | image::Usage::INPUT_ATTACHMENT;
+    }
+    usage
+}
+
+/// Split a usage into its individual flags.
+fn usage_bits(usage: image::Usage) -> impl Iterator<Item = image::Usage> {
+    (0 .. 32)
+        .map(|shift| image::Usage::from_bits_truncate(1 << shift))
+        .filter(move |&bit| !bit.is_empty() && usage.contains(bit))
+}
+
+/// Properties of an image satisfying the restrictions of both `a` and `b`.
+fn intersect_properties(
+    a: image::FormatProperties,
+    b: image::FormatProperties,
+) -> image::FormatProperties {
+    image::FormatProperties {
+        max_extent: image::Extent {
+            width: a.max_extent.width.min(b.max_extent.width),
+            height: a.max_extent.height.min(b.max_extent.height),
+            depth: a.max_extent.depth.min(b.max_extent.depth),
+        },
+        max_levels: a.max_levels.min(b.max_levels),
+        max_layers: a.max_layers.min(b.max_layers),
+        sample_count_mask: a.sample_count_mask & b.sample_count_mask,
+        max_resource_size: a.max_resource_size.min(b.max_resource_size),
+    }
+}
+
+/// View capabilities applicable to images with the given number of dimensions.
+fn view_caps_for(dimensions: u8) -> image::ViewCapabilities {
+    match dimensions {
+        2 => image::ViewCapabilities::MUTABLE_FORMAT | image::ViewCapabilities::KIND_CUBE,
+        3 => image::ViewCapabilities::MUTABLE_FORMAT | image::ViewCapabilities::KIND_2D_ARRAY,
+        _ => image::ViewCapabilities::MUTABLE_FORMAT,
+    }
+}
+
+/// Queue family, as recorded in a [device profile][DeviceProfile].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct QueueFamilyProfile {
+    /// Type of the queues.
+    pub queue_type: QueueType,
+    /// Maximum number of queues created from this family.
+    pub max_queues: usize,
+}
+
+/// Result of an [image format query][PhysicalDevice::image_format_properties],
+/// as recorded in a [device profile][DeviceProfile].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ImageFormatProfile {
+    /// Number of image dimensions.
+    pub dimensions: u8,
+    /// Image tiling.
+    pub tiling: image::Tiling,
+    /// Image usage the query was made with.
+    pub usage: image::Usage,
+    /// View capabilities the query was made with.
+    pub view_caps: image::ViewCapabilities,
+    /// Returned properties.
+    pub properties: image::FormatProperties,
+}
+
+/// Capabilities of a format, as recorded in a [device profile][DeviceProfile].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FormatProfile {
+    /// The format.
+    pub format: format::Format,
+    /// Format properties.
+    pub properties: format::Properties,
+    /// Successful image format queries.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub images: Vec<ImageFormatProfile>,
+}
+
+/// Everything a physical device reports about itself.
+///
+/// A profile is captured from a real device with [`Adapter::profile`],
+/// and can be (de)serialized in order to emulate that device elsewhere.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DeviceProfile {
+    /// Adapter information.
+    pub info: AdapterInfo,
+    /// Supported features.
+    pub features: Features,
+    /// Performance hints.
+    pub hints: Hints,
+    /// Resource limits.
+    pub limits: Limits,
+    /// Memory types and heaps.
+    pub memory_properties: MemoryProperties,
+    /// Queue families, in the order of their IDs.
+    pub queue_families: Vec<QueueFamilyProfile>,
+    /// Formats with any supported features. Missing formats are not supported.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub formats: Vec<FormatProfile>,
+}
+
+impl DeviceProfile {
+    /// Fetch details for a particular format, as [`PhysicalDevice::format_properties`] would.
+    pub fn format_properties(&self, format: Option<format::Format>) -> format::Properties {
+        format
+            .and_then(|format| self.formats.iter().find(|f| f.format == format))
+            .map_or(format::Properties::default(), |f| f.properties)
+    }
+
+    /// Fetch details for a particular image format,
+    /// as [`PhysicalDevice::image_format_properties`] would.
+    ///
+    /// Each flag of the requested usage is looked up in the first recorded query
+    /// with the same format, dimensions and tiling, which was made with that flag
+    /// and a superset of the requested view capabilities.
+    /// The result is the intersection of the properties found for every flag.
+    pub fn image_format_properties(
+        &self,
+        format: format::Format,
+        dimensions: u8,
+        tiling: image::Tiling,
+        usage: image::Usage,
+        view_caps: image::ViewCapabilities,
+    ) -> Option<image::FormatProperties> {
+        let images = &self.formats.iter().find(|f| f.format == format)?.images;
+        let lookup = |usage: image::Usage| {
+            images
+                .iter()
+                .find(|image| {
+                    image.dimensions == dimensions
+                        && image.tiling == tiling
+                        && image.usage.contains(usage)
+                        && image.view_caps.contains(view_caps)
+                })
+                .map(|image| image.properties)
+        };
+        let mut bits = usage_bits(usage);
+        match bits.next() {
+            Some(bit) => bits.try_fold(lookup(bit)?, |properties, bit| {
+                lookup(bit).map(|other| intersect_properties(properties, other))
+            }),
+            None => lookup(usage),
+        }
+    }
+}
@@ -124,7 +124,6 @@ impl std::error::Error for ViewCreationError {
 
 bitflags!(
     /// Buffer usage flags.
-    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct Usage: u32 {
         ///
         const TRANSFER_SRC  = 0x1;
@@ -147,6 +146,18 @@ bitflags!(
     }
 );
 
+named_flags!(Usage: u32 {
+    TRANSFER_SRC,
+    TRANSFER_DST,
+    UNIFORM_TEXEL,
+    STORAGE_TEXEL,
+    UNIFORM,
+    STORAGE,
+    INDEX,
+    VERTEX,
+    INDIRECT,
+});
+
 impl Usage {
     /// Returns if the buffer can be used in transfer operations.
     pub fn can_transfer(&self) -> bool {
@@ -158,7 +169,6 @@ bitflags!(
     /// Buffer access flags.
     ///
     /// Access of buffers by the pipeline or shaders.
-    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct Access: u32 {
         /// Read commands instruction for indirect execution.
         const INDIRECT_COMMAND_READ = 0x1;
@@ -191,6 +201,21 @@ bitflags!(
     }
 );
 
+named_flags!(Access: u32 {
+    INDIRECT_COMMAND_READ,
+    INDEX_BUFFER_READ,
+    VERTEX_BUFFER_READ,
+    UNIFORM_READ,
+    SHADER_READ,
+    SHADER_WRITE,
+    TRANSFER_READ,
+    TRANSFER_WRITE,
+    HOST_READ,
+    HOST_WRITE,
+    MEMORY_READ,
+    MEMORY_WRITE,
+});
+
 /// Index buffer view for `bind_index_buffer`.
 ///
 /// Defines a buffer slice used for acquiring the indices on draw commands.
@@ -0,0 +1,432 @@
+//! Layout and validation of indirect command arguments.
+
+use crate::{
+    buffer,
+    DrawCount,
+    Features,
+    IndexCount,
+    InstanceCount,
+    Limits,
+    VertexCount,
+    VertexOffset,
+    WorkGroupCount,
+};
+
+use std::{fmt, ptr, slice};
+
+/// Arguments of a single [`draw_indirect`][super::CommandBuffer::draw_indirect] draw.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DrawIndirectCommand {
+    /// Number of vertices to draw.
+    pub vertex_count: VertexCount,
+    /// Number of instances to draw.
+    pub instance_count: InstanceCount,
+    /// Index of the first vertex.
+    pub first_vertex: VertexCount,
+    /// Index of the first instance.
+    pub first_instance: InstanceCount,
+}
+
+/// Arguments of a single [`draw_indexed_indirect`][super::CommandBuffer::draw_indexed_indirect] draw.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DrawIndexedIndirectCommand {
+    /// Number of indices to draw.
+    pub index_count: IndexCount,
+    /// Number of instances to draw.
+    pub instance_count: InstanceCount,
+    /// Index of the first index in the index buffer.
+    pub first_index: IndexCount,
+    /// Value added to the vertex index before indexing into the vertex buffers.
+    pub vertex_offset: VertexOffset,
+    /// Index of the first instance.
+    pub first_instance: InstanceCount,
+}
+
+/// Arguments of a [`dispatch_indirect`][super::CommandBuffer::dispatch_indirect] dispatch.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DispatchIndirectCommand {
+    /// Number of work groups in the X dimension.
+    pub x: u32,
+    /// Number of work groups in the Y dimension.
+    pub y: u32,
+    /// Number of work groups in the Z dimension.
+    pub z: u32,
+}
+
+impl DispatchIndirectCommand {
+    /// Number of work groups in each dimension.
+    pub fn count(&self) -> WorkGroupCount {
+        [self.x, self.y, self.z]
+    }
+}
+
+impl From<WorkGroupCount> for DispatchIndirectCommand {
+    fn from(count: WorkGroupCount) -> Self {
+        DispatchIndirectCommand {
+            x: count[0],
+            y: count[1],
+            z: count[2],
+        }
+    }
+}
+
+/// Arguments of an indirect command, as stored in an argument buffer.
+///
+/// # Safety
+///
+/// Implementors must be `#[repr(C)]` structs of 4-byte integers without padding.
+pub unsafe trait IndirectCommand: Copy + Default {
+    /// Size in bytes of the arguments, which is also the tightly packed stride.
+    const STRIDE: u32;
+
+    /// View a slice of commands as the bytes of an argument buffer.
+    fn as_bytes(commands: &[Self]) -> &[u8] {
+        unsafe { slice::from_raw_parts(commands.as_ptr() as *const u8, size_of_val(commands)) }
+    }
+
+    /// Read `count` commands from an argument buffer, starting at `offset`
+    /// and advancing by `stride` bytes.
+    fn read(
+        bytes: &[u8],
+        offset: buffer::Offset,
+        count: DrawCount,
+        stride: u32,
+    ) -> Result<Vec<Self>, IndirectError> {
+        (0 .. count)
+            .map(|index| {
+                // Overflowing offsets are out of bounds too.
+                let start = (index as buffer::Offset)
+                    .checked_mul(stride as buffer::Offset)
+                    .and_then(|delta| delta.checked_add(offset));
+                let end = start.and_then(|start| start.checked_add(Self::STRIDE as buffer::Offset));
+                let (start, end) = match (start, end) {
+                    (Some(start), Some(end)) if end <= bytes.len() as buffer::Offset => {
+                        (start, end)
+                    }
+                    _ => return Err(IndirectError::OutOfBounds { index }),
+                };
+                let bytes = &bytes[start as usize .. end as usize];
+                Ok(unsafe { ptr::read_unaligned(bytes.as_ptr() as *const Self) })
+            })
+            .collect()
+    }
+}
+
+unsafe impl IndirectCommand for DrawIndirectCommand {
+    const STRIDE: u32 = 16;
+}
+
+unsafe impl IndirectCommand for DrawIndexedIndirectCommand {
+    const STRIDE: u32 = 20;
+}
+
+unsafe impl IndirectCommand for DispatchIndirectCommand {
+    const STRIDE: u32 = 12;
+}
+
+/// Error found while validating an argument buffer.
+#[derive(Clone, Debug, PartialEq)]
+pub enum IndirectError {
+    /// The offset into the argument buffer isn't a multiple of 4.
+    UnalignedOffset(buffer::Offset),
+    /// The stride isn't a multiple of 4 or is smaller than the arguments.
+    InvalidStride(u32),
+    /// The arguments of a command go past the end of the buffer.
+    OutOfBounds {
+        /// Index of the command.
+        index: DrawCount,
+    },
+    /// More than one draw requires `Features::MULTI_DRAW_INDIRECT`.
+    MultiDrawNotSupported,
+    /// The draw count exceeds `Limits::max_draw_indirect_count`.
+    TooManyDraws {
+        /// Requested draw count.
+        count: DrawCount,
+        /// Maximum draw count supported by the device.
+        max: DrawCount,
+    },
+    /// A non-zero first instance requires `Features::DRAW_INDIRECT_FIRST_INSTANCE`.
+    FirstInstanceNotSupported {
+        /// Index of the command.
+        index: DrawCount,
+    },
+    /// The work group count exceeds `Limits::max_compute_work_group_count`.
+    TooManyWorkGroups {
+        /// Requested work group count.
+        count: WorkGroupCount,
+        /// Maximum work group count supported by the device.
+        max: WorkGroupCount,
+    },
+}
+
+impl fmt::Display for IndirectError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            IndirectError::UnalignedOffset(offset) => write!(
+                fmt,
+                "Argument buffer offset {} is not a multiple of 4",
+                offset
+            ),
+            IndirectError::InvalidStride(stride) => write!(fmt, "Invalid stride {}", stride),
+            IndirectError::OutOfBounds { index } => write!(
+                fmt,
+                "Arguments of command {} are out of the buffer bounds",
+                index
+            ),
+            IndirectError::MultiDrawNotSupported => {
+                write!(fmt, "Multiple indirect draws are not supported")
+            }
+            IndirectError::TooManyDraws { count, max } => {
+                write!(fmt, "Draw count {} exceeds the limit of {}", count, max)
+            }
+            IndirectError::FirstInstanceNotSupported { index } => write!(
+                fmt,
+                "Command {} has a non-zero first instance, which is not supported",
+                index
+            ),
+            IndirectError::TooManyWorkGroups { count, max } => write!(
+                fmt,
+                "Work group count {:?} exceeds the limit of {:?}",
+                count, max
+            ),
+        }
+    }
+}
+
+impl std::error::Error for IndirectError {}
+
+fn read_draws<T: IndirectCommand>(
+    bytes: &[u8],
+    offset: buffer::Offset,
+    draw_count: DrawCount,
+    stride: u32,
+    features: Features,
+    limits: &Limits,
+) -> Result<Vec<T>, IndirectError> {
+    if !offset.is_multiple_of(4) {
+        return Err(IndirectError::UnalignedOffset(offset));
+    }
+    if draw_count > 1 {
+        if !stride.is_multiple_of(4) || stride < T::STRIDE {
+            return Err(IndirectError::InvalidStride(stride));
+        }
+        if !features.contains(Features::MULTI_DRAW_INDIRECT) {
+            return Err(IndirectError::MultiDrawNotSupported);
+        }
+    }
+    if draw_count > limits.max_draw_indirect_count {
+        return Err(IndirectError::TooManyDraws {
+            count: draw_count,
+            max: limits.max_draw_indirect_count,
+        });
+    }
+    T::read(bytes, offset, draw_count, stride)
+}
+
+/// Check the arguments of a `draw_indirect` call against the features and limits of a device.
+pub fn validate_draw_indirect(
+    bytes: &[u8],
+    offset: buffer::Offset,
+    draw_count: DrawCount,
+    stride: u32,
+    features: Features,
+    limits: &Limits,
+) -> Result<(), IndirectError> {
+    let draws =
+        read_draws::<DrawIndirectCommand>(bytes, offset, draw_count, stride, features, limits)?;
+    if !features.contains(Features::DRAW_INDIRECT_FIRST_INSTANCE) {
+        if let Some(index) = draws.iter().position(|draw| draw.first_instance != 0) {
+            return Err(IndirectError::FirstInstanceNotSupported {
+                index: index as DrawCount,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Check the arguments of a `draw_indexed_indirect` call against the features and limits of a device.
+pub fn validate_draw_indexed_indirect(
+    bytes: &[u8],
+    offset: buffer::Offset,
+    draw_count: DrawCount,
+    stride: u32,
+    features: Features,
+    limits: &Limits,
+) -> Result<(), IndirectError> {
+    let draws = read_draws::<DrawIndexedIndirectCommand>(
+        bytes, offset, draw_count, stride, features, limits,
+    )?;
+    if !features.contains(Features::DRAW_INDIRECT_FIRST_INSTANCE) {
+        if let Some(index) = draws.iter().position(|draw| draw.first_instance != 0) {
+            return Err(IndirectError::FirstInstanceNotSupported {
+                index: index as DrawCount,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Check the arguments of a `dispatch_indirect` call against the limits of a device.
+pub fn validate_dispatch_indirect(
+    bytes: &[u8],
+    offset: buffer::Offset,
+    limits: &Limits,
+) -> Result<(), IndirectError> {
+    if !offset.is_multiple_of(4) {
+        return Err(IndirectError::UnalignedOffset(offset));
+    }
+    let dispatch =
+        DispatchIndirectCommand::read(bytes, offset, 1, DispatchIndirectCommand::STRIDE)?[0];
+    let (count, max) = (dispatch.count(), limits.max_compute_work_group_count);
+    if count.iter().zip(max.iter()).any(|(c, m)| c > m) {
+        return Err(IndirectError::TooManyWorkGroups { count, max });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout() {
+        assert_eq!(
+            size_of::<DrawIndirectCommand>(),
+            DrawIndirectCommand::STRIDE as usize
+        );
+        assert_eq!(
+            size_of::<DrawIndexedIndirectCommand>(),
+            DrawIndexedIndirectCommand::STRIDE as usize
+        );
+        assert_eq!(
+            size_of::<DispatchIndirectCommand>(),
+            DispatchIndirectCommand::STRIDE as usize
+        );
+
+        let draws = [
+            DrawIndexedIndirectCommand {
+                index_count: 6,
+                instance_count: 1,
+                first_index: 0,
+                vertex_offset: -4,
+                first_instance: 0,
+            },
+            DrawIndexedIndirectCommand {
+                index_count: 3,
+                instance_count: 2,
+                first_index: 6,
+                vertex_offset: 0,
+                first_instance: 1,
+            },
+        ];
+        let bytes = IndirectCommand::as_bytes(&draws);
+        assert_eq!(bytes.len(), 40);
+        assert_eq!(&bytes[12 .. 16], &(-4i32).to_ne_bytes());
+
+        // Unaligned source.
+        let mut shifted = vec![0u8];
+        shifted.extend_from_slice(bytes);
+        assert_eq!(
+            DrawIndexedIndirectCommand::read(&shifted[1 ..], 0, 2, 20).unwrap(),
+            draws
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let limits = Limits {
+            max_draw_indirect_count: 4,
+            max_compute_work_group_count: [64, 64, 1],
+            ..Limits::default()
+        };
+        let draw = |first_instance| DrawIndirectCommand {
+            vertex_count: 3,
+            instance_count: 1,
+            first_vertex: 0,
+            first_instance,
+        };
+        let draws = [draw(0), draw(0), draw(2)];
+        let bytes = IndirectCommand::as_bytes(&draws);
+
+        assert_eq!(
+            validate_draw_indirect(bytes, 0, 2, 16, Features::MULTI_DRAW_INDIRECT, &limits),
+            Ok(())
+        );
+        assert_eq!(
+            validate_draw_indirect(bytes, 0, 2, 16, Features::empty(), &limits),
+            Err(IndirectError::MultiDrawNotSupported)
+        );
+        assert_eq!(
+            validate_draw_indirect(bytes, 2, 1, 16, Features::empty(), &limits),
+            Err(IndirectError::UnalignedOffset(2))
+        );
+        assert_eq!(
+            validate_draw_indirect(bytes, 0, 2, 12, Features::MULTI_DRAW_INDIRECT, &limits),
+            Err(IndirectError::InvalidStride(12))
+        );
+        assert_eq!(
+            validate_draw_indirect(bytes, 16, 3, 16, Features::MULTI_DRAW_INDIRECT, &limits),
+            Err(IndirectError::OutOfBounds { index: 2 })
+        );
+        assert_eq!(
+            validate_draw_indirect(bytes, 0, 5, 0, Features::MULTI_DRAW_INDIRECT, &limits),
+            Err(IndirectError::InvalidStride(0))
+        );
+        assert_eq!(
+            validate_draw_indirect(bytes, 0, 5, 16, Features::MULTI_DRAW_INDIRECT, &limits),
+            Err(IndirectError::TooManyDraws { count: 5, max: 4 })
+        );
+        assert_eq!(
+            validate_draw_indirect(bytes, 16, 2, 16, Features::MULTI_DRAW_INDIRECT, &limits),
+            Err(IndirectError::FirstInstanceNotSupported { index: 1 })
+        );
+        assert_eq!(
+            validate_draw_indirect(
+                bytes,
+                16,
+                2,
+                16,
+                Features::MULTI_DRAW_INDIRECT | Features::DRAW_INDIRECT_FIRST_INSTANCE,
+                &limits
+            ),
+            Ok(())
+        );
+
+        // Offsets near the end of the address space don't overflow.
+        let last = buffer::Offset::MAX - 3;
+        assert_eq!(
+            validate_draw_indirect(bytes, last, 1, 16, Features::empty(), &limits),
+            Err(IndirectError::OutOfBounds { index: 0 })
+        );
+        assert_eq!(
+            validate_draw_indexed_indirect(
+                bytes,
+                last,
+                2,
+                20,
+                Features::MULTI_DRAW_INDIRECT,
+                &limits
+            ),
+            Err(IndirectError::OutOfBounds { index: 0 })
+        );
+        assert_eq!(
+            validate_dispatch_indirect(bytes, last, &limits),
+            Err(IndirectError::OutOfBounds { index: 0 })
+        );
+
+        let dispatch = [DispatchIndirectCommand::from([64, 65, 1])];
+        assert_eq!(
+            validate_dispatch_indirect(IndirectCommand::as_bytes(&dispatch), 0, &limits),
+            Err(IndirectError::TooManyWorkGroups {
+                count: [64, 65, 1],
+                max: [64, 64, 1],
+            })
+        );
+    }
+}
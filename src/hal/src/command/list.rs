@@ -0,0 +1,1563 @@
+//! Backend-agnostic recorded command lists.
+//!
+//! A [`CommandList`][CommandList] records the same commands as a
+//! [`CommandBuffer`][super::CommandBuffer], but into an owned list that refers to
+//! resources by plain identifiers instead of backend handles. The list can be
+//! stored, compared or serialized, and later replayed onto a command buffer of
+//! any backend, with a [`Resolver`][Resolver] mapping the identifiers to the
+//! resources of that backend.
+
+use std::borrow::Borrow;
+use std::fmt;
+use std::ops::Range;
+
+use crate::command::{
+    AttachmentClear as HalAttachmentClear,
+    BufferCopy,
+    BufferImageCopy,
+    ClearColor,
+    ClearValue,
+    CommandBuffer,
+    DescriptorSetOffset,
+    ImageBlit,
+    ImageCopy,
+    ImageResolve,
+    SubpassContents,
+};
+use crate::image::{self, Filter, Layout, SubresourceRange};
+use crate::memory::{self, Dependencies};
+use crate::queue::QueueFamilyId;
+use crate::{buffer, pso, query, Backend, IndexType};
+use crate::{DrawCount, IndexCount, InstanceCount, VertexCount, VertexOffset, WorkGroupCount};
+
+macro_rules! ids {
+    ($($(#[$attr:meta])* $name:ident,)*) => {
+        $(
+            $(#[$attr])*
+            #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+            #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+            pub struct $name(pub u32);
+        )*
+    };
+}
+
+ids! {
+    /// Identifier of a buffer in a command list.
+    BufferId,
+    /// Identifier of an image in a command list.
+    ImageId,
+    /// Identifier of a render pass in a command list.
+    RenderPassId,
+    /// Identifier of a framebuffer in a command list.
+    FramebufferId,
+    /// Identifier of a graphics pipeline in a command list.
+    GraphicsPipelineId,
+    /// Identifier of a compute pipeline in a command list.
+    ComputePipelineId,
+    /// Identifier of a pipeline layout in a command list.
+    PipelineLayoutId,
+    /// Identifier of a descriptor set in a command list.
+    DescriptorSetId,
+    /// Identifier of an event in a command list.
+    EventId,
+    /// Identifier of a query pool in a command list.
+    QueryPoolId,
+    /// Identifier of a secondary command buffer in a command list.
+    CommandBufferId,
+}
+
+/// Maps the identifiers of a command list to the resources of a backend.
+///
+/// Replaying a list panics if the resolver can't provide one of its resources.
+pub trait Resolver<B: Backend> {
+    /// Return the buffer with the given identifier.
+    fn buffer(&self, id: BufferId) -> &B::Buffer;
+    /// Return the image with the given identifier.
+    fn image(&self, id: ImageId) -> &B::Image;
+    /// Return the render pass with the given identifier.
+    fn render_pass(&self, id: RenderPassId) -> &B::RenderPass;
+    /// Return the framebuffer with the given identifier.
+    fn framebuffer(&self, id: FramebufferId) -> &B::Framebuffer;
+    /// Return the graphics pipeline with the given identifier.
+    fn graphics_pipeline(&self, id: GraphicsPipelineId) -> &B::GraphicsPipeline;
+    /// Return the compute pipeline with the given identifier.
+    fn compute_pipeline(&self, id: ComputePipelineId) -> &B::ComputePipeline;
+    /// Return the pipeline layout with the given identifier.
+    fn pipeline_layout(&self, id: PipelineLayoutId) -> &B::PipelineLayout;
+    /// Return the descriptor set with the given identifier.
+    fn descriptor_set(&self, id: DescriptorSetId) -> &B::DescriptorSet;
+    /// Return the event with the given identifier.
+    fn event(&self, id: EventId) -> &B::Event;
+    /// Return the query pool with the given identifier.
+    fn query_pool(&self, id: QueryPoolId) -> &B::QueryPool;
+    /// Return the secondary command buffer with the given identifier.
+    fn command_buffer(&self, id: CommandBufferId) -> &B::CommandBuffer;
+}
+
+/// Simple [`Resolver`][Resolver] over borrowed resources.
+///
+/// Every `insert_*` method returns the identifier of the resource, which is
+/// its index among the resources of the same kind.
+pub struct Resources<'a, B: Backend> {
+    buffers: Vec<&'a B::Buffer>,
+    images: Vec<&'a B::Image>,
+    render_passes: Vec<&'a B::RenderPass>,
+    framebuffers: Vec<&'a B::Framebuffer>,
+    graphics_pipelines: Vec<&'a B::GraphicsPipeline>,
+    compute_pipelines: Vec<&'a B::ComputePipeline>,
+    pipeline_layouts: Vec<&'a B::PipelineLayout>,
+    descriptor_sets: Vec<&'a B::DescriptorSet>,
+    events: Vec<&'a B::Event>,
+    query_pools: Vec<&'a B::QueryPool>,
+    command_buffers: Vec<&'a B::CommandBuffer>,
+}
+
+impl<B: Backend> Default for Resources<'_, B> {
+    fn default() -> Self {
+        Resources {
+            buffers: Vec::new(),
+            images: Vec::new(),
+            render_passes: Vec::new(),
+            framebuffers: Vec::new(),
+            graphics_pipelines: Vec::new(),
+            compute_pipelines: Vec::new(),
+            pipeline_layouts: Vec::new(),
+            descriptor_sets: Vec::new(),
+            events: Vec::new(),
+            query_pools: Vec::new(),
+            command_buffers: Vec::new(),
+        }
+    }
+}
+
+impl<B: Backend> fmt::Debug for Resources<'_, B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Resources")
+            .field("buffers", &self.buffers.len())
+            .field("images", &self.images.len())
+            .field("render_passes", &self.render_passes.len())
+            .field("framebuffers", &self.framebuffers.len())
+            .field("graphics_pipelines", &self.graphics_pipelines.len())
+            .field("compute_pipelines", &self.compute_pipelines.len())
+            .field("pipeline_layouts", &self.pipeline_layouts.len())
+            .field("descriptor_sets", &self.descriptor_sets.len())
+            .field("events", &self.events.len())
+            .field("query_pools", &self.query_pools.len())
+            .field("command_buffers", &self.command_buffers.len())
+            .finish()
+    }
+}
+
+macro_rules! resources {
+    ($($field:ident, $insert:ident, $get:ident: $ty:ident => $id:ident;)*) => {
+        impl<'a, B: Backend> Resources<'a, B> {
+            $(
+                #[doc = concat!("Add a resource, returning its `", stringify!($id), "`.")]
+                pub fn $insert(&mut self, resource: &'a B::$ty) -> $id {
+                    self.$field.push(resource);
+                    $id(self.$field.len() as u32 - 1)
+                }
+            )*
+        }
+
+        impl<B: Backend> Resolver<B> for Resources<'_, B> {
+            $(
+                fn $get(&self, id: $id) -> &B::$ty {
+                    self.$field[id.0 as usize]
+                }
+            )*
+        }
+    };
+}
+
+resources! {
+    buffers, insert_buffer, buffer: Buffer => BufferId;
+    images, insert_image, image: Image => ImageId;
+    render_passes, insert_render_pass, render_pass: RenderPass => RenderPassId;
+    framebuffers, insert_framebuffer, framebuffer: Framebuffer => FramebufferId;
+    graphics_pipelines, insert_graphics_pipeline, graphics_pipeline: GraphicsPipeline => GraphicsPipelineId;
+    compute_pipelines, insert_compute_pipeline, compute_pipeline: ComputePipeline => ComputePipelineId;
+    pipeline_layouts, insert_pipeline_layout, pipeline_layout: PipelineLayout => PipelineLayoutId;
+    descriptor_sets, insert_descriptor_set, descriptor_set: DescriptorSet => DescriptorSetId;
+    events, insert_event, event: Event => EventId;
+    query_pools, insert_query_pool, query_pool: QueryPool => QueryPoolId;
+    command_buffers, insert_command_buffer, command_buffer: CommandBuffer => CommandBufferId;
+}
+
+/// Recorded counterpart of [`memory::Barrier`][memory::Barrier].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Barrier {
+    /// Applies the given access flags to all buffers in the range.
+    AllBuffers(Range<buffer::Access>),
+    /// Applies the given access flags to all images in the range.
+    AllImages(Range<image::Access>),
+    /// A memory barrier that defines access to a buffer.
+    Buffer {
+        /// The access flags controlling the buffer.
+        states: Range<buffer::State>,
+        /// The buffer the barrier controls.
+        target: BufferId,
+        /// Subrange of the buffer the barrier applies to.
+        range: buffer::SubRange,
+        /// The source and destination queue families of an ownership transfer.
+        families: Option<Range<QueueFamilyId>>,
+    },
+    /// A memory barrier that defines access to (a subset of) an image.
+    Image {
+        /// The access flags controlling the image.
+        states: Range<image::State>,
+        /// The image the barrier controls.
+        target: ImageId,
+        /// Section of the image the barrier applies to.
+        range: SubresourceRange,
+        /// The source and destination queue families of an ownership transfer.
+        families: Option<Range<QueueFamilyId>>,
+    },
+}
+
+impl Barrier {
+    fn resolve<'a, B: Backend, R: Resolver<B>>(&self, resolver: &'a R) -> memory::Barrier<'a, B> {
+        match *self {
+            Barrier::AllBuffers(ref access) => memory::Barrier::AllBuffers(access.clone()),
+            Barrier::AllImages(ref access) => memory::Barrier::AllImages(access.clone()),
+            Barrier::Buffer {
+                ref states,
+                target,
+                ref range,
+                ref families,
+            } => memory::Barrier::Buffer {
+                states: states.clone(),
+                target: resolver.buffer(target),
+                range: range.clone(),
+                families: families.clone(),
+            },
+            Barrier::Image {
+                ref states,
+                target,
+                ref range,
+                ref families,
+            } => memory::Barrier::Image {
+                states: states.clone(),
+                target: resolver.image(target),
+                range: range.clone(),
+                families: families.clone(),
+            },
+        }
+    }
+}
+
+/// Recorded counterpart of [`AttachmentClear`][HalAttachmentClear].
+///
+/// Clear colors are stored as raw bits, so that they compare and serialize
+/// regardless of the format of the attachment.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AttachmentClear {
+    /// Clear color attachment.
+    Color {
+        /// Index inside the `SubpassDesc::colors` array.
+        index: usize,
+        /// Bits of the value to clear with.
+        value: [u32; 4],
+    },
+    /// Clear depth-stencil attachment.
+    DepthStencil {
+        /// Depth value to clear with.
+        depth: Option<pso::DepthValue>,
+        /// Stencil value to clear with.
+        stencil: Option<pso::StencilValue>,
+    },
+}
+
+impl From<HalAttachmentClear> for AttachmentClear {
+    fn from(clear: HalAttachmentClear) -> Self {
+        match clear {
+            HalAttachmentClear::Color { index, value } => AttachmentClear::Color {
+                index,
+                value: unsafe { value.uint32 },
+            },
+            HalAttachmentClear::DepthStencil { depth, stencil } => {
+                AttachmentClear::DepthStencil { depth, stencil }
+            }
+        }
+    }
+}
+
+impl From<AttachmentClear> for HalAttachmentClear {
+    fn from(clear: AttachmentClear) -> Self {
+        match clear {
+            AttachmentClear::Color { index, value } => HalAttachmentClear::Color {
+                index,
+                value: ClearColor { uint32: value },
+            },
+            AttachmentClear::DepthStencil { depth, stencil } => {
+                HalAttachmentClear::DepthStencil { depth, stencil }
+            }
+        }
+    }
+}
+
+fn clear_bits(value: &ClearValue) -> [u32; 4] {
+    unsafe { value.color.uint32 }
+}
+
+fn clear_value(bits: [u32; 4]) -> ClearValue {
+    ClearValue {
+        color: ClearColor { uint32: bits },
+    }
+}
+
+/// A single recorded command.
+///
+/// Variants mirror the recording methods of [`CommandBuffer`][CommandBuffer].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[allow(missing_docs)]
+pub enum Command {
+    PipelineBarrier {
+        stages: Range<pso::PipelineStage>,
+        dependencies: Dependencies,
+        barriers: Vec<Barrier>,
+    },
+    FillBuffer {
+        buffer: BufferId,
+        range: buffer::SubRange,
+        data: u32,
+    },
+    UpdateBuffer {
+        buffer: BufferId,
+        offset: buffer::Offset,
+        data: Vec<u8>,
+    },
+    ClearImage {
+        image: ImageId,
+        layout: Layout,
+        value: [u32; 4],
+        subresource_ranges: Vec<SubresourceRange>,
+    },
+    ClearAttachments {
+        clears: Vec<AttachmentClear>,
+        rects: Vec<pso::ClearRect>,
+    },
+    ResolveImage {
+        src: ImageId,
+        src_layout: Layout,
+        dst: ImageId,
+        dst_layout: Layout,
+        regions: Vec<ImageResolve>,
+    },
+    BlitImage {
+        src: ImageId,
+        src_layout: Layout,
+        dst: ImageId,
+        dst_layout: Layout,
+        filter: Filter,
+        regions: Vec<ImageBlit>,
+    },
+    BindIndexBuffer {
+        buffer: BufferId,
+        range: buffer::SubRange,
+        index_type: IndexType,
+    },
+    BindVertexBuffers {
+        first_binding: pso::BufferIndex,
+        buffers: Vec<(BufferId, buffer::SubRange)>,
+    },
+    SetViewports {
+        first_viewport: u32,
+        viewports: Vec<pso::Viewport>,
+    },
+    SetScissors {
+        first_scissor: u32,
+        rects: Vec<pso::Rect>,
+    },
+    SetStencilReference {
+        faces: pso::Face,
+        value: pso::StencilValue,
+    },
+    SetStencilReadMask {
+        faces: pso::Face,
+        value: pso::StencilValue,
+    },
+    SetStencilWriteMask {
+        faces: pso::Face,
+        value: pso::StencilValue,
+    },
+    SetBlendConstants(pso::ColorValue),
+    SetDepthBounds(Range<f32>),
+    SetLineWidth(f32),
+    SetDepthBias(pso::DepthBias),
+    BeginRenderPass {
+        render_pass: RenderPassId,
+        framebuffer: FramebufferId,
+        render_area: pso::Rect,
+        clear_values: Vec<[u32; 4]>,
+        first_subpass: SubpassContents,
+    },
+    NextSubpass(SubpassContents),
+    EndRenderPass,
+    BindGraphicsPipeline(GraphicsPipelineId),
+    BindGraphicsDescriptorSets {
+        layout: PipelineLayoutId,
+        first_set: usize,
+        sets: Vec<DescriptorSetId>,
+        offsets: Vec<DescriptorSetOffset>,
+    },
+    BindComputePipeline(ComputePipelineId),
+    BindComputeDescriptorSets {
+        layout: PipelineLayoutId,
+        first_set: usize,
+        sets: Vec<DescriptorSetId>,
+        offsets: Vec<DescriptorSetOffset>,
+    },
+    Dispatch(WorkGroupCount),
+    DispatchIndirect {
+        buffer: BufferId,
+        offset: buffer::Offset,
+    },
+    CopyBuffer {
+        src: BufferId,
+        dst: BufferId,
+        regions: Vec<BufferCopy>,
+    },
+    CopyImage {
+        src: ImageId,
+        src_layout: Layout,
+        dst: ImageId,
+        dst_layout: Layout,
+        regions: Vec<ImageCopy>,
+    },
+    CopyBufferToImage {
+        src: BufferId,
+        dst: ImageId,
+        dst_layout: Layout,
+        regions: Vec<BufferImageCopy>,
+    },
+    CopyImageToBuffer {
+        src: ImageId,
+        src_layout: Layout,
+        dst: BufferId,
+        regions: Vec<BufferImageCopy>,
+    },
+    Draw {
+        vertices: Range<VertexCount>,
+        instances: Range<InstanceCount>,
+    },
+    DrawIndexed {
+        indices: Range<IndexCount>,
+        base_vertex: VertexOffset,
+        instances: Range<InstanceCount>,
+    },
+    DrawIndirect {
+        buffer: BufferId,
+        offset: buffer::Offset,
+        draw_count: DrawCount,
+        stride: u32,
+    },
+    DrawIndexedIndirect {
+        buffer: BufferId,
+        offset: buffer::Offset,
+        draw_count: DrawCount,
+        stride: u32,
+    },
+    SetEvent {
+        event: EventId,
+        stages: pso::PipelineStage,
+    },
+    ResetEvent {
+        event: EventId,
+        stages: pso::PipelineStage,
+    },
+    WaitEvents {
+        events: Vec<EventId>,
+        stages: Range<pso::PipelineStage>,
+        barriers: Vec<Barrier>,
+    },
+    BeginQuery {
+        pool: QueryPoolId,
+        id: query::Id,
+        flags: query::ControlFlags,
+    },
+    EndQuery {
+        pool: QueryPoolId,
+        id: query::Id,
+    },
+    ResetQueryPool {
+        pool: QueryPoolId,
+        queries: Range<query::Id>,
+    },
+    CopyQueryPoolResults {
+        pool: QueryPoolId,
+        queries: Range<query::Id>,
+        buffer: BufferId,
+        offset: buffer::Offset,
+        stride: buffer::Offset,
+        flags: query::ResultFlags,
+    },
+    WriteTimestamp {
+        stage: pso::PipelineStage,
+        pool: QueryPoolId,
+        id: query::Id,
+    },
+    PushGraphicsConstants {
+        layout: PipelineLayoutId,
+        stages: pso::ShaderStageFlags,
+        offset: u32,
+        constants: Vec<u32>,
+    },
+    PushComputeConstants {
+        layout: PipelineLayoutId,
+        offset: u32,
+        constants: Vec<u32>,
+    },
+    ExecuteCommands(Vec<CommandBufferId>),
+    InsertDebugMarker {
+        name: String,
+        color: u32,
+    },
+    BeginDebugMarker {
+        name: String,
+        color: u32,
+    },
+    EndDebugMarker,
+}
+
+/// An owned list of commands, recorded independently of any backend.
+///
+/// The recording methods mirror those of [`CommandBuffer`][CommandBuffer],
+/// except that resources are passed by identifier. Beginning, finishing and
+/// resetting are left to the command buffer the list is replayed onto.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CommandList {
+    /// Recorded commands, in order.
+    pub commands: Vec<Command>,
+}
+
+impl CommandList {
+    /// Create an empty command list.
+    pub fn new() -> Self {
+        CommandList::default()
+    }
+
+    /// Record a pipeline barrier.
+    pub fn pipeline_barrier<T>(
+        &mut self,
+        stages: Range<pso::PipelineStage>,
+        dependencies: Dependencies,
+        barriers: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<Barrier>,
+    {
+        self.commands.push(Command::PipelineBarrier {
+            stages,
+            dependencies,
+            barriers: barriers.into_iter().map(|b| b.borrow().clone()).collect(),
+        });
+    }
+
+    /// Record filling a buffer with the given `u32` value.
+    pub fn fill_buffer(&mut self, buffer: BufferId, range: buffer::SubRange, data: u32) {
+        self.commands.push(Command::FillBuffer {
+            buffer,
+            range,
+            data,
+        });
+    }
+
+    /// Record copying data from the given slice into a buffer.
+    pub fn update_buffer(&mut self, buffer: BufferId, offset: buffer::Offset, data: &[u8]) {
+        self.commands.push(Command::UpdateBuffer {
+            buffer,
+            offset,
+            data: data.to_vec(),
+        });
+    }
+
+    /// Record clearing an image.
+    pub fn clear_image<T>(
+        &mut self,
+        image: ImageId,
+        layout: Layout,
+        value: ClearValue,
+        subresource_ranges: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<SubresourceRange>,
+    {
+        self.commands.push(Command::ClearImage {
+            image,
+            layout,
+            value: clear_bits(&value),
+            subresource_ranges: subresource_ranges
+                .into_iter()
+                .map(|r| r.borrow().clone())
+                .collect(),
+        });
+    }
+
+    /// Record clearing regions of the current subpass attachments.
+    pub fn clear_attachments<T, U>(&mut self, clears: T, rects: U)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<HalAttachmentClear>,
+        U: IntoIterator,
+        U::Item: Borrow<pso::ClearRect>,
+    {
+        self.commands.push(Command::ClearAttachments {
+            clears: clears
+                .into_iter()
+                .map(|c| AttachmentClear::from(*c.borrow()))
+                .collect(),
+            rects: rects.into_iter().map(|r| r.borrow().clone()).collect(),
+        });
+    }
+
+    /// Record resolving a multisampled image.
+    pub fn resolve_image<T>(
+        &mut self,
+        src: ImageId,
+        src_layout: Layout,
+        dst: ImageId,
+        dst_layout: Layout,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<ImageResolve>,
+    {
+        self.commands.push(Command::ResolveImage {
+            src,
+            src_layout,
+            dst,
+            dst_layout,
+            regions: regions.into_iter().map(|r| r.borrow().clone()).collect(),
+        });
+    }
+
+    /// Record blitting regions between images.
+    pub fn blit_image<T>(
+        &mut self,
+        src: ImageId,
+        src_layout: Layout,
+        dst: ImageId,
+        dst_layout: Layout,
+        filter: Filter,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<ImageBlit>,
+    {
+        self.commands.push(Command::BlitImage {
+            src,
+            src_layout,
+            dst,
+            dst_layout,
+            filter,
+            regions: regions.into_iter().map(|r| r.borrow().clone()).collect(),
+        });
+    }
+
+    /// Record binding an index buffer.
+    pub fn bind_index_buffer(
+        &mut self,
+        buffer: BufferId,
+        range: buffer::SubRange,
+        index_type: IndexType,
+    ) {
+        self.commands.push(Command::BindIndexBuffer {
+            buffer,
+            range,
+            index_type,
+        });
+    }
+
+    /// Record binding vertex buffers.
+    pub fn bind_vertex_buffers<I>(&mut self, first_binding: pso::BufferIndex, buffers: I)
+    where
+        I: IntoIterator<Item = (BufferId, buffer::SubRange)>,
+    {
+        self.commands.push(Command::BindVertexBuffers {
+            first_binding,
+            buffers: buffers.into_iter().collect(),
+        });
+    }
+
+    /// Record setting viewports.
+    pub fn set_viewports<T>(&mut self, first_viewport: u32, viewports: T)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<pso::Viewport>,
+    {
+        self.commands.push(Command::SetViewports {
+            first_viewport,
+            viewports: viewports.into_iter().map(|v| v.borrow().clone()).collect(),
+        });
+    }
+
+    /// Record setting scissor rectangles.
+    pub fn set_scissors<T>(&mut self, first_scissor: u32, rects: T)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<pso::Rect>,
+    {
+        self.commands.push(Command::SetScissors {
+            first_scissor,
+            rects: rects.into_iter().map(|r| *r.borrow()).collect(),
+        });
+    }
+
+    /// Record setting the stencil reference value.
+    pub fn set_stencil_reference(&mut self, faces: pso::Face, value: pso::StencilValue) {
+        self.commands
+            .push(Command::SetStencilReference { faces, value });
+    }
+
+    /// Record setting the stencil read mask.
+    pub fn set_stencil_read_mask(&mut self, faces: pso::Face, value: pso::StencilValue) {
+        self.commands
+            .push(Command::SetStencilReadMask { faces, value });
+    }
+
+    /// Record setting the stencil write mask.
+    pub fn set_stencil_write_mask(&mut self, faces: pso::Face, value: pso::StencilValue) {
+        self.commands
+            .push(Command::SetStencilWriteMask { faces, value });
+    }
+
+    /// Record setting the blend constant color.
+    pub fn set_blend_constants(&mut self, color: pso::ColorValue) {
+        self.commands.push(Command::SetBlendConstants(color));
+    }
+
+    /// Record setting the depth bounds.
+    pub fn set_depth_bounds(&mut self, bounds: Range<f32>) {
+        self.commands.push(Command::SetDepthBounds(bounds));
+    }
+
+    /// Record setting the line width.
+    pub fn set_line_width(&mut self, width: f32) {
+        self.commands.push(Command::SetLineWidth(width));
+    }
+
+    /// Record setting the depth bias.
+    pub fn set_depth_bias(&mut self, depth_bias: pso::DepthBias) {
+        self.commands.push(Command::SetDepthBias(depth_bias));
+    }
+
+    /// Record beginning a render pass.
+    pub fn begin_render_pass<T>(
+        &mut self,
+        render_pass: RenderPassId,
+        framebuffer: FramebufferId,
+        render_area: pso::Rect,
+        clear_values: T,
+        first_subpass: SubpassContents,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<ClearValue>,
+    {
+        self.commands.push(Command::BeginRenderPass {
+            render_pass,
+            framebuffer,
+            render_area,
+            clear_values: clear_values
+                .into_iter()
+                .map(|v| clear_bits(v.borrow()))
+                .collect(),
+            first_subpass,
+        });
+    }
+
+    /// Record moving to the next subpass.
+    pub fn next_subpass(&mut self, contents: SubpassContents) {
+        self.commands.push(Command::NextSubpass(contents));
+    }
+
+    /// Record ending the current render pass.
+    pub fn end_render_pass(&mut self) {
+        self.commands.push(Command::EndRenderPass);
+    }
+
+    /// Record binding a graphics pipeline.
+    pub fn bind_graphics_pipeline(&mut self, pipeline: GraphicsPipelineId) {
+        self.commands.push(Command::BindGraphicsPipeline(pipeline));
+    }
+
+    /// Record binding descriptor sets for graphics.
+    pub fn bind_graphics_descriptor_sets<I, J>(
+        &mut self,
+        layout: PipelineLayoutId,
+        first_set: usize,
+        sets: I,
+        offsets: J,
+    ) where
+        I: IntoIterator<Item = DescriptorSetId>,
+        J: IntoIterator,
+        J::Item: Borrow<DescriptorSetOffset>,
+    {
+        self.commands.push(Command::BindGraphicsDescriptorSets {
+            layout,
+            first_set,
+            sets: sets.into_iter().collect(),
+            offsets: offsets.into_iter().map(|o| *o.borrow()).collect(),
+        });
+    }
+
+    /// Record binding a compute pipeline.
+    pub fn bind_compute_pipeline(&mut self, pipeline: ComputePipelineId) {
+        self.commands.push(Command::BindComputePipeline(pipeline));
+    }
+
+    /// Record binding descriptor sets for compute.
+    pub fn bind_compute_descriptor_sets<I, J>(
+        &mut self,
+        layout: PipelineLayoutId,
+        first_set: usize,
+        sets: I,
+        offsets: J,
+    ) where
+        I: IntoIterator<Item = DescriptorSetId>,
+        J: IntoIterator,
+        J::Item: Borrow<DescriptorSetOffset>,
+    {
+        self.commands.push(Command::BindComputeDescriptorSets {
+            layout,
+            first_set,
+            sets: sets.into_iter().collect(),
+            offsets: offsets.into_iter().map(|o| *o.borrow()).collect(),
+        });
+    }
+
+    /// Record a compute dispatch.
+    pub fn dispatch(&mut self, count: WorkGroupCount) {
+        self.commands.push(Command::Dispatch(count));
+    }
+
+    /// Record an indirect compute dispatch.
+    pub fn dispatch_indirect(&mut self, buffer: BufferId, offset: buffer::Offset) {
+        self.commands
+            .push(Command::DispatchIndirect { buffer, offset });
+    }
+
+    /// Record copying regions between buffers.
+    pub fn copy_buffer<T>(&mut self, src: BufferId, dst: BufferId, regions: T)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<BufferCopy>,
+    {
+        self.commands.push(Command::CopyBuffer {
+            src,
+            dst,
+            regions: regions.into_iter().map(|r| *r.borrow()).collect(),
+        });
+    }
+
+    /// Record copying regions between images.
+    pub fn copy_image<T>(
+        &mut self,
+        src: ImageId,
+        src_layout: Layout,
+        dst: ImageId,
+        dst_layout: Layout,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<ImageCopy>,
+    {
+        self.commands.push(Command::CopyImage {
+            src,
+            src_layout,
+            dst,
+            dst_layout,
+            regions: regions.into_iter().map(|r| r.borrow().clone()).collect(),
+        });
+    }
+
+    /// Record copying regions from a buffer to an image.
+    pub fn copy_buffer_to_image<T>(
+        &mut self,
+        src: BufferId,
+        dst: ImageId,
+        dst_layout: Layout,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<BufferImageCopy>,
+    {
+        self.commands.push(Command::CopyBufferToImage {
+            src,
+            dst,
+            dst_layout,
+            regions: regions.into_iter().map(|r| r.borrow().clone()).collect(),
+        });
+    }
+
+    /// Record copying regions from an image to a buffer.
+    pub fn copy_image_to_buffer<T>(
+        &mut self,
+        src: ImageId,
+        src_layout: Layout,
+        dst: BufferId,
+        regions: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<BufferImageCopy>,
+    {
+        self.commands.push(Command::CopyImageToBuffer {
+            src,
+            src_layout,
+            dst,
+            regions: regions.into_iter().map(|r| r.borrow().clone()).collect(),
+        });
+    }
+
+    /// Record a draw.
+    pub fn draw(&mut self, vertices: Range<VertexCount>, instances: Range<InstanceCount>) {
+        self.commands.push(Command::Draw {
+            vertices,
+            instances,
+        });
+    }
+
+    /// Record an indexed draw.
+    pub fn draw_indexed(
+        &mut self,
+        indices: Range<IndexCount>,
+        base_vertex: VertexOffset,
+        instances: Range<InstanceCount>,
+    ) {
+        self.commands.push(Command::DrawIndexed {
+            indices,
+            base_vertex,
+            instances,
+        });
+    }
+
+    /// Record an indirect draw.
+    pub fn draw_indirect(
+        &mut self,
+        buffer: BufferId,
+        offset: buffer::Offset,
+        draw_count: DrawCount,
+        stride: u32,
+    ) {
+        self.commands.push(Command::DrawIndirect {
+            buffer,
+            offset,
+            draw_count,
+            stride,
+        });
+    }
+
+    /// Record an indexed indirect draw.
+    pub fn draw_indexed_indirect(
+        &mut self,
+        buffer: BufferId,
+        offset: buffer::Offset,
+        draw_count: DrawCount,
+        stride: u32,
+    ) {
+        self.commands.push(Command::DrawIndexedIndirect {
+            buffer,
+            offset,
+            draw_count,
+            stride,
+        });
+    }
+
+    /// Record signaling an event.
+    pub fn set_event(&mut self, event: EventId, stages: pso::PipelineStage) {
+        self.commands.push(Command::SetEvent { event, stages });
+    }
+
+    /// Record unsignaling an event.
+    pub fn reset_event(&mut self, event: EventId, stages: pso::PipelineStage) {
+        self.commands.push(Command::ResetEvent { event, stages });
+    }
+
+    /// Record waiting for events.
+    pub fn wait_events<I, J>(&mut self, events: I, stages: Range<pso::PipelineStage>, barriers: J)
+    where
+        I: IntoIterator<Item = EventId>,
+        J: IntoIterator,
+        J::Item: Borrow<Barrier>,
+    {
+        self.commands.push(Command::WaitEvents {
+            events: events.into_iter().collect(),
+            stages,
+            barriers: barriers.into_iter().map(|b| b.borrow().clone()).collect(),
+        });
+    }
+
+    /// Record beginning a query.
+    pub fn begin_query(&mut self, pool: QueryPoolId, id: query::Id, flags: query::ControlFlags) {
+        self.commands.push(Command::BeginQuery { pool, id, flags });
+    }
+
+    /// Record ending a query.
+    pub fn end_query(&mut self, pool: QueryPoolId, id: query::Id) {
+        self.commands.push(Command::EndQuery { pool, id });
+    }
+
+    /// Record resetting a range of queries.
+    pub fn reset_query_pool(&mut self, pool: QueryPoolId, queries: Range<query::Id>) {
+        self.commands
+            .push(Command::ResetQueryPool { pool, queries });
+    }
+
+    /// Record copying query results into a buffer.
+    pub fn copy_query_pool_results(
+        &mut self,
+        pool: QueryPoolId,
+        queries: Range<query::Id>,
+        buffer: BufferId,
+        offset: buffer::Offset,
+        stride: buffer::Offset,
+        flags: query::ResultFlags,
+    ) {
+        self.commands.push(Command::CopyQueryPoolResults {
+            pool,
+            queries,
+            buffer,
+            offset,
+            stride,
+            flags,
+        });
+    }
+
+    /// Record writing a timestamp.
+    pub fn write_timestamp(&mut self, stage: pso::PipelineStage, pool: QueryPoolId, id: query::Id) {
+        self.commands
+            .push(Command::WriteTimestamp { stage, pool, id });
+    }
+
+    /// Record pushing graphics constants.
+    pub fn push_graphics_constants(
+        &mut self,
+        layout: PipelineLayoutId,
+        stages: pso::ShaderStageFlags,
+        offset: u32,
+        constants: &[u32],
+    ) {
+        self.commands.push(Command::PushGraphicsConstants {
+            layout,
+            stages,
+            offset,
+            constants: constants.to_vec(),
+        });
+    }
+
+    /// Record pushing compute constants.
+    pub fn push_compute_constants(
+        &mut self,
+        layout: PipelineLayoutId,
+        offset: u32,
+        constants: &[u32],
+    ) {
+        self.commands.push(Command::PushComputeConstants {
+            layout,
+            offset,
+            constants: constants.to_vec(),
+        });
+    }
+
+    /// Record executing secondary command buffers.
+    pub fn execute_commands<I>(&mut self, cmd_buffers: I)
+    where
+        I: IntoIterator<Item = CommandBufferId>,
+    {
+        self.commands
+            .push(Command::ExecuteCommands(cmd_buffers.into_iter().collect()));
+    }
+
+    /// Record inserting a debug marker.
+    pub fn insert_debug_marker(&mut self, name: &str, color: u32) {
+        self.commands.push(Command::InsertDebugMarker {
+            name: name.to_string(),
+            color,
+        });
+    }
+
+    /// Record beginning a debug marker region.
+    pub fn begin_debug_marker(&mut self, name: &str, color: u32) {
+        self.commands.push(Command::BeginDebugMarker {
+            name: name.to_string(),
+            color,
+        });
+    }
+
+    /// Record ending a debug marker region.
+    pub fn end_debug_marker(&mut self) {
+        self.commands.push(Command::EndDebugMarker);
+    }
+
+    /// Replay the recorded commands onto a command buffer.
+    ///
+    /// # Safety
+    ///
+    /// The command buffer must be in the recording state, and the resolver must
+    /// provide every resource referenced by the list. The recorded commands
+    /// must be valid for the command buffer, as if they were recorded directly.
+    pub unsafe fn replay<B, C, R>(&self, cmd: &mut C, resolver: &R)
+    where
+        B: Backend,
+        C: CommandBuffer<B>,
+        R: Resolver<B>,
+    {
+        for command in &self.commands {
+            command.replay(cmd, resolver);
+        }
+    }
+}
+
+impl Command {
+    unsafe fn replay<B, C, R>(&self, cmd: &mut C, resolver: &R)
+    where
+        B: Backend,
+        C: CommandBuffer<B>,
+        R: Resolver<B>,
+    {
+        match *self {
+            Command::PipelineBarrier {
+                ref stages,
+                dependencies,
+                ref barriers,
+            } => cmd.pipeline_barrier(
+                stages.clone(),
+                dependencies,
+                barriers.iter().map(|b| b.resolve(resolver)),
+            ),
+            Command::FillBuffer {
+                buffer,
+                ref range,
+                data,
+            } => cmd.fill_buffer(resolver.buffer(buffer), range.clone(), data),
+            Command::UpdateBuffer {
+                buffer,
+                offset,
+                ref data,
+            } => cmd.update_buffer(resolver.buffer(buffer), offset, data),
+            Command::ClearImage {
+                image,
+                layout,
+                value,
+                ref subresource_ranges,
+            } => cmd.clear_image(
+                resolver.image(image),
+                layout,
+                clear_value(value),
+                subresource_ranges,
+            ),
+            Command::ClearAttachments {
+                ref clears,
+                ref rects,
+            } => cmd.clear_attachments(clears.iter().map(|&c| HalAttachmentClear::from(c)), rects),
+            Command::ResolveImage {
+                src,
+                src_layout,
+                dst,
+                dst_layout,
+                ref regions,
+            } => cmd.resolve_image(
+                resolver.image(src),
+                src_layout,
+                resolver.image(dst),
+                dst_layout,
+                regions,
+            ),
+            Command::BlitImage {
+                src,
+                src_layout,
+                dst,
+                dst_layout,
+                filter,
+                ref regions,
+            } => cmd.blit_image(
+                resolver.image(src),
+                src_layout,
+                resolver.image(dst),
+                dst_layout,
+                filter,
+                regions,
+            ),
+            Command::BindIndexBuffer {
+                buffer,
+                ref range,
+                index_type,
+            } => cmd.bind_index_buffer(buffer::IndexBufferView {
+                buffer: resolver.buffer(buffer),
+                range: range.clone(),
+                index_type,
+            }),
+            Command::BindVertexBuffers {
+                first_binding,
+                ref buffers,
+            } => cmd.bind_vertex_buffers(
+                first_binding,
+                buffers
+                    .iter()
+                    .map(|&(id, ref range)| (resolver.buffer(id), range.clone())),
+            ),
+            Command::SetViewports {
+                first_viewport,
+                ref viewports,
+            } => cmd.set_viewports(first_viewport, viewports),
+            Command::SetScissors {
+                first_scissor,
+                ref rects,
+            } => cmd.set_scissors(first_scissor, rects),
+            Command::SetStencilReference { faces, value } => {
+                cmd.set_stencil_reference(faces, value)
+            }
+            Command::SetStencilReadMask { faces, value } => cmd.set_stencil_read_mask(faces, value),
+            Command::SetStencilWriteMask { faces, value } => {
+                cmd.set_stencil_write_mask(faces, value)
+            }
+            Command::SetBlendConstants(color) => cmd.set_blend_constants(color),
+            Command::SetDepthBounds(ref bounds) => cmd.set_depth_bounds(bounds.clone()),
+            Command::SetLineWidth(width) => cmd.set_line_width(width),
+            Command::SetDepthBias(depth_bias) => cmd.set_depth_bias(depth_bias),
+            Command::BeginRenderPass {
+                render_pass,
+                framebuffer,
+                render_area,
+                ref clear_values,
+                first_subpass,
+            } => cmd.begin_render_pass(
+                resolver.render_pass(render_pass),
+                resolver.framebuffer(framebuffer),
+                render_area,
+                clear_values.iter().map(|&bits| clear_value(bits)),
+                first_subpass,
+            ),
+            Command::NextSubpass(contents) => cmd.next_subpass(contents),
+            Command::EndRenderPass => cmd.end_render_pass(),
+            Command::BindGraphicsPipeline(pipeline) => {
+                cmd.bind_graphics_pipeline(resolver.graphics_pipeline(pipeline))
+            }
+            Command::BindGraphicsDescriptorSets {
+                layout,
+                first_set,
+                ref sets,
+                ref offsets,
+            } => cmd.bind_graphics_descriptor_sets(
+                resolver.pipeline_layout(layout),
+                first_set,
+                sets.iter().map(|&id| resolver.descriptor_set(id)),
+                offsets,
+            ),
+            Command::BindComputePipeline(pipeline) => {
+                cmd.bind_compute_pipeline(resolver.compute_pipeline(pipeline))
+            }
+            Command::BindComputeDescriptorSets {
+                layout,
+                first_set,
+                ref sets,
+                ref offsets,
+            } => cmd.bind_compute_descriptor_sets(
+                resolver.pipeline_layout(layout),
+                first_set,
+                sets.iter().map(|&id| resolver.descriptor_set(id)),
+                offsets,
+            ),
+            Command::Dispatch(count) => cmd.dispatch(count),
+            Command::DispatchIndirect { buffer, offset } => {
+                cmd.dispatch_indirect(resolver.buffer(buffer), offset)
+            }
+            Command::CopyBuffer {
+                src,
+                dst,
+                ref regions,
+            } => cmd.copy_buffer(resolver.buffer(src), resolver.buffer(dst), regions),
+            Command::CopyImage {
+                src,
+                src_layout,
+                dst,
+                dst_layout,
+                ref regions,
+            } => cmd.copy_image(
+                resolver.image(src),
+                src_layout,
+                resolver.image(dst),
+                dst_layout,
+                regions,
+            ),
+            Command::CopyBufferToImage {
+                src,
+                dst,
+                dst_layout,
+                ref regions,
+            } => cmd.copy_buffer_to_image(
+                resolver.buffer(src),
+                resolver.image(dst),
+                dst_layout,
+                regions,
+            ),
+            Command::CopyImageToBuffer {
+                src,
+                src_layout,
+                dst,
+                ref regions,
+            } => cmd.copy_image_to_buffer(
+                resolver.image(src),
+                src_layout,
+                resolver.buffer(dst),
+                regions,
+            ),
+            Command::Draw {
+                ref vertices,
+                ref instances,
+            } => cmd.draw(vertices.clone(), instances.clone()),
+            Command::DrawIndexed {
+                ref indices,
+                base_vertex,
+                ref instances,
+            } => cmd.draw_indexed(indices.clone(), base_vertex, instances.clone()),
+            Command::DrawIndirect {
+                buffer,
+                offset,
+                draw_count,
+                stride,
+            } => cmd.draw_indirect(resolver.buffer(buffer), offset, draw_count, stride),
+            Command::DrawIndexedIndirect {
+                buffer,
+                offset,
+                draw_count,
+                stride,
+            } => cmd.draw_indexed_indirect(resolver.buffer(buffer), offset, draw_count, stride),
+            Command::SetEvent { event, stages } => cmd.set_event(resolver.event(event), stages),
+            Command::ResetEvent { event, stages } => cmd.reset_event(resolver.event(event), stages),
+            Command::WaitEvents {
+                ref events,
+                ref stages,
+                ref barriers,
+            } => cmd.wait_events(
+                events.iter().map(|&id| resolver.event(id)),
+                stages.clone(),
+                barriers.iter().map(|b| b.resolve(resolver)),
+            ),
+            Command::BeginQuery { pool, id, flags } => cmd.begin_query(
+                query::Query {
+                    pool: resolver.query_pool(pool),
+                    id,
+                },
+                flags,
+            ),
+            Command::EndQuery { pool, id } => cmd.end_query(query::Query {
+                pool: resolver.query_pool(pool),
+                id,
+            }),
+            Command::ResetQueryPool { pool, ref queries } => {
+                cmd.reset_query_pool(resolver.query_pool(pool), queries.clone())
+            }
+            Command::CopyQueryPoolResults {
+                pool,
+                ref queries,
+                buffer,
+                offset,
+                stride,
+                flags,
+            } => cmd.copy_query_pool_results(
+                resolver.query_pool(pool),
+                queries.clone(),
+                resolver.buffer(buffer),
+                offset,
+                stride,
+                flags,
+            ),
+            Command::WriteTimestamp { stage, pool, id } => cmd.write_timestamp(
+                stage,
+                query::Query {
+                    pool: resolver.query_pool(pool),
+                    id,
+                },
+            ),
+            Command::PushGraphicsConstants {
+                layout,
+                stages,
+                offset,
+                ref constants,
+            } => cmd.push_graphics_constants(
+                resolver.pipeline_layout(layout),
+                stages,
+                offset,
+                constants,
+            ),
+            Command::PushComputeConstants {
+                layout,
+                offset,
+                ref constants,
+            } => cmd.push_compute_constants(resolver.pipeline_layout(layout), offset, constants),
+            Command::ExecuteCommands(ref cmd_buffers) => {
+                cmd.execute_commands(cmd_buffers.iter().map(|&id| resolver.command_buffer(id)))
+            }
+            Command::InsertDebugMarker { ref name, color } => cmd.insert_debug_marker(name, color),
+            Command::BeginDebugMarker { ref name, color } => cmd.begin_debug_marker(name, color),
+            Command::EndDebugMarker => cmd.end_debug_marker(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::Aspects;
+
+    #[test]
+    fn test_record() {
+        let mut list = CommandList::new();
+        list.pipeline_barrier(
+            pso::PipelineStage::TOP_OF_PIPE .. pso::PipelineStage::TRANSFER,
+            Dependencies::empty(),
+            [Barrier::Image {
+                states: (image::Access::empty(), Layout::Undefined)
+                    ..(image::Access::TRANSFER_WRITE, Layout::TransferDstOptimal),
+                target: ImageId(0),
+                range: SubresourceRange {
+                    aspects: Aspects::COLOR,
+                    levels: 0 .. 1,
+                    layers: 0 .. 1,
+                },
+                families: None,
+            }],
+        );
+        list.copy_buffer(
+            BufferId(1),
+            BufferId(2),
+            [BufferCopy {
+                src: 0,
+                dst: 16,
+                size: 64,
+            }],
+        );
+        list.bind_graphics_descriptor_sets(PipelineLayoutId(0), 1, vec![DescriptorSetId(3)], [8]);
+        list.draw(0 .. 3, 0 .. 1);
+
+        assert_eq!(list.commands.len(), 4);
+        assert_eq!(
+            list.commands[1],
+            Command::CopyBuffer {
+                src: BufferId(1),
+                dst: BufferId(2),
+                regions: vec![BufferCopy {
+                    src: 0,
+                    dst: 16,
+                    size: 64,
+                }],
+            }
+        );
+        assert_eq!(
+            list.commands[2],
+            Command::BindGraphicsDescriptorSets {
+                layout: PipelineLayoutId(0),
+                first_set: 1,
+                sets: vec![DescriptorSetId(3)],
+                offsets: vec![8],
+            }
+        );
+        assert_eq!(list.clone(), list);
+    }
+
+    #[test]
+    fn test_clear_values() {
+        let mut list = CommandList::new();
+        let color = ClearValue {
+            color: ClearColor {
+                float32: [0.25, 0.5, 1.0, 1.0],
+            },
+        };
+        list.begin_render_pass(
+            RenderPassId(0),
+            FramebufferId(0),
+            pso::Rect {
+                x: 0,
+                y: 0,
+                w: 4,
+                h: 4,
+            },
+            [color],
+            SubpassContents::Inline,
+        );
+        list.clear_attachments(
+            [HalAttachmentClear::Color {
+                index: 0,
+                value: ClearColor {
+                    sint32: [-1, 0, 1, 2],
+                },
+            }],
+            Vec::<pso::ClearRect>::new(),
+        );
+
+        match list.commands[0] {
+            Command::BeginRenderPass {
+                ref clear_values, ..
+            } => {
+                let value = clear_value(clear_values[0]);
+                assert_eq!(unsafe { value.color.float32 }, [0.25, 0.5, 1.0, 1.0]);
+            }
+            _ => panic!("unexpected command"),
+        }
+        match list.commands[1] {
+            Command::ClearAttachments { ref clears, .. } => {
+                match HalAttachmentClear::from(clears[0]) {
+                    HalAttachmentClear::Color { value, .. } => {
+                        assert_eq!(unsafe { value.sint32 }, [-1, 0, 1, 2])
+                    }
+                    _ => panic!("unexpected clear"),
+                }
+            }
+            _ => panic!("unexpected command"),
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_roundtrip() {
+        let mut list = CommandList::new();
+        list.pipeline_barrier(
+            pso::PipelineStage::TRANSFER .. pso::PipelineStage::FRAGMENT_SHADER,
+            Dependencies::BY_REGION,
+            [Barrier::Buffer {
+                states: buffer::Access::TRANSFER_WRITE .. buffer::Access::SHADER_READ,
+                target: BufferId(2),
+                range: buffer::SubRange {
+                    offset: 4,
+                    size: Some(12),
+                },
+                families: Some(QueueFamilyId(0) .. QueueFamilyId(1)),
+            }],
+        );
+        list.begin_render_pass(
+            RenderPassId(1),
+            FramebufferId(0),
+            pso::Rect {
+                x: 0,
+                y: 0,
+                w: 8,
+                h: 8,
+            },
+            [ClearValue {
+                color: ClearColor {
+                    float32: [0.0, 0.5, 1.0, 1.0],
+                },
+            }],
+            SubpassContents::Inline,
+        );
+        list.push_graphics_constants(
+            PipelineLayoutId(0),
+            pso::ShaderStageFlags::FRAGMENT,
+            4,
+            &[1, 2, 3],
+        );
+        list.draw_indexed(0 .. 6, -2, 0 .. 1);
+        list.end_render_pass();
+        list.execute_commands(vec![CommandBufferId(5)]);
+        list.insert_debug_marker("replay", 0xFF00_00FF);
+
+        let text = serde_json::to_string(&list).unwrap();
+        let parsed = serde_json::from_str::<CommandList>(&text).unwrap();
+        assert_eq!(parsed, list, "{}", text);
+    }
+}
@@ -14,6 +14,8 @@
 // TODO: Document pipelines and subpasses better.
 
 mod clear;
+mod indirect;
+pub mod list;
 mod structs;
 
 use std::any::Any;
@@ -35,6 +37,7 @@ use crate::{
 };
 
 pub use self::clear::*;
+pub use self::indirect::*;
 pub use self::structs::*;
 
 /// Offset for dynamic descriptors.
@@ -67,7 +70,8 @@ pub enum Level {
 }
 
 /// Specifies how commands for the following renderpasses will be recorded.
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum SubpassContents {
     /// Contents of the subpass will be inline in the command buffer,
     /// NOT in secondary command buffers.
@@ -5,7 +5,7 @@ use std::ops::Range;
 /// Specifies a source region and a destination
 /// region in a buffer for copying.  All values
 /// are in units of bytes.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BufferCopy {
     /// Buffer region source offset.
@@ -18,7 +18,7 @@ pub struct BufferCopy {
 
 /// Bundles together all the parameters needed to copy data from one `Image`
 /// to another.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ImageCopy {
     /// The image subresource to copy from.
@@ -35,7 +35,7 @@ pub struct ImageCopy {
 
 /// Bundles together all the parameters needed to copy a buffer
 /// to an image or vice-versa.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BufferImageCopy {
     /// Buffer offset in bytes.
@@ -55,7 +55,7 @@ pub struct BufferImageCopy {
 /// Parameters for an image resolve operation,
 /// where a multi-sampled image is copied into a single-sampled
 /// image.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ImageResolve {
     /// Source image and layers.
@@ -72,7 +72,7 @@ pub struct ImageResolve {
 
 /// Parameters for an image blit operation, where a portion of one image
 /// is copied into another, possibly with scaling and filtering.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ImageBlit {
     /// Source image and layers.
@@ -9,13 +9,15 @@
 //! for instance `R32_G32_B32_A32`.  The `ChannelType` specifies how the
 //! components are interpreted, for instance `Sfloat` or `Sint`.
 
+use crate::UnknownName;
+use std::{fmt, str::FromStr};
+
 bitflags!(
     /// Bitflags which describe what properties of an image
     /// a format specifies or does not specify.  For example,
     /// the `Rgba8Unorm` format only specifies a `COLOR` aspect,
     /// while `D32SfloatS8Uint` specifies both a depth and stencil
     /// aspect but no color.
-    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct Aspects: u8 {
         /// Color aspect.
         const COLOR = 0x1;
@@ -26,6 +28,12 @@ bitflags!(
     }
 );
 
+named_flags!(Aspects: u8 {
+    COLOR,
+    DEPTH,
+    STENCIL,
+});
+
 /// Description of a format.
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct FormatDesc {
@@ -143,7 +151,6 @@ pub struct Properties {
 bitflags!(
     /// Image feature flags.
     #[derive(Default)]
-    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct ImageFeature: u32 {
         /// Image view can be sampled.
         const SAMPLED = 0x1;
@@ -168,10 +175,21 @@ bitflags!(
     }
 );
 
+named_flags!(ImageFeature: u32 {
+    SAMPLED,
+    STORAGE,
+    STORAGE_ATOMIC,
+    COLOR_ATTACHMENT,
+    COLOR_ATTACHMENT_BLEND,
+    DEPTH_STENCIL_ATTACHMENT,
+    BLIT_SRC,
+    BLIT_DST,
+    SAMPLED_LINEAR,
+});
+
 bitflags!(
     /// Buffer feature flags.
     #[derive(Default)]
-    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct BufferFeature: u32 {
         /// Buffer view can be used as uniform texel buffer.
         const UNIFORM_TEXEL = 0x8;
@@ -184,6 +202,13 @@ bitflags!(
     }
 );
 
+named_flags!(BufferFeature: u32 {
+    UNIFORM_TEXEL,
+    STORAGE_TEXEL,
+    STORAGE_TEXEL_ATOMIC,
+    VERTEX,
+});
+
 /// Type of a surface channel. This is how we interpret the
 /// storage allocated with `SurfaceType`.
 #[repr(u8)]
@@ -325,6 +350,19 @@ surface_types! {
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BaseFormat(pub SurfaceType, pub ChannelType);
 
+/// Class of formats that can be reinterpreted as one another,
+/// for example when creating an image view with a different format.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CompatibilityClass {
+    /// Uncompressed color formats with the given number of bits per texel.
+    Bits(u16),
+    /// Compressed formats sharing the same block encoding.
+    Block(SurfaceType),
+    /// Depth and/or stencil formats, which are only compatible with themselves.
+    DepthStencil(SurfaceType),
+}
+
 /// Conversion trait into `Format`;
 pub trait AsFormat {
     /// Associated format.
@@ -365,6 +403,18 @@ macro_rules! formats {
             $(BaseFormat(SurfaceType::$surface_tail, ChannelType::$channel_tail), )*
         ];
 
+        /// List of all the formats, excluding `Undefined`, in ascending order.
+        const ALL_FORMATS: [Format; NUM_FORMATS-1] = [
+              Format::$name,
+            $(Format::$name_tail, )*
+        ];
+
+        /// Names of all the formats, matching `ALL_FORMATS`.
+        const FORMAT_NAMES: [&str; NUM_FORMATS-1] = [
+              stringify!($name),
+            $(stringify!($name_tail), )*
+        ];
+
             /// A struct equivalent to the matching `Format` enum member, which allows
             /// an API to be strongly typed on particular formats.
             #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -607,18 +657,243 @@ impl Format {
     pub fn is_stencil(self) -> bool {
         self.surface_desc().aspects.contains(Aspects::STENCIL)
     }
+
+    /// Get the name of the format, matching the enum variant.
+    pub fn name(self) -> &'static str {
+        FORMAT_NAMES[self as usize - 1]
+    }
+
+    /// Iterate over all the formats.
+    pub fn all() -> impl Iterator<Item = Format> {
+        ALL_FORMATS.iter().cloned()
+    }
+
+    /// Find the format matching a base format.
+    ///
+    /// Returns `None` if there is no such combination of the surface and channel types.
+    pub fn from_base_format(base: BaseFormat) -> Option<Format> {
+        Self::all().find(|format| format.base_format() == base)
+    }
+
+    /// Get the compatibility class of the format.
+    pub fn compatibility_class(self) -> CompatibilityClass {
+        let BaseFormat(surface, _) = self.base_format();
+        let desc = surface.desc();
+        if desc.is_compressed() {
+            CompatibilityClass::Block(surface)
+        } else if desc.aspects.contains(Aspects::COLOR) {
+            CompatibilityClass::Bits(desc.bits)
+        } else {
+            CompatibilityClass::DepthStencil(surface)
+        }
+    }
+
+    /// Returns if a view with the `other` format can be created for an image
+    /// of this format. Requires `ViewCapabilities::MUTABLE_FORMAT` on the image
+    /// unless the formats are the same.
+    pub fn is_view_compatible(self, other: Format) -> bool {
+        self == other || self.compatibility_class() == other.compatibility_class()
+    }
+
+    /// Returns if the texels of the image of this format can be copied
+    /// to or from the image of the `other` format.
+    ///
+    /// Compressed formats are compatible with uncompressed ones
+    /// if the size of a block matches the size of a texel.
+    pub fn is_copy_compatible(self, other: Format) -> bool {
+        match (self.compatibility_class(), other.compatibility_class()) {
+            (CompatibilityClass::DepthStencil(_), _) | (_, CompatibilityClass::DepthStencil(_)) => {
+                self == other
+            }
+            _ => self.surface_desc().bits == other.surface_desc().bits,
+        }
+    }
+
+    /// Get the sRGB encoded format with the same layout.
+    ///
+    /// Returns `None` if the format has no sRGB equivalent.
+    pub fn srgb_equivalent(self) -> Option<Format> {
+        match self.base_format() {
+            BaseFormat(_, ChannelType::Srgb) => Some(self),
+            BaseFormat(surface, ChannelType::Unorm) => {
+                Self::from_base_format(BaseFormat(surface, ChannelType::Srgb))
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the linear (`Unorm`) format with the same layout.
+    ///
+    /// Returns `None` if the format has no linear equivalent.
+    pub fn linear_equivalent(self) -> Option<Format> {
+        match self.base_format() {
+            BaseFormat(_, ChannelType::Unorm) => Some(self),
+            BaseFormat(surface, ChannelType::Srgb) => {
+                Self::from_base_format(BaseFormat(surface, ChannelType::Unorm))
+            }
+            _ => None,
+        }
+    }
 }
 
-// Common vertex attribute formats
-impl AsFormat for f32 {
-    const SELF: Format = Format::R32Sfloat;
+impl fmt::Display for Format {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str(self.name())
+    }
 }
-impl AsFormat for [f32; 2] {
-    const SELF: Format = Format::Rg32Sfloat;
+
+impl FromStr for Format {
+    type Err = UnknownName;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FORMAT_NAMES
+            .iter()
+            .position(|&name| name == s)
+            .map(|index| ALL_FORMATS[index])
+            .ok_or_else(|| UnknownName(s.to_string()))
+    }
 }
-impl AsFormat for [f32; 3] {
-    const SELF: Format = Format::Rgb32Sfloat;
+
+/// Unsigned normalized vertex data, read as floating point values in `[0, 1]` by shaders.
+///
+/// For example, `Unorm<[u8; 4]>` corresponds to `Format::Rgba8Unorm`.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Unorm<T>(pub T);
+
+/// Signed normalized vertex data, read as floating point values in `[-1, 1]` by shaders.
+///
+/// For example, `Snorm<[i16; 2]>` corresponds to `Format::Rg16Snorm`.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Snorm<T>(pub T);
+
+macro_rules! impl_as_format {
+    ($( $ty:ty = $format:ident, )*) => {
+        $(
+            impl AsFormat for $ty {
+                const SELF: Format = Format::$format;
+            }
+        )*
+    };
 }
-impl AsFormat for [f32; 4] {
-    const SELF: Format = Format::Rgba32Sfloat;
+
+// Common vertex attribute formats
+impl_as_format! {
+    f32 = R32Sfloat,
+    [f32; 2] = Rg32Sfloat,
+    [f32; 3] = Rgb32Sfloat,
+    [f32; 4] = Rgba32Sfloat,
+    f64 = R64Sfloat,
+    [f64; 2] = Rg64Sfloat,
+    [f64; 3] = Rgb64Sfloat,
+    [f64; 4] = Rgba64Sfloat,
+    u32 = R32Uint,
+    [u32; 2] = Rg32Uint,
+    [u32; 3] = Rgb32Uint,
+    [u32; 4] = Rgba32Uint,
+    i32 = R32Sint,
+    [i32; 2] = Rg32Sint,
+    [i32; 3] = Rgb32Sint,
+    [i32; 4] = Rgba32Sint,
+    u16 = R16Uint,
+    [u16; 2] = Rg16Uint,
+    [u16; 3] = Rgb16Uint,
+    [u16; 4] = Rgba16Uint,
+    i16 = R16Sint,
+    [i16; 2] = Rg16Sint,
+    [i16; 3] = Rgb16Sint,
+    [i16; 4] = Rgba16Sint,
+    u8 = R8Uint,
+    [u8; 2] = Rg8Uint,
+    [u8; 3] = Rgb8Uint,
+    [u8; 4] = Rgba8Uint,
+    i8 = R8Sint,
+    [i8; 2] = Rg8Sint,
+    [i8; 3] = Rgb8Sint,
+    [i8; 4] = Rgba8Sint,
+    Unorm<u8> = R8Unorm,
+    Unorm<[u8; 2]> = Rg8Unorm,
+    Unorm<[u8; 3]> = Rgb8Unorm,
+    Unorm<[u8; 4]> = Rgba8Unorm,
+    Unorm<u16> = R16Unorm,
+    Unorm<[u16; 2]> = Rg16Unorm,
+    Unorm<[u16; 3]> = Rgb16Unorm,
+    Unorm<[u16; 4]> = Rgba16Unorm,
+    Snorm<i8> = R8Snorm,
+    Snorm<[i8; 2]> = Rg8Snorm,
+    Snorm<[i8; 3]> = Rgb8Snorm,
+    Snorm<[i8; 4]> = Rgba8Snorm,
+    Snorm<i16> = R16Snorm,
+    Snorm<[i16; 2]> = Rg16Snorm,
+    Snorm<[i16; 3]> = Rgb16Snorm,
+    Snorm<[i16; 4]> = Rgba16Snorm,
+}
+
+#[cfg(feature = "mint")]
+impl_as_format! {
+    mint::Vector2<f32> = Rg32Sfloat,
+    mint::Vector3<f32> = Rgb32Sfloat,
+    mint::Vector4<f32> = Rgba32Sfloat,
+    mint::Point2<f32> = Rg32Sfloat,
+    mint::Point3<f32> = Rgb32Sfloat,
+    mint::Quaternion<f32> = Rgba32Sfloat,
+    mint::Vector2<i32> = Rg32Sint,
+    mint::Vector3<i32> = Rgb32Sint,
+    mint::Vector4<i32> = Rgba32Sint,
+    mint::Vector2<u32> = Rg32Uint,
+    mint::Vector3<u32> = Rgb32Uint,
+    mint::Vector4<u32> = Rgba32Uint,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_formats() {
+        assert_eq!(Format::all().count(), NUM_FORMATS - 1);
+        for (i, format) in Format::all().enumerate() {
+            assert_eq!(format as usize, i + 1);
+            assert_eq!(Format::from_base_format(format.base_format()), Some(format));
+        }
+    }
+
+    #[test]
+    fn test_names() {
+        for format in Format::all() {
+            assert_eq!(format.to_string().parse::<Format>(), Ok(format));
+        }
+        assert_eq!("Rgba8Srgb".parse::<Format>(), Ok(Format::Rgba8Srgb));
+        assert!("Rgba8".parse::<Format>().is_err());
+    }
+
+    #[test]
+    fn test_view_compatibility() {
+        assert!(Format::Rgba8Unorm.is_view_compatible(Format::R32Sfloat));
+        assert!(Format::Rgba8Srgb.is_view_compatible(Format::Bgra8Unorm));
+        assert!(!Format::Rgba8Unorm.is_view_compatible(Format::Rg8Unorm));
+        assert!(!Format::Bc1RgbUnorm.is_view_compatible(Format::Bc1RgbaUnorm));
+        assert!(Format::Bc7Unorm.is_view_compatible(Format::Bc7Srgb));
+        assert!(!Format::D32Sfloat.is_view_compatible(Format::R32Sfloat));
+        assert!(Format::D24UnormS8Uint.is_view_compatible(Format::D24UnormS8Uint));
+    }
+
+    #[test]
+    fn test_copy_compatibility() {
+        assert!(Format::Bc1RgbUnorm.is_copy_compatible(Format::Rg32Uint));
+        assert!(Format::Bc7Srgb.is_copy_compatible(Format::Rgba32Sfloat));
+        assert!(!Format::Bc7Srgb.is_copy_compatible(Format::Rgba8Unorm));
+        assert!(!Format::D32Sfloat.is_copy_compatible(Format::R32Sfloat));
+    }
+
+    #[test]
+    fn test_srgb_equivalents() {
+        assert_eq!(Format::Rgba8Unorm.srgb_equivalent(), Some(Format::Rgba8Srgb));
+        assert_eq!(Format::Bc3Srgb.linear_equivalent(), Some(Format::Bc3Unorm));
+        assert_eq!(Format::Rgba8Srgb.srgb_equivalent(), Some(Format::Rgba8Srgb));
+        assert_eq!(Format::R16Unorm.srgb_equivalent(), None);
+        assert_eq!(Format::Rgba32Sfloat.linear_equivalent(), None);
+    }
 }
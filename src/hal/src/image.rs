@@ -385,7 +385,6 @@ pub enum ViewKind {
 
 bitflags!(
     /// Capabilities to create views into an image.
-    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct ViewCapabilities: u32 {
         /// Support creation of views with different formats.
         const MUTABLE_FORMAT = 0x0000_0008;
@@ -396,11 +395,16 @@ bitflags!(
     }
 );
 
+named_flags!(ViewCapabilities: u32 {
+    MUTABLE_FORMAT,
+    KIND_CUBE,
+    KIND_2D_ARRAY,
+});
+
 bitflags!(
     /// TODO: Find out if TRANSIENT_ATTACHMENT + INPUT_ATTACHMENT
     /// are applicable on backends other than Vulkan. --AP
     /// Image usage flags
-    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct Usage: u32 {
         /// The image is used as a transfer source.
         const TRANSFER_SRC = 0x1;
@@ -422,6 +426,17 @@ bitflags!(
     }
 );
 
+named_flags!(Usage: u32 {
+    TRANSFER_SRC,
+    TRANSFER_DST,
+    SAMPLED,
+    STORAGE,
+    COLOR_ATTACHMENT,
+    DEPTH_STENCIL_ATTACHMENT,
+    TRANSIENT_ATTACHMENT,
+    INPUT_ATTACHMENT,
+});
+
 impl Usage {
     /// Returns true if this image can be used in transfer operations.
     pub fn can_transfer(&self) -> bool {
@@ -592,7 +607,6 @@ pub enum Layout {
 
 bitflags!(
     /// Bitflags to describe how memory in an image or buffer can be accessed.
-    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct Access: u32 {
         /// Read access to an input attachment from within a fragment shader.
         const INPUT_ATTACHMENT_READ = 0x10;
@@ -626,6 +640,22 @@ bitflags!(
     }
 );
 
+named_flags!(Access: u32 {
+    INPUT_ATTACHMENT_READ,
+    SHADER_READ,
+    SHADER_WRITE,
+    COLOR_ATTACHMENT_READ,
+    COLOR_ATTACHMENT_WRITE,
+    DEPTH_STENCIL_ATTACHMENT_READ,
+    DEPTH_STENCIL_ATTACHMENT_WRITE,
+    TRANSFER_READ,
+    TRANSFER_WRITE,
+    HOST_READ,
+    HOST_WRITE,
+    MEMORY_READ,
+    MEMORY_WRITE,
+});
+
 /// Image state, combining access methods and the image's layout.
 pub type State = (Access, Layout);
 
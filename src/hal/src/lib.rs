@@ -50,6 +50,9 @@ use std::any::Any;
 use std::fmt;
 use std::hash::Hash;
 
+#[macro_use]
+mod names;
+
 pub mod adapter;
 pub mod buffer;
 pub mod command;
@@ -100,7 +103,6 @@ bitflags! {
     ///
     /// Can be obtained from a [physical device][adapter::PhysicalDevice] by calling
     /// [`features`][adapter::PhysicalDevice::features].
-    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct Features: u128 {
         /// Bit mask of Vulkan Core features.
         const CORE_MASK = 0xFFFF_FFFF_FFFF_FFFF;
@@ -243,15 +245,82 @@ bitflags! {
     }
 }
 
+named_flags!(Features: u128 {
+    ROBUST_BUFFER_ACCESS,
+    FULL_DRAW_INDEX_U32,
+    IMAGE_CUBE_ARRAY,
+    INDEPENDENT_BLENDING,
+    GEOMETRY_SHADER,
+    TESSELLATION_SHADER,
+    SAMPLE_RATE_SHADING,
+    DUAL_SRC_BLENDING,
+    LOGIC_OP,
+    MULTI_DRAW_INDIRECT,
+    DRAW_INDIRECT_FIRST_INSTANCE,
+    DEPTH_CLAMP,
+    DEPTH_BIAS_CLAMP,
+    NON_FILL_POLYGON_MODE,
+    DEPTH_BOUNDS,
+    LINE_WIDTH,
+    POINT_SIZE,
+    ALPHA_TO_ONE,
+    MULTI_VIEWPORTS,
+    SAMPLER_ANISOTROPY,
+    FORMAT_ETC2,
+    FORMAT_ASTC_LDR,
+    FORMAT_BC,
+    PRECISE_OCCLUSION_QUERY,
+    PIPELINE_STATISTICS_QUERY,
+    VERTEX_STORES_AND_ATOMICS,
+    FRAGMENT_STORES_AND_ATOMICS,
+    SHADER_TESSELLATION_AND_GEOMETRY_POINT_SIZE,
+    SHADER_IMAGE_GATHER_EXTENDED,
+    SHADER_STORAGE_IMAGE_EXTENDED_FORMATS,
+    SHADER_STORAGE_IMAGE_MULTISAMPLE,
+    SHADER_STORAGE_IMAGE_READ_WITHOUT_FORMAT,
+    SHADER_STORAGE_IMAGE_WRITE_WITHOUT_FORMAT,
+    SHADER_UNIFORM_BUFFER_ARRAY_DYNAMIC_INDEXING,
+    SHADER_SAMPLED_IMAGE_ARRAY_DYNAMIC_INDEXING,
+    SHADER_STORAGE_BUFFER_ARRAY_DYNAMIC_INDEXING,
+    SHADER_STORAGE_IMAGE_ARRAY_DYNAMIC_INDEXING,
+    SHADER_CLIP_DISTANCE,
+    SHADER_CULL_DISTANCE,
+    SHADER_FLOAT64,
+    SHADER_INT64,
+    SHADER_INT16,
+    SHADER_RESOURCE_RESIDENCY,
+    SHADER_RESOURCE_MIN_LOD,
+    SPARSE_BINDING,
+    SPARSE_RESIDENCY_BUFFER,
+    SPARSE_RESIDENCY_IMAGE_2D,
+    SPARSE_RESIDENCY_IMAGE_3D,
+    SPARSE_RESIDENCY_2_SAMPLES,
+    SPARSE_RESIDENCY_4_SAMPLES,
+    SPARSE_RESIDENCY_8_SAMPLES,
+    SPARSE_RESIDENCY_16_SAMPLES,
+    SPARSE_RESIDENCY_ALIASED,
+    VARIABLE_MULTISAMPLE_RATE,
+    INHERITED_QUERIES,
+    SAMPLER_MIRROR_CLAMP_EDGE,
+    TRIANGLE_FAN,
+    SEPARATE_STENCIL_REF_VALUES,
+    INSTANCE_RATE,
+    SAMPLER_MIP_LOD_BIAS,
+    NDC_Y_UP,
+});
+
 bitflags! {
     /// Features that the device supports natively, but is able to emulate.
-    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct Hints: u32 {
         /// Support indexed, instanced drawing with base vertex and instance.
         const BASE_VERTEX_INSTANCE_DRAWING = 0x0001;
     }
 }
 
+named_flags!(Hints: u32 {
+    BASE_VERTEX_INSTANCE_DRAWING,
+});
+
 /// Resource limits of a particular graphics device.
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -414,6 +483,18 @@ pub enum IndexType {
     U32,
 }
 
+/// Error parsing a value from its name, containing the unrecognized name.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UnknownName(pub String);
+
+impl fmt::Display for UnknownName {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "Unknown name `{}`", self.0)
+    }
+}
+
+impl std::error::Error for UnknownName {}
+
 /// Error creating an instance of a backend on the platform that
 /// doesn't support this backend.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -482,6 +563,22 @@ pub trait Instance<B: Backend>: Any + Send + Sync + Sized {
     /// The surface shouldn't be destroyed before the attached swapchain
     /// is destroyed, otherwise crashes or undefined behavior could occur.
     unsafe fn destroy_surface(&self, surface: B::Surface);
+
+    /// Create a new [surface][window::Surface] which isn't tied to any window.
+    ///
+    /// The surface is configured and presented to like any other,
+    /// and the presented images are delivered to the sink of the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns `UnsupportedOffscreen` if the backend has no offscreen surfaces.
+    fn create_offscreen_surface(
+        &self,
+        config: window::OffscreenConfig,
+    ) -> Result<B::Surface, window::InitError> {
+        let _ = config;
+        Err(window::InitError::UnsupportedOffscreen)
+    }
 }
 
 /// A strongly-typed index to a particular `MemoryType`.
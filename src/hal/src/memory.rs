@@ -5,7 +5,6 @@ use std::ops::Range;
 
 bitflags!(
     /// Memory property flags.
-    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct Properties: u16 {
         /// Device local memory on the GPU.
         const DEVICE_LOCAL = 0x1;
@@ -29,6 +28,14 @@ bitflags!(
     }
 );
 
+named_flags!(Properties: u16 {
+    DEVICE_LOCAL,
+    CPU_VISIBLE,
+    COHERENT,
+    CPU_CACHED,
+    LAZILY_ALLOCATED,
+});
+
 bitflags!(
     /// Barrier dependency flags.
     #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -0,0 +1,349 @@
+//! Human-readable names for the bitflags types.
+//!
+//! Flags are displayed and parsed as `NAME | OTHER_NAME`. Serialization with
+//! human-readable formats uses a list of names, for example `[STORAGE, TRANSFER_SRC]`,
+//! while the `(bits: 0x21)` form is still accepted on deserialization.
+
+use crate::UnknownName;
+use std::fmt;
+
+/// Implement `Display`, `FromStr`, and optionally `Serialize` and `Deserialize`,
+/// for a bitflags type, given the list of its flags.
+///
+/// Composite flags have to be listed before the individual ones,
+/// so that they take precedence when displaying.
+///
+/// The bits are handled as `u128`, which makes the casts trivial for `Features`.
+macro_rules! named_flags {
+    ($ty:ident : $bits:ty { $($name:ident,)* }) => {
+        #[allow(trivial_numeric_casts)]
+        impl $ty {
+            const NAMES: &'static [(&'static str, u128)] = &[
+                $( (stringify!($name), $ty::$name.bits as u128), )*
+            ];
+        }
+
+        #[allow(trivial_numeric_casts)]
+        impl std::fmt::Display for $ty {
+            fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                crate::names::write_flags(fmt, Self::NAMES, self.bits as u128)
+            }
+        }
+
+        #[allow(trivial_numeric_casts)]
+        impl std::str::FromStr for $ty {
+            type Err = crate::UnknownName;
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                crate::names::parse_flags(Self::NAMES, s)
+                    .map(|bits| Self::from_bits_truncate(bits as $bits))
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        #[allow(trivial_numeric_casts)]
+        impl serde::Serialize for $ty {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                if serializer.is_human_readable() {
+                    crate::names::serialize_flags(
+                        serializer,
+                        stringify!($ty),
+                        Self::NAMES,
+                        self.bits as u128,
+                    )
+                } else {
+                    use serde::ser::SerializeStruct;
+                    let mut state = serializer.serialize_struct(stringify!($ty), 1)?;
+                    state.serialize_field("bits", &self.bits)?;
+                    state.end()
+                }
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        #[allow(trivial_numeric_casts)]
+        impl<'de> serde::Deserialize<'de> for $ty {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let bits = crate::names::deserialize_flags::<_, $bits>(
+                    deserializer,
+                    stringify!($ty),
+                    Self::NAMES,
+                    Self::all().bits as u128,
+                )?;
+                Ok(Self::from_bits_truncate(bits as $bits))
+            }
+        }
+    };
+}
+
+pub(crate) fn write_flags(
+    fmt: &mut fmt::Formatter<'_>,
+    names: &[(&str, u128)],
+    bits: u128,
+) -> fmt::Result {
+    let mut remaining = bits;
+    let mut first = true;
+    for &(name, value) in names {
+        if value != 0 && remaining & value == value {
+            if !first {
+                fmt.write_str(" | ")?;
+            }
+            fmt.write_str(name)?;
+            remaining &= !value;
+            first = false;
+        }
+    }
+    if remaining != 0 {
+        if !first {
+            fmt.write_str(" | ")?;
+        }
+        write!(fmt, "{:#x}", remaining)?;
+    }
+    Ok(())
+}
+
+fn find_flag(names: &[(&str, u128)], name: &str) -> Option<u128> {
+    names
+        .iter()
+        .find(|&&(n, _)| n == name)
+        .map(|&(_, value)| value)
+}
+
+pub(crate) fn parse_flags(names: &[(&str, u128)], s: &str) -> Result<u128, UnknownName> {
+    let mut bits = 0;
+    for name in s.split('|').map(str::trim).filter(|name| !name.is_empty()) {
+        bits |= find_flag(names, name).ok_or_else(|| UnknownName(name.to_string()))?;
+    }
+    Ok(bits)
+}
+
+#[cfg(feature = "serde")]
+pub(crate) use self::serde_impl::{deserialize_flags, serialize_flags};
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use super::find_flag;
+    use serde::{
+        de::{self, Deserializer, MapAccess, SeqAccess, Visitor},
+        ser::{SerializeSeq, Serializer},
+        Deserialize,
+    };
+    use std::{fmt, marker::PhantomData};
+
+    pub(crate) fn serialize_flags<S: Serializer>(
+        serializer: S,
+        type_name: &'static str,
+        names: &'static [(&'static str, u128)],
+        bits: u128,
+    ) -> Result<S::Ok, S::Error> {
+        let mut remaining = bits;
+        let flags = names
+            .iter()
+            .enumerate()
+            .filter(|&(_, &(_, value))| {
+                let contained = value != 0 && remaining & value == value;
+                if contained {
+                    remaining &= !value;
+                }
+                contained
+            })
+            .collect::<Vec<_>>();
+        let mut seq = serializer.serialize_seq(Some(flags.len()))?;
+        for (index, &(name, _)) in flags {
+            seq.serialize_element(&FlagName {
+                type_name,
+                index: index as u32,
+                name,
+            })?;
+        }
+        seq.end()
+    }
+
+    /// Serializes as a unit variant, so that the names show up
+    /// as plain identifiers in RON.
+    struct FlagName {
+        type_name: &'static str,
+        index: u32,
+        name: &'static str,
+    }
+
+    impl serde::Serialize for FlagName {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_unit_variant(self.type_name, self.index, self.name)
+        }
+    }
+
+    /// An identifier or a string.
+    struct Name(String);
+
+    impl<'de> Deserialize<'de> for Name {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct NameVisitor;
+            impl<'de> Visitor<'de> for NameVisitor {
+                type Value = Name;
+                fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    fmt.write_str("a flag name")
+                }
+                fn visit_str<E: de::Error>(self, value: &str) -> Result<Name, E> {
+                    Ok(Name(value.to_string()))
+                }
+            }
+            deserializer.deserialize_identifier(NameVisitor)
+        }
+    }
+
+    struct FlagsVisitor<T> {
+        type_name: &'static str,
+        names: &'static [(&'static str, u128)],
+        all: u128,
+        human_readable: bool,
+        raw: PhantomData<T>,
+    }
+
+    impl<T> FlagsVisitor<T> {
+        fn check<E: de::Error>(&self, bits: u128) -> Result<u128, E> {
+            if bits & !self.all != 0 {
+                Err(E::invalid_value(
+                    de::Unexpected::Other("unknown bits"),
+                    &"a combination of known flags",
+                ))
+            } else {
+                Ok(bits)
+            }
+        }
+    }
+
+    impl<'de, T: Deserialize<'de> + Into<u128>> Visitor<'de> for &FlagsVisitor<T> {
+        type Value = u128;
+
+        fn expecting(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(fmt, "a list of {} flag names, or the raw bits", self.type_name)
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<u128, E> {
+            self.check(value as u128)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<u128, A::Error> {
+            let mut bits = 0;
+            if !self.human_readable {
+                // Compact formats store the bits as a single-field struct.
+                let raw = seq
+                    .next_element::<T>()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                return self.check(raw.into());
+            }
+            while let Some(Name(name)) = seq.next_element()? {
+                bits |= find_flag(self.names, &name).ok_or_else(|| {
+                    de::Error::custom(format_args!(
+                        "unknown {} flag `{}`",
+                        self.type_name, name
+                    ))
+                })?;
+            }
+            Ok(bits)
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<u128, A::Error> {
+            let mut bits = None;
+            while let Some(Name(key)) = map.next_key()? {
+                if key != "bits" {
+                    return Err(de::Error::unknown_field(&key, &["bits"]));
+                }
+                if bits.is_some() {
+                    return Err(de::Error::duplicate_field("bits"));
+                }
+                bits = Some(map.next_value::<T>()?.into());
+            }
+            let bits = bits.ok_or_else(|| de::Error::missing_field("bits"))?;
+            self.check(bits)
+        }
+    }
+
+    pub(crate) fn deserialize_flags<'de, D, T>(
+        deserializer: D,
+        type_name: &'static str,
+        names: &'static [(&'static str, u128)],
+        all: u128,
+    ) -> Result<u128, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de> + Into<u128>,
+    {
+        let human_readable = deserializer.is_human_readable();
+        let visitor = FlagsVisitor::<T> {
+            type_name,
+            names,
+            all,
+            human_readable,
+            raw: PhantomData,
+        };
+        if human_readable {
+            deserializer.deserialize_any(&visitor)
+        } else {
+            deserializer.deserialize_struct(type_name, &["bits"], &visitor)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{buffer, pso::ShaderStageFlags, UnknownName};
+
+    #[test]
+    fn test_flag_names() {
+        let usage = buffer::Usage::STORAGE | buffer::Usage::TRANSFER_SRC;
+        assert_eq!(usage.to_string(), "TRANSFER_SRC | STORAGE");
+        assert_eq!("STORAGE|TRANSFER_SRC".parse(), Ok(usage));
+        assert_eq!("".parse(), Ok(buffer::Usage::empty()));
+        assert_eq!(
+            "STORAGE | TEXTURE".parse::<buffer::Usage>(),
+            Err(UnknownName("TEXTURE".to_string()))
+        );
+        assert_eq!(ShaderStageFlags::GRAPHICS.to_string(), "GRAPHICS");
+        assert_eq!(
+            (ShaderStageFlags::VERTEX | ShaderStageFlags::COMPUTE).to_string(),
+            "VERTEX | COMPUTE"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_flag_serde() {
+        let usage = buffer::Usage::STORAGE | buffer::Usage::TRANSFER_SRC;
+
+        let ron = ron::ser::to_string(&usage).unwrap();
+        assert_eq!(ron, "[TRANSFER_SRC,STORAGE,]");
+        assert_eq!(ron::de::from_str::<buffer::Usage>(&ron).unwrap(), usage);
+        let json = serde_json::to_string(&usage).unwrap();
+        assert_eq!(json, r#"["TRANSFER_SRC","STORAGE"]"#);
+        assert_eq!(serde_json::from_str::<buffer::Usage>(&json).unwrap(), usage);
+        assert_eq!(
+            ron::de::from_str::<ShaderStageFlags>("[GRAPHICS]").unwrap(),
+            ShaderStageFlags::GRAPHICS
+        );
+        assert!(ron::de::from_str::<buffer::Usage>("[TEXTURE]").is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_flag_serde_legacy() {
+        let usage = buffer::Usage::STORAGE | buffer::Usage::TRANSFER_SRC;
+        let bits = usage.bits();
+
+        assert_eq!(
+            ron::de::from_str::<buffer::Usage>(&format!("(bits: {})", bits)).unwrap(),
+            usage
+        );
+        assert_eq!(
+            serde_json::from_str::<buffer::Usage>(&format!(r#"{{"bits":{}}}"#, bits)).unwrap(),
+            usage
+        );
+
+        // Unknown bits used to be accepted, and silently kept.
+        let unknown = buffer::Usage::all().bits() + 1;
+        assert!(ron::de::from_str::<buffer::Usage>(&format!("(bits: {})", unknown)).is_err());
+        assert!(
+            serde_json::from_str::<buffer::Usage>(&format!(r#"{{"bits":{}}}"#, unknown)).is_err()
+        );
+    }
+}
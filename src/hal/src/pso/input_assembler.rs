@@ -81,6 +81,54 @@ pub struct AttributeDesc {
     pub element: Element<format::Format>,
 }
 
+/// Vertex type with a known memory layout.
+///
+/// It's usually implemented by `#[derive(Vertex)]` from the `gfx-hal-derive` crate,
+/// which computes the attribute offsets and maps the field types to formats
+/// with [`AsFormat`][format::AsFormat].
+pub trait Vertex: Sized {
+    /// Rate at which the vertex buffers of this type are advanced.
+    const RATE: VertexInputRate;
+    /// Location of the first attribute. The following attributes take consecutive locations.
+    const BASE_LOCATION: Location;
+
+    /// Return the format and offset of each attribute.
+    fn elements() -> Vec<Element<format::Format>>;
+
+    /// Describe a vertex buffer of this type bound at `binding`.
+    fn buffer_desc(binding: BufferIndex) -> VertexBufferDesc {
+        VertexBufferDesc {
+            binding,
+            stride: size_of::<Self>() as ElemStride,
+            rate: Self::RATE,
+        }
+    }
+
+    /// Describe the attributes read from a vertex buffer of this type bound at `binding`.
+    ///
+    /// Attributes wider than 128 bits (3- and 4-component 64-bit formats)
+    /// take two consecutive locations.
+    fn attributes(binding: BufferIndex) -> Vec<AttributeDesc> {
+        let mut location = Self::BASE_LOCATION;
+        Self::elements()
+            .into_iter()
+            .map(|element| {
+                let desc = AttributeDesc {
+                    location,
+                    binding,
+                    element,
+                };
+                location += if element.format.surface_desc().bits > 128 {
+                    2
+                } else {
+                    1
+                };
+                desc
+            })
+            .collect()
+    }
+}
+
 /// Describes the type of geometric primitives,
 /// created from vertex data.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
@@ -0,0 +1,675 @@
+//! Memory layout of shader interface blocks.
+//!
+//! Uniform buffers, storage buffers and push constants are filled with raw bytes, which
+//! the shaders interpret according to the `std140` or `std430` layout rules. A type
+//! implementing [`ShaderType`](trait.ShaderType.html) describes its [`Shape`](enum.Shape.html),
+//! from which the offsets of each member are computed, and can write itself with the
+//! padding expected by the shaders.
+//!
+//! Fixed size arrays of 2 to 4 scalars are vectors, and arrays of 2 to 4 such float
+//! vectors are column-major matrices. Arrays of any other type are expressed with the
+//! [`Array`](struct.Array.html) wrapper. Structures implement `ShaderType` with
+//! `#[derive(ShaderType)]` from the `gfx-hal-derive` crate.
+//!
+//! The layout can be checked against the one the shader was compiled with, given as a
+//! list of [`Entry`](struct.Entry.html), such as reflected from the SPIR-V decorations.
+
+use crate::Limits;
+
+use std::{fmt, ops::Range};
+
+/// Set of rules used to lay out the members of a block.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BlockLayout {
+    /// Rules of `layout(std140)`, the default for uniform blocks.
+    ///
+    /// The alignment of arrays and structures is rounded up to 16 bytes.
+    Std140,
+    /// Rules of `layout(std430)`, the default for storage blocks and push constants.
+    Std430,
+}
+
+/// Type of the components of a shader type.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Scalar {
+    /// 32-bit signed integer.
+    Int,
+    /// 32-bit unsigned integer. Booleans are stored as such.
+    Uint,
+    /// 32-bit floating point number.
+    Float,
+    /// 64-bit floating point number.
+    Double,
+}
+
+impl Scalar {
+    /// Size of the scalar, in bytes.
+    pub fn size(&self) -> u32 {
+        match *self {
+            Scalar::Int | Scalar::Uint | Scalar::Float => 4,
+            Scalar::Double => 8,
+        }
+    }
+}
+
+/// Named member of a structure.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Member {
+    /// Name of the member, used to report mismatches.
+    pub name: String,
+    /// Shape of the member.
+    pub shape: Shape,
+}
+
+/// Description of a type as seen by the shaders.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Shape {
+    /// Single scalar.
+    Scalar(Scalar),
+    /// Vector of 2 to 4 components.
+    Vector(Scalar, u32),
+    /// Column-major matrix.
+    Matrix {
+        /// Type of the components.
+        scalar: Scalar,
+        /// Number of columns.
+        columns: u32,
+        /// Number of rows, which is the size of each column vector.
+        rows: u32,
+    },
+    /// Array with a fixed number of elements.
+    Array(Box<Shape>, u32),
+    /// Structure, with its members in declaration order.
+    Struct(Vec<Member>),
+}
+
+fn align_to(offset: u32, alignment: u32) -> u32 {
+    offset.div_ceil(alignment) * alignment
+}
+
+impl Shape {
+    /// Base alignment, in bytes.
+    pub fn alignment(&self, layout: BlockLayout) -> u32 {
+        match *self {
+            Shape::Scalar(scalar) => scalar.size(),
+            Shape::Vector(scalar, 2) => 2 * scalar.size(),
+            Shape::Vector(scalar, _) => 4 * scalar.size(),
+            Shape::Matrix { scalar, rows, .. } => {
+                Self::array_alignment(Shape::Vector(scalar, rows).alignment(layout), layout)
+            }
+            Shape::Array(ref element, _) => {
+                Self::array_alignment(element.alignment(layout), layout)
+            }
+            Shape::Struct(ref members) => Self::array_alignment(
+                members
+                    .iter()
+                    .map(|member| member.shape.alignment(layout))
+                    .max()
+                    .unwrap_or(1),
+                layout,
+            ),
+        }
+    }
+
+    fn array_alignment(alignment: u32, layout: BlockLayout) -> u32 {
+        match layout {
+            BlockLayout::Std140 => align_to(alignment, 16),
+            BlockLayout::Std430 => alignment,
+        }
+    }
+
+    /// Size, in bytes, including the padding at the end of arrays and structures.
+    pub fn size(&self, layout: BlockLayout) -> u32 {
+        match *self {
+            Shape::Scalar(scalar) => scalar.size(),
+            Shape::Vector(scalar, count) => count * scalar.size(),
+            Shape::Matrix { columns, .. } => columns * self.stride(layout),
+            Shape::Array(_, count) => count * self.stride(layout),
+            Shape::Struct(ref members) => {
+                let end = match (members.last(), self.member_offsets(layout).last()) {
+                    (Some(member), Some(&offset)) => offset + member.shape.size(layout),
+                    _ => 0,
+                };
+                align_to(end, self.alignment(layout))
+            }
+        }
+    }
+
+    /// Distance between consecutive elements of an array, or columns of a matrix.
+    ///
+    /// Returns 0 for the other shapes.
+    pub fn stride(&self, layout: BlockLayout) -> u32 {
+        match *self {
+            Shape::Matrix { scalar, rows, .. } => {
+                align_to(rows * scalar.size(), self.alignment(layout))
+            }
+            Shape::Array(ref element, _) => align_to(element.size(layout), self.alignment(layout)),
+            _ => 0,
+        }
+    }
+
+    /// Offsets of the members of a structure.
+    ///
+    /// Returns an empty list for the other shapes.
+    pub fn member_offsets(&self, layout: BlockLayout) -> Vec<u32> {
+        let members = match *self {
+            Shape::Struct(ref members) => members,
+            _ => return Vec::new(),
+        };
+        let mut end = 0;
+        members
+            .iter()
+            .map(|member| {
+                let offset = align_to(end, member.shape.alignment(layout));
+                end = offset + member.shape.size(layout);
+                offset
+            })
+            .collect()
+    }
+
+    /// Flatten the shape into the scalars and vectors it's made of, in order.
+    ///
+    /// Matrices are split into their columns, and arrays into their elements.
+    pub fn entries(&self, layout: BlockLayout) -> Vec<Entry> {
+        let mut entries = Vec::new();
+        self.collect_entries(layout, String::new(), 0, &mut entries);
+        entries
+    }
+
+    fn collect_entries(
+        &self,
+        layout: BlockLayout,
+        path: String,
+        offset: u32,
+        entries: &mut Vec<Entry>,
+    ) {
+        match *self {
+            Shape::Scalar(scalar) => entries.push(Entry {
+                path,
+                offset,
+                scalar,
+                components: 1,
+            }),
+            Shape::Vector(scalar, components) => entries.push(Entry {
+                path,
+                offset,
+                scalar,
+                components,
+            }),
+            Shape::Matrix {
+                scalar,
+                columns,
+                rows,
+            } => {
+                let stride = self.stride(layout);
+                for column in 0 .. columns {
+                    entries.push(Entry {
+                        path: format!("{}[{}]", path, column),
+                        offset: offset + column * stride,
+                        scalar,
+                        components: rows,
+                    });
+                }
+            }
+            Shape::Array(ref element, count) => {
+                let stride = self.stride(layout);
+                for i in 0 .. count {
+                    element.collect_entries(
+                        layout,
+                        format!("{}[{}]", path, i),
+                        offset + i * stride,
+                        entries,
+                    );
+                }
+            }
+            Shape::Struct(ref members) => {
+                for (member, member_offset) in members.iter().zip(self.member_offsets(layout)) {
+                    let path = if path.is_empty() {
+                        member.name.clone()
+                    } else {
+                        format!("{}.{}", path, member.name)
+                    };
+                    member
+                        .shape
+                        .collect_entries(layout, path, offset + member_offset, entries);
+                }
+            }
+        }
+    }
+}
+
+/// Scalar or vector at a given offset of a block.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Entry {
+    /// Path to the entry in the block, like `lights[1].color`.
+    pub path: String,
+    /// Offset from the start of the block, in bytes.
+    pub offset: u32,
+    /// Type of the components.
+    pub scalar: Scalar,
+    /// Number of components, 1 for scalars.
+    pub components: u32,
+}
+
+impl Entry {
+    fn same_layout(&self, other: &Self) -> bool {
+        self.offset == other.offset
+            && self.scalar == other.scalar
+            && self.components == other.components
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "`{}` ({:?}", self.path, self.scalar)?;
+        if self.components > 1 {
+            write!(fmt, "x{}", self.components)?;
+        }
+        write!(fmt, " at offset {})", self.offset)
+    }
+}
+
+/// Difference between the layout of a type and the one expected by a shader.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum LayoutMismatch {
+    /// The entries at the same position differ.
+    Entry {
+        /// Entry of the type.
+        expected: Entry,
+        /// Entry of the shader.
+        found: Entry,
+    },
+    /// The shader has more entries than the type.
+    Missing(Entry),
+    /// The type has more entries than the shader.
+    Extra(Entry),
+}
+
+impl fmt::Display for LayoutMismatch {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            LayoutMismatch::Entry {
+                ref expected,
+                ref found,
+            } => write!(
+                fmt,
+                "Layout mismatch: {} against {} in the shader",
+                expected, found
+            ),
+            LayoutMismatch::Missing(ref entry) => {
+                write!(fmt, "Layout mismatch: {} is missing", entry)
+            }
+            LayoutMismatch::Extra(ref entry) => {
+                write!(fmt, "Layout mismatch: {} is not in the shader", entry)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LayoutMismatch {}
+
+/// Error computing a push constant range.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum PushConstantError {
+    /// The offset isn't a multiple of 4 bytes.
+    UnalignedOffset(u32),
+    /// The range ends past `Limits::max_push_constants_size`.
+    TooLarge {
+        /// Required range, in bytes.
+        range: Range<u32>,
+        /// Maximum size supported by the device.
+        max: usize,
+    },
+}
+
+impl fmt::Display for PushConstantError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            PushConstantError::UnalignedOffset(offset) => write!(
+                fmt,
+                "Push constant offset {} is not a multiple of 4",
+                offset
+            ),
+            PushConstantError::TooLarge { ref range, max } => write!(
+                fmt,
+                "Push constant range {:?} exceeds the maximum size of {} bytes",
+                range, max
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PushConstantError {}
+
+/// Type that can be stored in a uniform or storage block, or in push constants.
+pub trait ShaderType {
+    /// Describe the type.
+    fn shape() -> Shape;
+
+    /// Write the value at the start of `bytes`, which has room for the size of the shape.
+    ///
+    /// Padding bytes are left untouched.
+    fn write(&self, layout: BlockLayout, bytes: &mut [u8]) {
+        self.write_shaped(&Self::shape(), layout, bytes);
+    }
+
+    /// Same as `write`, given the shape of the type, so that it is only built once
+    /// for a whole block.
+    fn write_shaped(&self, shape: &Shape, layout: BlockLayout, bytes: &mut [u8]);
+}
+
+/// Fixed size array of any shader type.
+///
+/// Plain Rust arrays are vectors and matrices instead, see the [module](index.html) docs.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[repr(transparent)]
+pub struct Array<T, const N: usize>(pub [T; N]);
+
+impl<T: ShaderType, const N: usize> ShaderType for Array<T, N> {
+    fn shape() -> Shape {
+        Shape::Array(Box::new(T::shape()), N as u32)
+    }
+
+    fn write_shaped(&self, shape: &Shape, layout: BlockLayout, bytes: &mut [u8]) {
+        let element_shape = match *shape {
+            Shape::Array(ref element, _) => element,
+            _ => unreachable!(),
+        };
+        let stride = shape.stride(layout) as usize;
+        for (i, element) in self.0.iter().enumerate() {
+            element.write_shaped(element_shape, layout, &mut bytes[i * stride ..]);
+        }
+    }
+}
+
+macro_rules! impl_scalar {
+    ($($ty:ty = $scalar:ident,)*) => {
+        $(
+            impl ShaderType for $ty {
+                fn shape() -> Shape {
+                    Shape::Scalar(Scalar::$scalar)
+                }
+
+                fn write_shaped(&self, _: &Shape, _: BlockLayout, bytes: &mut [u8]) {
+                    let data = self.to_ne_bytes();
+                    bytes[.. data.len()].copy_from_slice(&data);
+                }
+            }
+
+            impl_vector!($ty = $scalar: 2, 3, 4);
+        )*
+    };
+}
+
+macro_rules! impl_vector {
+    ($ty:ty = $scalar:ident: $($count:expr),*) => {
+        $(
+            impl ShaderType for [$ty; $count] {
+                fn shape() -> Shape {
+                    Shape::Vector(Scalar::$scalar, $count)
+                }
+
+                fn write_shaped(&self, _: &Shape, layout: BlockLayout, bytes: &mut [u8]) {
+                    let size = Scalar::$scalar.size() as usize;
+                    let scalar = Shape::Scalar(Scalar::$scalar);
+                    for (i, component) in self.iter().enumerate() {
+                        component.write_shaped(&scalar, layout, &mut bytes[i * size ..]);
+                    }
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_matrix {
+    ($($ty:ty = $scalar:ident: $([$rows:expr; $columns:expr]),*;)*) => {
+        $($(
+            impl ShaderType for [[$ty; $rows]; $columns] {
+                fn shape() -> Shape {
+                    Shape::Matrix {
+                        scalar: Scalar::$scalar,
+                        columns: $columns,
+                        rows: $rows,
+                    }
+                }
+
+                fn write_shaped(&self, shape: &Shape, layout: BlockLayout, bytes: &mut [u8]) {
+                    let stride = shape.stride(layout) as usize;
+                    let column_shape = Shape::Vector(Scalar::$scalar, $rows);
+                    for (i, column) in self.iter().enumerate() {
+                        column.write_shaped(&column_shape, layout, &mut bytes[i * stride ..]);
+                    }
+                }
+            }
+        )*)*
+    };
+}
+
+impl_scalar! {
+    i32 = Int,
+    u32 = Uint,
+    f32 = Float,
+    f64 = Double,
+}
+
+impl_matrix! {
+    f32 = Float: [2; 2], [3; 2], [4; 2], [2; 3], [3; 3], [4; 3], [2; 4], [3; 4], [4; 4];
+    f64 = Double: [2; 2], [3; 2], [4; 2], [2; 3], [3; 3], [4; 3], [2; 4], [3; 4], [4; 4];
+}
+
+impl ShaderType for bool {
+    fn shape() -> Shape {
+        Shape::Scalar(Scalar::Uint)
+    }
+
+    fn write_shaped(&self, shape: &Shape, layout: BlockLayout, bytes: &mut [u8]) {
+        (*self as u32).write_shaped(shape, layout, bytes)
+    }
+}
+
+/// Write a value with the given layout, including the padding, which is zeroed.
+pub fn to_bytes<T: ShaderType>(value: &T, layout: BlockLayout) -> Vec<u8> {
+    let shape = T::shape();
+    let mut bytes = vec![0; shape.size(layout) as usize];
+    value.write_shaped(&shape, layout, &mut bytes);
+    bytes
+}
+
+/// Write a value with the given layout, padded to a whole number of words.
+///
+/// This is the form expected by `push_graphics_constants` and `push_compute_constants`.
+pub fn to_words<T: ShaderType>(value: &T, layout: BlockLayout) -> Vec<u32> {
+    let bytes = to_bytes(value, layout);
+    bytes
+        .chunks(4)
+        .map(|chunk| {
+            let mut word = [0; 4];
+            word[.. chunk.len()].copy_from_slice(chunk);
+            u32::from_ne_bytes(word)
+        })
+        .collect()
+}
+
+/// Compare the layout of a type with the entries of a shader block.
+pub fn check<T: ShaderType>(layout: BlockLayout, shader: &[Entry]) -> Result<(), LayoutMismatch> {
+    let entries = T::shape().entries(layout);
+    for (expected, found) in entries.iter().zip(shader) {
+        if !expected.same_layout(found) {
+            return Err(LayoutMismatch::Entry {
+                expected: expected.clone(),
+                found: found.clone(),
+            });
+        }
+    }
+    if let Some(entry) = shader.get(entries.len()) {
+        return Err(LayoutMismatch::Missing(entry.clone()));
+    }
+    if let Some(entry) = entries.get(shader.len()) {
+        return Err(LayoutMismatch::Extra(entry.clone()));
+    }
+    Ok(())
+}
+
+/// Compute the push constant range holding a value of type `T` at `offset`, in bytes.
+///
+/// The size is rounded up to a multiple of 4 bytes, and the range is checked against
+/// `Limits::max_push_constants_size`. Push constants use the `std430` layout.
+pub fn push_constant_range<T: ShaderType>(
+    offset: u32,
+    limits: &Limits,
+) -> Result<Range<u32>, PushConstantError> {
+    if !offset.is_multiple_of(4) {
+        return Err(PushConstantError::UnalignedOffset(offset));
+    }
+    let size = align_to(T::shape().size(BlockLayout::Std430), 4);
+    let range = offset .. offset + size;
+    if range.end as usize > limits.max_push_constants_size {
+        return Err(PushConstantError::TooLarge {
+            range,
+            max: limits.max_push_constants_size,
+        });
+    }
+    Ok(range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lights() -> Shape {
+        let light = Shape::Struct(vec![
+            Member {
+                name: "position".into(),
+                shape: <[f32; 3]>::shape(),
+            },
+            Member {
+                name: "radius".into(),
+                shape: f32::shape(),
+            },
+            Member {
+                name: "color".into(),
+                shape: <[f32; 2]>::shape(),
+            },
+        ]);
+        Shape::Struct(vec![
+            Member {
+                name: "transform".into(),
+                shape: <[[f32; 3]; 3]>::shape(),
+            },
+            Member {
+                name: "count".into(),
+                shape: u32::shape(),
+            },
+            Member {
+                name: "weights".into(),
+                shape: <Array<f32, 3>>::shape(),
+            },
+            Member {
+                name: "lights".into(),
+                shape: Shape::Array(Box::new(light), 2),
+            },
+        ])
+    }
+
+    #[test]
+    fn test_std140() {
+        let shape = lights();
+        let layout = BlockLayout::Std140;
+        assert_eq!(shape.member_offsets(layout), vec![0, 48, 64, 112]);
+        assert_eq!(shape.size(layout), 176);
+
+        let entries = shape.entries(layout);
+        let offsets: Vec<_> = entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.offset))
+            .collect();
+        assert_eq!(
+            offsets,
+            vec![
+                ("transform[0]", 0),
+                ("transform[1]", 16),
+                ("transform[2]", 32),
+                ("count", 48),
+                ("weights[0]", 64),
+                ("weights[1]", 80),
+                ("weights[2]", 96),
+                ("lights[0].position", 112),
+                ("lights[0].radius", 124),
+                ("lights[0].color", 128),
+                ("lights[1].position", 144),
+                ("lights[1].radius", 156),
+                ("lights[1].color", 160),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_std430() {
+        let shape = lights();
+        let layout = BlockLayout::Std430;
+        assert_eq!(shape.member_offsets(layout), vec![0, 48, 52, 64]);
+        assert_eq!(shape.size(layout), 128);
+        assert_eq!(<Array<[f32; 2], 3>>::shape().stride(layout), 8);
+        assert_eq!(<Array<[f32; 3], 3>>::shape().stride(layout), 16);
+    }
+
+    #[test]
+    fn test_write() {
+        let value = Array([[1.0f32, 2.0], [3.0, 4.0]]);
+        let words = to_words(&value, BlockLayout::Std140);
+        let floats: Vec<_> = words.iter().map(|&w| f32::from_bits(w)).collect();
+        assert_eq!(floats, [1.0, 2.0, 0.0, 0.0, 3.0, 4.0, 0.0, 0.0]);
+        assert_eq!(
+            to_words(&value, BlockLayout::Std430),
+            [1.0f32, 2.0, 3.0, 4.0]
+                .iter()
+                .map(|f| f.to_bits())
+                .collect::<Vec<_>>()
+        );
+
+        let nested = Array([Array([1u32, 2]), Array([3, 4])]);
+        assert_eq!(
+            to_words(&nested, BlockLayout::Std140),
+            [1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4, 0, 0, 0]
+        );
+        assert_eq!(to_words(&nested, BlockLayout::Std430), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_check() {
+        let mut entries = <[[f32; 4]; 2]>::shape().entries(BlockLayout::Std430);
+        assert_eq!(
+            check::<[[f32; 4]; 2]>(BlockLayout::Std430, &entries),
+            Ok(())
+        );
+        entries[1].offset = 20;
+        assert!(matches!(
+            check::<[[f32; 4]; 2]>(BlockLayout::Std430, &entries),
+            Err(LayoutMismatch::Entry { .. })
+        ));
+        entries.pop();
+        assert!(matches!(
+            check::<[[f32; 4]; 2]>(BlockLayout::Std430, &entries),
+            Err(LayoutMismatch::Extra(_))
+        ));
+    }
+
+    #[test]
+    fn test_push_constant_range() {
+        let limits = Limits {
+            max_push_constants_size: 128,
+            ..Limits::default()
+        };
+        assert_eq!(
+            push_constant_range::<[[f32; 4]; 4]>(64, &limits),
+            Ok(64 .. 128)
+        );
+        assert_eq!(
+            push_constant_range::<[[f32; 4]; 4]>(66, &limits),
+            Err(PushConstantError::UnalignedOffset(66))
+        );
+        assert!(push_constant_range::<[[f32; 4]; 4]>(68, &limits).is_err());
+    }
+}
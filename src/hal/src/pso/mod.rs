@@ -9,8 +9,10 @@ mod compute;
 mod descriptor;
 mod graphics;
 mod input_assembler;
+pub mod layout;
 mod output_merger;
 mod specialization;
+mod validate;
 
 pub use self::{
     compute::*,
@@ -19,6 +21,7 @@ pub use self::{
     input_assembler::*,
     output_merger::*,
     specialization::*,
+    validate::*,
 };
 
 /// Error types happening upon PSO creation on the device side.
@@ -67,7 +70,6 @@ bitflags!(
     ///
     /// The pipeline is structured by the ordering of the flags.
     /// Some stages are queue type dependent.
-    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct PipelineStage: u32 {
         /// Beginning of the command queue.
         const TOP_OF_PIPE = 0x1;
@@ -103,9 +105,26 @@ bitflags!(
     }
 );
 
+named_flags!(PipelineStage: u32 {
+    TOP_OF_PIPE,
+    DRAW_INDIRECT,
+    VERTEX_INPUT,
+    VERTEX_SHADER,
+    HULL_SHADER,
+    DOMAIN_SHADER,
+    GEOMETRY_SHADER,
+    FRAGMENT_SHADER,
+    EARLY_FRAGMENT_TESTS,
+    LATE_FRAGMENT_TESTS,
+    COLOR_ATTACHMENT_OUTPUT,
+    COMPUTE_SHADER,
+    TRANSFER,
+    BOTTOM_OF_PIPE,
+    HOST,
+});
+
 bitflags!(
     /// Combination of different shader pipeline stages.
-    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct ShaderStageFlags: u32 {
         /// Vertex shader stage.
         const VERTEX   = 0x1;
@@ -127,6 +146,17 @@ bitflags!(
     }
 );
 
+named_flags!(ShaderStageFlags: u32 {
+    ALL,
+    GRAPHICS,
+    VERTEX,
+    HULL,
+    DOMAIN,
+    GEOMETRY,
+    FRAGMENT,
+    COMPUTE,
+});
+
 // Note: this type is only needed for backends, not used anywhere within gfx_hal.
 /// Which program stage this shader represents.
 #[allow(missing_docs)]
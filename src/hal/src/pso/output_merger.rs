@@ -28,7 +28,6 @@ pub enum Comparison {
 
 bitflags!(
     /// Target output color mask.
-    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct ColorMask: u8 {
         /// Red mask
         const RED     = 0x1;
@@ -47,6 +46,15 @@ bitflags!(
     }
 );
 
+named_flags!(ColorMask: u8 {
+    ALL,
+    COLOR,
+    RED,
+    GREEN,
+    BLUE,
+    ALPHA,
+});
+
 impl Default for ColorMask {
     fn default() -> Self {
         Self::ALL
@@ -347,7 +355,6 @@ impl DepthStencilDesc {
 
 bitflags!(
     /// Face.
-    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct Face: u32 {
         /// Empty face. TODO: remove when constexpr are stabilized to use empty()
         const NONE = 0x0;
@@ -357,3 +364,8 @@ bitflags!(
         const BACK = 0x2;
     }
 );
+
+named_flags!(Face: u32 {
+    FRONT,
+    BACK,
+});
@@ -1,6 +1,6 @@
 //! Pipeline specialization types.
 
-use std::{borrow::Cow, ops::Range, slice};
+use std::{borrow::Cow, fmt, ops::Range, slice};
 
 /// Description of a specialization constant for the pipeline.
 #[derive(Debug, Clone, Hash, PartialEq)]
@@ -38,12 +38,165 @@ impl Specialization<'_> {
     };
 }
 
+impl Specialization<'_> {
+    /// Append the value of the constant `id`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the data would exceed `u16::MAX` bytes, which the ranges of
+    /// the constants can't address. The specialization is left unchanged.
+    pub fn push<T: SpecConstant>(&mut self, id: u32, value: T) {
+        let mut bytes = Vec::new();
+        value.write(&mut bytes);
+        let offset = self.data.len();
+        assert!(
+            offset + bytes.len() <= u16::MAX as usize,
+            "Specialization data exceeds {} bytes",
+            u16::MAX
+        );
+        self.data.to_mut().extend_from_slice(&bytes);
+        self.constants.to_mut().push(SpecializationConstant {
+            id,
+            range: offset as u16 .. self.data.len() as u16,
+        });
+    }
+}
+
 impl Default for Specialization<'_> {
     fn default() -> Self {
         Specialization::EMPTY
     }
 }
 
+/// Type of a specialization constant.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SpecConstantType {
+    /// Boolean, stored as a 32-bit integer.
+    Bool,
+    /// 32-bit signed integer.
+    Int,
+    /// 32-bit unsigned integer.
+    Uint,
+    /// 32-bit floating point number.
+    Float,
+    /// 64-bit floating point number.
+    Double,
+}
+
+/// Value of a specialization constant.
+pub trait SpecConstant: Copy {
+    /// Type of the constant in the shader.
+    const TYPE: SpecConstantType;
+
+    /// Append the bytes of the value.
+    fn write(&self, data: &mut Vec<u8>);
+}
+
+macro_rules! impl_spec_constant {
+    ($($ty:ty = $variant:ident,)*) => {
+        $(
+            impl SpecConstant for $ty {
+                const TYPE: SpecConstantType = SpecConstantType::$variant;
+
+                fn write(&self, data: &mut Vec<u8>) {
+                    data.extend_from_slice(&self.to_ne_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_spec_constant! {
+    i32 = Int,
+    u32 = Uint,
+    f32 = Float,
+    f64 = Double,
+}
+
+impl SpecConstant for bool {
+    const TYPE: SpecConstantType = SpecConstantType::Bool;
+
+    fn write(&self, data: &mut Vec<u8>) {
+        (*self as u32).write(data)
+    }
+}
+
+/// Constant whose type differs between a specialization and a shader.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SpecConstantMismatch {
+    /// Constant identifier.
+    pub id: u32,
+    /// Type of the specialized value.
+    pub expected: SpecConstantType,
+    /// Type declared by the shader.
+    pub found: SpecConstantType,
+}
+
+impl fmt::Display for SpecConstantMismatch {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            fmt,
+            "Specialization constant {} is a {:?}, but the shader declares a {:?}",
+            self.id, self.expected, self.found
+        )
+    }
+}
+
+impl std::error::Error for SpecConstantMismatch {}
+
+/// Specialization constant declared by a shader, for example as found by reflection.
+pub trait ShaderSpecConstant {
+    /// Constant identifier.
+    fn id(&self) -> u32;
+    /// Type of the constant.
+    fn ty(&self) -> SpecConstantType;
+}
+
+impl ShaderSpecConstant for (u32, SpecConstantType) {
+    fn id(&self) -> u32 {
+        self.0
+    }
+    fn ty(&self) -> SpecConstantType {
+        self.1
+    }
+}
+
+/// Set of typed specialization constants, usually implemented with
+/// `#[derive(Specialization)]` from the `gfx-hal-derive` crate.
+pub trait SpecConstants {
+    /// Identifier and type of each constant.
+    fn constant_types() -> Vec<(u32, SpecConstantType)>;
+
+    /// Build the specialization holding the values.
+    ///
+    /// # Panics
+    ///
+    /// The derived implementation panics if the values exceed `u16::MAX` bytes,
+    /// like `Specialization::push`.
+    fn specialization(&self) -> Specialization<'static>;
+
+    /// Check the types of the constants against the ones declared by a shader.
+    ///
+    /// Constants the shader doesn't declare are ignored, since a specialization
+    /// is usually shared by several stages.
+    fn check<C: ShaderSpecConstant>(shader: &[C]) -> Result<(), SpecConstantMismatch> {
+        for (id, expected) in Self::constant_types() {
+            if let Some(constant) = shader.iter().find(|constant| constant.id() == id) {
+                let found = constant.ty();
+                if found != expected {
+                    return Err(SpecConstantMismatch {
+                        id,
+                        expected,
+                        found,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 #[doc(hidden)]
 #[derive(Debug, Default)]
 pub struct SpecializationStorage {
@@ -132,3 +285,51 @@ macro_rules! spec_const_list {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push() {
+        let mut specialization = Specialization::default();
+        specialization.push(3, true);
+        specialization.push(1, 0.5f64);
+        specialization.push(0, -2i32);
+        assert_eq!(
+            *specialization.constants,
+            [
+                SpecializationConstant { id: 3, range: 0 .. 4 },
+                SpecializationConstant {
+                    id: 1,
+                    range: 4 .. 12
+                },
+                SpecializationConstant {
+                    id: 0,
+                    range: 12 .. 16
+                },
+            ]
+        );
+        assert_eq!(specialization.data[.. 4], 1u32.to_ne_bytes());
+        assert_eq!(specialization.data[4 .. 12], 0.5f64.to_ne_bytes());
+    }
+
+    #[test]
+    fn test_push_overflow() {
+        let limit = u16::MAX as usize;
+        let mut specialization = Specialization {
+            constants: Cow::Borrowed(&[]),
+            data: Cow::Owned(vec![0; limit - 2]),
+        };
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            specialization.push(0, 1u32);
+        }));
+        assert!(result.is_err());
+        assert_eq!(specialization.data.len(), limit - 2);
+        assert!(specialization.constants.is_empty());
+
+        specialization.data.to_mut().truncate(limit - 4);
+        specialization.push(0, 1u32);
+        assert_eq!(specialization.constants[0].range.end, u16::MAX);
+    }
+}
@@ -0,0 +1,411 @@
+//! Backend-independent validation of pipeline descriptions.
+
+use crate::{
+    pso::{
+        input_assembler::{BufferIndex, Location, Primitive, VertexInputRate},
+        output_merger::{BlendOp, BlendState, Factor},
+        ComputePipelineDesc,
+        EntryPoint,
+        GraphicsPipelineDesc,
+        PolygonMode,
+        ShaderStageFlags,
+        State,
+    },
+    Backend,
+    Features,
+    Limits,
+};
+
+use std::{fmt, ops::Range};
+
+/// Part of a pipeline description that a device can't support.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Violation {
+    /// Something is used that requires a feature the device doesn't have.
+    MissingFeature {
+        /// Required feature.
+        feature: Features,
+        /// What requires the feature.
+        usage: &'static str,
+    },
+    /// A value exceeds one of the device limits.
+    LimitExceeded {
+        /// Name of the `Limits` field.
+        limit: &'static str,
+        /// Value used by the description.
+        value: u64,
+        /// Maximum value supported by the device.
+        max: u64,
+    },
+    /// The rasterization sample count isn't a power of two supported for color attachments.
+    UnsupportedSampleCount(u8),
+    /// An attribute reads from a binding without a vertex buffer.
+    UndefinedBinding {
+        /// Location of the attribute.
+        location: Location,
+        /// Binding of the missing vertex buffer.
+        binding: BufferIndex,
+    },
+    /// Several attributes have the same location.
+    DuplicateLocation(Location),
+    /// Several vertex buffers have the same binding.
+    DuplicateBinding(BufferIndex),
+    /// Patch lists are used without tessellation shaders, or the other way around.
+    TessellationMismatch,
+    /// A specialization constant refers to bytes outside of the specialization data.
+    InvalidSpecialization {
+        /// Stage of the entry point.
+        stage: ShaderStageFlags,
+        /// Constant identifier.
+        id: u32,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Violation::MissingFeature { feature, usage } => {
+                write!(fmt, "{} requires feature {}", usage, feature)
+            }
+            Violation::LimitExceeded { limit, value, max } => {
+                write!(fmt, "{} of {} exceeds the limit of {}", limit, value, max)
+            }
+            Violation::UnsupportedSampleCount(samples) => {
+                write!(fmt, "Sample count {} is not supported", samples)
+            }
+            Violation::UndefinedBinding { location, binding } => write!(
+                fmt,
+                "Attribute at location {} reads from binding {} without a vertex buffer",
+                location, binding
+            ),
+            Violation::DuplicateLocation(location) => {
+                write!(fmt, "Several attributes have location {}", location)
+            }
+            Violation::DuplicateBinding(binding) => {
+                write!(fmt, "Several vertex buffers have binding {}", binding)
+            }
+            Violation::TessellationMismatch => write!(
+                fmt,
+                "Patch lists must be used together with hull and domain shaders"
+            ),
+            Violation::InvalidSpecialization { stage, id } => write!(
+                fmt,
+                "Specialization constant {} of the {:?} stage is out of the data range",
+                id, stage
+            ),
+        }
+    }
+}
+
+/// Error returned by pipeline description validation, listing every violation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationError {
+    /// Violations, in the order they were found.
+    pub violations: Vec<Violation>,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "Invalid pipeline description")?;
+        for (i, violation) in self.violations.iter().enumerate() {
+            write!(fmt, "{} {}", if i == 0 { ":" } else { ";" }, violation)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+struct Validator<'a> {
+    features: Features,
+    limits: &'a Limits,
+    violations: Vec<Violation>,
+}
+
+impl Validator<'_> {
+    fn require(&mut self, feature: Features, usage: &'static str) {
+        if !self.features.contains(feature) {
+            self.violations
+                .push(Violation::MissingFeature { feature, usage });
+        }
+    }
+
+    fn limit(&mut self, limit: &'static str, value: usize, max: usize) {
+        if value > max {
+            self.violations.push(Violation::LimitExceeded {
+                limit,
+                value: value as u64,
+                max: max as u64,
+            });
+        }
+    }
+
+    fn entry_point<B: Backend>(&mut self, stage: ShaderStageFlags, entry: &EntryPoint<B>) {
+        let size = entry.specialization.data.len();
+        for constant in entry.specialization.constants.iter() {
+            if constant.range.start > constant.range.end || constant.range.end as usize > size {
+                self.violations.push(Violation::InvalidSpecialization {
+                    stage,
+                    id: constant.id,
+                });
+            }
+        }
+    }
+
+    fn finish(self) -> Result<(), ValidationError> {
+        if self.violations.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError {
+                violations: self.violations,
+            })
+        }
+    }
+}
+
+fn uses_dual_source(blend: &BlendState) -> bool {
+    let dual = |factor: Factor| {
+        matches!(
+            factor,
+            Factor::Src1Color
+                | Factor::OneMinusSrc1Color
+                | Factor::Src1Alpha
+                | Factor::OneMinusSrc1Alpha
+        )
+    };
+    [blend.color, blend.alpha].iter().any(|op| match *op {
+        BlendOp::Add { src, dst } | BlendOp::Sub { src, dst } | BlendOp::RevSub { src, dst } => {
+            dual(src) || dual(dst)
+        }
+        BlendOp::Min | BlendOp::Max => false,
+    })
+}
+
+/// Check a graphics pipeline description against the features and limits of a device.
+///
+/// All the violations are reported, so that applications can check a description
+/// up front and backends can share the checks.
+///
+/// `Features::TRIANGLE_FAN` isn't checked, since [`Primitive`] has no triangle fan
+/// variant that could require it.
+pub fn validate_graphics_pipeline<B: Backend>(
+    desc: &GraphicsPipelineDesc<B>,
+    features: Features,
+    limits: &Limits,
+) -> Result<(), ValidationError> {
+    let mut v = Validator {
+        features,
+        limits,
+        violations: Vec::new(),
+    };
+
+    // Shaders
+    let shaders = &desc.shaders;
+    v.entry_point(ShaderStageFlags::VERTEX, &shaders.vertex);
+    if let Some(ref hull) = shaders.hull {
+        v.entry_point(ShaderStageFlags::HULL, hull);
+    }
+    if let Some(ref domain) = shaders.domain {
+        v.entry_point(ShaderStageFlags::DOMAIN, domain);
+    }
+    if let Some(ref geometry) = shaders.geometry {
+        v.require(Features::GEOMETRY_SHADER, "Geometry shader");
+        v.entry_point(ShaderStageFlags::GEOMETRY, geometry);
+    }
+    if let Some(ref fragment) = shaders.fragment {
+        v.entry_point(ShaderStageFlags::FRAGMENT, fragment);
+    }
+    let tessellation = shaders.hull.is_some() || shaders.domain.is_some();
+    if tessellation {
+        v.require(Features::TESSELLATION_SHADER, "Hull or domain shader");
+    }
+
+    // Input assembler
+    match desc.input_assembler.primitive {
+        Primitive::PatchList(size) => {
+            if !tessellation || shaders.hull.is_none() || shaders.domain.is_none() {
+                v.violations.push(Violation::TessellationMismatch);
+            }
+            v.limit(
+                "max_patch_size",
+                size as usize,
+                v.limits.max_patch_size as usize,
+            );
+        }
+        _ if tessellation => v.violations.push(Violation::TessellationMismatch),
+        _ => {}
+    }
+
+    v.limit(
+        "max_vertex_input_bindings",
+        desc.vertex_buffers.len(),
+        v.limits.max_vertex_input_bindings,
+    );
+    for (i, buffer) in desc.vertex_buffers.iter().enumerate() {
+        if desc.vertex_buffers[.. i]
+            .iter()
+            .any(|other| other.binding == buffer.binding)
+        {
+            v.violations
+                .push(Violation::DuplicateBinding(buffer.binding));
+        }
+        if buffer.binding as usize >= v.limits.max_vertex_input_bindings {
+            v.limit(
+                "max_vertex_input_bindings",
+                buffer.binding as usize + 1,
+                v.limits.max_vertex_input_bindings,
+            );
+        }
+        v.limit(
+            "max_vertex_input_binding_stride",
+            buffer.stride as usize,
+            v.limits.max_vertex_input_binding_stride,
+        );
+        match buffer.rate {
+            VertexInputRate::Instance(divisor) if divisor != 1 => v.require(
+                Features::INSTANCE_RATE,
+                "Instance rate divisor other than 1",
+            ),
+            _ => {}
+        }
+    }
+
+    v.limit(
+        "max_vertex_input_attributes",
+        desc.attributes.len(),
+        v.limits.max_vertex_input_attributes,
+    );
+    for (i, attribute) in desc.attributes.iter().enumerate() {
+        if desc.attributes[.. i]
+            .iter()
+            .any(|other| other.location == attribute.location)
+        {
+            v.violations
+                .push(Violation::DuplicateLocation(attribute.location));
+        }
+        if attribute.location as usize >= v.limits.max_vertex_input_attributes {
+            v.limit(
+                "max_vertex_input_attributes",
+                attribute.location as usize + 1,
+                v.limits.max_vertex_input_attributes,
+            );
+        }
+        if !desc
+            .vertex_buffers
+            .iter()
+            .any(|buffer| buffer.binding == attribute.binding)
+        {
+            v.violations.push(Violation::UndefinedBinding {
+                location: attribute.location,
+                binding: attribute.binding,
+            });
+        }
+        v.limit(
+            "max_vertex_input_attribute_offset",
+            attribute.element.offset as usize,
+            v.limits.max_vertex_input_attribute_offset,
+        );
+    }
+
+    // Rasterizer
+    let rasterizer = &desc.rasterizer;
+    match rasterizer.polygon_mode {
+        PolygonMode::Line => v.require(Features::NON_FILL_POLYGON_MODE, "PolygonMode::Line"),
+        PolygonMode::Point => v.require(Features::NON_FILL_POLYGON_MODE, "PolygonMode::Point"),
+        PolygonMode::Fill => {}
+    }
+    if rasterizer.depth_clamping {
+        v.require(Features::DEPTH_CLAMP, "Depth clamping");
+    }
+    if let Some(State::Static(bias)) = rasterizer.depth_bias {
+        if bias.clamp != 0.0 {
+            v.require(Features::DEPTH_BIAS_CLAMP, "Depth bias clamp");
+        }
+    }
+    if let State::Static(width) = rasterizer.line_width {
+        if width != 1.0 {
+            v.require(Features::LINE_WIDTH, "Line width other than 1");
+        }
+    }
+
+    // Output merger
+    let blender = &desc.blender;
+    if blender.logic_op.is_some() {
+        v.require(Features::LOGIC_OP, "Logic operation");
+    }
+    v.limit(
+        "max_color_attachments",
+        blender.targets.len(),
+        v.limits.max_color_attachments,
+    );
+    if blender
+        .targets
+        .iter()
+        .skip(1)
+        .any(|target| *target != blender.targets[0])
+    {
+        v.require(
+            Features::INDEPENDENT_BLENDING,
+            "Different blending per target",
+        );
+    }
+    if blender
+        .targets
+        .iter()
+        .filter_map(|target| target.blend.as_ref())
+        .any(uses_dual_source)
+    {
+        v.require(Features::DUAL_SRC_BLENDING, "Dual-source blend factor");
+        v.limit(
+            "max_fragment_dual_source_attachments",
+            blender.targets.len(),
+            v.limits.max_fragment_dual_source_attachments,
+        );
+    }
+    if desc.depth_stencil.depth_bounds {
+        v.require(Features::DEPTH_BOUNDS, "Depth bounds test");
+    }
+
+    if let Some(ref multisampling) = desc.multisampling {
+        let samples = multisampling.rasterization_samples;
+        if !samples.is_power_of_two() || samples & v.limits.framebuffer_color_sample_counts == 0 {
+            v.violations
+                .push(Violation::UnsupportedSampleCount(samples));
+        }
+        if multisampling.sample_shading.is_some() {
+            v.require(Features::SAMPLE_RATE_SHADING, "Sample shading");
+        }
+        if multisampling.alpha_to_one {
+            v.require(Features::ALPHA_TO_ONE, "Alpha to one");
+        }
+    }
+
+    v.finish()
+}
+
+/// Check a compute pipeline description against the features and limits of a device.
+///
+/// The push constant ranges are the ones the pipeline layout was created with.
+pub fn validate_compute_pipeline<B: Backend>(
+    desc: &ComputePipelineDesc<B>,
+    push_constant_ranges: &[(ShaderStageFlags, Range<u32>)],
+    features: Features,
+    limits: &Limits,
+) -> Result<(), ValidationError> {
+    let mut v = Validator {
+        features,
+        limits,
+        violations: Vec::new(),
+    };
+    v.entry_point(ShaderStageFlags::COMPUTE, &desc.shader);
+    for &(stages, ref range) in push_constant_ranges {
+        if stages.contains(ShaderStageFlags::COMPUTE) {
+            v.limit(
+                "max_push_constants_size",
+                range.end as usize,
+                v.limits.max_push_constants_size,
+            );
+        }
+    }
+    v.finish()
+}
@@ -18,6 +18,12 @@
 //! Once you have a window handle, you need to [create a surface][crate::Instance::create_surface]
 //! compatible with the [instance][crate::Instance] of the graphics API you currently use.
 //!
+//! ## Offscreen surface
+//!
+//! A surface can also be [created without a window][crate::Instance::create_offscreen_surface],
+//! for testing and headless rendering. It's used exactly like a window surface,
+//! but the presented images are delivered to a [frame sink][FrameSink] instead of the screen.
+//!
 //! ## Swapchain
 //!
 //! The most interesting part of a swapchain are the contained presentable images/backbuffers.
@@ -84,6 +90,8 @@ pub enum CreationError {
     SurfaceLost(device::SurfaceLost),
     /// Window in use
     WindowInUse(device::WindowInUse),
+    /// The configuration doesn't match the surface capabilities or formats.
+    Unsupported,
 }
 
 impl From<device::OutOfMemory> for CreationError {
@@ -125,6 +133,10 @@ impl std::fmt::Display for CreationError {
             CreationError::WindowInUse(err) => {
                 write!(fmt, "Failed to create or configure swapchain: {}", err)
             }
+            CreationError::Unsupported => write!(
+                fmt,
+                "Failed to create or configure swapchain: Configuration is not supported by the surface"
+            ),
         }
     }
 }
@@ -136,6 +148,7 @@ impl std::error::Error for CreationError {
             CreationError::DeviceLost(err) => Some(err),
             CreationError::SurfaceLost(err) => Some(err),
             CreationError::WindowInUse(err) => Some(err),
+            CreationError::Unsupported => None,
         }
     }
 }
@@ -602,6 +615,8 @@ pub trait Swapchain<B: Backend>: fmt::Debug + Any + Send + Sync {
 pub enum InitError {
     /// Window handle is not supported by the backend.
     UnsupportedWindowHandle,
+    /// Offscreen surfaces are not supported by the backend.
+    UnsupportedOffscreen,
 }
 
 impl std::fmt::Display for InitError {
@@ -611,8 +626,99 @@ impl std::fmt::Display for InitError {
                 fmt,
                 "Failed to create surface: Specified window handle is unsupported"
             ),
+            InitError::UnsupportedOffscreen => write!(
+                fmt,
+                "Failed to create surface: Offscreen surfaces are unsupported"
+            ),
         }
     }
 }
 
 impl std::error::Error for InitError {}
+
+/// An image presented to an offscreen surface.
+#[derive(Debug)]
+pub struct Frame<'a> {
+    /// Sequential number of the presentation, starting from zero.
+    ///
+    /// Frames replaced before being shown in `PresentMode::MAILBOX`
+    /// are never delivered, leaving gaps in the numbering.
+    pub index: u64,
+    /// Extent of the image.
+    pub extent: Extent2D,
+    /// Format of the image.
+    pub format: Format,
+    /// Texel data, in rows from top to bottom, without any padding.
+    pub data: &'a [u8],
+}
+
+/// Receiver of the images presented to an offscreen surface.
+///
+/// Implemented for closures taking a [`Frame`].
+pub trait FrameSink: Send {
+    /// Consume one presented frame.
+    fn consume(&mut self, frame: Frame<'_>);
+}
+
+impl<F: FnMut(Frame<'_>) + Send> FrameSink for F {
+    fn consume(&mut self, frame: Frame<'_>) {
+        self(frame)
+    }
+}
+
+/// Configuration of an [offscreen surface][crate::Instance::create_offscreen_surface].
+///
+/// This type implements the builder pattern, method calls can be
+/// easily chained.
+pub struct OffscreenConfig {
+    /// Formats reported by `Surface::supported_formats`.
+    pub formats: Vec<Format>,
+    /// Capabilities reported by `Surface::capabilities`.
+    pub capabilities: SurfaceCapabilities,
+    /// Receiver of the presented images. If `None`, the images are discarded.
+    pub sink: Option<Box<dyn FrameSink>>,
+}
+
+impl fmt::Debug for OffscreenConfig {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("OffscreenConfig")
+            .field("formats", &self.formats)
+            .field("capabilities", &self.capabilities)
+            .field("sink", &self.sink.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
+
+impl OffscreenConfig {
+    /// Create a new configuration for a surface of a fixed extent,
+    /// supporting all the present modes.
+    pub fn new(extent: Extent2D, formats: Vec<Format>) -> Self {
+        OffscreenConfig {
+            formats,
+            capabilities: SurfaceCapabilities {
+                image_count: 1 ..= DEFAULT_IMAGE_COUNT,
+                current_extent: Some(extent),
+                extents: extent ..= extent,
+                max_image_layers: 1,
+                usage: image::Usage::COLOR_ATTACHMENT
+                    | image::Usage::TRANSFER_SRC
+                    | image::Usage::TRANSFER_DST,
+                present_modes: PresentMode::all(),
+                composite_alpha_modes: CompositeAlphaMode::OPAQUE,
+            },
+            sink: None,
+        }
+    }
+
+    /// Specify the surface capabilities.
+    pub fn with_capabilities(mut self, capabilities: SurfaceCapabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Specify the receiver of presented images.
+    pub fn with_sink(mut self, sink: impl FrameSink + 'static) -> Self {
+        self.sink = Some(Box::new(sink));
+        self
+    }
+}
@@ -0,0 +1,650 @@
+//! Replaying a `CommandList` onto a command buffer that records the calls.
+
+use gfx_backend_empty::{Backend as Empty, CommandBuffer as EmptyCommandBuffer};
+use gfx_hal::{
+    buffer,
+    command::{self, list::*},
+    format::Aspects,
+    image,
+    memory,
+    pso,
+    query,
+};
+
+use std::borrow::Borrow;
+use std::cell::RefCell;
+use std::ops::Range;
+
+/// Resolver logging the identifiers it resolves.
+#[derive(Default)]
+struct LogResolver {
+    resolved: RefCell<Vec<String>>,
+}
+
+impl LogResolver {
+    fn log(&self, id: impl std::fmt::Debug) {
+        self.resolved.borrow_mut().push(format!("{:?}", id));
+    }
+}
+
+impl Resolver<Empty> for LogResolver {
+    fn buffer(&self, id: BufferId) -> &() {
+        self.log(id);
+        &()
+    }
+    fn image(&self, id: ImageId) -> &() {
+        self.log(id);
+        &()
+    }
+    fn render_pass(&self, id: RenderPassId) -> &() {
+        self.log(id);
+        &()
+    }
+    fn framebuffer(&self, id: FramebufferId) -> &() {
+        self.log(id);
+        &()
+    }
+    fn graphics_pipeline(&self, id: GraphicsPipelineId) -> &() {
+        self.log(id);
+        &()
+    }
+    fn compute_pipeline(&self, id: ComputePipelineId) -> &() {
+        self.log(id);
+        &()
+    }
+    fn pipeline_layout(&self, id: PipelineLayoutId) -> &() {
+        self.log(id);
+        &()
+    }
+    fn descriptor_set(&self, id: DescriptorSetId) -> &() {
+        self.log(id);
+        &()
+    }
+    fn event(&self, id: EventId) -> &() {
+        self.log(id);
+        &()
+    }
+    fn query_pool(&self, id: QueryPoolId) -> &() {
+        self.log(id);
+        &()
+    }
+    fn command_buffer(&self, id: CommandBufferId) -> &EmptyCommandBuffer {
+        self.log(id);
+        &EmptyCommandBuffer
+    }
+}
+
+/// Command buffer recording the calls of the test, with their arguments.
+#[derive(Debug, Default)]
+struct Recorder {
+    calls: Vec<String>,
+}
+
+impl command::CommandBuffer<Empty> for Recorder {
+    unsafe fn begin(
+        &mut self,
+        _: command::CommandBufferFlags,
+        _: command::CommandBufferInheritanceInfo<Empty>,
+    ) {
+        unreachable!()
+    }
+
+    unsafe fn finish(&mut self) {
+        unreachable!()
+    }
+
+    unsafe fn reset(&mut self, _: bool) {
+        unreachable!()
+    }
+
+    unsafe fn pipeline_barrier<'a, T>(
+        &mut self,
+        stages: Range<pso::PipelineStage>,
+        dependencies: memory::Dependencies,
+        barriers: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<memory::Barrier<'a, Empty>>,
+    {
+        let barriers = barriers
+            .into_iter()
+            .map(|b| format!("{:?}", b.borrow()))
+            .collect::<Vec<_>>();
+        self.calls.push(format!(
+            "pipeline_barrier {:?} {:?} {:?}",
+            stages, dependencies, barriers
+        ));
+    }
+
+    unsafe fn fill_buffer(&mut self, _: &(), range: buffer::SubRange, data: u32) {
+        self.calls
+            .push(format!("fill_buffer {:?} {:#x}", range, data));
+    }
+
+    unsafe fn update_buffer(&mut self, _: &(), _: buffer::Offset, _: &[u8]) {
+        unreachable!()
+    }
+
+    unsafe fn clear_image<T>(&mut self, _: &(), _: image::Layout, _: command::ClearValue, _: T)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<image::SubresourceRange>,
+    {
+        unreachable!()
+    }
+
+    unsafe fn clear_attachments<T, U>(&mut self, clears: T, rects: U)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<command::AttachmentClear>,
+        U: IntoIterator,
+        U::Item: Borrow<pso::ClearRect>,
+    {
+        let clears = clears
+            .into_iter()
+            .map(|c| match *c.borrow() {
+                command::AttachmentClear::Color { index, value } => {
+                    format!("Color {} {:?}", index, value.uint32)
+                }
+                command::AttachmentClear::DepthStencil { depth, stencil } => {
+                    format!("DepthStencil {:?} {:?}", depth, stencil)
+                }
+            })
+            .collect::<Vec<_>>();
+        let rects = rects
+            .into_iter()
+            .map(|r| r.borrow().clone())
+            .collect::<Vec<_>>();
+        self.calls
+            .push(format!("clear_attachments {:?} {:?}", clears, rects));
+    }
+
+    unsafe fn resolve_image<T>(&mut self, _: &(), _: image::Layout, _: &(), _: image::Layout, _: T)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<command::ImageResolve>,
+    {
+        unreachable!()
+    }
+
+    unsafe fn blit_image<T>(
+        &mut self,
+        _: &(),
+        _: image::Layout,
+        _: &(),
+        _: image::Layout,
+        _: image::Filter,
+        _: T,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<command::ImageBlit>,
+    {
+        unreachable!()
+    }
+
+    unsafe fn bind_index_buffer(&mut self, _: buffer::IndexBufferView<Empty>) {
+        unreachable!()
+    }
+
+    unsafe fn bind_vertex_buffers<I, T>(&mut self, _: u32, _: I)
+    where
+        I: IntoIterator<Item = (T, buffer::SubRange)>,
+        T: Borrow<()>,
+    {
+        unreachable!()
+    }
+
+    unsafe fn set_viewports<T>(&mut self, _: u32, _: T)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<pso::Viewport>,
+    {
+        unreachable!()
+    }
+
+    unsafe fn set_scissors<T>(&mut self, _: u32, _: T)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<pso::Rect>,
+    {
+        unreachable!()
+    }
+
+    unsafe fn set_stencil_reference(&mut self, faces: pso::Face, value: pso::StencilValue) {
+        self.calls
+            .push(format!("set_stencil_reference {:?} {}", faces, value));
+    }
+
+    unsafe fn set_stencil_read_mask(&mut self, _: pso::Face, _: pso::StencilValue) {
+        unreachable!()
+    }
+
+    unsafe fn set_stencil_write_mask(&mut self, _: pso::Face, _: pso::StencilValue) {
+        unreachable!()
+    }
+
+    unsafe fn set_blend_constants(&mut self, color: pso::ColorValue) {
+        self.calls.push(format!("set_blend_constants {:?}", color));
+    }
+
+    unsafe fn set_depth_bounds(&mut self, _: Range<f32>) {
+        unreachable!()
+    }
+
+    unsafe fn set_line_width(&mut self, _: f32) {
+        unreachable!()
+    }
+
+    unsafe fn set_depth_bias(&mut self, _: pso::DepthBias) {
+        unreachable!()
+    }
+
+    unsafe fn begin_render_pass<T>(
+        &mut self,
+        _: &(),
+        _: &(),
+        render_area: pso::Rect,
+        clear_values: T,
+        first_subpass: command::SubpassContents,
+    ) where
+        T: IntoIterator,
+        T::Item: Borrow<command::ClearValue>,
+    {
+        let clear_values = clear_values
+            .into_iter()
+            .map(|v| v.borrow().color.float32)
+            .collect::<Vec<_>>();
+        self.calls.push(format!(
+            "begin_render_pass {:?} {:?} {:?}",
+            render_area, clear_values, first_subpass
+        ));
+    }
+
+    unsafe fn next_subpass(&mut self, _: command::SubpassContents) {
+        unreachable!()
+    }
+
+    unsafe fn end_render_pass(&mut self) {
+        self.calls.push("end_render_pass".to_string());
+    }
+
+    unsafe fn bind_graphics_pipeline(&mut self, _: &()) {
+        self.calls.push("bind_graphics_pipeline".to_string());
+    }
+
+    unsafe fn bind_graphics_descriptor_sets<I, J>(
+        &mut self,
+        _: &(),
+        first_set: usize,
+        sets: I,
+        offsets: J,
+    ) where
+        I: IntoIterator,
+        I::Item: Borrow<()>,
+        J: IntoIterator,
+        J::Item: Borrow<command::DescriptorSetOffset>,
+    {
+        let sets = sets.into_iter().count();
+        let offsets = offsets.into_iter().map(|o| *o.borrow()).collect::<Vec<_>>();
+        self.calls.push(format!(
+            "bind_graphics_descriptor_sets {} {} {:?}",
+            first_set, sets, offsets
+        ));
+    }
+
+    unsafe fn bind_compute_pipeline(&mut self, _: &()) {
+        unreachable!()
+    }
+
+    unsafe fn bind_compute_descriptor_sets<I, J>(&mut self, _: &(), _: usize, _: I, _: J)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<()>,
+        J: IntoIterator,
+        J::Item: Borrow<command::DescriptorSetOffset>,
+    {
+        unreachable!()
+    }
+
+    unsafe fn dispatch(&mut self, _: gfx_hal::WorkGroupCount) {
+        unreachable!()
+    }
+
+    unsafe fn dispatch_indirect(&mut self, _: &(), _: buffer::Offset) {
+        unreachable!()
+    }
+
+    unsafe fn copy_buffer<T>(&mut self, _: &(), _: &(), regions: T)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<command::BufferCopy>,
+    {
+        let regions = regions.into_iter().map(|r| *r.borrow()).collect::<Vec<_>>();
+        self.calls.push(format!("copy_buffer {:?}", regions));
+    }
+
+    unsafe fn copy_image<T>(&mut self, _: &(), _: image::Layout, _: &(), _: image::Layout, _: T)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<command::ImageCopy>,
+    {
+        unreachable!()
+    }
+
+    unsafe fn copy_buffer_to_image<T>(&mut self, _: &(), _: &(), _: image::Layout, _: T)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<command::BufferImageCopy>,
+    {
+        unreachable!()
+    }
+
+    unsafe fn copy_image_to_buffer<T>(&mut self, _: &(), _: image::Layout, _: &(), _: T)
+    where
+        T: IntoIterator,
+        T::Item: Borrow<command::BufferImageCopy>,
+    {
+        unreachable!()
+    }
+
+    unsafe fn draw(
+        &mut self,
+        vertices: Range<gfx_hal::VertexCount>,
+        instances: Range<gfx_hal::InstanceCount>,
+    ) {
+        self.calls
+            .push(format!("draw {:?} {:?}", vertices, instances));
+    }
+
+    unsafe fn draw_indexed(
+        &mut self,
+        _: Range<gfx_hal::IndexCount>,
+        _: gfx_hal::VertexOffset,
+        _: Range<gfx_hal::InstanceCount>,
+    ) {
+        unreachable!()
+    }
+
+    unsafe fn draw_indirect(&mut self, _: &(), _: buffer::Offset, _: gfx_hal::DrawCount, _: u32) {
+        unreachable!()
+    }
+
+    unsafe fn draw_indexed_indirect(
+        &mut self,
+        _: &(),
+        offset: buffer::Offset,
+        draw_count: gfx_hal::DrawCount,
+        stride: u32,
+    ) {
+        self.calls.push(format!(
+            "draw_indexed_indirect {} {} {}",
+            offset, draw_count, stride
+        ));
+    }
+
+    unsafe fn set_event(&mut self, _: &(), stages: pso::PipelineStage) {
+        self.calls.push(format!("set_event {:?}", stages));
+    }
+
+    unsafe fn reset_event(&mut self, _: &(), _: pso::PipelineStage) {
+        unreachable!()
+    }
+
+    unsafe fn wait_events<'a, I, J>(&mut self, _: I, _: Range<pso::PipelineStage>, _: J)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<()>,
+        J: IntoIterator,
+        J::Item: Borrow<memory::Barrier<'a, Empty>>,
+    {
+        unreachable!()
+    }
+
+    unsafe fn begin_query(&mut self, query: query::Query<Empty>, flags: query::ControlFlags) {
+        self.calls
+            .push(format!("begin_query {} {:?}", query.id, flags));
+    }
+
+    unsafe fn end_query(&mut self, query: query::Query<Empty>) {
+        self.calls.push(format!("end_query {}", query.id));
+    }
+
+    unsafe fn reset_query_pool(&mut self, _: &(), _: Range<query::Id>) {
+        unreachable!()
+    }
+
+    unsafe fn copy_query_pool_results(
+        &mut self,
+        _: &(),
+        _: Range<query::Id>,
+        _: &(),
+        _: buffer::Offset,
+        _: buffer::Offset,
+        _: query::ResultFlags,
+    ) {
+        unreachable!()
+    }
+
+    unsafe fn write_timestamp(&mut self, _: pso::PipelineStage, _: query::Query<Empty>) {
+        unreachable!()
+    }
+
+    unsafe fn push_graphics_constants(
+        &mut self,
+        _: &(),
+        stages: pso::ShaderStageFlags,
+        offset: u32,
+        constants: &[u32],
+    ) {
+        self.calls.push(format!(
+            "push_graphics_constants {:?} {} {:?}",
+            stages, offset, constants
+        ));
+    }
+
+    unsafe fn push_compute_constants(&mut self, _: &(), _: u32, _: &[u32]) {
+        unreachable!()
+    }
+
+    unsafe fn execute_commands<'a, T, I>(&mut self, cmd_buffers: I)
+    where
+        T: 'a + Borrow<EmptyCommandBuffer>,
+        I: IntoIterator<Item = &'a T>,
+    {
+        let count = cmd_buffers.into_iter().count();
+        self.calls.push(format!("execute_commands {}", count));
+    }
+
+    unsafe fn insert_debug_marker(&mut self, name: &str, color: u32) {
+        self.calls
+            .push(format!("insert_debug_marker {} {:#x}", name, color));
+    }
+
+    unsafe fn begin_debug_marker(&mut self, _: &str, _: u32) {
+        unreachable!()
+    }
+
+    unsafe fn end_debug_marker(&mut self) {
+        unreachable!()
+    }
+}
+
+#[test]
+fn test_replay() {
+    let mut list = CommandList::new();
+    list.pipeline_barrier(
+        pso::PipelineStage::TOP_OF_PIPE .. pso::PipelineStage::TRANSFER,
+        memory::Dependencies::empty(),
+        vec![
+            Barrier::AllBuffers(buffer::Access::empty() .. buffer::Access::TRANSFER_WRITE),
+            Barrier::Image {
+                states: (image::Access::empty(), image::Layout::Undefined)
+                    .. (
+                        image::Access::TRANSFER_WRITE,
+                        image::Layout::TransferDstOptimal,
+                    ),
+                target: ImageId(4),
+                range: image::SubresourceRange {
+                    aspects: Aspects::COLOR,
+                    levels: 0 .. 1,
+                    layers: 0 .. 1,
+                },
+                families: None,
+            },
+        ],
+    );
+    list.fill_buffer(
+        BufferId(0),
+        buffer::SubRange {
+            offset: 0,
+            size: Some(16),
+        },
+        0xDEAD_BEEF,
+    );
+    list.copy_buffer(
+        BufferId(0),
+        BufferId(1),
+        vec![command::BufferCopy {
+            src: 4,
+            dst: 8,
+            size: 12,
+        }],
+    );
+    list.begin_render_pass(
+        RenderPassId(2),
+        FramebufferId(3),
+        pso::Rect {
+            x: 0,
+            y: 0,
+            w: 16,
+            h: 8,
+        },
+        vec![command::ClearValue {
+            color: command::ClearColor {
+                float32: [0.25, 0.5, 0.75, 1.0],
+            },
+        }],
+        command::SubpassContents::Inline,
+    );
+    list.bind_graphics_pipeline(GraphicsPipelineId(1));
+    list.bind_graphics_descriptor_sets(
+        PipelineLayoutId(5),
+        1,
+        vec![DescriptorSetId(6), DescriptorSetId(7)],
+        vec![32],
+    );
+    list.push_graphics_constants(
+        PipelineLayoutId(5),
+        pso::ShaderStageFlags::FRAGMENT,
+        4,
+        &[1, 2],
+    );
+    list.set_stencil_reference(pso::Face::FRONT, 0x80);
+    list.set_blend_constants([0.5, 0.0, 0.0, 1.0]);
+    list.clear_attachments(
+        vec![command::AttachmentClear::Color {
+            index: 0,
+            value: command::ClearColor {
+                uint32: [1, 2, 3, 4],
+            },
+        }],
+        vec![pso::ClearRect {
+            rect: pso::Rect {
+                x: 1,
+                y: 1,
+                w: 2,
+                h: 2,
+            },
+            layers: 0 .. 1,
+        }],
+    );
+    list.begin_query(QueryPoolId(8), 1, query::ControlFlags::PRECISE);
+    list.draw(0 .. 3, 0 .. 2);
+    list.end_query(QueryPoolId(8), 1);
+    list.draw_indexed_indirect(BufferId(9), 20, 1, 20);
+    list.end_render_pass();
+    list.set_event(EventId(10), pso::PipelineStage::BOTTOM_OF_PIPE);
+    list.execute_commands(vec![CommandBufferId(11), CommandBufferId(12)]);
+    list.insert_debug_marker("done", 0xFF00_00FF);
+
+    let resolver = LogResolver::default();
+    let mut recorder = Recorder::default();
+    unsafe {
+        list.replay(&mut recorder, &resolver);
+    }
+
+    let expected_calls = [
+        "pipeline_barrier TOP_OF_PIPE..TRANSFER (empty) [\
+            \"AllBuffers((empty)..TRANSFER_WRITE)\", \
+            \"Image { states: ((empty), Undefined)..(TRANSFER_WRITE, TransferDstOptimal), \
+                target: (), range: SubresourceRange { aspects: COLOR, levels: 0..1, layers: 0..1 }, \
+                families: None }\"]",
+        "fill_buffer SubRange { offset: 0, size: Some(16) } 0xdeadbeef",
+        "copy_buffer [BufferCopy { src: 4, dst: 8, size: 12 }]",
+        "begin_render_pass Rect { x: 0, y: 0, w: 16, h: 8 } [[0.25, 0.5, 0.75, 1.0]] Inline",
+        "bind_graphics_pipeline",
+        "bind_graphics_descriptor_sets 1 2 [32]",
+        "push_graphics_constants FRAGMENT 4 [1, 2]",
+        "set_stencil_reference FRONT 128",
+        "set_blend_constants [0.5, 0.0, 0.0, 1.0]",
+        "clear_attachments [\"Color 0 [1, 2, 3, 4]\"] \
+            [ClearRect { rect: Rect { x: 1, y: 1, w: 2, h: 2 }, layers: 0..1 }]",
+        "begin_query 1 PRECISE",
+        "draw 0..3 0..2",
+        "end_query 1",
+        "draw_indexed_indirect 20 1 20",
+        "end_render_pass",
+        "set_event BOTTOM_OF_PIPE",
+        "execute_commands 2",
+        "insert_debug_marker done 0xff0000ff",
+    ];
+    assert_eq!(recorder.calls, expected_calls);
+
+    let expected_resolved = [
+        "ImageId(4)",
+        "BufferId(0)",
+        "BufferId(0)",
+        "BufferId(1)",
+        "RenderPassId(2)",
+        "FramebufferId(3)",
+        "GraphicsPipelineId(1)",
+        "PipelineLayoutId(5)",
+        "DescriptorSetId(6)",
+        "DescriptorSetId(7)",
+        "PipelineLayoutId(5)",
+        "QueryPoolId(8)",
+        "QueryPoolId(8)",
+        "BufferId(9)",
+        "EventId(10)",
+        "CommandBufferId(11)",
+        "CommandBufferId(12)",
+    ];
+    assert_eq!(*resolver.resolved.borrow(), expected_resolved);
+}
+
+#[test]
+fn test_replay_resources() {
+    let mut list = CommandList::new();
+    let mut resources = Resources::<Empty>::default();
+    let src = resources.insert_buffer(&());
+    let dst = resources.insert_buffer(&());
+    assert_eq!((src, dst), (BufferId(0), BufferId(1)));
+    list.copy_buffer(
+        src,
+        dst,
+        vec![command::BufferCopy {
+            src: 0,
+            dst: 0,
+            size: 4,
+        }],
+    );
+
+    let mut recorder = Recorder::default();
+    unsafe {
+        list.replay(&mut recorder, &resources);
+    }
+    assert_eq!(
+        recorder.calls,
+        ["copy_buffer [BufferCopy { src: 0, dst: 0, size: 4 }]"]
+    );
+}
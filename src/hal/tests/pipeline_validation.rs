@@ -0,0 +1,291 @@
+//! Validating pipeline descriptions against the limits of the empty backend.
+
+use gfx_backend_empty::{builtin_profile, Backend as Empty};
+use gfx_hal::{format::Format, pass, pso, Features, Limits};
+
+fn entry_point() -> pso::EntryPoint<'static, Empty> {
+    pso::EntryPoint {
+        entry: "main",
+        module: &(),
+        specialization: pso::Specialization::default(),
+    }
+}
+
+/// A valid description, with one vertex buffer and one attribute.
+fn graphics_desc() -> pso::GraphicsPipelineDesc<'static, Empty> {
+    let shaders = pso::GraphicsShaderSet {
+        vertex: entry_point(),
+        hull: None,
+        domain: None,
+        geometry: None,
+        fragment: None,
+    };
+    let subpass = pass::Subpass {
+        index: 0,
+        main_pass: &(),
+    };
+    let mut desc = pso::GraphicsPipelineDesc::new(
+        shaders,
+        pso::Primitive::TriangleList,
+        pso::Rasterizer::FILL,
+        &(),
+        subpass,
+    );
+    desc.vertex_buffers.push(pso::VertexBufferDesc {
+        binding: 0,
+        stride: 8,
+        rate: pso::VertexInputRate::Vertex,
+    });
+    desc.attributes.push(attribute(0, 0));
+    desc
+}
+
+fn attribute(location: pso::Location, binding: pso::BufferIndex) -> pso::AttributeDesc {
+    pso::AttributeDesc {
+        location,
+        binding,
+        element: pso::Element {
+            format: Format::Rg32Sfloat,
+            offset: 0,
+        },
+    }
+}
+
+fn violations(desc: &pso::GraphicsPipelineDesc<Empty>, limits: &Limits) -> Vec<pso::Violation> {
+    match pso::validate_graphics_pipeline(desc, Features::empty(), limits) {
+        Ok(()) => Vec::new(),
+        Err(error) => error.violations,
+    }
+}
+
+#[test]
+fn test_valid() {
+    let limits = builtin_profile().limits;
+    assert_eq!(
+        pso::validate_graphics_pipeline(&graphics_desc(), Features::empty(), &limits),
+        Ok(())
+    );
+}
+
+#[test]
+fn test_missing_features() {
+    let limits = builtin_profile().limits;
+    let mut desc = graphics_desc();
+    desc.shaders.geometry = Some(entry_point());
+    desc.rasterizer.polygon_mode = pso::PolygonMode::Line;
+    desc.rasterizer.depth_clamping = true;
+    desc.blender.logic_op = Some(pso::LogicOp::Xor);
+    desc.blender.targets.push(pso::ColorBlendDesc {
+        mask: pso::ColorMask::ALL,
+        blend: Some(pso::BlendState {
+            color: pso::BlendOp::Add {
+                src: pso::Factor::Src1Color,
+                dst: pso::Factor::Zero,
+            },
+            alpha: pso::BlendOp::REPLACE,
+        }),
+    });
+    desc.vertex_buffers[0].rate = pso::VertexInputRate::Instance(2);
+
+    let missing: Vec<_> = violations(&desc, &limits)
+        .into_iter()
+        .filter_map(|v| match v {
+            pso::Violation::MissingFeature { feature, .. } => Some(feature),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        missing,
+        vec![
+            Features::GEOMETRY_SHADER,
+            Features::INSTANCE_RATE,
+            Features::NON_FILL_POLYGON_MODE,
+            Features::DEPTH_CLAMP,
+            Features::LOGIC_OP,
+            Features::DUAL_SRC_BLENDING,
+        ]
+    );
+
+    let all = Features::GEOMETRY_SHADER
+        | Features::INSTANCE_RATE
+        | Features::NON_FILL_POLYGON_MODE
+        | Features::DEPTH_CLAMP
+        | Features::LOGIC_OP
+        | Features::DUAL_SRC_BLENDING;
+    let mut limits = limits;
+    limits.max_fragment_dual_source_attachments = 1;
+    assert_eq!(pso::validate_graphics_pipeline(&desc, all, &limits), Ok(()));
+}
+
+#[test]
+fn test_limits_exceeded() {
+    let limits = builtin_profile().limits;
+    let mut desc = graphics_desc();
+    desc.vertex_buffers[0].stride = limits.max_vertex_input_binding_stride as pso::ElemStride + 1;
+    desc.attributes[0].element.offset =
+        limits.max_vertex_input_attribute_offset as pso::ElemOffset + 1;
+    desc.attributes
+        .push(attribute(limits.max_vertex_input_attributes as _, 0));
+
+    assert_eq!(
+        violations(&desc, &limits),
+        vec![
+            pso::Violation::LimitExceeded {
+                limit: "max_vertex_input_binding_stride",
+                value: limits.max_vertex_input_binding_stride as u64 + 1,
+                max: limits.max_vertex_input_binding_stride as u64,
+            },
+            pso::Violation::LimitExceeded {
+                limit: "max_vertex_input_attribute_offset",
+                value: limits.max_vertex_input_attribute_offset as u64 + 1,
+                max: limits.max_vertex_input_attribute_offset as u64,
+            },
+            pso::Violation::LimitExceeded {
+                limit: "max_vertex_input_attributes",
+                value: limits.max_vertex_input_attributes as u64 + 1,
+                max: limits.max_vertex_input_attributes as u64,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_vertex_input() {
+    let limits = builtin_profile().limits;
+    let mut desc = graphics_desc();
+    desc.vertex_buffers.push(desc.vertex_buffers[0]);
+    desc.attributes.push(attribute(0, 0));
+    desc.attributes.push(attribute(1, 2));
+
+    assert_eq!(
+        violations(&desc, &limits),
+        vec![
+            pso::Violation::DuplicateBinding(0),
+            pso::Violation::DuplicateLocation(0),
+            pso::Violation::UndefinedBinding {
+                location: 1,
+                binding: 2,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_tessellation() {
+    let mut limits = builtin_profile().limits;
+    limits.max_patch_size = 4;
+    let features = Features::TESSELLATION_SHADER;
+
+    let mut desc = graphics_desc();
+    desc.input_assembler.primitive = pso::Primitive::PatchList(3);
+    assert_eq!(
+        pso::validate_graphics_pipeline(&desc, features, &limits)
+            .unwrap_err()
+            .violations,
+        vec![pso::Violation::TessellationMismatch]
+    );
+
+    desc.shaders.hull = Some(entry_point());
+    desc.shaders.domain = Some(entry_point());
+    assert_eq!(
+        pso::validate_graphics_pipeline(&desc, features, &limits),
+        Ok(())
+    );
+
+    desc.input_assembler.primitive = pso::Primitive::TriangleList;
+    assert_eq!(
+        pso::validate_graphics_pipeline(&desc, features, &limits)
+            .unwrap_err()
+            .violations,
+        vec![pso::Violation::TessellationMismatch]
+    );
+}
+
+#[test]
+fn test_sample_count() {
+    let limits = builtin_profile().limits;
+    let mut desc = graphics_desc();
+    let multisampling = |samples| pso::Multisampling {
+        rasterization_samples: samples,
+        sample_shading: None,
+        sample_mask: !0,
+        alpha_coverage: false,
+        alpha_to_one: false,
+    };
+
+    desc.multisampling = Some(multisampling(1));
+    assert_eq!(violations(&desc, &limits), vec![]);
+    // 3 shares the bit of the supported single sample, but isn't a sample count.
+    desc.multisampling = Some(multisampling(3));
+    assert_eq!(
+        violations(&desc, &limits),
+        vec![pso::Violation::UnsupportedSampleCount(3)]
+    );
+    desc.multisampling = Some(multisampling(4));
+    assert_eq!(
+        violations(&desc, &limits),
+        vec![pso::Violation::UnsupportedSampleCount(4)]
+    );
+}
+
+#[test]
+fn test_specialization() {
+    let limits = builtin_profile().limits;
+    let mut desc = graphics_desc();
+    desc.shaders.vertex.specialization.push(5, 1u32);
+    desc.shaders.vertex.specialization.data.to_mut().pop();
+    assert_eq!(
+        violations(&desc, &limits),
+        vec![pso::Violation::InvalidSpecialization {
+            stage: pso::ShaderStageFlags::VERTEX,
+            id: 5,
+        }]
+    );
+}
+
+#[test]
+fn test_compute() {
+    let limits = builtin_profile().limits;
+    let mut specialization = pso::Specialization::default();
+    specialization.push(0, 1u32);
+    specialization.data.to_mut().pop();
+    let compute = pso::ComputePipelineDesc::<Empty>::new(
+        pso::EntryPoint {
+            entry: "main",
+            module: &(),
+            specialization,
+        },
+        &(),
+    );
+    let push_constants = [(
+        pso::ShaderStageFlags::COMPUTE,
+        0 .. limits.max_push_constants_size as u32 + 4,
+    )];
+    assert_eq!(
+        pso::validate_compute_pipeline(&compute, &push_constants, Features::empty(), &limits)
+            .unwrap_err()
+            .violations,
+        vec![
+            pso::Violation::InvalidSpecialization {
+                stage: pso::ShaderStageFlags::COMPUTE,
+                id: 0,
+            },
+            pso::Violation::LimitExceeded {
+                limit: "max_push_constants_size",
+                value: limits.max_push_constants_size as u64 + 4,
+                max: limits.max_push_constants_size as u64,
+            },
+        ]
+    );
+
+    // Ranges of other stages don't count.
+    let push_constants = [(
+        pso::ShaderStageFlags::VERTEX,
+        0 .. limits.max_push_constants_size as u32 + 4,
+    )];
+    let compute = pso::ComputePipelineDesc::<Empty>::new(entry_point(), &());
+    assert_eq!(
+        pso::validate_compute_pipeline(&compute, &push_constants, Features::empty(), &limits),
+        Ok(())
+    );
+}
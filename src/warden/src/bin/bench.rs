@@ -5,6 +5,7 @@
         feature = "dx11",
         feature = "metal",
         feature = "gl",
+        feature = "software",
     )),
     allow(dead_code)
 )]
@@ -14,11 +15,23 @@ extern crate gfx_warden as warden;
 extern crate serde;
 
 use hal::{adapter::PhysicalDevice as _, Instance as _};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
+use std::io;
 use std::path::PathBuf;
+use std::time::Instant;
 
 use ron::de;
+use warden::disabilities::Disabilities;
+use warden::stats::{self, BenchReport, Clock, Stats};
+
+macro_rules! progress {
+    ($harness:expr, $($arg:tt)*) => {
+        if !$harness.quiet {
+            print!($($arg)*);
+        }
+    };
+}
 
 #[derive(Debug, Deserialize)]
 enum Expectation {
@@ -33,7 +46,12 @@ struct Test {
 
 #[derive(Debug, Deserialize)]
 struct RawTestGroup {
-    features: Vec<warden::Feature>,
+    #[serde(default = "hal::Features::empty")]
+    features: hal::Features,
+    #[serde(default = "hal::Hints::empty")]
+    hints: hal::Hints,
+    #[serde(default)]
+    limits: BTreeMap<String, serde_json::Value>,
     tests: HashMap<String, Test>,
 }
 
@@ -43,21 +61,44 @@ struct TestGroup {
     name: String,
     scene: warden::raw::Scene,
     tests: HashMap<String, Test>,
-    features: hal::Features,
+    requirements: warden::Requirements,
 }
 
-#[derive(Default)]
-struct Disabilities {}
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Text,
+    Json,
+}
+
+struct Options {
+    suite: String,
+    /// Runs of each test before the measured ones.
+    warmup: usize,
+    /// Measured runs of each test.
+    iterations: usize,
+    /// Nanoseconds per tick of the device timestamps.
+    timestamp_period: f32,
+    format: Format,
+}
 
 struct Harness {
     base_path: PathBuf,
     suite: Vec<TestGroup>,
+    warmup: usize,
+    iterations: usize,
+    timestamp_period: f32,
+    /// Don't print the progress, as the results go to the standard output.
+    quiet: bool,
 }
 
 impl Harness {
-    fn new(suite_name: &str) -> Self {
+    fn new(options: &Options) -> Self {
+        let suite_name = &options.suite;
+        let quiet = options.format != Format::Text;
         let base_path = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/../../work"));
-        println!("Parsing test suite '{}'...", suite_name);
+        if !quiet {
+            println!("Parsing test suite '{}'...", suite_name);
+        }
 
         let suite_path = base_path
             .join("benches")
@@ -72,73 +113,108 @@ impl Harness {
                 let path = base_path.join("scenes").join(&name).with_extension("ron");
                 let scene = File::open(path)
                     .map_err(de::Error::from)
-                    .and_then(de::from_reader)
+                    .and_then(de::from_reader::<_, warden::raw::Scene>)
                     .expect("failed to open/parse the scene");
-                let features = raw_group
-                    .features
-                    .into_iter()
-                    .fold(hal::Features::empty(), |u, f| u | f.into_hal());
+                // Limits unknown to `hal::Limits` would skip the group everywhere.
+                let mut errors = warden::lint::lint(&scene);
+                errors.extend(warden::lint::lint_limits(
+                    &format!("suite[{:?}].limits", name),
+                    &raw_group.limits,
+                ));
+                if !errors.is_empty() {
+                    let errors = errors
+                        .iter()
+                        .map(|e| format!("\n\t{}", e))
+                        .collect::<String>();
+                    panic!("invalid test group '{}':{}", name, errors);
+                }
+                let mut requirements = scene.requirements();
+                requirements.merge(&warden::Requirements {
+                    features: raw_group.features,
+                    hints: raw_group.hints,
+                    limits: raw_group.limits,
+                });
                 TestGroup {
                     name,
                     scene,
                     tests: raw_group.tests,
-                    features,
+                    requirements,
                 }
             })
             .collect();
 
-        Harness { base_path, suite }
+        Harness {
+            base_path,
+            suite,
+            warmup: options.warmup,
+            iterations: options.iterations,
+            timestamp_period: options.timestamp_period,
+            quiet,
+        }
     }
 
-    fn run<B: hal::Backend>(&self, name: &str, disabilities: Disabilities) {
-        println!("Benching {}:", name);
+    fn run<B: hal::Backend>(&self, name: &str, disabilities: &Disabilities) -> Vec<BenchReport> {
+        progress!(self, "Benching {}:\n", name);
         let instance = B::Instance::create("warden", 1).unwrap();
-        self.run_instance(instance, disabilities)
+        self.run_instance(name, instance, disabilities)
     }
 
     fn run_instance<B: hal::Backend, I: hal::Instance<B>>(
         &self,
+        backend_name: &str,
         instance: I,
-        _disabilities: Disabilities,
-    ) {
+        disabilities: &Disabilities,
+    ) -> Vec<BenchReport> {
+        let mut reports = Vec::new();
         for tg in &self.suite {
             let mut adapters = instance.enumerate_adapters();
             let adapter = adapters.remove(0);
+            let info = adapter.info.clone();
             let supported_features = adapter.physical_device.features();
+            let hints = adapter.physical_device.hints();
             let limits = adapter.physical_device.limits();
             //println!("\t{:?}", adapter.info);
-            println!("\tScene '{}':", tg.name);
+            progress!(self, "\tScene '{}':\n", tg.name);
 
             #[cfg(not(feature = "glsl-to-spirv"))]
             {
+                let data_path = self.base_path.join("data");
                 let all_spirv = tg.scene.resources.values().all(|res| match *res {
-                    warden::raw::Resource::Shader(ref name) => name.ends_with(".spirv"),
+                    warden::raw::Resource::Shader(ref name) => {
+                        warden::gpu::spirv_path(&data_path, name).is_some()
+                    }
                     _ => true,
                 });
                 if !all_spirv {
-                    println!("\t\tskipped {} tests (GLSL shaders)", tg.tests.len());
+                    progress!(
+                        self,
+                        "\t\tskipped {} tests (GLSL shaders)\n",
+                        tg.tests.len()
+                    );
                     continue;
                 }
             }
 
-            if !supported_features.contains(tg.features) {
-                println!(
-                    "\tskipped (features missing: {:?})",
-                    tg.features - supported_features
-                );
+            if let Err(unmet) = tg.requirements.check(supported_features, hints, &limits) {
+                progress!(self, "\tskipped ({})\n", unmet);
                 continue;
             }
 
             let mut scene = warden::gpu::Scene::<B>::new(
                 adapter,
-                tg.features,
+                tg.requirements.features,
                 &tg.scene,
                 self.base_path.join("data"),
             )
             .unwrap();
 
             for (test_name, test) in &tg.tests {
-                print!("\t\tTest '{}' ...", test_name);
+                progress!(self, "\t\tTest '{}' ...", test_name);
+                // Known failures are not worth measuring either.
+                if let Some(rule) = disabilities.find(backend_name, &info, &tg.name, test_name) {
+                    progress!(self, "\tskipped (disabled: {})\n", rule.reason);
+                    continue;
+                }
                 let mut max_compute_work_groups = [0; 3];
                 for job_name in &test.jobs {
                     if let warden::raw::Job::Compute { dispatch, .. } = tg.scene.jobs[job_name] {
@@ -152,52 +228,158 @@ impl Harness {
                     || max_compute_work_groups[1] > limits.max_compute_work_group_size[1]
                     || max_compute_work_groups[2] > limits.max_compute_work_group_size[2]
                 {
-                    println!("\tskipped (compute {:?})", max_compute_work_groups);
+                    progress!(self, "\tskipped (compute {:?})\n", max_compute_work_groups);
                     continue;
                 }
 
-                scene.run(test.jobs.iter());
-                let time = scene.measure_time();
-                println!(" {} mcs", time / 1000);
+                for _ in 0 .. self.warmup {
+                    scene.run(test.jobs.iter());
+                    scene.measure_time(self.timestamp_period);
+                }
+                let mut gpu_samples = Vec::with_capacity(self.iterations);
+                let mut cpu_samples = Vec::with_capacity(self.iterations);
+                for _ in 0 .. self.iterations {
+                    let start = Instant::now();
+                    scene.run(test.jobs.iter());
+                    let gpu_time = scene.measure_time(self.timestamp_period);
+                    cpu_samples.push(start.elapsed());
+                    gpu_samples.extend(gpu_time);
+                }
+                // Don't mix the clocks if only some of the timestamps were available.
+                let (clock, samples) = if gpu_samples.len() == cpu_samples.len() {
+                    (Clock::Gpu, gpu_samples)
+                } else {
+                    (Clock::Cpu, cpu_samples)
+                };
+                let stats = Stats::new(&samples).unwrap();
+                match clock {
+                    Clock::Gpu => progress!(self, " {}\n", stats),
+                    Clock::Cpu => progress!(self, " {} [CPU clock]\n", stats),
+                }
+                reports.push(BenchReport {
+                    backend: backend_name.to_string(),
+                    scene: tg.name.clone(),
+                    test: test_name.clone(),
+                    clock,
+                    stats,
+                });
             }
         }
+
+        reports
     }
 }
 
 fn main() {
-    use std::env;
+    use std::{env, process};
 
     #[cfg(feature = "env_logger")]
     env_logger::init();
+    #[allow(unused_mut)]
+    let mut reports = Vec::<BenchReport>::new();
 
-    let suite_name = match env::args().nth(1) {
-        Some(name) => name,
-        None => {
+    let mut suite = None;
+    let mut warmup = 3;
+    let mut iterations = 10;
+    let mut timestamp_period = 1.0;
+    let mut format = Format::Text;
+    let mut baseline = None;
+    let mut threshold = 10.0;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next().unwrap_or_else(|| {
+                println!("Option '{}' requires a value", arg);
+                process::exit(-1);
+            })
+        };
+        fn parse<T: std::str::FromStr>(arg: &str, value: String) -> T {
+            value.parse().unwrap_or_else(|_| {
+                println!("Invalid value '{}' of option '{}'", value, arg);
+                process::exit(-1);
+            })
+        }
+        match arg.as_str() {
+            "--warmup" => warmup = parse(&arg, value()),
+            "--iterations" => iterations = parse(&arg, value()),
+            "--timestamp-period" => timestamp_period = parse(&arg, value()),
+            "--baseline" => baseline = Some(PathBuf::from(value())),
+            "--threshold" => threshold = parse(&arg, value()),
+            "--format" => {
+                format = match value().as_str() {
+                    "text" => Format::Text,
+                    "json" => Format::Json,
+                    other => {
+                        println!("Unknown format '{}'", other);
+                        process::exit(-1);
+                    }
+                }
+            }
+            _ if arg.starts_with("--") => {
+                println!("Unknown option '{}'", arg);
+                process::exit(-1);
+            }
+            _ => suite = Some(arg),
+        }
+    }
+    let suite = match suite {
+        Some(name) if iterations > 0 => name,
+        _ => {
             println!("Call with the argument of the bench suite name");
+            println!("Options:");
+            println!("\t--warmup <count>\truns of each test before measuring, 3 by default");
+            println!("\t--iterations <count>\tmeasured runs of each test, 10 by default");
+            println!("\t--timestamp-period <ns>\tnanoseconds per tick of the device timestamps, 1 by default");
+            println!("\t--format text|json\tformat of the results");
+            println!("\t--baseline <file>\tcompare against the JSON results of a previous run");
+            println!("\t--threshold <percent>\tslowdown of the median reported as a regression, 10 by default");
             return;
         }
     };
+    // Read the baseline early, rather than failing after all the measurements.
+    let baseline = baseline.map(|path| {
+        File::open(&path)
+            .map_err(serde_json::Error::io)
+            .and_then(stats::read_json)
+            .unwrap_or_else(|e| {
+                println!("Unable to read the baseline {:?}: {}", path, e);
+                process::exit(-1);
+            })
+    });
 
-    let harness = Harness::new(&suite_name);
+    let harness = Harness::new(&Options {
+        suite,
+        warmup,
+        iterations,
+        timestamp_period,
+        format,
+    });
+    let disabilities_path = harness.base_path.join("benches").join("disabilities.ron");
+    let disabilities = Disabilities::load(&disabilities_path)
+        .unwrap_or_else(|e| panic!("Unable to parse {:?}: {}", disabilities_path, e));
     #[cfg(feature = "vulkan")]
     {
-        harness.run::<gfx_backend_vulkan::Backend>("Vulkan", Disabilities::default());
+        reports.extend(harness.run::<gfx_backend_vulkan::Backend>("Vulkan", &disabilities));
     }
     #[cfg(feature = "dx12")]
     {
-        harness.run::<gfx_backend_dx12::Backend>("DX12", Disabilities::default());
+        reports.extend(harness.run::<gfx_backend_dx12::Backend>("DX12", &disabilities));
     }
     #[cfg(feature = "dx11")]
     {
-        harness.run::<gfx_backend_dx11::Backend>("DX11", Disabilities::default());
+        reports.extend(harness.run::<gfx_backend_dx11::Backend>("DX11", &disabilities));
     }
     #[cfg(feature = "metal")]
     {
-        harness.run::<gfx_backend_metal::Backend>("Metal", Disabilities::default());
+        reports.extend(harness.run::<gfx_backend_metal::Backend>("Metal", &disabilities));
     }
     #[cfg(feature = "gl")]
     {
-        harness.run::<gfx_backend_gl::Backend>("GL", Disabilities::default());
+        reports.extend(harness.run::<gfx_backend_gl::Backend>("GL", &disabilities));
+    }
+    #[cfg(feature = "software")]
+    {
+        reports.extend(harness.run::<gfx_backend_software::Backend>("Software", &disabilities));
     }
     #[cfg(not(any(
         feature = "vulkan",
@@ -205,9 +387,27 @@ fn main() {
         feature = "dx11",
         feature = "metal",
         feature = "gl",
+        feature = "software",
     )))]
     {
         println!("No backend selected!");
-        let _ = harness;
+    }
+    let _ = (harness, disabilities);
+
+    if format == Format::Json {
+        if let Err(e) = stats::write_json(&reports, io::stdout()) {
+            println!("Unable to write the results: {}", e);
+            process::exit(-1);
+        }
+    }
+    if let Some(baseline) = baseline {
+        // The standard output may hold the JSON results.
+        let regressions = stats::find_regressions(&reports, &baseline, threshold / 100.0);
+        for regression in &regressions {
+            eprintln!("Regression: {}", regression);
+        }
+        if !regressions.is_empty() {
+            process::exit(1);
+        }
     }
 }
@@ -5,6 +5,7 @@
         feature = "dx11",
         feature = "metal",
         feature = "gl",
+        feature = "software",
     )),
     allow(dead_code)
 )]
@@ -13,18 +14,45 @@ extern crate gfx_warden as warden;
 #[macro_use]
 extern crate serde;
 
-use hal::{adapter::PhysicalDevice as _, Instance as _};
-use std::collections::HashMap;
+use hal::{adapter::PhysicalDevice as _, format::SurfaceType as St, Instance as _};
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
-use std::path::PathBuf;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use ron::de;
+use warden::disabilities::{Disabilities, Disability};
+use warden::golden;
+use warden::report::{self, Filter, Outcome, SkipReason, TestReport};
 
+macro_rules! progress {
+    ($harness:expr, $($arg:tt)*) => {
+        if !$harness.quiet {
+            print!($($arg)*);
+        }
+    };
+}
 
 #[derive(Debug, Deserialize)]
 enum Expectation {
     Buffer(String, Vec<u8>),
     ImageRow(String, usize, Vec<u8>),
+    /// Whole image against a golden PNG in the data directory.
+    Image {
+        image: String,
+        golden: String,
+        #[serde(default)]
+        tolerance: golden::Tolerance,
+    },
+    /// Hash of the whole buffer contents.
+    BufferHash(String, u64),
+    /// Results of a range of queries of a pool, with all the values of
+    /// pipeline statistics queries in turn.
+    Queries(String, Range<hal::query::Id>, Vec<u64>),
+    /// Results of a range of queries never decreasing, e.g. timestamps.
+    QueriesOrdered(String, Range<hal::query::Id>),
 }
 
 #[derive(Debug, Deserialize)]
@@ -35,8 +63,13 @@ struct Test {
 
 #[derive(Debug, Deserialize)]
 struct RawTestGroup {
-    features: Vec<warden::Feature>,
-    tests: HashMap<String, Test>,
+    #[serde(default = "hal::Features::empty")]
+    features: hal::Features,
+    #[serde(default = "hal::Hints::empty")]
+    hints: hal::Hints,
+    #[serde(default)]
+    limits: BTreeMap<String, serde_json::Value>,
+    tests: BTreeMap<String, Test>,
 }
 
 type Suite = HashMap<String, RawTestGroup>;
@@ -44,8 +77,8 @@ type Suite = HashMap<String, RawTestGroup>;
 struct TestGroup {
     name: String,
     scene: warden::raw::Scene,
-    tests: HashMap<String, Test>,
-    features: hal::Features,
+    tests: BTreeMap<String, Test>,
+    requirements: warden::Requirements,
 }
 
 #[derive(Debug)]
@@ -53,108 +86,376 @@ struct TestResults {
     pass: usize,
     skip: usize,
     fail: usize,
+    xfail: usize,
+    xpass: usize,
 }
 
-#[derive(Default)]
-struct Disabilities {}
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Text,
+    Junit,
+    Json,
+}
+
+struct Options {
+    suite: String,
+    /// Regenerate the golden images instead of comparing against them.
+    bless: bool,
+    /// Directory receiving the actual and diff images of failed tests.
+    output: Option<PathBuf>,
+    format: Format,
+    filter: Filter,
+    /// Only list the selected tests.
+    list: bool,
+}
 
 struct Harness {
     base_path: PathBuf,
+    output_path: PathBuf,
+    bless: bool,
+    /// Only the results are printed, in a machine-readable format.
+    quiet: bool,
+    filter: Filter,
     suite: Vec<TestGroup>,
 }
 
+fn fetch_image<B: hal::Backend>(
+    scene: &mut warden::gpu::Scene<B>,
+    raw_scene: &warden::raw::Scene,
+    name: &str,
+) -> Result<golden::Image, String> {
+    let (extent, format) = match raw_scene.resources[name] {
+        warden::raw::Resource::Image {
+            ref kind, format, ..
+        } => (kind.extent(), format),
+        _ => panic!("Resource '{}' is not an image", name),
+    };
+    let surface = format.base_format().0;
+    let bgr = match surface {
+        St::R8 | St::R8_G8 | St::R8_G8_B8 | St::R8_G8_B8_A8 => false,
+        St::B8_G8_R8 | St::B8_G8_R8_A8 => true,
+        _ => {
+            return Err(format!(
+                "image '{}' has format {:?}, only formats with 8 bits per channel can be compared",
+                name, format
+            ))
+        }
+    };
+    let channels = surface.desc().bits as u32 / 8;
+    let width_bytes = (extent.width * channels) as usize;
+    let height = extent.height * extent.depth;
+    let guard = scene.fetch_image(name);
+    let mut data = (0 .. height as usize)
+        .flat_map(|y| guard.row(y)[.. width_bytes].iter().cloned())
+        .collect::<Vec<_>>();
+    if bgr {
+        for texel in data.chunks_mut(channels as usize) {
+            texel.swap(0, 2);
+        }
+    }
+    Ok(golden::Image {
+        width: extent.width,
+        height,
+        channels: channels as u8,
+        data,
+    })
+}
+
 impl Harness {
-    fn new(suite_name: &str) -> Self {
+    fn new(options: &Options) -> Self {
+        let suite_name = &options.suite;
         let base_path = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/../../work"));
-        println!("Parsing test suite '{}'...", suite_name);
+        let quiet = options.format != Format::Text || options.list;
+        if !quiet {
+            println!("Parsing test suite '{}'...", suite_name);
+        }
 
         let suite_path = base_path
             .join("reftests")
             .join(suite_name)
             .with_extension("ron");
-        let suite = File::open(&suite_path)
+        let mut suite = File::open(&suite_path)
             .map_err(de::Error::from)
             .and_then(de::from_reader::<_, Suite>)
             .expect(&format!("failed to open/parse the suite: {:?}", suite_path))
             .into_iter()
+            .filter(|(name, _)| options.filter.selects_scene(name))
             .map(|(name, raw_group)| {
                 let path = base_path.join("scenes").join(&name).with_extension("ron");
                 let scene = File::open(path)
                     .map_err(de::Error::from)
-                    .and_then(de::from_reader)
+                    .and_then(de::from_reader::<_, warden::raw::Scene>)
                     .expect(&format!("failed to open/parse the scene '{:?}'", name));
-                let features = raw_group
-                    .features
-                    .into_iter()
-                    .fold(hal::Features::empty(), |u, f| u | f.into_hal());
+                // Limits unknown to `hal::Limits` would skip the group everywhere.
+                let mut errors = warden::lint::lint(&scene);
+                errors.extend(warden::lint::lint_limits(
+                    &format!("suite[{:?}].limits", name),
+                    &raw_group.limits,
+                ));
+                if !errors.is_empty() {
+                    let errors = errors
+                        .iter()
+                        .map(|e| format!("\n\t{}", e))
+                        .collect::<String>();
+                    panic!("invalid test group '{}':{}", name, errors);
+                }
+                let mut requirements = scene.requirements();
+                requirements.merge(&warden::Requirements {
+                    features: raw_group.features,
+                    hints: raw_group.hints,
+                    limits: raw_group.limits,
+                });
                 TestGroup {
                     name,
                     scene,
                     tests: raw_group.tests,
-                    features,
+                    requirements,
                 }
             })
-            .collect();
+            .collect::<Vec<_>>();
+        suite.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let output_path = options
+            .output
+            .clone()
+            .unwrap_or_else(|| base_path.join("output"));
+        Harness {
+            base_path,
+            output_path,
+            bless: options.bless,
+            quiet,
+            filter: options.filter.clone(),
+            suite,
+        }
+    }
+
+    /// Check the expectation of a test, returning the failure details.
+    fn check<B: hal::Backend>(
+        &self,
+        scene: &mut warden::gpu::Scene<B>,
+        raw_scene: &warden::raw::Scene,
+        expect: &Expectation,
+        output: &Path,
+    ) -> Result<(), String> {
+        let (guard, row, data) = match *expect {
+            Expectation::Buffer(ref buffer, ref data) => (scene.fetch_buffer(buffer), 0, data),
+            Expectation::ImageRow(ref image, row, ref data) => {
+                (scene.fetch_image(image), row, data)
+            }
+            Expectation::Image {
+                ref image,
+                golden: ref golden_name,
+                tolerance,
+            } => {
+                let actual = fetch_image(scene, raw_scene, image)?;
+                let golden_path = self.base_path.join("data").join(golden_name);
+                if self.bless {
+                    return actual
+                        .save(&golden_path)
+                        .map_err(|e| format!("unable to bless {:?}: {}", golden_path, e));
+                }
+                let golden = golden::Image::load(&golden_path)
+                    .map_err(|e| format!("unable to load {:?}: {}", golden_path, e))?;
+                let test_name = output.file_name().unwrap().to_string_lossy();
+                let actual_path = output.with_file_name(format!("{}.actual.png", test_name));
+                return match golden::compare(&actual, &golden, tolerance) {
+                    Ok(()) => Ok(()),
+                    Err(golden::Mismatch::Layout {
+                        expected,
+                        actual: layout,
+                    }) => {
+                        let details = format!(
+                            "size {:?} instead of {:?} (width, height, channels)",
+                            layout, expected
+                        );
+                        Err(match actual.save(&actual_path) {
+                            Ok(()) => format!("{}, see {:?}", details, actual_path),
+                            Err(e) => format!("{} (unable to write image: {})", details, e),
+                        })
+                    }
+                    Err(golden::Mismatch::Pixels {
+                        count,
+                        max_difference,
+                        diff,
+                    }) => {
+                        let diff_path = output.with_file_name(format!("{}.diff.png", test_name));
+                        let saved = actual
+                            .save(&actual_path)
+                            .and_then(|_| diff.save(&diff_path));
+                        Err(match saved {
+                            Ok(()) => format!(
+                                "{} pixels differ by up to {}, see {:?}",
+                                count, max_difference, diff_path
+                            ),
+                            Err(e) => format!(
+                                "{} pixels differ by up to {} (unable to write images: {})",
+                                count, max_difference, e
+                            ),
+                        })
+                    }
+                };
+            }
+            Expectation::BufferHash(ref buffer, hash) => {
+                let guard = scene.fetch_buffer(buffer);
+                let actual = golden::hash(guard.row(0));
+                return if actual == hash {
+                    Ok(())
+                } else {
+                    Err(format!("hash {} instead of {}", actual, hash))
+                };
+            }
+            Expectation::Queries(ref pool, ref queries, ref results) => {
+                let actual = scene.fetch_queries(pool, queries.clone());
+                return if actual == *results {
+                    Ok(())
+                } else {
+                    Err(format!("{:?}", actual))
+                };
+            }
+            Expectation::QueriesOrdered(ref pool, ref queries) => {
+                let actual = scene.fetch_queries(pool, queries.clone());
+                return if actual.windows(2).all(|pair| pair[0] <= pair[1]) {
+                    Ok(())
+                } else {
+                    Err(format!("{:?} are not in order", actual))
+                };
+            }
+        };
 
-        Harness { base_path, suite }
+        if data.as_slice() == guard.row(row) {
+            Ok(())
+        } else {
+            Err(format!("{:?}", guard.row(row)))
+        }
+    }
+
+    /// Names of the selected tests, as `scene/test`.
+    fn list(&self) -> Vec<String> {
+        self.suite
+            .iter()
+            .flat_map(|tg| {
+                tg.tests
+                    .keys()
+                    .filter(move |test| self.filter.selects(&tg.name, test))
+                    .map(move |test| format!("{}/{}", tg.name, test))
+            })
+            .collect()
     }
 
-    fn run<B: hal::Backend>(&self, name: &str, disabilities: Disabilities) -> usize {
-        println!("Testing {}:", name);
+    fn run<B: hal::Backend>(&self, name: &str, disabilities: &Disabilities) -> Vec<TestReport> {
+        progress!(self, "Testing {}:\n", name);
         let instance = B::Instance::create("warden", 1).unwrap();
-        self.run_instance(instance, disabilities)
+        self.run_instance(name, instance, disabilities)
     }
 
     fn run_instance<B: hal::Backend, I: hal::Instance<B>>(
         &self,
+        backend_name: &str,
         instance: I,
-        _disabilities: Disabilities,
-    ) -> usize {
+        disabilities: &Disabilities,
+    ) -> Vec<TestReport> {
         let mut results = TestResults {
             pass: 0,
             skip: 0,
             fail: 0,
+            xfail: 0,
+            xpass: 0,
         };
+        let mut reports = Vec::new();
         for tg in &self.suite {
+            let tests = tg
+                .tests
+                .iter()
+                .filter(|&(test_name, _)| self.filter.selects(&tg.name, test_name))
+                .collect::<Vec<_>>();
+            if tests.is_empty() {
+                continue;
+            }
+            let report = |test_name: &str, duration, outcome| TestReport {
+                backend: backend_name.to_string(),
+                scene: tg.name.clone(),
+                test: test_name.to_string(),
+                duration,
+                outcome,
+            };
+            let skip_all = |reason: SkipReason| {
+                tests
+                    .iter()
+                    .map(|&(test_name, _)| {
+                        report(
+                            test_name,
+                            Duration::default(),
+                            Outcome::Skip(reason.clone()),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            };
+
             let mut adapters = instance.enumerate_adapters();
             let adapter = adapters.remove(0);
+            let info = adapter.info.clone();
             let supported_features = adapter.physical_device.features();
+            let hints = adapter.physical_device.hints();
             let limits = adapter.physical_device.limits();
             //println!("\t{:?}", adapter.info);
-            println!("\tScene '{}':", tg.name);
+            progress!(self, "\tScene '{}':\n", tg.name);
 
             #[cfg(not(feature = "glsl-to-spirv"))]
             {
+                let data_path = self.base_path.join("data");
                 let all_spirv = tg.scene.resources.values().all(|res| match *res {
-                    warden::raw::Resource::Shader(ref name) => name.ends_with(".spirv"),
+                    warden::raw::Resource::Shader(ref name) => {
+                        warden::gpu::spirv_path(&data_path, name).is_some()
+                    }
                     _ => true,
                 });
                 if !all_spirv {
-                    println!("\t\tskipped {} tests (GLSL shaders)", tg.tests.len());
-                    results.skip += tg.tests.len();
+                    progress!(self, "\t\tskipped {} tests (GLSL shaders)\n", tests.len());
+                    results.skip += tests.len();
+                    reports.extend(skip_all(SkipReason::GlslUnsupported));
                     continue;
                 }
             }
 
-            if !supported_features.contains(tg.features) {
-                println!(
-                    "\tskipped (features missing: {:?})",
-                    tg.features - supported_features
-                );
-                results.skip += tg.tests.len();
+            if let Err(unmet) = tg.requirements.check(supported_features, hints, &limits) {
+                progress!(self, "\tskipped ({})\n", unmet);
+                results.skip += tests.len();
+                reports.extend(skip_all(SkipReason::Unsupported(unmet)));
                 continue;
             }
 
             let mut scene = warden::gpu::Scene::<B>::new(
                 adapter,
-                tg.features,
+                tg.requirements.features,
                 &tg.scene,
                 self.base_path.join("data"),
             )
             .unwrap();
 
-            for (test_name, test) in &tg.tests {
-                print!("\t\tTest '{}' ...", test_name);
+            for (test_name, test) in tests {
+                progress!(self, "\t\tTest '{}' ...", test_name);
+                let rule = disabilities.find(backend_name, &info, &tg.name, test_name);
+                if let Some(rule) = rule.filter(|rule| rule.disability == Disability::Skip) {
+                    progress!(self, "\tskipped (disabled: {})\n", rule.reason);
+                    results.skip += 1;
+                    reports.push(report(
+                        test_name,
+                        Duration::default(),
+                        Outcome::Skip(SkipReason::Disabled(rule.reason.clone())),
+                    ));
+                    continue;
+                }
+                if let Some(rule) = rule.filter(|_| self.bless) {
+                    progress!(self, "\tskipped (known failure: {})\n", rule.reason);
+                    results.skip += 1;
+                    reports.push(report(
+                        test_name,
+                        Duration::default(),
+                        Outcome::Skip(SkipReason::KnownFailure(rule.reason.clone())),
+                    ));
+                    continue;
+                }
+                let known_failure = rule.is_some();
                 let mut max_compute_work_groups = [0; 3];
                 for job_name in &test.jobs {
                     if let warden::raw::Job::Compute { dispatch, .. } = tg.scene.jobs[job_name] {
@@ -168,35 +469,58 @@ impl Harness {
                     || max_compute_work_groups[1] > limits.max_compute_work_group_size[1]
                     || max_compute_work_groups[2] > limits.max_compute_work_group_size[2]
                 {
-                    println!("\tskipped (compute {:?})", max_compute_work_groups);
+                    progress!(self, "\tskipped (compute {:?})\n", max_compute_work_groups);
                     results.skip += 1;
+                    reports.push(report(
+                        test_name,
+                        Duration::default(),
+                        Outcome::Skip(SkipReason::ComputeLimits(max_compute_work_groups)),
+                    ));
                     continue;
                 }
 
+                let start = Instant::now();
                 scene.run(test.jobs.iter());
 
-                print!("\tran: ");
-                let (guard, row, data) = match test.expect {
-                    Expectation::Buffer(ref buffer, ref data) => {
-                        (scene.fetch_buffer(buffer), 0, data)
+                progress!(self, "\tran: ");
+                let output = self
+                    .output_path
+                    .join(backend_name)
+                    .join(&tg.name)
+                    .join(test_name);
+                let outcome = match self.check(&mut scene, &tg.scene, &test.expect, &output) {
+                    Ok(()) if known_failure => {
+                        progress!(self, "XPASS (known failure: {})\n", rule.unwrap().reason);
+                        results.xpass += 1;
+                        Outcome::XPass
                     }
-                    Expectation::ImageRow(ref image, row, ref data) => {
-                        (scene.fetch_image(image), row, data)
+                    Err(details) if known_failure => {
+                        progress!(self, "XFAIL {}\n", details);
+                        results.xfail += 1;
+                        Outcome::XFail(details)
+                    }
+                    Ok(()) if self.bless => {
+                        progress!(self, "BLESSED\n");
+                        results.pass += 1;
+                        Outcome::Pass
+                    }
+                    Ok(()) => {
+                        progress!(self, "PASS\n");
+                        results.pass += 1;
+                        Outcome::Pass
+                    }
+                    Err(details) => {
+                        progress!(self, "FAIL {}\n", details);
+                        results.fail += 1;
+                        Outcome::Fail(details)
                     }
                 };
-
-                if data.as_slice() == guard.row(row) {
-                    println!("PASS");
-                    results.pass += 1;
-                } else {
-                    println!("FAIL {:?}", guard.row(row));
-                    results.fail += 1;
-                }
+                reports.push(report(test_name, start.elapsed(), outcome));
             }
         }
 
-        println!("\t{:?}", results);
-        results.fail
+        progress!(self, "\t{:?}\n", results);
+        reports
     }
 }
 
@@ -205,39 +529,118 @@ fn main() {
 
     #[cfg(feature = "env_logger")]
     env_logger::init();
-    let mut num_failures = 0;
+    #[allow(unused_mut)]
+    let mut reports = Vec::<TestReport>::new();
 
-    let suite_name = match env::args().nth(1) {
+    let mut suite = None;
+    let mut bless = false;
+    let mut list = false;
+    let mut output = None;
+    let mut format = Format::Text;
+    let mut filter = Filter::default();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next().unwrap_or_else(|| {
+                println!("Option '{}' requires a value", arg);
+                process::exit(-1);
+            })
+        };
+        match arg.as_str() {
+            "--bless" => bless = true,
+            "--list" => list = true,
+            "--output" => output = Some(PathBuf::from(value())),
+            "--filter" => filter.patterns.push(value()),
+            "--scene" => filter.scenes.push(value()),
+            "--format" => {
+                format = match value().as_str() {
+                    "text" => Format::Text,
+                    "junit" => Format::Junit,
+                    "json" => Format::Json,
+                    other => {
+                        println!("Unknown format '{}'", other);
+                        process::exit(-1);
+                    }
+                }
+            }
+            _ if arg.starts_with("--") => {
+                println!("Unknown option '{}'", arg);
+                process::exit(-1);
+            }
+            _ => suite = Some(arg),
+        }
+    }
+    let suite = match suite {
         Some(name) => name,
         None => {
             println!("Call with the argument of the reftest suite name");
+            println!("Options:");
+            println!("\t--bless\tregenerate the golden images");
+            println!("\t--output <dir>\twrite the images of failed tests to <dir>");
+            println!("\t--format text|junit|json\tformat of the results");
+            println!("\t--filter <glob>\tonly run the tests matching 'scene/test'");
+            println!("\t--scene <name>\tonly run the tests of a scene");
+            println!("\t--list\tlist the selected tests instead of running them");
             return;
         }
     };
 
-    let harness = Harness::new(&suite_name);
+    let harness = Harness::new(&Options {
+        suite,
+        bless,
+        output,
+        format,
+        filter,
+        list,
+    });
+    if list {
+        for name in harness.list() {
+            println!("{}", name);
+        }
+        return;
+    }
+
+    let disabilities_path = harness.base_path.join("reftests").join("disabilities.ron");
+    let disabilities = Disabilities::load(&disabilities_path).unwrap_or_else(|e| {
+        println!("Unable to parse {:?}: {}", disabilities_path, e);
+        process::exit(-1);
+    });
+
     #[cfg(feature = "vulkan")]
     {
-        num_failures +=
-            harness.run::<gfx_backend_vulkan::Backend>("Vulkan", Disabilities::default());
+        reports.extend(harness.run::<gfx_backend_vulkan::Backend>("Vulkan", &disabilities));
     }
     #[cfg(feature = "dx12")]
     {
-        num_failures += harness.run::<gfx_backend_dx12::Backend>("DX12", Disabilities::default());
+        reports.extend(harness.run::<gfx_backend_dx12::Backend>("DX12", &disabilities));
     }
     #[cfg(feature = "dx11")]
     {
-        num_failures += harness.run::<gfx_backend_dx11::Backend>("DX11", Disabilities::default());
+        reports.extend(harness.run::<gfx_backend_dx11::Backend>("DX11", &disabilities));
     }
     #[cfg(feature = "metal")]
     {
-        num_failures += harness.run::<gfx_backend_metal::Backend>("Metal", Disabilities::default());
+        reports.extend(harness.run::<gfx_backend_metal::Backend>("Metal", &disabilities));
     }
     #[cfg(feature = "gl")]
     {
-        num_failures += harness.run::<gfx_backend_gl::Backend>("GL", Disabilities::default());
+        reports.extend(harness.run::<gfx_backend_gl::Backend>("GL", &disabilities));
+    }
+    #[cfg(feature = "software")]
+    {
+        reports.extend(harness.run::<gfx_backend_software::Backend>("Software", &disabilities));
+    }
+    let _ = (harness, disabilities);
+
+    let written = match format {
+        Format::Text => Ok(()),
+        Format::Junit => report::write_junit(&reports, io::stdout()),
+        Format::Json => report::write_json(&reports, io::stdout()),
+    };
+    if let Err(e) = written {
+        println!("Unable to write the results: {}", e);
+        process::exit(-1);
     }
-    let _ = harness;
-    num_failures += 0; // mark as mutated
+    let num_failures = reports.iter().filter(|r| r.outcome.is_failure()).count();
     process::exit(num_failures as _);
 }
@@ -0,0 +1,49 @@
+//! Checks scene definitions for broken references and inconsistent resources.
+//!
+//! Called with the paths of the scenes, or without arguments to check all
+//! the scenes in `work/scenes`.
+
+extern crate gfx_warden as warden;
+
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::{env, process};
+
+use ron::de;
+
+fn main() {
+    let mut paths = env::args().skip(1).map(PathBuf::from).collect::<Vec<_>>();
+    if paths.is_empty() {
+        let dir = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/../../work/scenes"));
+        paths = fs::read_dir(&dir)
+            .unwrap_or_else(|e| panic!("Unable to read {:?}: {}", dir, e))
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension() == Some("ron".as_ref()))
+            .collect();
+        paths.sort();
+    }
+
+    let mut num_errors = 0;
+    for path in &paths {
+        let scene = match File::open(path)
+            .map_err(de::Error::from)
+            .and_then(de::from_reader::<_, warden::raw::Scene>)
+        {
+            Ok(scene) => scene,
+            Err(e) => {
+                println!("{}: {}", path.display(), e);
+                num_errors += 1;
+                continue;
+            }
+        };
+        for error in warden::lint::lint(&scene) {
+            println!("{}: {}", path.display(), error);
+            num_errors += 1;
+        }
+    }
+
+    if num_errors != 0 {
+        println!("{} errors in {} scenes", num_errors, paths.len());
+        process::exit(1);
+    }
+}
@@ -0,0 +1,432 @@
+//! Typed construction of scenes from Rust, e.g. to generate one test per
+//! format in a loop instead of writing each of them in RON.
+//!
+//! ```
+//! use gfx_warden::builder::SceneBuilder;
+//! use hal::{buffer::Usage, command::BufferCopy};
+//! use gfx_warden::raw::TransferCommand;
+//!
+//! let scene = SceneBuilder::new()
+//!     .buffer("input", 4, Usage::TRANSFER_SRC | Usage::TRANSFER_DST)
+//!     .buffer("output", 4, Usage::TRANSFER_DST)
+//!     .transfer(
+//!         "copy",
+//!         vec![TransferCommand::CopyBuffer {
+//!             src: "input".to_string(),
+//!             dst: "output".to_string(),
+//!             regions: vec![BufferCopy { src: 0, dst: 0, size: 4 }],
+//!         }],
+//!     )
+//!     .build()
+//!     .unwrap();
+//! println!("{}", scene.to_ron().unwrap());
+//! ```
+
+use std::collections::BTreeMap;
+
+use hal::{buffer as b, format as f, image as i, pass, pso, query, queue};
+
+use crate::lint::{lint, LintError};
+use crate::raw;
+
+/// Builder of a `raw::Scene`, validated with `lint` when built.
+///
+/// Resources and jobs are referenced by name, like in RON scenes. The
+/// helpers cover the common resources, and any other can be added with
+/// `resource` and `job`.
+pub struct SceneBuilder {
+    scene: raw::Scene,
+    errors: Vec<LintError>,
+}
+
+impl Default for SceneBuilder {
+    fn default() -> Self {
+        SceneBuilder::new()
+    }
+}
+
+impl SceneBuilder {
+    pub fn new() -> Self {
+        SceneBuilder {
+            scene: raw::Scene {
+                features: hal::Features::empty(),
+                hints: hal::Hints::empty(),
+                limits: BTreeMap::new(),
+                queues: BTreeMap::new(),
+                resources: BTreeMap::new(),
+                jobs: BTreeMap::new(),
+            },
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn features(mut self, features: hal::Features) -> Self {
+        self.scene.features |= features;
+        self
+    }
+
+    pub fn hints(mut self, hints: hal::Hints) -> Self {
+        self.scene.hints |= hints;
+        self
+    }
+
+    /// Require a minimum (or a maximum, for alignments) of a `hal::Limits` field.
+    pub fn limit<V: Into<serde_json::Value>>(mut self, name: &str, value: V) -> Self {
+        self.scene.limits.insert(name.to_string(), value.into());
+        self
+    }
+
+    /// Queue besides the default one, on a family of the given type.
+    pub fn queue(mut self, name: &str, ty: queue::QueueType) -> Self {
+        self.scene.queues.insert(name.to_string(), ty);
+        self
+    }
+
+    pub fn resource(mut self, name: &str, resource: raw::Resource) -> Self {
+        if self
+            .scene
+            .resources
+            .insert(name.to_string(), resource)
+            .is_some()
+        {
+            self.errors.push(LintError {
+                location: format!("resources[{:?}]", name),
+                message: "resource is defined twice".to_string(),
+            });
+        }
+        self
+    }
+
+    /// Buffer with undefined initial contents.
+    pub fn buffer(self, name: &str, size: usize, usage: b::Usage) -> Self {
+        self.buffer_with_data(name, size, usage, "")
+    }
+
+    /// Buffer initialized with the contents of a file in the data directory.
+    pub fn buffer_with_data(self, name: &str, size: usize, usage: b::Usage, data: &str) -> Self {
+        self.resource(
+            name,
+            raw::Resource::Buffer {
+                size,
+                usage,
+                data: data.to_string(),
+            },
+        )
+    }
+
+    /// Image with a single mip level and undefined initial contents.
+    pub fn image(self, name: &str, kind: i::Kind, format: f::Format, usage: i::Usage) -> Self {
+        self.resource(
+            name,
+            raw::Resource::Image {
+                kind,
+                num_levels: 1,
+                format,
+                usage,
+                data: String::new(),
+            },
+        )
+    }
+
+    /// View of the first level and layer of an image, with all the aspects
+    /// of the format.
+    pub fn image_view(self, name: &str, image: &str, kind: i::ViewKind, format: f::Format) -> Self {
+        self.resource(
+            name,
+            raw::Resource::ImageView {
+                image: image.to_string(),
+                kind,
+                format,
+                swizzle: f::Swizzle::NO,
+                range: i::SubresourceRange {
+                    aspects: format.surface_desc().aspects,
+                    levels: 0 .. 1,
+                    layers: 0 .. 1,
+                },
+            },
+        )
+    }
+
+    pub fn sampler(self, name: &str, info: i::SamplerDesc) -> Self {
+        self.resource(name, raw::Resource::Sampler { info })
+    }
+
+    /// Render pass with a single subpass, named "main", drawing to the
+    /// `colors` attachments in this order and to the `depth_stencil` one,
+    /// all of them in the `General` layout.
+    pub fn render_pass(
+        self,
+        name: &str,
+        colors: &[(&str, pass::Attachment)],
+        depth_stencil: Option<(&str, pass::Attachment)>,
+    ) -> Self {
+        let layout = i::Layout::General;
+        let mut attachments = BTreeMap::new();
+        for (att_name, attachment) in colors.iter().chain(&depth_stencil) {
+            attachments.insert(att_name.to_string(), attachment.clone());
+        }
+        let subpass = raw::Subpass {
+            colors: colors
+                .iter()
+                .map(|&(att_name, _)| raw::AttachmentRef(att_name.to_string(), layout))
+                .collect(),
+            depth_stencil: depth_stencil
+                .as_ref()
+                .map(|&(att_name, _)| raw::AttachmentRef(att_name.to_string(), layout)),
+            inputs: Vec::new(),
+            preserves: Vec::new(),
+            resolves: Vec::new(),
+        };
+        let mut subpasses = BTreeMap::new();
+        subpasses.insert("main".to_string(), subpass);
+        self.resource(
+            name,
+            raw::Resource::RenderPass {
+                attachments,
+                subpasses,
+                dependencies: Vec::new(),
+            },
+        )
+    }
+
+    /// Framebuffer of a render pass, with the view of each attachment given
+    /// as `(attachment, view)`.
+    pub fn framebuffer(
+        self,
+        name: &str,
+        pass: &str,
+        views: &[(&str, &str)],
+        extent: i::Extent,
+    ) -> Self {
+        self.resource(
+            name,
+            raw::Resource::Framebuffer {
+                pass: pass.to_string(),
+                views: views
+                    .iter()
+                    .map(|&(att_name, view)| (att_name.to_string(), view.to_string()))
+                    .collect(),
+                extent,
+            },
+        )
+    }
+
+    /// Shader compiled from a GLSL file in the data directory.
+    pub fn shader(self, name: &str, file: &str) -> Self {
+        self.resource(name, raw::Resource::Shader(file.to_string()))
+    }
+
+    pub fn descriptor_set_layout(
+        self,
+        name: &str,
+        bindings: Vec<pso::DescriptorSetLayoutBinding>,
+    ) -> Self {
+        self.resource(
+            name,
+            raw::Resource::DescriptorSetLayout {
+                bindings,
+                immutable_samplers: Vec::new(),
+            },
+        )
+    }
+
+    pub fn descriptor_pool(
+        self,
+        name: &str,
+        capacity: usize,
+        ranges: Vec<pso::DescriptorRangeDesc>,
+    ) -> Self {
+        self.resource(name, raw::Resource::DescriptorPool { capacity, ranges })
+    }
+
+    /// Descriptor set allocated from `pool`, with the resources of each
+    /// binding of `layout` in order.
+    pub fn descriptor_set(
+        self,
+        name: &str,
+        pool: &str,
+        layout: &str,
+        data: Vec<raw::DescriptorRange>,
+    ) -> Self {
+        self.resource(
+            name,
+            raw::Resource::DescriptorSet {
+                pool: pool.to_string(),
+                layout: layout.to_string(),
+                data,
+            },
+        )
+    }
+
+    pub fn pipeline_layout(
+        self,
+        name: &str,
+        set_layouts: &[&str],
+        push_constant_ranges: Vec<(pso::ShaderStageFlags, std::ops::Range<u32>)>,
+    ) -> Self {
+        self.resource(
+            name,
+            raw::Resource::PipelineLayout {
+                set_layouts: set_layouts.iter().map(|s| s.to_string()).collect(),
+                push_constant_ranges,
+            },
+        )
+    }
+
+    /// Graphics pipeline for the first subpass of `pass`, without vertex
+    /// buffers, filling the polygons and writing every color attachment
+    /// without blending.
+    ///
+    /// The render pass has to be added first, as it gives the number of
+    /// color attachments.
+    pub fn graphics_pipeline(
+        mut self,
+        name: &str,
+        vertex: &str,
+        fragment: &str,
+        layout: &str,
+        pass: &str,
+        primitive: pso::Primitive,
+    ) -> Self {
+        let colors = match self.scene.resources.get(pass) {
+            Some(raw::Resource::RenderPass { subpasses, .. }) => subpasses
+                .values()
+                .next()
+                .map_or(0, |subpass| subpass.colors.len()),
+            _ => {
+                self.errors.push(LintError {
+                    location: format!("resources[{:?}].subpass.parent", name),
+                    message: format!("render pass '{}' isn't added before the pipeline", pass),
+                });
+                0
+            }
+        };
+        self.resource(
+            name,
+            raw::Resource::GraphicsPipeline {
+                shaders: raw::GraphicsShaderSet {
+                    vertex: vertex.to_string(),
+                    hull: String::new(),
+                    domain: String::new(),
+                    geometry: String::new(),
+                    fragment: fragment.to_string(),
+                },
+                rasterizer: pso::Rasterizer::FILL,
+                vertex_buffers: Vec::new(),
+                attributes: Vec::new(),
+                input_assembler: pso::InputAssemblerDesc::new(primitive),
+                blender: pso::BlendDesc {
+                    logic_op: None,
+                    targets: vec![pso::ColorBlendDesc::EMPTY; colors],
+                },
+                depth_stencil: pso::DepthStencilDesc::default(),
+                layout: layout.to_string(),
+                subpass: raw::SubpassRef {
+                    parent: pass.to_string(),
+                    index: 0,
+                },
+            },
+        )
+    }
+
+    pub fn compute_pipeline(self, name: &str, shader: &str, layout: &str) -> Self {
+        self.resource(
+            name,
+            raw::Resource::ComputePipeline {
+                shader: shader.to_string(),
+                layout: layout.to_string(),
+            },
+        )
+    }
+
+    pub fn query_pool(self, name: &str, ty: query::Type, count: query::Id) -> Self {
+        self.resource(name, raw::Resource::QueryPool { ty, count })
+    }
+
+    pub fn semaphore(self, name: &str) -> Self {
+        self.resource(name, raw::Resource::Semaphore)
+    }
+
+    pub fn event(self, name: &str) -> Self {
+        self.resource(name, raw::Resource::Event)
+    }
+
+    pub fn job(mut self, name: &str, job: raw::Job) -> Self {
+        if self.scene.jobs.insert(name.to_string(), job).is_some() {
+            self.errors.push(LintError {
+                location: format!("jobs[{:?}]", name),
+                message: "job is defined twice".to_string(),
+            });
+        }
+        self
+    }
+
+    pub fn transfer(self, name: &str, commands: Vec<raw::TransferCommand>) -> Self {
+        self.job(
+            name,
+            raw::Job::Transfer {
+                queue: String::new(),
+                wait: Vec::new(),
+                signal: Vec::new(),
+                commands,
+            },
+        )
+    }
+
+    /// Compute job with a direct dispatch.
+    pub fn compute(
+        self,
+        name: &str,
+        pipeline: &str,
+        descriptor_sets: &[&str],
+        dispatch: hal::WorkGroupCount,
+    ) -> Self {
+        self.job(
+            name,
+            raw::Job::Compute {
+                queue: String::new(),
+                wait: Vec::new(),
+                signal: Vec::new(),
+                pipeline: pipeline.to_string(),
+                descriptor_sets: descriptor_sets.iter().map(|s| s.to_string()).collect(),
+                push_constants: Vec::new(),
+                dispatch,
+                dispatch_indirect: None,
+            },
+        )
+    }
+
+    /// Finish the scene, returning the problems found by `lint` if any.
+    pub fn build(mut self) -> Result<raw::Scene, Vec<LintError>> {
+        self.errors.extend(lint(&self.scene));
+        if self.errors.is_empty() {
+            Ok(self.scene)
+        } else {
+            Err(self.errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicates() {
+        let errors = SceneBuilder::new()
+            .buffer("buffer", 4, b::Usage::TRANSFER_SRC)
+            .buffer("buffer", 8, b::Usage::TRANSFER_DST)
+            .transfer("copy", Vec::new())
+            .transfer("copy", Vec::new())
+            .build()
+            .unwrap_err();
+        let errors = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![
+                r#"resources["buffer"]: resource is defined twice"#,
+                r#"jobs["copy"]: job is defined twice"#,
+            ]
+        );
+    }
+}
@@ -0,0 +1,128 @@
+//! Known problems of backends and adapters, keeping suites green without
+//! removing the affected tests.
+
+use std::fs::File;
+use std::path::Path;
+
+use crate::report::glob_matches;
+
+/// How a disabled test is handled.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub enum Disability {
+    /// The test runs and is expected to fail.
+    Failure,
+    /// The test doesn't run at all, for example because it crashes or hangs.
+    Skip,
+}
+
+/// Tests affected by a known problem, on a subset of the backends and adapters.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Rule {
+    /// Glob pattern matched against `scene/test`.
+    pub tests: String,
+    /// Name of the backend, as printed by the harness, all backends if `None`.
+    #[serde(default)]
+    pub backend: Option<String>,
+    /// PCI ID of the adapter vendor, all vendors if `None`.
+    #[serde(default)]
+    pub vendor: Option<usize>,
+    /// Case-insensitive part of the adapter name, all adapters if `None`.
+    #[serde(default)]
+    pub adapter: Option<String>,
+    pub disability: Disability,
+    /// Description of the problem, or a link to the issue tracking it.
+    #[serde(default)]
+    pub reason: String,
+}
+
+impl Rule {
+    fn applies(&self, backend: &str, info: &hal::adapter::AdapterInfo, test: &str) -> bool {
+        self.backend.as_ref().is_none_or(|b| b == backend)
+            && self.vendor.is_none_or(|vendor| vendor == info.vendor)
+            && self
+                .adapter
+                .as_ref()
+                .is_none_or(|name| info.name.to_lowercase().contains(&name.to_lowercase()))
+            && glob_matches(&self.tests, test)
+    }
+}
+
+/// List of known problems.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(transparent)]
+pub struct Disabilities {
+    pub rules: Vec<Rule>,
+}
+
+impl Disabilities {
+    /// Load the list from a RON file, which may not exist.
+    pub fn load(path: &Path) -> Result<Self, ron::de::Error> {
+        if !path.exists() {
+            return Ok(Disabilities::default());
+        }
+        File::open(path)
+            .map_err(ron::de::Error::from)
+            .and_then(ron::de::from_reader)
+    }
+
+    /// Find the first rule applying to a test of `scene` on an adapter of `backend`.
+    pub fn find(
+        &self,
+        backend: &str,
+        info: &hal::adapter::AdapterInfo,
+        scene: &str,
+        test: &str,
+    ) -> Option<&Rule> {
+        let name = format!("{}/{}", scene, test);
+        self.rules
+            .iter()
+            .find(|rule| rule.applies(backend, info, &name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules() {
+        let disabilities: Disabilities = ron::de::from_str(
+            r#"[
+                (
+                    tests: "transfer/fill-*",
+                    backend: Some("Vulkan"),
+                    adapter: Some("llvmpipe"),
+                    disability: Failure,
+                    reason: "rounding",
+                ),
+                (
+                    tests: "compute/*",
+                    vendor: Some(0x8086),
+                    disability: Skip,
+                ),
+            ]"#,
+        )
+        .unwrap();
+        let info = |name: &str, vendor| hal::adapter::AdapterInfo {
+            name: name.to_string(),
+            vendor,
+            device: 0,
+            device_type: hal::adapter::DeviceType::Cpu,
+        };
+        let llvmpipe = info("LLVMpipe (LLVM 10.0.0, 256 bits)", 0x10005);
+        let intel = info("Intel(R) UHD Graphics 620", 0x8086);
+
+        let rule = disabilities.find("Vulkan", &llvmpipe, "transfer", "fill-whole");
+        assert_eq!(rule.map(|r| r.disability), Some(Disability::Failure));
+        assert_eq!(
+            disabilities.find("GL", &llvmpipe, "transfer", "fill-whole"),
+            None
+        );
+        assert_eq!(
+            disabilities.find("Vulkan", &intel, "transfer", "fill-whole"),
+            None
+        );
+        let rule = disabilities.find("GL", &intel, "compute", "fill");
+        assert_eq!(rule.map(|r| r.disability), Some(Disability::Skip));
+    }
+}
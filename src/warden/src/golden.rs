@@ -0,0 +1,274 @@
+//! Comparison of fetched results against golden references.
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter};
+use std::path::Path;
+
+/// Tightly packed image with 8 bits per channel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    /// Number of channels, from 1 (gray) to 4 (RGBA).
+    pub channels: u8,
+    pub data: Vec<u8>,
+}
+
+fn color_type(channels: u8) -> io::Result<png::ColorType> {
+    Ok(match channels {
+        1 => png::ColorType::Grayscale,
+        2 => png::ColorType::GrayscaleAlpha,
+        3 => png::ColorType::RGB,
+        4 => png::ColorType::RGBA,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} channels can't be stored in a PNG", channels),
+            ))
+        }
+    })
+}
+
+impl Image {
+    /// Load a PNG image, expanding palettes and bit depths below 8 bits.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let mut decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+        decoder.set_transformations(png::Transformations::EXPAND);
+        let (info, mut reader) = decoder.read_info()?;
+        // Palette and low bit depth images are expanded while decoding,
+        // so only the output layout is relevant.
+        let (color_type, bit_depth) = reader.output_color_type();
+        if bit_depth != png::BitDepth::Eight {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{:?} is not an 8-bit image", path),
+            ));
+        }
+        let channels = match color_type {
+            png::ColorType::Grayscale => 1,
+            png::ColorType::GrayscaleAlpha => 2,
+            png::ColorType::RGB => 3,
+            png::ColorType::RGBA => 4,
+            png::ColorType::Indexed => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{:?} wasn't expanded from its palette", path),
+                ))
+            }
+        };
+        let mut data = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut data)?;
+        Ok(Image {
+            width: info.width,
+            height: info.height,
+            channels,
+            data,
+        })
+    }
+
+    /// Save as a PNG image, creating the parent directories.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(file, self.width, self.height);
+        encoder.set_color(color_type(self.channels)?);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.data)?;
+        Ok(())
+    }
+
+    fn pixels(&self) -> impl Iterator<Item = &[u8]> {
+        self.data.chunks(self.channels as usize)
+    }
+}
+
+/// Allowed differences between an image and its golden reference.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+pub struct Tolerance {
+    /// Maximum difference of any channel for a pixel to be considered equal.
+    #[serde(default)]
+    pub channel: u8,
+    /// Maximum number of pixels which may differ.
+    #[serde(default)]
+    pub pixels: usize,
+}
+
+/// Reason an image doesn't match its golden reference.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Mismatch {
+    /// The images have different dimensions or channel counts.
+    Layout {
+        expected: (u32, u32, u8),
+        actual: (u32, u32, u8),
+    },
+    /// Too many pixels differ.
+    Pixels {
+        /// Number of pixels differing by more than the tolerance.
+        count: usize,
+        /// Largest difference of any channel.
+        max_difference: u8,
+        /// RGBA image with the differing pixels in red over the dimmed reference.
+        diff: Image,
+    },
+}
+
+/// Compare an image against its golden reference.
+pub fn compare(actual: &Image, golden: &Image, tolerance: Tolerance) -> Result<(), Mismatch> {
+    let layout = |image: &Image| (image.width, image.height, image.channels);
+    if layout(actual) != layout(golden) {
+        return Err(Mismatch::Layout {
+            expected: layout(golden),
+            actual: layout(actual),
+        });
+    }
+
+    let mut count = 0;
+    let mut max_difference = 0;
+    let mut diff = Vec::with_capacity(golden.width as usize * golden.height as usize * 4);
+    for (a, g) in actual.pixels().zip(golden.pixels()) {
+        let difference = a
+            .iter()
+            .zip(g)
+            .map(|(&a, &g)| a.max(g) - a.min(g))
+            .max()
+            .unwrap_or(0);
+        max_difference = max_difference.max(difference);
+        if difference > tolerance.channel {
+            count += 1;
+            diff.extend_from_slice(&[0xFF, 0, 0, 0xFF]);
+        } else {
+            let luma = (g[.. g.len().min(3)].iter().map(|&c| c as u32).sum::<u32>()
+                / g.len().min(3) as u32) as u8;
+            diff.extend_from_slice(&[luma / 4, luma / 4, luma / 4, 0xFF]);
+        }
+    }
+
+    if count > tolerance.pixels {
+        Err(Mismatch::Pixels {
+            count,
+            max_difference,
+            diff: Image {
+                width: golden.width,
+                height: golden.height,
+                channels: 4,
+                data: diff,
+            },
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Stable 64-bit FNV-1a hash of fetched data.
+pub fn hash(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(data: &[u8]) -> Image {
+        Image {
+            width: 2,
+            height: 1,
+            channels: 4,
+            data: data.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_tolerance() {
+        let golden = image(&[10, 20, 30, 255, 100, 100, 100, 255]);
+        let actual = image(&[12, 20, 30, 255, 100, 90, 100, 255]);
+
+        match compare(&actual, &golden, Tolerance::default()) {
+            Err(Mismatch::Pixels {
+                count,
+                max_difference,
+                diff,
+            }) => {
+                assert_eq!((count, max_difference), (2, 10));
+                assert_eq!(diff.data, [255, 0, 0, 255, 255, 0, 0, 255]);
+            }
+            other => panic!("Unexpected {:?}", other),
+        }
+        match compare(
+            &actual,
+            &golden,
+            Tolerance {
+                channel: 2,
+                pixels: 0,
+            },
+        ) {
+            Err(Mismatch::Pixels { count, diff, .. }) => {
+                assert_eq!(count, 1);
+                assert_eq!(diff.data, [5, 5, 5, 255, 255, 0, 0, 255]);
+            }
+            other => panic!("Unexpected {:?}", other),
+        }
+        assert_eq!(
+            compare(
+                &actual,
+                &golden,
+                Tolerance {
+                    channel: 2,
+                    pixels: 1,
+                }
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            compare(&Image { width: 1, ..actual }, &golden, Tolerance::default()),
+            Err(Mismatch::Layout {
+                expected: (2, 1, 4),
+                actual: (1, 1, 4),
+            })
+        );
+    }
+
+    #[test]
+    fn test_png_round_trip() {
+        let path = std::env::temp_dir().join("warden-golden-test.png");
+        let original = image(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        original.save(&path).unwrap();
+        assert_eq!(Image::load(&path).unwrap(), original);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_png_indexed() {
+        let path = std::env::temp_dir().join("warden-golden-indexed-test.png");
+        {
+            let file = BufWriter::new(File::create(&path).unwrap());
+            let mut encoder = png::Encoder::new(file, 4, 1);
+            encoder.set_color(png::ColorType::Indexed);
+            encoder.set_depth(png::BitDepth::Two);
+            encoder.set_palette(vec![255, 0, 0, 0, 255, 0, 0, 0, 255]);
+            let mut writer = encoder.write_header().unwrap();
+            // Palette indices 0, 1, 2 and 1, two bits each.
+            writer.write_image_data(&[0b0001_1001]).unwrap();
+        }
+        let loaded = Image::load(&path).unwrap();
+        let _ = fs::remove_file(path);
+        assert_eq!(
+            loaded,
+            Image {
+                width: 4,
+                height: 1,
+                channels: 3,
+                data: vec![255, 0, 0, 0, 255, 0, 0, 0, 255, 0, 255, 0],
+            }
+        );
+    }
+
+    #[test]
+    fn test_fnv() {
+        assert_eq!(hash(&[]), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
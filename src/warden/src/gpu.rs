@@ -2,10 +2,12 @@
 use glsl_to_spirv;
 
 use std::collections::hash_map::{Entry, HashMap};
+use std::collections::HashSet;
 use std::fs::File;
 use std::io::Read;
 use std::ops::Range;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use std::{iter, mem, slice};
 
 use hal::{
@@ -154,6 +156,21 @@ pub struct DescriptorSet<B: hal::Backend> {
     views: Vec<(String, i::Layout)>,
 }
 
+pub struct QueryPool<B: hal::Backend> {
+    pub handle: B::QueryPool,
+    ty: query::Type,
+}
+
+impl<B: hal::Backend> QueryPool<B> {
+    /// Number of values in the results of each query.
+    fn num_values(&self) -> usize {
+        match self.ty {
+            query::Type::PipelineStatistics(statistics) => statistics.bits().count_ones() as usize,
+            query::Type::Occlusion | query::Type::Timestamp => 1,
+        }
+    }
+}
+
 pub struct Resources<B: hal::Backend> {
     pub buffers: HashMap<String, Buffer<B>>,
     pub images: HashMap<String, Image<B>>,
@@ -169,10 +186,17 @@ pub struct Resources<B: hal::Backend> {
     pub pipeline_layouts: HashMap<String, B::PipelineLayout>,
     pub graphics_pipelines: HashMap<String, B::GraphicsPipeline>,
     pub compute_pipelines: HashMap<String, (String, B::ComputePipeline)>,
+    pub query_pools: HashMap<String, QueryPool<B>>,
+    pub semaphores: HashMap<String, B::Semaphore>,
+    pub events: HashMap<String, B::Event>,
 }
 
 pub struct Job<B: hal::Backend> {
     submission: B::CommandBuffer,
+    /// Indices of the queue group and of the queue in it.
+    queue: (usize, usize),
+    wait: Vec<(String, pso::PipelineStage)>,
+    signal: Vec<String>,
 }
 
 pub struct Scene<B: hal::Backend> {
@@ -181,14 +205,32 @@ pub struct Scene<B: hal::Backend> {
     init_submit: B::CommandBuffer,
     finish_submit: B::CommandBuffer,
     device: B::Device,
-    queue_group: queue::QueueGroup<B>,
-    command_pool: Option<B::CommandPool>,
+    /// Queue groups of the used families, the first one having the default queue.
+    queue_groups: Vec<queue::QueueGroup<B>>,
+    /// Command pools of the queue groups.
+    command_pools: Vec<B::CommandPool>,
     query_pool: Option<B::QueryPool>,
     upload_buffers: HashMap<String, (B::Buffer, B::Memory)>,
     download_types: Vec<hal::MemoryTypeId>,
     limits: hal::Limits,
 }
 
+/// Return the SPIR-V binary of a shader in the data directory, if it can be
+/// loaded without compiling GLSL: either a `.spirv` file, or a GLSL file with
+/// its binary precompiled as `{file}.spirv` next to it.
+pub fn spirv_path(data_path: &Path, shader: &str) -> Option<PathBuf> {
+    let path = if shader.ends_with(".spirv") {
+        data_path.join(shader)
+    } else {
+        data_path.join(format!("{}.spirv", shader))
+    };
+    if path.is_file() {
+        Some(path)
+    } else {
+        None
+    }
+}
+
 fn align(x: u64, y: u64) -> u64 {
     if x > 0 && y > 0 {
         ((x - 1) | (y - 1)) + 1
@@ -208,15 +250,78 @@ impl<B: hal::Backend> Scene<B> {
         let memory_types = adapter.physical_device.memory_properties().memory_types;
         let limits = adapter.physical_device.limits();
 
+        // pick the queues: the default one is the first of the first family,
+        // and the named ones come next in the family matching their type
+        let mut families = vec![(&adapter.queue_families[0], 1)];
+        let mut queue_refs = HashMap::new();
+        queue_refs.insert(String::new(), (0, 0));
+        for (name, &ty) in &raw.queues {
+            let supports = |family_ty: queue::QueueType| {
+                (family_ty.supports_graphics() || !ty.supports_graphics())
+                    && (family_ty.supports_compute() || !ty.supports_compute())
+            };
+            let family = adapter
+                .queue_families
+                .iter()
+                .find(|family| family.queue_type() == ty)
+                .or_else(|| {
+                    adapter
+                        .queue_families
+                        .iter()
+                        .find(|family| supports(family.queue_type()))
+                })
+                .expect(&format!("No queue family for the {:?} queue: {}", ty, name));
+            let queue_ref = match families.iter().position(|&(f, _)| f.id() == family.id()) {
+                Some(group) => {
+                    // share the last queue if the family has no more
+                    let count = &mut families[group].1;
+                    if *count < family.max_queues() {
+                        *count += 1;
+                    }
+                    (group, *count - 1)
+                }
+                None => {
+                    families.push((family, 1));
+                    (families.len() - 1, 0)
+                }
+            };
+            queue_refs.insert(name.clone(), queue_ref);
+        }
+        let priorities = families
+            .iter()
+            .map(|&(_, count)| vec![1.0; count])
+            .collect::<Vec<_>>();
+
         // initialize graphics
-        let mut gpu = unsafe {
+        let gpu = unsafe {
             adapter
                 .physical_device
-                .open(&[(&adapter.queue_families[0], &[1.0])], featues)
+                .open(
+                    &families
+                        .iter()
+                        .zip(&priorities)
+                        .map(|(&(family, _), priorities)| (family, &priorities[..]))
+                        .collect::<Vec<_>>(),
+                    featues,
+                )
                 .unwrap()
         };
         let device = gpu.device;
-        let queue_group = gpu.queue_groups.pop().unwrap();
+        let mut opened_groups = gpu.queue_groups;
+        let queue_groups = families
+            .iter()
+            .map(|&(family, _)| {
+                let position = opened_groups
+                    .iter()
+                    .position(|group| group.family == family.id())
+                    .unwrap();
+                opened_groups.swap_remove(position)
+            })
+            .collect::<Vec<_>>();
+        let queue_families = queue_refs
+            .iter()
+            .map(|(name, &(group, _))| (name.as_str(), queue_groups[group].family))
+            .collect::<HashMap<_, _>>();
 
         let upload_types: Vec<hal::MemoryTypeId> = memory_types
             .iter()
@@ -246,14 +351,14 @@ impl<B: hal::Backend> Scene<B> {
         info!("upload memory: {:?}", upload_types);
         info!("download memory: {:?}", &download_types);
 
-        let mut command_pool = unsafe {
-            device
-                .create_command_pool(
-                    queue_group.family,
-                    hal::pool::CommandPoolCreateFlags::empty(),
-                )
-                .unwrap()
-        };
+        let mut command_pools = queue_groups
+            .iter()
+            .map(|group| unsafe {
+                device
+                    .create_command_pool(group.family, hal::pool::CommandPoolCreateFlags::empty())
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
         let query_pool = unsafe { device.create_query_pool(query::Type::Timestamp, 2) };
 
         // create resources
@@ -271,11 +376,14 @@ impl<B: hal::Backend> Scene<B> {
             pipeline_layouts: HashMap::new(),
             graphics_pipelines: HashMap::new(),
             compute_pipelines: HashMap::new(),
+            query_pools: HashMap::new(),
+            semaphores: HashMap::new(),
+            events: HashMap::new(),
         };
         let mut upload_buffers = HashMap::new();
         let (mut finish_cmd, mut init_cmd);
         unsafe {
-            finish_cmd = command_pool.allocate_one(c::Level::Primary);
+            finish_cmd = command_pools[0].allocate_one(c::Level::Primary);
             finish_cmd.begin_primary(c::CommandBufferFlags::empty());
             if let Ok(ref pool) = query_pool {
                 finish_cmd.write_timestamp(
@@ -287,7 +395,7 @@ impl<B: hal::Backend> Scene<B> {
             finish_cmd.finish();
         }
         unsafe {
-            init_cmd = command_pool.allocate_one(c::Level::Primary);
+            init_cmd = command_pools[0].allocate_one(c::Level::Primary);
             init_cmd.begin_primary(c::CommandBufferFlags::empty());
             init_cmd.begin_debug_marker("_init", 0x0000FF00);
             if let Ok(ref pool) = query_pool {
@@ -328,8 +436,14 @@ impl<B: hal::Backend> Scene<B> {
                     }
 
                     // process initial data for the buffer
+                    // Indirect arguments are read by the commands, not the shaders.
+                    let read_access = if usage.contains(b::Usage::INDIRECT) {
+                        b::Access::SHADER_READ | b::Access::INDIRECT_COMMAND_READ
+                    } else {
+                        b::Access::SHADER_READ
+                    };
                     let stable_state = if data.is_empty() {
-                        let access = b::Access::SHADER_READ; //TODO
+                        let access = read_access; //TODO
                         if false {
                             //TODO
                             let buffer_barrier = memory::Barrier::whole_buffer(
@@ -377,7 +491,7 @@ impl<B: hal::Backend> Scene<B> {
                             device.unmap_memory(&upload_memory);
                         }
                         // add init commands
-                        let final_state = b::Access::SHADER_READ;
+                        let final_state = read_access;
                         let pre_barrier = memory::Barrier::whole_buffer(
                             &buffer,
                             b::Access::empty() .. b::Access::TRANSFER_WRITE,
@@ -682,17 +796,23 @@ impl<B: hal::Backend> Scene<B> {
                         file.read_to_string(&mut code).unwrap();
                         glsl_to_spirv::compile(&code, ty).unwrap()
                     }
-                    let full_path = data_path.join(local_path);
-                    let base_file = File::open(&full_path).unwrap();
-                    let file = match &*full_path.extension().unwrap().to_string_lossy() {
-                        "spirv" => base_file,
-                        #[cfg(feature = "glsl-to-spirv")]
-                        "vert" => transpile(base_file, glsl_to_spirv::ShaderType::Vertex),
-                        #[cfg(feature = "glsl-to-spirv")]
-                        "frag" => transpile(base_file, glsl_to_spirv::ShaderType::Fragment),
-                        #[cfg(feature = "glsl-to-spirv")]
-                        "comp" => transpile(base_file, glsl_to_spirv::ShaderType::Compute),
-                        other => panic!("Unknown shader extension: {}", other),
+                    #[cfg(feature = "glsl-to-spirv")]
+                    let file = {
+                        let full_path = data_path.join(local_path);
+                        let base_file = File::open(&full_path).unwrap();
+                        match &*full_path.extension().unwrap().to_string_lossy() {
+                            "spirv" => base_file,
+                            "vert" => transpile(base_file, glsl_to_spirv::ShaderType::Vertex),
+                            "frag" => transpile(base_file, glsl_to_spirv::ShaderType::Fragment),
+                            "comp" => transpile(base_file, glsl_to_spirv::ShaderType::Compute),
+                            other => panic!("Unknown shader extension: {}", other),
+                        }
+                    };
+                    // Without a GLSL compiler, use the precompiled binaries.
+                    #[cfg(not(feature = "glsl-to-spirv"))]
+                    let file = match spirv_path(&data_path, local_path) {
+                        Some(path) => File::open(path).unwrap(),
+                        None => panic!("Shader {:?} is not precompiled to SPIR-V", local_path),
                     };
                     let spirv = pso::read_spirv(file).unwrap();
                     let module = unsafe { device.create_shader_module(&spirv) }.unwrap();
@@ -728,6 +848,30 @@ impl<B: hal::Backend> Scene<B> {
                     .expect("Descriptor pool creation failure!");
                     resources.desc_pools.insert(name.clone(), pool);
                 }
+                raw::Resource::QueryPool { ty, count } => {
+                    let pool = unsafe { device.create_query_pool(ty, count) }
+                        .expect("Query pool creation failure!");
+                    // Queries must be reset before each use, so before every run.
+                    unsafe {
+                        init_cmd.reset_query_pool(&pool, 0 .. count);
+                    }
+                    resources
+                        .query_pools
+                        .insert(name.clone(), QueryPool { handle: pool, ty });
+                }
+                raw::Resource::Semaphore => {
+                    let semaphore = device
+                        .create_semaphore()
+                        .expect("Semaphore creation failure!");
+                    resources.semaphores.insert(name.clone(), semaphore);
+                }
+                raw::Resource::Event => {
+                    let event = device.create_event().expect("Event creation failure!");
+                    unsafe {
+                        init_cmd.reset_event(&event, pso::PipelineStage::TOP_OF_PIPE);
+                    }
+                    resources.events.insert(name.clone(), event);
+                }
                 _ => {}
             }
         }
@@ -985,14 +1129,17 @@ impl<B: hal::Backend> Scene<B> {
         let mut jobs = HashMap::new();
         for (name, job) in &raw.jobs {
             use crate::raw::TransferCommand as Tc;
+            let queue_ref = *queue_refs
+                .get(job.queue())
+                .expect(&format!("Missing queue: {}", job.queue()));
             let mut command_buf;
             unsafe {
-                command_buf = command_pool.allocate_one(c::Level::Primary);
+                command_buf = command_pools[queue_ref.0].allocate_one(c::Level::Primary);
                 command_buf.begin_primary(c::CommandBufferFlags::SIMULTANEOUS_USE);
                 command_buf.begin_debug_marker(name, 0x00FF0000);
             }
             match *job {
-                raw::Job::Transfer { ref commands } => {
+                raw::Job::Transfer { ref commands, .. } => {
                     let mut buffers = HashMap::new();
                     let mut images = HashMap::new();
                     let src_stage =
@@ -1210,6 +1357,163 @@ impl<B: hal::Backend> Scene<B> {
                                     data,
                                 );
                             },
+                            Tc::WriteTimestamp {
+                                ref pool,
+                                id,
+                                stage,
+                            } => unsafe {
+                                let pool = resources
+                                    .query_pools
+                                    .get(pool)
+                                    .expect(&format!("Missing query pool: {}", pool));
+                                command_buf.write_timestamp(
+                                    stage,
+                                    query::Query {
+                                        pool: &pool.handle,
+                                        id,
+                                    },
+                                );
+                            },
+                            Tc::CopyQueryResults {
+                                ref pool,
+                                ref queries,
+                                ref buffer,
+                                offset,
+                                stride,
+                                flags,
+                            } => unsafe {
+                                let pool = resources
+                                    .query_pools
+                                    .get(pool)
+                                    .expect(&format!("Missing query pool: {}", pool));
+                                let buf = resources
+                                    .buffers
+                                    .get(buffer)
+                                    .expect(&format!("Missing buffer: {}", buffer));
+                                command_buf.pipeline_barrier(
+                                    src_stage .. pso::PipelineStage::TRANSFER,
+                                    memory::Dependencies::empty(),
+                                    buf.barrier(buffers.entry(buffer), b::State::TRANSFER_WRITE),
+                                );
+                                command_buf.copy_query_pool_results(
+                                    &pool.handle,
+                                    queries.clone(),
+                                    &buf.handle,
+                                    offset,
+                                    stride,
+                                    flags,
+                                );
+                            },
+                            Tc::SetEvent { ref event, stages } => unsafe {
+                                let event = resources
+                                    .events
+                                    .get(event)
+                                    .expect(&format!("Missing event: {}", event));
+                                command_buf.set_event(event, stages);
+                            },
+                            Tc::ResetEvent { ref event, stages } => unsafe {
+                                let event = resources
+                                    .events
+                                    .get(event)
+                                    .expect(&format!("Missing event: {}", event));
+                                command_buf.reset_event(event, stages);
+                            },
+                            Tc::WaitEvents {
+                                ref events,
+                                ref stages,
+                            } => unsafe {
+                                let transfer_buffers =
+                                    b::Access::TRANSFER_READ | b::Access::TRANSFER_WRITE;
+                                let transfer_images =
+                                    i::Access::TRANSFER_READ | i::Access::TRANSFER_WRITE;
+                                command_buf.wait_events(
+                                    events.iter().map(|name| {
+                                        resources
+                                            .events
+                                            .get(name)
+                                            .expect(&format!("Missing event: {}", name))
+                                    }),
+                                    stages.clone(),
+                                    &[
+                                        memory::Barrier::AllBuffers(
+                                            b::Access::TRANSFER_WRITE .. transfer_buffers,
+                                        ),
+                                        memory::Barrier::AllImages(
+                                            i::Access::TRANSFER_WRITE .. transfer_images,
+                                        ),
+                                    ],
+                                );
+                            },
+                            Tc::Release {
+                                ref resource,
+                                ref queue,
+                            }
+                            | Tc::Acquire {
+                                ref resource,
+                                ref queue,
+                            } => unsafe {
+                                let own = queue_families[job.queue()];
+                                let other = *queue_families
+                                    .get(queue.as_str())
+                                    .expect(&format!("Missing queue: {}", queue));
+                                let (families, release) = match *command {
+                                    Tc::Release { .. } => (own .. other, true),
+                                    _ => (other .. own, false),
+                                };
+                                // The destination stage of a release is ignored,
+                                // the acquire does the waiting.
+                                let transfer_stages = if release {
+                                    src_stage .. pso::PipelineStage::BOTTOM_OF_PIPE
+                                } else {
+                                    src_stage .. pso::PipelineStage::TRANSFER
+                                };
+                                // Released resources are brought to their stable
+                                // state, which is the one they are acquired in.
+                                if let Some(buf) = resources.buffers.get(resource) {
+                                    if release {
+                                        command_buf.pipeline_barrier(
+                                            src_stage .. pso::PipelineStage::TRANSFER,
+                                            memory::Dependencies::empty(),
+                                            buf.barrier(buffers.entry(resource), buf.stable_state),
+                                        );
+                                        buffers.remove(resource);
+                                    }
+                                    command_buf.pipeline_barrier(
+                                        transfer_stages,
+                                        memory::Dependencies::empty(),
+                                        &[memory::Barrier::Buffer {
+                                            states: buf.stable_state .. buf.stable_state,
+                                            target: &buf.handle,
+                                            range: b::SubRange::WHOLE,
+                                            families: Some(families),
+                                        }],
+                                    );
+                                } else {
+                                    let img = resources
+                                        .images
+                                        .get(resource)
+                                        .expect(&format!("Missing buffer or image: {}", resource));
+                                    if release {
+                                        let (access, layout) = img.stable_state;
+                                        command_buf.pipeline_barrier(
+                                            src_stage .. pso::PipelineStage::TRANSFER,
+                                            memory::Dependencies::empty(),
+                                            img.barrier(images.entry(resource), access, layout),
+                                        );
+                                        images.remove(resource);
+                                    }
+                                    command_buf.pipeline_barrier(
+                                        transfer_stages,
+                                        memory::Dependencies::empty(),
+                                        &[memory::Barrier::Image {
+                                            states: img.stable_state .. img.stable_state,
+                                            target: &img.handle,
+                                            range: img.range.clone(),
+                                            families: Some(families),
+                                        }],
+                                    );
+                                }
+                            },
                         }
                     }
 
@@ -1237,6 +1541,7 @@ impl<B: hal::Backend> Scene<B> {
                     ref framebuffer,
                     ref pass,
                     ref clear_values,
+                    ..
                 } => unsafe {
                     // collect all used image descriptors
                     let mut all_images = Vec::new();
@@ -1384,12 +1689,124 @@ impl<B: hal::Backend> Scene<B> {
                                         instances.clone(),
                                     );
                                 }
+                                Dc::DrawIndirect {
+                                    ref buffer,
+                                    offset,
+                                    draw_count,
+                                    stride,
+                                } => {
+                                    let buf = resources
+                                        .buffers
+                                        .get(buffer)
+                                        .expect(&format!("Missing indirect buffer: {}", buffer));
+                                    command_buf.draw_indirect(
+                                        &buf.handle,
+                                        offset,
+                                        draw_count,
+                                        stride,
+                                    );
+                                }
+                                Dc::DrawIndexedIndirect {
+                                    ref buffer,
+                                    offset,
+                                    draw_count,
+                                    stride,
+                                } => {
+                                    let buf = resources
+                                        .buffers
+                                        .get(buffer)
+                                        .expect(&format!("Missing indirect buffer: {}", buffer));
+                                    command_buf.draw_indexed_indirect(
+                                        &buf.handle,
+                                        offset,
+                                        draw_count,
+                                        stride,
+                                    );
+                                }
+                                Dc::PushConstants {
+                                    ref layout,
+                                    stages,
+                                    offset,
+                                    ref data,
+                                } => {
+                                    command_buf.push_graphics_constants(
+                                        resources.pipeline_layouts.get(layout).expect(&format!(
+                                            "Missing pipeline layout: {}",
+                                            layout
+                                        )),
+                                        stages,
+                                        offset,
+                                        data,
+                                    );
+                                }
                                 Dc::SetViewports(ref viewports) => {
                                     command_buf.set_viewports(0, viewports);
                                 }
                                 Dc::SetScissors(ref scissors) => {
                                     command_buf.set_scissors(0, scissors);
                                 }
+                                Dc::SetStencilReference { faces, value } => {
+                                    command_buf.set_stencil_reference(faces, value);
+                                }
+                                Dc::SetBlendConstants(color) => {
+                                    command_buf.set_blend_constants(color);
+                                }
+                                Dc::SetDepthBounds(ref bounds) => {
+                                    command_buf.set_depth_bounds(bounds.clone());
+                                }
+                                Dc::ClearAttachments {
+                                    ref clears,
+                                    ref rects,
+                                } => {
+                                    command_buf.clear_attachments(
+                                        clears.iter().map(|clear| clear.to_raw()),
+                                        rects,
+                                    );
+                                }
+                                Dc::BeginQuery {
+                                    ref pool,
+                                    id,
+                                    flags,
+                                } => {
+                                    let pool = resources
+                                        .query_pools
+                                        .get(pool)
+                                        .expect(&format!("Missing query pool: {}", pool));
+                                    command_buf.begin_query(
+                                        query::Query {
+                                            pool: &pool.handle,
+                                            id,
+                                        },
+                                        flags,
+                                    );
+                                }
+                                Dc::EndQuery { ref pool, id } => {
+                                    let pool = resources
+                                        .query_pools
+                                        .get(pool)
+                                        .expect(&format!("Missing query pool: {}", pool));
+                                    command_buf.end_query(query::Query {
+                                        pool: &pool.handle,
+                                        id,
+                                    });
+                                }
+                                Dc::WriteTimestamp {
+                                    ref pool,
+                                    id,
+                                    stage,
+                                } => {
+                                    let pool = resources
+                                        .query_pools
+                                        .get(pool)
+                                        .expect(&format!("Missing query pool: {}", pool));
+                                    command_buf.write_timestamp(
+                                        stage,
+                                        query::Query {
+                                            pool: &pool.handle,
+                                            id,
+                                        },
+                                    );
+                                }
                             }
                         }
                     }
@@ -1417,15 +1834,22 @@ impl<B: hal::Backend> Scene<B> {
                 raw::Job::Compute {
                     ref pipeline,
                     ref descriptor_sets,
+                    ref push_constants,
                     dispatch,
+                    ref dispatch_indirect,
+                    ..
                 } => unsafe {
                     let (ref layout, ref pso) = resources.compute_pipelines[pipeline];
+                    let layout = resources
+                        .pipeline_layouts
+                        .get(layout)
+                        .expect(&format!("Missing pipeline layout: {}", layout));
                     command_buf.bind_compute_pipeline(pso);
+                    for &(offset, ref data) in push_constants {
+                        command_buf.push_compute_constants(layout, offset, data);
+                    }
                     command_buf.bind_compute_descriptor_sets(
-                        resources
-                            .pipeline_layouts
-                            .get(layout)
-                            .expect(&format!("Missing pipeline layout: {}", layout)),
+                        layout,
                         0,
                         descriptor_sets.iter().map(|name| {
                             &resources
@@ -1436,7 +1860,16 @@ impl<B: hal::Backend> Scene<B> {
                         }),
                         &[],
                     );
-                    command_buf.dispatch(dispatch);
+                    match *dispatch_indirect {
+                        Some((ref buffer, offset)) => {
+                            let buf = resources
+                                .buffers
+                                .get(buffer)
+                                .expect(&format!("Missing indirect buffer: {}", buffer));
+                            command_buf.dispatch_indirect(&buf.handle, offset);
+                        }
+                        None => command_buf.dispatch(dispatch),
+                    }
                 },
             }
 
@@ -1448,6 +1881,9 @@ impl<B: hal::Backend> Scene<B> {
                 name.clone(),
                 Job {
                     submission: command_buf,
+                    queue: queue_ref,
+                    wait: job.wait().to_vec(),
+                    signal: job.signal().to_vec(),
                 },
             );
         }
@@ -1459,8 +1895,8 @@ impl<B: hal::Backend> Scene<B> {
             init_submit: init_cmd,
             finish_submit: finish_cmd,
             device,
-            queue_group,
-            command_pool: Some(command_pool),
+            queue_groups,
+            command_pools,
             query_pool: query_pool.ok(),
             upload_buffers,
             download_types,
@@ -1476,18 +1912,72 @@ impl<B: hal::Backend> Scene<B> {
         I::Item: AsRef<str>,
     {
         let jobs = &self.jobs;
-        let submits = job_names.map(|name| {
-            &jobs
-                .get(name.as_ref())
-                .expect(&format!("Missing job: {}", name.as_ref()))
-                .submission
-        });
-
-        let command_buffers = iter::once(&self.init_submit)
-            .chain(submits)
-            .chain(iter::once(&self.finish_submit));
+        let jobs = job_names
+            .map(|name| {
+                jobs.get(name.as_ref())
+                    .expect(&format!("Missing job: {}", name.as_ref()))
+            })
+            .collect::<Vec<_>>();
+
+        // Semaphores have to be signaled before being waited on, and waited
+        // on before being signaled again, or the queues would never finish.
+        let mut signaled = HashSet::new();
+        for job in &jobs {
+            for (semaphore, _) in &job.wait {
+                assert!(
+                    signaled.remove(semaphore),
+                    "Semaphore waited on before being signaled: {}",
+                    semaphore
+                );
+            }
+            for semaphore in &job.signal {
+                assert!(
+                    signaled.insert(semaphore),
+                    "Semaphore signaled twice: {}",
+                    semaphore
+                );
+            }
+        }
+        assert!(
+            signaled.is_empty(),
+            "Semaphores signaled but not waited on: {:?}",
+            signaled
+        );
+
+        let single_queue = jobs
+            .iter()
+            .all(|job| job.queue == (0, 0) && job.wait.is_empty() && job.signal.is_empty());
+        if single_queue {
+            let command_buffers = iter::once(&self.init_submit)
+                .chain(jobs.iter().map(|job| &job.submission))
+                .chain(iter::once(&self.finish_submit));
+            unsafe {
+                self.queue_groups[0].queues[0].submit_without_semaphores(command_buffers, None);
+            }
+            return;
+        }
+
+        let semaphores = &self.resources.semaphores;
         unsafe {
-            self.queue_group.queues[0].submit_without_semaphores(command_buffers, None);
+            self.queue_groups[0].queues[0]
+                .submit_without_semaphores(iter::once(&self.init_submit), None);
+            // the other queues start once the resources are initialized
+            self.queue_groups[0].queues[0].wait_idle().unwrap();
+            for job in &jobs {
+                let submission = queue::Submission {
+                    command_buffers: iter::once(&job.submission),
+                    wait_semaphores: job
+                        .wait
+                        .iter()
+                        .map(|(name, stage)| (&semaphores[name], *stage)),
+                    signal_semaphores: job.signal.iter().map(|name| &semaphores[name]),
+                };
+                self.queue_groups[job.queue.0].queues[job.queue.1].submit(submission, None);
+            }
+            // the jobs of all the queues are finished before the final timestamp
+            self.device.wait_idle().unwrap();
+            self.queue_groups[0].queues[0]
+                .submit_without_semaphores(iter::once(&self.finish_submit), None);
         }
     }
 
@@ -1523,7 +2013,7 @@ impl<B: hal::Backend> Scene<B> {
 
         let mut command_pool = unsafe {
             self.device.create_command_pool(
-                self.queue_group.family,
+                self.queue_groups[0].family,
                 hal::pool::CommandPoolCreateFlags::empty(),
             )
         }
@@ -1568,7 +2058,7 @@ impl<B: hal::Backend> Scene<B> {
             .create_fence(false)
             .expect("Can't create copy-fence");
         unsafe {
-            self.queue_group.queues[0]
+            self.queue_groups[0].queues[0]
                 .submit_without_semaphores(iter::once(&cmd_buffer), Some(&copy_fence));
             self.device.wait_for_fence(&copy_fence, !0).unwrap();
             self.device.destroy_fence(copy_fence);
@@ -1634,7 +2124,7 @@ impl<B: hal::Backend> Scene<B> {
 
         let mut command_pool = unsafe {
             self.device.create_command_pool(
-                self.queue_group.family,
+                self.queue_groups[0].family,
                 hal::pool::CommandPoolCreateFlags::empty(),
             )
         }
@@ -1701,7 +2191,7 @@ impl<B: hal::Backend> Scene<B> {
             .create_fence(false)
             .expect("Can't create copy-fence");
         unsafe {
-            self.queue_group.queues[0]
+            self.queue_groups[0].queues[0]
                 .submit_without_semaphores(iter::once(&cmd_buffer), Some(&copy_fence));
             self.device.wait_for_fence(&copy_fence, !0).unwrap();
             self.device.destroy_fence(copy_fence);
@@ -1721,18 +2211,62 @@ impl<B: hal::Backend> Scene<B> {
         }
     }
 
-    pub fn measure_time(&self) -> u32 {
-        let mut results = vec![0u32; 2];
-        if let Some(ref pool) = self.query_pool {
-            unsafe {
-                self.device.wait_idle().unwrap();
-                let raw_data = slice::from_raw_parts_mut(results.as_mut_ptr() as *mut u8, 4 * 2);
-                self.device
-                    .get_query_pool_results(pool, 0 .. 2, raw_data, 4, query::ResultFlags::empty())
-                    .unwrap();
+    /// Wait for the submitted jobs to finish, then return the 64-bit results
+    /// of the `queries` of a pool, with all the values of each query in turn.
+    pub fn fetch_queries(&self, name: &str, queries: Range<query::Id>) -> Vec<u64> {
+        let pool = self
+            .resources
+            .query_pools
+            .get(name)
+            .expect(&format!("Unable to find query pool to fetch: {}", name));
+        let num_values = pool.num_values();
+        let mut results = vec![0u64; (queries.end - queries.start) as usize * num_values];
+        self.device.wait_idle().unwrap();
+        unsafe {
+            let raw_data = slice::from_raw_parts_mut(
+                results.as_mut_ptr() as *mut u8,
+                results.len() * mem::size_of::<u64>(),
+            );
+            self.device
+                .get_query_pool_results(
+                    &pool.handle,
+                    queries,
+                    raw_data,
+                    (num_values * mem::size_of::<u64>()) as _,
+                    query::ResultFlags::BITS_64 | query::ResultFlags::WAIT,
+                )
+                .unwrap();
+        }
+        results
+    }
+
+    /// Wait for the submitted jobs to finish, then return the time they took
+    /// on the device, or `None` if the backend has no timestamp queries.
+    ///
+    /// `timestamp_period` is the number of nanoseconds per timestamp tick.
+    pub fn measure_time(&self, timestamp_period: f32) -> Option<Duration> {
+        self.device.wait_idle().unwrap();
+        let pool = self.query_pool.as_ref()?;
+        let mut results = [0u64; 2];
+        let ready = unsafe {
+            let raw_data = slice::from_raw_parts_mut(results.as_mut_ptr() as *mut u8, 8 * 2);
+            self.device.get_query_pool_results(
+                pool,
+                0 .. 2,
+                raw_data,
+                8,
+                query::ResultFlags::BITS_64 | query::ResultFlags::WAIT,
+            )
+        };
+        match ready {
+            Ok(true) if results[1] > results[0] => {
+                let ticks = results[1] - results[0];
+                Some(Duration::from_secs_f64(
+                    ticks as f64 * timestamp_period as f64 * 1e-9,
+                ))
             }
+            _ => None,
         }
-        results[1] - results[0]
     }
 }
 
@@ -1744,9 +2278,15 @@ impl<B: hal::Backend> Drop for Scene<B> {
                 self.device.free_memory(memory);
             }
             //TODO: free those properly
-            let _ = &self.queue_group;
-            self.device
-                .destroy_command_pool(self.command_pool.take().unwrap());
+            for pool in self.command_pools.drain(..) {
+                self.device.destroy_command_pool(pool);
+            }
+            for (_, semaphore) in self.resources.semaphores.drain() {
+                self.device.destroy_semaphore(semaphore);
+            }
+            for (_, event) in self.resources.events.drain() {
+                self.device.destroy_event(event);
+            }
             if let Some(pool) = self.query_pool.take() {
                 self.device.destroy_query_pool(pool);
             }
@@ -6,14 +6,15 @@ extern crate log;
 #[macro_use]
 extern crate serde;
 
+pub mod builder;
+pub mod disabilities;
+pub mod golden;
 pub mod gpu;
+pub mod lint;
 pub mod raw;
+pub mod report;
+pub mod requirements;
+pub mod stats;
 
-#[derive(Debug, serde::Deserialize)]
-pub enum Feature {}
+pub use self::requirements::Requirements;
 
-impl Feature {
-    pub fn into_hal(self) -> hal::Features {
-        match self {}
-    }
-}
@@ -0,0 +1,1472 @@
+//! Static validation of scenes, catching broken references and inconsistent
+//! resources before the scene is created on a device.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::ops::Range;
+
+use hal::{buffer as b, format as f, image as i, pso, query};
+
+use crate::raw::{self, Resource};
+
+/// Problem found in a scene.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LintError {
+    /// Path to the faulty value, e.g. `resources["fbo"].views["c"]`.
+    pub location: String,
+    pub message: String,
+}
+
+impl fmt::Display for LintError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+fn kind_name(resource: &Resource) -> &'static str {
+    match *resource {
+        Resource::Buffer { .. } => "buffer",
+        Resource::Image { .. } => "image",
+        Resource::ImageView { .. } => "image view",
+        Resource::Sampler { .. } => "sampler",
+        Resource::RenderPass { .. } => "render pass",
+        Resource::Shader(_) => "shader",
+        Resource::DescriptorSetLayout { .. } => "descriptor set layout",
+        Resource::DescriptorPool { .. } => "descriptor pool",
+        Resource::DescriptorSet { .. } => "descriptor set",
+        Resource::PipelineLayout { .. } => "pipeline layout",
+        Resource::GraphicsPipeline { .. } => "graphics pipeline",
+        Resource::ComputePipeline { .. } => "compute pipeline",
+        Resource::Framebuffer { .. } => "framebuffer",
+        Resource::QueryPool { .. } => "query pool",
+        Resource::Semaphore => "semaphore",
+        Resource::Event => "event",
+    }
+}
+
+fn with_article(noun: &str) -> String {
+    match noun.chars().next() {
+        Some('a') | Some('e') | Some('i') | Some('o') | Some('u') => format!("an {}", noun),
+        _ => format!("a {}", noun),
+    }
+}
+
+/// How queries are used by a command.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum QueryUse {
+    /// Reading the results.
+    Any,
+    Timestamp,
+    /// Occlusion and pipeline statistics queries, between `BeginQuery` and `EndQuery`.
+    Scoped,
+}
+
+/// Image view as seen by its users.
+struct ViewInfo<'a> {
+    image: &'a str,
+    format: f::Format,
+    usage: i::Usage,
+    extent: i::Extent,
+}
+
+struct Linter<'a> {
+    scene: &'a raw::Scene,
+    errors: Vec<LintError>,
+    /// First job using each event, with its queue.
+    event_queues: HashMap<&'a str, (String, &'a str)>,
+    /// First job releasing or acquiring each buffer and image.
+    transferred: HashMap<&'a str, &'a str>,
+}
+
+impl<'a> Linter<'a> {
+    fn error(&mut self, location: &str, message: String) {
+        self.errors.push(LintError {
+            location: location.to_string(),
+            message,
+        });
+    }
+
+    /// Look up the resource `name`, which must be of the `expected` kind.
+    fn get(&mut self, location: &str, name: &str, expected: &str) -> Option<&'a Resource> {
+        match self.scene.resources.get(name) {
+            Some(resource) if kind_name(resource) == expected => Some(resource),
+            Some(resource) => {
+                let message = format!(
+                    "'{}' is {}, not {}",
+                    name,
+                    with_article(kind_name(resource)),
+                    with_article(expected)
+                );
+                self.error(location, message);
+                None
+            }
+            None => {
+                self.error(location, format!("missing {} '{}'", expected, name));
+                None
+            }
+        }
+    }
+
+    fn check_buffer_usage(&mut self, location: &str, name: &str, usage: b::Usage) {
+        if let Some(&Resource::Buffer { usage: actual, .. }) = self.get(location, name, "buffer") {
+            if !actual.contains(usage) {
+                let message = format!("buffer '{}' lacks the {:?} usage", name, usage - actual);
+                self.error(location, message);
+            }
+        }
+    }
+
+    fn check_image_usage(&mut self, location: &str, name: &str, usage: i::Usage) {
+        if let Some(&Resource::Image { usage: actual, .. }) = self.get(location, name, "image") {
+            if !actual.contains(usage) {
+                let message = format!("image '{}' lacks the {:?} usage", name, usage - actual);
+                self.error(location, message);
+            }
+        }
+    }
+
+    fn view(&mut self, location: &str, name: &str) -> Option<ViewInfo<'a>> {
+        let (image, format, level) = match self.get(location, name, "image view")? {
+            Resource::ImageView {
+                image,
+                format,
+                range,
+                ..
+            } => (image, *format, range.levels.start),
+            _ => unreachable!(),
+        };
+        match self.scene.resources.get(image)? {
+            Resource::Image { kind, usage, .. } => Some(ViewInfo {
+                image,
+                format,
+                usage: *usage,
+                extent: kind.level_extent(level),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Whether render passes `a` and `b` are compatible, i.e. have the same
+    /// attachment formats and sample counts, and the same number of subpasses.
+    /// Missing render passes are reported elsewhere, and considered compatible.
+    fn compatible(&self, a: &str, b: &str) -> bool {
+        let passes = (self.scene.resources.get(a), self.scene.resources.get(b));
+        match passes {
+            (
+                Some(Resource::RenderPass {
+                    attachments: atts_a,
+                    subpasses: subs_a,
+                    ..
+                }),
+                Some(Resource::RenderPass {
+                    attachments: atts_b,
+                    subpasses: subs_b,
+                    ..
+                }),
+            ) => {
+                subs_a.len() == subs_b.len()
+                    && atts_a.len() == atts_b.len()
+                    && atts_a.iter().all(|(name, att)| match atts_b.get(name) {
+                        Some(other) => other.format == att.format && other.samples == att.samples,
+                        None => false,
+                    })
+            }
+            _ => true,
+        }
+    }
+
+    fn lint_resource(&mut self, name: &str, resource: &'a Resource) {
+        let at = |field: &str| format!("resources[{:?}]{}", name, field);
+        match *resource {
+            Resource::Buffer {
+                size,
+                usage,
+                ref data,
+            } => {
+                if size == 0 {
+                    self.error(&at(".size"), "buffer is empty".to_string());
+                }
+                if !data.is_empty() && !usage.contains(b::Usage::TRANSFER_DST) {
+                    let message = "initial data needs the TRANSFER_DST usage".to_string();
+                    self.error(&at(".usage"), message);
+                }
+            }
+            Resource::Image {
+                kind,
+                num_levels,
+                usage,
+                ref data,
+                ..
+            } => {
+                if num_levels == 0 {
+                    self.error(&at(".num_levels"), "image has no levels".to_string());
+                }
+                if kind.num_layers() == 0 {
+                    self.error(&at(".kind"), "image has no layers".to_string());
+                }
+                if !data.is_empty() && !usage.contains(i::Usage::TRANSFER_DST) {
+                    let message = "initial data needs the TRANSFER_DST usage".to_string();
+                    self.error(&at(".usage"), message);
+                }
+            }
+            Resource::ImageView {
+                ref image,
+                format,
+                ref range,
+                ..
+            } => {
+                if let Some(&Resource::Image {
+                    kind,
+                    num_levels,
+                    format: image_format,
+                    ..
+                }) = self.get(&at(".image"), image, "image")
+                {
+                    // Images are created without `MUTABLE_FORMAT`.
+                    if format != image_format {
+                        let message = format!(
+                            "format {:?} differs from the format {:?} of image '{}'",
+                            format, image_format, image
+                        );
+                        self.error(&at(".format"), message);
+                    }
+                    if range.levels.start >= range.levels.end || range.levels.end > num_levels {
+                        let message = format!(
+                            "levels {:?} are out of the {} levels of image '{}'",
+                            range.levels, num_levels, image
+                        );
+                        self.error(&at(".range.levels"), message);
+                    }
+                    if range.layers.start >= range.layers.end
+                        || range.layers.end > kind.num_layers()
+                    {
+                        let message = format!(
+                            "layers {:?} are out of the {} layers of image '{}'",
+                            range.layers,
+                            kind.num_layers(),
+                            image
+                        );
+                        self.error(&at(".range.layers"), message);
+                    }
+                }
+                let aspects = format.surface_desc().aspects;
+                if range.aspects.is_empty() || !aspects.contains(range.aspects) {
+                    let message = format!(
+                        "aspects {:?} don't match the aspects {:?} of format {:?}",
+                        range.aspects, aspects, format
+                    );
+                    self.error(&at(".range.aspects"), message);
+                }
+            }
+            Resource::Sampler { .. } => {}
+            Resource::RenderPass {
+                ref attachments,
+                ref subpasses,
+                ref dependencies,
+            } => {
+                if subpasses.is_empty() {
+                    self.error(
+                        &at(".subpasses"),
+                        "render pass has no subpasses".to_string(),
+                    );
+                }
+                for (sp_name, subpass) in subpasses {
+                    let at = |field: &str| at(&format!(".subpasses[{:?}].{}", sp_name, field));
+                    let mut check =
+                        |location: String, att: &str, aspects: f::Aspects| match attachments
+                            .get(att)
+                        {
+                            None => {
+                                let message = format!("missing attachment '{}'", att);
+                                self.error(&location, message);
+                            }
+                            Some(&hal::pass::Attachment {
+                                format: Some(format),
+                                ..
+                            }) if !format.surface_desc().aspects.intersects(aspects) => {
+                                let message = format!(
+                                    "attachment '{}' has format {:?} without {:?}",
+                                    att, format, aspects
+                                );
+                                self.error(&location, message);
+                            }
+                            Some(_) => {}
+                        };
+                    for (k, aref) in subpass.colors.iter().enumerate() {
+                        check(at(&format!("colors[{}]", k)), &aref.0, f::Aspects::COLOR);
+                    }
+                    if let Some(ref aref) = subpass.depth_stencil {
+                        let aspects = f::Aspects::DEPTH | f::Aspects::STENCIL;
+                        check(at("depth_stencil"), &aref.0, aspects);
+                    }
+                    for (k, aref) in subpass.inputs.iter().enumerate() {
+                        check(at(&format!("inputs[{}]", k)), &aref.0, f::Aspects::all());
+                    }
+                    for (k, att) in subpass.preserves.iter().enumerate() {
+                        check(at(&format!("preserves[{}]", k)), att, f::Aspects::all());
+                    }
+                    for (k, aref) in subpass.resolves.iter().enumerate() {
+                        check(at(&format!("resolves[{}]", k)), &aref.0, f::Aspects::COLOR);
+                    }
+                    if !subpass.resolves.is_empty()
+                        && subpass.resolves.len() != subpass.colors.len()
+                    {
+                        let message = format!(
+                            "{} resolve attachments for {} color attachments",
+                            subpass.resolves.len(),
+                            subpass.colors.len()
+                        );
+                        self.error(&at("resolves"), message);
+                    }
+                }
+                // An empty subpass name stands for the outside of the render pass.
+                for (k, dependency) in dependencies.iter().enumerate() {
+                    for sp_name in &[&dependency.passes.start, &dependency.passes.end] {
+                        if !sp_name.is_empty() && !subpasses.contains_key(*sp_name) {
+                            let message = format!("missing subpass '{}'", sp_name);
+                            self.error(&at(&format!(".dependencies[{}].passes", k)), message);
+                        }
+                    }
+                }
+            }
+            Resource::Shader(ref path) => {
+                let extension = path.rsplit('.').next().unwrap_or_default();
+                if !path.contains('.') || !["spirv", "vert", "frag", "comp"].contains(&extension) {
+                    let message = format!("unknown shader extension of '{}'", path);
+                    self.error(&at(""), message);
+                }
+            }
+            Resource::DescriptorSetLayout {
+                ref bindings,
+                ref immutable_samplers,
+            } => {
+                if bindings.is_empty() {
+                    self.error(&at(".bindings"), "layout has no bindings".to_string());
+                }
+                let mut seen = HashSet::new();
+                for (k, binding) in bindings.iter().enumerate() {
+                    if !seen.insert(binding.binding) {
+                        let message = format!("binding {} is repeated", binding.binding);
+                        self.error(&at(&format!(".bindings[{}]", k)), message);
+                    }
+                }
+                if !immutable_samplers.is_empty() {
+                    let message = "immutable samplers are not supported".to_string();
+                    self.error(&at(".immutable_samplers"), message);
+                }
+            }
+            Resource::DescriptorPool {
+                capacity,
+                ref ranges,
+            } => {
+                if capacity == 0 {
+                    self.error(&at(".capacity"), "pool has no capacity".to_string());
+                }
+                if ranges.is_empty() {
+                    self.error(&at(".ranges"), "pool has no ranges".to_string());
+                }
+            }
+            Resource::DescriptorSet {
+                ref pool,
+                ref layout,
+                ref data,
+            } => {
+                let ranges = match self.get(&at(".pool"), pool, "descriptor pool") {
+                    Some(Resource::DescriptorPool { ranges, .. }) => Some(ranges),
+                    _ => None,
+                };
+                let bindings = match self.get(&at(".layout"), layout, "descriptor set layout") {
+                    Some(Resource::DescriptorSetLayout { bindings, .. }) => bindings,
+                    _ => return,
+                };
+                if let Some(ranges) = ranges {
+                    for binding in bindings {
+                        if !ranges.iter().any(|range| range.ty == binding.ty) {
+                            let message = format!(
+                                "pool '{}' has no range of {:?} descriptors",
+                                pool, binding.ty
+                            );
+                            self.error(&at(".pool"), message);
+                        }
+                    }
+                }
+                if data.len() > bindings.len() {
+                    let message = format!(
+                        "{} descriptor ranges for the {} bindings of layout '{}'",
+                        data.len(),
+                        bindings.len(),
+                        layout
+                    );
+                    self.error(&at(".data"), message);
+                }
+                for (k, (binding, range)) in bindings.iter().zip(data).enumerate() {
+                    self.lint_descriptors(&at(&format!(".data[{}]", k)), binding, range);
+                }
+            }
+            Resource::PipelineLayout {
+                ref set_layouts,
+                ref push_constant_ranges,
+            } => {
+                for (k, set_layout) in set_layouts.iter().enumerate() {
+                    let location = at(&format!(".set_layouts[{}]", k));
+                    self.get(&location, set_layout, "descriptor set layout");
+                }
+                for (k, (_, range)) in push_constant_ranges.iter().enumerate() {
+                    if range.start >= range.end || range.start % 4 != 0 || range.end % 4 != 0 {
+                        let message = format!(
+                            "push constant range {:?} is empty or not aligned to 4 bytes",
+                            range
+                        );
+                        self.error(&at(&format!(".push_constant_ranges[{}]", k)), message);
+                    }
+                }
+            }
+            Resource::GraphicsPipeline {
+                ref shaders,
+                ref vertex_buffers,
+                ref attributes,
+                ref blender,
+                ref layout,
+                ref subpass,
+                ..
+            } => {
+                self.get(&at(".shaders.vertex"), &shaders.vertex, "shader");
+                for &(stage, shader) in &[
+                    ("hull", &shaders.hull),
+                    ("domain", &shaders.domain),
+                    ("geometry", &shaders.geometry),
+                    ("fragment", &shaders.fragment),
+                ] {
+                    if !shader.is_empty() {
+                        self.get(&at(&format!(".shaders.{}", stage)), shader, "shader");
+                    }
+                }
+                self.get(&at(".layout"), layout, "pipeline layout");
+                for (k, attribute) in attributes.iter().enumerate() {
+                    if !vertex_buffers
+                        .iter()
+                        .any(|vb| vb.binding == attribute.binding)
+                    {
+                        let message = format!("missing vertex buffer {}", attribute.binding);
+                        self.error(&at(&format!(".attributes[{}].binding", k)), message);
+                    }
+                }
+                if let Some(Resource::RenderPass { subpasses, .. }) =
+                    self.get(&at(".subpass.parent"), &subpass.parent, "render pass")
+                {
+                    if subpass.index as usize >= subpasses.len() {
+                        let message = format!(
+                            "render pass '{}' has only {} subpasses",
+                            subpass.parent,
+                            subpasses.len()
+                        );
+                        self.error(&at(".subpass.index"), message);
+                    } else {
+                        let colors = subpasses
+                            .values()
+                            .nth(subpass.index as usize)
+                            .unwrap()
+                            .colors
+                            .len();
+                        if blender.targets.len() != colors {
+                            let message = format!(
+                                "{} blend targets for {} color attachments",
+                                blender.targets.len(),
+                                colors
+                            );
+                            self.error(&at(".blender.targets"), message);
+                        }
+                    }
+                }
+            }
+            Resource::ComputePipeline {
+                ref shader,
+                ref layout,
+            } => {
+                self.get(&at(".shader"), shader, "shader");
+                self.get(&at(".layout"), layout, "pipeline layout");
+            }
+            Resource::QueryPool { count, .. } => {
+                if count == 0 {
+                    self.error(&at(".count"), "pool has no queries".to_string());
+                }
+            }
+            Resource::Semaphore | Resource::Event => {}
+            Resource::Framebuffer {
+                ref pass,
+                ref views,
+                extent,
+            } => {
+                let attachments = match self.get(&at(".pass"), pass, "render pass") {
+                    Some(Resource::RenderPass { attachments, .. }) => attachments,
+                    _ => return,
+                };
+                for (att_name, attachment) in attachments {
+                    let view_name = match views.get(att_name) {
+                        Some(view_name) => view_name,
+                        None => {
+                            let message = format!("no view for attachment '{}'", att_name);
+                            self.error(&at(".views"), message);
+                            continue;
+                        }
+                    };
+                    let location = at(&format!(".views[{:?}]", att_name));
+                    let view = match self.view(&location, view_name) {
+                        Some(view) => view,
+                        None => continue,
+                    };
+                    if let Some(format) = attachment.format {
+                        if format != view.format {
+                            let message = format!(
+                                "view '{}' has format {:?} instead of {:?}",
+                                view_name, view.format, format
+                            );
+                            self.error(&location, message);
+                        }
+                    }
+                    let usage = if view.format.is_color() {
+                        i::Usage::COLOR_ATTACHMENT
+                    } else {
+                        i::Usage::DEPTH_STENCIL_ATTACHMENT
+                    };
+                    if !view.usage.contains(usage) {
+                        let message = format!("image '{}' lacks the {:?} usage", view.image, usage);
+                        self.error(&location, message);
+                    }
+                    if view.extent.width < extent.width
+                        || view.extent.height < extent.height
+                        || view.extent.depth < extent.depth
+                    {
+                        let message = format!(
+                            "image '{}' is smaller than the framebuffer extent",
+                            view.image
+                        );
+                        self.error(&location, message);
+                    }
+                }
+                for att_name in views.keys() {
+                    if !attachments.contains_key(att_name) {
+                        let message =
+                            format!("render pass '{}' has no attachment '{}'", pass, att_name);
+                        self.error(&at(&format!(".views[{:?}]", att_name)), message);
+                    }
+                }
+            }
+        }
+    }
+
+    fn lint_descriptors(
+        &mut self,
+        location: &str,
+        binding: &pso::DescriptorSetLayoutBinding,
+        range: &raw::DescriptorRange,
+    ) {
+        use hal::pso::{BufferDescriptorFormat as Bdf, BufferDescriptorType as Bdt};
+        use hal::pso::{DescriptorType as Dt, ImageDescriptorType as Idt};
+
+        let count = match *range {
+            raw::DescriptorRange::Buffers(ref names) => {
+                let usage = match binding.ty {
+                    Dt::Buffer {
+                        ty: Bdt::Uniform,
+                        format: Bdf::Structured { .. },
+                    } => Some(b::Usage::UNIFORM),
+                    Dt::Buffer {
+                        ty: Bdt::Storage { .. },
+                        format: Bdf::Structured { .. },
+                    } => Some(b::Usage::STORAGE),
+                    _ => None,
+                };
+                for (k, name) in names.iter().enumerate() {
+                    let location = format!("{}[{}]", location, k);
+                    match usage {
+                        Some(usage) => self.check_buffer_usage(&location, name, usage),
+                        None => {
+                            self.get(&location, name, "buffer");
+                        }
+                    }
+                }
+                if usage.is_none() {
+                    self.mismatch(location, "buffers", binding);
+                }
+                names.len()
+            }
+            raw::DescriptorRange::Images(ref names) => {
+                let usage = match binding.ty {
+                    Dt::Image {
+                        ty:
+                            Idt::Sampled {
+                                with_sampler: false,
+                            },
+                    } => Some(i::Usage::SAMPLED),
+                    Dt::Image {
+                        ty: Idt::Storage { .. },
+                    } => Some(i::Usage::STORAGE),
+                    Dt::InputAttachment => Some(i::Usage::INPUT_ATTACHMENT),
+                    _ => None,
+                };
+                for (k, (name, _)) in names.iter().enumerate() {
+                    let location = format!("{}[{}]", location, k);
+                    if let (Some(view), Some(usage)) = (self.view(&location, name), usage) {
+                        if !view.usage.contains(usage) {
+                            let message =
+                                format!("image '{}' lacks the {:?} usage", view.image, usage);
+                            self.error(&location, message);
+                        }
+                    }
+                }
+                if usage.is_none() {
+                    self.mismatch(location, "images", binding);
+                }
+                names.len()
+            }
+            raw::DescriptorRange::Samplers(ref names) => {
+                for (k, name) in names.iter().enumerate() {
+                    self.get(&format!("{}[{}]", location, k), name, "sampler");
+                }
+                if binding.ty != Dt::Sampler {
+                    self.mismatch(location, "samplers", binding);
+                }
+                names.len()
+            }
+        };
+        if count > binding.count {
+            let message = format!(
+                "{} descriptors for the {} of binding {}",
+                count, binding.count, binding.binding
+            );
+            self.error(location, message);
+        }
+    }
+
+    fn mismatch(&mut self, location: &str, what: &str, binding: &pso::DescriptorSetLayoutBinding) {
+        let message = format!(
+            "{} can't be written to binding {} of type {:?}",
+            what, binding.binding, binding.ty
+        );
+        self.error(location, message);
+    }
+
+    fn lint_job(&mut self, name: &str, job: &'a raw::Job) {
+        let at = |field: &str| format!("jobs[{:?}]{}", name, field);
+        self.lint_submission(name, job);
+        // Only transfer jobs bring the resources they use to the state
+        // expected by the ownership transfers.
+        for resource in self.job_resources(job) {
+            if let Some(&transfer_job) = self.transferred.get(resource) {
+                let message = format!(
+                    "'{}' changes queue family in job '{}', which is only supported \
+                     for resources used by transfer jobs",
+                    resource, transfer_job
+                );
+                self.error(&at(""), message);
+            }
+        }
+        match *job {
+            raw::Job::Transfer { ref commands, .. } => {
+                use crate::raw::TransferCommand as Tc;
+                for (k, command) in commands.iter().enumerate() {
+                    let location = at(&format!(".commands[{}]", k));
+                    match *command {
+                        Tc::CopyBuffer {
+                            ref src, ref dst, ..
+                        } => {
+                            self.check_buffer_usage(&location, src, b::Usage::TRANSFER_SRC);
+                            self.check_buffer_usage(&location, dst, b::Usage::TRANSFER_DST);
+                        }
+                        Tc::CopyImage {
+                            ref src, ref dst, ..
+                        }
+                        | Tc::BlitImage {
+                            ref src, ref dst, ..
+                        } => {
+                            self.check_image_usage(&location, src, i::Usage::TRANSFER_SRC);
+                            self.check_image_usage(&location, dst, i::Usage::TRANSFER_DST);
+                        }
+                        Tc::CopyBufferToImage {
+                            ref src, ref dst, ..
+                        } => {
+                            self.check_buffer_usage(&location, src, b::Usage::TRANSFER_SRC);
+                            self.check_image_usage(&location, dst, i::Usage::TRANSFER_DST);
+                        }
+                        Tc::CopyImageToBuffer {
+                            ref src, ref dst, ..
+                        } => {
+                            self.check_image_usage(&location, src, i::Usage::TRANSFER_SRC);
+                            self.check_buffer_usage(&location, dst, b::Usage::TRANSFER_DST);
+                        }
+                        Tc::ClearImage { ref image, .. } => {
+                            self.check_image_usage(&location, image, i::Usage::TRANSFER_DST);
+                        }
+                        Tc::FillBuffer { ref buffer, .. } => {
+                            self.check_buffer_usage(&location, buffer, b::Usage::TRANSFER_DST);
+                        }
+                        Tc::WriteTimestamp { ref pool, id, .. } => {
+                            self.check_queries(&location, pool, id .. id + 1, QueryUse::Timestamp);
+                        }
+                        Tc::CopyQueryResults {
+                            ref pool,
+                            ref queries,
+                            ref buffer,
+                            ..
+                        } => {
+                            self.check_queries(&location, pool, queries.clone(), QueryUse::Any);
+                            self.check_buffer_usage(&location, buffer, b::Usage::TRANSFER_DST);
+                        }
+                        Tc::SetEvent { ref event, .. } | Tc::ResetEvent { ref event, .. } => {
+                            self.get(&location, event, "event");
+                            self.check_event_queue(&location, event, name, job.queue());
+                        }
+                        Tc::WaitEvents { ref events, .. } => {
+                            for event in events {
+                                self.get(&location, event, "event");
+                                self.check_event_queue(&location, event, name, job.queue());
+                            }
+                        }
+                        Tc::Release {
+                            ref resource,
+                            ref queue,
+                        }
+                        | Tc::Acquire {
+                            ref resource,
+                            ref queue,
+                        } => {
+                            match self.scene.resources.get(resource) {
+                                Some(Resource::Buffer { .. }) | Some(Resource::Image { .. }) => {}
+                                Some(other) => {
+                                    let message = format!(
+                                        "'{}' is {}, not a buffer or an image",
+                                        resource,
+                                        with_article(kind_name(other))
+                                    );
+                                    self.error(&location, message);
+                                }
+                                None => {
+                                    let message = format!("missing buffer or image '{}'", resource);
+                                    self.error(&location, message);
+                                }
+                            }
+                            if queue == job.queue() {
+                                let message = format!("'{}' is the queue of the job", queue);
+                                self.error(&location, message);
+                            } else if !queue.is_empty() && !self.scene.queues.contains_key(queue) {
+                                self.error(&location, format!("missing queue '{}'", queue));
+                            }
+                        }
+                    }
+                }
+            }
+            raw::Job::Graphics {
+                ref framebuffer,
+                ref pass,
+                ..
+            } => {
+                if let Some(Resource::Framebuffer { pass: fb_pass, .. }) =
+                    self.get(&at(".framebuffer"), framebuffer, "framebuffer")
+                {
+                    if !self.compatible(fb_pass, &pass.0) {
+                        let message = format!(
+                            "framebuffer '{}' is for render pass '{}', incompatible with '{}'",
+                            framebuffer, fb_pass, pass.0
+                        );
+                        self.error(&at(".framebuffer"), message);
+                    }
+                }
+                let subpasses = match self.get(&at(".pass"), &pass.0, "render pass") {
+                    Some(Resource::RenderPass { subpasses, .. }) => subpasses,
+                    _ => return,
+                };
+                for sp_name in subpasses.keys() {
+                    if !pass.1.contains_key(sp_name) {
+                        let message = format!("no commands for subpass '{}'", sp_name);
+                        self.error(&at(".pass"), message);
+                    }
+                }
+                for (sp_name, draw_pass) in &pass.1 {
+                    let location = at(&format!(".pass[{:?}]", sp_name));
+                    let subpass = subpasses.get(sp_name);
+                    if subpass.is_none() {
+                        let message =
+                            format!("render pass '{}' has no subpass '{}'", pass.0, sp_name);
+                        self.error(&location, message);
+                    }
+                    self.lint_draw_pass(&location, &pass.0, subpass, draw_pass);
+                }
+            }
+            raw::Job::Compute {
+                ref pipeline,
+                ref descriptor_sets,
+                ref push_constants,
+                dispatch,
+                ref dispatch_indirect,
+                ..
+            } => {
+                let layout = match self.get(&at(".pipeline"), pipeline, "compute pipeline") {
+                    Some(Resource::ComputePipeline { layout, .. }) => Some(layout),
+                    _ => None,
+                };
+                // A missing layout is reported with the pipeline.
+                let layout = layout.filter(|layout| {
+                    matches!(
+                        self.scene.resources.get(*layout),
+                        Some(Resource::PipelineLayout { .. })
+                    )
+                });
+                self.lint_set_bindings(&at(".descriptor_sets"), layout, 0, descriptor_sets);
+                if let Some(layout) = layout {
+                    for (k, &(offset, ref data)) in push_constants.iter().enumerate() {
+                        let location = at(&format!(".push_constants[{}]", k));
+                        let stages = pso::ShaderStageFlags::COMPUTE;
+                        self.check_push_constants(&location, layout, stages, offset, data.len());
+                    }
+                }
+                match *dispatch_indirect {
+                    Some((ref buffer, _)) => {
+                        let location = at(".dispatch_indirect");
+                        self.check_buffer_usage(&location, buffer, b::Usage::INDIRECT);
+                        if dispatch != [0; 3] {
+                            let message = "dispatch is ignored with dispatch_indirect".to_string();
+                            self.error(&at(".dispatch"), message);
+                        }
+                    }
+                    None if dispatch.contains(&0) => {
+                        let message = format!("dispatch {:?} is empty", dispatch);
+                        self.error(&at(".dispatch"), message);
+                    }
+                    None => {}
+                }
+            }
+        }
+    }
+
+    /// Buffers and images used by a graphics or compute job.
+    fn job_resources(&self, job: &'a raw::Job) -> BTreeSet<&'a str> {
+        let scene = self.scene;
+        let view_image = |view: &str| match scene.resources.get(view) {
+            Some(Resource::ImageView { image, .. }) => Some(image.as_str()),
+            _ => None,
+        };
+        let mut used = BTreeSet::new();
+        let mut sets = Vec::new();
+        match *job {
+            raw::Job::Transfer { .. } => {}
+            raw::Job::Graphics {
+                ref framebuffer,
+                ref pass,
+                ..
+            } => {
+                if let Some(Resource::Framebuffer { views, .. }) = scene.resources.get(framebuffer)
+                {
+                    used.extend(views.values().filter_map(|view| view_image(view)));
+                }
+                for command in pass.1.values().flat_map(|draw_pass| &draw_pass.commands) {
+                    match *command {
+                        raw::DrawCommand::BindIndexBuffer { ref buffer, .. }
+                        | raw::DrawCommand::DrawIndirect { ref buffer, .. }
+                        | raw::DrawCommand::DrawIndexedIndirect { ref buffer, .. } => {
+                            used.insert(buffer.as_str());
+                        }
+                        raw::DrawCommand::BindVertexBuffers(ref buffers) => {
+                            used.extend(buffers.iter().map(|(buffer, _)| buffer.as_str()));
+                        }
+                        raw::DrawCommand::BindDescriptorSets {
+                            sets: ref bound, ..
+                        } => {
+                            sets.extend(bound);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            raw::Job::Compute {
+                ref descriptor_sets,
+                ref dispatch_indirect,
+                ..
+            } => {
+                sets.extend(descriptor_sets);
+                if let Some((ref buffer, _)) = *dispatch_indirect {
+                    used.insert(buffer.as_str());
+                }
+            }
+        }
+        for set in sets {
+            if let Some(Resource::DescriptorSet { data, .. }) = scene.resources.get(set) {
+                for range in data {
+                    match *range {
+                        raw::DescriptorRange::Buffers(ref buffers) => {
+                            used.extend(buffers.iter().map(String::as_str));
+                        }
+                        raw::DescriptorRange::Images(ref views) => {
+                            used.extend(views.iter().filter_map(|(view, _)| view_image(view)));
+                        }
+                        raw::DescriptorRange::Samplers(_) => {}
+                    }
+                }
+            }
+        }
+        used
+    }
+
+    /// Check that an event is only used on a single queue, as events can't
+    /// synchronize work across queues.
+    fn check_event_queue(&mut self, location: &str, event: &'a str, job: &str, queue: &'a str) {
+        let (first_job, first_queue) = self
+            .event_queues
+            .entry(event)
+            .or_insert_with(|| (job.to_string(), queue));
+        if *first_queue != queue {
+            let message = format!(
+                "event '{}' is used on queue '{}' by job '{}', and on queue '{}' here",
+                event, first_queue, first_job, queue
+            );
+            self.error(location, message);
+        }
+    }
+
+    /// Check the queue of the job and its semaphores.
+    fn lint_submission(&mut self, name: &str, job: &raw::Job) {
+        let at = |field: &str| format!("jobs[{:?}]{}", name, field);
+        let queue = job.queue();
+        if !queue.is_empty() {
+            match self.scene.queues.get(queue) {
+                Some(&ty) => {
+                    let (supported, kind) = match *job {
+                        raw::Job::Transfer { .. } => (true, "transfer"),
+                        raw::Job::Graphics { .. } => (ty.supports_graphics(), "graphics"),
+                        raw::Job::Compute { .. } => (ty.supports_compute(), "compute"),
+                    };
+                    if !supported {
+                        let message = format!("{:?} queue '{}' can't run {} jobs", ty, queue, kind);
+                        self.error(&at(".queue"), message);
+                    }
+                }
+                None => self.error(&at(".queue"), format!("missing queue '{}'", queue)),
+            }
+        }
+        for (k, (semaphore, _)) in job.wait().iter().enumerate() {
+            self.get(&at(&format!(".wait[{}]", k)), semaphore, "semaphore");
+        }
+        for (k, semaphore) in job.signal().iter().enumerate() {
+            self.get(&at(&format!(".signal[{}]", k)), semaphore, "semaphore");
+        }
+    }
+
+    /// Check that the `queries` exist in `pool`, and that its type allows the use.
+    fn check_queries(
+        &mut self,
+        location: &str,
+        pool: &str,
+        queries: Range<query::Id>,
+        usage: QueryUse,
+    ) {
+        let (ty, count) = match self.get(location, pool, "query pool") {
+            Some(&Resource::QueryPool { ty, count }) => (ty, count),
+            _ => return,
+        };
+        if queries.start >= queries.end || queries.end > count {
+            let message = format!(
+                "queries {:?} are out of the {} queries of pool '{}'",
+                queries, count, pool
+            );
+            self.error(location, message);
+        }
+        let allowed = match usage {
+            QueryUse::Any => true,
+            QueryUse::Timestamp => ty == query::Type::Timestamp,
+            QueryUse::Scoped => ty != query::Type::Timestamp,
+        };
+        if !allowed {
+            let message = format!("pool '{}' of {:?} queries can't be used here", pool, ty);
+            self.error(location, message);
+        }
+    }
+
+    /// Check that `size` push constants from `offset` are in the ranges of
+    /// the pipeline layout for the `stages`.
+    fn check_push_constants(
+        &mut self,
+        location: &str,
+        layout: &str,
+        stages: pso::ShaderStageFlags,
+        offset: u32,
+        size: usize,
+    ) {
+        let ranges = match self.get(location, layout, "pipeline layout") {
+            Some(Resource::PipelineLayout {
+                push_constant_ranges,
+                ..
+            }) => push_constant_ranges,
+            _ => return,
+        };
+        // Push constant ranges are in bytes, but the data in 32-bit words.
+        let bytes = offset * 4 .. (offset + size as u32) * 4;
+        let covered = ranges.iter().any(|&(range_stages, ref range)| {
+            range_stages.contains(stages) && range.start <= bytes.start && bytes.end <= range.end
+        });
+        if size == 0 || !covered {
+            let message = format!(
+                "bytes {:?} for {:?} are not in a push constant range of layout '{}'",
+                bytes, stages, layout
+            );
+            self.error(location, message);
+        }
+    }
+
+    fn lint_draw_pass(
+        &mut self,
+        location: &str,
+        pass: &str,
+        subpass: Option<&raw::Subpass>,
+        draw_pass: &'a raw::DrawPass,
+    ) {
+        use crate::raw::DrawCommand as Dc;
+        let mut has_pipeline = false;
+        let mut has_index_buffer = false;
+        let mut active_queries = Vec::new();
+        for (k, command) in draw_pass.commands.iter().enumerate() {
+            let location = format!("{}.commands[{}]", location, k);
+            match *command {
+                Dc::BindIndexBuffer { ref buffer, .. } => {
+                    self.check_buffer_usage(&location, buffer, b::Usage::INDEX);
+                    has_index_buffer = true;
+                }
+                Dc::BindVertexBuffers(ref buffers) => {
+                    for (buffer, _) in buffers {
+                        self.check_buffer_usage(&location, buffer, b::Usage::VERTEX);
+                    }
+                }
+                Dc::BindPipeline(ref name) => {
+                    if let Some(Resource::GraphicsPipeline { subpass, .. }) =
+                        self.get(&location, name, "graphics pipeline")
+                    {
+                        if !self.compatible(&subpass.parent, pass) {
+                            let message = format!(
+                                "pipeline '{}' is for render pass '{}', incompatible with '{}'",
+                                name, subpass.parent, pass
+                            );
+                            self.error(&location, message);
+                        }
+                    }
+                    has_pipeline = true;
+                }
+                Dc::BindDescriptorSets {
+                    ref layout,
+                    first,
+                    ref sets,
+                } => {
+                    self.lint_set_bindings(&location, Some(layout), first, sets);
+                }
+                Dc::Draw { .. }
+                | Dc::DrawIndexed { .. }
+                | Dc::DrawIndirect { .. }
+                | Dc::DrawIndexedIndirect { .. } => {
+                    if !has_pipeline {
+                        self.error(&location, "draw without a bound pipeline".to_string());
+                    }
+                    match *command {
+                        Dc::DrawIndexed { .. } | Dc::DrawIndexedIndirect { .. }
+                            if !has_index_buffer =>
+                        {
+                            let message = "indexed draw without a bound index buffer".to_string();
+                            self.error(&location, message);
+                        }
+                        _ => {}
+                    }
+                    match *command {
+                        Dc::DrawIndirect { ref buffer, .. }
+                        | Dc::DrawIndexedIndirect { ref buffer, .. } => {
+                            self.check_buffer_usage(&location, buffer, b::Usage::INDIRECT);
+                        }
+                        _ => {}
+                    }
+                }
+                Dc::PushConstants {
+                    ref layout,
+                    stages,
+                    offset,
+                    ref data,
+                } => {
+                    self.check_push_constants(&location, layout, stages, offset, data.len());
+                }
+                Dc::ClearAttachments { ref clears, .. } => {
+                    let subpass = match subpass {
+                        Some(subpass) => subpass,
+                        None => continue,
+                    };
+                    for clear in clears {
+                        match *clear {
+                            raw::AttachmentClear::Color { index, .. }
+                                if index >= subpass.colors.len() =>
+                            {
+                                let message = format!(
+                                    "the subpass has no color attachment {} to clear",
+                                    index
+                                );
+                                self.error(&location, message);
+                            }
+                            raw::AttachmentClear::DepthStencil { .. }
+                                if subpass.depth_stencil.is_none() =>
+                            {
+                                let message =
+                                    "the subpass has no depth-stencil attachment to clear"
+                                        .to_string();
+                                self.error(&location, message);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                Dc::BeginQuery { ref pool, id, .. } => {
+                    self.check_queries(&location, pool, id .. id + 1, QueryUse::Scoped);
+                    if active_queries.contains(&(pool, id)) {
+                        let message = format!("query {} of pool '{}' is already active", id, pool);
+                        self.error(&location, message);
+                    } else {
+                        active_queries.push((pool, id));
+                    }
+                }
+                Dc::EndQuery { ref pool, id } => {
+                    match active_queries
+                        .iter()
+                        .position(|&active| active == (pool, id))
+                    {
+                        Some(index) => {
+                            active_queries.swap_remove(index);
+                        }
+                        None => {
+                            let message = format!("query {} of pool '{}' is not active", id, pool);
+                            self.error(&location, message);
+                        }
+                    }
+                }
+                Dc::WriteTimestamp { ref pool, id, .. } => {
+                    self.check_queries(&location, pool, id .. id + 1, QueryUse::Timestamp);
+                }
+                Dc::SetViewports(_)
+                | Dc::SetScissors(_)
+                | Dc::SetStencilReference { .. }
+                | Dc::SetBlendConstants(_)
+                | Dc::SetDepthBounds(_) => {}
+            }
+        }
+        // Queries can't span several subpasses.
+        for (pool, id) in active_queries {
+            let message = format!("query {} of pool '{}' is not ended", id, pool);
+            self.error(location, message);
+        }
+    }
+
+    /// Check binding the descriptor `sets` from the `first` set of the
+    /// pipeline layout, if it is known.
+    fn lint_set_bindings(
+        &mut self,
+        location: &str,
+        layout: Option<&String>,
+        first: usize,
+        sets: &[String],
+    ) {
+        let set_layouts = match layout {
+            Some(layout) => match self.get(location, layout, "pipeline layout") {
+                Some(Resource::PipelineLayout { set_layouts, .. }) => Some(set_layouts),
+                _ => None,
+            },
+            None => None,
+        };
+        if let Some(set_layouts) = set_layouts {
+            if first + sets.len() > set_layouts.len() {
+                let message = format!(
+                    "{} descriptor sets from set {} for the {} sets of layout '{}'",
+                    sets.len(),
+                    first,
+                    set_layouts.len(),
+                    layout.unwrap()
+                );
+                self.error(location, message);
+            }
+        }
+        for (k, set) in sets.iter().enumerate() {
+            let set_layout = match self.get(location, set, "descriptor set") {
+                Some(Resource::DescriptorSet { layout, .. }) => layout,
+                _ => continue,
+            };
+            match set_layouts.and_then(|layouts| layouts.get(first + k)) {
+                Some(expected) if expected != set_layout => {
+                    let message = format!(
+                        "descriptor set '{}' has layout '{}' instead of '{}'",
+                        set, set_layout, expected
+                    );
+                    self.error(location, message);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Check that the names of required limits are fields of `hal::Limits`, as
+/// unknown names could never be met. `location` is the path of the map,
+/// e.g. `limits` for the ones of a scene.
+pub fn lint_limits(location: &str, limits: &BTreeMap<String, serde_json::Value>) -> Vec<LintError> {
+    let known = serde_json::to_value(hal::Limits::default()).unwrap_or_default();
+    limits
+        .keys()
+        .filter(|name| known.get(name.as_str()).is_none())
+        .map(|name| LintError {
+            location: format!("{}[{:?}]", location, name),
+            message: format!("unknown limit '{}', not a field of `hal::Limits`", name),
+        })
+        .collect()
+}
+
+/// Check a scene for dangling references and inconsistencies between its
+/// resources and jobs, returning all the problems found.
+pub fn lint(scene: &raw::Scene) -> Vec<LintError> {
+    let mut linter = Linter {
+        scene,
+        errors: lint_limits("limits", &scene.limits),
+        event_queues: HashMap::new(),
+        transferred: HashMap::new(),
+    };
+    for (name, resource) in &scene.resources {
+        linter.lint_resource(name, resource);
+    }
+    for (name, job) in &scene.jobs {
+        if let raw::Job::Transfer { ref commands, .. } = *job {
+            for command in commands {
+                if let raw::TransferCommand::Release { ref resource, .. }
+                | raw::TransferCommand::Acquire { ref resource, .. } = *command
+                {
+                    linter
+                        .transferred
+                        .entry(resource.as_str())
+                        .or_insert(name.as_str());
+                }
+            }
+        }
+    }
+    for (name, job) in &scene.jobs {
+        linter.lint_job(name, job);
+    }
+    linter.errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn lint_str(source: &str) -> Vec<String> {
+        let scene = ron::de::from_str::<raw::Scene>(source).unwrap();
+        lint(&scene).iter().map(|e| e.to_string()).collect()
+    }
+
+    #[test]
+    fn test_scenes() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../../work/scenes");
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let scene =
+                ron::de::from_reader::<_, raw::Scene>(fs::File::open(&path).unwrap()).unwrap();
+            assert_eq!(lint(&scene), Vec::new(), "{:?}", path);
+        }
+    }
+
+    #[test]
+    fn test_references() {
+        let errors = lint_str(
+            r#"(
+                resources: {
+                    "buffer": Buffer(size: 4, usage: [TRANSFER_SRC], data: "data.bin"),
+                    "image": Image(
+                        kind: D2(1, 1, 1, 1),
+                        num_levels: 1,
+                        format: Rgba8Unorm,
+                        usage: [TRANSFER_DST],
+                    ),
+                    "view": ImageView(
+                        image: "buffer",
+                        kind: D2,
+                        format: Rgba8Unorm,
+                        range: (aspects: (bits: 1), levels: (start: 0, end: 1), layers: (start: 0, end: 1)),
+                    ),
+                    "pipe": ComputePipeline(shader: "shader", layout: "layuot"),
+                },
+                jobs: {
+                    "copy": Transfer(commands: [
+                        CopyBufferToImage(src: "buffer", dst: "image", regions: []),
+                        CopyImageToBuffer(src: "image", dst: "buffer", regions: []),
+                    ]),
+                    "fill": Compute(pipeline: "pipe", descriptor_sets: [], dispatch: (1, 1, 1)),
+                },
+            )"#,
+        );
+        assert_eq!(
+            errors,
+            vec![
+                r#"resources["buffer"].usage: initial data needs the TRANSFER_DST usage"#,
+                r#"resources["pipe"].shader: missing shader 'shader'"#,
+                r#"resources["pipe"].layout: missing pipeline layout 'layuot'"#,
+                r#"resources["view"].image: 'buffer' is a buffer, not an image"#,
+                r#"jobs["copy"].commands[1]: image 'image' lacks the TRANSFER_SRC usage"#,
+                r#"jobs["copy"].commands[1]: buffer 'buffer' lacks the TRANSFER_DST usage"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_queries() {
+        let errors = lint_str(
+            r#"(
+                resources: {
+                    "timestamps": QueryPool(ty: Timestamp, count: 2),
+                    "occlusion": QueryPool(ty: Occlusion, count: 1),
+                    "buffer": Buffer(size: 8, usage: [INDIRECT]),
+                },
+                jobs: {
+                    "write": Transfer(commands: [
+                        WriteTimestamp(pool: "occlusion", id: 0, stage: [TRANSFER]),
+                        CopyQueryResults(
+                            pool: "timestamps",
+                            queries: (start: 1, end: 3),
+                            buffer: "buffer",
+                            offset: 0,
+                            stride: 8,
+                        ),
+                    ]),
+                    "dispatch": Compute(
+                        pipeline: "pipe",
+                        descriptor_sets: [],
+                        dispatch_indirect: Some(("buffer", 0)),
+                    ),
+                },
+            )"#,
+        );
+        assert_eq!(
+            errors,
+            vec![
+                r#"jobs["dispatch"].pipeline: missing compute pipeline 'pipe'"#,
+                r#"jobs["write"].commands[0]: pool 'occlusion' of Occlusion queries can't be used here"#,
+                r#"jobs["write"].commands[1]: queries 1..3 are out of the 2 queries of pool 'timestamps'"#,
+                r#"jobs["write"].commands[1]: buffer 'buffer' lacks the TRANSFER_DST usage"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sync() {
+        let errors = lint_str(
+            r#"(
+                queues: {"transfer": Transfer},
+                resources: {
+                    "buffer": Buffer(size: 4, usage: [TRANSFER_SRC]),
+                    "done": Event,
+                },
+                jobs: {
+                    "dispatch": Compute(
+                        queue: "transfer",
+                        pipeline: "pipe",
+                        descriptor_sets: [],
+                        dispatch: (1, 1, 1),
+                    ),
+                    "release": Transfer(
+                        queue: "transfre",
+                        signal: ["done"],
+                        commands: [
+                            Release(resource: "buffer", queue: "transfer"),
+                            Acquire(resource: "done", queue: "compute"),
+                            SetEvent(event: "done", stages: [TRANSFER]),
+                        ],
+                    ),
+                    "wait": Transfer(
+                        queue: "transfer",
+                        commands: [
+                            WaitEvents(events: ["done"], stages: (start: [TRANSFER], end: [TRANSFER])),
+                        ],
+                    ),
+                },
+            )"#,
+        );
+        assert_eq!(
+            errors,
+            vec![
+                r#"jobs["dispatch"].queue: Transfer queue 'transfer' can't run compute jobs"#,
+                r#"jobs["dispatch"].pipeline: missing compute pipeline 'pipe'"#,
+                r#"jobs["release"].queue: missing queue 'transfre'"#,
+                r#"jobs["release"].signal[0]: 'done' is an event, not a semaphore"#,
+                r#"jobs["release"].commands[1]: 'done' is an event, not a buffer or an image"#,
+                r#"jobs["release"].commands[1]: missing queue 'compute'"#,
+                r#"jobs["wait"].commands[0]: event 'done' is used on queue 'transfre' by job 'release', and on queue 'transfer' here"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ownership() {
+        let errors = lint_str(
+            r#"(
+                queues: {"upload": Transfer},
+                resources: {
+                    "buffer": Buffer(size: 16, usage: [INDIRECT, TRANSFER_SRC]),
+                    "pipe": ComputePipeline(shader: "shader", layout: "layout"),
+                    "shader": Shader("fill.comp"),
+                    "layout": PipelineLayout(set_layouts: [], push_constant_ranges: []),
+                },
+                jobs: {
+                    "acquire": Transfer(
+                        queue: "upload",
+                        commands: [
+                            Acquire(resource: "buffer", queue: ""),
+                        ],
+                    ),
+                    "dispatch": Compute(
+                        pipeline: "pipe",
+                        descriptor_sets: [],
+                        dispatch_indirect: Some(("buffer", 0)),
+                    ),
+                    "release": Transfer(
+                        commands: [
+                            Release(resource: "buffer", queue: "upload"),
+                        ],
+                    ),
+                },
+            )"#,
+        );
+        assert_eq!(
+            errors,
+            vec![
+                r#"jobs["dispatch"]: 'buffer' changes queue family in job 'acquire', which is only supported for resources used by transfer jobs"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_limits() {
+        let errors = lint_str(
+            r#"(
+                limits: {
+                    "max_image_2d_sise": 4096,
+                    "max_image_3d_size": 256,
+                },
+                resources: {},
+                jobs: {},
+            )"#,
+        );
+        assert_eq!(
+            errors,
+            vec![
+                r#"limits["max_image_2d_sise"]: unknown limit 'max_image_2d_sise', not a field of `hal::Limits`"#,
+            ]
+        );
+
+        let mut limits = BTreeMap::new();
+        limits.insert("max_viewports".to_string(), 2.into());
+        limits.insert("viewports".to_string(), 2.into());
+        let errors = lint_limits(r#"suite["basic"].limits"#, &limits);
+        assert_eq!(
+            errors.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+            vec![
+                r#"suite["basic"].limits["viewports"]: unknown limit 'viewports', not a field of `hal::Limits`"#,
+            ]
+        );
+    }
+}
@@ -1,9 +1,11 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::ops::Range;
 
 use hal;
 
-#[derive(Debug, Deserialize)]
+use crate::Requirements;
+
+#[derive(Debug, Serialize, Deserialize)]
 pub enum ClearColor {
     Float([f32; 4]),
     Uint([u32; 4]),
@@ -20,7 +22,7 @@ impl ClearColor {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub enum ClearValue {
     Color(ClearColor),
     DepthStencil(hal::command::ClearDepthStencil),
@@ -37,10 +39,34 @@ impl ClearValue {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
+pub enum AttachmentClear {
+    /// Clear the color attachment at `index` of the current subpass.
+    Color { index: usize, value: ClearColor },
+    DepthStencil {
+        depth: Option<hal::pso::DepthValue>,
+        stencil: Option<hal::pso::StencilValue>,
+    },
+}
+
+impl AttachmentClear {
+    pub fn to_raw(&self) -> hal::command::AttachmentClear {
+        match *self {
+            AttachmentClear::Color { index, ref value } => hal::command::AttachmentClear::Color {
+                index,
+                value: value.to_raw(),
+            },
+            AttachmentClear::DepthStencil { depth, stencil } => {
+                hal::command::AttachmentClear::DepthStencil { depth, stencil }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct AttachmentRef(pub String, pub hal::pass::AttachmentLayout);
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Subpass {
     pub colors: Vec<AttachmentRef>,
     pub depth_stencil: Option<AttachmentRef>,
@@ -52,38 +78,38 @@ pub struct Subpass {
     pub resolves: Vec<AttachmentRef>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SubpassDependency {
     pub passes: Range<String>,
     pub stages: Range<hal::pso::PipelineStage>,
     pub accesses: Range<hal::image::Access>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct GraphicsShaderSet {
     pub vertex: String,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub hull: String,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub domain: String,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub geometry: String,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub fragment: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SubpassRef {
     pub parent: String,
     pub index: hal::pass::SubpassId,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub enum Resource {
     Buffer {
         size: usize,
         usage: hal::buffer::Usage,
-        #[serde(default)]
+        #[serde(default, skip_serializing_if = "String::is_empty")]
         data: String,
     },
     Image {
@@ -91,7 +117,7 @@ pub enum Resource {
         num_levels: hal::image::Level,
         format: hal::format::Format,
         usage: hal::image::Usage,
-        #[serde(default)]
+        #[serde(default, skip_serializing_if = "String::is_empty")]
         data: String,
     },
     ImageView {
@@ -105,9 +131,11 @@ pub enum Resource {
     Sampler {
         info: hal::image::SamplerDesc,
     },
+    /// Attachments and subpasses are ordered by name, which gives the
+    /// order of the clear values and the subpass indices.
     RenderPass {
-        attachments: HashMap<String, hal::pass::Attachment>,
-        subpasses: HashMap<String, Subpass>,
+        attachments: BTreeMap<String, hal::pass::Attachment>,
+        subpasses: BTreeMap<String, Subpass>,
         dependencies: Vec<SubpassDependency>,
     },
     Shader(String),
@@ -149,12 +177,21 @@ pub enum Resource {
     },
     Framebuffer {
         pass: String,
-        views: HashMap<String, String>,
+        views: BTreeMap<String, String>,
         extent: hal::image::Extent,
     },
+    /// Query pool, reset before every run of the jobs.
+    QueryPool {
+        ty: hal::query::Type,
+        count: hal::query::Id,
+    },
+    /// Semaphore ordering jobs, signaled by a job and waited by a later one.
+    Semaphore,
+    /// Event set and waited on within a queue, reset before every run of the jobs.
+    Event,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub enum TransferCommand {
     CopyBuffer {
         src: String,
@@ -193,9 +230,44 @@ pub enum TransferCommand {
         size: Option<hal::buffer::Offset>,
         data: u32,
     },
+    WriteTimestamp {
+        pool: String,
+        id: hal::query::Id,
+        stage: hal::pso::PipelineStage,
+    },
+    CopyQueryResults {
+        pool: String,
+        queries: Range<hal::query::Id>,
+        buffer: String,
+        offset: hal::buffer::Offset,
+        stride: hal::buffer::Offset,
+        #[serde(default = "hal::query::ResultFlags::empty")]
+        flags: hal::query::ResultFlags,
+    },
+    SetEvent {
+        event: String,
+        stages: hal::pso::PipelineStage,
+    },
+    ResetEvent {
+        event: String,
+        stages: hal::pso::PipelineStage,
+    },
+    /// Wait for the events, making the transfer writes before them visible
+    /// to the transfers after.
+    WaitEvents {
+        events: Vec<String>,
+        stages: Range<hal::pso::PipelineStage>,
+    },
+    /// Release a buffer or image, in its stable state, to the family of
+    /// another queue. The resource isn't used by the job afterwards.
+    ///
+    /// Resources changing queue family can only be used by transfer jobs.
+    Release { resource: String, queue: String },
+    /// Acquire a buffer or image released by another queue.
+    Acquire { resource: String, queue: String },
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum DescriptorRange {
     Buffers(Vec<String>),
     Images(Vec<(String, hal::image::Layout)>),
@@ -206,7 +278,7 @@ fn default_instance_range() -> Range<hal::InstanceCount> {
     0 .. 1
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub enum DrawCommand {
     BindIndexBuffer {
         buffer: String,
@@ -230,34 +302,206 @@ pub enum DrawCommand {
         base_vertex: hal::VertexOffset,
         instances: Range<hal::InstanceCount>,
     },
+    DrawIndirect {
+        buffer: String,
+        offset: hal::buffer::Offset,
+        draw_count: hal::DrawCount,
+        stride: u32,
+    },
+    DrawIndexedIndirect {
+        buffer: String,
+        offset: hal::buffer::Offset,
+        draw_count: hal::DrawCount,
+        stride: u32,
+    },
+    PushConstants {
+        layout: String,
+        stages: hal::pso::ShaderStageFlags,
+        offset: u32,
+        data: Vec<u32>,
+    },
     SetViewports(Vec<hal::pso::Viewport>),
     SetScissors(Vec<hal::pso::Rect>),
+    SetStencilReference {
+        faces: hal::pso::Face,
+        value: hal::pso::StencilValue,
+    },
+    SetBlendConstants(hal::pso::ColorValue),
+    SetDepthBounds(Range<f32>),
+    ClearAttachments {
+        clears: Vec<AttachmentClear>,
+        rects: Vec<hal::pso::ClearRect>,
+    },
+    BeginQuery {
+        pool: String,
+        id: hal::query::Id,
+        #[serde(default = "hal::query::ControlFlags::empty")]
+        flags: hal::query::ControlFlags,
+    },
+    EndQuery {
+        pool: String,
+        id: hal::query::Id,
+    },
+    WriteTimestamp {
+        pool: String,
+        id: hal::query::Id,
+        stage: hal::pso::PipelineStage,
+    },
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct DrawPass {
     pub commands: Vec<DrawCommand>,
 }
 
-#[derive(Debug, Deserialize)]
+/// Job submitted to a queue.
+///
+/// Every job runs on its `queue`, the default queue if empty, once the
+/// semaphores of `wait` are signaled for their stages, and signals the
+/// semaphores of `signal` when done.
+#[derive(Debug, Serialize, Deserialize)]
 pub enum Job {
     Transfer {
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        queue: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        wait: Vec<(String, hal::pso::PipelineStage)>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        signal: Vec<String>,
         commands: Vec<TransferCommand>,
     },
+    /// Render pass with the commands of each subpass, which are recorded
+    /// in the order of the subpasses.
     Graphics {
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        queue: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        wait: Vec<(String, hal::pso::PipelineStage)>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        signal: Vec<String>,
         framebuffer: String,
         clear_values: Vec<ClearValue>,
-        pass: (String, HashMap<String, DrawPass>),
+        pass: (String, BTreeMap<String, DrawPass>),
     },
     Compute {
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        queue: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        wait: Vec<(String, hal::pso::PipelineStage)>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        signal: Vec<String>,
         pipeline: String,
         descriptor_sets: Vec<String>,
+        /// Offsets and data of the push constants.
+        #[serde(default)]
+        push_constants: Vec<(u32, Vec<u32>)>,
+        #[serde(default)]
         dispatch: hal::WorkGroupCount,
+        /// Buffer and offset of a `DispatchIndirectCommand`, used instead
+        /// of `dispatch`.
+        #[serde(default)]
+        dispatch_indirect: Option<(String, hal::buffer::Offset)>,
     },
 }
 
-#[derive(Debug, Deserialize)]
+impl Job {
+    /// Name of the queue running the job, empty for the default queue.
+    pub fn queue(&self) -> &str {
+        match *self {
+            Job::Transfer { ref queue, .. }
+            | Job::Graphics { ref queue, .. }
+            | Job::Compute { ref queue, .. } => queue,
+        }
+    }
+
+    /// Semaphores waited before the job, with the stages waiting for them.
+    pub fn wait(&self) -> &[(String, hal::pso::PipelineStage)] {
+        match *self {
+            Job::Transfer { ref wait, .. }
+            | Job::Graphics { ref wait, .. }
+            | Job::Compute { ref wait, .. } => wait,
+        }
+    }
+
+    /// Semaphores signaled after the job.
+    pub fn signal(&self) -> &[String] {
+        match *self {
+            Job::Transfer { ref signal, .. }
+            | Job::Graphics { ref signal, .. }
+            | Job::Compute { ref signal, .. } => signal,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Scene {
-    pub resources: HashMap<String, Resource>,
-    pub jobs: HashMap<String, Job>,
+    #[serde(default = "hal::Features::empty")]
+    pub features: hal::Features,
+    #[serde(default = "hal::Hints::empty")]
+    pub hints: hal::Hints,
+    #[serde(default)]
+    pub limits: BTreeMap<String, serde_json::Value>,
+    /// Queues besides the default one, with the type of their family.
+    /// A family of the exact type is preferred, then any family supporting it.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub queues: BTreeMap<String, hal::queue::QueueType>,
+    pub resources: BTreeMap<String, Resource>,
+    pub jobs: BTreeMap<String, Job>,
+}
+
+impl Scene {
+    /// Device capabilities needed by the scene, including the features
+    /// implied by its resources and commands.
+    pub fn requirements(&self) -> Requirements {
+        Requirements {
+            features: self.features | self.implied_features(),
+            hints: self.hints,
+            limits: self.limits.clone(),
+        }
+    }
+
+    /// Serialize the scene in the RON format of the scene files.
+    pub fn to_ron(&self) -> Result<String, ron::ser::Error> {
+        let config = ron::ser::PrettyConfig {
+            indentor: "\t".to_string(),
+            ..ron::ser::PrettyConfig::default()
+        };
+        ron::ser::to_string_pretty(self, config)
+    }
+
+    fn implied_features(&self) -> hal::Features {
+        use hal::Features as F;
+        let mut features = F::empty();
+        for resource in self.resources.values() {
+            if let Resource::QueryPool {
+                ty: hal::query::Type::PipelineStatistics(_),
+                ..
+            } = *resource
+            {
+                features |= F::PIPELINE_STATISTICS_QUERY;
+            }
+        }
+        let draw_commands = self.jobs.values().flat_map(|job| match *job {
+            Job::Graphics { ref pass, .. } => pass.1.values().flat_map(|dp| &dp.commands).collect(),
+            Job::Transfer { .. } | Job::Compute { .. } => Vec::new(),
+        });
+        for command in draw_commands {
+            features |= match *command {
+                DrawCommand::DrawIndirect { draw_count, .. }
+                | DrawCommand::DrawIndexedIndirect { draw_count, .. }
+                    if draw_count > 1 =>
+                {
+                    F::MULTI_DRAW_INDIRECT
+                }
+                DrawCommand::SetDepthBounds(_) => F::DEPTH_BOUNDS,
+                DrawCommand::BeginQuery { flags, .. }
+                    if flags.contains(hal::query::ControlFlags::PRECISE) =>
+                {
+                    F::PRECISE_OCCLUSION_QUERY
+                }
+                _ => F::empty(),
+            };
+        }
+        features
+    }
 }
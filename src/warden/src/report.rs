@@ -0,0 +1,327 @@
+//! Selection of reftests and reporting of their results.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::time::Duration;
+
+use crate::requirements::Unmet;
+
+/// Reason a test was not run.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum SkipReason {
+    /// The device doesn't meet the requirements of the scene or test group.
+    Unsupported(Unmet),
+    /// The scene has GLSL shaders, which can't be compiled without `glsl-to-spirv`.
+    GlslUnsupported,
+    /// A compute job dispatches more work groups than the device supports.
+    ComputeLimits([u32; 3]),
+    /// The test is disabled on this backend or adapter, for the given reason.
+    Disabled(String),
+    /// The test is a known failure for the given reason, so it isn't blessed.
+    KnownFailure(String),
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SkipReason::Unsupported(ref unmet) => write!(f, "{}", unmet),
+            SkipReason::GlslUnsupported => write!(f, "GLSL shaders"),
+            SkipReason::ComputeLimits(count) => write!(f, "compute {:?}", count),
+            SkipReason::Disabled(ref reason) => write!(f, "disabled: {}", reason),
+            SkipReason::KnownFailure(ref reason) => write!(f, "known failure: {}", reason),
+        }
+    }
+}
+
+/// Result of a test.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "status", content = "details", rename_all = "lowercase")]
+pub enum Outcome {
+    Pass,
+    Fail(String),
+    Skip(SkipReason),
+    /// Known failure, with the failure details.
+    XFail(String),
+    /// Known failure which unexpectedly passed.
+    XPass,
+}
+
+impl Outcome {
+    /// Whether the outcome makes the suite fail.
+    pub fn is_failure(&self) -> bool {
+        match *self {
+            Outcome::Fail(_) | Outcome::XPass => true,
+            Outcome::Pass | Outcome::Skip(_) | Outcome::XFail(_) => false,
+        }
+    }
+}
+
+/// Result of a test on a backend.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TestReport {
+    pub backend: String,
+    pub scene: String,
+    pub test: String,
+    /// Time spent running the jobs and checking the expectation.
+    #[serde(serialize_with = "serialize_seconds")]
+    pub duration: Duration,
+    #[serde(flatten)]
+    pub outcome: Outcome,
+}
+
+fn serialize_seconds<S: serde::Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+/// Whether `name` matches a glob `pattern`, where `*` matches any sequence
+/// of characters and `?` any single character.
+pub fn glob_matches(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    // Position after the last `*`, and of the name where it started matching.
+    let mut star = None;
+    let (mut p, mut n) = (0, 0);
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p ..].iter().all(|&c| c == '*')
+}
+
+/// Selection of the tests to run.
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+    /// Glob patterns matched against `scene/test`, any of which selects a test.
+    pub patterns: Vec<String>,
+    /// Names of the scenes to run, all of them if empty.
+    pub scenes: Vec<String>,
+}
+
+impl Filter {
+    pub fn selects_scene(&self, scene: &str) -> bool {
+        self.scenes.is_empty() || self.scenes.iter().any(|s| s == scene)
+    }
+
+    pub fn selects(&self, scene: &str, test: &str) -> bool {
+        if !self.selects_scene(scene) {
+            return false;
+        }
+        let name = format!("{}/{}", scene, test);
+        self.patterns.is_empty() || self.patterns.iter().any(|p| glob_matches(p, &name))
+    }
+}
+
+/// Write the reports as a JSON array.
+pub fn write_json<W: Write>(reports: &[TestReport], mut writer: W) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut writer, reports)?;
+    writeln!(writer)
+}
+
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Write the reports as JUnit XML, with a test suite per backend and scene.
+pub fn write_junit<W: Write>(reports: &[TestReport], mut writer: W) -> io::Result<()> {
+    fn count(reports: &[&TestReport]) -> (usize, usize, f64) {
+        let failures = reports.iter().filter(|r| r.outcome.is_failure()).count();
+        let skipped = reports
+            .iter()
+            .filter(|r| matches!(r.outcome, Outcome::Skip(_) | Outcome::XFail(_)))
+            .count();
+        let time = reports
+            .iter()
+            .fold(0.0, |time, r| time + r.duration.as_secs_f64());
+        (failures, skipped, time)
+    }
+
+    // Group by backend and scene, in the order of the reports.
+    let mut suites = Vec::<(String, Vec<&TestReport>)>::new();
+    for report in reports {
+        let name = format!("{}.{}", report.backend, report.scene);
+        match suites.iter_mut().find(|(suite, _)| *suite == name) {
+            Some((_, tests)) => tests.push(report),
+            None => suites.push((name, vec![report])),
+        }
+    }
+
+    let (failures, skipped, time) = count(&reports.iter().collect::<Vec<_>>());
+    writeln!(writer, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        writer,
+        "<testsuites tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.6}\">",
+        reports.len(),
+        failures,
+        skipped,
+        time
+    )?;
+    for (name, tests) in suites {
+        let (failures, skipped, time) = count(&tests);
+        let name = escape_xml(&name);
+        writeln!(
+            writer,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.6}\">",
+            name,
+            tests.len(),
+            failures,
+            skipped,
+            time
+        )?;
+        for report in tests {
+            write!(
+                writer,
+                "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.6}\"",
+                name,
+                escape_xml(&report.test),
+                report.duration.as_secs_f64()
+            )?;
+            match report.outcome {
+                Outcome::Pass => writeln!(writer, "/>")?,
+                Outcome::Fail(ref details) => writeln!(
+                    writer,
+                    ">\n      <failure message=\"{}\"/>\n    </testcase>",
+                    escape_xml(details)
+                )?,
+                Outcome::Skip(ref reason) => writeln!(
+                    writer,
+                    ">\n      <skipped message=\"{}\"/>\n    </testcase>",
+                    escape_xml(&reason.to_string())
+                )?,
+                Outcome::XFail(ref details) => writeln!(
+                    writer,
+                    ">\n      <skipped message=\"expected failure: {}\"/>\n    </testcase>",
+                    escape_xml(details)
+                )?,
+                Outcome::XPass => writeln!(
+                    writer,
+                    ">\n      <failure message=\"unexpected pass of a known failure\"/>\n    </testcase>"
+                )?,
+            }
+        }
+        writeln!(writer, "  </testsuite>")?;
+    }
+    writeln!(writer, "</testsuites>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(test: &str, outcome: Outcome) -> TestReport {
+        TestReport {
+            backend: "Vulkan".to_string(),
+            scene: "basic".to_string(),
+            test: test.to_string(),
+            duration: Duration::from_millis(5),
+            outcome,
+        }
+    }
+
+    #[test]
+    fn test_glob() {
+        assert!(glob_matches("basic/*", "basic/render-pass-clear"));
+        assert!(glob_matches("*clear*", "basic/render-pass-clear"));
+        assert!(glob_matches("*/copy-?uf", "transfer/copy-buf"));
+        assert!(glob_matches("a*b*c", "aXbYbZc"));
+        assert!(!glob_matches("basic/*", "transfer/copy-buf"));
+        assert!(!glob_matches("*/copy-?uf", "transfer/copy-buf-cut"));
+        assert!(glob_matches("*", ""));
+    }
+
+    #[test]
+    fn test_filter() {
+        let filter = Filter {
+            patterns: vec!["*/copy-*".to_string()],
+            scenes: vec!["transfer".to_string()],
+        };
+        assert!(filter.selects("transfer", "copy-buf"));
+        assert!(!filter.selects("transfer", "fill-whole"));
+        assert!(!filter.selects("compute", "copy-buf"));
+        assert!(Filter::default().selects("compute", "fill"));
+    }
+
+    #[test]
+    fn test_junit() {
+        let reports = [
+            report("pass", Outcome::Pass),
+            report("fail", Outcome::Fail("[1, 2] <".to_string())),
+            report("skip", Outcome::Skip(SkipReason::ComputeLimits([1, 2, 3]))),
+            report("xfail", Outcome::XFail("[3]".to_string())),
+            report("xpass", Outcome::XPass),
+        ];
+        let mut output = Vec::new();
+        write_junit(&reports, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains(
+            "<testsuite name=\"Vulkan.basic\" tests=\"5\" failures=\"2\" skipped=\"2\" time=\"0.025000\">"
+        ));
+        assert!(output
+            .contains("<testcase classname=\"Vulkan.basic\" name=\"pass\" time=\"0.005000\"/>"));
+        assert!(output.contains("<failure message=\"[1, 2] &lt;\"/>"));
+        assert!(output.contains("<skipped message=\"compute [1, 2, 3]\"/>"));
+        assert!(output.contains("<skipped message=\"expected failure: [3]\"/>"));
+        assert!(output.contains("<failure message=\"unexpected pass of a known failure\"/>"));
+    }
+
+    #[test]
+    fn test_json() {
+        let reports = [
+            report("fail", Outcome::Fail("oops".to_string())),
+            report("skip", Outcome::Skip(SkipReason::GlslUnsupported)),
+        ];
+        let mut output = Vec::new();
+        write_json(&reports, &mut output).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!([
+                {
+                    "backend": "Vulkan",
+                    "scene": "basic",
+                    "test": "fail",
+                    "duration": 0.005,
+                    "status": "fail",
+                    "details": "oops",
+                },
+                {
+                    "backend": "Vulkan",
+                    "scene": "basic",
+                    "test": "skip",
+                    "duration": 0.005,
+                    "status": "skip",
+                    "details": "GlslUnsupported",
+                },
+            ])
+        );
+    }
+}
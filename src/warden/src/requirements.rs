@@ -0,0 +1,305 @@
+//! Device capabilities required by scenes and test groups.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde_json::Value;
+
+/// Features, hints and limits a device needs to run some tests.
+///
+/// Limits are named after the fields of `hal::Limits`, with values of the same
+/// shape. The device values must be at least the required ones, except for
+/// alignments and granularities which must be at most the required ones,
+/// sample counts which must contain the required ones, and flags which must
+/// be set if required.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Requirements {
+    #[serde(default = "hal::Features::empty")]
+    pub features: hal::Features,
+    #[serde(default = "hal::Hints::empty")]
+    pub hints: hal::Hints,
+    #[serde(default)]
+    pub limits: BTreeMap<String, Value>,
+}
+
+impl Default for Requirements {
+    fn default() -> Self {
+        Requirements {
+            features: hal::Features::empty(),
+            hints: hal::Hints::empty(),
+            limits: BTreeMap::new(),
+        }
+    }
+}
+
+/// Requirements a device doesn't meet.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Unmet {
+    pub features: hal::Features,
+    pub hints: hal::Hints,
+    /// Names of the unmet limits.
+    pub limits: Vec<String>,
+}
+
+impl fmt::Display for Unmet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut parts = Vec::new();
+        if !self.features.is_empty() {
+            parts.push(format!("features missing: {}", self.features));
+        }
+        if !self.hints.is_empty() {
+            parts.push(format!("hints missing: {}", self.hints));
+        }
+        if !self.limits.is_empty() {
+            parts.push(format!("limits too low: {}", self.limits.join(", ")));
+        }
+        write!(f, "{}", parts.join("; "))
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Direction {
+    AtLeast,
+    AtMost,
+    Contains,
+}
+
+impl Direction {
+    fn of(name: &str) -> Self {
+        if name.contains("alignment")
+            || name.contains("granularity")
+            || name == "non_coherent_atom_size"
+        {
+            Direction::AtMost
+        } else if name.ends_with("sample_counts") {
+            Direction::Contains
+        } else {
+            Direction::AtLeast
+        }
+    }
+}
+
+fn satisfies(direction: Direction, device: &Value, required: &Value) -> bool {
+    match (device, required) {
+        (Value::Number(device), Value::Number(required)) => match direction {
+            Direction::Contains => match (device.as_u64(), required.as_u64()) {
+                (Some(device), Some(required)) => device & required == required,
+                _ => false,
+            },
+            Direction::AtLeast => device.as_f64() >= required.as_f64(),
+            Direction::AtMost => device.as_f64() <= required.as_f64(),
+        },
+        (&Value::Bool(device), &Value::Bool(required)) => device || !required,
+        (Value::Array(device), Value::Array(required)) => {
+            device.len() == required.len()
+                && device
+                    .iter()
+                    .zip(required)
+                    .all(|(d, r)| satisfies(direction, d, r))
+        }
+        (Value::Object(device), Value::Object(required)) => {
+            required.iter().all(|(key, r)| match device.get(key) {
+                Some(d) => satisfies(direction, d, r),
+                None => false,
+            })
+        }
+        _ => false,
+    }
+}
+
+/// Return the stricter of two required values, preferring `other` when
+/// they can't be compared.
+fn stricter(direction: Direction, value: &Value, other: &Value) -> Value {
+    match (value, other) {
+        (Value::Number(a), Value::Number(b)) => match direction {
+            Direction::Contains => match (a.as_u64(), b.as_u64()) {
+                (Some(a), Some(b)) => Value::from(a | b),
+                _ => other.clone(),
+            },
+            Direction::AtLeast if a.as_f64() > b.as_f64() => value.clone(),
+            Direction::AtMost if a.as_f64() < b.as_f64() => value.clone(),
+            _ => other.clone(),
+        },
+        (&Value::Bool(a), &Value::Bool(b)) => Value::Bool(a || b),
+        (Value::Array(a), Value::Array(b)) if a.len() == b.len() => a
+            .iter()
+            .zip(b)
+            .map(|(a, b)| stricter(direction, a, b))
+            .collect(),
+        (Value::Object(a), Value::Object(b)) => {
+            let mut merged = a.clone();
+            for (key, b) in b {
+                let value = match a.get(key) {
+                    Some(a) => stricter(direction, a, b),
+                    None => b.clone(),
+                };
+                merged.insert(key.clone(), value);
+            }
+            Value::Object(merged)
+        }
+        _ => other.clone(),
+    }
+}
+
+impl Requirements {
+    /// Combine with other requirements, keeping the stricter of both limits.
+    pub fn merge(&mut self, other: &Requirements) {
+        self.features |= other.features;
+        self.hints |= other.hints;
+        for (name, value) in &other.limits {
+            let merged = match self.limits.get(name) {
+                Some(current) => stricter(Direction::of(name), current, value),
+                None => value.clone(),
+            };
+            self.limits.insert(name.clone(), merged);
+        }
+    }
+
+    /// Check the requirements against the capabilities of a device.
+    ///
+    /// Unknown limit names are reported as unmet, they are rejected
+    /// beforehand by `lint::lint_limits`.
+    pub fn check(
+        &self,
+        features: hal::Features,
+        hints: hal::Hints,
+        limits: &hal::Limits,
+    ) -> Result<(), Unmet> {
+        let device_limits = serde_json::to_value(limits).unwrap_or(Value::Null);
+        let unmet = Unmet {
+            features: self.features - features,
+            hints: self.hints - hints,
+            limits: self
+                .limits
+                .iter()
+                .filter(|&(name, required)| match device_limits.get(name) {
+                    Some(device) => !satisfies(Direction::of(name), device, required),
+                    None => true,
+                })
+                .map(|(name, _)| name.clone())
+                .collect(),
+        };
+        if unmet.features.is_empty() && unmet.hints.is_empty() && unmet.limits.is_empty() {
+            Ok(())
+        } else {
+            Err(unmet)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let requirements: Requirements = ron::de::from_str(
+            r#"(
+                features: [GEOMETRY_SHADER, SAMPLER_ANISOTROPY],
+                hints: [BASE_VERTEX_INSTANCE_DRAWING],
+                limits: {
+                    "max_image_2d_size": 4096,
+                    "max_compute_work_group_count": [64, 1, 1],
+                    "min_uniform_buffer_offset_alignment": 256,
+                    "framebuffer_color_sample_counts": 4,
+                    "max_framebuffer_extent": {"width": 4096},
+                },
+            )"#,
+        )
+        .unwrap();
+        assert_eq!(
+            requirements.features,
+            hal::Features::GEOMETRY_SHADER | hal::Features::SAMPLER_ANISOTROPY
+        );
+
+        let mut limits = hal::Limits {
+            max_image_2d_size: 8192,
+            max_compute_work_group_count: [65535, 65535, 65535],
+            min_uniform_buffer_offset_alignment: 64,
+            framebuffer_color_sample_counts: 0b1111,
+            max_framebuffer_extent: hal::image::Extent {
+                width: 4096,
+                height: 4096,
+                depth: 1,
+            },
+            ..hal::Limits::default()
+        };
+        let features = hal::Features::GEOMETRY_SHADER | hal::Features::SAMPLER_ANISOTROPY;
+        let hints = hal::Hints::BASE_VERTEX_INSTANCE_DRAWING;
+        assert_eq!(requirements.check(features, hints, &limits), Ok(()));
+
+        limits.max_image_2d_size = 2048;
+        limits.min_uniform_buffer_offset_alignment = 512;
+        limits.framebuffer_color_sample_counts = 0b0011;
+        let unmet = requirements
+            .check(hal::Features::GEOMETRY_SHADER, hal::Hints::empty(), &limits)
+            .unwrap_err();
+        assert_eq!(
+            unmet,
+            Unmet {
+                features: hal::Features::SAMPLER_ANISOTROPY,
+                hints: hal::Hints::BASE_VERTEX_INSTANCE_DRAWING,
+                limits: vec![
+                    "framebuffer_color_sample_counts".to_string(),
+                    "max_image_2d_size".to_string(),
+                    "min_uniform_buffer_offset_alignment".to_string(),
+                ],
+            }
+        );
+        assert_eq!(
+            unmet.to_string(),
+            "features missing: SAMPLER_ANISOTROPY; hints missing: BASE_VERTEX_INSTANCE_DRAWING; \
+             limits too low: framebuffer_color_sample_counts, max_image_2d_size, \
+             min_uniform_buffer_offset_alignment"
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut scene: Requirements = ron::de::from_str(
+            r#"(
+                features: [GEOMETRY_SHADER],
+                limits: {
+                    "max_image_2d_size": 4096,
+                    "max_compute_work_group_count": [64, 1, 16],
+                    "min_uniform_buffer_offset_alignment": 64,
+                    "framebuffer_color_sample_counts": 4,
+                    "max_framebuffer_extent": {"width": 4096},
+                },
+            )"#,
+        )
+        .unwrap();
+        let group: Requirements = ron::de::from_str(
+            r#"(
+                hints: [BASE_VERTEX_INSTANCE_DRAWING],
+                limits: {
+                    "max_image_2d_size": 1024,
+                    "max_compute_work_group_count": [16, 8, 1],
+                    "min_uniform_buffer_offset_alignment": 256,
+                    "framebuffer_color_sample_counts": 1,
+                    "max_framebuffer_extent": {"width": 1024, "height": 1024},
+                    "max_viewports": 2,
+                },
+            )"#,
+        )
+        .unwrap();
+        scene.merge(&group);
+
+        let expected: Requirements = ron::de::from_str(
+            r#"(
+                features: [GEOMETRY_SHADER],
+                hints: [BASE_VERTEX_INSTANCE_DRAWING],
+                limits: {
+                    "max_image_2d_size": 4096,
+                    "max_compute_work_group_count": [64, 8, 16],
+                    "min_uniform_buffer_offset_alignment": 64,
+                    "framebuffer_color_sample_counts": 5,
+                    "max_framebuffer_extent": {"width": 4096, "height": 1024},
+                    "max_viewports": 2,
+                },
+            )"#,
+        )
+        .unwrap();
+        assert_eq!(scene, expected);
+    }
+}
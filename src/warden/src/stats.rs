@@ -0,0 +1,237 @@
+//! Statistics of benchmark timings, and their comparison against a baseline.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::time::Duration;
+
+/// Source of the timings of a benchmark.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Clock {
+    /// Timestamp queries around the submitted jobs.
+    Gpu,
+    /// CPU wall-clock time of the submission until the device is idle,
+    /// for backends without timestamp queries.
+    Cpu,
+}
+
+/// Summary of the timings of a benchmark, in microseconds.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    pub iterations: usize,
+    pub min: f64,
+    pub median: f64,
+    pub p95: f64,
+    pub mean: f64,
+    pub stddev: f64,
+}
+
+impl Stats {
+    /// Compute the statistics of the samples, or `None` if there are none.
+    pub fn new(samples: &[Duration]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut micros = samples
+            .iter()
+            .map(|d| d.as_secs_f64() * 1e6)
+            .collect::<Vec<_>>();
+        micros.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let n = micros.len();
+        let median = if n % 2 == 0 {
+            0.5 * (micros[n / 2 - 1] + micros[n / 2])
+        } else {
+            micros[n / 2]
+        };
+        // Nearest rank, so that the percentile is one of the samples.
+        let p95 = micros[((0.95 * n as f64).ceil() as usize).max(1) - 1];
+        let mean = micros.iter().sum::<f64>() / n as f64;
+        let variance = if n > 1 {
+            micros.iter().map(|t| (t - mean) * (t - mean)).sum::<f64>() / (n - 1) as f64
+        } else {
+            0.0
+        };
+
+        Some(Stats {
+            iterations: n,
+            min: micros[0],
+            median,
+            p95,
+            mean,
+            stddev: variance.sqrt(),
+        })
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "median {:.1} us (min {:.1}, p95 {:.1}, stddev {:.1}, {} iterations)",
+            self.median, self.min, self.p95, self.stddev, self.iterations
+        )
+    }
+}
+
+/// Result of a benchmark on a backend.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BenchReport {
+    pub backend: String,
+    pub scene: String,
+    pub test: String,
+    pub clock: Clock,
+    #[serde(flatten)]
+    pub stats: Stats,
+}
+
+/// Benchmark getting slower than its baseline.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Regression {
+    pub backend: String,
+    pub scene: String,
+    pub test: String,
+    /// Median time of the baseline, in microseconds.
+    pub baseline: f64,
+    /// Median time of the current run, in microseconds.
+    pub current: f64,
+}
+
+impl Regression {
+    /// Relative increase of the median time.
+    pub fn slowdown(&self) -> f64 {
+        self.current / self.baseline - 1.0
+    }
+}
+
+impl fmt::Display for Regression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}/{} {:.1} -> {:.1} us ({:+.1}%)",
+            self.backend,
+            self.scene,
+            self.test,
+            self.baseline,
+            self.current,
+            100.0 * self.slowdown()
+        )
+    }
+}
+
+/// Compare the medians of the reports against the baseline, returning the
+/// benchmarks more than `threshold` (relative) slower.
+///
+/// Benchmarks missing from the baseline, or timed with a different clock,
+/// are not compared.
+pub fn find_regressions(
+    reports: &[BenchReport],
+    baseline: &[BenchReport],
+    threshold: f64,
+) -> Vec<Regression> {
+    reports
+        .iter()
+        .filter_map(|report| {
+            let base = baseline.iter().find(|b| {
+                b.backend == report.backend
+                    && b.scene == report.scene
+                    && b.test == report.test
+                    && b.clock == report.clock
+            })?;
+            if report.stats.median > base.stats.median * (1.0 + threshold) {
+                Some(Regression {
+                    backend: report.backend.clone(),
+                    scene: report.scene.clone(),
+                    test: report.test.clone(),
+                    baseline: base.stats.median,
+                    current: report.stats.median,
+                })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Write the reports as a JSON array, which can be read back as a baseline.
+pub fn write_json<W: Write>(reports: &[BenchReport], mut writer: W) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut writer, reports)?;
+    writeln!(writer)
+}
+
+/// Read the reports written by `write_json`.
+pub fn read_json<R: Read>(reader: R) -> Result<Vec<BenchReport>, serde_json::Error> {
+    serde_json::from_reader(reader)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(test: &str, clock: Clock, median: f64) -> BenchReport {
+        BenchReport {
+            backend: "Vulkan".to_string(),
+            scene: "large".to_string(),
+            test: test.to_string(),
+            clock,
+            stats: Stats {
+                iterations: 10,
+                min: median,
+                median,
+                p95: median,
+                mean: median,
+                stddev: 0.0,
+            },
+        }
+    }
+
+    #[test]
+    fn test_stats() {
+        assert_eq!(Stats::new(&[]), None);
+
+        let samples = (1 ..= 20)
+            .rev()
+            .map(Duration::from_micros)
+            .collect::<Vec<_>>();
+        let stats = Stats::new(&samples).unwrap();
+        assert_eq!(stats.iterations, 20);
+        assert_eq!(stats.min, 1.0);
+        assert_eq!(stats.median, 10.5);
+        assert_eq!(stats.p95, 19.0);
+        assert_eq!(stats.mean, 10.5);
+        assert!((stats.stddev - 5.9161).abs() < 1e-3);
+
+        let single = Stats::new(&[Duration::from_micros(7)]).unwrap();
+        assert_eq!((single.median, single.p95, single.stddev), (7.0, 7.0, 0.0));
+    }
+
+    #[test]
+    fn test_regressions() {
+        let baseline = vec![
+            report("copy", Clock::Gpu, 100.0),
+            report("blit", Clock::Gpu, 100.0),
+            report("shader-copy", Clock::Cpu, 100.0),
+        ];
+        let reports = vec![
+            report("copy", Clock::Gpu, 105.0),
+            report("blit", Clock::Gpu, 120.0),
+            report("shader-copy", Clock::Gpu, 200.0),
+            report("new", Clock::Gpu, 200.0),
+        ];
+        let regressions = find_regressions(&reports, &baseline, 0.1);
+        assert_eq!(regressions.len(), 1);
+        assert_eq!(regressions[0].test, "blit");
+        assert!((regressions[0].slowdown() - 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_json_roundtrip() {
+        let reports = vec![report("copy", Clock::Gpu, 12.5)];
+        let mut out = Vec::new();
+        write_json(&reports, &mut out).unwrap();
+        let text = String::from_utf8(out.clone()).unwrap();
+        assert!(text.contains("\"clock\": \"gpu\""));
+        assert!(text.contains("\"median\": 12.5"));
+        assert_eq!(read_json(&out[..]).unwrap(), reports);
+    }
+}
@@ -0,0 +1,236 @@
+use gfx_warden::{builder::SceneBuilder, raw};
+use hal::{buffer, command, format::Format, image, pass, pso};
+
+use std::fs;
+
+/// Formats of the generated copy tests.
+const FORMATS: &[Format] = &[
+    Format::R8Unorm,
+    Format::Rg8Unorm,
+    Format::Rgba8Unorm,
+    Format::Rgba8Srgb,
+    Format::R16Uint,
+    Format::Rgba16Sfloat,
+    Format::R32Uint,
+    Format::Rg32Sfloat,
+    Format::Rgba32Uint,
+];
+
+const EXTENT: image::Extent = image::Extent {
+    width: 2,
+    height: 2,
+    depth: 1,
+};
+
+/// Scene copying distinct words through an image of the format, and the
+/// expected contents of its "output" buffer.
+fn copy_scene(format: Format) -> (raw::Scene, Vec<u8>) {
+    let texel_size = format.surface_desc().bits as usize / 8;
+    let size = texel_size * (EXTENT.width * EXTENT.height) as usize;
+    let words = (0 .. size as u32 / 4)
+        .map(|k| 0x0403_0201 + k * 0x0404_0404)
+        .collect::<Vec<_>>();
+
+    let mut commands = words
+        .iter()
+        .enumerate()
+        .map(|(k, &data)| raw::TransferCommand::FillBuffer {
+            buffer: "input".to_string(),
+            offset: 4 * k as buffer::Offset,
+            size: Some(4),
+            data,
+        })
+        .collect::<Vec<_>>();
+    let region = command::BufferImageCopy {
+        buffer_offset: 0,
+        buffer_width: EXTENT.width,
+        buffer_height: EXTENT.height,
+        image_layers: image::SubresourceLayers {
+            aspects: format.surface_desc().aspects,
+            level: 0,
+            layers: 0 .. 1,
+        },
+        image_offset: image::Offset::ZERO,
+        image_extent: EXTENT,
+    };
+    commands.push(raw::TransferCommand::CopyBufferToImage {
+        src: "input".to_string(),
+        dst: "image".to_string(),
+        regions: vec![region.clone()],
+    });
+    commands.push(raw::TransferCommand::CopyImageToBuffer {
+        src: "image".to_string(),
+        dst: "output".to_string(),
+        regions: vec![region],
+    });
+
+    let transfer = buffer::Usage::TRANSFER_SRC | buffer::Usage::TRANSFER_DST;
+    let scene = SceneBuilder::new()
+        .buffer("input", size, transfer)
+        .buffer("output", size, transfer)
+        .image(
+            "image",
+            image::Kind::D2(EXTENT.width, EXTENT.height, 1, 1),
+            format,
+            image::Usage::TRANSFER_SRC | image::Usage::TRANSFER_DST,
+        )
+        .transfer("copy", commands)
+        .build()
+        .unwrap_or_else(|errors| panic!("{:?}: {:?}", format, errors));
+    let expected = words
+        .iter()
+        .flat_map(|w| w.to_le_bytes().to_vec())
+        .collect();
+    (scene, expected)
+}
+
+/// Scene drawing a copy of an image to another, sampled with the filter.
+fn sample_scene(filter: image::Filter) -> raw::Scene {
+    let kind = image::Kind::D2(EXTENT.width, EXTENT.height, 1, 1);
+    let format = Format::Rgba8Unorm;
+    let attachment = pass::Attachment {
+        format: Some(format),
+        samples: 1,
+        ops: pass::AttachmentOps::new(
+            pass::AttachmentLoadOp::Clear,
+            pass::AttachmentStoreOp::Store,
+        ),
+        stencil_ops: pass::AttachmentOps::DONT_CARE,
+        layouts: image::Layout::General .. image::Layout::General,
+    };
+    let binding = |binding, ty| pso::DescriptorSetLayoutBinding {
+        binding,
+        ty,
+        count: 1,
+        stage_flags: pso::ShaderStageFlags::FRAGMENT,
+        immutable_samplers: false,
+    };
+    let range = |ty| pso::DescriptorRangeDesc { ty, count: 1 };
+    let sampled = pso::DescriptorType::Image {
+        ty: pso::ImageDescriptorType::Sampled {
+            with_sampler: false,
+        },
+    };
+
+    SceneBuilder::new()
+        .image("source", kind, format, image::Usage::SAMPLED)
+        .image_view("source.view", "source", image::ViewKind::D2, format)
+        .image("target", kind, format, image::Usage::COLOR_ATTACHMENT)
+        .image_view("target.view", "target", image::ViewKind::D2, format)
+        .sampler(
+            "sampler",
+            image::SamplerDesc::new(filter, image::WrapMode::Clamp),
+        )
+        .descriptor_set_layout(
+            "set-layout",
+            vec![
+                binding(0, sampled),
+                binding(1, pso::DescriptorType::Sampler),
+            ],
+        )
+        .descriptor_pool(
+            "pool",
+            1,
+            vec![range(sampled), range(pso::DescriptorType::Sampler)],
+        )
+        .descriptor_set(
+            "set",
+            "pool",
+            "set-layout",
+            vec![
+                raw::DescriptorRange::Images(vec![(
+                    "source.view".to_string(),
+                    image::Layout::ShaderReadOnlyOptimal,
+                )]),
+                raw::DescriptorRange::Samplers(vec!["sampler".to_string()]),
+            ],
+        )
+        .render_pass("pass", &[("c", attachment)], None)
+        .framebuffer("fbo", "pass", &[("c", "target.view")], EXTENT)
+        .shader("shader.vs", "copy.vert")
+        .shader("shader.fs", "copy.frag")
+        .pipeline_layout("pipe-layout", &["set-layout"], Vec::new())
+        .graphics_pipeline(
+            "pipe",
+            "shader.vs",
+            "shader.fs",
+            "pipe-layout",
+            "pass",
+            pso::Primitive::TriangleList,
+        )
+        .build()
+        .unwrap_or_else(|errors| panic!("{:?}: {:?}", filter, errors))
+}
+
+#[test]
+fn test_graphics_resources() {
+    for &filter in &[image::Filter::Nearest, image::Filter::Linear] {
+        let scene = sample_scene(filter);
+        match scene.resources["pipe"] {
+            raw::Resource::GraphicsPipeline { ref blender, .. } => {
+                assert_eq!(blender.targets, vec![pso::ColorBlendDesc::EMPTY])
+            }
+            ref other => panic!("{:?}", other),
+        }
+        let text = scene.to_ron().unwrap();
+        assert!(text.contains(&format!("{:?}", filter)), "{}", text);
+        ron::de::from_str::<raw::Scene>(&text).unwrap();
+    }
+
+    // The pipeline needs the render pass to count the blend targets.
+    let errors = SceneBuilder::new()
+        .graphics_pipeline(
+            "pipe",
+            "vs",
+            "fs",
+            "layout",
+            "pass",
+            pso::Primitive::TriangleList,
+        )
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        errors[0].to_string(),
+        r#"resources["pipe"].subpass.parent: render pass 'pass' isn't added before the pipeline"#
+    );
+}
+
+#[test]
+fn test_ron_roundtrip() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../../work/scenes");
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let source = fs::read_to_string(&path).unwrap();
+        let scene = ron::de::from_str::<raw::Scene>(&source).unwrap();
+        let text = scene.to_ron().unwrap();
+        let reparsed = ron::de::from_str::<raw::Scene>(&text)
+            .unwrap_or_else(|e| panic!("{:?}: {}\n{}", path, e, text));
+        assert_eq!(reparsed.to_ron().unwrap(), text, "{:?}", path);
+    }
+
+    for &format in FORMATS {
+        let (scene, _) = copy_scene(format);
+        let text = scene.to_ron().unwrap();
+        assert!(text.contains(&format!("format: {:?}", format)), "{}", text);
+        ron::de::from_str::<raw::Scene>(&text).unwrap();
+    }
+}
+
+#[cfg(feature = "software")]
+#[test]
+fn test_copy_formats() {
+    use hal::Instance as _;
+
+    let data_path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../work/data");
+    let instance = gfx_backend_software::Instance::create("warden", 1).unwrap();
+    for &format in FORMATS {
+        let (raw_scene, expected) = copy_scene(format);
+        let adapter = instance.enumerate_adapters().remove(0);
+        let features = raw_scene.requirements().features;
+        let mut scene =
+            gfx_warden::gpu::Scene::new(adapter, features, &raw_scene, data_path.into()).unwrap();
+        scene.run(Some("copy").into_iter());
+        let guard = scene.fetch_buffer("output");
+        assert_eq!(guard.row(0), &expected[..], "{:?}", format);
+    }
+}
@@ -0,0 +1,13 @@
+#version 450
+#extension GL_ARB_separate_shader_objects : enable
+
+layout(push_constant) uniform PushConstants
+{
+    vec4 color;
+};
+
+layout(location = 0) out vec4 o_Color;
+
+void main() {
+    o_Color = color;
+}